@@ -1,5 +1,6 @@
 use crate::bench_work_dir::BenchmarkWorkDir;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::fs::read_to_string;
 use std::path::PathBuf;
@@ -22,10 +23,120 @@ pub struct BenchEval {
     pub selector: String,
     pub post_process_cmd: Option<PathBuf>,
     pub parallel_safe: bool,
+    /// Times to run a failing eval again, an eval that passes on a retry is reported as flaky
+    #[serde(default)]
+    pub retries: usize,
 }
+/// Remote goose-server to run evaluations against instead of an in-process agent
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BenchServerConfig {
+    pub url: String,
+    /// Falls back to `GOOSE_SERVER__SECRET_KEY` when unset
+    pub secret_key: Option<String>,
+    /// Switch the server's provider to the benchmarked model before each eval
+    #[serde(default)]
+    pub update_provider: bool,
+}
+/// A version of the agent to benchmark: the goose binary to run and the environment to run it in
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct BenchAgentVersion {
+    pub name: String,
+    /// Defaults to the binary running the benchmark
+    #[serde(default)]
+    pub binary: Option<PathBuf>,
+    #[serde(default)]
+    pub envs: HashMap<String, String>,
+}
+
+impl BenchAgentVersion {
+    pub fn current() -> Self {
+        BenchAgentVersion {
+            name: "current".to_string(),
+            binary: None,
+            envs: HashMap::new(),
+        }
+    }
+}
+
+/// Combinations to leave out of a matrix, such as models a provider doesn't offer. Unset
+/// fields match anything.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct BenchMatrixExclude {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub agent: Option<String>,
+    pub suite: Option<String>,
+}
+
+impl BenchMatrixExclude {
+    fn matches(&self, cell: &BenchMatrixCell) -> bool {
+        let matches =
+            |field: &Option<String>, value: &str| field.as_deref().is_none_or(|f| f == value);
+        matches(&self.provider, &cell.provider)
+            && matches(&self.model, &cell.model)
+            && matches(&self.agent, &cell.agent)
+            && matches(&self.suite, &cell.suite)
+    }
+}
+
+/// Benchmark every combination of providers, models, agent versions and suites
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BenchMatrix {
+    pub providers: Vec<String>,
+    pub models: Vec<String>,
+    /// Defaults to only the running binary
+    #[serde(default)]
+    pub agents: Vec<BenchAgentVersion>,
+    pub suites: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<BenchMatrixExclude>,
+}
+
+/// One combination from a [`BenchMatrix`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BenchMatrixCell {
+    pub agent: String,
+    pub provider: String,
+    pub model: String,
+    pub suite: String,
+}
+
+impl BenchMatrix {
+    pub fn agent_versions(&self) -> Vec<BenchAgentVersion> {
+        if self.agents.is_empty() {
+            vec![BenchAgentVersion::current()]
+        } else {
+            self.agents.clone()
+        }
+    }
+
+    /// Every combination that isn't excluded
+    pub fn expand(&self) -> Vec<BenchMatrixCell> {
+        let mut cells = Vec::new();
+        for agent in self.agent_versions() {
+            for provider in &self.providers {
+                for model in &self.models {
+                    for suite in &self.suites {
+                        cells.push(BenchMatrixCell {
+                            agent: agent.name.clone(),
+                            provider: provider.clone(),
+                            model: model.clone(),
+                            suite: suite.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        cells.retain(|cell| !self.exclude.iter().any(|exclude| exclude.matches(cell)));
+        cells
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct BenchRunConfig {
+    #[serde(default)]
     pub models: Vec<BenchModel>,
+    #[serde(default)]
     pub evals: Vec<BenchEval>,
     pub include_dirs: Vec<PathBuf>,
     pub repeat: Option<usize>,
@@ -34,6 +145,14 @@ pub struct BenchRunConfig {
     pub eval_result_filename: String,
     pub run_summary_filename: String,
     pub env_file: Option<PathBuf>,
+    #[serde(default)]
+    pub server: Option<BenchServerConfig>,
+    /// Directory of a Parquet dataset that results are appended to after each model run
+    #[serde(default)]
+    pub results_dataset: Option<PathBuf>,
+    /// Run every combination in the matrix instead of `models` and `evals`
+    #[serde(default)]
+    pub matrix: Option<BenchMatrix>,
 }
 
 impl Default for BenchRunConfig {
@@ -60,6 +179,7 @@ impl Default for BenchRunConfig {
                 selector: "core".into(),
                 post_process_cmd: None,
                 parallel_safe: true, // Default to true
+                retries: 0,
             }],
             include_dirs: vec![],
             repeat: Some(2),
@@ -68,6 +188,9 @@ impl Default for BenchRunConfig {
             eval_result_filename: "eval-results.json".to_string(),
             run_summary_filename: "run-results-summary.json".to_string(),
             env_file: None,
+            server: None,
+            results_dataset: None,
+            matrix: None,
         }
     }
 }
@@ -98,6 +221,47 @@ impl BenchRunConfig {
         Ok(config)
     }
 
+    /// Run only the given selectors, keeping the options of the first configured eval
+    pub fn set_selectors(&mut self, selectors: &[String]) {
+        let (post_process_cmd, parallel_safe, retries) = self
+            .evals
+            .first()
+            .map(|eval| {
+                (
+                    eval.post_process_cmd.clone(),
+                    eval.parallel_safe,
+                    eval.retries,
+                )
+            })
+            .unwrap_or((None, true, 0));
+        self.evals = selectors
+            .iter()
+            .map(|selector| BenchEval {
+                selector: selector.clone(),
+                post_process_cmd: post_process_cmd.clone(),
+                parallel_safe,
+                retries,
+            })
+            .collect();
+    }
+
+    /// Retry every failing eval up to `retries` times
+    pub fn set_retries(&mut self, retries: usize) {
+        for eval in &mut self.evals {
+            eval.retries = retries;
+        }
+    }
+
+    /// Benchmark only the given model
+    pub fn set_model(&mut self, provider: String, name: String) {
+        self.models = vec![BenchModel {
+            provider,
+            name,
+            parallel_safe: true,
+            tool_shim: None,
+        }];
+    }
+
     pub fn to_string(&self) -> anyhow::Result<String> {
         Ok(serde_json::to_string_pretty(self)?)
     }
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use goose::message::Message;
+use goose::providers::base::ResponseTiming;
 
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -21,6 +22,7 @@ pub trait BenchBaseSession: Send + Sync {
     fn session_file(&self) -> PathBuf;
     fn message_history(&self) -> Vec<Message>;
     fn get_total_token_usage(&self) -> anyhow::Result<Option<i32>>;
+    fn get_last_response_timing(&self) -> anyhow::Result<Option<ResponseTiming>>;
 }
 // struct for managing agent-session-access. to be passed to evals for benchmarking
 pub struct BenchAgent {
@@ -52,6 +54,9 @@ impl BenchAgent {
     pub(crate) async fn get_token_usage(&self) -> Option<i32> {
         self.session.get_total_token_usage().ok().flatten()
     }
+    pub(crate) async fn get_response_timing(&self) -> Option<ResponseTiming> {
+        self.session.get_last_response_timing().ok().flatten()
+    }
     pub(crate) fn session_file(&self) -> PathBuf {
         self.session.session_file()
     }
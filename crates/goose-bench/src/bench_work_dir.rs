@@ -130,6 +130,16 @@ impl BenchmarkWorkDir {
             .unwrap_or_else(|_| panic!("Failed to execute cd into {}", eval_dir.clone().display()));
     }
 
+    /// Empty the current eval directory, so a retried eval doesn't see the files of the
+    /// attempt before it
+    pub fn reset_eval(&mut self) -> anyhow::Result<()> {
+        let eval_dir = self.cwd.clone();
+        std::env::set_current_dir(&self.base_path)?;
+        fs::remove_dir_all(&eval_dir)?;
+        self.cd(eval_dir)?;
+        Ok(())
+    }
+
     fn chop_relative_base<P: AsRef<Path>>(path: P) -> anyhow::Result<PathBuf> {
         let path = path.as_ref();
 
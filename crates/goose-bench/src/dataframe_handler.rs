@@ -0,0 +1,177 @@
+use crate::bench_config::BenchModel;
+use crate::eval_suites::EvalMetricValue;
+use crate::reporting::{BenchmarkResults, CaseOutcome};
+use anyhow::{Context, Result};
+use arrow::array::{ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Schema shared by every file in a results dataset, one row per metric.
+///
+/// Metric values are split into typed, nullable columns so the dataset can be queried
+/// without parsing strings. Columns must only ever be appended to keep old files readable.
+pub fn results_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("run_id", DataType::Utf8, false),
+        Field::new("repeat", DataType::Int64, false),
+        Field::new("provider", DataType::Utf8, false),
+        Field::new("model", DataType::Utf8, false),
+        Field::new("start_time", DataType::Utf8, false),
+        Field::new("suite", DataType::Utf8, false),
+        Field::new("evaluation", DataType::Utf8, false),
+        Field::new("metric", DataType::Utf8, false),
+        Field::new("metric_type", DataType::Utf8, false),
+        Field::new("int_value", DataType::Int64, true),
+        Field::new("float_value", DataType::Float64, true),
+        Field::new("bool_value", DataType::Boolean, true),
+        Field::new("string_value", DataType::Utf8, true),
+        Field::new("error_count", DataType::Int64, false),
+        Field::new("outcome", DataType::Utf8, true),
+        Field::new("attempts", DataType::Int64, true),
+    ]))
+}
+
+#[derive(Default)]
+struct ResultColumns {
+    run_id: Vec<String>,
+    repeat: Vec<i64>,
+    provider: Vec<String>,
+    model: Vec<String>,
+    start_time: Vec<String>,
+    suite: Vec<String>,
+    evaluation: Vec<String>,
+    metric: Vec<String>,
+    metric_type: Vec<&'static str>,
+    int_value: Vec<Option<i64>>,
+    float_value: Vec<Option<f64>>,
+    bool_value: Vec<Option<bool>>,
+    string_value: Vec<Option<String>>,
+    error_count: Vec<i64>,
+    outcome: Vec<Option<&'static str>>,
+    attempts: Vec<Option<i64>>,
+}
+
+impl ResultColumns {
+    fn push_run(&mut self, run_id: &str, repeat: i64, model: &BenchModel, run: &BenchmarkResults) {
+        for suite in &run.suites {
+            for eval in &suite.evaluations {
+                for (metric, value) in &eval.metrics {
+                    self.run_id.push(run_id.to_string());
+                    self.repeat.push(repeat);
+                    self.provider.push(model.provider.clone());
+                    self.model.push(model.name.clone());
+                    self.start_time.push(run.start_time.clone());
+                    self.suite.push(suite.name.clone());
+                    self.evaluation.push(eval.name.clone());
+                    self.metric.push(metric.clone());
+                    self.error_count.push(eval.errors.len() as i64);
+                    self.outcome.push(eval.outcome.map(|outcome| match outcome {
+                        CaseOutcome::Passed => "passed",
+                        CaseOutcome::Flaky => "flaky",
+                        CaseOutcome::Failed => "failed",
+                    }));
+                    self.attempts
+                        .push((eval.attempts > 0).then_some(eval.attempts as i64));
+
+                    let (metric_type, int, float, boolean, string) = match value {
+                        EvalMetricValue::Integer(i) => ("integer", Some(*i), None, None, None),
+                        EvalMetricValue::Float(f) => ("float", None, Some(*f), None, None),
+                        EvalMetricValue::Boolean(b) => ("boolean", None, None, Some(*b), None),
+                        EvalMetricValue::String(s) => ("string", None, None, None, Some(s.clone())),
+                    };
+                    self.metric_type.push(metric_type);
+                    self.int_value.push(int);
+                    self.float_value.push(float);
+                    self.bool_value.push(boolean);
+                    self.string_value.push(string);
+                }
+            }
+        }
+    }
+
+    fn into_batch(self) -> Result<RecordBatch> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(self.run_id)),
+            Arc::new(Int64Array::from(self.repeat)),
+            Arc::new(StringArray::from(self.provider)),
+            Arc::new(StringArray::from(self.model)),
+            Arc::new(StringArray::from(self.start_time)),
+            Arc::new(StringArray::from(self.suite)),
+            Arc::new(StringArray::from(self.evaluation)),
+            Arc::new(StringArray::from(self.metric)),
+            Arc::new(StringArray::from(self.metric_type)),
+            Arc::new(Int64Array::from(self.int_value)),
+            Arc::new(Float64Array::from(self.float_value)),
+            Arc::new(BooleanArray::from(self.bool_value)),
+            Arc::new(StringArray::from(self.string_value)),
+            Arc::new(Int64Array::from(self.error_count)),
+            Arc::new(StringArray::from(self.outcome)),
+            Arc::new(Int64Array::from(self.attempts)),
+        ];
+        RecordBatch::try_new(results_schema(), columns).context("Failed to build results batch")
+    }
+}
+
+/// Append the results of a model's runs to a Parquet results dataset.
+///
+/// Files are laid out as `<dataset>/run_id=<run_id>/<provider>-<model>.parquet`, a hive-style
+/// partitioning that DuckDB (`hive_partitioning = true`) and pandas/pyarrow read directly.
+/// Each model writes its own file, so concurrent model runners never contend, and re-running
+/// the same run id and model replaces that file instead of duplicating rows.
+pub fn append_results(
+    dataset_dir: &Path,
+    run_id: &str,
+    model: &BenchModel,
+    runs: &[(usize, BenchmarkResults)],
+) -> Result<PathBuf> {
+    let mut columns = ResultColumns::default();
+    for (repeat, run) in runs {
+        columns.push_run(run_id, *repeat as i64, model, run);
+    }
+    let batch = columns.into_batch()?;
+
+    let partition = dataset_dir.join(format!("run_id={}", sanitize(run_id)));
+    fs::create_dir_all(&partition)
+        .with_context(|| format!("Failed to create results partition {}", partition.display()))?;
+
+    let file_name = format!("{}-{}", sanitize(&model.provider), sanitize(&model.name));
+    let path = partition.join(format!("{}.parquet", file_name));
+    // write to a temporary file first so readers never observe a partially written file
+    let tmp_path = partition.join(format!(".{}.parquet.tmp", file_name));
+
+    let file = File::create(&tmp_path)
+        .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(file, results_schema(), Some(props))
+        .context("Failed to create Parquet writer")?;
+    writer.write(&batch).context("Failed to write results")?;
+    writer.close().context("Failed to finish Parquet file")?;
+
+    fs::rename(&tmp_path, &path)
+        .with_context(|| format!("Failed to move results into {}", path.display()))?;
+
+    tracing::info!(
+        "Appended {} result rows to {}",
+        batch.num_rows(),
+        path.display()
+    );
+    Ok(path)
+}
+
+fn sanitize(component: &str) -> String {
+    component
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '=' => '_',
+            c => c,
+        })
+        .collect()
+}
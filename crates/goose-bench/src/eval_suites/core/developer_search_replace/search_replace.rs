@@ -1,8 +1,8 @@
 use crate::bench_session::BenchAgent;
 use crate::bench_work_dir::BenchmarkWorkDir;
 use crate::eval_suites::{
-    collect_baseline_metrics, metrics_hashmap_to_vec, EvalMetricValue, Evaluation,
-    ExtensionRequirements,
+    check_trajectory, collect_baseline_metrics, metrics_hashmap_to_vec, EvalMetricValue,
+    Evaluation, ExtensionRequirements, ToolMatcher, TrajectoryAssertion,
 };
 use crate::register_evaluation;
 use async_trait::async_trait;
@@ -36,7 +36,7 @@ impl Evaluation for DeveloperSearchReplace {
         source_file.push("assets/kubernetes_swagger.json");
 
         // Send the prompt to modify the file
-        let (_messages, perf_metrics) = collect_baseline_metrics(
+        let (messages, perf_metrics) = collect_baseline_metrics(
             agent,
             "Remove the io.k8s.api.admissionregistration.v1.ServiceReference definition block and replace with a new definition for io.k8s.api.admissionregistration.v1.FakeServiceReference. Update the fields in the definition as well to be consistent. Don't change the property names. Don't update any references to the old definition. Only modify the definition and it's description to 'FakeServiceReference simulates a reference to a fake service for testing purposes.'.The file to modify is kubernetes_swagger.json.".to_string()
         ).await;
@@ -92,6 +92,20 @@ impl Evaluation for DeveloperSearchReplace {
             EvalMetricValue::Boolean(changes_match),
         ));
 
+        // The file should be read before it is edited, and edited rather than rewritten from a shell
+        let view = ToolMatcher::tool("text_editor").arg_eq("command", "view");
+        let str_replace = ToolMatcher::tool("text_editor").arg_eq("command", "str_replace");
+        metrics.extend(check_trajectory(
+            &messages,
+            &[
+                TrajectoryAssertion::CallsBefore(view, str_replace),
+                TrajectoryAssertion::NeverCalls(
+                    ToolMatcher::tool("shell").arg_matches("command", r"\brm\s"),
+                ),
+                TrajectoryAssertion::MaxToolCalls(20),
+            ],
+        ));
+
         metrics.push((
             "score".to_string(),
             EvalMetricValue::Float((changes_match as u8) as f64 / 1.0),
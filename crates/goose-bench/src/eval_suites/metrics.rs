@@ -4,7 +4,7 @@ use goose::message::{Message, MessageContent};
 use std::collections::HashMap;
 use std::time::Instant;
 
-/// Collect baseline metrics including execution time, tool usage, and token count
+/// Collect baseline metrics including execution time, tool usage, token count and response latency
 pub async fn collect_baseline_metrics(
     agent: &mut BenchAgent,
     prompt: String,
@@ -57,6 +57,26 @@ pub async fn collect_baseline_metrics(
         );
     }
 
+    // Latency of the final provider response, to compare responsiveness across models
+    if let Some(timing) = agent.get_response_timing().await {
+        metrics.insert(
+            "response_time_ms".to_string(),
+            EvalMetricValue::Integer(timing.total_ms as i64),
+        );
+        if let Some(ttft) = timing.time_to_first_token_ms {
+            metrics.insert(
+                "time_to_first_token_ms".to_string(),
+                EvalMetricValue::Integer(ttft as i64),
+            );
+        }
+        if let Some(rate) = timing.tokens_per_second {
+            metrics.insert(
+                "tokens_per_second".to_string(),
+                EvalMetricValue::Float(rate),
+            );
+        }
+    }
+
     (messages, metrics)
 }
 
@@ -2,10 +2,12 @@ mod core;
 mod evaluation;
 mod factory;
 mod metrics;
+mod trajectory;
 mod utils;
 mod vibes;
 
 pub use evaluation::*;
 pub use factory::{register_eval, EvaluationSuite};
 pub use metrics::*;
+pub use trajectory::*;
 pub use utils::*;
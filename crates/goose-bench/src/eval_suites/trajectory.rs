@@ -0,0 +1,236 @@
+use crate::eval_suites::EvalMetricValue;
+use goose::message::{Message, MessageContent};
+use regex::Regex;
+use serde_json::Value;
+use std::fmt;
+
+/// A tool call the agent made, in the order it was requested
+#[derive(Debug, Clone)]
+pub struct ToolCallRecord {
+    pub name: String,
+    pub arguments: Value,
+}
+
+/// Every tool call recorded in the conversation, in order
+pub fn tool_trajectory(messages: &[Message]) -> Vec<ToolCallRecord> {
+    messages
+        .iter()
+        .flat_map(|message| message.content.iter())
+        .filter_map(|content| match content {
+            MessageContent::ToolRequest(request) => request.tool_call.as_ref().ok(),
+            _ => None,
+        })
+        .map(|tool_call| ToolCallRecord {
+            name: tool_call.name.clone(),
+            arguments: tool_call.arguments.clone(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+enum ArgPattern {
+    Equals(String),
+    Contains(String),
+    Matches(Regex),
+}
+
+impl ArgPattern {
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            ArgPattern::Equals(expected) => value == expected,
+            ArgPattern::Contains(needle) => value.contains(needle.as_str()),
+            ArgPattern::Matches(regex) => regex.is_match(value),
+        }
+    }
+}
+
+/// Selects tool calls by name and string arguments, e.g.
+/// `ToolMatcher::tool("text_editor").arg_eq("command", "view")`
+#[derive(Debug, Clone)]
+pub struct ToolMatcher {
+    tool: String,
+    args: Vec<(String, ArgPattern)>,
+}
+
+impl ToolMatcher {
+    /// Calls of `tool`, either the full name such as `developer__shell` or the name without
+    /// the extension prefix
+    pub fn tool(tool: &str) -> Self {
+        Self {
+            tool: tool.to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg_eq(mut self, key: &str, value: &str) -> Self {
+        self.args
+            .push((key.to_string(), ArgPattern::Equals(value.to_string())));
+        self
+    }
+
+    pub fn arg_contains(mut self, key: &str, needle: &str) -> Self {
+        self.args
+            .push((key.to_string(), ArgPattern::Contains(needle.to_string())));
+        self
+    }
+
+    /// Panics if `pattern` is not a valid regex, as it is written in the eval itself
+    pub fn arg_matches(mut self, key: &str, pattern: &str) -> Self {
+        let regex = Regex::new(pattern).expect("invalid argument pattern");
+        self.args
+            .push((key.to_string(), ArgPattern::Matches(regex)));
+        self
+    }
+
+    pub fn matches(&self, call: &ToolCallRecord) -> bool {
+        let name_matches = call.name == self.tool
+            || call
+                .name
+                .split_once("__")
+                .is_some_and(|(_, name)| name == self.tool);
+        name_matches
+            && self.args.iter().all(|(key, pattern)| {
+                call.arguments
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .is_some_and(|value| pattern.matches(value))
+            })
+    }
+
+    fn first_index(&self, trajectory: &[ToolCallRecord]) -> Option<usize> {
+        trajectory.iter().position(|call| self.matches(call))
+    }
+}
+
+impl fmt::Display for ToolMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tool)?;
+        if self.args.is_empty() {
+            return Ok(());
+        }
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|(key, pattern)| match pattern {
+                ArgPattern::Equals(value) => format!("{}={}", key, value),
+                ArgPattern::Contains(needle) => format!("{}~{}", key, needle),
+                ArgPattern::Matches(regex) => format!("{}=/{}/", key, regex),
+            })
+            .collect();
+        write!(f, "({})", args.join(", "))
+    }
+}
+
+/// A check on the order and number of the agent's tool calls, for regression tests on how
+/// it works rather than only on its final answer
+#[derive(Debug, Clone)]
+pub enum TrajectoryAssertion {
+    /// At least one matching call
+    Calls(ToolMatcher),
+    /// No matching call
+    NeverCalls(ToolMatcher),
+    /// Any call matching the second comes after a call matching the first
+    CallsBefore(ToolMatcher, ToolMatcher),
+    /// At most this many tool calls in total
+    MaxToolCalls(usize),
+    /// At most this many matching calls
+    MaxCallsOf(ToolMatcher, usize),
+}
+
+impl TrajectoryAssertion {
+    /// Whether the trajectory satisfies the assertion, with the reason when it doesn't
+    pub fn check(&self, trajectory: &[ToolCallRecord]) -> Result<(), String> {
+        match self {
+            TrajectoryAssertion::Calls(matcher) => match matcher.first_index(trajectory) {
+                Some(_) => Ok(()),
+                None => Err(format!("{} was never called", matcher)),
+            },
+            TrajectoryAssertion::NeverCalls(matcher) => match matcher.first_index(trajectory) {
+                Some(index) => Err(format!("{} was called at step {}", matcher, index + 1)),
+                None => Ok(()),
+            },
+            TrajectoryAssertion::CallsBefore(first, then) => {
+                let Some(then_index) = then.first_index(trajectory) else {
+                    return Ok(());
+                };
+                match first.first_index(trajectory) {
+                    Some(first_index) if first_index < then_index => Ok(()),
+                    _ => Err(format!(
+                        "{} was called at step {} before any {}",
+                        then,
+                        then_index + 1,
+                        first
+                    )),
+                }
+            }
+            TrajectoryAssertion::MaxToolCalls(max) => {
+                if trajectory.len() <= *max {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{} tool calls, expected at most {}",
+                        trajectory.len(),
+                        max
+                    ))
+                }
+            }
+            TrajectoryAssertion::MaxCallsOf(matcher, max) => {
+                let count = trajectory
+                    .iter()
+                    .filter(|call| matcher.matches(call))
+                    .count();
+                if count <= *max {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{} was called {} times, expected at most {}",
+                        matcher, count, max
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for TrajectoryAssertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrajectoryAssertion::Calls(matcher) => write!(f, "calls {}", matcher),
+            TrajectoryAssertion::NeverCalls(matcher) => write!(f, "never calls {}", matcher),
+            TrajectoryAssertion::CallsBefore(first, then) => {
+                write!(f, "calls {} before {}", first, then)
+            }
+            TrajectoryAssertion::MaxToolCalls(max) => write!(f, "at most {} tool calls", max),
+            TrajectoryAssertion::MaxCallsOf(matcher, max) => {
+                write!(f, "calls {} at most {} times", matcher, max)
+            }
+        }
+    }
+}
+
+/// Check the assertions against the tool calls in `messages`, returning a boolean metric for
+/// each and `trajectory_passed` for all of them
+pub fn check_trajectory(
+    messages: &[Message],
+    assertions: &[TrajectoryAssertion],
+) -> Vec<(String, EvalMetricValue)> {
+    let trajectory = tool_trajectory(messages);
+    let mut metrics = Vec::new();
+    let mut all_passed = true;
+    for assertion in assertions {
+        let result = assertion.check(&trajectory);
+        if let Err(reason) = &result {
+            println!("Trajectory assertion failed: {}: {}", assertion, reason);
+            all_passed = false;
+        }
+        metrics.push((
+            format!("trajectory: {}", assertion),
+            EvalMetricValue::Boolean(result.is_ok()),
+        ));
+    }
+    metrics.push((
+        "trajectory_passed".to_string(),
+        EvalMetricValue::Boolean(all_passed),
+    ));
+    metrics
+}
@@ -1,8 +1,11 @@
 pub mod bench_config;
 pub mod bench_session;
 pub mod bench_work_dir;
+pub mod dataframe_handler;
 pub mod error_capture;
 pub mod eval_suites;
+pub mod manifest;
 pub mod reporting;
 pub mod runners;
+pub mod server_session;
 pub mod utilities;
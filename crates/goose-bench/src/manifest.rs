@@ -0,0 +1,386 @@
+use crate::bench_config::BenchRunConfig;
+use crate::bench_work_dir::BUILTIN_EVAL_ASSETS;
+use crate::eval_suites::EvaluationSuite;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use goose::config::extension_health::EXTENSION_HEALTH_KEY;
+use goose::config::key_manager::KEY_METADATA_KEY;
+use goose::config::Config;
+use include_dir::{Dir, DirEntry};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Written next to `config.cfg` in every experiment directory
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// Set by the build script when goose is built from a git checkout
+const GOOSE_COMMIT: Option<&str> = option_env!("GOOSE_GIT_COMMIT");
+
+/// Goose config keys left out of its hash: bookkeeping goose updates as it runs, such as when
+/// a key was last used, and settings that only change how the CLI looks
+const UNHASHED_GOOSE_CONFIG_KEYS: &[&str] = &[
+    KEY_METADATA_KEY,
+    EXTENSION_HEALTH_KEY,
+    "GOOSE_CLI_THEME",
+    "GOOSE_CLI_MIN_PRIORITY",
+];
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ManifestModel {
+    pub provider: String,
+    pub name: String,
+    pub tool_shim_model: Option<String>,
+}
+
+impl fmt::Display for ManifestModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.provider, self.name)?;
+        if let Some(shim) = &self.tool_shim_model {
+            write!(f, " (tool shim {})", shim)?;
+        }
+        Ok(())
+    }
+}
+
+/// An extension the selected evals load. Builtin extensions share goose's version, external
+/// ones are versioned by the package pinned in their command, if any.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ManifestExtension {
+    pub kind: String,
+    /// The extension's name, command or URL
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl fmt::Display for ManifestExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} '{}'", self.kind, self.name)?;
+        match &self.version {
+            Some(version) => write!(f, " {}", version),
+            None => write!(f, " (unpinned)"),
+        }
+    }
+}
+
+/// What a benchmark run depended on, to tell whether a later run can reproduce its results
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct BenchManifest {
+    pub created_at: DateTime<Utc>,
+    pub goose_version: String,
+    /// None for builds outside a git checkout
+    pub goose_commit: Option<String>,
+    pub models: Vec<ManifestModel>,
+    /// Providers are sent no sampling seed, so the temperature is what decides how much
+    /// replies vary between runs
+    pub temperature: Option<f32>,
+    /// Hash of the bench config, leaving out where and under which id results are written
+    pub config_hash: String,
+    /// Hash of the settings and extensions in the goose config that can change results
+    pub goose_config_hash: String,
+    pub extensions: Vec<ManifestExtension>,
+    pub evals: Vec<String>,
+    /// Hash of the builtin eval assets and every file in the config's `include_dirs`
+    pub dataset_hash: String,
+}
+
+impl BenchManifest {
+    /// Describe the environment a run of `config` would have now. Runs of other agent
+    /// versions in a matrix are recorded as the running binary's version.
+    pub fn capture(config: &BenchRunConfig) -> Result<Self> {
+        let goose_version = env!("CARGO_PKG_VERSION").to_string();
+        let evals = selected_evals(config);
+        let mut extensions = BTreeSet::new();
+        for name in &evals {
+            let Some(eval) = EvaluationSuite::from(name) else {
+                continue;
+            };
+            let requirements = eval.required_extensions();
+            extensions.extend(
+                requirements
+                    .builtin
+                    .into_iter()
+                    .map(|name| ManifestExtension {
+                        kind: "builtin".to_string(),
+                        name,
+                        version: Some(goose_version.clone()),
+                    }),
+            );
+            extensions.extend(
+                requirements
+                    .external
+                    .into_iter()
+                    .map(|cmd| ManifestExtension {
+                        kind: "external".to_string(),
+                        version: pinned_version(&cmd),
+                        name: cmd,
+                    }),
+            );
+            extensions.extend(
+                requirements
+                    .remote
+                    .into_iter()
+                    .map(|uri| ManifestExtension {
+                        kind: "remote".to_string(),
+                        name: uri,
+                        version: None,
+                    }),
+            );
+        }
+
+        let mut run_config = config.clone();
+        run_config.run_id = None;
+        run_config.output_dir = None;
+        run_config.results_dataset = None;
+        let mut goose_config = Config::global()
+            .load_values()
+            .context("Failed to read the goose config")?;
+        goose_config.retain(|key, _| !UNHASHED_GOOSE_CONFIG_KEYS.contains(&key.as_str()));
+
+        Ok(BenchManifest {
+            created_at: Utc::now(),
+            goose_version,
+            goose_commit: GOOSE_COMMIT.map(str::to_string),
+            models: models(config),
+            temperature: Config::global().get_param("GOOSE_TEMPERATURE").ok(),
+            config_hash: hash_json(serde_json::to_value(&run_config)?),
+            goose_config_hash: hash_json(serde_json::to_value(goose_config)?),
+            extensions: extensions.into_iter().collect(),
+            evals,
+            dataset_hash: dataset_hash(&config.include_dirs)?,
+        })
+    }
+
+    pub fn from(path: &Path) -> Result<Self> {
+        let manifest = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&manifest)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// How `current` differs from this manifest, one line per field
+    pub fn differences(&self, current: &BenchManifest) -> Vec<String> {
+        fn optional<T: ToString>(value: &Option<T>) -> String {
+            value
+                .as_ref()
+                .map_or_else(|| "none".to_string(), T::to_string)
+        }
+        fn list<T: ToString>(values: &[T]) -> String {
+            values
+                .iter()
+                .map(T::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+
+        let fields = [
+            (
+                "goose version",
+                self.goose_version.clone(),
+                current.goose_version.clone(),
+            ),
+            (
+                "goose commit",
+                optional(&self.goose_commit),
+                optional(&current.goose_commit),
+            ),
+            ("models", list(&self.models), list(&current.models)),
+            (
+                "temperature",
+                optional(&self.temperature),
+                optional(&current.temperature),
+            ),
+            (
+                "bench config hash",
+                self.config_hash.clone(),
+                current.config_hash.clone(),
+            ),
+            (
+                "goose config hash",
+                self.goose_config_hash.clone(),
+                current.goose_config_hash.clone(),
+            ),
+            (
+                "extensions",
+                list(&self.extensions),
+                list(&current.extensions),
+            ),
+            ("evals", list(&self.evals), list(&current.evals)),
+            (
+                "dataset hash",
+                self.dataset_hash.clone(),
+                current.dataset_hash.clone(),
+            ),
+        ];
+        fields
+            .into_iter()
+            .filter(|(_, recorded, now)| recorded != now)
+            .map(|(field, recorded, now)| {
+                format!("{}: '{}' in the manifest, '{}' now", field, recorded, now)
+            })
+            .collect()
+    }
+
+    /// Check that running `config` now would happen in the environment recorded in the
+    /// manifest at `path`. The config defaults to the one saved next to the manifest.
+    pub fn verify(path: &Path, config: Option<PathBuf>) -> Result<()> {
+        let manifest = Self::from(path)?;
+        let config_path = match config {
+            Some(config) => config,
+            None => path.with_file_name("config.cfg"),
+        };
+        let config = BenchRunConfig::from(config_path.clone())
+            .with_context(|| format!("Failed to load {}", config_path.display()))?;
+
+        let differences = manifest.differences(&Self::capture(&config)?);
+        if differences.is_empty() {
+            println!(
+                "The environment matches the manifest recorded at {}",
+                manifest.created_at
+            );
+            return Ok(());
+        }
+        for difference in &differences {
+            println!("  {}", difference);
+        }
+        bail!(
+            "{} difference(s) from the manifest recorded at {}",
+            differences.len(),
+            manifest.created_at
+        )
+    }
+}
+
+fn models(config: &BenchRunConfig) -> Vec<ManifestModel> {
+    let models: BTreeSet<_> = match &config.matrix {
+        Some(matrix) => matrix
+            .expand()
+            .into_iter()
+            .map(|cell| ManifestModel {
+                provider: cell.provider,
+                name: cell.model,
+                tool_shim_model: None,
+            })
+            .collect(),
+        None => config
+            .models
+            .iter()
+            .map(|model| ManifestModel {
+                provider: model.provider.clone(),
+                name: model.name.clone(),
+                tool_shim_model: model
+                    .tool_shim
+                    .as_ref()
+                    .filter(|shim| shim.use_tool_shim)
+                    .and_then(|shim| shim.tool_shim_model.clone()),
+            })
+            .collect(),
+    };
+    models.into_iter().collect()
+}
+
+fn selected_evals(config: &BenchRunConfig) -> Vec<String> {
+    let selectors = match &config.matrix {
+        Some(matrix) => matrix.suites.clone(),
+        None => config
+            .evals
+            .iter()
+            .map(|eval| eval.selector.clone())
+            .collect(),
+    };
+    let evals: BTreeSet<_> = EvaluationSuite::select(selectors)
+        .into_values()
+        .flatten()
+        .map(str::to_string)
+        .collect();
+    evals.into_iter().collect()
+}
+
+/// The version pinned in an extension command, such as `npx @scope/server@1.2.0` or
+/// `uvx mcp-server-fetch==2025.4.7`
+fn pinned_version(cmd: &str) -> Option<String> {
+    cmd.split_whitespace().find_map(|arg| {
+        let (_, version) = arg.rsplit_once("==").or_else(|| {
+            // a leading @ starts a package scope rather than a version
+            let at = arg.rfind('@').filter(|&at| at > 0)?;
+            Some((&arg[..at], &arg[at + 1..]))
+        })?;
+        (!version.is_empty()).then(|| version.to_string())
+    })
+}
+
+/// Objects with their keys sorted, so equal values always serialize the same way
+fn sorted(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let sorted: BTreeMap<_, _> = map.into_iter().map(|(k, v)| (k, sorted(v))).collect();
+            Value::Object(sorted.into_iter().collect::<Map<_, _>>())
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sorted).collect()),
+        value => value,
+    }
+}
+
+fn hash_json(value: Value) -> String {
+    format!("{:x}", Sha256::digest(sorted(value).to_string()))
+}
+
+fn hash_file(hasher: &mut Sha256, path: &str, contents: &[u8]) {
+    hasher.update(path.as_bytes());
+    hasher.update([0u8]);
+    hasher.update((contents.len() as u64).to_le_bytes());
+    hasher.update(contents);
+}
+
+fn hash_assets(hasher: &mut Sha256, dir: &Dir) {
+    let mut entries: Vec<_> = dir.entries().iter().collect();
+    entries.sort_by(|a, b| a.path().cmp(b.path()));
+    for entry in entries {
+        match entry {
+            DirEntry::Dir(dir) => hash_assets(hasher, dir),
+            DirEntry::File(file) => hash_file(
+                hasher,
+                &format!("assets/{}", file.path().display()),
+                file.contents(),
+            ),
+        }
+    }
+}
+
+fn hash_dir(hasher: &mut Sha256, root: &Path, dir: &Path) -> Result<()> {
+    let mut paths = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            hash_dir(hasher, root, &path)?;
+        } else {
+            let contents =
+                fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            hash_file(hasher, &relative.display().to_string(), &contents);
+        }
+    }
+    Ok(())
+}
+
+fn dataset_hash(include_dirs: &[PathBuf]) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hash_assets(&mut hasher, &BUILTIN_EVAL_ASSETS);
+    for dir in include_dirs {
+        // the directory's own name is where evals find it in the work directory
+        hash_dir(&mut hasher, dir.parent().unwrap_or(dir), dir)?;
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
@@ -2,14 +2,35 @@ use crate::bench_session::BenchAgentError;
 use crate::eval_suites::EvalMetricValue;
 use chrono::Local;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
 
+/// Whether an evaluation passed, and whether it needed retries to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseOutcome {
+    Passed,
+    /// Failed at first but passed on a retry
+    Flaky,
+    /// Failed on every attempt
+    Failed,
+}
+
 /// Represents a single evaluation result
 #[derive(Default, Deserialize, Serialize)]
 pub struct EvaluationResult {
     pub name: String,
     pub metrics: Vec<(String, EvalMetricValue)>,
     pub errors: Vec<BenchAgentError>,
+    /// None for results recorded before retries were tracked
+    #[serde(default)]
+    pub outcome: Option<CaseOutcome>,
+    /// Number of times the evaluation ran
+    #[serde(default)]
+    pub attempts: usize,
+    /// Why each failed attempt failed, in order
+    #[serde(default)]
+    pub attempt_failures: Vec<String>,
 }
 
 /// Represents results for an entire suite
@@ -19,6 +40,45 @@ pub struct SuiteResult {
     pub evaluations: Vec<EvaluationResult>,
 }
 
+/// How many evaluations passed outright, passed on a retry, or failed every attempt
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OutcomeCounts {
+    pub passed: usize,
+    pub flaky: usize,
+    pub failed: usize,
+}
+
+impl OutcomeCounts {
+    fn add(&mut self, outcome: Option<CaseOutcome>) {
+        match outcome {
+            Some(CaseOutcome::Passed) => self.passed += 1,
+            Some(CaseOutcome::Flaky) => self.flaky += 1,
+            Some(CaseOutcome::Failed) => self.failed += 1,
+            None => {}
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.passed + self.flaky + self.failed
+    }
+
+    /// Share of evaluations that only passed on a retry
+    pub fn flake_rate(&self) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.flaky as f64 / total as f64,
+        }
+    }
+
+    /// Share of evaluations that failed every attempt
+    pub fn failure_rate(&self) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.failed as f64 / total as f64,
+        }
+    }
+}
+
 /// Contains all benchmark results and metadata
 #[derive(Default, Deserialize, Serialize)]
 pub struct BenchmarkResults {
@@ -33,6 +93,9 @@ impl EvaluationResult {
             name,
             metrics: Vec::new(),
             errors: Vec::new(),
+            outcome: None,
+            attempts: 0,
+            attempt_failures: Vec::new(),
         }
     }
 
@@ -43,6 +106,40 @@ impl EvaluationResult {
     pub fn add_error(&mut self, error: BenchAgentError) {
         self.errors.push(error);
     }
+
+    /// Why this attempt failed, None when it passed. An attempt fails when the evaluation
+    /// returned an error, a boolean metric is false or the agent logged an error.
+    pub fn attempt_failure(&self, run_error: Option<&str>) -> Option<String> {
+        if let Some(error) = run_error {
+            return Some(format!("evaluation error: {}", error));
+        }
+        let false_metrics: Vec<&str> = self
+            .metrics
+            .iter()
+            .filter(|(_, value)| matches!(value, EvalMetricValue::Boolean(false)))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if !false_metrics.is_empty() {
+            return Some(format!("false metrics: {}", false_metrics.join(", ")));
+        }
+        self.errors
+            .iter()
+            .find(|error| error.level == "ERROR")
+            .map(|error| format!("agent error: {}", error.message))
+    }
+
+    /// Record the outcome once the last attempt ran
+    pub fn finish(&mut self, attempts: usize, attempt_failures: Vec<String>) {
+        self.outcome = Some(if attempt_failures.len() >= attempts {
+            CaseOutcome::Failed
+        } else if attempt_failures.is_empty() {
+            CaseOutcome::Passed
+        } else {
+            CaseOutcome::Flaky
+        });
+        self.attempts = attempts;
+        self.attempt_failures = attempt_failures;
+    }
 }
 
 impl SuiteResult {
@@ -56,6 +153,14 @@ impl SuiteResult {
     pub fn add_evaluation(&mut self, eval: EvaluationResult) {
         self.evaluations.push(eval);
     }
+
+    pub fn outcome_counts(&self) -> OutcomeCounts {
+        let mut counts = OutcomeCounts::default();
+        for eval in &self.evaluations {
+            counts.add(eval.outcome);
+        }
+        counts
+    }
 }
 
 impl BenchmarkResults {
@@ -71,6 +176,16 @@ impl BenchmarkResults {
         self.suites.push(suite);
     }
 
+    pub fn outcome_counts(&self) -> OutcomeCounts {
+        let mut counts = OutcomeCounts::default();
+        for suite in &self.suites {
+            for eval in &suite.evaluations {
+                counts.add(eval.outcome);
+            }
+        }
+        counts
+    }
+
     /// Generate a summary of the benchmark results
     pub fn summary(&self) -> String {
         let mut summary = String::new();
@@ -92,6 +207,20 @@ impl BenchmarkResults {
             if total_errors > 0 {
                 summary.push_str(&format!("  Total errors: {}\n", total_errors));
             }
+
+            // Flaky evaluations are counted apart from failures, so a provider hiccup that a
+            // retry got past doesn't read as a regression
+            let counts = suite.outcome_counts();
+            if counts.total() > 0 {
+                summary.push_str(&format!(
+                    "  Passed: {}, flaky: {} ({:.0}%), failed: {} ({:.0}%)\n",
+                    counts.passed,
+                    counts.flaky,
+                    counts.flake_rate() * 100.0,
+                    counts.failed,
+                    counts.failure_rate() * 100.0
+                ));
+            }
         }
 
         summary
@@ -110,6 +239,18 @@ impl fmt::Display for BenchmarkResults {
 
             for eval in &suite.evaluations {
                 writeln!(f, "  Evaluation: {}", eval.name)?;
+                match eval.outcome {
+                    Some(CaseOutcome::Flaky) => {
+                        writeln!(f, "    Outcome: flaky, passed on attempt {}", eval.attempts)?
+                    }
+                    Some(CaseOutcome::Failed) => {
+                        writeln!(f, "    Outcome: failed all {} attempts", eval.attempts)?
+                    }
+                    Some(CaseOutcome::Passed) | None => {}
+                }
+                for (attempt, failure) in eval.attempt_failures.iter().enumerate() {
+                    writeln!(f, "    Attempt {} failed: {}", attempt + 1, failure)?;
+                }
                 for (metric_name, metric_value) in &eval.metrics {
                     writeln!(f, "    {}: {}", metric_name, metric_value)?;
                 }
@@ -131,3 +272,129 @@ impl fmt::Display for BenchmarkResults {
         Ok(())
     }
 }
+
+/// One metric of one evaluation in a [`PivotTable`], with a value per column
+#[derive(Debug, Serialize)]
+pub struct PivotRow {
+    pub suite: String,
+    pub evaluation: String,
+    pub metric: String,
+    /// Mean over the column's runs, `None` when the column didn't record the metric
+    pub values: Vec<Option<f64>>,
+}
+
+/// Numeric metrics side by side for several benchmark configurations, such as the cells of a
+/// matrix run, to compare them at a glance. Booleans count as 0 or 1, so their mean is a
+/// pass rate; string metrics are left out.
+#[derive(Debug, Default, Serialize)]
+pub struct PivotTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<PivotRow>,
+}
+
+impl PivotTable {
+    /// Build a table with a column per label, averaging the metrics of each label's runs
+    pub fn from_runs(columns: &[(String, Vec<BenchmarkResults>)]) -> Self {
+        let mut sums: BTreeMap<(String, String, String), Vec<(f64, usize)>> = BTreeMap::new();
+        for (index, (_, runs)) in columns.iter().enumerate() {
+            for run in runs {
+                for suite in &run.suites {
+                    for eval in &suite.evaluations {
+                        for (metric, value) in &eval.metrics {
+                            let value = match value {
+                                EvalMetricValue::Integer(i) => *i as f64,
+                                EvalMetricValue::Float(f) => *f,
+                                EvalMetricValue::Boolean(b) => f64::from(u8::from(*b)),
+                                EvalMetricValue::String(_) => continue,
+                            };
+                            let key = (suite.name.clone(), eval.name.clone(), metric.clone());
+                            let cells = sums
+                                .entry(key)
+                                .or_insert_with(|| vec![(0.0, 0); columns.len()]);
+                            cells[index].0 += value;
+                            cells[index].1 += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        PivotTable {
+            columns: columns.iter().map(|(label, _)| label.clone()).collect(),
+            rows: sums
+                .into_iter()
+                .map(|((suite, evaluation, metric), cells)| PivotRow {
+                    suite,
+                    evaluation,
+                    metric,
+                    values: cells
+                        .into_iter()
+                        .map(|(sum, count)| (count > 0).then(|| sum / count as f64))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        fn field(value: &str) -> String {
+            if value.contains([',', '"', '\n']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.to_string()
+            }
+        }
+
+        let mut csv = String::from("suite,evaluation,metric");
+        for column in &self.columns {
+            csv.push(',');
+            csv.push_str(&field(column));
+        }
+        csv.push('\n');
+        for row in &self.rows {
+            csv.push_str(&format!(
+                "{},{},{}",
+                field(&row.suite),
+                field(&row.evaluation),
+                field(&row.metric)
+            ));
+            for value in &row.values {
+                csv.push(',');
+                if let Some(value) = value {
+                    csv.push_str(&format!("{:.4}", value));
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+impl fmt::Display for PivotTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| format!("{}/{} {}", row.suite, row.evaluation, row.metric))
+            .collect();
+        let label_width = labels.iter().map(|l| l.len()).max().unwrap_or(0).max(6);
+        let widths: Vec<usize> = self.columns.iter().map(|c| c.len().max(8)).collect();
+
+        write!(f, "{:<label_width$}", "metric")?;
+        for (column, width) in self.columns.iter().zip(&widths) {
+            write!(f, "  {:>width$}", column)?;
+        }
+        writeln!(f)?;
+        for (label, row) in labels.iter().zip(&self.rows) {
+            write!(f, "{:<label_width$}", label)?;
+            for (value, width) in row.values.iter().zip(&widths) {
+                match value {
+                    Some(value) => write!(f, "  {:>width$.2}", value)?,
+                    None => write!(f, "  {:>width$}", "-")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,13 @@
 use crate::bench_config::{BenchModel, BenchRunConfig};
 use crate::bench_work_dir::BenchmarkWorkDir;
 use crate::eval_suites::EvaluationSuite;
+use crate::manifest::{BenchManifest, MANIFEST_FILENAME};
+use crate::reporting::BenchmarkResults;
 use crate::runners::model_runner::ModelRunner;
 use crate::utilities::{await_process_exits, parallel_bench_cmd};
 use anyhow::Context;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 #[derive(Clone)]
 pub struct BenchRunner {
@@ -14,13 +17,34 @@ pub struct BenchRunner {
 impl BenchRunner {
     pub fn new(config_path: PathBuf) -> anyhow::Result<BenchRunner> {
         let config = BenchRunConfig::from(config_path.clone())?;
+        Self::with_config(config, &format!("'{}'", config_path.display()))
+    }
+
+    /// Set up a run of an already loaded config, `source` names where it came from in errors
+    pub fn with_config(config: BenchRunConfig, source: &str) -> anyhow::Result<BenchRunner> {
+        let config = Self::init_experiment(config, source)?;
+        Ok(BenchRunner { config })
+    }
+
+    /// Create the experiment directory for `config` and move into it, saving the config and a
+    /// manifest of the environment the run happens in. Returns the config with its paths resolved.
+    pub fn init_experiment(
+        mut config: BenchRunConfig,
+        source: &str,
+    ) -> anyhow::Result<BenchRunConfig> {
+        // resolve the results dataset before moving into the experiment directory
+        if let Some(dataset) = &config.results_dataset {
+            if !dataset.is_absolute() {
+                config.results_dataset = Some(std::env::current_dir()?.join(dataset));
+            }
+        }
 
         let resolved_output_dir = match &config.output_dir {
             Some(path) => {
                 if !path.is_absolute() {
                     anyhow::bail!(
-                         "Config Error in '{}': 'output_dir' must be an absolute path, but found relative path: {}",
-                         config_path.display(),
+                         "Config Error in {}: 'output_dir' must be an absolute path, but found relative path: {}",
+                         source,
                          path.display()
                      );
                 }
@@ -34,7 +58,8 @@ impl BenchRunner {
         BenchmarkWorkDir::init_experiment(resolved_output_dir)?;
 
         config.save("config.cfg".to_string());
-        Ok(BenchRunner { config })
+        BenchManifest::capture(&config)?.save(Path::new(MANIFEST_FILENAME))?;
+        Ok(config)
     }
 
     pub fn from(config: String) -> anyhow::Result<BenchRunner> {
@@ -71,6 +96,39 @@ impl BenchRunner {
         Ok(())
     }
 
+    /// Results of every model run in the current experiment, read from their run summaries
+    pub fn collect_results(&self) -> anyhow::Result<Vec<(PathBuf, BenchmarkResults)>> {
+        fn find(dir: &Path, filename: &str, found: &mut Vec<PathBuf>) -> std::io::Result<()> {
+            for entry in fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    find(&path, filename, found)?;
+                } else if path.file_name().is_some_and(|name| name == filename) {
+                    found.push(path);
+                }
+            }
+            Ok(())
+        }
+
+        let experiment_dir = std::env::current_dir()?;
+        let mut summaries = Vec::new();
+        find(
+            &experiment_dir,
+            &self.config.run_summary_filename,
+            &mut summaries,
+        )?;
+        summaries.sort();
+
+        summaries
+            .into_iter()
+            .map(|path| {
+                let results = serde_json::from_str(&fs::read_to_string(&path)?)
+                    .with_context(|| format!("Failed to parse {}", path.display()))?;
+                Ok((path, results))
+            })
+            .collect()
+    }
+
     pub fn list_selectors(_config: Option<PathBuf>) -> anyhow::Result<()> {
         let selector_eval_counts = EvaluationSuite::available_selectors();
         let mut keys: Vec<_> = selector_eval_counts.keys().collect();
@@ -3,6 +3,7 @@ use crate::bench_session::BenchAgent;
 use crate::bench_work_dir::BenchmarkWorkDir;
 use crate::eval_suites::{EvaluationSuite, ExtensionRequirements};
 use crate::reporting::EvaluationResult;
+use crate::server_session::ServerSession;
 use crate::utilities::await_process_exits;
 use anyhow::{bail, Context, Result};
 use std::env;
@@ -82,35 +83,77 @@ impl EvalRunner {
         tracing::info!("Set evaluation directory for {}", bench_eval.selector);
 
         if let Some(eval) = EvaluationSuite::from(&bench_eval.selector) {
-            let now_stamp = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .context("Failed to get current timestamp")?
-                .as_nanos();
-
-            let session_id = format!("{}-{}", bench_eval.selector.clone(), now_stamp);
-            let mut agent = agent_generator(eval.required_extensions(), session_id).await;
-            tracing::info!("Agent created for {}", eval.name());
-
-            let mut result = EvaluationResult::new(eval.name().to_string());
-
-            match eval.run(&mut agent, &mut work_dir).await {
-                Ok(metrics) => {
-                    tracing::info!("Evaluation run successful with {} metrics", metrics.len());
-                    for (name, metric) in metrics {
-                        result.add_metric(name, metric);
+            let mut attempt_failures = Vec::new();
+            let (result, agent) = loop {
+                let now_stamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .context("Failed to get current timestamp")?
+                    .as_nanos();
+
+                // Every attempt gets a fresh session, so a retry doesn't see the failed attempt
+                let session_id = format!("{}-{}", bench_eval.selector.clone(), now_stamp);
+                let mut agent = match &self.config.server {
+                    Some(server) => {
+                        let session = ServerSession::connect(
+                            server,
+                            self.config.models.first(),
+                            eval.required_extensions(),
+                            session_id,
+                        )
+                        .await
+                        .context("Failed to connect to goose-server")?;
+                        BenchAgent::new(Box::new(session))
                     }
+                    None => agent_generator(eval.required_extensions(), session_id).await,
+                };
+                tracing::info!("Agent created for {}", eval.name());
+
+                let mut result = EvaluationResult::new(eval.name().to_string());
+
+                let run_error = match eval.run(&mut agent, &mut work_dir).await {
+                    Ok(metrics) => {
+                        tracing::info!("Evaluation run successful with {} metrics", metrics.len());
+                        for (name, metric) in metrics {
+                            result.add_metric(name, metric);
+                        }
+                        None
+                    }
+                    Err(e) => {
+                        tracing::error!("Evaluation run failed: {}", e);
+                        Some(e.to_string())
+                    }
+                };
+
+                // Add any errors that occurred
+                let errors = agent.get_errors().await;
+                tracing::info!("Agent reported {} errors", errors.len());
+                for error in errors {
+                    result.add_error(error);
                 }
-                Err(e) => {
-                    tracing::error!("Evaluation run failed: {}", e);
-                }
-            }
 
-            // Add any errors that occurred
-            let errors = agent.get_errors().await;
-            tracing::info!("Agent reported {} errors", errors.len());
-            for error in errors {
-                result.add_error(error);
-            }
+                let attempts = attempt_failures.len() + 1;
+                let failure = result.attempt_failure(run_error.as_deref());
+                let Some(failure) = failure else {
+                    result.finish(attempts, attempt_failures);
+                    break (result, agent);
+                };
+                attempt_failures.push(failure);
+                if attempts > bench_eval.retries {
+                    result.finish(attempts, attempt_failures);
+                    break (result, agent);
+                }
+                // Logged at info level, the error capture layer would record a warning as an
+                // agent error of the next attempt
+                tracing::info!(
+                    "Attempt {} of {} failed, retrying: {}",
+                    attempts,
+                    eval.name(),
+                    attempt_failures.last().unwrap()
+                );
+                work_dir
+                    .reset_eval()
+                    .context("Failed to reset the evaluation directory for a retry")?;
+            };
 
             // Write results to file
             let eval_results = serde_json::to_string_pretty(&result)
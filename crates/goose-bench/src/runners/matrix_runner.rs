@@ -0,0 +1,170 @@
+use crate::bench_config::{BenchAgentVersion, BenchMatrix, BenchRunConfig};
+use crate::reporting::{BenchmarkResults, PivotTable};
+use crate::runners::bench_runner::BenchRunner;
+use crate::utilities::await_process_exits;
+use anyhow::{bail, Context, Result};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const PIVOT_FILENAME: &str = "matrix-pivot.csv";
+
+/// The cells of a matrix sharing an agent version, provider and model. They run in one model
+/// runner, so every suite shares its process, environment and work directories.
+#[derive(Clone, Debug)]
+pub struct MatrixGroup {
+    pub agent: BenchAgentVersion,
+    pub provider: String,
+    pub model: String,
+    pub suites: Vec<String>,
+}
+
+impl MatrixGroup {
+    /// Names the group's column in the comparison
+    pub fn label(&self) -> String {
+        format!("{}/{}/{}", self.agent.name, self.provider, self.model)
+    }
+
+    /// Each agent version runs in its own directory of the experiment, since results are laid
+    /// out by provider and model only
+    fn agent_dir(&self) -> String {
+        self.agent
+            .name
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' => '_',
+                c => c,
+            })
+            .collect()
+    }
+}
+
+pub struct MatrixRunner {
+    config: BenchRunConfig,
+    groups: Vec<MatrixGroup>,
+}
+
+impl MatrixRunner {
+    /// Set up a run of the config's matrix, `source` names where it came from in errors
+    pub fn with_config(config: BenchRunConfig, source: &str) -> Result<MatrixRunner> {
+        let matrix = config
+            .matrix
+            .clone()
+            .with_context(|| format!("Config Error in {}: there is no 'matrix' to run", source))?;
+        let groups = Self::group_cells(&matrix);
+        if groups.is_empty() {
+            bail!(
+                "Config Error in {}: every combination in the matrix is excluded",
+                source
+            );
+        }
+
+        let config = BenchRunner::init_experiment(config, source)?;
+        Ok(MatrixRunner { config, groups })
+    }
+
+    fn group_cells(matrix: &BenchMatrix) -> Vec<MatrixGroup> {
+        let agents = matrix.agent_versions();
+        let mut groups: Vec<MatrixGroup> = Vec::new();
+        for cell in matrix.expand() {
+            if let Some(group) = groups.iter_mut().find(|group| {
+                group.agent.name == cell.agent
+                    && group.provider == cell.provider
+                    && group.model == cell.model
+            }) {
+                group.suites.push(cell.suite);
+                continue;
+            }
+            if let Some(agent) = agents.iter().find(|agent| agent.name == cell.agent) {
+                groups.push(MatrixGroup {
+                    agent: agent.clone(),
+                    provider: cell.provider,
+                    model: cell.model,
+                    suites: vec![cell.suite],
+                });
+            }
+        }
+        groups
+    }
+
+    pub fn groups(&self) -> &[MatrixGroup] {
+        &self.groups
+    }
+
+    /// Run every group at once, each with its agent version's binary and environment
+    pub fn run(&self) -> Result<()> {
+        let experiment_dir = env::current_dir()?;
+        let mut children = Vec::new();
+
+        for group in &self.groups {
+            let dir = experiment_dir.join(group.agent_dir());
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+            let mut config = self.config.clone();
+            config.matrix = None;
+            config.set_model(group.provider.clone(), group.model.clone());
+            config.set_selectors(&group.suites);
+
+            let binary = match &group.agent.binary {
+                Some(binary) => binary.clone(),
+                None => env::current_exe().context("Failed to get current executable path")?,
+            };
+            tracing::info!("Running {} with {}", group.label(), binary.display());
+            let child = Command::new(&binary)
+                .arg("bench")
+                .arg("eval-model")
+                .arg("--config")
+                .arg(config.to_string()?)
+                .current_dir(&dir)
+                .envs(&group.agent.envs)
+                .spawn()
+                .with_context(|| format!("Failed to run {}", binary.display()))?;
+            children.push(child);
+        }
+
+        await_process_exits(&mut children, Vec::new());
+        Ok(())
+    }
+
+    /// The run summaries of each group, labelled for the comparison. Groups that failed to
+    /// produce results are kept with no runs so they still get a column.
+    pub fn collect_results(&self) -> Result<Vec<(String, Vec<BenchmarkResults>)>> {
+        let experiment_dir = env::current_dir()?;
+        let mut results = Vec::new();
+
+        for group in &self.groups {
+            let model_dir = experiment_dir
+                .join(group.agent_dir())
+                .join(format!("{}-{}", group.provider, group.model));
+            let mut runs = Vec::new();
+            for i in 0..self.config.repeat.unwrap_or(1) {
+                let path = model_dir
+                    .join(format!("run-{}", i))
+                    .join(&self.config.run_summary_filename);
+                match fs::read_to_string(&path) {
+                    Ok(content) => runs.push(
+                        serde_json::from_str(&content)
+                            .with_context(|| format!("Failed to parse {}", path.display()))?,
+                    ),
+                    Err(e) => tracing::warn!("No results for {} run {}: {}", group.label(), i, e),
+                }
+            }
+            results.push((group.label(), runs));
+        }
+        Ok(results)
+    }
+
+    /// Compare the groups side by side and write the comparison to the experiment directory
+    pub fn write_pivot(
+        &self,
+        results: &[(String, Vec<BenchmarkResults>)],
+    ) -> Result<(PivotTable, PathBuf)> {
+        let pivot = PivotTable::from_runs(results);
+        let path = env::current_dir()?.join(PIVOT_FILENAME);
+        fs::write(&path, pivot.to_csv())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok((pivot, path))
+    }
+}
@@ -1,4 +1,5 @@
 pub mod bench_runner;
 pub mod eval_runner;
+pub mod matrix_runner;
 pub mod metric_aggregator;
 pub mod model_runner;
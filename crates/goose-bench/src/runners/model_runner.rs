@@ -1,4 +1,5 @@
 use crate::bench_config::{BenchEval, BenchModel, BenchRunConfig};
+use crate::dataframe_handler::append_results;
 use crate::eval_suites::EvaluationSuite;
 use crate::reporting::{BenchmarkResults, SuiteResult};
 use crate::runners::eval_runner::EvalRunner;
@@ -45,19 +46,37 @@ impl ModelRunner {
         }
         await_process_exits(&mut Vec::new(), handles);
 
-        let mut all_runs_results: Vec<BenchmarkResults> = Vec::new();
+        let mut all_runs_results: Vec<(usize, BenchmarkResults)> = Vec::new();
         for i in 0..self.config.repeat.unwrap_or(1) {
             match self.collect_run_results(model.clone(), suites.clone(), i.to_string()) {
-                Ok(run_results) => all_runs_results.push(run_results),
+                Ok(run_results) => all_runs_results.push((i, run_results)),
                 Err(e) => {
                     tracing::error!("Failed to collect results for run {}: {}", i, e)
                 }
             }
         }
 
+        if let Some(dataset) = &self.config.results_dataset {
+            append_results(dataset, &self.dataset_run_id()?, model, &all_runs_results)
+                .context("Failed to append results to dataset")?;
+        }
+
         Ok(())
     }
 
+    /// Key for this benchmark in the results dataset: the configured run id, or the name
+    /// of the experiment directory the benchmark is running in
+    fn dataset_run_id(&self) -> Result<String> {
+        if let Some(run_id) = &self.config.run_id {
+            return Ok(run_id.clone());
+        }
+        let current_dir = std::env::current_dir().context("Failed to get current directory")?;
+        Ok(current_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "run-0".to_string()))
+    }
+
     fn run_benchmark(
         &self,
         model: &BenchModel,
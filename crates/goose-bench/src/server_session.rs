@@ -0,0 +1,284 @@
+use crate::bench_config::{BenchModel, BenchServerConfig};
+use crate::bench_session::BenchBaseSession;
+use crate::eval_suites::ExtensionRequirements;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use goose::message::{Message, MessageContent};
+use goose::providers::base::ResponseTiming;
+use goose::session::{self, Identifier, SessionMetadata};
+use reqwest::{Client, Response};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::env;
+use std::path::PathBuf;
+
+/// Environment variable used for the server secret when the config does not set one
+pub const SERVER_SECRET_KEY_ENV: &str = "GOOSE_SERVER__SECRET_KEY";
+
+const SECRET_KEY_HEADER: &str = "X-Secret-Key";
+
+/// Events streamed by the goose-server `/reply` endpoint
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum ReplyEvent {
+    Message {
+        message: Message,
+    },
+    Error {
+        error: String,
+    },
+    Finish {
+        reason: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionHistoryResponse {
+    metadata: SessionMetadata,
+    messages: Vec<Message>,
+}
+
+/// Benchmark session that drives a running goose-server over HTTP/SSE.
+///
+/// The conversation is replayed through `/reply` on every prompt, exactly as the desktop
+/// client does, so the benchmark exercises the server's own provider, extensions and
+/// middleware. After each reply the server-side session history is mirrored into a local
+/// session file so results can be collected the same way as for in-process runs.
+pub struct ServerSession {
+    client: Client,
+    base_url: String,
+    secret_key: String,
+    session_id: String,
+    working_dir: PathBuf,
+    session_file: PathBuf,
+    messages: Vec<Message>,
+    total_tokens: Option<i32>,
+    last_response_timing: Option<ResponseTiming>,
+}
+
+impl ServerSession {
+    /// Connect to the server, optionally switch its provider to the benchmarked model and
+    /// enable the extensions required by the evaluation.
+    pub async fn connect(
+        config: &BenchServerConfig,
+        model: Option<&BenchModel>,
+        requirements: ExtensionRequirements,
+        session_id: String,
+    ) -> Result<Self> {
+        let secret_key = match &config.secret_key {
+            Some(key) => key.clone(),
+            None => env::var(SERVER_SECRET_KEY_ENV).with_context(|| {
+                format!(
+                    "No secret key configured for goose-server, set {}",
+                    SERVER_SECRET_KEY_ENV
+                )
+            })?,
+        };
+
+        let session = ServerSession {
+            client: Client::new(),
+            base_url: config.url.trim_end_matches('/').to_string(),
+            secret_key,
+            session_file: session::get_path(Identifier::Name(session_id.clone())),
+            session_id,
+            working_dir: env::current_dir().context("Failed to get current directory")?,
+            messages: Vec::new(),
+            total_tokens: None,
+            last_response_timing: None,
+        };
+
+        if config.update_provider {
+            let model = model.context("No model specified in configuration")?;
+            session
+                .post(
+                    "/agent/update_provider",
+                    json!({ "provider": model.provider, "model": model.name }),
+                )
+                .await
+                .context("Failed to update goose-server provider")?;
+        }
+
+        for extension in extension_requests(&requirements) {
+            let response = session
+                .post("/extensions/add", extension)
+                .await
+                .context("Failed to add extension to goose-server")?;
+            let body: Value = response.json().await?;
+            if body.get("error").and_then(Value::as_bool).unwrap_or(false) {
+                bail!(
+                    "goose-server failed to add extension: {}",
+                    body.get("message").and_then(Value::as_str).unwrap_or("")
+                );
+            }
+        }
+
+        Ok(session)
+    }
+
+    async fn post(&self, path: &str, body: Value) -> Result<Response> {
+        let response = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .header(SECRET_KEY_HEADER, &self.secret_key)
+            .json(&body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            bail!("goose-server returned {} for {}", response.status(), path);
+        }
+        Ok(response)
+    }
+
+    /// Approve any tool confirmation requests so headless runs never stall on the server
+    async fn confirm_tool_requests(&self, message: &Message) -> Result<()> {
+        for content in &message.content {
+            if let MessageContent::ToolConfirmationRequest(request) = content {
+                self.post(
+                    "/confirm",
+                    json!({ "id": request.id, "action": "allow_once" }),
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Mirror the server's view of the session into the local session file
+    async fn sync_session(&mut self) -> Result<()> {
+        let response = self
+            .client
+            .get(format!("{}/sessions/{}", self.base_url, self.session_id))
+            .header(SECRET_KEY_HEADER, &self.secret_key)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            bail!(
+                "goose-server returned {} for session {}",
+                response.status(),
+                self.session_id
+            );
+        }
+
+        let history: SessionHistoryResponse = response.json().await?;
+        self.total_tokens = history.metadata.total_tokens;
+        self.last_response_timing = history.metadata.last_response_timing;
+        session::storage::save_messages_with_metadata(
+            &self.session_file,
+            &history.metadata,
+            &history.messages,
+        )
+    }
+}
+
+#[async_trait]
+impl BenchBaseSession for ServerSession {
+    async fn headless(&mut self, message: String) -> Result<()> {
+        self.messages.push(Message::user().with_text(message));
+
+        let mut response = self
+            .post(
+                "/reply",
+                json!({
+                    "messages": self.messages,
+                    "session_id": self.session_id,
+                    "session_working_dir": self.working_dir.to_string_lossy(),
+                }),
+            )
+            .await?;
+
+        let mut buffer = Vec::new();
+        'stream: while let Some(chunk) = response.chunk().await? {
+            buffer.extend_from_slice(&chunk);
+            for event in drain_events(&mut buffer) {
+                match event {
+                    ReplyEvent::Message { message } => {
+                        self.confirm_tool_requests(&message).await?;
+                        self.messages.push(message);
+                    }
+                    ReplyEvent::Error { error } => bail!("goose-server error: {}", error),
+                    ReplyEvent::Finish { reason } => {
+                        tracing::debug!("goose-server reply finished: {}", reason);
+                        break 'stream;
+                    }
+                    ReplyEvent::Other => {}
+                }
+            }
+        }
+
+        if let Err(e) = self.sync_session().await {
+            tracing::warn!("Failed to sync session from goose-server: {}", e);
+        }
+        Ok(())
+    }
+
+    fn session_file(&self) -> PathBuf {
+        self.session_file.clone()
+    }
+
+    fn message_history(&self) -> Vec<Message> {
+        self.messages.clone()
+    }
+
+    fn get_total_token_usage(&self) -> Result<Option<i32>> {
+        Ok(self.total_tokens)
+    }
+
+    fn get_last_response_timing(&self) -> Result<Option<ResponseTiming>> {
+        Ok(self.last_response_timing)
+    }
+}
+
+/// Build `/extensions/add` request bodies for the extensions an evaluation requires
+fn extension_requests(requirements: &ExtensionRequirements) -> Vec<Value> {
+    let mut requests = Vec::new();
+
+    for builtin in &requirements.builtin {
+        for name in builtin.split(',') {
+            requests.push(json!({ "type": "builtin", "name": name.trim() }));
+        }
+    }
+
+    for (i, extension) in requirements.external.iter().enumerate() {
+        let mut parts = extension.split_whitespace();
+        if let Some(cmd) = parts.next() {
+            requests.push(json!({
+                "type": "stdio",
+                "name": format!("bench-stdio-{}", i),
+                "cmd": cmd,
+                "args": parts.collect::<Vec<_>>(),
+            }));
+        }
+    }
+
+    for (i, uri) in requirements.remote.iter().enumerate() {
+        requests.push(json!({
+            "type": "sse",
+            "name": format!("bench-sse-{}", i),
+            "uri": uri,
+        }));
+    }
+
+    requests
+}
+
+/// Take every complete `data:` event out of the buffer, leaving any partial event behind
+fn drain_events(buffer: &mut Vec<u8>) -> Vec<ReplyEvent> {
+    let mut events = Vec::new();
+
+    while let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+        let raw: Vec<u8> = buffer.drain(..end + 2).collect();
+        for line in String::from_utf8_lossy(&raw).lines() {
+            let Some(data) = line.strip_prefix("data:") else {
+                continue;
+            };
+            match serde_json::from_str(data.trim()) {
+                Ok(event) => events.push(event),
+                Err(e) => tracing::warn!("Skipping malformed goose-server event: {}", e),
+            }
+        }
+    }
+
+    events
+}
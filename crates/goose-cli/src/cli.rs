@@ -1,31 +1,58 @@
 use anyhow::Result;
 use clap::{Args, Parser, Subcommand};
 
+use goose::agents::dry_run::DryRunMode;
+use goose::agents::workspace::{IsolatedWorkspace, WORKSPACE_ISOLATION_KEY};
 use goose::config::{Config, ExtensionConfig};
+use goose_mcp::memory_transfer::ConflictStrategy;
 
-use crate::commands::bench::agent_generator;
+use crate::commands::bench::{agent_generator, handle_bench_run};
+use crate::commands::bundle::{handle_configure_export, handle_configure_import};
 use crate::commands::configure::handle_configure;
+use crate::commands::diff_apply::handle_diff_apply;
+use crate::commands::doctor::handle_doctor;
+use crate::commands::extensions::{
+    handle_extensions_enable, handle_extensions_install, handle_extensions_search,
+    handle_extensions_show,
+};
 use crate::commands::info::handle_info;
+use crate::commands::init::handle_init;
+use crate::commands::keys::{handle_keys_list, handle_keys_remove, handle_keys_rotate};
 use crate::commands::mcp::run_server;
+use crate::commands::memory::{handle_memory_export, handle_memory_import, handle_memory_sync};
 use crate::commands::project::{handle_project_default, handle_projects_interactive};
+use crate::commands::prompt::{
+    handle_prompt_list, handle_prompt_remove, handle_prompt_run, handle_prompt_save,
+};
 use crate::commands::recipe::{handle_deeplink, handle_validate};
+use crate::commands::review::{review_run, WorktreeSnapshot};
 // Import the new handlers from commands::schedule
+use crate::commands::run::{run_headless, RunOutcome};
 use crate::commands::schedule::{
     handle_schedule_add, handle_schedule_list, handle_schedule_remove, handle_schedule_run_now,
     handle_schedule_sessions,
 };
-use crate::commands::session::{handle_session_list, handle_session_remove};
+use crate::commands::session::{
+    handle_session_context, handle_session_list, handle_session_migrate, handle_session_pause,
+    handle_session_remove, handle_session_rollback, paused_run,
+};
+use crate::commands::session_logs::handle_session_logs;
+use crate::commands::tasks::{handle_run_tasks, TaskOptions};
+use crate::commands::usage::handle_usage;
+use crate::commands::watch::watch_and_run;
 use crate::logging::setup_logging;
 use crate::recipes::recipe::{explain_recipe_with_parameters, load_recipe_as_template};
 use crate::session;
 use crate::session::{build_session, SessionBuilderConfig};
 use goose_bench::bench_config::BenchRunConfig;
+use goose_bench::manifest::BenchManifest;
 use goose_bench::runners::bench_runner::BenchRunner;
 use goose_bench::runners::eval_runner::EvalRunner;
 use goose_bench::runners::metric_aggregator::MetricAggregator;
 use goose_bench::runners::model_runner::ModelRunner;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(author, version, display_name = "", about, long_about = None)]
@@ -94,6 +121,22 @@ enum SessionCommand {
             long_help = "Sort sessions by date in ascending order (oldest first). Default is descending order (newest first)."
         )]
         ascending: bool,
+
+        #[arg(
+            long = "tag",
+            value_name = "TAG",
+            help = "Only list sessions with this tag, can be repeated",
+            long_help = "Only list sessions tagged with /tag. When repeated, sessions must have every tag."
+        )]
+        tags: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            value_name = "TEXT",
+            help = "Only list sessions whose description or notes contain this text"
+        )]
+        search: Option<String>,
     },
     #[command(about = "Remove sessions. Runs interactively if no ID or regex is provided.")]
     Remove {
@@ -115,6 +158,67 @@ enum SessionCommand {
         )]
         output: Option<PathBuf>,
     },
+    #[command(
+        about = "Upgrade session files written by older versions of goose",
+        long_help = "Rewrite session files in the current message format so they keep working after an upgrade. The original of each migrated file is kept next to it as a .bak file."
+    )]
+    Migrate {
+        #[arg(
+            long = "dry-run",
+            help = "Only report which sessions would be migrated"
+        )]
+        dry_run: bool,
+    },
+    #[command(
+        about = "Undo every file edit goose made during a run",
+        long_help = "Restore the files the developer extension wrote during a run to how they were before it, using its edit journal rather than git. Runs are listed interactively when no ID is given."
+    )]
+    Rollback {
+        #[arg(help = "ID of the run to roll back (optional)")]
+        run_id: Option<String>,
+
+        #[arg(
+            long,
+            help = "Also restore files that were changed after goose edited them"
+        )]
+        force: bool,
+    },
+    #[command(
+        about = "Show what fills the context window of a session",
+        long_help = "Break the context window of a session down into the system prompt, each extension's instructions and tool schemas, and the conversation, with token counts and what providers with prompt caching reuse between turns. Uses the most recent session when none is given, with the extensions in your config."
+    )]
+    Context {
+        #[command(flatten)]
+        identifier: Option<Identifier>,
+    },
+    #[command(
+        about = "Pause a running headless session so it can be continued interactively",
+        long_help = "Ask a `goose run` working on the session to stop after its current step and save a checkpoint, the same as pressing Ctrl+C in its terminal. Continue it with `goose session --resume --from-run <ID>`."
+    )]
+    Pause {
+        #[arg(help = "ID of the run, the name of its session")]
+        run_id: String,
+    },
+    #[command(
+        about = "Show the logs of a session",
+        long_help = "Merge the CLI, agent and extension logs of a session by time, from every day it ran. Uses the most recent session when none is given."
+    )]
+    Logs {
+        #[command(flatten)]
+        identifier: Option<Identifier>,
+
+        #[arg(short, long, help = "Keep printing new log lines as they are written")]
+        follow: bool,
+
+        #[arg(
+            short,
+            long,
+            value_name = "LEVEL",
+            help = "Lowest level to show (error, warn, info, debug, trace)",
+            default_value = "info"
+        )]
+        level: String,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -157,6 +261,161 @@ enum SchedulerCommand {
     },
 }
 
+#[derive(Subcommand)]
+enum ConfigureCommand {
+    #[command(about = "Package the configuration into a bundle for another machine")]
+    Export {
+        /// Bundle to write, gzipped when it ends in .tgz or .gz
+        path: PathBuf,
+        #[arg(
+            long,
+            help = "Include the stored secrets, encrypted with a passphrase",
+            long_help = "Include the stored secrets and the credentials redacted from config.yaml, encrypted with AES-256-GCM under a passphrase from GOOSE_BUNDLE_PASSPHRASE or a prompt"
+        )]
+        include_secrets: bool,
+    },
+    #[command(about = "Apply a bundle written by export, backing up the files it replaces")]
+    Import {
+        /// Bundle to read
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeysCommand {
+    #[command(about = "List stored provider keys with their age and expiry")]
+    List {},
+    #[command(about = "Replace a provider key with a new value")]
+    Rotate {
+        /// Name of the key, e.g. OPENAI_API_KEY
+        name: String,
+        #[arg(long, help = "Number of days until the new key expires")]
+        expires_in_days: Option<i64>,
+    },
+    #[command(about = "Remove a provider key from the keyring")]
+    Remove {
+        /// Name of the key, e.g. OPENAI_API_KEY
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum MemoryCommand {
+    #[command(about = "Export global and local memories to a JSON file")]
+    Export {
+        /// File to write the memories to
+        path: PathBuf,
+    },
+    #[command(about = "Import memories from a file written by export")]
+    Import {
+        /// File to read the memories from
+        path: PathBuf,
+        #[arg(
+            long,
+            default_value = "merge",
+            help = "How to handle categories that already exist: merge, overwrite or skip"
+        )]
+        strategy: ConflictStrategy,
+    },
+    #[command(about = "Merge memories with a git repository and push the result")]
+    Sync {
+        #[arg(
+            long,
+            help = "Local git repository to sync through, defaults to $GOOSE_MEMORY_SYNC_REPO"
+        )]
+        repo: Option<PathBuf>,
+        #[arg(long, help = "Remote to clone when the repository does not exist yet")]
+        remote: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExtensionsCommand {
+    #[command(about = "Search the extension registry by keyword")]
+    Search {
+        /// Words that must all appear in the id, name or description, lists everything when left out
+        query: Option<String>,
+    },
+    #[command(about = "Show the command and environment variables of a registry extension")]
+    Show {
+        /// ID of the extension, e.g. github
+        id: String,
+    },
+    #[command(about = "Add a registry extension to your config without prompting")]
+    Install {
+        /// ID of the extension, e.g. github
+        id: String,
+        #[arg(
+            long,
+            help = "Name to configure the extension under, defaults to its ID"
+        )]
+        name: Option<String>,
+        #[arg(
+            long = "env",
+            value_name = "KEY=VALUE",
+            help = "Environment variable for the extension (can be specified multiple times)",
+            long_help = "Environment variable for the extension, stored in the keyring when possible. Can be specified multiple times. Required variables already in the keyring or the environment don't need to be passed.",
+            action = clap::ArgAction::Append,
+            value_parser = parse_key_val,
+        )]
+        envs: Vec<(String, String)>,
+        #[arg(long, help = "Timeout for the extension's tools in seconds")]
+        timeout: Option<u64>,
+    },
+    #[command(about = "Turn an extension back on after it was disabled for failing repeatedly")]
+    Enable {
+        /// Name of the extension, as shown when it was disabled
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PromptCommand {
+    #[command(about = "Save a prompt under a name, replacing any prompt with that name")]
+    Save {
+        /// Name to run the prompt by, e.g. review
+        name: String,
+        /// Text of the prompt, {{ variable }} placeholders are filled in when it runs.
+        /// Read from stdin when omitted or -
+        text: Option<String>,
+    },
+    #[command(about = "List saved prompts and the variables they take")]
+    List {
+        #[arg(short, long, help = "Also show the text of each prompt")]
+        verbose: bool,
+    },
+    #[command(about = "Run a saved prompt in a new session")]
+    Run {
+        /// Name of the prompt
+        name: String,
+        #[arg(
+            long,
+            value_name = "KEY=VALUE",
+            help = "Value for a variable in the prompt (can be specified multiple times)",
+            action = clap::ArgAction::Append,
+            value_parser = parse_key_val,
+        )]
+        params: Vec<(String, String)>,
+        #[arg(
+            short = 's',
+            long = "interactive",
+            help = "Continue in interactive mode after running the prompt"
+        )]
+        interactive: bool,
+        #[arg(
+            long,
+            value_name = "SECONDS",
+            help = "Stop the run after this many seconds"
+        )]
+        timeout: Option<u64>,
+    },
+    #[command(about = "Remove a saved prompt")]
+    Remove {
+        /// Name of the prompt
+        name: String,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum BenchCommand {
     #[command(name = "init-config", about = "Create a new starter-config")]
@@ -165,14 +424,49 @@ pub enum BenchCommand {
         name: String,
     },
 
-    #[command(about = "Run all benchmarks from a config")]
+    #[command(
+        about = "Run benchmarks from a config, or against the configured model",
+        long_help = "Run benchmarks and print a summary of the results. Without --config the default suites run against the configured provider and model. --suite, --provider and --model override the config."
+    )]
     Run {
         #[arg(
             short,
             long,
             help = "A config file generated by the config-init command"
         )]
-        config: PathBuf,
+        config: Option<PathBuf>,
+
+        #[arg(
+            short,
+            long = "suite",
+            value_name = "SELECTOR",
+            help = "Eval selector to run, such as 'core' (can be specified multiple times)"
+        )]
+        suites: Vec<String>,
+
+        #[arg(
+            long,
+            help = "Provider to benchmark, defaults to the configured provider"
+        )]
+        provider: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "Model to benchmark, defaults to the configured model"
+        )]
+        model: Option<String>,
+
+        #[arg(long, help = "Number of times to run each eval")]
+        repeat: Option<usize>,
+
+        #[arg(
+            long,
+            value_name = "NUMBER",
+            help = "Run a failing eval again up to NUMBER times",
+            long_help = "Run a failing eval again up to NUMBER times. Evals that pass on a retry are reported as flaky, apart from evals that fail every attempt."
+        )]
+        retries: Option<usize>,
     },
 
     #[command(about = "List all available selectors")]
@@ -209,6 +503,22 @@ pub enum BenchCommand {
         )]
         benchmark_dir: PathBuf,
     },
+
+    #[command(
+        about = "Check that the environment matches a benchmark run's manifest",
+        long_help = "Compare the goose version and commit, models, configs, extensions, evals and datasets a run of the config would use now with the manifest.json recorded in an experiment directory. Exits with an error listing each difference."
+    )]
+    Verify {
+        #[arg(help = "Path to the manifest.json of a previous run")]
+        manifest: PathBuf,
+
+        #[arg(
+            short,
+            long,
+            help = "Config of the new run, defaults to the config.cfg next to the manifest"
+        )]
+        config: Option<PathBuf>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -236,7 +546,10 @@ enum RecipeCommand {
 enum Command {
     /// Configure Goose settings
     #[command(about = "Configure Goose settings")]
-    Configure {},
+    Configure {
+        #[command(subcommand)]
+        command: Option<ConfigureCommand>,
+    },
 
     /// Display Goose configuration information
     #[command(about = "Display Goose information")]
@@ -246,6 +559,68 @@ enum Command {
         verbose: bool,
     },
 
+    /// Check that goose can reach the configured provider
+    #[command(
+        about = "Check the HTTP proxy and TLS settings and that the provider can be reached"
+    )]
+    Doctor {
+        #[arg(
+            long,
+            help = "Endpoint to check instead of the configured provider's host"
+        )]
+        url: Option<String>,
+    },
+
+    /// Scaffold goose files for the current project
+    #[command(about = "Set up .goose/config.yaml, .goosehints and .gooseignore for this project")]
+    Init {
+        /// Overwrite files that already exist
+        #[arg(long, help = "Overwrite existing files")]
+        force: bool,
+    },
+
+    /// Show token usage and estimated spend
+    #[command(about = "Show monthly token usage and estimated spend")]
+    Usage {
+        /// Month to summarize, defaults to the current month
+        #[arg(short, long, help = "Month to summarize as YYYY-MM")]
+        month: Option<String>,
+
+        /// Also show usage per day
+        #[arg(short, long, help = "Break usage down by day")]
+        daily: bool,
+    },
+
+    /// Manage stored provider keys
+    #[command(about = "List, rotate and remove stored provider keys")]
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
+
+    /// Move memories between machines
+    #[command(about = "Export, import and sync memories stored by the memory extension")]
+    Memory {
+        #[command(subcommand)]
+        command: MemoryCommand,
+    },
+
+    /// Find and add extensions from the registry
+    #[command(
+        about = "Search, show and install extensions from the extension registry, or re-enable a failing one"
+    )]
+    Extensions {
+        #[command(subcommand)]
+        command: ExtensionsCommand,
+    },
+
+    /// Reuse common instructions
+    #[command(about = "Save, list and run named prompts, also available as /prompts in sessions")]
+    Prompt {
+        #[command(subcommand)]
+        command: PromptCommand,
+    },
+
     /// Manage system prompts and behaviors
     #[command(about = "Run one of the mcp servers bundled with goose")]
     Mcp { name: String },
@@ -279,6 +654,17 @@ enum Command {
         )]
         history: bool,
 
+        /// Continue a paused headless run
+        #[arg(
+            long = "from-run",
+            value_name = "ID",
+            help = "Continue a headless run that was paused, with its extensions",
+            long_help = "Open a `goose run` that was paused with Ctrl+C or `goose session pause` in an interactive session, with the extensions it had, so you can steer it. Hand it back with /handoff.",
+            requires = "resume",
+            conflicts_with_all = ["name", "path"]
+        )]
+        from_run: Option<String>,
+
         /// Enable debug output mode
         #[arg(
             long,
@@ -287,6 +673,14 @@ enum Command {
         )]
         debug: bool,
 
+        /// Print markdown as it is written
+        #[arg(
+            long,
+            help = "Print replies as raw markdown instead of rendering them",
+            long_help = "Replies are rendered for the terminal, with headings, lists and tables laid out and code highlighted. This prints the markdown as the model wrote it instead, set GOOSE_CLI_PLAIN to true to make it the default."
+        )]
+        plain: bool,
+
         /// Maximum number of consecutive identical tool calls allowed
         #[arg(
             long = "max-tool-repetitions",
@@ -296,6 +690,14 @@ enum Command {
         )]
         max_tool_repetitions: Option<u32>,
 
+        /// Don't load what earlier sessions in this project did
+        #[arg(
+            long = "no-project-history",
+            help = "Don't start with a digest of earlier sessions in this project",
+            long_help = "New sessions start with a short digest of recent sessions in the same repository: what they were asked, which files they changed and what they left open. This skips it for this session, set GOOSE_PROJECT_HISTORY to false to turn it off everywhere."
+        )]
+        no_project_history: bool,
+
         /// Add stdio extensions with environment variables and commands
         #[arg(
             long = "with-extension",
@@ -416,6 +818,14 @@ enum Command {
         )]
         max_tool_repetitions: Option<u32>,
 
+        /// Don't load what earlier sessions in this project did
+        #[arg(
+            long = "no-project-history",
+            help = "Don't start with a digest of earlier sessions in this project",
+            long_help = "New sessions start with a short digest of recent sessions in the same repository: what they were asked, which files they changed and what they left open. This skips it for this session, set GOOSE_PROJECT_HISTORY to false to turn it off everywhere."
+        )]
+        no_project_history: bool,
+
         /// Identifier for this run session
         #[command(flatten)]
         identifier: Option<Identifier>,
@@ -438,6 +848,14 @@ enum Command {
         )]
         debug: bool,
 
+        /// Print markdown as it is written
+        #[arg(
+            long,
+            help = "Print replies as raw markdown instead of rendering them",
+            long_help = "Replies are rendered for the terminal, with headings, lists and tables laid out and code highlighted. This prints the markdown as the model wrote it instead, set GOOSE_CLI_PLAIN to true to make it the default."
+        )]
+        plain: bool,
+
         /// Add stdio extensions with environment variables and commands
         #[arg(
             long = "with-extension",
@@ -467,6 +885,121 @@ enum Command {
             value_delimiter = ','
         )]
         builtins: Vec<String>,
+
+        /// Work on an isolated copy of the current directory
+        #[arg(
+            long = "isolated",
+            help = "Run on an isolated copy of the current directory and write the changes to a patch",
+            long_help = "Run the agent on a scratch copy of the current directory (a git worktree inside repositories) instead of the real checkout. When the run finishes, its changes are written to a patch file for review. Enabled by default when GOOSE_WORKSPACE_ISOLATION is true."
+        )]
+        isolated: bool,
+
+        /// Intercept tool calls that aren't read-only
+        #[arg(
+            long = "dry-run",
+            value_name = "MODE",
+            num_args = 0..=1,
+            default_missing_value = "simulate",
+            help = "Intercept tool calls that aren't read-only: simulate (default) or prompt",
+            long_help = "Develop recipes without side effects. Read-only tools run normally, while every other tool call is intercepted: 'simulate' has the model make up a plausible result and 'prompt' asks you to type the result. Defaults to GOOSE_DRY_RUN."
+        )]
+        dry_run: Option<DryRunMode>,
+
+        /// Re-run whenever matching files change
+        #[arg(
+            long = "watch",
+            value_name = "GLOB",
+            help = "Run again whenever files matching GLOB change (can be specified multiple times)",
+            long_help = "Watch files matching GLOB, such as 'src/**/*.rs', and run the instructions headless again whenever they change. Changes are debounced, and changes made while a run is in progress, including the run's own edits, don't start another run. The session carries over between runs.",
+            action = clap::ArgAction::Append,
+            conflicts_with_all = ["interactive", "isolated"]
+        )]
+        watch: Vec<String>,
+
+        /// Stop the run after a time limit
+        #[arg(
+            long = "timeout",
+            value_name = "SECONDS",
+            help = "Stop the run after SECONDS and exit with code 124",
+            long_help = "Stop a headless run that hasn't finished after SECONDS, including any retries, and exit with code 124. Other failures have their own exit codes: 3 when the run ended on a failed tool call and 4 when the agent gave up on a provider error.",
+            conflicts_with_all = ["interactive", "watch"]
+        )]
+        timeout: Option<u64>,
+
+        /// Retry transient provider failures
+        #[arg(
+            long = "retries",
+            value_name = "NUMBER",
+            help = "Retry provider calls that fail with a transient error up to NUMBER times",
+            long_help = "Retry provider calls that fail with rate limits, server errors or dropped connections up to NUMBER times, waiting longer between each attempt. Only the failed call is sent again, so tool calls that already ran aren't repeated. Defaults to GOOSE_PROVIDER_RETRIES."
+        )]
+        retries: Option<u32>,
+
+        /// Review the changed files before keeping them
+        #[arg(
+            long = "review",
+            help = "Review the run's file changes as diffs and accept or reject each one",
+            long_help = "When the headless run finishes, show every file it changed as a diff and accept or reject each one. Rejected files are restored from a snapshot of the working tree taken before the run, or from the developer extension's edit journal outside a git repository, and inside a git repository the accepted files can then be committed.",
+            conflicts_with_all = ["interactive", "watch", "isolated", "no_session"]
+        )]
+        review: bool,
+
+        /// Run a batch of independent prompts in parallel
+        #[arg(
+            long = "tasks",
+            value_name = "FILE",
+            help = "Run every task in a YAML or JSON file as its own headless session, in parallel",
+            long_help = "Run a list of independent tasks, each with a 'prompt' and optionally a 'name' and a 'dir' to run in, or 'dirs' to run the prompt in each of several directories. Every task is a separate headless session with its own log. A table of the results, tokens and costs is printed once all of them finish, and the command fails when any task did. Extension, timeout, retry and isolation options apply to every task.",
+            conflicts_with_all = ["instructions", "input_text", "recipe", "interactive", "no_session", "resume", "name", "path", "watch", "review", "dry_run"]
+        )]
+        tasks: Option<PathBuf>,
+
+        /// How many tasks run at once
+        #[arg(
+            long = "concurrency",
+            value_name = "NUMBER",
+            requires = "tasks",
+            help = "Run at most NUMBER tasks at once (default 4)"
+        )]
+        concurrency: Option<usize>,
+    },
+
+    /// Apply a patch written by a goose run
+    #[command(
+        name = "diff-apply",
+        about = "Apply a patch written by a goose run to the current checkout"
+    )]
+    DiffApply {
+        /// Patch file to apply
+        #[arg(
+            value_name = "PATCH",
+            help = "Patch file to apply, such as the one written by 'goose run --isolated'"
+        )]
+        patch: PathBuf,
+
+        /// Directory to apply the patch in
+        #[arg(
+            short = 'C',
+            long = "dir",
+            value_name = "DIR",
+            help = "Apply the patch in DIR instead of the current repository's root"
+        )]
+        dir: Option<PathBuf>,
+
+        /// Only report whether the patch applies
+        #[arg(
+            long,
+            help = "Check that the patch applies cleanly without changing any files"
+        )]
+        check: bool,
+
+        /// Merge conflicting files instead of giving up
+        #[arg(
+            long = "3way",
+            help = "Merge files that conflict, leaving conflict markers where needed",
+            long_help = "When files changed since the patch was written, fall back to a three-way merge instead of refusing to apply. Files that still conflict get conflict markers and the command exits with an error. Needs a git repository where the patched files have no unstaged changes, and updates its index like 'git apply --3way'."
+        )]
+        three_way: bool,
     },
 
     /// Recipe utilities for validation and deeplinking
@@ -544,6 +1077,55 @@ struct InputConfig {
     additional_system_prompt: Option<String>,
 }
 
+/// Write the changes made in an isolated workspace to a patch next to the real checkout. The
+/// workspace is kept when they can't be written, so they aren't lost.
+fn finish_isolated_run(workspace: IsolatedWorkspace, dir: &Path, name: &str) -> Result<()> {
+    let written = workspace.diff().and_then(|patch| {
+        if patch.is_empty() {
+            eprintln!("No changes were made in the isolated workspace");
+        } else {
+            let patch_file = dir.join(format!("goose-{}.patch", name));
+            std::fs::write(&patch_file, patch)?;
+            eprintln!(
+                "Changes written to {}\nReview and apply them with: goose diff-apply --dir {} {}",
+                patch_file.display(),
+                workspace.source_root().display(),
+                patch_file.display()
+            );
+        }
+        Ok(())
+    });
+    if let Err(e) = written {
+        let path = workspace.keep();
+        return Err(e.context(format!(
+            "Failed to write the changes, kept the isolated workspace {}",
+            path.display()
+        )));
+    }
+    workspace.cleanup()
+}
+
+/// Checkpoint a headless run that was paused, so it can be continued interactively
+async fn pause_run(session: &crate::Session, reason: &str, no_session: bool) -> Result<()> {
+    if no_session {
+        eprintln!("Run stopped ({}), it has no session to continue", reason);
+        return Ok(());
+    }
+    session
+        .checkpoint(goose::session::HandoffMode::Headless, reason)
+        .await?;
+    let session_file = session.session_file();
+    let run_id = session_file
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    eprintln!(
+        "Run paused ({}), continue it with: goose session --resume --from-run {}",
+        reason, run_id
+    );
+    Ok(())
+}
+
 pub async fn cli() -> Result<()> {
     let cli = Cli::parse();
 
@@ -553,14 +1135,90 @@ pub async fn cli() -> Result<()> {
     }
 
     match cli.command {
-        Some(Command::Configure {}) => {
-            let _ = handle_configure().await;
+        Some(Command::Configure { command }) => {
+            match command {
+                Some(ConfigureCommand::Export {
+                    path,
+                    include_secrets,
+                }) => handle_configure_export(&path, include_secrets)?,
+                Some(ConfigureCommand::Import { path }) => handle_configure_import(&path)?,
+                None => {
+                    let _ = handle_configure().await;
+                }
+            }
             return Ok(());
         }
         Some(Command::Info { verbose }) => {
             handle_info(verbose)?;
             return Ok(());
         }
+        Some(Command::Doctor { url }) => {
+            handle_doctor(url).await?;
+            return Ok(());
+        }
+        Some(Command::Init { force }) => {
+            handle_init(&std::env::current_dir()?, force)?;
+            return Ok(());
+        }
+        Some(Command::Usage { month, daily }) => {
+            handle_usage(month, daily)?;
+            return Ok(());
+        }
+        Some(Command::Keys { command }) => {
+            match command {
+                KeysCommand::List {} => handle_keys_list()?,
+                KeysCommand::Rotate {
+                    name,
+                    expires_in_days,
+                } => handle_keys_rotate(name, expires_in_days)?,
+                KeysCommand::Remove { name } => handle_keys_remove(name)?,
+            }
+            return Ok(());
+        }
+        Some(Command::Memory { command }) => {
+            match command {
+                MemoryCommand::Export { path } => handle_memory_export(&path)?,
+                MemoryCommand::Import { path, strategy } => handle_memory_import(&path, strategy)?,
+                MemoryCommand::Sync { repo, remote } => handle_memory_sync(repo, remote).await?,
+            }
+            return Ok(());
+        }
+        Some(Command::Prompt { command }) => {
+            match command {
+                PromptCommand::Save { name, text } => handle_prompt_save(&name, text)?,
+                PromptCommand::List { verbose } => handle_prompt_list(verbose)?,
+                PromptCommand::Remove { name } => handle_prompt_remove(&name)?,
+                PromptCommand::Run {
+                    name,
+                    params,
+                    interactive,
+                    timeout,
+                } => {
+                    let outcome = handle_prompt_run(&name, params, interactive, timeout).await?;
+                    if let Some(description) = outcome.describe() {
+                        eprintln!("{}: {}", console::style("Error").red().bold(), description);
+                    }
+                    if outcome != RunOutcome::Completed {
+                        std::process::exit(outcome.exit_code());
+                    }
+                }
+            }
+            return Ok(());
+        }
+        Some(Command::Extensions { command }) => {
+            match command {
+                ExtensionsCommand::Search { query } => handle_extensions_search(query).await?,
+                ExtensionsCommand::Show { id } => handle_extensions_show(&id).await?,
+                ExtensionsCommand::Install {
+                    id,
+                    name,
+                    envs,
+                    timeout,
+                } => handle_extensions_install(&id, name, envs, timeout).await?,
+                ExtensionsCommand::Enable { name } => handle_extensions_enable(&name)?,
+            }
+            return Ok(());
+        }
         Some(Command::Mcp { name }) => {
             let _ = run_server(&name).await;
         }
@@ -569,8 +1227,11 @@ pub async fn cli() -> Result<()> {
             identifier,
             resume,
             history,
+            from_run,
             debug,
+            plain,
             max_tool_repetitions,
+            no_project_history,
             extensions,
             remote_extensions,
             builtins,
@@ -580,8 +1241,10 @@ pub async fn cli() -> Result<()> {
                     verbose,
                     format,
                     ascending,
+                    tags,
+                    search,
                 }) => {
-                    handle_session_list(verbose, format, ascending)?;
+                    handle_session_list(verbose, format, ascending, tags, search)?;
                     Ok(())
                 }
                 Some(SessionCommand::Remove { id, regex }) => {
@@ -605,10 +1268,43 @@ pub async fn cli() -> Result<()> {
                     crate::commands::session::handle_session_export(session_identifier, output)?;
                     Ok(())
                 }
+                Some(SessionCommand::Migrate { dry_run }) => {
+                    handle_session_migrate(dry_run)?;
+                    Ok(())
+                }
+                Some(SessionCommand::Rollback { run_id, force }) => {
+                    handle_session_rollback(run_id, force)?;
+                    Ok(())
+                }
+                Some(SessionCommand::Context { identifier }) => {
+                    handle_session_context(identifier.map(extract_identifier)).await?;
+                    Ok(())
+                }
+                Some(SessionCommand::Pause { run_id }) => {
+                    handle_session_pause(&run_id)?;
+                    Ok(())
+                }
+                Some(SessionCommand::Logs {
+                    identifier,
+                    follow,
+                    level,
+                }) => {
+                    handle_session_logs(identifier.map(extract_identifier), follow, &level).await?;
+                    Ok(())
+                }
                 None => {
+                    if plain {
+                        session::set_plain(true);
+                    }
+                    // Show where a paused run got to, so it can be steered from there
+                    let history = history || from_run.is_some();
+                    let identifier = match from_run {
+                        Some(run_id) => Some(paused_run(&run_id)?),
+                        None => identifier.map(extract_identifier),
+                    };
                     // Run session command by default
                     let mut session: crate::Session = build_session(SessionBuilderConfig {
-                        identifier: identifier.map(extract_identifier),
+                        identifier,
                         resume,
                         no_session: false,
                         extensions,
@@ -618,6 +1314,9 @@ pub async fn cli() -> Result<()> {
                         additional_system_prompt: None,
                         debug,
                         max_tool_repetitions,
+                        dry_run: None,
+                        project_history: !no_project_history,
+                        provider_retries: None,
                     })
                     .await;
                     setup_logging(
@@ -654,13 +1353,40 @@ pub async fn cli() -> Result<()> {
             resume,
             no_session,
             debug,
+            plain,
             max_tool_repetitions,
+            no_project_history,
             extensions,
             remote_extensions,
             builtins,
             params,
             explain,
+            isolated,
+            dry_run,
+            watch,
+            timeout,
+            retries,
+            review,
+            tasks,
+            concurrency,
         }) => {
+            if let Some(tasks) = tasks {
+                let options = TaskOptions {
+                    extensions,
+                    remote_extensions,
+                    builtins,
+                    max_tool_repetitions,
+                    no_project_history,
+                    isolated,
+                    timeout,
+                    retries,
+                };
+                handle_run_tasks(&tasks, concurrency, options).await?;
+                return Ok(());
+            }
+            if plain {
+                session::set_plain(true);
+            }
             let input_config = match (instructions, input_text, recipe, explain) {
                 (Some(file), _, _, _) if file == "-" => {
                     let mut input = String::new();
@@ -715,6 +1441,36 @@ pub async fn cli() -> Result<()> {
                 }
             };
 
+            // Checked before an isolated workspace is made, so exiting leaves nothing behind
+            if input_config.contents.is_none() && (!watch.is_empty() || !interactive) {
+                let mode = if watch.is_empty() {
+                    "headless"
+                } else {
+                    "watch"
+                };
+                eprintln!("Error: no text provided for prompt in {} mode", mode);
+                std::process::exit(1);
+            }
+
+            let isolated = isolated
+                || Config::global()
+                    .get_param::<bool>(WORKSPACE_ISOLATION_KEY)
+                    .unwrap_or(false);
+            let original_dir = std::env::current_dir()?;
+            // Extensions inherit the working directory, so switch before building the session.
+            // The workspace is removed when dropped, also when the run fails early.
+            let workspace = if isolated {
+                let workspace = IsolatedWorkspace::create(&original_dir)?;
+                std::env::set_current_dir(workspace.path())?;
+                eprintln!(
+                    "Running in isolated workspace {}",
+                    workspace.path().display()
+                );
+                Some(workspace)
+            } else {
+                None
+            };
+
             let mut session = build_session(SessionBuilderConfig {
                 identifier: identifier.map(extract_identifier),
                 resume,
@@ -726,6 +1482,9 @@ pub async fn cli() -> Result<()> {
                 additional_system_prompt: input_config.additional_system_prompt,
                 debug,
                 max_tool_repetitions,
+                dry_run,
+                project_history: !no_project_history,
+                provider_retries: retries,
             })
             .await;
 
@@ -734,15 +1493,65 @@ pub async fn cli() -> Result<()> {
                 None,
             )?;
 
-            if interactive {
-                let _ = session.interactive(input_config.contents).await;
-            } else if let Some(contents) = input_config.contents {
-                let _ = session.headless(contents).await;
+            let snapshot = if review {
+                WorktreeSnapshot::capture(&std::env::current_dir()?)?
             } else {
-                eprintln!("Error: no text provided for prompt in headless mode");
-                std::process::exit(1);
+                None
+            };
+            let started_at = chrono::Utc::now();
+            let mut outcome = RunOutcome::Completed;
+            match input_config.contents {
+                Some(contents) if !watch.is_empty() => {
+                    watch_and_run(&mut session, &watch, contents).await?;
+                }
+                contents if interactive => {
+                    let _ = session.interactive(contents).await;
+                }
+                Some(contents) => {
+                    outcome =
+                        run_headless(&mut session, contents, timeout.map(Duration::from_secs))
+                            .await;
+                }
+                None => unreachable!("runs without a prompt exit before the session is built"),
+            }
+
+            if let RunOutcome::Paused(reason) = &outcome {
+                pause_run(&session, reason, no_session).await?;
+            } else if review {
+                let session_file = session.session_file();
+                let name = session_file
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default();
+                review_run(name, started_at, snapshot.as_ref())?;
             }
 
+            if let Some(workspace) = workspace {
+                std::env::set_current_dir(&original_dir)?;
+                // A paused run continues in the workspace, so keep it until then
+                if matches!(outcome, RunOutcome::Paused(_)) && !no_session {
+                    let path = workspace.keep();
+                    eprintln!(
+                        "Kept the isolated workspace {} for the paused run",
+                        path.display()
+                    );
+                } else {
+                    let name = session
+                        .session_file()
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("run")
+                        .to_string();
+                    finish_isolated_run(workspace, &original_dir, &name)?;
+                }
+            }
+
+            if let Some(description) = outcome.describe() {
+                eprintln!("{}: {}", console::style("Error").red().bold(), description);
+            }
+            if outcome != RunOutcome::Completed {
+                std::process::exit(outcome.exit_code());
+            }
             return Ok(());
         }
         Some(Command::Schedule { command }) => {
@@ -771,6 +1580,15 @@ pub async fn cli() -> Result<()> {
             }
             return Ok(());
         }
+        Some(Command::DiffApply {
+            patch,
+            dir,
+            check,
+            three_way,
+        }) => {
+            handle_diff_apply(&patch, dir, check, three_way)?;
+            return Ok(());
+        }
         Some(Command::Update {
             canary,
             reconfigure,
@@ -788,7 +1606,14 @@ pub async fn cli() -> Result<()> {
                     config.output_dir = Some(cwd);
                     config.save(name);
                 }
-                BenchCommand::Run { config } => BenchRunner::new(config)?.run()?,
+                BenchCommand::Run {
+                    config,
+                    suites,
+                    provider,
+                    model,
+                    repeat,
+                    retries,
+                } => handle_bench_run(config, suites, provider, model, repeat, retries)?,
                 BenchCommand::EvalModel { config } => ModelRunner::from(config)?.run()?,
                 BenchCommand::ExecEval { config } => {
                     EvalRunner::from(config)?.run(agent_generator).await?
@@ -796,6 +1621,9 @@ pub async fn cli() -> Result<()> {
                 BenchCommand::GenerateLeaderboard { benchmark_dir } => {
                     MetricAggregator::generate_csv_from_benchmark_dir(&benchmark_dir)?
                 }
+                BenchCommand::Verify { manifest, config } => {
+                    BenchManifest::verify(&manifest, config)?
+                }
             }
             return Ok(());
         }
@@ -831,6 +1659,9 @@ pub async fn cli() -> Result<()> {
                     additional_system_prompt: None,
                     debug: false,
                     max_tool_repetitions: None,
+                    dry_run: None,
+                    project_history: true,
+                    provider_retries: None,
                 })
                 .await;
                 setup_logging(
@@ -1,10 +1,17 @@
 use crate::session::build_session;
 use crate::session::SessionBuilderConfig;
 use crate::{logging, session, Session};
+use anyhow::Context;
 use async_trait::async_trait;
+use console::style;
+use goose::config::Config;
 use goose::message::Message;
+use goose::providers::base::ResponseTiming;
+use goose_bench::bench_config::BenchRunConfig;
 use goose_bench::bench_session::{BenchAgent, BenchBaseSession};
 use goose_bench::eval_suites::ExtensionRequirements;
+use goose_bench::runners::bench_runner::BenchRunner;
+use goose_bench::runners::matrix_runner::MatrixRunner;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -24,6 +31,9 @@ impl BenchBaseSession for Session {
     fn get_total_token_usage(&self) -> anyhow::Result<Option<i32>> {
         self.get_total_token_usage()
     }
+    fn get_last_response_timing(&self) -> anyhow::Result<Option<ResponseTiming>> {
+        self.get_last_response_timing()
+    }
 }
 pub async fn agent_generator(
     requirements: ExtensionRequirements,
@@ -42,6 +52,9 @@ pub async fn agent_generator(
         additional_system_prompt: None,
         debug: false,
         max_tool_repetitions: None,
+        dry_run: None,
+        project_history: false,
+        provider_retries: None,
     })
     .await;
 
@@ -54,3 +67,105 @@ pub async fn agent_generator(
 
     bench_agent
 }
+
+/// Run benchmarks from a config file, or with the default suites against the configured
+/// provider and model when no config is given, then print a summary of each run
+pub fn handle_bench_run(
+    config_path: Option<PathBuf>,
+    suites: Vec<String>,
+    provider: Option<String>,
+    model: Option<String>,
+    repeat: Option<usize>,
+    retries: Option<usize>,
+) -> anyhow::Result<()> {
+    let (mut config, source) = match &config_path {
+        Some(path) => (
+            BenchRunConfig::from(path.clone())?,
+            format!("'{}'", path.display()),
+        ),
+        None => (BenchRunConfig::default(), "the default config".to_string()),
+    };
+
+    if let Some(retries) = retries {
+        config.set_retries(retries);
+    }
+
+    if let Some(mut matrix) = config.matrix.take() {
+        if let Some(provider) = provider {
+            matrix.providers = vec![provider];
+        }
+        if let Some(model) = model {
+            matrix.models = vec![model];
+        }
+        if !suites.is_empty() {
+            matrix.suites = suites;
+        }
+        if repeat.is_some() {
+            config.repeat = repeat;
+        }
+        config.matrix = Some(matrix);
+        return handle_matrix_run(config, &source);
+    }
+
+    if config_path.is_none() || provider.is_some() || model.is_some() {
+        let goose_config = Config::global();
+        let provider = provider
+            .or_else(|| goose_config.get_param("GOOSE_PROVIDER").ok())
+            .context("No provider configured, pass --provider or run 'goose configure'")?;
+        let model = model
+            .or_else(|| goose_config.get_param("GOOSE_MODEL").ok())
+            .context("No model configured, pass --model or run 'goose configure'")?;
+        config.set_model(provider, model);
+    }
+    if !suites.is_empty() {
+        config.set_selectors(&suites);
+    }
+    if repeat.is_some() {
+        config.repeat = repeat;
+    }
+
+    let mut runner = BenchRunner::with_config(config, &source)?;
+    runner.run()?;
+
+    let results = runner.collect_results()?;
+    if results.is_empty() {
+        println!("{}", style("No benchmark results were recorded").yellow());
+    }
+    for (path, run) in results {
+        println!("{}", run.summary());
+        println!("  Results: {}\n", style(path.display()).dim());
+    }
+    Ok(())
+}
+
+/// Run every combination of a matrix config, then print how they compare
+fn handle_matrix_run(config: BenchRunConfig, source: &str) -> anyhow::Result<()> {
+    let runner = MatrixRunner::with_config(config, source)?;
+    println!(
+        "Running {} configurations of the matrix",
+        runner.groups().len()
+    );
+    runner.run()?;
+
+    let results = runner.collect_results()?;
+    let missing: Vec<&str> = results
+        .iter()
+        .filter(|(_, runs)| runs.is_empty())
+        .map(|(label, _)| label.as_str())
+        .collect();
+    if !missing.is_empty() {
+        println!(
+            "{}",
+            style(format!(
+                "No results were recorded for {}",
+                missing.join(", ")
+            ))
+            .yellow()
+        );
+    }
+
+    let (pivot, path) = runner.write_pivot(&results)?;
+    println!("{}", pivot);
+    println!("  Comparison: {}\n", style(path.display()).dim());
+    Ok(())
+}
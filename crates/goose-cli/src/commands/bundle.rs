@@ -0,0 +1,676 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use console::style;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use goose::config::Config;
+use pbkdf2::pbkdf2_hmac;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Passphrase for the secrets in a bundle, prompted for when unset
+pub const BUNDLE_PASSPHRASE_ENV: &str = "GOOSE_BUNDLE_PASSPHRASE";
+
+/// Version 1 bundles encrypted their secrets with the openssl CLI
+const BUNDLE_VERSION: u32 = 2;
+const MANIFEST_NAME: &str = "manifest.json";
+const SECRETS_NAME: &str = "secrets.enc";
+const CONFIG_PREFIX: &str = "config/";
+const CONFIG_FILE: &str = "config.yaml";
+/// Where the keyring fallback stores secrets, only ever restored through the encrypted payload
+const SECRETS_FILE: &str = "secrets.yaml";
+const REDACTED: &str = "<redacted>";
+const KDF_ROUNDS: u32 = 200_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// What a bundle takes from the config dir. secrets.yaml is never bundled in plain text.
+const BUNDLED_PATHS: &[&str] = &[
+    CONFIG_FILE,
+    "permission.yaml",
+    ".goosehints",
+    ".gooseignore",
+    "recipes",
+    "prompts",
+    "profiles",
+];
+
+/// Whether `relative` is one of the `BUNDLED_PATHS`, or a file under one of its directories
+fn is_bundled(relative: &str) -> bool {
+    relative != SECRETS_FILE
+        && BUNDLED_PATHS.iter().any(|bundled| {
+            relative == *bundled
+                || relative
+                    .strip_prefix(bundled)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+}
+
+/// Whether writing `relative` in `config_dir` would follow a symlink, and so could land
+/// outside of it
+fn through_symlink(config_dir: &Path, relative: &str) -> bool {
+    let mut path = config_dir.to_path_buf();
+    relative.split('/').any(|component| {
+        path.push(component);
+        std::fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+    })
+}
+
+/// Describes a bundle, so import can report what it holds before writing anything
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    goose_version: String,
+    created: DateTime<Utc>,
+    /// Paths relative to the config dir
+    files: Vec<String>,
+    /// Values of config.yaml replaced with a placeholder because they look like credentials
+    redacted: Vec<String>,
+    /// Names of the secrets in the encrypted payload, empty when secrets weren't included
+    secrets: Vec<String>,
+}
+
+/// The encrypted part of a bundle
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SecretPayload {
+    secrets: HashMap<String, serde_json::Value>,
+    /// Redacted config values by their key path
+    config: Vec<(Vec<String>, Value)>,
+}
+
+fn config_dir() -> Result<PathBuf> {
+    PathBuf::from(Config::global().path())
+        .parent()
+        .map(Path::to_path_buf)
+        .context("The config file has no parent directory")
+}
+
+fn looks_secret(key: &str) -> bool {
+    let key = key.to_uppercase();
+    key.ends_with("_KEY")
+        || [
+            "TOKEN",
+            "SECRET",
+            "PASSWORD",
+            "API_KEY",
+            "ACCESS_KEY",
+            "CREDENTIAL",
+        ]
+        .iter()
+        .any(|marker| key.contains(marker))
+}
+
+/// Replace the values of credential-looking keys in nested mappings with a placeholder,
+/// collecting the key paths and original values
+fn redact(value: &mut Value, path: &mut Vec<String>, redacted: &mut Vec<(Vec<String>, Value)>) {
+    let Value::Mapping(mapping) = value else {
+        return;
+    };
+    for (key, child) in mapping.iter_mut() {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        path.push(key.to_string());
+        match child {
+            Value::Mapping(_) => redact(child, path, redacted),
+            Value::String(_) | Value::Number(_) if looks_secret(key) => {
+                let original = std::mem::replace(child, Value::String(REDACTED.to_string()));
+                redacted.push((path.clone(), original));
+            }
+            _ => {}
+        }
+        path.pop();
+    }
+}
+
+fn set_path(value: &mut Value, path: &[String], new_value: Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut current = value;
+    for key in parents {
+        let Value::Mapping(mapping) = current else {
+            return;
+        };
+        current = mapping
+            .entry(Value::String(key.clone()))
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+    }
+    if let Value::Mapping(mapping) = current {
+        mapping.insert(Value::String(last.clone()), new_value);
+    }
+}
+
+/// Remove the placeholders left by `redact`, so importing keeps the values already set
+fn strip_redacted(value: &mut Value) {
+    if let Value::Mapping(mapping) = value {
+        mapping.retain(|_, child| child.as_str() != Some(REDACTED));
+        for (_, child) in mapping.iter_mut() {
+            strip_redacted(child);
+        }
+    }
+}
+
+/// Merge `incoming` into `current`: nested mappings are merged, other values replaced
+fn merge(current: &mut Value, incoming: Value) {
+    match (current, incoming) {
+        (Value::Mapping(current), Value::Mapping(incoming)) => {
+            for (key, value) in incoming {
+                match current.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        current.insert(key, value);
+                    }
+                }
+            }
+        }
+        (current, incoming) => *current = incoming,
+    }
+}
+
+/// Every file under `path`, relative to `base`, skipping symlinks
+fn collect_files(base: &Path, path: &Path, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        let mut entries: Vec<_> = std::fs::read_dir(path)?.collect::<Result<_, _>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            collect_files(base, &entry.path(), files)?;
+        }
+    } else if metadata.is_file() {
+        let relative = path.strip_prefix(base)?;
+        let name = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push((name, path.to_path_buf()));
+    }
+    Ok(())
+}
+
+fn write_archive(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, data) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o600);
+        header.set_mtime(Utc::now().timestamp().max(0) as u64);
+        header.set_cksum();
+        builder.append_data(&mut header, name, data.as_slice())?;
+    }
+    Ok(builder.into_inner()?)
+}
+
+/// The files of an archive by name, refusing names that would escape the config dir
+fn read_archive(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut files = BTreeMap::new();
+    let mut archive = tar::Archive::new(bytes);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        if !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            bail!("The bundle contains an unsafe path: {}", path.display());
+        }
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        files.insert(path.to_string_lossy().replace('\\', "/"), data);
+    }
+    Ok(files)
+}
+
+fn is_gzip_path(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    name.ends_with(".tgz") || name.ends_with(".gz")
+}
+
+fn gzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+fn gunzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    GzDecoder::new(data)
+        .read_to_end(&mut decoded)
+        .context("The bundle is not a valid gzip file")?;
+    Ok(decoded)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Key<Aes256Gcm> {
+    let mut key = Key::<Aes256Gcm>::default();
+    pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    key
+}
+
+/// AES-256-GCM with a key derived from the passphrase, laid out as salt, nonce, ciphertext
+fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ciphertext = Aes256Gcm::new(&derive_key(passphrase, &salt))
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow!("Failed to encrypt the secrets"))?;
+    Ok([salt.as_slice(), nonce.as_slice(), ciphertext.as_slice()].concat())
+}
+
+fn decrypt(passphrase: &str, encrypted: &[u8]) -> Result<Vec<u8>> {
+    if encrypted.len() < SALT_LEN + NONCE_LEN {
+        bail!("The encrypted secrets are truncated");
+    }
+    let (salt, rest) = encrypted.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    Aes256Gcm::new(&derive_key(passphrase, salt))
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt the secrets, is the passphrase right?"))
+}
+
+fn passphrase(prompt: &str) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(BUNDLE_PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    let passphrase: String = cliclack::password(prompt).mask('▪').interact()?;
+    if passphrase.is_empty() {
+        bail!("The passphrase can't be empty");
+    }
+    Ok(passphrase)
+}
+
+/// Package the config dir into a bundle at `path`, with the secrets encrypted when asked
+pub fn handle_configure_export(path: &Path, include_secrets: bool) -> Result<()> {
+    let config_dir = config_dir()?;
+    let mut files = Vec::new();
+    for bundled in BUNDLED_PATHS {
+        let path = config_dir.join(bundled);
+        if path.exists() {
+            collect_files(&config_dir, &path, &mut files)?;
+        }
+    }
+    if files.is_empty() {
+        bail!(
+            "Nothing to export from {}, run 'goose configure' first",
+            config_dir.display()
+        );
+    }
+
+    let mut entries = Vec::new();
+    let mut redacted = Vec::new();
+    for (name, file) in &files {
+        let mut data = std::fs::read(file)?;
+        if name == CONFIG_FILE {
+            let mut values: Value = serde_yaml::from_slice(&data)?;
+            redact(&mut values, &mut Vec::new(), &mut redacted);
+            data = serde_yaml::to_string(&values)?.into_bytes();
+        }
+        entries.push((format!("{}{}", CONFIG_PREFIX, name), data));
+    }
+
+    let mut secret_names = Vec::new();
+    if include_secrets {
+        let payload = SecretPayload {
+            secrets: Config::global().load_secrets()?,
+            config: redacted.clone(),
+        };
+        secret_names = payload.secrets.keys().cloned().collect();
+        secret_names.sort();
+        let passphrase = passphrase("Passphrase to encrypt the secrets with")?;
+        let encrypted = encrypt(&passphrase, &serde_json::to_vec(&payload)?)?;
+        entries.push((SECRETS_NAME.to_string(), encrypted));
+    }
+
+    let manifest = Manifest {
+        version: BUNDLE_VERSION,
+        goose_version: env!("CARGO_PKG_VERSION").to_string(),
+        created: Utc::now(),
+        files: files.iter().map(|(name, _)| name.clone()).collect(),
+        redacted: redacted.iter().map(|(path, _)| path.join(".")).collect(),
+        secrets: secret_names,
+    };
+    entries.insert(
+        0,
+        (
+            MANIFEST_NAME.to_string(),
+            serde_json::to_vec_pretty(&manifest)?,
+        ),
+    );
+
+    let mut archive = write_archive(&entries)?;
+    if is_gzip_path(path) {
+        archive = gzip(&archive)?;
+    }
+    std::fs::write(path, archive).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    println!(
+        "Exported goose configuration to {}",
+        style(path.display()).cyan()
+    );
+    print_report(&manifest, include_secrets);
+    Ok(())
+}
+
+fn print_report(manifest: &Manifest, include_secrets: bool) {
+    println!("  {}", style("Included:").bold());
+    for file in &manifest.files {
+        println!("    {}", file);
+    }
+    if !manifest.redacted.is_empty() {
+        let note = if include_secrets {
+            "Moved from config.yaml into the encrypted secrets:"
+        } else {
+            "Redacted from config.yaml:"
+        };
+        println!("  {}", style(note).bold());
+        for path in &manifest.redacted {
+            println!("    {}", path);
+        }
+    }
+    if include_secrets {
+        println!(
+            "  {} {}",
+            style("Encrypted secrets:").bold(),
+            if manifest.secrets.is_empty() {
+                "none stored".to_string()
+            } else {
+                manifest.secrets.join(", ")
+            }
+        );
+    } else {
+        println!(
+            "  {} not included, pass --include-secrets to encrypt them into the bundle",
+            style("Secrets:").bold()
+        );
+    }
+}
+
+/// Write the files of a bundle into the config dir, merging config.yaml and backing up
+/// other files it replaces. Returns the written and backed up paths. Nothing is written when
+/// the bundle holds a file export wouldn't have put there.
+fn apply_bundle(
+    config_dir: &Path,
+    mut files: BTreeMap<String, Vec<u8>>,
+    payload: Option<SecretPayload>,
+) -> Result<(Vec<String>, Vec<String>)> {
+    for name in files.keys() {
+        let relative = name.strip_prefix(CONFIG_PREFIX).unwrap_or_default();
+        if !is_bundled(relative) {
+            bail!(
+                "The bundle contains {}, which isn't part of a goose configuration",
+                name
+            );
+        }
+        if through_symlink(config_dir, relative) {
+            bail!(
+                "Refusing to write {} through a symlink in {}",
+                relative,
+                config_dir.display()
+            );
+        }
+    }
+
+    let mut written = Vec::new();
+    let mut backups = Vec::new();
+    std::fs::create_dir_all(config_dir)?;
+
+    let config_key = format!("{}{}", CONFIG_PREFIX, CONFIG_FILE);
+    if let Some(data) = files.remove(&config_key) {
+        let mut incoming: Value = serde_yaml::from_slice(&data)?;
+        if let Some(payload) = &payload {
+            for (path, value) in &payload.config {
+                set_path(&mut incoming, path, value.clone());
+            }
+        }
+        strip_redacted(&mut incoming);
+        let path = config_dir.join(CONFIG_FILE);
+        let mut current = if path.exists() {
+            let current = std::fs::read(&path)?;
+            std::fs::write(config_dir.join(format!("{}.bak", CONFIG_FILE)), &current)?;
+            backups.push(format!("{}.bak", CONFIG_FILE));
+            serde_yaml::from_slice(&current)?
+        } else {
+            Value::Mapping(Mapping::new())
+        };
+        if current.is_null() {
+            current = Value::Mapping(Mapping::new());
+        }
+        merge(&mut current, incoming);
+        std::fs::write(&path, serde_yaml::to_string(&current)?)?;
+        written.push(CONFIG_FILE.to_string());
+    }
+
+    for (name, data) in files {
+        let relative = name.strip_prefix(CONFIG_PREFIX).unwrap_or_default();
+        let path = config_dir.join(relative);
+        if let Ok(existing) = std::fs::read(&path) {
+            if existing == data {
+                continue;
+            }
+            let backup = format!("{}.bak", relative);
+            std::fs::write(config_dir.join(&backup), existing)?;
+            backups.push(backup);
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, data)?;
+        written.push(relative.to_string());
+    }
+    Ok((written, backups))
+}
+
+/// Unpack a bundle written by export into the config dir and the secret storage
+pub fn handle_configure_import(path: &Path) -> Result<()> {
+    let mut bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        bytes = gunzip(&bytes)?;
+    }
+    let mut files = read_archive(&bytes)?;
+    let manifest: Manifest = serde_json::from_slice(
+        &files
+            .remove(MANIFEST_NAME)
+            .context("Not a goose configuration bundle, it has no manifest")?,
+    )?;
+    if manifest.version > BUNDLE_VERSION {
+        bail!(
+            "The bundle was written by goose {}, upgrade goose to import it",
+            manifest.goose_version
+        );
+    }
+
+    let payload = match files.remove(SECRETS_NAME) {
+        Some(_) if manifest.version < 2 => bail!(
+            "The secrets in this bundle were encrypted by goose {}, export it again with this version",
+            manifest.goose_version
+        ),
+        Some(encrypted) => {
+            let passphrase = passphrase("Passphrase the secrets were encrypted with")?;
+            let decrypted = decrypt(&passphrase, &encrypted)?;
+            Some(serde_json::from_slice::<SecretPayload>(&decrypted)?)
+        }
+        None => None,
+    };
+
+    let config_dir = config_dir()?;
+    let secrets: Vec<(String, serde_json::Value)> = payload
+        .as_ref()
+        .map(|payload| payload.secrets.clone().into_iter().collect())
+        .unwrap_or_default();
+    let (written, backups) = apply_bundle(&config_dir, files, payload)?;
+    for (key, value) in &secrets {
+        Config::global().set_secret(key, value.clone())?;
+    }
+
+    println!(
+        "Imported goose configuration from {} into {}",
+        style(path.display()).cyan(),
+        style(config_dir.display()).cyan()
+    );
+    for file in &written {
+        println!("  wrote {}", file);
+    }
+    for backup in &backups {
+        println!(
+            "  {}",
+            style(format!("backed up the previous file to {}", backup)).dim()
+        );
+    }
+    if !secrets.is_empty() {
+        println!("  stored {} secrets", secrets.len());
+    } else if !manifest.redacted.is_empty() {
+        println!(
+            "  {}",
+            style(format!(
+                "The bundle has no secrets, set these yourself: {}",
+                manifest.redacted.join(", ")
+            ))
+            .yellow()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let mut config: Value = serde_yaml::from_str(
+            r#"
+GOOSE_PROVIDER: openai
+OPENAI_API_KEY: sk-123
+extensions:
+  github:
+    enabled: true
+    envs:
+      GITHUB_TOKEN: ghp_abc
+    env_keys: [GITHUB_TOKEN]
+"#,
+        )
+        .unwrap();
+        let mut redacted = Vec::new();
+        redact(&mut config, &mut Vec::new(), &mut redacted);
+        let paths: Vec<String> = redacted.iter().map(|(path, _)| path.join(".")).collect();
+        assert_eq!(
+            paths,
+            vec!["OPENAI_API_KEY", "extensions.github.envs.GITHUB_TOKEN"]
+        );
+        assert_eq!(config["OPENAI_API_KEY"].as_str(), Some(REDACTED));
+        assert_eq!(config["GOOSE_PROVIDER"].as_str(), Some("openai"));
+
+        let archive = write_archive(&[
+            (
+                "config/config.yaml".to_string(),
+                serde_yaml::to_string(&config).unwrap().into_bytes(),
+            ),
+            (
+                "config/recipes/daily.yaml".to_string(),
+                b"title: d".to_vec(),
+            ),
+        ])
+        .unwrap();
+        let files = read_archive(&archive).unwrap();
+        assert_eq!(files.len(), 2);
+
+        // Without secrets, redacted values keep what the machine already has
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(CONFIG_FILE),
+            "OPENAI_API_KEY: sk-local\nGOOSE_MODEL: gpt-4o\n",
+        )
+        .unwrap();
+        let (written, backups) = apply_bundle(dir.path(), files.clone(), None).unwrap();
+        assert_eq!(written, vec!["config.yaml", "recipes/daily.yaml"]);
+        assert_eq!(backups, vec!["config.yaml.bak"]);
+        let merged: Value =
+            serde_yaml::from_str(&std::fs::read_to_string(dir.path().join(CONFIG_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(merged["OPENAI_API_KEY"].as_str(), Some("sk-local"));
+        assert_eq!(merged["GOOSE_MODEL"].as_str(), Some("gpt-4o"));
+        assert_eq!(merged["GOOSE_PROVIDER"].as_str(), Some("openai"));
+        assert!(merged["extensions"]["github"]["envs"]
+            .get("GITHUB_TOKEN")
+            .is_none());
+
+        // With secrets, they are restored from the payload
+        let payload = SecretPayload {
+            secrets: HashMap::new(),
+            config: redacted,
+        };
+        apply_bundle(dir.path(), files, Some(payload)).unwrap();
+        let merged: Value =
+            serde_yaml::from_str(&std::fs::read_to_string(dir.path().join(CONFIG_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(merged["OPENAI_API_KEY"].as_str(), Some("sk-123"));
+        assert_eq!(
+            merged["extensions"]["github"]["envs"]["GITHUB_TOKEN"].as_str(),
+            Some("ghp_abc")
+        );
+    }
+
+    #[test]
+    fn test_hostile_bundle_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let hostile = |name: &str| {
+            BTreeMap::from([
+                (
+                    "config/recipes/daily.yaml".to_string(),
+                    b"title: d".to_vec(),
+                ),
+                (name.to_string(), b"pwned".to_vec()),
+            ])
+        };
+
+        for name in [
+            "config/secrets.yaml",
+            "config/.bashrc",
+            "config/bin/goose",
+            "config/recipes.yaml",
+            "config/recipesx/daily.yaml",
+            "README.md",
+        ] {
+            assert!(apply_bundle(dir.path(), hostile(name), None).is_err());
+        }
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        #[cfg(unix)]
+        {
+            let outside = tempfile::tempdir().unwrap();
+            std::os::unix::fs::symlink(outside.path(), dir.path().join("prompts")).unwrap();
+            assert!(apply_bundle(dir.path(), hostile("config/prompts/p.md"), None).is_err());
+            assert_eq!(std::fs::read_dir(outside.path()).unwrap().count(), 0);
+            assert!(!dir.path().join("recipes").exists());
+        }
+    }
+
+    #[test]
+    fn test_secrets_encryption() {
+        let encrypted = encrypt("hunter2", b"{\"secrets\":{}}").unwrap();
+        assert_eq!(decrypt("hunter2", &encrypted).unwrap(), b"{\"secrets\":{}}");
+        assert!(decrypt("hunter3", &encrypted).is_err());
+        assert!(decrypt("hunter2", &encrypted[..SALT_LEN]).is_err());
+        // A fresh salt and nonce every time
+        assert_ne!(encrypted, encrypt("hunter2", b"{\"secrets\":{}}").unwrap());
+
+        let archive = write_archive(&[("manifest.json".to_string(), b"{}".to_vec())]).unwrap();
+        let compressed = gzip(&archive).unwrap();
+        assert!(compressed.starts_with(&[0x1f, 0x8b]));
+        assert_eq!(gunzip(&compressed).unwrap(), archive);
+    }
+}
@@ -10,7 +10,7 @@ use goose::agents::{extension::Envs, ExtensionConfig};
 use goose::config::extensions::name_to_key;
 use goose::config::permission::PermissionLevel;
 use goose::config::{
-    Config, ConfigError, ExperimentManager, ExtensionConfigManager, ExtensionEntry,
+    Config, ConfigError, ExperimentManager, ExtensionConfigManager, ExtensionEntry, KeyManager,
     PermissionManager,
 };
 use goose::message::Message;
@@ -32,7 +32,13 @@ fn get_display_name(extension_id: &str) -> String {
         "developer" => "Developer Tools".to_string(),
         "computercontroller" => "Computer Controller".to_string(),
         "googledrive" => "Google Drive".to_string(),
+        "gosling" => "Gosling".to_string(),
         "memory" => "Memory".to_string(),
+        "notebook" => "Notebook".to_string(),
+        "pim" => "Email & Calendar".to_string(),
+        "secrets" => "Secrets".to_string(),
+        "speech" => "Speech".to_string(),
+        "tracker" => "Issue Tracker".to_string(),
         "tutorial" => "Tutorial".to_string(),
         "jetbrains" => "JetBrains".to_string(),
         // Add other extensions as needed
@@ -258,7 +264,7 @@ pub async fn configure_provider_dialog() -> Result<bool, Box<dyn Error>> {
                     .interact()?
                 {
                     if key.secret {
-                        config.set_secret(&key.name, Value::String(env_value))?;
+                        KeyManager::set_key(&key.name, env_value, None)?;
                     } else {
                         config.set_param(&key.name, Value::String(env_value))?;
                     }
@@ -291,7 +297,7 @@ pub async fn configure_provider_dialog() -> Result<bool, Box<dyn Error>> {
                             };
 
                             if key.secret {
-                                config.set_secret(&key.name, Value::String(new_value))?;
+                                KeyManager::set_key(&key.name, new_value, None)?;
                             } else {
                                 config.set_param(&key.name, Value::String(new_value))?;
                             }
@@ -317,7 +323,7 @@ pub async fn configure_provider_dialog() -> Result<bool, Box<dyn Error>> {
                         };
 
                         if key.secret {
-                            config.set_secret(&key.name, Value::String(value))?;
+                            KeyManager::set_key(&key.name, value, None)?;
                         } else {
                             config.set_param(&key.name, Value::String(value))?;
                         }
@@ -518,11 +524,41 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                     "Google Drive",
                     "Search and read content from google drive - additional config required",
                 )
+                .item(
+                    "gosling",
+                    "Gosling",
+                    "Install apps and capture logs on Android devices - requires adb",
+                )
                 .item(
                     "memory",
                     "Memory",
                     "Tools to save and retrieve durable memories",
                 )
+                .item(
+                    "notebook",
+                    "Notebook",
+                    "Run code in Jupyter kernels and edit notebooks cell by cell - requires jupyter_client",
+                )
+                .item(
+                    "pim",
+                    "Email & Calendar",
+                    "Search email, save reply drafts and manage events over IMAP and CalDAV",
+                )
+                .item(
+                    "secrets",
+                    "Secrets",
+                    "Use Vault or 1Password secrets in commands without exposing them",
+                )
+                .item(
+                    "speech",
+                    "Speech",
+                    "Transcribe recordings and read text aloud with whisper.cpp or an audio API",
+                )
+                .item(
+                    "tracker",
+                    "Issue Tracker",
+                    "Search, read and comment on GitHub, GitLab and Jira issues",
+                )
                 .item(
                     "tutorial",
                     "Tutorial",
@@ -656,6 +692,9 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                     description,
                     timeout: Some(timeout),
                     bundled: None,
+                    cwd: None,
+                    inherit_env: None,
+                    path_prepend: Vec::new(),
                 },
             })?;
 
@@ -0,0 +1,307 @@
+use anyhow::{bail, Context, Result};
+use console::style;
+use goose::agents::change_summary::git_root;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A file touched by the patch, with line counts as reported by `git apply --numstat`.
+/// The counts are `None` for binary files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PatchedFile {
+    path: String,
+    added: Option<usize>,
+    removed: Option<usize>,
+}
+
+/// A file the patch can't be applied to, with git's explanation
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Conflict {
+    path: String,
+    reason: String,
+}
+
+fn git_apply(root: &Path, args: &[&str], patch: &Path) -> Result<Output> {
+    Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .arg("apply")
+        .args(args)
+        .arg(patch)
+        .output()
+        .context("Failed to run git, is it installed?")
+}
+
+fn parse_numstat(output: &str) -> Vec<PatchedFile> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let added = fields.next()?;
+            let removed = fields.next()?;
+            let path = fields.next()?;
+            Some(PatchedFile {
+                path: path.to_string(),
+                added: added.parse().ok(),
+                removed: removed.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+/// Files `git apply --check` refused, one per file even when git explains a failure over
+/// several lines
+fn parse_conflicts(stderr: &str) -> Vec<Conflict> {
+    let mut conflicts: Vec<Conflict> = Vec::new();
+    for line in stderr.lines() {
+        let Some(message) = line.strip_prefix("error: ") else {
+            continue;
+        };
+        // "patch failed: <path>:<line>" is always followed by "<path>: patch does not apply"
+        if message.starts_with("patch failed: ") {
+            continue;
+        }
+        let Some((path, reason)) = message.rsplit_once(": ") else {
+            continue;
+        };
+        if !conflicts.iter().any(|conflict| conflict.path == path) {
+            conflicts.push(Conflict {
+                path: path.to_string(),
+                reason: reason.to_string(),
+            });
+        }
+    }
+    conflicts
+}
+
+/// Files left with conflict markers by `git apply --3way`
+fn parse_unmerged(stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .filter_map(|line| line.strip_prefix("U "))
+        .map(str::to_string)
+        .collect()
+}
+
+fn format_counts(added: usize, removed: usize) -> String {
+    format!(
+        "{} {}",
+        style(format!("+{}", added)).green(),
+        style(format!("-{}", removed)).red()
+    )
+}
+
+fn print_files(files: &[PatchedFile]) {
+    for file in files {
+        let counts = match (file.added, file.removed) {
+            (Some(added), Some(removed)) => format_counts(added, removed),
+            _ => style("binary").dim().to_string(),
+        };
+        println!("  {} {}", file.path, counts);
+    }
+    let added = files.iter().filter_map(|file| file.added).sum();
+    let removed = files.iter().filter_map(|file| file.removed).sum();
+    println!(
+        "{} file(s) changed, {}",
+        files.len(),
+        format_counts(added, removed)
+    );
+}
+
+/// Apply a patch written by a goose run, such as the one left by `goose run --isolated`, to
+/// `dir` or else to the repository containing the current directory. Nothing is changed when
+/// any file conflicts, unless `three_way` merges the conflicting hunks with conflict markers.
+pub fn handle_diff_apply(
+    patch: &Path,
+    dir: Option<PathBuf>,
+    check: bool,
+    three_way: bool,
+) -> Result<()> {
+    let patch = patch
+        .canonicalize()
+        .with_context(|| format!("Failed to read {}", patch.display()))?;
+    let root = match dir {
+        Some(dir) => dir,
+        None => {
+            let cwd = std::env::current_dir()?;
+            git_root(&cwd).unwrap_or(cwd)
+        }
+    };
+    if three_way && git_root(&root).is_none() {
+        bail!(
+            "--3way needs a git repository, {} isn't in one",
+            root.display()
+        );
+    }
+
+    let numstat = git_apply(&root, &["--numstat"], &patch)?;
+    if !numstat.status.success() {
+        bail!(
+            "{} isn't a valid patch: {}",
+            patch.display(),
+            String::from_utf8_lossy(&numstat.stderr).trim()
+        );
+    }
+    let files = parse_numstat(&String::from_utf8_lossy(&numstat.stdout));
+    if files.is_empty() {
+        println!("The patch doesn't change any files");
+        return Ok(());
+    }
+
+    let checked = git_apply(&root, &["--check"], &patch)?;
+    if !checked.status.success() {
+        let stderr = String::from_utf8_lossy(&checked.stderr);
+        let conflicts = parse_conflicts(&stderr);
+        if conflicts.is_empty() {
+            bail!("The patch doesn't apply: {}", stderr.trim());
+        }
+
+        println!(
+            "{}",
+            style(format!(
+                "{} of {} file(s) conflict with {}:",
+                conflicts.len(),
+                files.len(),
+                root.display()
+            ))
+            .yellow()
+            .bold()
+        );
+        for conflict in &conflicts {
+            println!("  {} {}", conflict.path, style(&conflict.reason).dim());
+        }
+        if check || !three_way {
+            bail!("The patch doesn't apply cleanly, nothing was changed");
+        }
+
+        let merged = git_apply(&root, &["--3way"], &patch)?;
+        let unmerged = parse_unmerged(&String::from_utf8_lossy(&merged.stderr));
+        if merged.status.success() {
+            println!("{}", style("Merged the conflicting files cleanly").green());
+        } else if unmerged.is_empty() {
+            bail!(
+                "Failed to merge the patch: {}",
+                String::from_utf8_lossy(&merged.stderr).trim()
+            );
+        } else {
+            print_files(&files);
+            println!(
+                "{}",
+                style("Resolve the conflict markers in these files, then stage them:").yellow()
+            );
+            for path in &unmerged {
+                println!("  {}", path);
+            }
+            bail!("The patch was applied with conflicts");
+        }
+    } else if check {
+        print_files(&files);
+        println!("{}", style("The patch applies cleanly").green());
+        return Ok(());
+    } else {
+        let applied = git_apply(&root, &[], &patch)?;
+        if !applied.status.success() {
+            bail!(
+                "Failed to apply the patch: {}",
+                String::from_utf8_lossy(&applied.stderr).trim()
+            );
+        }
+    }
+
+    print_files(&files);
+    println!(
+        "{}",
+        style(format!("Applied {} to {}", patch.display(), root.display())).green()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_git_output() {
+        assert_eq!(
+            parse_numstat("3\t1\tsrc/main.rs\n-\t-\tlogo.png\n"),
+            vec![
+                PatchedFile {
+                    path: "src/main.rs".to_string(),
+                    added: Some(3),
+                    removed: Some(1),
+                },
+                PatchedFile {
+                    path: "logo.png".to_string(),
+                    added: None,
+                    removed: None,
+                },
+            ]
+        );
+
+        let conflicts = parse_conflicts(
+            "error: patch failed: a.txt:1\n\
+             error: a.txt: patch does not apply\n\
+             error: b.txt: already exists in working directory\n",
+        );
+        assert_eq!(
+            conflicts,
+            vec![
+                Conflict {
+                    path: "a.txt".to_string(),
+                    reason: "patch does not apply".to_string(),
+                },
+                Conflict {
+                    path: "b.txt".to_string(),
+                    reason: "already exists in working directory".to_string(),
+                },
+            ]
+        );
+
+        assert_eq!(
+            parse_unmerged("Applied patch to 'a.txt' with conflicts.\nU a.txt\n"),
+            vec!["a.txt"]
+        );
+    }
+
+    #[test]
+    fn test_conflicts_leave_checkout_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "changed locally\n").unwrap();
+        let patch = dir.path().join("goose.patch");
+        fs::write(
+            &patch,
+            "diff --git a/a.txt b/a.txt\n\
+             --- a/a.txt\n\
+             +++ b/a.txt\n\
+             @@ -1 +1 @@\n\
+             -one\n\
+             +two\n\
+             diff --git a/b.txt b/b.txt\n\
+             --- a/b.txt\n\
+             +++ b/b.txt\n\
+             @@ -1 +1 @@\n\
+             -original\n\
+             +edited\n",
+        )
+        .unwrap();
+
+        let result = handle_diff_apply(&patch, Some(dir.path().to_path_buf()), false, false);
+        assert!(result.is_err());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "one\n"
+        );
+
+        fs::write(dir.path().join("b.txt"), "original\n").unwrap();
+        handle_diff_apply(&patch, Some(dir.path().to_path_buf()), false, false).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "two\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("b.txt")).unwrap(),
+            "edited\n"
+        );
+    }
+}
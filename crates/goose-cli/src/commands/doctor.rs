@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use console::style;
+use goose::config::Config;
+use goose::providers::http::{self, HttpSettings, CA_BUNDLE_KEY, HTTP_PROXY_KEY, TLS_INSECURE_KEY};
+use goose::providers::providers;
+use std::time::Duration;
+
+const CONNECTIVITY_TIMEOUT: Duration = Duration::from_secs(15);
+
+fn print_aligned(label: &str, value: &str) {
+    println!("  {:<16} {}", label, value);
+}
+
+/// The endpoints of the configured provider, from its host settings or their defaults
+fn provider_urls(config: &Config) -> Vec<(String, String)> {
+    let Ok(provider) = config.get_param::<String>("GOOSE_PROVIDER") else {
+        return Vec::new();
+    };
+    let Some(metadata) = providers().into_iter().find(|p| p.name == provider) else {
+        return Vec::new();
+    };
+    metadata
+        .config_keys
+        .into_iter()
+        .filter(|key| key.name.ends_with("_HOST") || key.name.ends_with("_ENDPOINT"))
+        .filter_map(|key| {
+            let value = config.get_param::<String>(&key.name).ok().or(key.default)?;
+            let url = if value.contains("://") {
+                value
+            } else {
+                format!("https://{}", value)
+            };
+            Some((key.name, url))
+        })
+        .collect()
+}
+
+/// What to try when a provider can't be reached
+fn hint(error: &str) -> Option<String> {
+    let error = error.to_lowercase();
+    if error.contains("certificate") || error.contains("unknownissuer") {
+        Some(format!(
+            "The certificate is not trusted. If your network inspects TLS traffic, set {} to your company's CA bundle.",
+            CA_BUNDLE_KEY
+        ))
+    } else if error.contains("dns") || error.contains("connect") || error.contains("timed out") {
+        Some(format!(
+            "The host could not be reached. If you are behind a proxy, set {} or HTTPS_PROXY.",
+            HTTP_PROXY_KEY
+        ))
+    } else {
+        None
+    }
+}
+
+pub async fn handle_doctor(url: Option<String>) -> Result<()> {
+    let config = Config::global();
+    let settings = HttpSettings::from_config();
+
+    println!("{}", style("HTTP settings:").cyan().bold());
+    let env_proxy = ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok());
+    let proxy = match (&settings.proxy, env_proxy) {
+        (Some(proxy), _) => proxy.clone(),
+        (None, Some(proxy)) => format!("{} (from environment)", proxy),
+        (None, None) => "none".to_string(),
+    };
+    print_aligned("Proxy:", &proxy);
+    print_aligned("No proxy:", settings.no_proxy.as_deref().unwrap_or("-"));
+    print_aligned(
+        "CA bundle:",
+        &settings
+            .ca_bundle
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "system certificates".to_string()),
+    );
+    print_aligned(
+        "Min TLS version:",
+        settings.min_tls_version.as_deref().unwrap_or("default"),
+    );
+    if settings.accept_invalid_certs {
+        print_aligned(
+            "Certificates:",
+            &style(format!("not verified ({} is set)", TLS_INSECURE_KEY))
+                .yellow()
+                .to_string(),
+        );
+    }
+    if let Err(e) = http::client() {
+        println!("\n{} {}", style("✗").red().bold(), e);
+        return Err(anyhow!("The HTTP settings are invalid"));
+    }
+
+    let urls = match url {
+        Some(url) => vec![("--url".to_string(), url)],
+        None => provider_urls(config),
+    };
+    println!("\n{}", style("Connectivity:").cyan().bold());
+    if urls.is_empty() {
+        println!("  No provider host configured, pass --url to check an endpoint");
+        return Ok(());
+    }
+
+    let mut failed = false;
+    for (source, url) in urls {
+        match http::check_connectivity(&url, CONNECTIVITY_TIMEOUT).await {
+            Ok(status) => println!(
+                "  {} {} {}",
+                style("✓").green().bold(),
+                url,
+                style(format!("(HTTP {}, {})", status.as_u16(), source)).dim()
+            ),
+            Err(e) => {
+                failed = true;
+                println!("  {} {} {}", style("✗").red().bold(), url, e);
+                if let Some(hint) = hint(&e.to_string()) {
+                    println!("    {}", style(hint).yellow());
+                }
+            }
+        }
+    }
+    if failed {
+        return Err(anyhow!("Some endpoints could not be reached"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hint() {
+        assert!(
+            hint("error sending request: invalid peer certificate: UnknownIssuer")
+                .unwrap()
+                .contains(CA_BUNDLE_KEY)
+        );
+        assert!(hint("error trying to connect: dns error")
+            .unwrap()
+            .contains(HTTP_PROXY_KEY));
+        assert!(hint("HTTP 500").is_none());
+    }
+}
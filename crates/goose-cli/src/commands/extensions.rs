@@ -0,0 +1,200 @@
+use anyhow::{bail, Result};
+use console::style;
+use goose::config::extensions::name_to_key;
+use goose::config::{Config, ExtensionConfigManager, ExtensionEntry, ExtensionHealthManager};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::extension_registry::{find, load_registry, RegistryEntry};
+
+fn badges(entry: &RegistryEntry) -> String {
+    let mut badges = Vec::new();
+    if entry.is_builtin {
+        badges.push("built-in");
+    }
+    if entry.endorsed {
+        badges.push("endorsed");
+    }
+    if badges.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", badges.join(", "))
+    }
+}
+
+/// List the registry's extensions that match every word of `query`, all of them without one
+pub async fn handle_extensions_search(query: Option<String>) -> Result<()> {
+    let entries = load_registry().await?;
+    let query = query.unwrap_or_default();
+    let matching: Vec<&RegistryEntry> = entries
+        .iter()
+        .filter(|entry| entry.matches(&query))
+        .collect();
+    if matching.is_empty() {
+        println!("No extensions match '{}'", query);
+        return Ok(());
+    }
+
+    for entry in matching {
+        println!(
+            "{} {}{}",
+            style(&entry.id).cyan().bold(),
+            entry.name,
+            style(badges(entry)).dim()
+        );
+        if !entry.description.is_empty() {
+            println!("    {}", entry.description);
+        }
+    }
+    println!(
+        "\n{}",
+        style(
+            "Show details with `goose extensions show <id>`, \
+             add one with `goose extensions install <id>`"
+        )
+        .dim()
+    );
+    Ok(())
+}
+
+pub async fn handle_extensions_show(id: &str) -> Result<()> {
+    let entries = load_registry().await?;
+    let Some(entry) = find(&entries, id) else {
+        bail!(
+            "No extension '{}' in the registry, try `goose extensions search`",
+            id
+        );
+    };
+
+    println!(
+        "{}{}",
+        style(&entry.name).bold(),
+        style(badges(entry)).dim()
+    );
+    if !entry.description.is_empty() {
+        println!("{}", entry.description);
+    }
+    println!();
+    println!("  {:<12} {}", "ID", entry.id);
+    match &entry.command {
+        Some(command) if !entry.is_builtin => println!("  {:<12} {}", "Command", command),
+        _ => println!("  {:<12} bundled with goose", "Command"),
+    }
+    if let Some(link) = &entry.link {
+        println!("  {:<12} {}", "Link", link);
+    }
+    if !entry.environment_variables.is_empty() {
+        println!("\n{}", style("Environment variables:").bold());
+        for var in &entry.environment_variables {
+            let required = if var.required { " (required)" } else { "" };
+            println!(
+                "  {}{} {}",
+                var.name,
+                required,
+                style(&var.description).dim()
+            );
+        }
+    }
+    if let Some(notes) = &entry.installation_notes {
+        println!("\n{}", notes);
+    }
+    Ok(())
+}
+
+/// Add a registry extension to the config without prompting. Values passed with `--env` are
+/// stored in the keyring where possible, and required variables that are already set there or
+/// in the environment are referenced rather than copied.
+pub async fn handle_extensions_install(
+    id: &str,
+    name: Option<String>,
+    envs: Vec<(String, String)>,
+    timeout: Option<u64>,
+) -> Result<()> {
+    let entries = load_registry().await?;
+    let Some(entry) = find(&entries, id) else {
+        bail!(
+            "No extension '{}' in the registry, try `goose extensions search`",
+            id
+        );
+    };
+    // Built-in extensions are started by their id, so they can't be renamed
+    let name = match name {
+        Some(name) if !entry.is_builtin => name,
+        _ => entry.id.clone(),
+    };
+    if ExtensionConfigManager::get_all_names()?.contains(&name) {
+        bail!(
+            "An extension named '{}' is already configured, pick another with --name",
+            name
+        );
+    }
+
+    let config = Config::global();
+    let given: HashMap<String, String> = envs.into_iter().collect();
+    let mut env_keys: Vec<String> = Vec::new();
+    let mut missing = Vec::new();
+    for var in entry
+        .environment_variables
+        .iter()
+        .filter(|var| !given.contains_key(&var.name))
+    {
+        if config.get_secret::<Value>(&var.name).is_ok() {
+            env_keys.push(var.name.clone());
+        } else if var.required {
+            missing.push(format!("  {}  {}", var.name, var.description));
+        }
+    }
+    if !missing.is_empty() {
+        bail!(
+            "'{}' needs these environment variables, pass them with --env NAME=VALUE:\n{}",
+            entry.id,
+            missing.join("\n")
+        );
+    }
+
+    let mut plain_envs = HashMap::new();
+    for (key, value) in given {
+        match config.set_secret(&key, Value::String(value.clone())) {
+            Ok(_) => env_keys.push(key),
+            Err(_) => {
+                plain_envs.insert(key, value);
+            }
+        }
+    }
+
+    ExtensionConfigManager::set(ExtensionEntry {
+        enabled: true,
+        config: entry.to_extension_config(&name, plain_envs, env_keys, timeout)?,
+    })?;
+
+    println!("Installed and enabled {}", style(&name).green());
+    if let Some(notes) = &entry.installation_notes {
+        println!("{}", style(notes).dim());
+    }
+    Ok(())
+}
+
+/// Clear the failures of an extension that was disabled for failing repeatedly, so the next
+/// session starts it again
+pub fn handle_extensions_enable(name: &str) -> Result<()> {
+    let health = ExtensionHealthManager::get(name);
+    let configured = ExtensionConfigManager::get_config_by_name(name)?.is_some();
+    if health.is_none() && !configured {
+        bail!("No extension named '{}' is configured or disabled", name);
+    }
+
+    ExtensionHealthManager::enable(name)?;
+    if configured {
+        ExtensionConfigManager::set_enabled(&name_to_key(name), true)?;
+    }
+    match health {
+        Some(health) if health.is_disabled() => {
+            println!("Enabled {}", style(name).green());
+            if let Some(error) = health.last_error {
+                println!("{}", style(format!("It last failed with: {}", error)).dim());
+            }
+        }
+        _ => println!("{} is enabled", style(name).green()),
+    }
+    Ok(())
+}
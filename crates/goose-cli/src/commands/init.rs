@@ -0,0 +1,302 @@
+use anyhow::Result;
+use console::style;
+use goose::config::PROJECT_CONFIG_DIR;
+use std::fs;
+use std::path::Path;
+
+/// What a quick scan of a project found out about how it is built
+#[derive(Debug, Default, PartialEq)]
+pub struct ProjectInfo {
+    pub languages: Vec<&'static str>,
+    pub build_tool: Option<&'static str>,
+    pub build_command: Option<String>,
+    pub test_command: Option<String>,
+    pub ignore_patterns: Vec<&'static str>,
+}
+
+impl ProjectInfo {
+    fn set_tool(&mut self, tool: &'static str, build: Option<String>, test: Option<String>) {
+        // The first build tool found wins, later ones only add languages
+        if self.build_tool.is_none() {
+            self.build_tool = Some(tool);
+            self.build_command = build;
+            self.test_command = test;
+        }
+    }
+
+    fn add_language(&mut self, language: &'static str, ignore: &[&'static str]) {
+        if !self.languages.contains(&language) {
+            self.languages.push(language);
+        }
+        for pattern in ignore {
+            if !self.ignore_patterns.contains(pattern) {
+                self.ignore_patterns.push(pattern);
+            }
+        }
+    }
+}
+
+fn has(dir: &Path, file: &str) -> bool {
+    dir.join(file).exists()
+}
+
+fn read(dir: &Path, file: &str) -> String {
+    fs::read_to_string(dir.join(file)).unwrap_or_default()
+}
+
+/// Detect languages, the build tool and the test command from the files at the project root
+pub fn detect_project(dir: &Path) -> ProjectInfo {
+    let mut info = ProjectInfo::default();
+
+    if has(dir, "Cargo.toml") {
+        info.add_language("Rust", &["target/"]);
+        info.set_tool(
+            "cargo",
+            Some("cargo build".to_string()),
+            Some("cargo test".to_string()),
+        );
+    }
+
+    if has(dir, "package.json") {
+        let language = if has(dir, "tsconfig.json") {
+            "TypeScript"
+        } else {
+            "JavaScript"
+        };
+        info.add_language(language, &["node_modules/", "dist/", "build/"]);
+        let manager = if has(dir, "pnpm-lock.yaml") {
+            "pnpm"
+        } else if has(dir, "yarn.lock") {
+            "yarn"
+        } else if has(dir, "bun.lockb") {
+            "bun"
+        } else {
+            "npm"
+        };
+        let scripts = serde_json::from_str::<serde_json::Value>(&read(dir, "package.json"))
+            .ok()
+            .and_then(|package| package.get("scripts").cloned())
+            .unwrap_or_default();
+        let script = |name: &str| {
+            scripts
+                .get(name)
+                .map(|_| format!("{} run {}", manager, name))
+        };
+        info.set_tool(manager, script("build"), script("test"));
+    }
+
+    let pyproject = read(dir, "pyproject.toml");
+    if !pyproject.is_empty() || has(dir, "requirements.txt") || has(dir, "setup.py") {
+        info.add_language(
+            "Python",
+            &["__pycache__/", ".venv/", "*.egg-info/", ".pytest_cache/"],
+        );
+        let (tool, runner) = if has(dir, "uv.lock") {
+            ("uv", "uv run ")
+        } else if has(dir, "poetry.lock") || pyproject.contains("[tool.poetry]") {
+            ("poetry", "poetry run ")
+        } else {
+            ("pip", "")
+        };
+        info.set_tool(tool, None, Some(format!("{}pytest", runner)));
+    }
+
+    if has(dir, "go.mod") {
+        info.add_language("Go", &[]);
+        info.set_tool(
+            "go",
+            Some("go build ./...".to_string()),
+            Some("go test ./...".to_string()),
+        );
+    }
+
+    if has(dir, "pom.xml") {
+        info.add_language("Java", &["target/"]);
+        info.set_tool(
+            "maven",
+            Some("mvn package".to_string()),
+            Some("mvn test".to_string()),
+        );
+    }
+
+    if has(dir, "build.gradle") || has(dir, "build.gradle.kts") {
+        let language = if has(dir, "build.gradle.kts") {
+            "Kotlin"
+        } else {
+            "Java"
+        };
+        info.add_language(language, &["build/", ".gradle/"]);
+        let gradle = if has(dir, "gradlew") {
+            "./gradlew"
+        } else {
+            "gradle"
+        };
+        info.set_tool(
+            "gradle",
+            Some(format!("{} build", gradle)),
+            Some(format!("{} test", gradle)),
+        );
+    }
+
+    if has(dir, "Makefile") {
+        let makefile = read(dir, "Makefile");
+        let target = |name: &str| {
+            makefile
+                .lines()
+                .any(|line| line.starts_with(&format!("{}:", name)))
+                .then(|| format!("make {}", name))
+        };
+        info.set_tool("make", Some("make".to_string()), target("test"));
+    }
+
+    info
+}
+
+const CONFIG_TEMPLATE: &str = r#"# Project settings for goose.
+# Values here override ~/.config/goose/config.yaml when goose runs in this directory,
+# environment variables still take precedence over both.
+#
+# GOOSE_PROVIDER: anthropic
+# GOOSE_MODEL: claude-sonnet-4-0
+# GOOSE_MODE: smart_approve
+"#;
+
+fn hints_template(info: &ProjectInfo) -> String {
+    let mut hints = String::from("# Project hints for goose\n\n");
+    hints.push_str("## Overview\n\n");
+    hints.push_str("Describe what this project does and how it is laid out.\n\n");
+
+    hints.push_str("## Development\n\n");
+    if !info.languages.is_empty() {
+        hints.push_str(&format!("- Language: {}\n", info.languages.join(", ")));
+    }
+    if let Some(tool) = info.build_tool {
+        hints.push_str(&format!("- Build tool: {}\n", tool));
+    }
+    if let Some(build) = &info.build_command {
+        hints.push_str(&format!("- Build with `{}`\n", build));
+    }
+    if let Some(test) = &info.test_command {
+        hints.push_str(&format!(
+            "- Run the tests with `{}` after making changes\n",
+            test
+        ));
+    }
+    if info.build_tool.is_none() {
+        hints.push_str("- Add how to build and test the project here\n");
+    }
+
+    hints.push_str("\n## Conventions\n\n");
+    hints.push_str("Add coding conventions goose should follow in this project.\n");
+    hints
+}
+
+fn ignore_template(info: &ProjectInfo) -> String {
+    let mut ignore = String::from(
+        "# Files goose should not read or modify, using .gitignore syntax\n\
+         **/.env\n\
+         **/.env.*\n\
+         **/secrets.*\n\
+         **/*.pem\n\
+         **/*.key\n",
+    );
+    for pattern in &info.ignore_patterns {
+        ignore.push_str(pattern);
+        ignore.push('\n');
+    }
+    ignore
+}
+
+/// Write `content` to `path` unless it exists, returns whether it was written
+fn write_file(dir: &Path, relative: &str, content: &str, force: bool) -> Result<bool> {
+    let path = dir.join(relative);
+    if path.exists() && !force {
+        println!("  {} {} (already exists)", style("skip").dim(), relative);
+        return Ok(false);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, content)?;
+    println!("  {} {}", style("create").green(), relative);
+    Ok(true)
+}
+
+/// Scaffold the goose files for the project in `dir`
+pub fn handle_init(dir: &Path, force: bool) -> Result<()> {
+    let info = detect_project(dir);
+
+    println!(
+        "{} {}",
+        style("Initializing goose in").cyan(),
+        style(dir.display()).cyan().bold()
+    );
+    if info.languages.is_empty() {
+        println!("  No known project type detected, writing generic templates");
+    } else {
+        println!("  Detected {}", info.languages.join(", "));
+    }
+
+    let config = format!("{}/config.yaml", PROJECT_CONFIG_DIR);
+    let mut written = write_file(dir, &config, CONFIG_TEMPLATE, force)?;
+    written |= write_file(dir, ".goosehints", &hints_template(&info), force)?;
+    written |= write_file(dir, ".gooseignore", &ignore_template(&info), force)?;
+
+    if written {
+        println!(
+            "\nReview the generated files, then run {} to start a session",
+            style("goose session").cyan()
+        );
+    } else {
+        println!("\nNothing to do, pass --force to overwrite the existing files");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_detect_project() {
+        let dir = tempdir().unwrap();
+        assert_eq!(detect_project(dir.path()), ProjectInfo::default());
+
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"scripts": {"test": "vitest"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("pnpm-lock.yaml"), "").unwrap();
+        fs::write(dir.path().join("pyproject.toml"), "[tool.poetry]\n").unwrap();
+
+        let info = detect_project(dir.path());
+        assert_eq!(info.languages, vec!["JavaScript", "Python"]);
+        assert_eq!(info.build_tool, Some("pnpm"));
+        assert_eq!(info.build_command, None);
+        assert_eq!(info.test_command.as_deref(), Some("pnpm run test"));
+        assert!(info.ignore_patterns.contains(&"node_modules/"));
+        assert!(info.ignore_patterns.contains(&"__pycache__/"));
+    }
+
+    #[test]
+    fn test_init_keeps_existing_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(dir.path().join(".goosehints"), "my hints").unwrap();
+
+        handle_init(dir.path(), false).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join(".goosehints")).unwrap(),
+            "my hints"
+        );
+        assert!(dir.path().join(".goose/config.yaml").exists());
+        let ignore = fs::read_to_string(dir.path().join(".gooseignore")).unwrap();
+        assert!(ignore.contains("target/"));
+
+        handle_init(dir.path(), true).unwrap();
+        let hints = fs::read_to_string(dir.path().join(".goosehints")).unwrap();
+        assert!(hints.contains("`cargo test`"));
+    }
+}
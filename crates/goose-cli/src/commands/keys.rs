@@ -0,0 +1,119 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use console::style;
+use goose::config::{Config, KeyManager};
+use goose::providers::providers;
+use serde_json::Value;
+
+fn format_date(date: Option<DateTime<Utc>>) -> String {
+    date.map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Secret keys declared by the providers, with the provider that uses them
+fn provider_secret_keys() -> Vec<(String, String)> {
+    let mut keys: Vec<(String, String)> = providers()
+        .into_iter()
+        .flat_map(|p| {
+            let provider = p.name.clone();
+            p.config_keys
+                .into_iter()
+                .filter(|key| key.secret)
+                .map(move |key| (key.name, provider.clone()))
+        })
+        .collect();
+    keys.sort();
+    keys.dedup_by(|a, b| a.0 == b.0);
+    keys
+}
+
+pub fn handle_keys_list() -> Result<()> {
+    let config = Config::global();
+    let metadata = KeyManager::get_all();
+    let now = Utc::now();
+    let warn_before = KeyManager::expiry_warning_window();
+
+    println!("{}", style("Provider keys:").cyan().bold());
+    println!(
+        "  {:<32} {:<16} {:<12} {:<12} {:<12} {:<12}",
+        "Key", "Provider", "Source", "Created", "Expires", "Last used"
+    );
+
+    let mut any = false;
+    for (name, provider) in provider_secret_keys() {
+        let source = if std::env::var(&name).is_ok() {
+            "environment"
+        } else if config.get_secret::<Value>(&name).is_ok() {
+            "keyring"
+        } else {
+            continue;
+        };
+        any = true;
+
+        let key_metadata = metadata.get(&name).cloned().unwrap_or_default();
+        println!(
+            "  {:<32} {:<16} {:<12} {:<12} {:<12} {:<12}",
+            name,
+            provider,
+            source,
+            format_date(key_metadata.created_at),
+            format_date(key_metadata.expires_at),
+            format_date(key_metadata.last_used),
+        );
+        if let Some(warning) = key_metadata.warning(&name, now, warn_before) {
+            println!("    {}", style(warning.to_string()).yellow());
+        }
+    }
+
+    if !any {
+        println!("  No provider keys stored");
+        println!(
+            "  Run '{}' to configure a provider",
+            style("goose configure").cyan()
+        );
+    }
+    Ok(())
+}
+
+pub fn handle_keys_rotate(name: String, expires_in_days: Option<i64>) -> Result<()> {
+    if !KeyManager::is_provider_key(&name) {
+        bail!("'{}' is not a provider key, see 'goose keys list'", name);
+    }
+
+    let value: String = cliclack::password(format!("Enter the new value for {}", name))
+        .mask('▪')
+        .interact()?;
+    let expires_at = expires_in_days.map(|days| Utc::now() + Duration::days(days));
+    KeyManager::set_key(&name, value, expires_at)?;
+
+    println!("{} {}", style("Rotated").green(), name);
+    if let Some(expires_at) = expires_at {
+        println!("  Expires on {}", format_date(Some(expires_at)));
+    }
+    if std::env::var(&name).is_ok() {
+        println!(
+            "  {}",
+            style(format!(
+                "{} is also set in the environment, which takes precedence over the keyring",
+                name
+            ))
+            .yellow()
+        );
+    }
+    Ok(())
+}
+
+pub fn handle_keys_remove(name: String) -> Result<()> {
+    if !KeyManager::is_provider_key(&name) {
+        bail!("'{}' is not a provider key, see 'goose keys list'", name);
+    }
+    if !cliclack::confirm(format!("Remove {} from the keyring?", name))
+        .initial_value(false)
+        .interact()?
+    {
+        return Ok(());
+    }
+    KeyManager::remove_key(&name)?;
+    println!("{} {}", style("Removed").green(), name);
+    Ok(())
+}
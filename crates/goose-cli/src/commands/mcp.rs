@@ -1,10 +1,11 @@
 use anyhow::Result;
 use goose_mcp::{
-    ComputerControllerRouter, DeveloperRouter, GoogleDriveRouter, JetBrainsRouter, MemoryRouter,
+    ComputerControllerRouter, DeveloperRouter, GoogleDriveRouter, GoslingRouter, JetBrainsRouter,
+    MemoryRouter, NotebookRouter, PimRouter, SecretsRouter, SpeechRouter, TrackerRouter,
     TutorialRouter,
 };
 use mcp_server::router::RouterService;
-use mcp_server::{BoundedService, ByteTransport, Server};
+use mcp_server::{BoundedService, ByteTransport, Server, TraceConfig, TraceService};
 use tokio::io::{stdin, stdout};
 
 use std::sync::Arc;
@@ -18,8 +19,12 @@ use nix::unistd::getpgrp;
 use nix::unistd::Pid;
 
 pub async fn run_server(name: &str) -> Result<()> {
-    // Initialize logging
-    crate::logging::setup_logging(Some(&format!("mcp-{name}")), None)?;
+    // Initialize logging, tagged with the session that started the server
+    let log_name = match std::env::var(crate::logging::LOG_SESSION_ENV) {
+        Ok(session) => format!("mcp-{name}@{session}"),
+        Err(_) => format!("mcp-{name}"),
+    };
+    crate::logging::setup_logging(Some(&log_name), None)?;
 
     tracing::info!("Starting MCP server");
 
@@ -31,7 +36,13 @@ pub async fn run_server(name: &str) -> Result<()> {
             let router = GoogleDriveRouter::new().await;
             Some(Box::new(RouterService(router)))
         }
+        "gosling" => Some(Box::new(RouterService(GoslingRouter::new()))),
         "memory" => Some(Box::new(RouterService(MemoryRouter::new()))),
+        "notebook" => Some(Box::new(RouterService(NotebookRouter::new()))),
+        "pim" => Some(Box::new(RouterService(PimRouter::new()))),
+        "secrets" => Some(Box::new(RouterService(SecretsRouter::new()))),
+        "speech" => Some(Box::new(RouterService(SpeechRouter::new()))),
+        "tracker" => Some(Box::new(RouterService(TrackerRouter::new()))),
         "tutorial" => Some(Box::new(RouterService(TutorialRouter::new()))),
         _ => None,
     };
@@ -47,7 +58,9 @@ pub async fn run_server(name: &str) -> Result<()> {
     });
 
     // Create and run the server
-    let server = Server::new(router.unwrap_or_else(|| panic!("Unknown server requested {}", name)));
+    // Wrap the router so requests are logged with durations and slow tool calls are flagged
+    let router = router.unwrap_or_else(|| panic!("Unknown server requested {}", name));
+    let server = Server::new(TraceService::new(router, TraceConfig::from_env(name)));
     let transport = ByteTransport::new(stdin(), stdout());
 
     tracing::info!("Server initialized and ready to handle requests");
@@ -0,0 +1,38 @@
+use anyhow::{anyhow, Result};
+use console::style;
+use goose_mcp::memory_transfer::{self, ConflictStrategy};
+use goose_mcp::MemoryRouter;
+use std::path::{Path, PathBuf};
+
+pub fn handle_memory_export(path: &Path) -> Result<()> {
+    MemoryRouter::new().export_to_file(path)?;
+    println!("Exported memories to {}", style(path.display()).cyan());
+    Ok(())
+}
+
+pub fn handle_memory_import(path: &Path, strategy: ConflictStrategy) -> Result<()> {
+    let summary = MemoryRouter::new().import_from_file(path, strategy)?;
+    println!(
+        "Imported memories from {}: {}",
+        style(path.display()).cyan(),
+        summary
+    );
+    Ok(())
+}
+
+pub async fn handle_memory_sync(repo: Option<PathBuf>, remote: Option<String>) -> Result<()> {
+    let repo = match repo {
+        Some(repo) => repo,
+        None => std::env::var(memory_transfer::SYNC_REPO_ENV)
+            .map(PathBuf::from)
+            .map_err(|_| {
+                anyhow!(
+                    "Pass --repo or set {} to the git repository to sync with",
+                    memory_transfer::SYNC_REPO_ENV
+                )
+            })?,
+    };
+    let message = MemoryRouter::new().sync(&repo, remote.as_deref()).await?;
+    println!("{}", message);
+    Ok(())
+}
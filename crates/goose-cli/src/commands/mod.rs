@@ -1,10 +1,24 @@
 pub mod bench;
+pub mod bundle;
 pub mod configure;
+pub mod diff_apply;
+pub mod doctor;
+pub mod extensions;
 pub mod info;
+pub mod init;
+pub mod keys;
 pub mod mcp;
+pub mod memory;
 pub mod project;
+pub mod prompt;
 pub mod recipe;
+pub mod review;
+pub mod run;
 pub mod schedule;
 pub mod session;
+pub mod session_logs;
+pub mod tasks;
 pub mod update;
+pub mod usage;
+pub mod watch;
 pub mod web;
@@ -0,0 +1,102 @@
+use anyhow::{anyhow, Result};
+use console::style;
+use std::collections::HashMap;
+use std::io::Read;
+use std::time::Duration;
+
+use crate::commands::run::{run_headless, RunOutcome};
+use crate::logging::setup_logging;
+use crate::prompt_library::{self, SavedPrompt};
+use crate::session::{build_session, SessionBuilderConfig};
+
+/// Save a prompt, reading it from stdin when no text or `-` is given
+pub fn handle_prompt_save(name: &str, text: Option<String>) -> Result<()> {
+    let text = match text.as_deref() {
+        Some("-") | None => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+        Some(text) => text.to_string(),
+    };
+    if text.trim().is_empty() {
+        return Err(anyhow!("Prompt '{}' is empty", name));
+    }
+    let path = prompt_library::save_prompt(name, text.trim_end())?;
+    println!(
+        "Saved prompt {} to {}",
+        style(name).green().bold(),
+        style(path.display()).cyan()
+    );
+    Ok(())
+}
+
+pub fn handle_prompt_list(verbose: bool) -> Result<()> {
+    let prompts = prompt_library::list_prompts()?;
+    if prompts.is_empty() {
+        println!(
+            "No saved prompts, add one with: goose prompt save <name> \"<text>\"\n\
+             Use {{{{ name }}}} in the text for values filled in when it runs."
+        );
+        return Ok(());
+    }
+    for prompt in prompts {
+        let variables = prompt.variables();
+        if variables.is_empty() {
+            println!("{}", style(&prompt.name).green().bold());
+        } else {
+            println!(
+                "{} {}",
+                style(&prompt.name).green().bold(),
+                style(format!("({})", variables.join(", "))).dim()
+            );
+        }
+        if verbose {
+            println!("{}\n", prompt.text);
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_prompt_remove(name: &str) -> Result<()> {
+    if prompt_library::remove_prompt(name)? {
+        println!("Removed prompt {}", style(name).green().bold());
+        Ok(())
+    } else {
+        Err(anyhow!("No saved prompt named '{}'", name))
+    }
+}
+
+fn load_saved_prompt(name: &str) -> Result<SavedPrompt> {
+    prompt_library::load_prompt(name)?
+        .ok_or_else(|| anyhow!("No saved prompt named '{}', see goose prompt list", name))
+}
+
+/// Fill in a saved prompt and run it in a new session
+pub async fn handle_prompt_run(
+    name: &str,
+    params: Vec<(String, String)>,
+    interactive: bool,
+    timeout: Option<u64>,
+) -> Result<RunOutcome> {
+    let prompt = load_saved_prompt(name)?;
+    let values: HashMap<String, String> = params.into_iter().collect();
+    let text = prompt.render(&values)?;
+
+    let mut session = build_session(SessionBuilderConfig {
+        project_history: true,
+        ..Default::default()
+    })
+    .await;
+    setup_logging(
+        session.session_file().file_stem().and_then(|s| s.to_str()),
+        None,
+    )?;
+
+    if interactive {
+        session.interactive(Some(text)).await?;
+        Ok(RunOutcome::Completed)
+    } else {
+        Ok(run_headless(&mut session, text, timeout.map(Duration::from_secs)).await)
+    }
+}
@@ -0,0 +1,319 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use cliclack::{confirm, input, select};
+use console::style;
+use goose::agents::change_summary::git_root;
+use goose::agents::edit_preview::unified_diff;
+use goose_mcp::edit_journal::{self, FileChange};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::session::render_diff;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    Accept,
+    Reject,
+    AcceptRest,
+    RejectRest,
+}
+
+fn show_change(change: &FileChange, index: usize, total: usize) {
+    let name = change.path.display().to_string();
+    let status = match (&change.before, change.path.exists()) {
+        (None, _) => " (new file)",
+        (Some(_), false) => " (deleted)",
+        (Some(_), true) => "",
+    };
+    println!(
+        "\n{} {}{}",
+        style(format!("[{}/{}]", index + 1, total)).dim(),
+        style(&name).bold(),
+        status
+    );
+    if !change.is_current() {
+        println!(
+            "{}",
+            style("Changed again since goose's last edit, rejecting it drops those changes too")
+                .yellow()
+        );
+    }
+
+    let before = change.before.as_deref().unwrap_or_default();
+    let after = fs::read(&change.path).unwrap_or_default();
+    match (std::str::from_utf8(before), std::str::from_utf8(&after)) {
+        (Ok(before), Ok(after)) => render_diff(&unified_diff(before, after, &name, &name)),
+        _ => println!("{}", style("Binary file changed").dim()),
+    }
+}
+
+fn git(root: &Path, args: &[&str], paths: &[PathBuf]) -> Result<()> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .arg("--")
+        .args(paths)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Run git in `root` with its own index file, returning the output
+fn git_output(root: &Path, index: Option<&Path>, args: &[&str]) -> Result<Vec<u8>> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(root).args(args);
+    if let Some(index) = index {
+        command.env("GIT_INDEX_FILE", index);
+    }
+    let output = command.output().context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Record every file in the working tree, tracked or not, as a tree object. A separate index
+/// is used so whatever the user staged stays as it is.
+fn write_tree(root: &Path) -> Result<String> {
+    let git_path = |name: &str| -> Result<PathBuf> {
+        let path = git_output(root, None, &["rev-parse", "--git-path", name])?;
+        Ok(root.join(String::from_utf8_lossy(&path).trim()))
+    };
+    let index = git_path("goose-review-index")?;
+    // Starting from the real index spares hashing the files that didn't change
+    let _ = fs::copy(git_path("index")?, &index);
+    let tree = git_output(root, Some(&index), &["add", "--all"])
+        .and_then(|_| git_output(root, Some(&index), &["write-tree"]));
+    let _ = fs::remove_file(&index);
+    Ok(String::from_utf8_lossy(&tree?).trim().to_string())
+}
+
+/// The working tree of the repository a run works in, taken before the run so the review
+/// also covers files changed through the shell rather than the editor
+pub struct WorktreeSnapshot {
+    root: PathBuf,
+    tree: String,
+}
+
+impl WorktreeSnapshot {
+    /// Snapshot the repository `dir` is in, None when it isn't in one
+    pub fn capture(dir: &Path) -> Result<Option<Self>> {
+        let Some(root) = git_root(dir) else {
+            return Ok(None);
+        };
+        let root = root.canonicalize().unwrap_or(root);
+        let tree = write_tree(&root)?;
+        Ok(Some(Self { root, tree }))
+    }
+
+    /// The files that differ from the snapshot, with their content when it was taken
+    fn changes(&self) -> Result<Vec<FileChange>> {
+        let now = write_tree(&self.root)?;
+        let output = git_output(
+            &self.root,
+            None,
+            &[
+                "diff-tree",
+                "-r",
+                "-z",
+                "--no-renames",
+                "--name-status",
+                &self.tree,
+                &now,
+            ],
+        )?;
+        let mut fields = output
+            .split(|byte| *byte == 0)
+            .filter(|field| !field.is_empty());
+        let mut changes = Vec::new();
+        while let (Some(status), Some(name)) = (fields.next(), fields.next()) {
+            let name = String::from_utf8_lossy(name);
+            let before = match status {
+                b"A" => None,
+                _ => Some(git_output(
+                    &self.root,
+                    None,
+                    &["cat-file", "blob", &format!("{}:{}", self.tree, name)],
+                )?),
+            };
+            changes.push(FileChange::new(self.root.join(&*name), before));
+        }
+        Ok(changes)
+    }
+}
+
+/// Offer to commit the accepted files, leaving anything else in the repository alone
+fn commit_accepted(accepted: &[PathBuf], session: &str) -> Result<()> {
+    let Some(root) = git_root(&std::env::current_dir()?) else {
+        return Ok(());
+    };
+    let root = root.canonicalize().unwrap_or(root);
+    let paths: Vec<PathBuf> = accepted
+        .iter()
+        .filter(|path| path.starts_with(&root))
+        .cloned()
+        .collect();
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let should_commit = confirm(format!("Commit the {} accepted file(s)?", paths.len()))
+        .initial_value(false)
+        .interact()?;
+    if !should_commit {
+        return Ok(());
+    }
+    let message: String = input("Commit message")
+        .default_input(&format!("Apply changes from goose run {}", session))
+        .interact()?;
+
+    git(&root, &["add", "--all"], &paths)?;
+    git(&root, &["commit", "--message", &message], &paths)?;
+    println!("{}", style("Committed the accepted changes").green());
+    Ok(())
+}
+
+/// Show the files a headless run changed as diffs and accept or reject each one, then offer
+/// to commit the accepted ones. In a repository the working tree is compared with the
+/// snapshot taken before the run, so changes made any way are found. Files outside of it,
+/// or every file without a snapshot, come from the developer extension's edit journal.
+pub fn review_run(
+    session: &str,
+    since: DateTime<Utc>,
+    snapshot: Option<&WorktreeSnapshot>,
+) -> Result<()> {
+    let mut changes = match snapshot {
+        Some(snapshot) => snapshot.changes()?,
+        None => Vec::new(),
+    };
+    let journaled = edit_journal::session_changes(&edit_journal::journal_root(), session, since)?;
+    for change in journaled {
+        let path = change.path.canonicalize().unwrap_or(change.path.clone());
+        if !changes.iter().any(|found| found.path == path) {
+            changes.push(change);
+        }
+    }
+    changes.retain(|change| !change.is_noop());
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    if changes.is_empty() {
+        println!("The run didn't change any files");
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        style(format!("The run changed {} file(s)", changes.len())).bold()
+    );
+    let mut accepted = Vec::new();
+    let mut rejected = Vec::new();
+    let mut rest = None;
+    for (index, change) in changes.iter().enumerate() {
+        let decision = match rest {
+            Some(decision) => decision,
+            None => {
+                show_change(change, index, changes.len());
+                let decision = select(format!("Keep the changes to {}?", change.path.display()))
+                    .item(Decision::Accept, "Accept", "")
+                    .item(Decision::Reject, "Reject", "restore the file")
+                    .item(Decision::AcceptRest, "Accept all remaining", "")
+                    .item(Decision::RejectRest, "Reject all remaining", "")
+                    .interact()?;
+                match decision {
+                    Decision::AcceptRest => rest = Some(Decision::Accept),
+                    Decision::RejectRest => rest = Some(Decision::Reject),
+                    _ => {}
+                }
+                rest.unwrap_or(decision)
+            }
+        };
+
+        if decision == Decision::Accept {
+            accepted.push(change.path.clone());
+        } else {
+            change
+                .restore()
+                .with_context(|| format!("Failed to restore {}", change.path.display()))?;
+            rejected.push(change.path.clone());
+        }
+    }
+
+    println!(
+        "\nAccepted {} file(s), restored {} file(s)",
+        accepted.len(),
+        rejected.len()
+    );
+    if !accepted.is_empty() {
+        commit_accepted(&accepted, session)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_git(dir: &Path, args: &[&str]) -> String {
+        String::from_utf8(git_output(dir, None, args).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_finds_changes_made_outside_the_editor() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        run_git(&root, &["init", "--quiet"]);
+        run_git(&root, &["config", "user.email", "test@example.com"]);
+        run_git(&root, &["config", "user.name", "test"]);
+        fs::write(root.join("kept.txt"), "kept").unwrap();
+        fs::write(root.join("edited.txt"), "before").unwrap();
+        fs::write(root.join("removed.txt"), "removed").unwrap();
+        run_git(&root, &["add", "--all"]);
+        run_git(&root, &["commit", "--quiet", "--message", "initial"]);
+        // Uncommitted work from before the run is part of the snapshot, not a change
+        fs::write(root.join("draft.txt"), "draft").unwrap();
+
+        let snapshot = WorktreeSnapshot::capture(&root).unwrap().unwrap();
+        fs::write(root.join("edited.txt"), "after").unwrap();
+        fs::remove_file(root.join("removed.txt")).unwrap();
+        fs::write(root.join("created.txt"), "created").unwrap();
+
+        let mut changes = snapshot.changes().unwrap();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        let found: Vec<(PathBuf, Option<Vec<u8>>)> = changes
+            .iter()
+            .map(|change| (change.path.clone(), change.before.clone()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (root.join("created.txt"), None),
+                (root.join("edited.txt"), Some(b"before".to_vec())),
+                (root.join("removed.txt"), Some(b"removed".to_vec())),
+            ]
+        );
+
+        for change in &changes {
+            change.restore().unwrap();
+        }
+        assert_eq!(
+            fs::read_to_string(root.join("edited.txt")).unwrap(),
+            "before"
+        );
+        assert!(root.join("removed.txt").exists());
+        assert!(!root.join("created.txt").exists());
+        // The user's index is left alone
+        assert_eq!(run_git(&root, &["diff", "--cached", "--name-only"]), "");
+    }
+}
@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use goose::message::{Message, MessageContent};
+
+use crate::Session;
+
+/// The run ended on a tool call that failed. 1 is any other error and clap exits with 2 on
+/// bad arguments, so those are left alone.
+pub const EXIT_TOOL_FAILED: i32 = 3;
+/// The agent stopped on a provider error, after any retries
+pub const EXIT_AGENT_GAVE_UP: i32 = 4;
+/// Same as coreutils `timeout`
+pub const EXIT_TIMEOUT: i32 = 124;
+/// Same as a shell for a command stopped by Ctrl+C
+pub const EXIT_PAUSED: i32 = 130;
+
+/// How a headless run ended, so CI scripts can tell failures apart by exit code
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunOutcome {
+    Completed,
+    ToolFailed(String),
+    GaveUp(String),
+    TimedOut(Duration),
+    /// Stopped part way by Ctrl+C or a pause request, to be continued later
+    Paused(String),
+}
+
+impl RunOutcome {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunOutcome::Completed => 0,
+            RunOutcome::ToolFailed(_) => EXIT_TOOL_FAILED,
+            RunOutcome::GaveUp(_) => EXIT_AGENT_GAVE_UP,
+            RunOutcome::TimedOut(_) => EXIT_TIMEOUT,
+            RunOutcome::Paused(_) => EXIT_PAUSED,
+        }
+    }
+
+    /// What went wrong, None when the run completed or was paused on purpose
+    pub fn describe(&self) -> Option<String> {
+        match self {
+            RunOutcome::Completed | RunOutcome::Paused(_) => None,
+            RunOutcome::ToolFailed(error) => {
+                Some(format!("The run ended on a failed tool call: {}", error))
+            }
+            RunOutcome::GaveUp(error) => Some(format!("The agent gave up: {}", error)),
+            RunOutcome::TimedOut(limit) => {
+                Some(format!("The run didn't finish within {}s", limit.as_secs()))
+            }
+        }
+    }
+}
+
+/// The error of the last tool call in `messages` when it failed. The agent often recovers from
+/// a failed call on its own, so only a failure it didn't follow up on counts.
+pub fn last_tool_failure(messages: &[Message]) -> Option<String> {
+    let contents: Vec<&MessageContent> = messages.iter().flat_map(|m| &m.content).collect();
+    let response = contents.iter().rev().find_map(|content| match content {
+        MessageContent::ToolResponse(response) => Some(response),
+        _ => None,
+    })?;
+    let error = response.tool_result.as_ref().err()?;
+    let tool_name = contents
+        .iter()
+        .filter_map(|content| content.as_tool_request())
+        .find(|request| request.id == response.id)
+        .and_then(|request| request.tool_call.as_ref().ok())
+        .map(|call| call.name.clone());
+    Some(match tool_name {
+        Some(name) => format!("{}: {}", name, error),
+        None => error.to_string(),
+    })
+}
+
+/// Run `prompt` headless, stopping after `timeout` when one is given
+pub async fn run_headless(
+    session: &mut Session,
+    prompt: String,
+    timeout: Option<Duration>,
+) -> RunOutcome {
+    let start = session.message_history().len();
+    let result = match timeout {
+        Some(limit) => match tokio::time::timeout(limit, session.headless(prompt)).await {
+            Ok(result) => result,
+            Err(_) => return RunOutcome::TimedOut(limit),
+        },
+        None => session.headless(prompt).await,
+    };
+
+    if let Err(e) = result {
+        return RunOutcome::GaveUp(e.to_string());
+    }
+    if let Some(reason) = session.pause_reason() {
+        return RunOutcome::Paused(reason.to_string());
+    }
+    if let Some(error) = session.last_error().await {
+        return RunOutcome::GaveUp(error);
+    }
+    let messages = session.message_history();
+    match last_tool_failure(&messages[start.min(messages.len())..]) {
+        Some(error) => RunOutcome::ToolFailed(error),
+        None => RunOutcome::Completed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::{tool::ToolCall, Content, ToolError};
+    use serde_json::json;
+
+    #[test]
+    fn test_last_tool_failure() {
+        let request = |id: &str| {
+            Message::assistant().with_tool_request(
+                id,
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "make"}),
+                )),
+            )
+        };
+        let failed = Message::user().with_tool_response(
+            "1",
+            Err(ToolError::ExecutionError("exit status 2".to_string())),
+        );
+        let mut messages = vec![request("1"), failed];
+        assert_eq!(
+            last_tool_failure(&messages).as_deref(),
+            Some("developer__shell: Execution failed: exit status 2")
+        );
+
+        // A later successful call means the agent recovered
+        messages.push(request("2"));
+        messages.push(Message::user().with_tool_response("2", Ok(vec![Content::text("ok")])));
+        messages.push(Message::assistant().with_text("Fixed the build"));
+        assert_eq!(last_tool_failure(&messages), None);
+        assert_eq!(last_tool_failure(&[]), None);
+
+        assert_eq!(RunOutcome::Completed.exit_code(), 0);
+        assert_eq!(
+            RunOutcome::TimedOut(Duration::from_secs(5)).exit_code(),
+            EXIT_TIMEOUT
+        );
+        let paused = RunOutcome::Paused("interrupted".to_string());
+        assert_eq!(paused.exit_code(), EXIT_PAUSED);
+        assert_eq!(paused.describe(), None);
+    }
+}
@@ -1,8 +1,9 @@
-use crate::session::message_to_markdown;
+use crate::session::{build_session, message_to_markdown, SessionBuilderConfig};
 use anyhow::{Context, Result};
 use cliclack::{confirm, multiselect, select};
 use goose::session::info::{get_session_info, SessionInfo, SortOrder};
-use goose::session::{self, Identifier};
+use goose::session::{self, Identifier, MigrationOutcome};
+use goose_mcp::edit_journal;
 use regex::Regex;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -118,15 +119,44 @@ pub fn handle_session_remove(id: Option<String>, regex_string: Option<String>) -
     remove_sessions(matched_sessions)
 }
 
-pub fn handle_session_list(verbose: bool, format: String, ascending: bool) -> Result<()> {
+/// Whether a session has every tag in `tags` and, with `search`, mentions it in its
+/// description or notes
+fn session_matches(session: &SessionInfo, tags: &[String], search: Option<&str>) -> bool {
+    let metadata = &session.metadata;
+    if !tags.iter().all(|tag| metadata.has_tag(tag)) {
+        return false;
+    }
+    match search {
+        Some(search) => {
+            let search = search.to_lowercase();
+            metadata.description.to_lowercase().contains(&search)
+                || metadata
+                    .notes
+                    .iter()
+                    .any(|note| note.to_lowercase().contains(&search))
+        }
+        None => true,
+    }
+}
+
+pub fn handle_session_list(
+    verbose: bool,
+    format: String,
+    ascending: bool,
+    tags: Vec<String>,
+    search: Option<String>,
+) -> Result<()> {
     let sort_order = if ascending {
         SortOrder::Ascending
     } else {
         SortOrder::Descending
     };
 
-    let sessions = match get_session_info(sort_order) {
-        Ok(sessions) => sessions,
+    let sessions: Vec<SessionInfo> = match get_session_info(sort_order) {
+        Ok(sessions) => sessions
+            .into_iter()
+            .filter(|session| session_matches(session, &tags, search.as_deref()))
+            .collect(),
         Err(e) => {
             tracing::error!("Failed to list sessions: {:?}", e);
             return Err(anyhow::anyhow!("Failed to list sessions"));
@@ -155,10 +185,21 @@ pub fn handle_session_list(verbose: bool, format: String, ascending: bool) -> Re
                     } else {
                         &metadata.description
                     };
-                    let output = format!("{} - {} - {}", id, description, modified);
+                    let mut output = format!("{} - {} - {}", id, description, modified);
+                    if !metadata.tags.is_empty() {
+                        let tags: Vec<String> = metadata
+                            .tags
+                            .iter()
+                            .map(|tag| format!("#{}", tag))
+                            .collect();
+                        output.push_str(&format!(" [{}]", tags.join(" ")));
+                    }
                     if verbose {
                         println!("  {}", output);
                         println!("    Path: {}", path);
+                        for note in &metadata.notes {
+                            println!("    Note: {}", note);
+                        }
                     } else {
                         println!("{}", output);
                     }
@@ -169,6 +210,179 @@ pub fn handle_session_list(verbose: bool, format: String, ascending: bool) -> Re
     Ok(())
 }
 
+/// Rewrite every session written in an older message format in the current one
+pub fn handle_session_migrate(dry_run: bool) -> Result<()> {
+    let mut sessions = session::list_sessions()?;
+    sessions.sort();
+
+    let (mut migrated, mut failed) = (0, 0);
+    for (id, path) in sessions {
+        match session::migrate_session_file(&path, dry_run) {
+            Ok(MigrationOutcome::UpToDate) => {}
+            Ok(MigrationOutcome::Pending { from }) => {
+                println!("{} - would migrate from format v{}", id, from);
+                migrated += 1;
+            }
+            Ok(MigrationOutcome::Migrated { from, backup }) => {
+                println!(
+                    "{} - migrated from format v{} (original kept at {})",
+                    id,
+                    from,
+                    backup.display()
+                );
+                migrated += 1;
+            }
+            Err(e) => {
+                eprintln!("{} - failed to migrate: {}", id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if migrated == 0 && failed == 0 {
+        println!(
+            "All sessions are up to date (format v{})",
+            session::CURRENT_SCHEMA_VERSION
+        );
+    } else if dry_run {
+        println!("{} session(s) would be migrated", migrated);
+    } else {
+        println!("{} session(s) migrated", migrated);
+    }
+    if failed > 0 {
+        return Err(anyhow::anyhow!(
+            "{} session(s) could not be migrated",
+            failed
+        ));
+    }
+    Ok(())
+}
+
+/// Restore every file the developer extension edited during a run, prompting for the run
+/// when none is given
+pub fn handle_session_rollback(run_id: Option<String>, force: bool) -> Result<()> {
+    let root = edit_journal::journal_root();
+    let runs = edit_journal::list_runs(&root)?;
+
+    let run = match run_id {
+        Some(run_id) => runs
+            .into_iter()
+            .find(|run| run.info.run_id == run_id)
+            .with_context(|| format!("No recorded edits for run '{}'", run_id))?,
+        None => {
+            let mut runs: Vec<_> = runs
+                .into_iter()
+                .filter(|run| run.info.rolled_back_at.is_none())
+                .collect();
+            if runs.is_empty() {
+                println!("No runs with edits to roll back");
+                return Ok(());
+            }
+            let mut selector = select("Select a run to roll back:");
+            for (i, run) in runs.iter().enumerate() {
+                selector = selector.item(
+                    i,
+                    format!(
+                        "{} - {} file(s) in {}",
+                        run.info.run_id,
+                        run.files.len(),
+                        run.info.working_dir.display()
+                    ),
+                    "",
+                );
+            }
+            let selected: usize = selector.interact()?;
+            runs.swap_remove(selected)
+        }
+    };
+
+    println!("Files edited in run {}:", run.info.run_id);
+    for file in &run.files {
+        println!("  {}", file.display());
+    }
+    let should_rollback = confirm(format!(
+        "Restore these {} file(s) to how they were before the run?",
+        run.files.len()
+    ))
+    .initial_value(false)
+    .interact()?;
+    if !should_rollback {
+        println!("Rollback cancelled");
+        return Ok(());
+    }
+
+    let report = edit_journal::rollback(&root, &run.info.run_id, force)?;
+    for path in &report.restored {
+        println!("Restored {}", path.display());
+    }
+    for path in &report.removed {
+        println!("Removed {}", path.display());
+    }
+    if !report.conflicts.is_empty() {
+        println!("Changed since the run, left as they are:");
+        for path in &report.conflicts {
+            println!("  {}", path.display());
+        }
+        println!("Run again with --force to restore them too");
+    }
+    Ok(())
+}
+
+/// Ask the headless run working on a session to checkpoint and stop
+pub fn handle_session_pause(run_id: &str) -> Result<()> {
+    let session_file = session::get_path(Identifier::Name(run_id.to_string()));
+    session::checkpoint::request_pause(&session_file)
+        .with_context(|| format!("No session for run '{}'", run_id))?;
+    println!(
+        "Asked run {} to pause after its current step, continue it with `goose session --resume --from-run {}`",
+        run_id, run_id
+    );
+    Ok(())
+}
+
+/// The session of a headless run that was paused and can be continued interactively. Moves
+/// into the directory the run was working in, which for isolated runs is the kept workspace,
+/// so the extensions started for the session pick up from there.
+pub fn paused_run(run_id: &str) -> Result<Identifier> {
+    let identifier = Identifier::Name(run_id.to_string());
+    let checkpoint = session::checkpoint::read_checkpoint(&session::get_path(identifier.clone()))?;
+    match checkpoint {
+        Some(checkpoint) if checkpoint.paused_from == session::HandoffMode::Headless => {
+            std::env::set_current_dir(&checkpoint.working_dir).with_context(|| {
+                format!(
+                    "Run '{}' was working in {}, which no longer exists",
+                    run_id,
+                    checkpoint.working_dir.display()
+                )
+            })?;
+            Ok(identifier)
+        }
+        _ => Err(anyhow::anyhow!(
+            "Run '{}' is not paused, pause it with Ctrl+C or `goose session pause {}` first",
+            run_id,
+            run_id
+        )),
+    }
+}
+
+/// Show what fills the context window of a session, without picking up the session itself
+pub async fn handle_session_context(identifier: Option<Identifier>) -> Result<()> {
+    let session_file = match identifier {
+        Some(identifier) => session::get_path(identifier),
+        None => session::get_most_recent_session().context("No sessions found")?,
+    };
+    let messages = session::read_messages(&session_file)
+        .with_context(|| format!("Failed to read session {}", session_file.display()))?;
+
+    // A throwaway session for the agent, so the real one and its checkpoints are left alone
+    let session = build_session(SessionBuilderConfig {
+        no_session: true,
+        ..Default::default()
+    })
+    .await;
+    session.display_context_breakdown(&messages).await
+}
+
 /// Export a session to Markdown without creating a full Session object
 ///
 /// This function directly reads messages from the session file and converts them to Markdown
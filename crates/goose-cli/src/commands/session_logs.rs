@@ -0,0 +1,271 @@
+use crate::logging::log_base_directory;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use console::style;
+use goose::session::{self, Identifier};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::Level;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// One line of a structured log file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub timestamp: Option<DateTime<Local>>,
+    pub level: Level,
+    /// The cli, the agent, or the extension that wrote the line
+    pub source: String,
+    pub message: String,
+}
+
+/// Parse a JSON log line, None for lines that aren't log entries
+pub fn parse_log_line(line: &str, file_source: &str) -> Option<LogEntry> {
+    let json: Value = serde_json::from_str(line).ok()?;
+    let level: Level = json.get("level")?.as_str()?.parse().ok()?;
+    let timestamp = json
+        .get("timestamp")
+        .and_then(Value::as_str)
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|ts| ts.with_timezone(&Local));
+    let target = json.get("target").and_then(Value::as_str).unwrap_or("");
+
+    // The CLI log also holds the agent's entries, tell them apart by module
+    let source = if file_source == "cli" && target.starts_with("goose::") {
+        "agent".to_string()
+    } else {
+        file_source.to_string()
+    };
+
+    let mut message = String::new();
+    if let Some(fields) = json.get("fields").and_then(Value::as_object) {
+        if let Some(text) = fields.get("message").and_then(Value::as_str) {
+            message.push_str(text);
+        }
+        for (key, value) in fields.iter().filter(|(key, _)| *key != "message") {
+            let value = value
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| value.to_string());
+            message.push_str(&format!(" {}={}", key, value));
+        }
+    }
+
+    Some(LogEntry {
+        timestamp,
+        level,
+        source,
+        message: message.trim().to_string(),
+    })
+}
+
+/// What wrote a log file of the session, None for the logs of other sessions. Files are
+/// named `<timestamp>-<session>.log` by the CLI and `<timestamp>-mcp-<extension>@<session>.log`
+/// by the extensions it started.
+pub fn log_source(file_name: &str, session: &str) -> Option<String> {
+    let name = file_name.strip_suffix(".log")?;
+    let (_, name) = name.split_once('-')?;
+    if name == session {
+        return Some("cli".to_string());
+    }
+    let (extension, tagged) = name.strip_prefix("mcp-")?.rsplit_once('@')?;
+    (tagged == session).then(|| extension.to_string())
+}
+
+/// The log files of the session across all days, with what wrote them
+fn session_log_files(base: &Path, session: &str) -> Vec<(String, PathBuf)> {
+    let Ok(days) = std::fs::read_dir(base) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(String, PathBuf)> = days
+        .filter_map(|day| day.ok())
+        .filter_map(|day| std::fs::read_dir(day.path()).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let source = log_source(entry.file_name().to_str()?, session)?;
+            Some((source, entry.path()))
+        })
+        .collect();
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+    files
+}
+
+/// A log file read incrementally, keeping an incomplete last line for the next read
+struct TailedFile {
+    source: String,
+    path: PathBuf,
+    offset: u64,
+    partial: String,
+}
+
+impl TailedFile {
+    fn read_new(&mut self) -> Vec<LogEntry> {
+        let Ok(mut file) = File::open(&self.path) else {
+            return Vec::new();
+        };
+        if file.seek(SeekFrom::Start(self.offset)).is_err() {
+            return Vec::new();
+        }
+        let mut bytes = Vec::new();
+        let Ok(read) = file.read_to_end(&mut bytes) else {
+            return Vec::new();
+        };
+        self.offset += read as u64;
+        self.partial.push_str(&String::from_utf8_lossy(&bytes));
+
+        let Some(end) = self.partial.rfind('\n') else {
+            return Vec::new();
+        };
+        let complete: String = self.partial.drain(..=end).collect();
+        complete
+            .lines()
+            .filter_map(|line| parse_log_line(line, &self.source))
+            .collect()
+    }
+}
+
+fn format_entry(entry: &LogEntry) -> String {
+    let time = entry
+        .timestamp
+        .map(|ts| ts.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+        .unwrap_or_default();
+    let level = format!("{:<5}", entry.level);
+    let level = match entry.level {
+        Level::ERROR => style(level).red().bold(),
+        Level::WARN => style(level).yellow(),
+        Level::INFO => style(level).green(),
+        Level::DEBUG => style(level).blue(),
+        Level::TRACE => style(level).dim(),
+    };
+    format!(
+        "{} {} {} {}",
+        style(time).dim(),
+        level,
+        style(format!("[{}]", entry.source)).cyan(),
+        entry.message
+    )
+}
+
+fn print_entries(mut entries: Vec<LogEntry>, level: Level) {
+    // Stable, so lines without a timestamp keep their place in their file
+    entries.sort_by_key(|entry| entry.timestamp);
+    for entry in entries.iter().filter(|entry| entry.level <= level) {
+        println!("{}", format_entry(entry));
+    }
+}
+
+/// Print the CLI, agent and extension logs of a session merged by time, and with `follow`
+/// keep printing new lines as they are written
+pub async fn handle_session_logs(
+    identifier: Option<Identifier>,
+    follow: bool,
+    level: &str,
+) -> Result<()> {
+    let level: Level = level.parse().map_err(|_| {
+        anyhow!(
+            "Unknown level '{}', use error, warn, info, debug or trace",
+            level
+        )
+    })?;
+    let session_file = match identifier {
+        Some(identifier) => session::get_path(identifier),
+        None => session::get_most_recent_session()?,
+    };
+    let session = session_file
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| anyhow!("Invalid session path {}", session_file.display()))?
+        .to_string();
+    let base = log_base_directory()?;
+
+    let mut files: Vec<TailedFile> = Vec::new();
+    loop {
+        // Pick up the logs of extensions started since the last look
+        for (source, path) in session_log_files(&base, &session) {
+            if !files.iter().any(|file| file.path == path) {
+                files.push(TailedFile {
+                    source,
+                    path,
+                    offset: 0,
+                    partial: String::new(),
+                });
+            }
+        }
+        if files.is_empty() && !follow {
+            return Err(anyhow!(
+                "No logs found for session '{}' in {}",
+                session,
+                base.display()
+            ));
+        }
+
+        let entries: Vec<LogEntry> = files.iter_mut().flat_map(|file| file.read_new()).collect();
+        print_entries(entries, level);
+
+        if !follow {
+            return Ok(());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_log_source() {
+        assert_eq!(
+            log_source("20250101_120000-my-session.log", "my-session"),
+            Some("cli".to_string())
+        );
+        assert_eq!(
+            log_source("20250101_120000-mcp-developer@my-session.log", "my-session"),
+            Some("developer".to_string())
+        );
+        assert_eq!(
+            log_source("20250101_120000-mcp-developer.log", "my-session"),
+            None
+        );
+        assert_eq!(log_source("20250101_120000-other.log", "my-session"), None);
+    }
+
+    #[test]
+    fn test_parse_and_tail() {
+        let agent_line = r#"{"timestamp":"2025-01-01T12:00:01.000Z","level":"DEBUG","fields":{"message":"calling tool","tool":"shell"},"target":"goose::agents::agent"}"#;
+        let entry = parse_log_line(agent_line, "cli").unwrap();
+        assert_eq!(entry.source, "agent");
+        assert_eq!(entry.level, Level::DEBUG);
+        assert_eq!(entry.message, "calling tool tool=shell");
+        assert!(parse_log_line("not json", "cli").is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("20250101_120000-mcp-developer@s.log");
+        let mut file = File::create(&path).unwrap();
+        let mut tailed = TailedFile {
+            source: "developer".to_string(),
+            path: path.clone(),
+            offset: 0,
+            partial: String::new(),
+        };
+
+        write!(
+            file,
+            r#"{{"timestamp":"2025-01-01T12:00:00.000Z","level":"INFO","fields":{{"message":"started"}},"target":"mcp_server"}}"#
+        )
+        .unwrap();
+        // Nothing until the line is complete
+        assert!(tailed.read_new().is_empty());
+        writeln!(file).unwrap();
+        let entries = tailed.read_new();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].source, "developer");
+        assert_eq!(entries[0].message, "started");
+        assert!(tailed.read_new().is_empty());
+    }
+}
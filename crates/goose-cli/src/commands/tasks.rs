@@ -0,0 +1,480 @@
+use anyhow::{bail, Context, Result};
+use console::style;
+use futures::stream::{self, StreamExt};
+use goose::config::Config;
+use goose::session::{self, Identifier};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+use crate::commands::run::{EXIT_AGENT_GAVE_UP, EXIT_PAUSED, EXIT_TIMEOUT, EXIT_TOOL_FAILED};
+use crate::log_usage::{get_model_pricing, lookup_pricing};
+use crate::logging::log_base_directory;
+
+/// Tasks run at once when `--concurrency` isn't given
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// A task as written in the tasks file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TaskSpec {
+    name: Option<String>,
+    prompt: String,
+    /// Directory to run in, relative to the tasks file
+    dir: Option<PathBuf>,
+    /// Run the prompt once in each of these directories
+    #[serde(default)]
+    dirs: Vec<PathBuf>,
+}
+
+/// Either a list of tasks or a `tasks` key holding one
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TasksFile {
+    List(Vec<TaskSpec>),
+    Tasks { tasks: Vec<TaskSpec> },
+}
+
+/// One headless session to run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    /// Unique within the file, names the task's session and log
+    pub name: String,
+    pub prompt: String,
+    pub dir: PathBuf,
+}
+
+/// Options of `goose run` passed on to every task
+#[derive(Debug, Clone, Default)]
+pub struct TaskOptions {
+    pub extensions: Vec<String>,
+    pub remote_extensions: Vec<String>,
+    pub builtins: Vec<String>,
+    pub max_tool_repetitions: Option<u32>,
+    pub no_project_history: bool,
+    pub isolated: bool,
+    pub timeout: Option<u64>,
+    pub retries: Option<u32>,
+}
+
+impl TaskOptions {
+    fn args(&self) -> Vec<String> {
+        let mut args = vec!["--plain".to_string()];
+        for extension in &self.extensions {
+            args.extend(["--with-extension".to_string(), extension.clone()]);
+        }
+        for extension in &self.remote_extensions {
+            args.extend(["--with-remote-extension".to_string(), extension.clone()]);
+        }
+        if !self.builtins.is_empty() {
+            args.extend(["--with-builtin".to_string(), self.builtins.join(",")]);
+        }
+        if let Some(max) = self.max_tool_repetitions {
+            args.extend(["--max-tool-repetitions".to_string(), max.to_string()]);
+        }
+        if self.no_project_history {
+            args.push("--no-project-history".to_string());
+        }
+        if self.isolated {
+            args.push("--isolated".to_string());
+        }
+        if let Some(timeout) = self.timeout {
+            args.extend(["--timeout".to_string(), timeout.to_string()]);
+        }
+        if let Some(retries) = self.retries {
+            args.extend(["--retries".to_string(), retries.to_string()]);
+        }
+        args
+    }
+}
+
+/// Letters, digits, `-` and `_`, so the name can be used for files
+fn sanitize_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    name.trim_matches('-').to_string()
+}
+
+/// The tasks in `contents`, with directories resolved against `base`
+fn parse_tasks(contents: &str, base: &Path) -> Result<Vec<Task>> {
+    let specs = match serde_yaml::from_str(contents)? {
+        TasksFile::List(specs) | TasksFile::Tasks { tasks: specs } => specs,
+    };
+
+    let mut tasks = Vec::new();
+    let mut names = HashSet::new();
+    for (index, spec) in specs.into_iter().enumerate() {
+        if spec.prompt.trim().is_empty() {
+            bail!("Task {} has an empty prompt", index + 1);
+        }
+        if spec.dir.is_some() && !spec.dirs.is_empty() {
+            bail!("Task {} sets both 'dir' and 'dirs'", index + 1);
+        }
+        let expand = !spec.dirs.is_empty();
+        let has_dir = expand || spec.dir.is_some();
+        let dirs = match (spec.dir, spec.dirs) {
+            (Some(dir), _) => vec![dir],
+            (None, dirs) if !dirs.is_empty() => dirs,
+            (None, _) => vec![base.to_path_buf()],
+        };
+
+        for dir in dirs {
+            let dir = base.join(dir);
+            if !dir.is_dir() {
+                bail!("Task {}: {} isn't a directory", index + 1, dir.display());
+            }
+            let dir_name = dir.canonicalize().ok().and_then(|dir| {
+                dir.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+            });
+            let name = match (&spec.name, dir_name) {
+                (Some(name), Some(dir_name)) if expand => format!("{}-{}", name, dir_name),
+                (Some(name), _) => name.clone(),
+                (None, Some(dir_name)) if has_dir => dir_name,
+                (None, _) => format!("task-{}", index + 1),
+            };
+            let mut name = match sanitize_name(&name) {
+                name if name.is_empty() => format!("task-{}", index + 1),
+                name => name,
+            };
+            // Tasks in same-named directories still get their own session
+            let base_name = name.clone();
+            let mut suffix = 2;
+            while !names.insert(name.clone()) {
+                name = format!("{}-{}", base_name, suffix);
+                suffix += 1;
+            }
+            tasks.push(Task {
+                name,
+                prompt: spec.prompt.clone(),
+                dir,
+            });
+        }
+    }
+    if tasks.is_empty() {
+        bail!("The tasks file has no tasks");
+    }
+    Ok(tasks)
+}
+
+pub fn load_tasks(path: &Path) -> Result<Vec<Task>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let base = path
+        .canonicalize()?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    parse_tasks(&contents, &base).with_context(|| format!("Invalid tasks file {}", path.display()))
+}
+
+/// How a task's `goose run` exited, see [`crate::commands::run::RunOutcome`]
+fn describe_exit(code: Option<i32>) -> String {
+    match code {
+        Some(0) => "done".to_string(),
+        Some(EXIT_TOOL_FAILED) => "tool failed".to_string(),
+        Some(EXIT_AGENT_GAVE_UP) => "gave up".to_string(),
+        Some(EXIT_TIMEOUT) => "timed out".to_string(),
+        Some(EXIT_PAUSED) => "interrupted".to_string(),
+        Some(code) => format!("failed ({})", code),
+        None => "killed".to_string(),
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+struct TaskResult {
+    task: Task,
+    /// None when the task couldn't be started or was killed by a signal
+    exit_code: Option<i32>,
+    error: Option<String>,
+    duration: Duration,
+    log: PathBuf,
+    tokens: Option<i32>,
+    cost: Option<f64>,
+}
+
+impl TaskResult {
+    fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    fn status(&self) -> String {
+        match &self.error {
+            Some(error) => format!("failed to start: {}", error),
+            None => describe_exit(self.exit_code),
+        }
+    }
+}
+
+/// Tokens and estimated cost recorded in a task's session, costs of models the provider didn't
+/// price come from `GOOSE_MODEL_PRICING`
+fn session_usage(session_name: &str) -> (Option<i32>, Option<f64>) {
+    let path = session::get_path(Identifier::Name(session_name.to_string()));
+    let Ok(metadata) = session::read_metadata(&path) else {
+        return (None, None);
+    };
+    let provider = Config::global()
+        .get_param::<String>("GOOSE_PROVIDER")
+        .unwrap_or_default();
+    let pricing = get_model_pricing();
+    let costs: Vec<f64> = metadata
+        .message_usage
+        .iter()
+        .filter_map(|usage| {
+            usage.cost_usd.or_else(|| {
+                lookup_pricing(&pricing, &provider, &usage.model).map(|price| {
+                    (usage.input_tokens.unwrap_or(0).max(0) as f64 * price.input
+                        + usage.output_tokens.unwrap_or(0).max(0) as f64 * price.output)
+                        / 1_000_000.0
+                })
+            })
+        })
+        .collect();
+    let cost = (!costs.is_empty()).then(|| costs.iter().sum());
+    (metadata.accumulated_total_tokens, cost)
+}
+
+async fn run_task(task: Task, session_name: String, args: &[String], log_dir: &Path) -> TaskResult {
+    let log = log_dir.join(format!("{}.log", task.name));
+    let started = Instant::now();
+    let status = async {
+        let exe = std::env::current_exe()?;
+        let log_file = File::create(&log)?;
+        Command::new(exe)
+            .arg("run")
+            .arg("--text")
+            .arg(&task.prompt)
+            .arg("--name")
+            .arg(&session_name)
+            .args(args)
+            .current_dir(&task.dir)
+            .stdin(Stdio::null())
+            .stdout(log_file.try_clone()?)
+            .stderr(log_file)
+            .kill_on_drop(true)
+            .status()
+            .await
+    }
+    .await;
+
+    let (tokens, cost) = session_usage(&session_name);
+    let (exit_code, error) = match status {
+        Ok(status) => (status.code(), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    TaskResult {
+        task,
+        exit_code,
+        error,
+        duration: started.elapsed(),
+        log,
+        tokens,
+        cost,
+    }
+}
+
+fn print_summary(results: &[TaskResult]) {
+    let name_width = results
+        .iter()
+        .map(|result| result.task.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("Task".len());
+    println!(
+        "\n{}",
+        style(format!(
+            "{:<name_width$}  {:<20} {:>8} {:>10} {:>9}  Log",
+            "Task", "Status", "Time", "Tokens", "Cost"
+        ))
+        .bold()
+    );
+    for result in results {
+        let status = format!("{:<20}", result.status());
+        let status = if result.succeeded() {
+            style(status).green()
+        } else {
+            style(status).red()
+        };
+        println!(
+            "{:<name_width$}  {} {:>8} {:>10} {:>9}  {}",
+            result.task.name,
+            status,
+            format_duration(result.duration),
+            result
+                .tokens
+                .map_or_else(|| "-".to_string(), |tokens| tokens.to_string()),
+            result
+                .cost
+                .map_or_else(|| "-".to_string(), |cost| format!("${:.2}", cost)),
+            result.log.display()
+        );
+    }
+
+    let succeeded = results.iter().filter(|result| result.succeeded()).count();
+    let tokens: i64 = results
+        .iter()
+        .filter_map(|result| result.tokens)
+        .map(i64::from)
+        .sum();
+    let costs: Vec<f64> = results.iter().filter_map(|result| result.cost).collect();
+    let cost = if costs.is_empty() {
+        String::new()
+    } else {
+        format!(", ${:.2}", costs.iter().sum::<f64>())
+    };
+    println!(
+        "\n{} of {} task(s) succeeded, {} tokens{}",
+        succeeded,
+        results.len(),
+        tokens,
+        cost
+    );
+}
+
+/// Run every task in the file at `path` as its own headless `goose run`, `concurrency` at a
+/// time. Each task's output goes to its own log, and a table of results and costs is printed
+/// once all of them finish.
+pub async fn handle_run_tasks(
+    path: &Path,
+    concurrency: Option<usize>,
+    options: TaskOptions,
+) -> Result<()> {
+    let tasks = load_tasks(path)?;
+    let concurrency = concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1);
+    let run_id = format!("tasks-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let log_dir = log_base_directory()?.join("tasks").join(&run_id);
+    fs::create_dir_all(&log_dir)
+        .with_context(|| format!("Failed to create {}", log_dir.display()))?;
+
+    let total = tasks.len();
+    println!(
+        "{}",
+        style(format!(
+            "Running {} task(s), {} at a time, logs in {}",
+            total,
+            concurrency.min(total),
+            log_dir.display()
+        ))
+        .cyan()
+    );
+
+    let order: Vec<String> = tasks.iter().map(|task| task.name.clone()).collect();
+    let args = options.args();
+    let mut running = stream::iter(tasks)
+        .map(|task| {
+            println!("{} {}", style("started").dim(), task.name);
+            let session_name = format!("{}-{}", run_id, task.name);
+            run_task(task, session_name, &args, &log_dir)
+        })
+        .buffer_unordered(concurrency);
+
+    let mut results = Vec::new();
+    while let Some(result) = running.next().await {
+        let mark = if result.succeeded() {
+            style("✓").green()
+        } else {
+            style("✗").red()
+        };
+        println!(
+            "[{}/{}] {} {} {} ({})",
+            results.len() + 1,
+            total,
+            mark,
+            result.task.name,
+            result.status(),
+            format_duration(result.duration)
+        );
+        results.push(result);
+    }
+
+    results.sort_by_key(|result| order.iter().position(|name| *name == result.task.name));
+    print_summary(&results);
+
+    let failed = results.iter().filter(|result| !result.succeeded()).count();
+    if failed > 0 {
+        bail!("{} of {} task(s) failed", failed, total);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tasks() {
+        let base = tempfile::tempdir().unwrap();
+        for repo in ["api", "web"] {
+            fs::create_dir_all(base.path().join("repos").join(repo)).unwrap();
+        }
+
+        let tasks = parse_tasks(
+            "tasks:\n\
+             - prompt: Update the README\n\
+             \x20 name: readme\n\
+             \x20 dirs: [repos/api, repos/web]\n\
+             - prompt: Bump the version\n\
+             \x20 dir: repos/api\n\
+             - prompt: Tidy up\n\
+             \x20 name: readme api\n",
+            base.path(),
+        )
+        .unwrap();
+        let names: Vec<_> = tasks.iter().map(|task| task.name.as_str()).collect();
+        assert_eq!(names, ["readme-api", "readme-web", "api", "readme-api-2"]);
+        assert_eq!(tasks[1].dir, base.path().join("repos/web"));
+        assert_eq!(tasks[3].dir, base.path());
+
+        // JSON is read the same way
+        let tasks = parse_tasks(r#"[{"prompt": "Say hi"}]"#, base.path()).unwrap();
+        assert_eq!(tasks[0].name, "task-1");
+
+        assert!(parse_tasks("[]", base.path()).is_err());
+        assert!(parse_tasks(r#"[{"prompt": "x", "dir": "missing"}]"#, base.path()).is_err());
+        assert!(parse_tasks(r#"[{"prompt": "x", "promt": "y"}]"#, base.path()).is_err());
+    }
+
+    #[test]
+    fn test_task_options_args() {
+        let options = TaskOptions {
+            builtins: vec!["developer".to_string(), "memory".to_string()],
+            timeout: Some(600),
+            isolated: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            options.args(),
+            [
+                "--plain",
+                "--with-builtin",
+                "developer,memory",
+                "--isolated",
+                "--timeout",
+                "600"
+            ]
+        );
+        assert_eq!(describe_exit(Some(EXIT_TIMEOUT)), "timed out");
+        assert_eq!(describe_exit(Some(1)), "failed (1)");
+    }
+}
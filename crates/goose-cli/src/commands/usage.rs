@@ -0,0 +1,113 @@
+use anyhow::{Context, Result};
+use chrono::{Datelike, Local, NaiveDate};
+use console::style;
+
+use crate::log_usage::{
+    get_model_pricing, get_monthly_budget, lookup_pricing, read_usage_records, MonthlyUsage,
+    MODEL_PRICING_KEY,
+};
+
+fn format_cost(cost: Option<f64>) -> String {
+    cost.map(|c| format!("${:.2}", c))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Parse a `YYYY-MM` month, defaulting to the current month
+fn parse_month(month: Option<String>) -> Result<(i32, u32)> {
+    match month {
+        Some(month) => {
+            let date = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+                .with_context(|| format!("Invalid month '{}', expected YYYY-MM", month))?;
+            Ok((date.year(), date.month()))
+        }
+        None => {
+            let now = Local::now();
+            Ok((now.year(), now.month()))
+        }
+    }
+}
+
+pub fn handle_usage(month: Option<String>, daily: bool) -> Result<()> {
+    let (year, month) = parse_month(month)?;
+    let usage = MonthlyUsage::from_records(&read_usage_records()?, year, month);
+    let pricing = get_model_pricing();
+
+    println!(
+        "{}",
+        style(format!("Usage for {}-{:02}:", year, month))
+            .cyan()
+            .bold()
+    );
+
+    if usage.by_model.is_empty() {
+        println!("  No usage recorded");
+        return Ok(());
+    }
+
+    println!(
+        "  {:<40} {:>12} {:>12} {:>12} {:>10}",
+        "Provider/Model", "Input", "Output", "Total", "Cost"
+    );
+    for ((provider, model), totals) in &usage.by_model {
+        let cost = totals.estimated_cost(lookup_pricing(&pricing, provider, model));
+        println!(
+            "  {:<40} {:>12} {:>12} {:>12} {:>10}",
+            format!("{}/{}", provider, model),
+            totals.input_tokens,
+            totals.output_tokens,
+            totals.total_tokens,
+            format_cost(cost)
+        );
+    }
+
+    if daily {
+        println!("\n{}", style("Daily usage:").cyan().bold());
+        for ((day, provider, model), totals) in &usage.by_day {
+            let cost = totals.estimated_cost(lookup_pricing(&pricing, provider, model));
+            println!(
+                "  {} {:<40} {:>12} {:>10}",
+                day,
+                format!("{}/{}", provider, model),
+                totals.total_tokens,
+                format_cost(cost)
+            );
+        }
+    }
+
+    let spent = usage.estimated_cost(&pricing);
+    println!();
+    match get_monthly_budget() {
+        Some(budget) => {
+            let line = format!("Estimated spend: ${:.2} of ${:.2} budget", spent, budget);
+            if spent > budget {
+                println!("{}", style(line).red().bold());
+            } else {
+                println!("{}", line);
+            }
+        }
+        None => println!("Estimated spend: ${:.2}", spent),
+    }
+    let saved = usage.savings();
+    if saved > 0.0 {
+        println!(
+            "{}",
+            style(format!(
+                "Tiered model routing saved an estimated ${:.2}",
+                saved
+            ))
+            .green()
+        );
+    }
+    if pricing.is_empty() {
+        println!(
+            "{}",
+            style(format!(
+                "Set {} in your config to estimate spend per model",
+                MODEL_PRICING_KEY
+            ))
+            .dim()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use anyhow::{Context, Result};
+use console::style;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::session::Session;
+
+/// How long files have to stay unchanged before a run starts, editors and formatters often
+/// write several times in a row
+const DEBOUNCE: Duration = Duration::from_millis(750);
+/// Directories that are never watched, build output changes on every build
+const SKIPPED_DIRS: &[&str] = &[".git", "target", "node_modules"];
+
+/// Globs matched against paths relative to the directory being watched. `**` matches any
+/// number of directories, `*` and `?` match within one, `{a,b}` matches either.
+pub struct WatchPatterns {
+    globs: GlobSet,
+}
+
+impl WatchPatterns {
+    pub fn new(globs: &[String]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for glob in globs {
+            builder.add(
+                GlobBuilder::new(glob.trim_start_matches("./"))
+                    .literal_separator(true)
+                    .build()
+                    .with_context(|| format!("Invalid watch pattern '{}'", glob))?,
+            );
+        }
+        Ok(Self {
+            globs: builder.build()?,
+        })
+    }
+
+    pub fn matches(&self, relative: &Path) -> bool {
+        self.globs.is_match(relative)
+    }
+}
+
+fn is_skipped(relative: &Path) -> bool {
+    relative.components().any(|component| {
+        matches!(component, Component::Normal(name) if SKIPPED_DIRS.contains(&name.to_string_lossy().as_ref()))
+    })
+}
+
+/// How many files under `root` match, for the startup message
+fn count_matching(root: &Path, patterns: &WatchPatterns) -> usize {
+    ignore::WalkBuilder::new(root)
+        .standard_filters(false)
+        .filter_entry(|entry| !SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()))
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter(|entry| {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            patterns.matches(relative)
+        })
+        .count()
+}
+
+/// Add the matching files an event created, changed or removed to `changed`. Reads show up
+/// as access events and are left out, the agent reads files all the time.
+fn collect_changes(
+    root: &Path,
+    patterns: &WatchPatterns,
+    event: notify::Result<Event>,
+    changed: &mut BTreeSet<PathBuf>,
+) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            tracing::warn!("File watcher error: {}", e);
+            return;
+        }
+    };
+    if !matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        return;
+    }
+    for path in event.paths {
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        if !is_skipped(relative) && patterns.matches(relative) {
+            changed.insert(relative.to_path_buf());
+        }
+    }
+}
+
+/// Run `prompt` headless in `session` each time files matching `globs` change, until Ctrl-C.
+/// Changes made while a run is in progress, including the run's own edits, don't start
+/// another one.
+pub async fn watch_and_run(session: &mut Session, globs: &[String], prompt: String) -> Result<()> {
+    let patterns = WatchPatterns::new(globs)?;
+    // Watchers report resolved paths on some platforms
+    let root = std::env::current_dir()?.canonicalize()?;
+
+    let (tx, mut events) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .context("Failed to start the file watcher")?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch {}", root.display()))?;
+
+    println!(
+        "{}",
+        style(format!(
+            "Watching {} ({} files), press Ctrl-C to stop",
+            globs.join(", "),
+            count_matching(&root, &patterns)
+        ))
+        .dim()
+    );
+
+    loop {
+        let event = tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            event = events.recv() => event,
+        };
+        let Some(event) = event else {
+            break;
+        };
+        let mut changed = BTreeSet::new();
+        collect_changes(&root, &patterns, event, &mut changed);
+        if changed.is_empty() {
+            continue;
+        }
+
+        // Wait for the files to settle
+        while let Ok(Some(event)) = tokio::time::timeout(DEBOUNCE, events.recv()).await {
+            collect_changes(&root, &patterns, event, &mut changed);
+        }
+
+        let names: Vec<String> = changed.iter().map(|p| p.display().to_string()).collect();
+        println!(
+            "\n{} {}",
+            style("Changed:").cyan().bold(),
+            style(names.join(", ")).dim()
+        );
+        let message = format!(
+            "{}\n\nFiles that just changed: {}",
+            prompt,
+            names.join(", ")
+        );
+        if let Err(e) = session.headless(message).await {
+            eprintln!("{}: {}", style("Run failed").red().bold(), e);
+        }
+
+        // Drop what changed during the run
+        while events.try_recv().is_ok() {}
+        println!("{}", style("Watching for changes...").dim());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, ModifyKind, RemoveKind};
+    use std::fs;
+
+    #[test]
+    fn test_watch_patterns() {
+        let patterns =
+            WatchPatterns::new(&["src/**/*.rs".to_string(), "*.{toml,md}".to_string()]).unwrap();
+        assert!(patterns.matches(Path::new("src/main.rs")));
+        assert!(patterns.matches(Path::new("src/commands/watch.rs")));
+        assert!(patterns.matches(Path::new("Cargo.toml")));
+        assert!(!patterns.matches(Path::new("tests/cli.rs")));
+        assert!(!patterns.matches(Path::new("docs/README.md")));
+        assert!(!patterns.matches(Path::new("src/main.rs.bak")));
+        assert!(WatchPatterns::new(&["src/[a".to_string()]).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/target")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        // Build output is skipped even when it matches
+        fs::write(dir.path().join("src/target/generated.rs"), "").unwrap();
+        assert_eq!(count_matching(dir.path(), &patterns), 1);
+    }
+
+    #[test]
+    fn test_collect_changes() {
+        let root = Path::new("/work");
+        let patterns = WatchPatterns::new(&["src/**/*.rs".to_string()]).unwrap();
+        let event = |kind, path: &str| Ok(Event::new(kind).add_path(root.join(path)));
+
+        let mut changed = BTreeSet::new();
+        collect_changes(
+            root,
+            &patterns,
+            event(EventKind::Create(CreateKind::File), "src/new.rs"),
+            &mut changed,
+        );
+        collect_changes(
+            root,
+            &patterns,
+            event(EventKind::Remove(RemoveKind::File), "src/lib.rs"),
+            &mut changed,
+        );
+        collect_changes(
+            root,
+            &patterns,
+            event(EventKind::Modify(ModifyKind::Any), "src/new.rs"),
+            &mut changed,
+        );
+        // Reads, build output and other files don't count
+        collect_changes(
+            root,
+            &patterns,
+            event(
+                EventKind::Access(notify::event::AccessKind::Any),
+                "src/main.rs",
+            ),
+            &mut changed,
+        );
+        collect_changes(
+            root,
+            &patterns,
+            event(EventKind::Create(CreateKind::File), "src/target/gen.rs"),
+            &mut changed,
+        );
+        collect_changes(
+            root,
+            &patterns,
+            event(EventKind::Modify(ModifyKind::Any), "README.md"),
+            &mut changed,
+        );
+        assert_eq!(
+            changed.into_iter().collect::<Vec<_>>(),
+            vec![PathBuf::from("src/lib.rs"), PathBuf::from("src/new.rs")]
+        );
+    }
+}
@@ -63,6 +63,8 @@ enum WebSocketMessage {
         tool_name: String,
         arguments: serde_json::Value,
         needs_confirmation: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        diff: Option<String>,
     },
     #[serde(rename = "error")]
     Error { message: String },
@@ -527,6 +529,7 @@ async fn process_message_streaming(
                                                     tool_name: confirmation.tool_name.clone(),
                                                     arguments: confirmation.arguments.clone(),
                                                     needs_confirmation: true,
+                                                    diff: confirmation.diff.clone(),
                                                 },
                                             )
                                             .unwrap()
@@ -589,6 +592,26 @@ async fn process_message_streaming(
                         // For now, we'll just log them
                         tracing::info!("Received MCP notification in web interface");
                     }
+                    Ok(AgentEvent::Timeout(timeout)) => {
+                        tracing::warn!("Stopped waiting for the user: {:?}", timeout);
+                    }
+                    Ok(AgentEvent::Guardrail(event)) => {
+                        tracing::warn!("{}", event.summary());
+                    }
+                    Ok(AgentEvent::ExtensionDisabled(event)) => {
+                        tracing::warn!("{}", event.summary());
+                    }
+                    Ok(AgentEvent::ContextRecovered(event)) => {
+                        tracing::info!("{}", event.summary());
+                    }
+                    Ok(AgentEvent::HistoryReplaced(history)) => {
+                        let current_messages = {
+                            let mut session_msgs = session_messages.lock().await;
+                            *session_msgs = history;
+                            session_msgs.clone()
+                        };
+                        session::persist_messages(&session_file, &current_messages, None).await?;
+                    }
                     Err(e) => {
                         error!("Error in message stream: {}", e);
                         let mut sender = sender.lock().await;
@@ -0,0 +1,198 @@
+use anyhow::{bail, Context, Result};
+use goose::agents::extension::Envs;
+use goose::config::{Config, ExtensionConfig, DEFAULT_EXTENSION_TIMEOUT};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Config key holding the URL of an extra registry, a JSON list in the same format as the
+/// bundled `servers.json`. Its entries replace bundled ones with the same id.
+pub const REGISTRY_URL_KEY: &str = "GOOSE_EXTENSION_REGISTRY_URL";
+
+const BUNDLED_REGISTRY: &str = include_str!("servers.json");
+
+/// An environment variable an extension needs
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RegistryEnvVar {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// An extension listed in the registry
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RegistryEntry {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Command that starts the server, unset for extensions bundled with goose
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub is_builtin: bool,
+    /// Whether the goose team vouches for the extension
+    #[serde(default)]
+    pub endorsed: bool,
+    #[serde(default)]
+    pub link: Option<String>,
+    #[serde(default)]
+    pub installation_notes: Option<String>,
+    #[serde(default, rename = "environmentVariables")]
+    pub environment_variables: Vec<RegistryEnvVar>,
+}
+
+impl RegistryEntry {
+    /// Whether every word of `query` appears in the id, name or description
+    pub fn matches(&self, query: &str) -> bool {
+        let haystack = format!("{} {} {}", self.id, self.name, self.description).to_lowercase();
+        query
+            .split_whitespace()
+            .all(|word| haystack.contains(&word.to_lowercase()))
+    }
+
+    /// The extension config to install the entry under `name`, with the values of its
+    /// environment variables kept in `envs` and the names of those stored as secrets in
+    /// `env_keys`
+    pub fn to_extension_config(
+        &self,
+        name: &str,
+        envs: HashMap<String, String>,
+        env_keys: Vec<String>,
+        timeout: Option<u64>,
+    ) -> Result<ExtensionConfig> {
+        let timeout = Some(timeout.unwrap_or(DEFAULT_EXTENSION_TIMEOUT));
+        if self.is_builtin {
+            return Ok(ExtensionConfig::Builtin {
+                name: self.id.clone(),
+                display_name: Some(self.name.clone()),
+                timeout,
+                bundled: Some(true),
+            });
+        }
+
+        let Some(command) = &self.command else {
+            bail!("Registry entry '{}' has no command to run", self.id);
+        };
+        let mut parts = shlex::split(command)
+            .filter(|parts| !parts.is_empty())
+            .with_context(|| format!("Can't parse the command of '{}': {}", self.id, command))?
+            .into_iter();
+        let cmd = parts.next().unwrap_or_default();
+        Ok(ExtensionConfig::Stdio {
+            name: name.to_string(),
+            cmd,
+            args: parts.collect(),
+            envs: Envs::new(envs),
+            env_keys,
+            timeout,
+            description: Some(self.description.clone()).filter(|d| !d.is_empty()),
+            bundled: None,
+            cwd: None,
+            inherit_env: None,
+            path_prepend: Vec::new(),
+        })
+    }
+}
+
+fn parse_registry(json: &str) -> Result<Vec<RegistryEntry>> {
+    serde_json::from_str(json).context("Failed to parse the extension registry")
+}
+
+/// `remote` on top of `bundled`, replacing entries with the same id
+fn merge(bundled: Vec<RegistryEntry>, remote: Vec<RegistryEntry>) -> Vec<RegistryEntry> {
+    let mut entries: Vec<RegistryEntry> = bundled
+        .into_iter()
+        .filter(|entry| !remote.iter().any(|r| r.id == entry.id))
+        .collect();
+    entries.extend(remote);
+    entries.sort_by(|a, b| b.endorsed.cmp(&a.endorsed).then(a.id.cmp(&b.id)));
+    entries
+}
+
+/// The registry bundled with goose, plus the one at `GOOSE_EXTENSION_REGISTRY_URL` when set. A
+/// remote registry that can't be loaded is skipped with a warning.
+pub async fn load_registry() -> Result<Vec<RegistryEntry>> {
+    let bundled = parse_registry(BUNDLED_REGISTRY)?;
+    let Ok(url) = Config::global().get_param::<String>(REGISTRY_URL_KEY) else {
+        return Ok(merge(bundled, Vec::new()));
+    };
+
+    let remote = async {
+        let body = reqwest::get(&url).await?.error_for_status()?.text().await?;
+        parse_registry(&body)
+    };
+    match remote.await {
+        Ok(remote) => Ok(merge(bundled, remote)),
+        Err(e) => {
+            eprintln!(
+                "{}",
+                console::style(format!("Couldn't load the registry at {}: {}", url, e)).yellow()
+            );
+            Ok(merge(bundled, Vec::new()))
+        }
+    }
+}
+
+/// The entry with this id, or failing that this name, ignoring case
+pub fn find<'a>(entries: &'a [RegistryEntry], id: &str) -> Option<&'a RegistryEntry> {
+    entries
+        .iter()
+        .find(|entry| entry.id.eq_ignore_ascii_case(id))
+        .or_else(|| {
+            entries
+                .iter()
+                .find(|entry| entry.name.eq_ignore_ascii_case(id))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_registry() {
+        let entries = merge(parse_registry(BUNDLED_REGISTRY).unwrap(), Vec::new());
+        assert!(entries.iter().all(|e| e.is_builtin || e.command.is_some()));
+
+        let github = find(&entries, "GitHub").unwrap();
+        assert!(github.matches("github pull"));
+        assert!(!github.matches("github slack"));
+        let ExtensionConfig::Stdio {
+            cmd, args, name, ..
+        } = github
+            .to_extension_config("gh", HashMap::new(), vec![], None)
+            .unwrap()
+        else {
+            panic!("expected a stdio extension");
+        };
+        assert_eq!((name.as_str(), cmd.as_str()), ("gh", "npx"));
+        assert_eq!(args, vec!["-y", "@modelcontextprotocol/server-github"]);
+
+        let developer = find(&entries, "developer").unwrap();
+        assert!(matches!(
+            developer.to_extension_config("developer", HashMap::new(), vec![], Some(60)),
+            Ok(ExtensionConfig::Builtin {
+                timeout: Some(60),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_merge_prefers_remote() {
+        let remote = parse_registry(
+            r#"[{"id": "github", "name": "GitHub", "command": "github-mcp-server stdio"},
+                {"id": "linear", "name": "Linear", "command": "npx -y linear-mcp"}]"#,
+        )
+        .unwrap();
+        let entries = merge(parse_registry(BUNDLED_REGISTRY).unwrap(), remote);
+        assert_eq!(entries.iter().filter(|e| e.id == "github").count(), 1);
+        assert_eq!(
+            find(&entries, "github").unwrap().command.as_deref(),
+            Some("github-mcp-server stdio")
+        );
+        assert!(find(&entries, "linear").is_some());
+    }
+}
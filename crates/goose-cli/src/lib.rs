@@ -2,8 +2,11 @@ use etcetera::AppStrategyArgs;
 use once_cell::sync::Lazy;
 pub mod cli;
 pub mod commands;
+pub mod extension_registry;
+pub mod log_usage;
 pub mod logging;
 pub mod project_tracker;
+pub mod prompt_library;
 pub mod recipes;
 pub mod session;
 pub mod signal;
@@ -0,0 +1,295 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
+use etcetera::{choose_app_strategy, AppStrategy};
+use goose::config::Config;
+pub use goose::providers::pricing::{
+    get_model_pricing, lookup_pricing, ModelPricing, MODEL_PRICING_KEY,
+};
+use goose::session::MessageUsage;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+/// Config key for the monthly budget in USD
+pub const MONTHLY_BUDGET_KEY: &str = "GOOSE_MONTHLY_BUDGET";
+
+/// A single provider usage entry, appended after every agent response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_tokens: i64,
+    /// Estimated USD saved by routing these responses to a cheaper model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved_usd: Option<f64>,
+}
+
+/// One record per model for the responses in `usage`, in order of first use
+pub fn records_by_model(
+    usage: &[MessageUsage],
+    session_id: Option<String>,
+    provider: &str,
+) -> Vec<UsageRecord> {
+    let mut records: Vec<UsageRecord> = Vec::new();
+    for entry in usage {
+        let index = match records.iter().position(|r| r.model == entry.model) {
+            Some(index) => index,
+            None => {
+                records.push(UsageRecord {
+                    timestamp: Utc::now(),
+                    session_id: session_id.clone(),
+                    provider: provider.to_string(),
+                    model: entry.model.clone(),
+                    input_tokens: 0,
+                    output_tokens: 0,
+                    total_tokens: 0,
+                    saved_usd: None,
+                });
+                records.len() - 1
+            }
+        };
+        let record = &mut records[index];
+        let input = entry.input_tokens.unwrap_or(0).max(0) as i64;
+        let output = entry.output_tokens.unwrap_or(0).max(0) as i64;
+        record.input_tokens += input;
+        record.output_tokens += output;
+        record.total_tokens += input + output;
+        if let Some(saved) = entry.saved_usd {
+            record.saved_usd = Some(record.saved_usd.unwrap_or(0.0) + saved);
+        }
+    }
+    records
+}
+
+/// Token totals for one provider and model
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageTotals {
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_tokens: i64,
+    pub requests: usize,
+    /// Estimated USD saved by tiered model routing
+    pub saved_usd: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, record: &UsageRecord) {
+        self.input_tokens += record.input_tokens;
+        self.output_tokens += record.output_tokens;
+        self.total_tokens += record.total_tokens;
+        self.requests += 1;
+        self.saved_usd += record.saved_usd.unwrap_or(0.0);
+    }
+
+    /// Estimated cost in USD, if the model has a configured price
+    pub fn estimated_cost(&self, pricing: Option<&ModelPricing>) -> Option<f64> {
+        pricing.map(|p| {
+            (self.input_tokens as f64 * p.input + self.output_tokens as f64 * p.output)
+                / 1_000_000.0
+        })
+    }
+}
+
+/// Usage for a calendar month, aggregated by provider/model and by day
+#[derive(Debug, Default)]
+pub struct MonthlyUsage {
+    /// (provider, model) => totals for the month
+    pub by_model: BTreeMap<(String, String), UsageTotals>,
+    /// (day, provider, model) => totals for that day
+    pub by_day: BTreeMap<(NaiveDate, String, String), UsageTotals>,
+}
+
+impl MonthlyUsage {
+    /// Aggregate the records that fall into the given month (in local time)
+    pub fn from_records(records: &[UsageRecord], year: i32, month: u32) -> Self {
+        let mut usage = MonthlyUsage::default();
+        for record in records {
+            let day = record.timestamp.with_timezone(&Local).date_naive();
+            if day.year() != year || day.month() != month {
+                continue;
+            }
+            let key = (record.provider.clone(), record.model.clone());
+            usage.by_model.entry(key).or_default().add(record);
+            usage
+                .by_day
+                .entry((day, record.provider.clone(), record.model.clone()))
+                .or_default()
+                .add(record);
+        }
+        usage
+    }
+
+    /// Estimated spend for the month, counting only models with a configured price
+    pub fn estimated_cost(&self, pricing: &HashMap<String, ModelPricing>) -> f64 {
+        self.by_model
+            .iter()
+            .filter_map(|((provider, model), totals)| {
+                totals.estimated_cost(lookup_pricing(pricing, provider, model))
+            })
+            .sum()
+    }
+
+    /// Estimated USD saved by tiered model routing during the month
+    pub fn savings(&self) -> f64 {
+        self.by_model.values().map(|totals| totals.saved_usd).sum()
+    }
+}
+
+/// Get the path to the usage log, creating its directory if needed
+fn get_usage_file() -> Result<PathBuf> {
+    let strategy =
+        choose_app_strategy(crate::APP_STRATEGY.clone()).context("goose requires a home dir")?;
+    let log_dir = strategy
+        .in_state_dir("logs")
+        .unwrap_or_else(|| strategy.in_data_dir("logs"));
+    fs::create_dir_all(&log_dir)?;
+    Ok(log_dir.join("usage.jsonl"))
+}
+
+/// Append a usage record to the usage log
+pub fn log_usage(record: &UsageRecord) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(get_usage_file()?)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Read every record from the usage log, skipping lines that cannot be parsed
+pub fn read_usage_records() -> Result<Vec<UsageRecord>> {
+    let path = get_usage_file()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let reader = BufReader::new(fs::File::open(path)?);
+    Ok(reader
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+/// Configured monthly budget in USD, if any
+pub fn get_monthly_budget() -> Option<f64> {
+    Config::global()
+        .get_param::<f64>(MONTHLY_BUDGET_KEY)
+        .ok()
+        .filter(|budget| *budget > 0.0)
+}
+
+/// Estimated spend and budget for the current month when the budget has been exceeded
+pub fn check_budget() -> Result<Option<(f64, f64)>> {
+    let Some(budget) = get_monthly_budget() else {
+        return Ok(None);
+    };
+    let now = Local::now();
+    let usage = MonthlyUsage::from_records(&read_usage_records()?, now.year(), now.month());
+    let spent = usage.estimated_cost(&get_model_pricing());
+    Ok((spent > budget).then_some((spent, budget)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn record(day: u32, provider: &str, model: &str, input: i64, output: i64) -> UsageRecord {
+        UsageRecord {
+            timestamp: Local
+                .with_ymd_and_hms(2025, 3, day, 12, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc),
+            session_id: None,
+            provider: provider.to_string(),
+            model: model.to_string(),
+            input_tokens: input,
+            output_tokens: output,
+            total_tokens: input + output,
+            saved_usd: None,
+        }
+    }
+
+    #[test]
+    fn test_monthly_usage_aggregation_and_cost() {
+        let records = vec![
+            record(1, "openai", "gpt-4o", 1_000_000, 100_000),
+            record(1, "openai", "gpt-4o", 500_000, 0),
+            record(2, "anthropic", "claude", 2_000, 1_000),
+            record(2, "ollama", "qwen", 10, 10),
+        ];
+        let usage = MonthlyUsage::from_records(&records, 2025, 3);
+
+        assert_eq!(usage.by_model.len(), 3);
+        assert_eq!(usage.by_day.len(), 3);
+        let gpt = &usage.by_model[&("openai".to_string(), "gpt-4o".to_string())];
+        assert_eq!(gpt.input_tokens, 1_500_000);
+        assert_eq!(gpt.requests, 2);
+
+        let pricing = HashMap::from([
+            (
+                "gpt-4o".to_string(),
+                ModelPricing {
+                    input: 2.0,
+                    output: 10.0,
+                },
+            ),
+            (
+                "anthropic/claude".to_string(),
+                ModelPricing {
+                    input: 3.0,
+                    output: 15.0,
+                },
+            ),
+        ]);
+        // 1.5M * $2 + 0.1M * $10 + 2k * $3/M + 1k * $15/M, the unpriced model is ignored
+        let cost = usage.estimated_cost(&pricing);
+        assert!((cost - 4.021).abs() < 1e-9);
+
+        assert!(MonthlyUsage::from_records(&records, 2025, 4)
+            .by_model
+            .is_empty());
+    }
+
+    #[test]
+    fn test_records_by_model() {
+        let entry = |model: &str, input: i32, saved_usd: Option<f64>| MessageUsage {
+            message_index: 0,
+            model: model.to_string(),
+            input_tokens: Some(input),
+            output_tokens: Some(10),
+            cost_usd: None,
+            saved_usd,
+        };
+        let records = records_by_model(
+            &[
+                entry("gpt-4o", 100, None),
+                entry("gpt-4o-mini", 50, Some(0.25)),
+                entry("gpt-4o-mini", 30, Some(0.5)),
+            ],
+            Some("session".to_string()),
+            "openai",
+        );
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].model, "gpt-4o");
+        assert_eq!(records[0].saved_usd, None);
+        assert_eq!(records[1].input_tokens, 80);
+        assert_eq!(records[1].total_tokens, 100);
+        assert_eq!(records[1].saved_usd, Some(0.75));
+
+        let now = Utc::now();
+        let usage = MonthlyUsage::from_records(
+            &records,
+            now.with_timezone(&Local).year(),
+            now.with_timezone(&Local).month(),
+        );
+        assert!((usage.savings() - 0.75).abs() < 1e-9);
+    }
+}
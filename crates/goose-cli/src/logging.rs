@@ -18,14 +18,12 @@ use goose_bench::error_capture::ErrorCaptureLayer;
 // Used to ensure we only set up tracing once
 static INIT: Once = Once::new();
 
-/// Returns the directory where log files should be stored.
-/// Creates the directory structure if it doesn't exist.
-fn get_log_directory() -> Result<PathBuf> {
-    get_log_directory_with_date(None)
-}
+/// Name of the session the process works for, inherited by the extensions it starts so their
+/// logs can be found with `goose session logs`
+pub const LOG_SESSION_ENV: &str = "GOOSE_LOG_SESSION";
 
-/// Internal function that allows specifying a custom date string for testing
-fn get_log_directory_with_date(test_date: Option<String>) -> Result<PathBuf> {
+/// The directory holding a subdirectory of logs for each day
+pub fn log_base_directory() -> Result<PathBuf> {
     // choose_app_strategy().state_dir()
     // - macOS/Linux: ~/.local/state/goose/logs/cli
     // - Windows:     ~\AppData\Roaming\Block\goose\data\logs\cli
@@ -33,9 +31,20 @@ fn get_log_directory_with_date(test_date: Option<String>) -> Result<PathBuf> {
     let home_dir = choose_app_strategy(crate::APP_STRATEGY.clone())
         .context("HOME environment variable not set")?;
 
-    let base_log_dir = home_dir
+    Ok(home_dir
         .in_state_dir("logs/cli")
-        .unwrap_or_else(|| home_dir.in_data_dir("logs/cli"));
+        .unwrap_or_else(|| home_dir.in_data_dir("logs/cli")))
+}
+
+/// Returns the directory where log files should be stored.
+/// Creates the directory structure if it doesn't exist.
+fn get_log_directory() -> Result<PathBuf> {
+    get_log_directory_with_date(None)
+}
+
+/// Internal function that allows specifying a custom date string for testing
+fn get_log_directory_with_date(test_date: Option<String>) -> Result<PathBuf> {
+    let base_log_dir = log_base_directory()?;
 
     // Create date-based subdirectory
     let date_str = test_date.unwrap_or_else(|| {
@@ -0,0 +1,195 @@
+use anyhow::{Context, Result};
+use etcetera::{choose_app_strategy, AppStrategy};
+use minijinja::{Environment, UndefinedBehavior};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `{{ name }}` placeholders, filled in when the prompt is run
+static VARIABLE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").unwrap());
+
+/// A prompt snippet saved with `goose prompt save`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedPrompt {
+    pub name: String,
+    pub text: String,
+}
+
+impl SavedPrompt {
+    /// The variables the prompt needs, in the order they first appear
+    pub fn variables(&self) -> Vec<String> {
+        let mut variables: Vec<String> = Vec::new();
+        for caps in VARIABLE.captures_iter(&self.text) {
+            let name = caps[1].to_string();
+            if !variables.contains(&name) {
+                variables.push(name);
+            }
+        }
+        variables
+    }
+
+    /// The prompt with its variables filled in from `values`
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<String> {
+        let missing: Vec<String> = self
+            .variables()
+            .into_iter()
+            .filter(|name| !values.contains_key(name))
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "Prompt '{}' needs values for: {}. Pass them as key=value",
+                self.name,
+                missing.join(", ")
+            );
+        }
+        let mut env = Environment::new();
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+        env.template_from_str(&self.text)
+            .and_then(|template| template.render(values))
+            .map_err(|e| anyhow::anyhow!("Failed to render prompt '{}': {}", self.name, e))
+    }
+}
+
+/// Where saved prompts are kept, one `<name>.md` file each
+/// - macOS/Linux: ~/.config/goose/prompts/
+/// - Windows:     ~\AppData\Roaming\Block\goose\config\prompts\
+pub fn prompts_dir() -> Result<PathBuf> {
+    Ok(choose_app_strategy(crate::APP_STRATEGY.clone())
+        .context("goose requires a home dir")?
+        .in_config_dir("prompts"))
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!(
+            "Invalid prompt name '{}', use letters, digits, - and _",
+            name
+        );
+    }
+    Ok(())
+}
+
+pub fn save_prompt_in(dir: &Path, name: &str, text: &str) -> Result<PathBuf> {
+    validate_name(name)?;
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.md", name));
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+pub fn load_prompt_in(dir: &Path, name: &str) -> Result<Option<SavedPrompt>> {
+    validate_name(name)?;
+    match fs::read_to_string(dir.join(format!("{}.md", name))) {
+        Ok(text) => Ok(Some(SavedPrompt {
+            name: name.to_string(),
+            text,
+        })),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// True if there was a prompt to remove
+pub fn remove_prompt_in(dir: &Path, name: &str) -> Result<bool> {
+    validate_name(name)?;
+    match fs::remove_file(dir.join(format!("{}.md", name))) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Every saved prompt, sorted by name
+pub fn list_prompts_in(dir: &Path) -> Result<Vec<SavedPrompt>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut prompts = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        prompts.push(SavedPrompt {
+            name: name.to_string(),
+            text: fs::read_to_string(&path)?,
+        });
+    }
+    prompts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(prompts)
+}
+
+pub fn save_prompt(name: &str, text: &str) -> Result<PathBuf> {
+    save_prompt_in(&prompts_dir()?, name, text)
+}
+
+pub fn load_prompt(name: &str) -> Result<Option<SavedPrompt>> {
+    load_prompt_in(&prompts_dir()?, name)
+}
+
+pub fn remove_prompt(name: &str) -> Result<bool> {
+    remove_prompt_in(&prompts_dir()?, name)
+}
+
+pub fn list_prompts() -> Result<Vec<SavedPrompt>> {
+    list_prompts_in(&prompts_dir()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_save_list_and_load() -> Result<()> {
+        let dir = tempdir()?;
+        save_prompt_in(dir.path(), "review", "Review this diff for {{ focus }}")?;
+        save_prompt_in(dir.path(), "explain", "Explain the code")?;
+        assert!(save_prompt_in(dir.path(), "../escape", "nope").is_err());
+
+        let names: Vec<String> = list_prompts_in(dir.path())?
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, vec!["explain", "review"]);
+
+        let review = load_prompt_in(dir.path(), "review")?.unwrap();
+        assert_eq!(review.variables(), vec!["focus"]);
+        assert!(load_prompt_in(dir.path(), "missing")?.is_none());
+
+        assert!(remove_prompt_in(dir.path(), "explain")?);
+        assert!(!remove_prompt_in(dir.path(), "explain")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render() {
+        let prompt = SavedPrompt {
+            name: "review".to_string(),
+            text: "Review {{ file }} for {{focus}}, then {{ file }} again".to_string(),
+        };
+        assert_eq!(prompt.variables(), vec!["file", "focus"]);
+
+        let values = HashMap::from([
+            ("file".to_string(), "main.rs".to_string()),
+            ("focus".to_string(), "bugs".to_string()),
+        ]);
+        assert_eq!(
+            prompt.render(&values).unwrap(),
+            "Review main.rs for bugs, then main.rs again"
+        );
+
+        let error = prompt.render(&HashMap::new()).unwrap_err().to_string();
+        assert!(error.contains("file, focus"));
+    }
+}
@@ -1,10 +1,15 @@
 use console::style;
+use goose::agents::dry_run::DryRunMode;
 use goose::agents::extension::ExtensionError;
 use goose::agents::Agent;
-use goose::config::{Config, ExtensionConfig, ExtensionConfigManager};
+use goose::config::{Config, ExtensionConfig, ExtensionConfigManager, KeyManager};
+use goose::model::ModelConfig;
 use goose::providers::create;
+use goose::providers::ollama::OllamaProvider;
 use goose::session;
+use goose::session::history;
 use goose::session::Identifier;
+use indicatif::{ProgressBar, ProgressStyle};
 use mcp_client::transport::Error as McpClientError;
 use std::process;
 use std::sync::Arc;
@@ -38,6 +43,56 @@ pub struct SessionBuilderConfig {
     pub debug: bool,
     /// Maximum number of consecutive identical tool calls allowed
     pub max_tool_repetitions: Option<u32>,
+    /// Intercept tool calls that aren't read-only, overriding GOOSE_DRY_RUN
+    pub dry_run: Option<DryRunMode>,
+    /// Start new sessions with a digest of earlier sessions in the same project
+    pub project_history: bool,
+    /// Retries for provider calls that fail with a transient error, overriding
+    /// GOOSE_PROVIDER_RETRIES
+    pub provider_retries: Option<u32>,
+}
+
+/// Make sure the Ollama model exists locally before starting, showing progress if it is pulled
+async fn ensure_ollama_model(model_config: ModelConfig) {
+    let Ok(provider) = OllamaProvider::from_env(model_config) else {
+        return;
+    };
+
+    let bar = ProgressBar::hidden();
+    bar.set_style(
+        ProgressStyle::with_template("{msg} [{bar:40}] {bytes}/{total_bytes}")
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    let result = provider
+        .ensure_model_available(|progress| {
+            if bar.is_hidden() {
+                bar.set_draw_target(indicatif::ProgressDrawTarget::stderr());
+            }
+            bar.set_message(progress.status.clone());
+            if let (Some(total), Some(completed)) = (progress.total, progress.completed) {
+                bar.set_length(total);
+                bar.set_position(completed);
+            }
+        })
+        .await;
+    bar.finish_and_clear();
+
+    if let Err(e) = result {
+        output::render_error(&e.to_string());
+        process::exit(1);
+    }
+}
+
+/// Record that the provider's keys are in use and warn about keys that need attention
+fn check_provider_keys(provider_name: &str) {
+    let keys = KeyManager::provider_keys(provider_name);
+    for warning in KeyManager::warnings(&keys) {
+        output::render_key_warning(&warning);
+    }
+    if let Err(e) = KeyManager::mark_used(&keys) {
+        tracing::warn!("Failed to record provider key usage: {}", e);
+    }
 }
 
 pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
@@ -53,6 +108,11 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
         .expect("No model configured. Run 'goose configure' first");
     let model_config = goose::model::ModelConfig::new(model.clone());
 
+    if provider_name == "ollama" {
+        ensure_ollama_model(model_config.clone()).await;
+    }
+    check_provider_keys(&provider_name);
+
     // Create the agent
     let agent: Agent = Agent::new();
     let new_provider = create(&provider_name, model_config).unwrap();
@@ -85,6 +145,17 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
         agent.configure_tool_monitor(Some(max_repetitions)).await;
     }
 
+    if let Some(mode) = session_config.dry_run {
+        agent.set_dry_run(mode).await;
+    }
+
+    if let Some(retries) = session_config.provider_retries {
+        agent.set_provider_retries(retries).await;
+    }
+
+    // The CLI answers the agent's questions itself, or by GOOSE_ASK_USER_POLICY when headless
+    agent.enable_ask_user().await;
+
     // Handle session file resolution and resuming
     let session_file = if session_config.no_session {
         // Use a temporary path that won't be written to
@@ -158,6 +229,16 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
                 }
             }
         }
+
+        // Keep the files pinned in the session in context
+        agent.set_pinned_paths(metadata.pinned).await;
+    }
+
+    // Extensions started from here inherit the session name to tag their logs with
+    if !session_config.no_session {
+        if let Some(name) = session_file.file_stem().and_then(|s| s.to_str()) {
+            std::env::set_var(crate::logging::LOG_SESSION_ENV, name);
+        }
     }
 
     // Setup extensions for the agent
@@ -176,6 +257,11 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
 
     for extension in extensions_to_run {
         if let Err(e) = agent.add_extension(extension.clone()).await {
+            // A disabled extension shouldn't keep the session from starting
+            if let ExtensionError::Suspended(_) = e {
+                eprintln!("{}", style(e.to_string()).yellow());
+                continue;
+            }
             let err = match e {
                 ExtensionError::Transport(McpClientError::StdioProcessError(inner)) => inner,
                 _ => e.to_string(),
@@ -216,6 +302,41 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
         }
     }
 
+    // Pick up a run that was paused or handed off in the other mode, with the extensions it had
+    if session_config.resume && !session_config.no_session {
+        // A pause meant for an earlier run shouldn't stop this one
+        session::checkpoint::take_pause_request(&session_file);
+        match session::checkpoint::take_checkpoint(&session_file) {
+            Ok(Some(checkpoint)) => {
+                let running: Vec<String> = session
+                    .agent
+                    .extension_configs()
+                    .await
+                    .iter()
+                    .map(|config| config.name())
+                    .collect();
+                for extension in &checkpoint.extensions {
+                    if running.contains(&extension.name()) {
+                        continue;
+                    }
+                    if let Err(e) = session.agent.add_extension(extension.clone()).await {
+                        eprintln!(
+                            "Failed to start extension {} from the paused run: {}",
+                            extension.name(),
+                            e
+                        );
+                    }
+                }
+                output::render_checkpoint(&checkpoint);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!(
+                "Warning: Failed to read the checkpoint of the session: {}",
+                e
+            ),
+        }
+    }
+
     // Add CLI-specific system prompt extension
     session
         .agent
@@ -226,6 +347,22 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
         session.agent.extend_system_prompt(additional_prompt).await;
     }
 
+    // Tell new sessions what earlier ones in this project did and left open
+    if session_config.project_history && !session_config.resume && history::is_enabled() {
+        let working_dir = std::env::current_dir().expect("Failed to get current working directory");
+        let digests = history::project_history(
+            &working_dir,
+            Some(&session_file),
+            history::DEFAULT_HISTORY_SESSIONS,
+        );
+        if let Some(digest) =
+            history::render_project_history(&digests, &history::project_root(&working_dir))
+        {
+            tracing::info!("Loaded {} earlier sessions of this project", digests.len());
+            session.agent.extend_system_prompt(digest).await;
+        }
+    }
+
     // Only override system prompt if a system override exists
     let system_prompt_file: Option<String> = config.get_param("GOOSE_SYSTEM_PROMPT_FILE_PATH").ok();
     if let Some(ref path) = system_prompt_file {
@@ -130,6 +130,10 @@ impl GooseCompleter {
             "/prompt",
             "/mode",
             "/recipe",
+            "/tag",
+            "/note",
+            "/pin",
+            "/unpin",
         ];
 
         // Find commands that match the prefix
@@ -0,0 +1,227 @@
+use super::completion::GooseCompleter;
+use anyhow::{anyhow, Result};
+use etcetera::{choose_app_strategy, AppStrategy};
+use goose::config::Config;
+use rustyline::{
+    Cmd, EditMode, Editor, EventHandler, KeyCode, KeyEvent, Modifiers, Movement, Word,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub type GooseEditor = Editor<GooseCompleter, rustyline::history::DefaultHistory>;
+
+const DEFAULT_HISTORY_SIZE: usize = 1000;
+
+/// Line editing settings for the interactive prompt, read from the goose config:
+/// - `GOOSE_CLI_EDIT_MODE`: `emacs` (default) or `vi`
+/// - `GOOSE_CLI_KEYBINDINGS`: a map of keys to actions, e.g. `{"alt-enter": "newline"}`
+/// - `GOOSE_CLI_HISTORY_SIZE`: how many entries of history to keep
+#[derive(Debug)]
+pub struct EditorSettings {
+    pub edit_mode: EditMode,
+    pub keybindings: Vec<(KeyEvent, Cmd)>,
+    pub history_size: usize,
+}
+
+impl EditorSettings {
+    pub fn from_config() -> Result<Self> {
+        let config = Config::global();
+
+        let edit_mode = match config.get_param::<String>("GOOSE_CLI_EDIT_MODE") {
+            Ok(mode) => parse_edit_mode(&mode)?,
+            Err(_) => EditMode::Emacs,
+        };
+
+        // Ctrl-J always inserts a newline unless it is explicitly rebound
+        let mut keybindings = vec![(KeyEvent::new('j', Modifiers::CTRL), Cmd::Newline)];
+        let custom: HashMap<String, String> = config
+            .get_param("GOOSE_CLI_KEYBINDINGS")
+            .unwrap_or_default();
+        for (key, action) in custom {
+            let key = parse_key(&key)?;
+            let cmd = parse_action(&action)?;
+            keybindings.retain(|(bound, _)| *bound != key);
+            keybindings.push((key, cmd));
+        }
+
+        let history_size = config
+            .get_param::<usize>("GOOSE_CLI_HISTORY_SIZE")
+            .unwrap_or(DEFAULT_HISTORY_SIZE);
+
+        Ok(Self {
+            edit_mode,
+            keybindings,
+            history_size,
+        })
+    }
+
+    /// Create an editor with these settings and history loaded from `history_file()`
+    pub fn build(&self, completer: GooseCompleter) -> Result<GooseEditor> {
+        let config = rustyline::Config::builder()
+            .completion_type(rustyline::CompletionType::Circular)
+            .edit_mode(self.edit_mode)
+            .max_history_size(self.history_size)?
+            .history_ignore_dups(true)?
+            .history_ignore_space(true)
+            .build();
+        let mut editor = GooseEditor::with_config(config)?;
+        editor.set_helper(Some(completer));
+
+        for (key, cmd) in &self.keybindings {
+            editor.bind_sequence(*key, EventHandler::Simple(cmd.clone()));
+        }
+
+        let history_file = history_file();
+        if let Some(parent) = history_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if history_file.exists() {
+            if let Err(err) = editor.load_history(&history_file) {
+                eprintln!("Warning: Failed to load command history: {}", err);
+            }
+        }
+
+        Ok(editor)
+    }
+}
+
+/// A global history file in the goose config dir, so command history persists across
+/// sessions instead of being tied to each session's messages
+pub fn history_file() -> PathBuf {
+    choose_app_strategy(crate::APP_STRATEGY.clone())
+        .expect("goose requires a home dir")
+        .config_dir()
+        .join("history.txt")
+}
+
+pub fn save_history(editor: &mut GooseEditor) {
+    if let Err(err) = editor.save_history(&history_file()) {
+        eprintln!("Warning: Failed to save command history: {}", err);
+    }
+}
+
+fn parse_edit_mode(mode: &str) -> Result<EditMode> {
+    match mode.to_lowercase().as_str() {
+        "emacs" => Ok(EditMode::Emacs),
+        "vi" | "vim" => Ok(EditMode::Vi),
+        _ => Err(anyhow!(
+            "Unknown GOOSE_CLI_EDIT_MODE '{}', expected 'emacs' or 'vi'",
+            mode
+        )),
+    }
+}
+
+/// Parse keys like `ctrl-r`, `alt-enter` or `shift-tab`
+fn parse_key(key: &str) -> Result<KeyEvent> {
+    let lower = key.to_lowercase();
+    let mut parts: Vec<&str> = lower.split(['-', '+']).collect();
+    let name = parts
+        .pop()
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| anyhow!("Invalid key '{}'", key))?;
+
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        modifiers |= match part {
+            "ctrl" | "c" => Modifiers::CTRL,
+            "alt" | "meta" | "m" => Modifiers::ALT,
+            "shift" | "s" => Modifiers::SHIFT,
+            _ => return Err(anyhow!("Unknown modifier '{}' in key '{}'", part, key)),
+        };
+    }
+
+    if name.chars().count() == 1 {
+        return Ok(KeyEvent::new(name.chars().next().unwrap(), modifiers));
+    }
+    let code = match name {
+        "enter" | "return" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "esc" | "escape" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => return Ok(KeyEvent::new(' ', modifiers)),
+        _ if name.starts_with('f') => name[1..]
+            .parse()
+            .map(KeyCode::F)
+            .map_err(|_| anyhow!("Unknown key '{}'", key))?,
+        _ => return Err(anyhow!("Unknown key '{}'", key)),
+    };
+    Ok(KeyEvent(code, modifiers))
+}
+
+/// Parse an action, named after its readline equivalent
+fn parse_action(action: &str) -> Result<Cmd> {
+    let cmd = match action.to_lowercase().replace('_', "-").as_str() {
+        "accept-line" | "submit" => Cmd::AcceptLine,
+        "newline" => Cmd::Newline,
+        "complete" => Cmd::Complete,
+        "clear-screen" => Cmd::ClearScreen,
+        "reverse-search-history" => Cmd::ReverseSearchHistory,
+        "forward-search-history" => Cmd::ForwardSearchHistory,
+        "history-search-backward" => Cmd::HistorySearchBackward,
+        "history-search-forward" => Cmd::HistorySearchForward,
+        "previous-history" => Cmd::PreviousHistory,
+        "next-history" => Cmd::NextHistory,
+        "beginning-of-line" => Cmd::Move(Movement::BeginningOfLine),
+        "end-of-line" => Cmd::Move(Movement::EndOfLine),
+        "kill-line" => Cmd::Kill(Movement::EndOfLine),
+        "unix-line-discard" => Cmd::Kill(Movement::BeginningOfLine),
+        "backward-kill-word" => Cmd::Kill(Movement::BackwardWord(1, Word::Emacs)),
+        "undo" => Cmd::Undo(1),
+        "yank" => Cmd::Yank(1, rustyline::Anchor::Before),
+        "interrupt" => Cmd::Interrupt,
+        "noop" | "none" => Cmd::Noop,
+        _ => return Err(anyhow!("Unknown keybinding action '{}'", action)),
+    };
+    Ok(cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(
+            parse_key("ctrl-r").unwrap(),
+            KeyEvent::new('r', Modifiers::CTRL)
+        );
+        assert_eq!(
+            parse_key("Alt+Enter").unwrap(),
+            KeyEvent(KeyCode::Enter, Modifiers::ALT)
+        );
+        assert_eq!(
+            parse_key("ctrl-shift-up").unwrap(),
+            KeyEvent(KeyCode::Up, Modifiers::CTRL_SHIFT)
+        );
+        assert_eq!(
+            parse_key("f5").unwrap(),
+            KeyEvent(KeyCode::F(5), Modifiers::NONE)
+        );
+        assert!(parse_key("hyper-x").is_err());
+        assert!(parse_key("ctrl-").is_err());
+        assert!(parse_key("foo").is_err());
+    }
+
+    #[test]
+    fn test_parse_action_and_mode() {
+        assert_eq!(parse_action("newline").unwrap(), Cmd::Newline);
+        assert_eq!(
+            parse_action("reverse_search_history").unwrap(),
+            Cmd::ReverseSearchHistory
+        );
+        assert!(parse_action("self-destruct").is_err());
+
+        assert_eq!(parse_edit_mode("Vi").unwrap(), EditMode::Vi);
+        assert_eq!(parse_edit_mode("emacs").unwrap(), EditMode::Emacs);
+        assert!(parse_edit_mode("nano").is_err());
+    }
+}
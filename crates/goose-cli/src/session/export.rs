@@ -123,8 +123,8 @@ pub fn tool_request_to_markdown(req: &ToolRequest, export_all_content: bool) ->
             };
 
             md.push_str(&format!(
-                "#### Tool Call: `{}` (namespace: `{}`)\n",
-                tool_name_only, namespace
+                "#### Tool Call: `{}` (namespace: `{}`, id: `{}`)\n",
+                tool_name_only, namespace, req.id
             ));
             md.push_str("**Arguments:**\n");
 
@@ -201,7 +201,8 @@ pub fn tool_request_to_markdown(req: &ToolRequest, export_all_content: bool) ->
 
 pub fn tool_response_to_markdown(resp: &ToolResponse, export_all_content: bool) -> String {
     let mut md = String::new();
-    md.push_str("#### Tool Response:\n");
+    // The id is what citations of the final answer refer to
+    md.push_str(&format!("#### Tool Response: `{}`\n", resp.id));
 
     match &resp.tool_result {
         Ok(contents) => {
@@ -335,6 +336,9 @@ pub fn message_to_markdown(message: &Message, export_all_content: bool) -> Strin
                     image.data.chars().take(30).collect::<String>()
                 ));
             }
+            // Thinking is only part of the transcript when exporting everything
+            MessageContent::Thinking(_) | MessageContent::RedactedThinking(_)
+                if !export_all_content => {}
             MessageContent::Thinking(thinking) => {
                 md.push_str("**Thinking:**\n");
                 md.push_str("> ");
@@ -490,6 +494,7 @@ mod tests {
         let result = tool_request_to_markdown(&tool_request, true);
         assert!(result.contains("#### Tool Call: `shell`"));
         assert!(result.contains("namespace: `developer`"));
+        assert!(result.contains("id: `test-id`"));
         assert!(result.contains("**command**:"));
         assert!(result.contains("```sh"));
         assert!(result.contains("ls -la"));
@@ -529,7 +534,7 @@ mod tests {
         };
 
         let result = tool_response_to_markdown(&tool_response, true);
-        assert!(result.contains("#### Tool Response:"));
+        assert!(result.contains("#### Tool Response: `test-id`"));
         assert!(result.contains("Command executed successfully"));
     }
 
@@ -581,6 +586,10 @@ mod tests {
         let result = message_to_markdown(&message, true);
         assert!(result.contains("**Thinking:**"));
         assert!(result.contains("> I need to analyze this problem..."));
+
+        // Hidden unless exporting all content
+        let result = message_to_markdown(&message, false);
+        assert!(!result.contains("Thinking"));
     }
 
     #[test]
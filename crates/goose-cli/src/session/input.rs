@@ -1,6 +1,5 @@
-use super::completion::GooseCompleter;
+use super::editor::GooseEditor;
 use anyhow::Result;
-use rustyline::Editor;
 use shlex;
 use std::collections::HashMap;
 
@@ -10,6 +9,8 @@ pub enum InputResult {
     Exit,
     AddExtension(String),
     AddBuiltin(String),
+    ListExtensions,
+    ReloadExtension(String),
     ToggleTheme,
     Retry,
     ListPrompts(Option<String>),
@@ -19,6 +20,12 @@ pub enum InputResult {
     EndPlan,
     Recipe(Option<String>),
     Summarize,
+    Tag(Vec<String>),
+    Note(String),
+    Handoff(Option<String>),
+    Context,
+    Pin(Option<String>),
+    Unpin(String),
 }
 
 #[derive(Debug)]
@@ -33,15 +40,7 @@ pub struct PlanCommandOptions {
     pub message_text: String,
 }
 
-pub fn get_input(
-    editor: &mut Editor<GooseCompleter, rustyline::history::DefaultHistory>,
-) -> Result<InputResult> {
-    // Ensure Ctrl-J binding is set for newlines
-    editor.bind_sequence(
-        rustyline::KeyEvent(rustyline::KeyCode::Char('j'), rustyline::Modifiers::CTRL),
-        rustyline::EventHandler::Simple(rustyline::Cmd::Newline),
-    );
-
+pub fn get_input(editor: &mut GooseEditor) -> Result<InputResult> {
     let prompt = format!("{} ", console::style("( O)>").cyan().bold());
     let input = match editor.readline(&prompt) {
         Ok(text) => text,
@@ -86,6 +85,7 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
     const CMD_PROMPTS: &str = "/prompts ";
     const CMD_PROMPT: &str = "/prompt";
     const CMD_PROMPT_WITH_SPACE: &str = "/prompt ";
+    const CMD_EXTENSIONS: &str = "/extensions";
     const CMD_EXTENSION: &str = "/extension ";
     const CMD_BUILTIN: &str = "/builtin ";
     const CMD_MODE: &str = "/mode ";
@@ -93,6 +93,12 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
     const CMD_ENDPLAN: &str = "/endplan";
     const CMD_RECIPE: &str = "/recipe";
     const CMD_SUMMARIZE: &str = "/summarize";
+    const CMD_TAG: &str = "/tag";
+    const CMD_NOTE: &str = "/note";
+    const CMD_HANDOFF: &str = "/handoff";
+    const CMD_CONTEXT: &str = "/context";
+    const CMD_PIN: &str = "/pin";
+    const CMD_UNPIN: &str = "/unpin ";
 
     match input {
         "/exit" | "/quit" => Some(InputResult::Exit),
@@ -123,6 +129,9 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
                 None
             }
         }
+        s if s == CMD_EXTENSIONS || s.starts_with("/extensions ") => {
+            parse_extensions_command(s[CMD_EXTENSIONS.len()..].trim())
+        }
         s if s.starts_with(CMD_EXTENSION) => Some(InputResult::AddExtension(
             s[CMD_EXTENSION.len()..].to_string(),
         )),
@@ -136,10 +145,51 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
         s if s == CMD_ENDPLAN => Some(InputResult::EndPlan),
         s if s.starts_with(CMD_RECIPE) => parse_recipe_command(s),
         s if s == CMD_SUMMARIZE => Some(InputResult::Summarize),
+        s if s == CMD_TAG || s.starts_with("/tag ") => Some(InputResult::Tag(
+            s[CMD_TAG.len()..]
+                .split([' ', ','])
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect(),
+        )),
+        s if s == CMD_NOTE || s.starts_with("/note ") => {
+            Some(InputResult::Note(s[CMD_NOTE.len()..].trim().to_string()))
+        }
+        s if s == CMD_CONTEXT => Some(InputResult::Context),
+        s if s == CMD_PIN || s.starts_with("/pin ") => {
+            let path = s[CMD_PIN.len()..].trim();
+            Some(InputResult::Pin(
+                (!path.is_empty()).then(|| path.to_string()),
+            ))
+        }
+        s if s.starts_with(CMD_UNPIN) => {
+            Some(InputResult::Unpin(s[CMD_UNPIN.len()..].trim().to_string()))
+        }
+        s if s == CMD_HANDOFF || s.starts_with("/handoff ") => {
+            let instructions = s[CMD_HANDOFF.len()..].trim();
+            Some(InputResult::Handoff(
+                (!instructions.is_empty()).then(|| instructions.to_string()),
+            ))
+        }
         _ => None,
     }
 }
 
+fn parse_extensions_command(args: &str) -> Option<InputResult> {
+    let parts: Vec<&str> = args.split_whitespace().collect();
+    match parts.as_slice() {
+        [] => Some(InputResult::ListExtensions),
+        ["reload", name] => Some(InputResult::ReloadExtension(name.to_string())),
+        _ => {
+            println!(
+                "{}",
+                console::style("Usage: /extensions [reload <name>]").red()
+            );
+            Some(InputResult::Retry)
+        }
+    }
+}
+
 fn parse_recipe_command(s: &str) -> Option<InputResult> {
     const CMD_RECIPE: &str = "/recipe";
 
@@ -233,8 +283,9 @@ fn print_help() {
 /t - Toggle Light/Dark/Ansi theme
 /extension <command> - Add a stdio extension (format: ENV1=val1 command args...)
 /builtin <names> - Add builtin extensions by name (comma-separated)
-/prompts [--extension <name>] - List all available prompts, optionally filtered by extension
-/prompt <n> [--info] [key=value...] - Get prompt info or execute a prompt
+/extensions [reload <name>] - List the running extensions, or restart one to pick up changes to its server or config
+/prompts [--extension <name>] - List all available prompts, including those saved with 'goose prompt save', optionally filtered by extension
+/prompt <n> [--info] [key=value...] - Get prompt info or execute a prompt, key=value fills in the variables of a saved prompt
 /mode <name> - Set the goose mode to use ('auto', 'approve', 'chat')
 /plan <message_text> -  Enters 'plan' mode with optional message. Create a plan based on the current messages and asks user if they want to act on it.
                         If user acts on the plan, goose mode is set to 'auto' and returns to 'normal' goose mode.
@@ -245,12 +296,22 @@ fn print_help() {
 /recipe [filepath] - Generate a recipe from the current conversation and save it to the specified filepath (must end with .yaml).
                        If no filepath is provided, it will be saved to ./recipe.yaml.
 /summarize - Summarize the current conversation to reduce context length while preserving key information.
+/tag [tags...] - Tag this session to find it later with `goose session list --tag`, -tag removes one. Lists the tags without arguments.
+/note [text] - Add a note to this session. Lists the notes without text.
+/context - Show what fills the context window: the system prompt, each extension's instructions and tools, and the conversation
+/pin [path] - Keep a file or directory in context, refreshed when it changes and never summarized away. Lists the pinned paths without a path.
+/unpin <path> - Stop keeping a pinned path in context
+/handoff [instructions] - Continue this session as a headless run in the background and exit. Pause it with `goose session pause`.
 /? or /help - Display this help message
 
 Navigation:
 Ctrl+C - Interrupt goose (resets the interaction to before the interrupted user request)
 Ctrl+J - Add a newline
-Up/Down arrows - Navigate through command history"
+Up/Down arrows - Navigate through command history
+Ctrl+R - Search command history
+
+Line editing is emacs style by default. Set GOOSE_CLI_EDIT_MODE to 'vi' for vi style editing,
+and GOOSE_CLI_KEYBINDINGS to a map of keys to actions to rebind keys, e.g. {{\"alt-enter\": \"newline\"}}"
     );
 }
 
@@ -300,6 +361,59 @@ mod tests {
             panic!("Expected AddBuiltin");
         }
 
+        // Test extensions command
+        assert!(matches!(
+            handle_slash_command("/extensions"),
+            Some(InputResult::ListExtensions)
+        ));
+        if let Some(InputResult::ReloadExtension(name)) =
+            handle_slash_command("/extensions reload developer")
+        {
+            assert_eq!(name, "developer");
+        } else {
+            panic!("Expected ReloadExtension");
+        }
+        assert!(matches!(
+            handle_slash_command("/extensions reload"),
+            Some(InputResult::Retry)
+        ));
+
+        // Test context command
+        assert!(matches!(
+            handle_slash_command("/context"),
+            Some(InputResult::Context)
+        ));
+
+        // Test pin commands
+        assert!(matches!(
+            handle_slash_command("/pin"),
+            Some(InputResult::Pin(None))
+        ));
+        if let Some(InputResult::Pin(Some(path))) = handle_slash_command("/pin  src/schema.sql ") {
+            assert_eq!(path, "src/schema.sql");
+        } else {
+            panic!("Expected Pin");
+        }
+        if let Some(InputResult::Unpin(path)) = handle_slash_command("/unpin docs/api.md") {
+            assert_eq!(path, "docs/api.md");
+        } else {
+            panic!("Expected Unpin");
+        }
+        assert!(handle_slash_command("/unpin").is_none());
+
+        // Test handoff command
+        assert!(matches!(
+            handle_slash_command("/handoff"),
+            Some(InputResult::Handoff(None))
+        ));
+        if let Some(InputResult::Handoff(Some(instructions))) =
+            handle_slash_command("/handoff  finish the migration ")
+        {
+            assert_eq!(instructions, "finish the migration");
+        } else {
+            panic!("Expected Handoff");
+        }
+
         // Test unknown commands
         assert!(handle_slash_command("/unknown").is_none());
     }
@@ -489,4 +603,23 @@ mod tests {
         let result = handle_slash_command("  /summarize  ");
         assert!(matches!(result, Some(InputResult::Summarize)));
     }
+
+    #[test]
+    fn test_tag_and_note_commands() {
+        let result = handle_slash_command("/tag refactor, #migration -old");
+        assert!(
+            matches!(result, Some(InputResult::Tag(tags)) if tags == vec!["refactor", "#migration", "-old"])
+        );
+
+        let result = handle_slash_command("/tag");
+        assert!(matches!(result, Some(InputResult::Tag(tags)) if tags.is_empty()));
+
+        let result = handle_slash_command("/note  Has the migration plan ");
+        assert!(
+            matches!(result, Some(InputResult::Note(note)) if note == "Has the migration plan")
+        );
+
+        // Not a prefix of other words
+        assert!(handle_slash_command("/tagged").is_none());
+    }
 }
@@ -0,0 +1,504 @@
+use console::{measure_text_width, pad_str, Alignment as Align, Color, Style};
+use pulldown_cmark::{
+    Alignment, CodeBlockKind, Event, HeadingLevel, LinkType, Options, Parser, Tag,
+};
+use std::sync::LazyLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+use super::output::Theme;
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+impl Theme {
+    /// The syntect theme for code blocks, none for the ansi theme which sticks to the
+    /// terminal's own palette
+    fn code_theme(&self) -> Option<&'static str> {
+        match self {
+            Theme::Light => Some("InspiredGitHub"),
+            Theme::Dark => Some("base16-ocean.dark"),
+            Theme::Ansi => None,
+        }
+    }
+}
+
+/// Inline formatting in effect, nested emphasis adds up
+#[derive(Clone, Copy, Default)]
+struct Inline {
+    bold: bool,
+    italic: bool,
+    strikethrough: bool,
+    underlined: bool,
+    color: Option<Color>,
+}
+
+impl Inline {
+    fn style(&self) -> Style {
+        let mut style = Style::new();
+        if self.bold {
+            style = style.bold();
+        }
+        if self.italic {
+            style = style.italic();
+        }
+        if self.strikethrough {
+            style = style.strikethrough();
+        }
+        if self.underlined {
+            style = style.underlined();
+        }
+        if let Some(color) = self.color {
+            style = style.fg(color);
+        }
+        style
+    }
+}
+
+#[derive(Default)]
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<String>>,
+    /// Rows of the header, drawn above a separator
+    head_rows: usize,
+    cell: Option<String>,
+}
+
+struct Renderer {
+    out: String,
+    theme: Theme,
+    colored: bool,
+    inline: Vec<Inline>,
+    /// Open lists, with the number of the next item for ordered ones
+    lists: Vec<Option<u64>>,
+    quote_depth: usize,
+    line_start: bool,
+    code: Option<(String, String)>,
+    table: Option<Table>,
+    /// Urls of the open links and images, unset where the text already shows the url
+    links: Vec<Option<String>>,
+}
+
+impl Renderer {
+    fn new(theme: Theme, colored: bool) -> Self {
+        Self {
+            out: String::new(),
+            theme,
+            colored,
+            inline: vec![Inline::default()],
+            lists: Vec::new(),
+            quote_depth: 0,
+            line_start: true,
+            code: None,
+            table: None,
+            links: Vec::new(),
+        }
+    }
+
+    fn paint(&self, text: &str, style: Style) -> String {
+        if self.colored {
+            style.force_styling(true).apply_to(text).to_string()
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// What starts each line, quote bars and the indentation of list items
+    fn prefix(&self) -> String {
+        let bars = self.paint(&"│ ".repeat(self.quote_depth), Style::new().dim());
+        format!("{}{}", bars, "   ".repeat(self.lists.len()))
+    }
+
+    fn write(&mut self, text: &str) {
+        if let Some(cell) = self.table.as_mut().and_then(|table| table.cell.as_mut()) {
+            cell.push_str(text);
+            return;
+        }
+        if self.line_start && !text.is_empty() {
+            self.out.push_str(&self.prefix());
+            self.line_start = false;
+        }
+        self.out.push_str(text);
+    }
+
+    fn write_styled(&mut self, text: &str) {
+        let style = self.inline.last().copied().unwrap_or_default().style();
+        let styled = self.paint(text, style);
+        self.write(&styled);
+    }
+
+    fn newline(&mut self) {
+        if self
+            .table
+            .as_ref()
+            .is_some_and(|table| table.cell.is_some())
+        {
+            self.write(" ");
+            return;
+        }
+        self.out.push('\n');
+        self.line_start = true;
+    }
+
+    fn end_line(&mut self) {
+        if !self.line_start {
+            self.newline();
+        }
+    }
+
+    /// Close a block, leaving a blank line after it unless it sits in a list item
+    fn end_block(&mut self) {
+        self.end_line();
+        if self.lists.is_empty() {
+            self.newline();
+        }
+    }
+
+    fn push_inline(&mut self, change: impl FnOnce(&mut Inline)) {
+        let mut inline = self.inline.last().copied().unwrap_or_default();
+        change(&mut inline);
+        self.inline.push(inline);
+    }
+
+    fn pop_inline(&mut self) {
+        if self.inline.len() > 1 {
+            self.inline.pop();
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading(level, _, _) => {
+                self.end_line();
+                let color = match level {
+                    HeadingLevel::H1 | HeadingLevel::H2 => Some(Color::Cyan),
+                    _ => None,
+                };
+                let underlined = level == HeadingLevel::H1;
+                self.push_inline(|inline| {
+                    inline.bold = true;
+                    inline.underlined = underlined;
+                    inline.color = color;
+                });
+            }
+            Tag::BlockQuote => {
+                self.end_line();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.end_line();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((language, String::new()));
+            }
+            Tag::List(start) => {
+                self.end_line();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.end_line();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "•".to_string(),
+                };
+                // The item's own marker takes the place of its indentation
+                let depth = self.lists.len().saturating_sub(1);
+                let bars = self.paint(&"│ ".repeat(self.quote_depth), Style::new().dim());
+                let marker = self.paint(&format!("{:<3}", marker), Style::new().dim());
+                self.out
+                    .push_str(&format!("{}{}{}", bars, "   ".repeat(depth), marker));
+                self.line_start = false;
+            }
+            Tag::Table(alignments) => {
+                self.end_line();
+                self.table = Some(Table {
+                    alignments,
+                    ..Default::default()
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = self.table.as_mut() {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(table) = self.table.as_mut() {
+                    table.cell = Some(String::new());
+                }
+            }
+            Tag::Emphasis => self.push_inline(|inline| inline.italic = true),
+            Tag::Strong => self.push_inline(|inline| inline.bold = true),
+            Tag::Strikethrough => self.push_inline(|inline| inline.strikethrough = true),
+            Tag::Link(link_type, url, _) => {
+                let shown = matches!(link_type, LinkType::Autolink | LinkType::Email);
+                self.links.push(Some(url.to_string()).filter(|_| !shown));
+                self.push_inline(|inline| {
+                    inline.underlined = true;
+                    inline.color = Some(Color::Blue);
+                });
+            }
+            Tag::Image(_, url, _) => {
+                self.links.push(Some(url.to_string()));
+                self.write_styled("[image: ");
+            }
+            Tag::Paragraph | Tag::FootnoteDefinition(_) => {}
+        }
+    }
+
+    fn end(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading(..) => {
+                self.pop_inline();
+                self.end_block();
+            }
+            Tag::Paragraph => self.end_block(),
+            Tag::BlockQuote => {
+                self.end_line();
+                self.quote_depth -= 1;
+                if self.quote_depth == 0 {
+                    self.end_block();
+                }
+            }
+            Tag::CodeBlock(_) => {
+                if let Some((language, code)) = self.code.take() {
+                    self.write_code(&language, &code);
+                }
+                self.end_block();
+            }
+            Tag::List(_) => {
+                self.lists.pop();
+                self.end_line();
+                if self.lists.is_empty() {
+                    self.newline();
+                }
+            }
+            Tag::Item => self.end_line(),
+            Tag::Table(_) => {
+                if let Some(table) = self.table.take() {
+                    self.write_table(table);
+                }
+                self.newline();
+            }
+            Tag::TableHead => {
+                if let Some(table) = self.table.as_mut() {
+                    table.head_rows = table.rows.len();
+                }
+            }
+            Tag::TableCell => {
+                if let Some(table) = self.table.as_mut() {
+                    let cell = table.cell.take().unwrap_or_default();
+                    if let Some(row) = table.rows.last_mut() {
+                        row.push(cell.trim().to_string());
+                    }
+                }
+            }
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough => self.pop_inline(),
+            Tag::Link(..) => {
+                self.pop_inline();
+                if let Some(url) = self.links.pop().flatten() {
+                    let url = self.paint(&format!(" ({})", url), Style::new().dim());
+                    self.write(&url);
+                }
+            }
+            Tag::Image(..) => {
+                let url = self.links.pop().flatten().unwrap_or_default();
+                self.write_styled(&format!("]({})", url));
+            }
+            Tag::TableRow | Tag::FootnoteDefinition(_) => {}
+        }
+    }
+
+    fn write_code(&mut self, language: &str, code: &str) {
+        let highlighter = self
+            .theme
+            .code_theme()
+            .filter(|_| self.colored)
+            .and_then(|name| THEMES.themes.get(name))
+            .map(|theme| {
+                let syntax = SYNTAXES
+                    .find_syntax_by_token(language)
+                    .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text());
+                HighlightLines::new(syntax, theme)
+            });
+
+        match highlighter {
+            Some(mut highlighter) => {
+                for line in LinesWithEndings::from(code) {
+                    let highlighted = highlighter
+                        .highlight_line(line, &SYNTAXES)
+                        .map(|ranges| as_24_bit_terminal_escaped(&ranges, false))
+                        .unwrap_or_else(|_| line.to_string());
+                    self.write(highlighted.trim_end_matches('\n'));
+                    self.write("\x1b[0m");
+                    self.newline();
+                }
+            }
+            None => {
+                for line in code.lines() {
+                    let line = self.paint(line, Style::new().yellow());
+                    self.write(&line);
+                    self.newline();
+                }
+            }
+        }
+    }
+
+    fn write_table(&mut self, table: Table) {
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                table
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| measure_text_width(cell))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let border = Style::new().dim();
+
+        for (index, row) in table.rows.iter().enumerate() {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(column, width)| {
+                    let cell = row.get(column).map(String::as_str).unwrap_or("");
+                    let align = match table.alignments.get(column) {
+                        Some(Alignment::Right) => Align::Right,
+                        Some(Alignment::Center) => Align::Center,
+                        _ => Align::Left,
+                    };
+                    let cell = pad_str(cell, *width, align, None).to_string();
+                    if index < table.head_rows {
+                        self.paint(&cell, Style::new().bold())
+                    } else {
+                        cell
+                    }
+                })
+                .collect();
+            let separator = self.paint(" │ ", border.clone());
+            self.write(&cells.join(&separator));
+            self.newline();
+
+            if index + 1 == table.head_rows {
+                let rule: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+                let rule = self.paint(&rule.join("─┼─"), border.clone());
+                self.write(&rule);
+                self.newline();
+            }
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => match self.code.as_mut() {
+                Some((_, code)) => code.push_str(&text),
+                None => {
+                    // Text can span lines, each needs the prefix of the block it sits in
+                    for (index, line) in text.split('\n').enumerate() {
+                        if index > 0 {
+                            self.newline();
+                        }
+                        self.write_styled(line);
+                    }
+                }
+            },
+            Event::Code(code) => {
+                let code = self.paint(&code, Style::new().yellow());
+                self.write(&code);
+            }
+            Event::Html(html) => self.write(html.trim_end_matches('\n')),
+            Event::FootnoteReference(name) => self.write_styled(&format!("[^{}]", name)),
+            Event::SoftBreak | Event::HardBreak => self.newline(),
+            Event::Rule => {
+                self.end_line();
+                let rule = self.paint(&"─".repeat(40), Style::new().dim());
+                self.write(&rule);
+                self.end_block();
+            }
+            Event::TaskListMarker(checked) => {
+                self.write(if checked { "[x] " } else { "[ ] " });
+            }
+        }
+    }
+}
+
+/// Render markdown for the terminal, with headings, lists, quotes and tables laid out and
+/// fenced code highlighted for its language. Without `colored` only the layout is kept.
+pub fn render_markdown(content: &str, theme: Theme, colored: bool) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut renderer = Renderer::new(theme, colored);
+    for event in Parser::new_ext(content, options) {
+        renderer.event(event);
+    }
+    let mut out = renderer.out;
+    out.truncate(out.trim_end().len());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(content: &str) -> String {
+        render_markdown(content, Theme::Dark, false)
+    }
+
+    #[test]
+    fn test_blocks() {
+        let rendered = plain("# Title\n\nSome **bold** and `code`.\n\n---\n\nAfter");
+        assert_eq!(
+            rendered,
+            format!(
+                "Title\n\nSome bold and code.\n\n{}\n\nAfter",
+                "─".repeat(40)
+            )
+        );
+        assert_eq!(plain("> quoted\n> twice"), "│ quoted\n│ twice");
+        assert_eq!(
+            plain("See [the docs](https://example.com) or <https://example.com>"),
+            "See the docs (https://example.com) or https://example.com"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        assert_eq!(
+            plain("- one\n- two\n  - nested\n\n3. three\n4. four"),
+            "•  one\n•  two\n   •  nested\n\n3. three\n4. four"
+        );
+        assert_eq!(plain("- [x] done\n- [ ] todo"), "•  [x] done\n•  [ ] todo");
+    }
+
+    #[test]
+    fn test_table() {
+        let rendered = plain("| name | size |\n|---|--:|\n| a | 1 |\n| longer | 100 |");
+        assert_eq!(
+            rendered,
+            "name   │ size\n───────┼─────\na      │    1\nlonger │  100"
+        );
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let code = "```rust\nfn main() {}\n```\n\nafter";
+        assert_eq!(plain(code), "fn main() {}\n\nafter");
+
+        let highlighted = render_markdown(code, Theme::Dark, true);
+        assert!(highlighted.contains("\x1b[38;2;"));
+        assert!(console::strip_ansi_codes(&highlighted).contains("fn main() {}"));
+    }
+}
@@ -1,34 +1,43 @@
 mod builder;
 mod completion;
+mod editor;
 mod export;
 mod input;
+mod markdown;
 mod output;
 mod prompt;
 mod thinking;
 
 pub use self::export::message_to_markdown;
+pub use self::output::{render_diff, set_plain};
 pub use builder::{build_session, SessionBuilderConfig};
 use console::Color;
+use goose::agents::timeouts::TimeoutAction;
 use goose::agents::AgentEvent;
 use goose::permission::permission_confirmation::PrincipalType;
 use goose::permission::Permission;
 use goose::permission::PermissionConfirmation;
-use goose::providers::base::Provider;
+use goose::providers::base::{Provider, ResponseTiming};
 pub use goose::session::Identifier;
 
+use crate::prompt_library::SavedPrompt;
 use anyhow::{Context, Result};
 use completion::GooseCompleter;
-use etcetera::{choose_app_strategy, AppStrategy};
+use goose::agents::ask_user::{user_answer, AskUserPolicy, UserQuestion};
+use goose::agents::change_summary::{change_summary_enabled, git_root, ChangedFile};
 use goose::agents::extension::{Envs, ExtensionConfig};
 use goose::agents::{Agent, SessionConfig};
 use goose::config::Config;
+use goose::context_mgmt::pinned::is_within;
 use goose::message::{Message, MessageContent};
 use goose::session;
+use goose_mcp::edit_journal;
 use input::InputResult;
-use mcp_core::handler::ToolError;
-use mcp_core::prompt::PromptMessage;
+use mcp_core::handler::{ToolError, ToolResult};
+use mcp_core::prompt::{PromptArgument, PromptMessage};
 use mcp_core::protocol::JsonRpcMessage;
 use mcp_core::protocol::JsonRpcNotification;
+use mcp_core::Content;
 
 use rand::{distributions::Alphanumeric, Rng};
 use serde_json::Value;
@@ -38,6 +47,33 @@ use std::sync::Arc;
 use std::time::Instant;
 use tokio;
 
+/// What a headless run handed a session with `/handoff` is asked to do, unless told otherwise
+const HANDOFF_PROMPT: &str =
+    "Continue working on the task from where the conversation left off, without waiting for input.";
+
+/// The group `/prompts` lists prompts saved with `goose prompt save` under
+const SAVED_PROMPTS: &str = "saved prompts";
+
+/// Saved prompts take their variables as required arguments
+fn saved_prompt_info(prompt: SavedPrompt) -> output::PromptInfo {
+    output::PromptInfo {
+        arguments: Some(
+            prompt
+                .variables()
+                .into_iter()
+                .map(|name| PromptArgument {
+                    name,
+                    description: None,
+                    required: Some(true),
+                })
+                .collect(),
+        ),
+        description: Some(prompt.text),
+        name: prompt.name,
+        extension: Some(SAVED_PROMPTS.to_string()),
+    }
+}
+
 pub enum RunMode {
     Normal,
     Plan,
@@ -51,6 +87,11 @@ pub struct Session {
     completion_cache: Arc<std::sync::RwLock<CompletionCache>>,
     debug: bool, // New field for debug mode
     run_mode: RunMode,
+    budget_warned: bool,
+    /// An exception that ended the last reply, see `last_error`
+    run_error: Option<String>,
+    /// Why a headless reply was paused part way, see `pause_reason`
+    pause_reason: Option<String>,
 }
 
 // Cache structure for completion data
@@ -108,6 +149,13 @@ pub async fn classify_planner_response(
 
 impl Session {
     pub fn new(agent: Agent, session_file: PathBuf, debug: bool) -> Self {
+        // Keep what arrived of a response that was still streaming when goose last stopped
+        match session::recover_partial_message(&session_file) {
+            Ok(Some(_)) => eprintln!("Recovered an incomplete response from the previous run"),
+            Ok(None) => {}
+            Err(e) => eprintln!("Warning: Failed to recover incomplete response: {}", e),
+        }
+
         let messages = match session::read_messages(&session_file) {
             Ok(msgs) => msgs,
             Err(e) => {
@@ -123,6 +171,9 @@ impl Session {
             completion_cache: Arc::new(std::sync::RwLock::new(CompletionCache::new())),
             debug,
             run_mode: RunMode::Normal,
+            budget_warned: false,
+            run_error: None,
+            pause_reason: None,
         }
     }
 
@@ -182,6 +233,9 @@ impl Session {
             // TODO: should set timeout
             timeout: Some(goose::config::DEFAULT_EXTENSION_TIMEOUT),
             bundled: None,
+            cwd: None,
+            inherit_env: None,
+            path_prepend: Vec::new(),
         };
 
         self.agent
@@ -228,6 +282,66 @@ impl Session {
         Ok(())
     }
 
+    /// Restart a running extension to pick up changes to its server or its configuration
+    ///
+    /// # Arguments
+    /// * `name` - Name of the running extension
+    pub async fn reload_extension(&mut self, name: &str) -> Result<usize> {
+        let tools = self
+            .agent
+            .reload_extension(name)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to reload extension: {}", e))?;
+
+        // The extension's tools and prompts may have changed
+        self.invalidate_completion_cache().await;
+
+        Ok(tools.len())
+    }
+
+    /// Save where the session got to, with its extensions, so it can be continued in the
+    /// other mode with `--resume`
+    pub async fn checkpoint(&self, paused_from: session::HandoffMode, reason: &str) -> Result<()> {
+        let checkpoint =
+            session::RunCheckpoint::new(paused_from, reason, self.agent.extension_configs().await);
+        session::checkpoint::write_checkpoint(&self.session_file, &checkpoint)
+    }
+
+    /// Hand the session over to a headless `goose run` in the background, which picks it up
+    /// from a checkpoint with the same extensions. Returns the pid of the run and its log file.
+    pub async fn handoff(&mut self, instructions: Option<String>) -> Result<(u32, PathBuf)> {
+        if !self.session_file.exists() {
+            anyhow::bail!("Nothing to hand off yet, send a message first");
+        }
+        self.checkpoint(session::HandoffMode::Interactive, "handed off")
+            .await?;
+
+        let log_path = self.session_file.with_extension("log");
+        let log = std::fs::File::create(&log_path)?;
+        let mut command = std::process::Command::new(std::env::current_exe()?);
+        command
+            .arg("run")
+            .arg("--resume")
+            .arg("--path")
+            .arg(&self.session_file)
+            .arg("--text")
+            .arg(instructions.unwrap_or_else(|| HANDOFF_PROMPT.to_string()))
+            .stdin(std::process::Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log);
+        // Keep Ctrl+C in this terminal from reaching the run once it is on its own
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+        match command.spawn() {
+            Ok(child) => Ok((child.id(), log_path)),
+            Err(e) => {
+                session::checkpoint::take_checkpoint(&self.session_file)?;
+                Err(anyhow::anyhow!("Failed to start the headless run: {}", e))
+            }
+        }
+    }
+
     /// Add a builtin extension to the session
     ///
     /// # Arguments
@@ -267,14 +381,35 @@ impl Session {
         }
 
         // Convert prompts into filtered map of extension names to prompt names
-        Ok(prompts
+        let mut prompts: HashMap<String, Vec<String>> = prompts
             .into_iter()
             .filter(|(ext, _)| extension.as_ref().is_none_or(|f| f == ext))
             .map(|(extension, prompt_list)| {
                 let names = prompt_list.into_iter().map(|p| p.name).collect();
                 (extension, names)
             })
-            .collect())
+            .collect();
+
+        // Prompts saved with `goose prompt save` are listed alongside the extensions' prompts
+        if extension.is_none() {
+            let saved: Vec<String> = crate::prompt_library::list_prompts()?
+                .into_iter()
+                .map(|p| p.name)
+                .collect();
+            if !saved.is_empty() {
+                prompts.insert(SAVED_PROMPTS.to_string(), saved);
+            }
+        }
+        Ok(prompts)
+    }
+
+    /// A prompt saved with `goose prompt save`, unless an extension has a prompt of that name
+    async fn saved_prompt(&self, name: &str) -> Result<Option<SavedPrompt>> {
+        let prompts = self.agent.list_extension_prompts().await;
+        if prompts.values().flatten().any(|p| p.name == name) {
+            return Ok(None);
+        }
+        crate::prompt_library::load_prompt(name)
     }
 
     pub async fn get_prompt_info(&mut self, name: &str) -> Result<Option<output::PromptInfo>> {
@@ -292,7 +427,7 @@ impl Session {
             }
         }
 
-        Ok(None)
+        Ok(self.saved_prompt(name).await?.map(saved_prompt_info))
     }
 
     pub async fn get_prompt(&mut self, name: &str, arguments: Value) -> Result<Vec<PromptMessage>> {
@@ -325,7 +460,9 @@ impl Session {
             );
         }
 
+        let usage_before = self.get_metadata().ok();
         self.process_agent_response(false).await?;
+        self.record_usage(usage_before).await;
         Ok(())
     }
 
@@ -339,50 +476,12 @@ impl Session {
         // Initialize the completion cache
         self.update_completion_cache().await?;
 
-        // Create a new editor with our custom completer
-        let config = rustyline::Config::builder()
-            .completion_type(rustyline::CompletionType::Circular)
-            .build();
-        let mut editor =
-            rustyline::Editor::<GooseCompleter, rustyline::history::DefaultHistory>::with_config(
-                config,
-            )?;
-
-        // Set up the completer with a reference to the completion cache
+        // Create a new editor with our custom completer and the user's line editing settings
         let completer = GooseCompleter::new(self.completion_cache.clone());
-        editor.set_helper(Some(completer));
-
-        // Create and use a global history file in ~/.config/goose directory
-        // This allows command history to persist across different chat sessions
-        // instead of being tied to each individual session's messages
-        let strategy =
-            choose_app_strategy(crate::APP_STRATEGY.clone()).expect("goose requires a home dir");
-        let config_dir = strategy.config_dir();
-        let history_file = config_dir.join("history.txt");
-
-        // Ensure config directory exists
-        if let Some(parent) = history_file.parent() {
-            if !parent.exists() {
-                std::fs::create_dir_all(parent)?;
-            }
-        }
-
-        // Load history from the global file
-        if history_file.exists() {
-            if let Err(err) = editor.load_history(&history_file) {
-                eprintln!("Warning: Failed to load command history: {}", err);
-            }
-        }
-
-        // Helper function to save history after commands
-        let save_history =
-            |editor: &mut rustyline::Editor<GooseCompleter, rustyline::history::DefaultHistory>| {
-                if let Err(err) = editor.save_history(&history_file) {
-                    eprintln!("Warning: Failed to save command history: {}", err);
-                }
-            };
+        let mut editor = editor::EditorSettings::from_config()?.build(completer)?;
 
         output::display_greeting();
+        self.warn_if_over_budget();
         loop {
             // Display context usage before each prompt
             self.display_context_usage().await?;
@@ -391,7 +490,7 @@ impl Session {
                 input::InputResult::Message(content) => {
                     match self.run_mode {
                         RunMode::Normal => {
-                            save_history(&mut editor);
+                            editor::save_history(&mut editor);
 
                             self.messages.push(Message::user().with_text(&content));
 
@@ -420,9 +519,11 @@ impl Session {
                             )
                             .await?;
 
+                            let usage_before = self.get_metadata().ok();
                             output::show_thinking();
                             self.process_agent_response(true).await?;
                             output::hide_thinking();
+                            self.record_usage(usage_before).await;
                         }
                         RunMode::Plan => {
                             let mut plan_messages = self.messages.clone();
@@ -435,7 +536,7 @@ impl Session {
                 }
                 input::InputResult::Exit => break,
                 input::InputResult::AddExtension(cmd) => {
-                    save_history(&mut editor);
+                    editor::save_history(&mut editor);
 
                     match self.add_extension(cmd.clone()).await {
                         Ok(_) => output::render_extension_success(&cmd),
@@ -443,15 +544,37 @@ impl Session {
                     }
                 }
                 input::InputResult::AddBuiltin(names) => {
-                    save_history(&mut editor);
+                    editor::save_history(&mut editor);
 
                     match self.add_builtin(names.clone()).await {
                         Ok(_) => output::render_builtin_success(&names),
                         Err(e) => output::render_builtin_error(&names, &e.to_string()),
                     }
                 }
+                input::InputResult::ListExtensions => {
+                    editor::save_history(&mut editor);
+
+                    let mut extensions = self.agent.list_extensions().await;
+                    extensions.sort();
+                    if extensions.is_empty() {
+                        println!("No extensions are running");
+                    }
+                    for name in extensions {
+                        println!("- {}", name);
+                    }
+                    continue;
+                }
+                input::InputResult::ReloadExtension(name) => {
+                    editor::save_history(&mut editor);
+
+                    match self.reload_extension(&name).await {
+                        Ok(tools) => output::render_extension_reloaded(&name, tools),
+                        Err(e) => output::render_error(&e.to_string()),
+                    }
+                    continue;
+                }
                 input::InputResult::ToggleTheme => {
-                    save_history(&mut editor);
+                    editor::save_history(&mut editor);
 
                     let current = output::get_theme();
                     let new_theme = match current {
@@ -472,8 +595,112 @@ impl Session {
                     continue;
                 }
                 input::InputResult::Retry => continue,
+                input::InputResult::Tag(changes) => {
+                    editor::save_history(&mut editor);
+
+                    let tags = if changes.is_empty() {
+                        self.editable_metadata().map(|metadata| metadata.tags)
+                    } else {
+                        self.update_tags(&changes).await
+                    };
+                    match tags {
+                        Ok(tags) if tags.is_empty() => println!("This session has no tags"),
+                        Ok(tags) => println!(
+                            "Tags: {}",
+                            tags.iter()
+                                .map(|tag| format!("#{}", tag))
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        ),
+                        Err(e) => output::render_error(&e.to_string()),
+                    }
+                    continue;
+                }
+                input::InputResult::Handoff(instructions) => {
+                    editor::save_history(&mut editor);
+
+                    match self.handoff(instructions).await {
+                        Ok((pid, log)) => {
+                            let session_id = self
+                                .session_file
+                                .file_stem()
+                                .and_then(|s| s.to_str())
+                                .unwrap_or_default()
+                                .to_string();
+                            output::render_handoff(&session_id, pid, &log);
+                            break;
+                        }
+                        Err(e) => output::render_error(&e.to_string()),
+                    }
+                    continue;
+                }
+                input::InputResult::Context => {
+                    editor::save_history(&mut editor);
+
+                    if let Err(e) = self.display_context_breakdown(&self.messages).await {
+                        output::render_error(&e.to_string());
+                    }
+                    continue;
+                }
+                input::InputResult::Pin(path) => {
+                    editor::save_history(&mut editor);
+
+                    match path {
+                        Some(path) => match self.pin(&path).await {
+                            Ok(true) => {
+                                println!("{}", console::style(format!("Pinned {}", path)).green())
+                            }
+                            Ok(false) => println!("{} is already pinned", path),
+                            Err(e) => output::render_error(&e.to_string()),
+                        },
+                        None => {
+                            let pinned = self.agent.pinned_paths().await;
+                            if pinned.is_empty() {
+                                println!("Nothing is pinned")
+                            }
+                            for path in pinned {
+                                println!("- {}", path.display());
+                            }
+                        }
+                    }
+                    continue;
+                }
+                input::InputResult::Unpin(path) => {
+                    editor::save_history(&mut editor);
+
+                    match self.unpin(&path).await {
+                        Ok(true) => {
+                            println!("{}", console::style(format!("Unpinned {}", path)).green())
+                        }
+                        Ok(false) => output::render_error(&format!("{} is not pinned", path)),
+                        Err(e) => output::render_error(&e.to_string()),
+                    }
+                    continue;
+                }
+                input::InputResult::Note(note) => {
+                    editor::save_history(&mut editor);
+
+                    if note.is_empty() {
+                        match self.editable_metadata() {
+                            Ok(metadata) if metadata.notes.is_empty() => {
+                                println!("This session has no notes")
+                            }
+                            Ok(metadata) => {
+                                for note in metadata.notes {
+                                    println!("- {}", note);
+                                }
+                            }
+                            Err(e) => output::render_error(&e.to_string()),
+                        }
+                    } else if let Err(e) = self.add_note(note).await {
+                        output::render_error(&e.to_string());
+                    } else {
+                        println!("{}", console::style("Note added").green());
+                    }
+                    continue;
+                }
                 input::InputResult::ListPrompts(extension) => {
-                    save_history(&mut editor);
+                    editor::save_history(&mut editor);
 
                     match self.list_prompts(extension).await {
                         Ok(prompts) => output::render_prompts(&prompts),
@@ -481,7 +708,7 @@ impl Session {
                     }
                 }
                 input::InputResult::GooseMode(mode) => {
-                    save_history(&mut editor);
+                    editor::save_history(&mut editor);
 
                     let config = Config::global();
                     let mode = mode.to_lowercase();
@@ -522,7 +749,7 @@ impl Session {
                     continue;
                 }
                 input::InputResult::PromptCommand(opts) => {
-                    save_history(&mut editor);
+                    editor::save_history(&mut editor);
                     self.handle_prompt_command(opts).await?;
                 }
                 InputResult::Recipe(filepath_opt) => {
@@ -559,7 +786,7 @@ impl Session {
                     continue;
                 }
                 InputResult::Summarize => {
-                    save_history(&mut editor);
+                    editor::save_history(&mut editor);
 
                     let prompt = "Are you sure you want to summarize this conversation? This will condense the message history.";
                     let should_summarize =
@@ -701,12 +928,92 @@ impl Session {
         Ok(())
     }
 
+    /// Append the tokens used since `before` to the usage log and check the monthly budget
+    async fn record_usage(&mut self, before: Option<session::SessionMetadata>) {
+        let Ok(after) = self.get_metadata() else {
+            return;
+        };
+        let before = before.unwrap_or_default();
+        let delta = |after: Option<i32>, before: Option<i32>| {
+            (after.unwrap_or(0) - before.unwrap_or(0)).max(0) as i64
+        };
+        let input_tokens = delta(
+            after.accumulated_input_tokens,
+            before.accumulated_input_tokens,
+        );
+        let output_tokens = delta(
+            after.accumulated_output_tokens,
+            before.accumulated_output_tokens,
+        );
+        let total_tokens = delta(
+            after.accumulated_total_tokens,
+            before.accumulated_total_tokens,
+        );
+        if total_tokens == 0 && input_tokens == 0 && output_tokens == 0 {
+            return;
+        }
+
+        let Ok(provider) = self.agent.provider().await else {
+            return;
+        };
+        let session_id = self
+            .session_file
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_string());
+        let provider_name = Config::global()
+            .get_param::<String>("GOOSE_PROVIDER")
+            .unwrap_or_else(|_| "unknown".to_string());
+        let new_usage = after
+            .message_usage
+            .get(before.message_usage.len()..)
+            .unwrap_or_default();
+        let records = if new_usage.is_empty() {
+            vec![crate::log_usage::UsageRecord {
+                timestamp: chrono::Utc::now(),
+                session_id,
+                provider: provider_name,
+                model: provider.get_model_config().model_name,
+                input_tokens,
+                output_tokens,
+                total_tokens,
+                saved_usd: None,
+            }]
+        } else {
+            // Responses are attributed to the model that produced them, which differs per
+            // turn with tiered models
+            crate::log_usage::records_by_model(new_usage, session_id, &provider_name)
+        };
+        for record in records {
+            if let Err(e) = crate::log_usage::log_usage(&record) {
+                eprintln!("Warning: Failed to record usage: {}", e);
+            }
+        }
+
+        self.warn_if_over_budget();
+    }
+
+    /// Show the budget banner once per session when the monthly budget is exceeded
+    fn warn_if_over_budget(&mut self) {
+        if self.budget_warned {
+            return;
+        }
+        if let Ok(Some((spent, budget))) = crate::log_usage::check_budget() {
+            output::render_budget_warning(spent, budget);
+            self.budget_warned = true;
+        }
+    }
+
     /// Process a single message and exit
     pub async fn headless(&mut self, message: String) -> Result<()> {
         self.process_message(message).await
     }
 
     async fn process_agent_response(&mut self, interactive: bool) -> Result<()> {
+        self.run_error = None;
+        self.pause_reason = None;
+        let started_at = chrono::Utc::now();
+        let mut finished = false;
         let session_id = session::Identifier::Path(self.session_file.clone());
         let mut stream = self
             .agent
@@ -722,6 +1029,7 @@ impl Session {
             .await?;
 
         let mut progress_bars = output::McpSpinners::new();
+        let mut replacement_explained = false;
 
         use futures::StreamExt;
         loop {
@@ -733,8 +1041,16 @@ impl Session {
                             if let Some(MessageContent::ToolConfirmationRequest(confirmation)) = message.content.first() {
                                 output::hide_thinking();
 
+                                if let Some(diff) = &confirmation.diff {
+                                    output::render_diff(diff);
+                                }
+
                                 // Format the confirmation prompt
-                                let prompt = "Goose would like to call the above tool, do you allow?".to_string();
+                                let prompt = if confirmation.destructive {
+                                    "Goose would like to call the above tool, which is marked as destructive. Do you allow?".to_string()
+                                } else {
+                                    "Goose would like to call the above tool, do you allow?".to_string()
+                                };
 
                                 // Get confirmation from user
                                 let permission_result = cliclack::select(prompt)
@@ -775,6 +1091,56 @@ impl Session {
                                         permission,
                                     },).await;
                                 }
+                            } else if let Some(MessageContent::FrontendToolRequest(request)) = message.content.first() {
+                                // Dry runs in prompt mode ask for the result of intercepted tools,
+                                // which is answered here rather than persisted
+                                output::hide_thinking();
+                                let call = match &request.tool_call {
+                                    Ok(call) => call,
+                                    Err(e) => {
+                                        self.agent.handle_tool_result(request.id.clone(), Err(e.clone())).await;
+                                        continue;
+                                    }
+                                };
+                                if let Some(question) = UserQuestion::from_call(call) {
+                                    let _ = progress_bars.hide();
+                                    match answer_question(&question, interactive)? {
+                                        Some(answer) => {
+                                            self.agent.handle_tool_result(request.id.clone(), answer).await;
+                                        }
+                                        None => {
+                                            drop(stream);
+                                            self.pause_for_answer(&request.id, &question).await?;
+                                            break;
+                                        }
+                                    }
+                                    if interactive {output::show_thinking()};
+                                    continue;
+                                }
+                                output::render_text(
+                                    &format!("Dry run: {} was called with {}", call.name, call.arguments),
+                                    Some(Color::Yellow),
+                                    true,
+                                );
+                                let result = if interactive {
+                                    match cliclack::input("What should the tool return?")
+                                        .default_input("")
+                                        .required(false)
+                                        .interact::<String>()
+                                    {
+                                        Ok(text) => Ok(vec![Content::text(text)]),
+                                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => Err(
+                                            ToolError::ExecutionError("The user declined to provide a result".to_string()),
+                                        ),
+                                        Err(e) => return Err(e.into()),
+                                    }
+                                } else {
+                                    Err(ToolError::ExecutionError(
+                                        "No result was provided because the run is not interactive".to_string(),
+                                    ))
+                                };
+                                self.agent.handle_tool_result(request.id.clone(), result).await;
+                                if interactive {output::show_thinking()};
                             } else if let Some(MessageContent::ContextLengthExceeded(_)) = message.content.first() {
                                 output::hide_thinking();
 
@@ -913,6 +1279,55 @@ impl Session {
                                 }
                             }
                         }
+                        Some(Ok(AgentEvent::HistoryReplaced(history))) => {
+                            let collapsed = self.messages.len().saturating_sub(history.len()) + 1;
+                            self.messages = history;
+                            session::persist_messages(&self.session_file, &self.messages, None).await?;
+
+                            // A context recovery already said what was replaced
+                            if std::mem::take(&mut replacement_explained) {
+                                continue;
+                            }
+                            if interactive {output::hide_thinking()};
+                            let _ = progress_bars.hide();
+                            output::render_text(
+                                &format!("Summarized {} earlier messages to free up context.", collapsed),
+                                Some(Color::Yellow),
+                                true,
+                            );
+                            if interactive {output::show_thinking()};
+                        }
+                        Some(Ok(AgentEvent::Guardrail(event))) => {
+                            if interactive {output::hide_thinking()};
+                            let _ = progress_bars.hide();
+                            output::render_text(&event.summary(), Some(Color::Yellow), true);
+                            if interactive {output::show_thinking()};
+                        }
+                        Some(Ok(AgentEvent::ExtensionDisabled(event))) => {
+                            if interactive {output::hide_thinking()};
+                            let _ = progress_bars.hide();
+                            output::render_error(&event.summary());
+                            if interactive {output::show_thinking()};
+                        }
+                        Some(Ok(AgentEvent::ContextRecovered(event))) => {
+                            replacement_explained = true;
+                            if interactive {output::hide_thinking()};
+                            let _ = progress_bars.hide();
+                            output::render_text(&event.summary(), Some(Color::Yellow), true);
+                            if interactive {output::show_thinking()};
+                        }
+                        Some(Ok(AgentEvent::Timeout(timeout))) => {
+                            let _ = progress_bars.hide();
+                            output::render_error(&format!(
+                                "No response for '{}' after {}s, {}",
+                                timeout.tool_name,
+                                timeout.waited_secs,
+                                match timeout.action {
+                                    TimeoutAction::Deny => "it was not run",
+                                    TimeoutAction::Park => "the session was paused, send a message to continue",
+                                }
+                            ));
+                        }
                         Some(Err(e)) => {
                             eprintln!("Error: {}", e);
                             drop(stream);
@@ -925,9 +1340,13 @@ impl Session {
                                 We've removed the conversation up to the most recent user message\n\
                                 - depending on the error you may be able to continue",
                             );
+                            self.run_error = Some(e.to_string());
+                            break;
+                        }
+                        None => {
+                            finished = true;
                             break;
                         }
-                        None => break,
                     }
                 }
                 _ = tokio::signal::ctrl_c() => {
@@ -935,11 +1354,82 @@ impl Session {
                     if let Err(e) = self.handle_interrupted_messages(true).await {
                         eprintln!("Error handling interruption: {}", e);
                     }
+                    if !interactive {
+                        self.pause_reason = Some("interrupted".to_string());
+                    }
+                    break;
+                }
+                _ = session::checkpoint::pause_requested(&self.session_file), if !interactive => {
+                    drop(stream);
+                    if let Err(e) = self.handle_interrupted_messages(true).await {
+                        eprintln!("Error handling interruption: {}", e);
+                    }
+                    self.pause_reason = Some("pause requested".to_string());
                     break;
                 }
             }
         }
 
+        if finished && change_summary_enabled() {
+            if let Err(e) = self.append_change_summary(started_at, interactive).await {
+                tracing::warn!("Failed to summarize the changes of the run: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Append a summary of the files the reply changed inside the current git repository to
+    /// its final message, from the developer extension's edit journal
+    async fn append_change_summary(
+        &mut self,
+        since: chrono::DateTime<chrono::Utc>,
+        interactive: bool,
+    ) -> Result<()> {
+        let Some(name) = self.session_file.file_stem().and_then(|s| s.to_str()) else {
+            return Ok(());
+        };
+        let Some(root) = git_root(&std::env::current_dir()?) else {
+            return Ok(());
+        };
+        let root = root.canonicalize().unwrap_or(root);
+        let ends_with_reply = self
+            .messages
+            .last()
+            .is_some_and(|message| message.role == mcp_core::role::Role::Assistant);
+        if !ends_with_reply {
+            return Ok(());
+        }
+
+        let changes: Vec<ChangedFile> =
+            edit_journal::session_changes(&edit_journal::journal_root(), name, since)?
+                .into_iter()
+                .filter(|change| !change.is_noop() && change.path.starts_with(&root))
+                .map(|change| ChangedFile {
+                    after: std::fs::read(&change.path).ok(),
+                    path: change.path,
+                    before: change.before,
+                })
+                .collect();
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let Some(summary) = self.agent.summarize_changes(&root, &changes).await else {
+            return Ok(());
+        };
+
+        let rendered = summary.render();
+        if let Some(message) = self.messages.last_mut() {
+            message.content.push(MessageContent::text(rendered.clone()));
+        }
+        session::persist_messages(&self.session_file, &self.messages, None).await?;
+        if interactive {
+            output::hide_thinking();
+        }
+        output::render_message(&Message::assistant().with_text(rendered), self.debug);
+        if interactive {
+            output::show_thinking();
+        }
         Ok(())
     }
 
@@ -1017,6 +1507,17 @@ impl Session {
                             );
                         }
                         Some(_) => {
+                            // Part of the reply may have streamed in before the interruption
+                            if let Some(partial) =
+                                session::partial::take_partial_message(&self.session_file)?
+                            {
+                                self.messages.push(partial.clone());
+                                session::persist_messages(&self.session_file, &self.messages, None)
+                                    .await?;
+                                output::render_message(&partial, self.debug);
+                                return Ok(());
+                            }
+
                             // A real users message
                             self.messages.pop();
                             let prompt = "Interrupted before the model replied and removed the last message.";
@@ -1042,6 +1543,7 @@ impl Session {
     pub async fn update_completion_cache(&mut self) -> Result<()> {
         // Get fresh data
         let prompts = self.agent.list_extension_prompts().await;
+        let saved = crate::prompt_library::list_prompts().unwrap_or_default();
 
         // Update the cache with write lock
         let mut cache = self.completion_cache.write().unwrap();
@@ -1065,6 +1567,21 @@ impl Session {
             }
         }
 
+        // Extension prompts win over saved prompts of the same name
+        let saved: Vec<SavedPrompt> = saved
+            .into_iter()
+            .filter(|p| !cache.prompt_info.contains_key(&p.name))
+            .collect();
+        if !saved.is_empty() {
+            let names = saved.iter().map(|p| p.name.clone()).collect();
+            cache.prompts.insert(SAVED_PROMPTS.to_string(), names);
+            for prompt in saved {
+                cache
+                    .prompt_info
+                    .insert(prompt.name.clone(), saved_prompt_info(prompt));
+            }
+        }
+
         cache.last_updated = Instant::now();
         Ok(())
     }
@@ -1078,6 +1595,34 @@ impl Session {
         cache.last_updated = Instant::now();
     }
 
+    /// The error that ended the last reply early, None when it finished
+    pub async fn last_error(&self) -> Option<String> {
+        match &self.run_error {
+            Some(error) => Some(error.clone()),
+            None => self.agent.last_error().await,
+        }
+    }
+
+    /// Stop a headless reply on a question from the agent, so it is answered when resumed
+    async fn pause_for_answer(&mut self, request_id: &str, question: &UserQuestion) -> Result<()> {
+        self.messages.push(Message::user().with_tool_response(
+            request_id,
+            Ok(vec![Content::text(
+                "The run was paused until the user answers, their next message is the answer.",
+            )]),
+        ));
+        self.messages
+            .push(Message::assistant().with_text(&question.question));
+        session::persist_messages(&self.session_file, &self.messages, None).await?;
+        self.pause_reason = Some(format!("waiting for an answer to: {}", question.question));
+        Ok(())
+    }
+
+    /// Why the last headless reply was paused before it finished, by Ctrl+C or a pause request
+    pub fn pause_reason(&self) -> Option<&str> {
+        self.pause_reason.as_deref()
+    }
+
     pub fn message_history(&self) -> Vec<Message> {
         self.messages.clone()
     }
@@ -1116,12 +1661,87 @@ impl Session {
         session::read_metadata(&self.session_file)
     }
 
+    /// Metadata to edit, a session that hasn't been written yet starts from the current directory
+    fn editable_metadata(&self) -> Result<session::SessionMetadata> {
+        if self.session_file.exists() {
+            session::read_metadata(&self.session_file)
+        } else {
+            Ok(session::SessionMetadata::new(std::env::current_dir()?))
+        }
+    }
+
+    /// Add tags to the session, or remove those written as `-tag`, returning the tags it has now
+    pub async fn update_tags(&self, changes: &[String]) -> Result<Vec<String>> {
+        let mut metadata = self.editable_metadata()?;
+        for change in changes {
+            let (tag, remove) = match change.strip_prefix('-') {
+                Some(tag) => (tag, true),
+                None => (change.as_str(), false),
+            };
+            if session::SessionMetadata::normalize_tag(tag).is_none() {
+                return Err(anyhow::anyhow!("Invalid tag '{}'", change));
+            }
+            if remove {
+                metadata.remove_tag(tag);
+            } else {
+                metadata.add_tag(tag);
+            }
+        }
+        session::update_metadata(&self.session_file, &metadata).await?;
+        Ok(metadata.tags)
+    }
+
+    /// Append a note to the session
+    pub async fn add_note(&self, note: String) -> Result<()> {
+        let mut metadata = self.editable_metadata()?;
+        metadata.notes.push(note);
+        session::update_metadata(&self.session_file, &metadata).await
+    }
+
+    /// Pin a path relative to the working directory and save it with the session, returning
+    /// false if it was already pinned. Only paths in the session's working directory can be
+    /// pinned.
+    pub async fn pin(&self, path: &str) -> Result<bool> {
+        let path = resolve_pin_path(path)?;
+        let working_dir = self.editable_metadata()?.working_dir;
+        if !is_within(&path, &working_dir) {
+            return Err(anyhow::anyhow!(
+                "{} is outside the session's working directory {}",
+                path.display(),
+                working_dir.display()
+            ));
+        }
+        let pinned = self.agent.pin_path(path).await?;
+        self.save_pinned().await?;
+        Ok(pinned)
+    }
+
+    /// Unpin a path and save the change with the session, returning false if it wasn't pinned
+    pub async fn unpin(&self, path: &str) -> Result<bool> {
+        let path = resolve_pin_path(path)?;
+        let unpinned = self.agent.unpin_path(&path).await;
+        self.save_pinned().await?;
+        Ok(unpinned)
+    }
+
+    async fn save_pinned(&self) -> Result<()> {
+        let mut metadata = self.editable_metadata()?;
+        metadata.pinned = self.agent.pinned_paths().await;
+        session::update_metadata(&self.session_file, &metadata).await
+    }
+
     // Get the session's total token usage
     pub fn get_total_token_usage(&self) -> Result<Option<i32>> {
         let metadata = self.get_metadata()?;
         Ok(metadata.total_tokens)
     }
 
+    // Get the latency of the provider's last response
+    pub fn get_last_response_timing(&self) -> Result<Option<ResponseTiming>> {
+        let metadata = self.get_metadata()?;
+        Ok(metadata.last_response_timing)
+    }
+
     /// Display enhanced context usage with session totals
     pub async fn display_context_usage(&self) -> Result<()> {
         let provider = self.agent.provider().await?;
@@ -1133,15 +1753,29 @@ impl Session {
                 let total_tokens = metadata.total_tokens.unwrap_or(0) as usize;
 
                 output::display_context_usage(total_tokens, context_limit);
+                if let Some(timing) = metadata.last_response_timing {
+                    output::display_response_timing(&timing);
+                }
             }
             Err(_) => {
                 output::display_context_usage(0, context_limit);
             }
         }
+        if let Some(stats) = self.agent.tool_compression_stats().await {
+            output::display_tool_compression(&stats);
+        }
 
         Ok(())
     }
 
+    /// Show what fills the context window with `messages`, by the session's system prompt,
+    /// extensions and conversation
+    pub async fn display_context_breakdown(&self, messages: &[Message]) -> Result<()> {
+        let breakdown = self.agent.context_breakdown(messages).await?;
+        output::render_context_breakdown(&breakdown);
+        Ok(())
+    }
+
     /// Handle prompt command execution
     async fn handle_prompt_command(&mut self, opts: input::PromptCommandOptions) -> Result<()> {
         // name is required
@@ -1155,6 +1789,19 @@ impl Session {
                 Some(info) => output::render_prompt_info(&info),
                 None => output::render_error(&format!("Prompt '{}' not found", opts.name)),
             }
+        } else if let Some(saved) = self.saved_prompt(&opts.name).await? {
+            match saved.render(&opts.arguments) {
+                Ok(text) => {
+                    let msg = Message::user().with_text(&text);
+                    output::render_message(&msg, self.debug);
+                    self.messages.push(msg);
+
+                    output::show_thinking();
+                    self.process_agent_response(true).await?;
+                    output::hide_thinking();
+                }
+                Err(e) => output::render_error(&e.to_string()),
+            }
         } else {
             // Convert the arguments HashMap to a Value
             let arguments = serde_json::to_value(opts.arguments)
@@ -1247,6 +1894,63 @@ impl Session {
     }
 }
 
+/// Ask the user a question from the agent. Headless runs answer according to
+/// `GOOSE_ASK_USER_POLICY`, None when the run should pause for the answer instead.
+fn answer_question(
+    question: &UserQuestion,
+    interactive: bool,
+) -> Result<Option<ToolResult<Vec<Content>>>> {
+    if !interactive {
+        return Ok(match AskUserPolicy::from_config() {
+            AskUserPolicy::Pause => None,
+            AskUserPolicy::Default => {
+                output::render_text(
+                    &format!(
+                        "Goose asked: {}
+Answering with its default",
+                        question.question
+                    ),
+                    Some(Color::Yellow),
+                    true,
+                );
+                Some(Ok(vec![Content::text(question.default_answer())]))
+            }
+        });
+    }
+
+    let answer = if question.options.is_empty() {
+        let mut input = cliclack::input(&question.question).required(true);
+        if let Some(default) = &question.default {
+            input = input.default_input(default);
+        }
+        input.interact::<String>()
+    } else {
+        // An empty choice stands for typing an answer that isn't among the options
+        let mut select = cliclack::select(&question.question);
+        for option in &question.options {
+            select = select.item(option.clone(), option, "");
+        }
+        select = select.item(String::new(), "Something else", "type an answer");
+        if let Some(default) = &question.default {
+            select = select.initial_value(default.clone());
+        }
+        select.interact().and_then(|choice: String| {
+            if choice.is_empty() {
+                cliclack::input("Your answer").interact::<String>()
+            } else {
+                Ok(choice)
+            }
+        })
+    };
+    match answer {
+        Ok(text) => Ok(Some(Ok(user_answer(&text)))),
+        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => Ok(Some(Err(
+            ToolError::ExecutionError("The user declined to answer".to_string()),
+        ))),
+        Err(e) => Err(e.into()),
+    }
+}
+
 fn get_reasoner() -> Result<Arc<dyn Provider>, anyhow::Error> {
     use goose::model::ModelConfig;
     use goose::providers::create;
@@ -1278,3 +1982,13 @@ fn get_reasoner() -> Result<Arc<dyn Provider>, anyhow::Error> {
 
     Ok(reasoner)
 }
+
+/// The absolute path of a path given to /pin or /unpin, relative to the working directory
+fn resolve_pin_path(path: &str) -> Result<PathBuf> {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => etcetera::home_dir()?.join(rest),
+        None => std::env::current_dir()?.join(path),
+    };
+    // Resolve `..` and symlinks so the same file is pinned once, paths that are gone stay as given
+    Ok(path.canonicalize().unwrap_or(path))
+}
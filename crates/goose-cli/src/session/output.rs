@@ -1,7 +1,12 @@
 use bat::WrappingMode;
 use console::{style, Color};
+use goose::agents::tool_compression::CompressionStats;
+use goose::config::key_manager::KeyWarning;
 use goose::config::Config;
+use goose::context_mgmt::breakdown::{ContextBreakdown, ContextSegment, SegmentKind};
 use goose::message::{Message, MessageContent, ToolRequest, ToolResponse};
+use goose::providers::base::ResponseTiming;
+use goose::session::{HandoffMode, RunCheckpoint};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use mcp_core::prompt::PromptArgument;
 use mcp_core::tool::ToolCall;
@@ -10,9 +15,12 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::Error;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
 use std::time::Duration;
 
+use super::markdown::render_markdown;
+
 // Re-export theme for use in main
 #[derive(Clone, Copy)]
 pub enum Theme {
@@ -73,6 +81,19 @@ pub fn get_theme() -> Theme {
     CURRENT_THEME.with(|t| *t.borrow())
 }
 
+/// Print markdown as it is written rather than rendering it, from `--plain` or GOOSE_CLI_PLAIN
+static PLAIN: LazyLock<AtomicBool> = LazyLock::new(|| {
+    AtomicBool::new(
+        Config::global()
+            .get_param::<bool>("GOOSE_CLI_PLAIN")
+            .unwrap_or(false),
+    )
+});
+
+pub fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
 // Simple wrapper around spinner to manage its state
 #[derive(Default)]
 pub struct ThinkingIndicator {
@@ -136,8 +157,10 @@ pub fn render_message(message: &Message, debug: bool) {
             }
             MessageContent::RedactedThinking(_) => {
                 // For redacted thinking, print thinking was redacted
-                println!("\n{}", style("Thinking:").dim().italic());
-                print_markdown("Thinking was redacted", theme);
+                if std::env::var("GOOSE_CLI_SHOW_THINKING").is_ok() {
+                    println!("\n{}", style("Thinking:").dim().italic());
+                    print_markdown("Thinking was redacted", theme);
+                }
             }
             _ => {
                 println!("WARNING: Message content type could not be rendered");
@@ -238,6 +261,25 @@ fn render_tool_response(resp: &ToolResponse, theme: Theme, debug: bool) {
     }
 }
 
+pub fn render_budget_warning(spent: f64, budget: f64) {
+    println!(
+        "\n  {} estimated spend this month is ${:.2}, over your ${:.2} budget. Run {} for details.\n",
+        style("budget exceeded:").yellow().bold(),
+        spent,
+        budget,
+        style("goose usage").cyan()
+    );
+}
+
+pub fn render_key_warning(warning: &KeyWarning) {
+    println!(
+        "\n  {} {}. Run {} to replace it.\n",
+        style("key warning:").yellow().bold(),
+        warning,
+        style(format!("goose keys rotate {}", warning.key_name())).cyan()
+    );
+}
+
 pub fn render_error(message: &str) {
     println!("\n  {} {}\n", style("error:").red().bold(), message);
 }
@@ -309,6 +351,71 @@ pub fn render_extension_error(name: &str, error: &str) {
     println!();
 }
 
+pub fn render_extension_reloaded(name: &str, tools: usize) {
+    println!();
+    println!(
+        "  {} extension `{}` with {} tool{}",
+        style("reloaded").green(),
+        style(name).cyan(),
+        tools,
+        if tools == 1 { "" } else { "s" }
+    );
+    println!();
+}
+
+/// Show the change a tool call would make to a file, before asking to approve it
+pub fn render_diff(diff: &str) {
+    println!();
+    for line in diff.lines() {
+        let styled = if line.starts_with("+++") || line.starts_with("---") {
+            style(line).bold()
+        } else if line.starts_with("@@") {
+            style(line).cyan()
+        } else if line.starts_with('+') {
+            style(line).green()
+        } else if line.starts_with('-') {
+            style(line).red()
+        } else {
+            style(line).dim()
+        };
+        println!("{}", styled);
+    }
+    println!();
+}
+
+pub fn render_checkpoint(checkpoint: &RunCheckpoint) {
+    let mode = match checkpoint.paused_from {
+        HandoffMode::Headless => "headless run",
+        HandoffMode::Interactive => "interactive session",
+    };
+    println!(
+        "{} a {} that stopped at {} ({})",
+        style("Picking up").green(),
+        mode,
+        checkpoint
+            .paused_at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S"),
+        checkpoint.reason
+    );
+}
+
+pub fn render_handoff(session_id: &str, pid: u32, log: &Path) {
+    println!();
+    println!(
+        "  {} to a headless run (pid {}), its output goes to {}",
+        style("handed off").green(),
+        pid,
+        style(log.display()).cyan()
+    );
+    println!(
+        "  pause it with {} and continue it here with {}",
+        style(format!("goose session pause {}", session_id)).cyan(),
+        style(format!("goose session --resume --from-run {}", session_id)).cyan()
+    );
+    println!();
+}
+
 pub fn render_builtin_success(names: &str) {
     println!();
     println!(
@@ -402,6 +509,14 @@ pub fn env_no_color() -> bool {
 }
 
 fn print_markdown(content: &str, theme: Theme) {
+    if !PLAIN.load(Ordering::Relaxed) {
+        let rendered = render_markdown(content, theme, console::colors_enabled());
+        if !rendered.is_empty() {
+            println!("{}", rendered);
+        }
+        return;
+    }
+
     bat::PrettyPrinter::new()
         .input(bat::Input::from_bytes(content.as_bytes()))
         .theme(theme.as_str())
@@ -641,6 +756,92 @@ pub fn display_context_usage(total_tokens: usize, context_limit: usize) {
     );
 }
 
+/// Show where the tokens of the context window go
+pub fn render_context_breakdown(breakdown: &ContextBreakdown) {
+    let total = breakdown.total_tokens();
+    println!();
+    println!(
+        "Context: {} of {} tokens ({:.1}%), {} cacheable by providers with prompt caching",
+        total,
+        breakdown.context_limit,
+        breakdown.percent(total),
+        breakdown.cached_tokens()
+    );
+    println!();
+
+    let width = breakdown
+        .segments
+        .iter()
+        .map(|segment| segment_label(segment).len())
+        .max()
+        .unwrap_or(0);
+    for segment in &breakdown.segments {
+        let percent = breakdown.percent(segment.tokens);
+        let bar_len = ((percent / 100.0) * 20.0).round() as usize;
+        let bar = format!(
+            "{}{}",
+            "█".repeat(bar_len.min(20)),
+            "░".repeat(20 - bar_len.min(20))
+        );
+        println!(
+            "  {:<width$}  {:>8}  {:>5.1}%  {}{}",
+            segment_label(segment),
+            segment.tokens,
+            percent,
+            style(bar).cyan(),
+            if segment.cached {
+                style("  cached").dim().to_string()
+            } else {
+                String::new()
+            },
+            width = width
+        );
+    }
+    println!();
+}
+
+fn segment_label(segment: &ContextSegment) -> String {
+    match segment.kind {
+        SegmentKind::ExtensionInstructions => format!("{} instructions", segment.label),
+        SegmentKind::Tools => format!("{} tools", segment.label),
+        _ => segment.label.clone(),
+    }
+}
+
+/// Display how quickly the provider produced its last response
+pub fn display_response_timing(timing: &ResponseTiming) {
+    let mut parts = vec![format!("{:.1}s", timing.total_ms as f64 / 1000.0)];
+    if let Some(ttft) = timing.time_to_first_token_ms {
+        parts.push(format!("first token {:.1}s", ttft as f64 / 1000.0));
+    }
+    if let Some(rate) = timing.tokens_per_second {
+        parts.push(format!("{:.1} tok/s", rate));
+    }
+
+    println!(
+        "{}",
+        style(format!("Last response: {}", parts.join(" · "))).dim()
+    );
+}
+
+pub fn display_tool_compression(stats: &CompressionStats) {
+    if stats.tools == 0 {
+        return;
+    }
+    println!(
+        "{}",
+        style(format!(
+            "Tool descriptions: {} of {} tools compressed, {} → {} tokens ({} saved)",
+            stats.tools_compressed,
+            stats.tools,
+            stats.original_tokens,
+            stats.compressed_tokens,
+            stats.tokens_saved()
+        ))
+        .dim()
+    );
+}
+
 pub struct McpSpinners {
     bars: HashMap<String, ProgressBar>,
     log_spinner: Option<ProgressBar>,
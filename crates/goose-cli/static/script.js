@@ -347,6 +347,7 @@ function handleToolConfirmation(data) {
         <div class="tool-confirm-content">
             <strong>${data.tool_name}</strong> wants to execute with:
             <pre><code>${JSON.stringify(data.arguments, null, 2)}</code></pre>
+            ${data.diff ? `<pre class="tool-confirm-diff"><code>${escapeHtml(data.diff)}</code></pre>` : ''}
         </div>
         <div class="tool-confirm-note">Auto-approved in web mode (UI coming soon)</div>
     `;
@@ -266,6 +266,21 @@ pub unsafe extern "C" fn goose_agent_send_message(
                 Ok(AgentEvent::McpNotification(_)) => {
                     // TODO: Handle MCP notifications.
                 }
+                Ok(AgentEvent::Timeout(_)) => {
+                    // The agent records the timed out request in the messages it sends
+                }
+                Ok(AgentEvent::HistoryReplaced(_)) => {
+                    // Only the reply is returned, the caller keeps its own history
+                }
+                Ok(AgentEvent::Guardrail(_)) => {
+                    // The reply that follows is already masked
+                }
+                Ok(AgentEvent::ExtensionDisabled(event)) => {
+                    full_response.push_str(&format!("\n{}", event.summary()));
+                }
+                Ok(AgentEvent::ContextRecovered(_)) => {
+                    // The reply carries on with a smaller history
+                }
                 Err(e) => {
                     full_response.push_str(&format!("\nError in message stream: {}", e));
                 }
@@ -1,18 +1,17 @@
 use std::{collections::HashMap, time::Instant};
 
 use anyhow::Result;
-use chrono::Utc;
-use serde_json::Value;
 
 use crate::{
     message::{Message, MessageContent},
-    prompt_template,
-    providers::create,
+    providers::{create, formats::repair::coerce_tool_calls},
+    system_prompt::{create_system_prompt, PromptSource, SystemPrompt},
     types::{
         completion::{
             CompletionError, CompletionRequest, CompletionResponse, ExtensionConfig,
             RuntimeMetrics, ToolApprovalMode, ToolConfig,
         },
+        conversation::Conversation,
         core::ToolCall,
     },
 };
@@ -36,21 +35,22 @@ pub async fn completion(req: CompletionRequest) -> Result<CompletionResponse, Co
     )
     .map_err(|_| CompletionError::UnknownProvider(req.provider_name.to_string()))?;
 
-    let system_prompt = construct_system_prompt(
-        &req.system_preamble,
-        &req.system_prompt_override,
-        &req.extensions,
-    )?;
+    let system_prompt = construct_system_prompt(&req)?;
     let tools = collect_prefixed_tools(&req.extensions);
+    // Reject histories the provider would refuse, with an error that says what is wrong
+    let conversation = Conversation::new(req.messages)?;
 
     // Call the LLM provider
     let start_provider = Instant::now();
     let mut response = provider
-        .complete(&system_prompt, &req.messages, &tools)
+        .complete_with_system_prompt(&system_prompt, conversation.messages(), &tools)
         .await?;
     let provider_elapsed_sec = start_provider.elapsed().as_secs_f32();
     let usage_tokens = response.usage.total_tokens;
 
+    // Fix arguments whose types don't match the tool's schema before they reach the caller
+    coerce_tool_calls(&mut response.message, &tools, &response.model);
+
     let tool_configs = collect_prefixed_tool_configs(&req.extensions);
     update_needs_approval_for_tool_calls(&mut response.message, &tool_configs)?;
 
@@ -62,35 +62,16 @@ pub async fn completion(req: CompletionRequest) -> Result<CompletionResponse, Co
     ))
 }
 
-/// Render the global `system.md` template with the provided context.
-fn construct_system_prompt(
-    preamble: &Option<String>,
-    prompt_override: &Option<String>,
-    extensions: &[ExtensionConfig],
-) -> Result<String, CompletionError> {
-    // If both system_preamble and system_prompt_override are provided, then prompt_override takes precedence
-    // and we don't render the template using preamble and extensions. Just return the prompt_override as is.
-    if prompt_override.is_some() {
-        return Ok(prompt_override.clone().unwrap());
+/// Compose the system prompt from the request. A `system_prompt_override` replaces the whole
+/// prompt, then a `system_prompt` composed by the caller is used as is.
+fn construct_system_prompt(req: &CompletionRequest) -> Result<SystemPrompt, CompletionError> {
+    if let Some(prompt_override) = &req.system_prompt_override {
+        return Ok(SystemPrompt::new().with_block(PromptSource::Base, prompt_override, true));
     }
-
-    let system_preamble = {
-        if let Some(p) = preamble {
-            p
-        } else {
-            "You are a helpful assistant."
-        }
-    };
-
-    let mut context: HashMap<&str, Value> = HashMap::new();
-    context.insert("system_preamble", Value::String(system_preamble.to_owned()));
-    context.insert("extensions", serde_json::to_value(extensions)?);
-    context.insert(
-        "current_date",
-        Value::String(Utc::now().format("%Y-%m-%d").to_string()),
-    );
-
-    Ok(prompt_template::render_global_file("system.md", &context)?)
+    if let Some(prompt) = &req.system_prompt {
+        return Ok(prompt.clone());
+    }
+    create_system_prompt(req.system_preamble.clone(), None, req.extensions.clone())
 }
 
 /// Determine if a tool call requires manual approval.
@@ -4,12 +4,19 @@ mod completion;
 pub mod extractors;
 pub mod message;
 mod model;
+pub mod model_registry;
 mod prompt_template;
 pub mod providers;
+pub mod storage;
 mod structured_outputs;
+pub mod system_prompt;
 pub mod types;
 
 pub use completion::completion;
 pub use message::Message;
 pub use model::ModelConfig;
-pub use structured_outputs::generate_structured_outputs;
+pub use model_registry::{lookup_model_info, ModelInfo};
+pub use structured_outputs::{
+    generate_structured_outputs, generate_structured_outputs_batch, StructuredOutputResult,
+};
+pub use system_prompt::{create_system_prompt, SystemPrompt};
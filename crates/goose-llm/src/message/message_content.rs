@@ -52,13 +52,14 @@ impl From<Result<Vec<Content>, crate::types::core::ToolError>> for ToolResponseT
 // — Register the newtypes with UniFFI, converting via JSON strings —
 // UniFFI’s FFI layer supports only primitive buffers (here String), so we JSON-serialize
 // through our `tool_result_serde` to preserve the same success/error schema on both sides.
+// Malformed JSON from the foreign side is returned as an error instead of panicking.
 
 uniffi::custom_type!(ToolRequestToolCall, String, {
     lower: |obj| {
         serde_json::to_string(&obj.0).unwrap()
     },
     try_lift: |val| {
-        Ok(serde_json::from_str(&val).unwrap() )
+        Ok(serde_json::from_str(&val)?)
     },
 });
 
@@ -67,7 +68,7 @@ uniffi::custom_type!(ToolResponseToolResult, String, {
         serde_json::to_string(&obj.0).unwrap()
     },
     try_lift: |val| {
-        Ok(serde_json::from_str(&val).unwrap() )
+        Ok(serde_json::from_str(&val)?)
     },
 });
 
@@ -238,3 +239,134 @@ impl From<Content> for MessageContent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Message;
+    use crate::providers::base::Usage;
+    use crate::types::core::{Role, ToolError};
+    use crate::UniFfiTag;
+    use serde_json::json;
+    use uniffi::{Lift, Lower};
+
+    /// Small deterministic generator, so failures reproduce without extra dependencies
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        fn string(&mut self) -> String {
+            const PIECES: &[&str] = &["", "a", "hello world", "ü ✓ 日本", "\"quoted\"", "\n\t\\"];
+            (0..self.below(3))
+                .map(|_| PIECES[self.below(PIECES.len() as u64) as usize])
+                .collect()
+        }
+
+        fn error(&mut self) -> ToolError {
+            match self.below(4) {
+                0 => ToolError::InvalidParameters(self.string()),
+                1 => ToolError::ExecutionError(self.string()),
+                2 => ToolError::SchemaError(self.string()),
+                _ => ToolError::NotFound(self.string()),
+            }
+        }
+
+        fn content(&mut self) -> Content {
+            if self.below(2) == 0 {
+                Content::text(self.string())
+            } else {
+                Content::image(self.string(), "image/png")
+            }
+        }
+
+        fn message_content(&mut self) -> MessageContent {
+            match self.below(6) {
+                0 => MessageContent::text(self.string()),
+                1 => MessageContent::image(self.string(), "image/jpeg"),
+                2 => {
+                    let call = if self.below(3) == 0 {
+                        Err(self.error())
+                    } else {
+                        let mut call = ToolCall::new(
+                            self.string(),
+                            json!({"path": self.string(), "nested": {"n": self.below(100)}}),
+                        );
+                        call.set_needs_approval(self.below(2) == 0);
+                        Ok(call)
+                    };
+                    MessageContent::tool_request(self.string(), call.into())
+                }
+                3 => {
+                    let result = if self.below(3) == 0 {
+                        Err(self.error())
+                    } else {
+                        Ok((0..self.below(3)).map(|_| self.content()).collect())
+                    };
+                    MessageContent::tool_response(self.string(), result.into())
+                }
+                4 => MessageContent::thinking(self.string(), self.string()),
+                _ => MessageContent::redacted_thinking(self.string()),
+            }
+        }
+
+        fn message(&mut self) -> Message {
+            let mut message = Message::new(if self.below(2) == 0 {
+                Role::User
+            } else {
+                Role::Assistant
+            });
+            for _ in 0..self.below(5) {
+                message.content.push(self.message_content());
+            }
+            message
+        }
+    }
+
+    #[test]
+    fn test_message_round_trips() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..500 {
+            let message = rng.message();
+
+            let json = serde_json::to_string(&message).unwrap();
+            let from_json: Message = serde_json::from_str(&json).unwrap();
+            assert_eq!(from_json, message, "JSON round trip of {}", json);
+
+            let lifted = <Message as Lift<UniFfiTag>>::try_lift(
+                <Message as Lower<UniFfiTag>>::lower(message.clone()),
+            )
+            .unwrap();
+            assert_eq!(lifted, message, "FFI round trip of {}", json);
+        }
+
+        let usage = Usage::new(Some(10), None, Some(10));
+        let lifted =
+            <Usage as Lift<UniFfiTag>>::try_lift(<Usage as Lower<UniFfiTag>>::lower(usage.clone()))
+                .unwrap();
+        assert_eq!(lifted, usage);
+    }
+
+    #[test]
+    fn test_malformed_ffi_json_is_an_error() {
+        let lowered = <String as Lower<UniFfiTag>>::lower("{not json".to_string());
+        assert!(<ToolRequestToolCall as Lift<UniFfiTag>>::try_lift(lowered).is_err());
+
+        // Errors written before they kept their kind still read as execution errors
+        let legacy: ToolResponseToolResult =
+            serde_json::from_str(r#"{"status": "error", "error": "Tool not found: x"}"#).unwrap();
+        assert_eq!(
+            legacy.0,
+            Err(ToolError::ExecutionError("Tool not found: x".to_string()))
+        );
+    }
+}
@@ -15,14 +15,39 @@ where
             state.end()
         }
         Err(err) => {
-            let mut state = serializer.serialize_struct("ToolResult", 2)?;
+            // `error` is the display string older readers expect, `kind` and `message` let the
+            // error be rebuilt exactly
+            let (kind, message) = error_parts(err);
+            let mut state = serializer.serialize_struct("ToolResult", 4)?;
             state.serialize_field("status", "error")?;
             state.serialize_field("error", &err.to_string())?;
+            state.serialize_field("kind", kind)?;
+            state.serialize_field("message", message)?;
             state.end()
         }
     }
 }
 
+fn error_parts(err: &ToolError) -> (&'static str, &str) {
+    match err {
+        ToolError::InvalidParameters(message) => ("invalidParameters", message),
+        ToolError::ExecutionError(message) => ("executionError", message),
+        ToolError::SchemaError(message) => ("schemaError", message),
+        ToolError::NotFound(message) => ("notFound", message),
+    }
+}
+
+fn error_from_parts(kind: Option<&str>, message: Option<String>, error: String) -> ToolError {
+    match (kind, message) {
+        (Some("invalidParameters"), Some(message)) => ToolError::InvalidParameters(message),
+        (Some("schemaError"), Some(message)) => ToolError::SchemaError(message),
+        (Some("notFound"), Some(message)) => ToolError::NotFound(message),
+        (Some("executionError"), Some(message)) => ToolError::ExecutionError(message),
+        // Written before errors kept their kind, or by a newer writer with kinds we don't know
+        _ => ToolError::ExecutionError(error),
+    }
+}
+
 // For deserialization, let's use a simpler approach that works with the format we're serializing to
 pub fn deserialize<'de, T, D>(deserializer: D) -> Result<ToolResult<T>, D::Error>
 where
@@ -33,8 +58,18 @@ where
     #[derive(Deserialize)]
     #[serde(untagged)]
     enum ResultFormat<T> {
-        Success { status: String, value: T },
-        Error { status: String, error: String },
+        Success {
+            status: String,
+            value: T,
+        },
+        Error {
+            status: String,
+            error: String,
+            #[serde(default)]
+            kind: Option<String>,
+            #[serde(default)]
+            message: Option<String>,
+        },
     }
 
     let format = ResultFormat::deserialize(deserializer)?;
@@ -50,9 +85,14 @@ where
                 )))
             }
         }
-        ResultFormat::Error { status, error } => {
+        ResultFormat::Error {
+            status,
+            error,
+            kind,
+            message,
+        } => {
             if status == "error" {
-                Ok(Err(ToolError::ExecutionError(error)))
+                Ok(Err(error_from_parts(kind.as_deref(), message, error)))
             } else {
                 Err(serde::de::Error::custom(format!(
                     "Expected status 'error', got '{}'",
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::model_registry::{lookup_model_info, ModelInfo};
+
 const DEFAULT_CONTEXT_LIMIT: u32 = 128_000;
 
 /// Configuration for model-specific settings and limits
@@ -20,10 +22,10 @@ impl ModelConfig {
     ///
     /// The context limit is set with the following precedence:
     /// 1. Explicit context_limit if provided in config
-    /// 2. Model-specific default based on model name
+    /// 2. The model's entry in the model registry, including overrides
     /// 3. Global default (128_000) (in get_context_limit)
     pub fn new(model_name: String) -> Self {
-        let context_limit = Self::get_model_specific_limit(&model_name);
+        let context_limit = lookup_model_info(&model_name).map(|info| info.context_limit);
 
         Self {
             model_name,
@@ -33,23 +35,9 @@ impl ModelConfig {
         }
     }
 
-    /// Get model-specific context limit based on model name
-    fn get_model_specific_limit(model_name: &str) -> Option<u32> {
-        // Implement some sensible defaults
-        match model_name {
-            // OpenAI models, https://platform.openai.com/docs/models#models-overview
-            name if name.contains("gpt-4o") => Some(128_000),
-            name if name.contains("gpt-4-turbo") => Some(128_000),
-
-            // Anthropic models, https://docs.anthropic.com/en/docs/about-claude/models
-            name if name.contains("claude-3") => Some(200_000),
-            name if name.contains("claude-4") => Some(200_000),
-
-            // Meta Llama models, https://github.com/meta-llama/llama-models/tree/main?tab=readme-ov-file#llama-models-1
-            name if name.contains("llama3.2") => Some(128_000),
-            name if name.contains("llama3.3") => Some(128_000),
-            _ => None,
-        }
+    /// Registry metadata for this model, e.g. tool and vision support or pricing
+    pub fn info(&self) -> Option<ModelInfo> {
+        lookup_model_info(&self.model_name)
     }
 
     /// Set an explicit context limit
@@ -100,6 +88,9 @@ mod tests {
         let config = ModelConfig::new("gpt-4-turbo".to_string());
         assert_eq!(config.context_limit(), 128_000);
 
+        let config = ModelConfig::new("gemini-2.5-pro-preview".to_string());
+        assert_eq!(config.context_limit(), 1_048_576);
+
         // Test fallback to default
         let config = ModelConfig::new("unknown-model".to_string());
         assert_eq!(config.context_limit(), DEFAULT_CONTEXT_LIMIT);
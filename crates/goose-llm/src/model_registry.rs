@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::providers::Usage;
+
+/// Path to a JSON file of model metadata that overrides or extends the built-in registry, e.g.
+/// `{"my-finetune": {"context_limit": 32000, "supports_vision": false}}`
+pub const MODEL_REGISTRY_ENV: &str = "GOOSE_MODEL_REGISTRY";
+
+/// What a model can do and what it costs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct ModelInfo {
+    /// The registry entry, matched against model names as a prefix or after a provider prefix
+    pub name: String,
+    pub context_limit: u32,
+    pub max_output_tokens: Option<u32>,
+    pub supports_tools: bool,
+    pub supports_vision: bool,
+    /// USD per million input tokens
+    pub input_price_per_million: Option<f64>,
+    /// USD per million output tokens
+    pub output_price_per_million: Option<f64>,
+}
+
+impl ModelInfo {
+    /// Estimated cost in USD of a request with `usage`, None when the price or usage is unknown
+    pub fn estimate_cost(&self, usage: &Usage) -> Option<f64> {
+        let input = usage.input_tokens? as f64 * self.input_price_per_million?;
+        let output = usage.output_tokens? as f64 * self.output_price_per_million?;
+        Some((input + output) / 1_000_000.0)
+    }
+}
+
+/// An entry of the override file, missing fields keep the built-in values
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ModelInfoOverride {
+    context_limit: Option<u32>,
+    max_output_tokens: Option<u32>,
+    supports_tools: Option<bool>,
+    supports_vision: Option<bool>,
+    input_price_per_million: Option<f64>,
+    output_price_per_million: Option<f64>,
+}
+
+impl ModelInfoOverride {
+    fn apply(self, name: &str, base: Option<&ModelInfo>) -> Option<ModelInfo> {
+        let context_limit = self
+            .context_limit
+            .or_else(|| base.map(|base| base.context_limit))?;
+        Some(ModelInfo {
+            name: name.to_string(),
+            context_limit,
+            max_output_tokens: self
+                .max_output_tokens
+                .or_else(|| base.and_then(|base| base.max_output_tokens)),
+            supports_tools: self
+                .supports_tools
+                .or_else(|| base.map(|base| base.supports_tools))
+                .unwrap_or(true),
+            supports_vision: self
+                .supports_vision
+                .or_else(|| base.map(|base| base.supports_vision))
+                .unwrap_or(false),
+            input_price_per_million: self
+                .input_price_per_million
+                .or_else(|| base.and_then(|base| base.input_price_per_million)),
+            output_price_per_million: self
+                .output_price_per_million
+                .or_else(|| base.and_then(|base| base.output_price_per_million)),
+        })
+    }
+}
+
+/// Name, context limit, max output tokens, tools, vision, input and output USD per million tokens
+type BuiltinModel = (
+    &'static str,
+    u32,
+    Option<u32>,
+    bool,
+    bool,
+    Option<f64>,
+    Option<f64>,
+);
+
+#[rustfmt::skip]
+const BUILTIN_MODELS: &[BuiltinModel] = &[
+    // OpenAI models, https://platform.openai.com/docs/models
+    ("gpt-4o", 128_000, Some(16_384), true, true, Some(2.5), Some(10.0)),
+    ("gpt-4o-mini", 128_000, Some(16_384), true, true, Some(0.15), Some(0.6)),
+    ("gpt-4-turbo", 128_000, Some(4_096), true, true, Some(10.0), Some(30.0)),
+    ("gpt-4.1", 1_047_576, Some(32_768), true, true, Some(2.0), Some(8.0)),
+    ("gpt-4.1-mini", 1_047_576, Some(32_768), true, true, Some(0.4), Some(1.6)),
+    ("gpt-4.1-nano", 1_047_576, Some(32_768), true, true, Some(0.1), Some(0.4)),
+    ("o1", 200_000, Some(100_000), true, true, Some(15.0), Some(60.0)),
+    ("o3", 200_000, Some(100_000), true, true, Some(2.0), Some(8.0)),
+    ("o3-mini", 200_000, Some(100_000), true, false, Some(1.1), Some(4.4)),
+    ("o4-mini", 200_000, Some(100_000), true, true, Some(1.1), Some(4.4)),
+    // Anthropic models, https://docs.anthropic.com/en/docs/about-claude/models
+    ("claude-3", 200_000, Some(4_096), true, true, None, None),
+    ("claude-3-haiku", 200_000, Some(4_096), true, true, Some(0.25), Some(1.25)),
+    ("claude-3-opus", 200_000, Some(4_096), true, true, Some(15.0), Some(75.0)),
+    ("claude-3-5-haiku", 200_000, Some(8_192), true, true, Some(0.8), Some(4.0)),
+    ("claude-3-5-sonnet", 200_000, Some(8_192), true, true, Some(3.0), Some(15.0)),
+    ("claude-3-7-sonnet", 200_000, Some(64_000), true, true, Some(3.0), Some(15.0)),
+    ("claude-4", 200_000, Some(32_000), true, true, None, None),
+    ("claude-sonnet-4", 200_000, Some(64_000), true, true, Some(3.0), Some(15.0)),
+    ("claude-opus-4", 200_000, Some(32_000), true, true, Some(15.0), Some(75.0)),
+    // Google models, https://ai.google.dev/gemini-api/docs/models
+    ("gemini-2.0-flash", 1_048_576, Some(8_192), true, true, Some(0.1), Some(0.4)),
+    ("gemini-2.5-flash", 1_048_576, Some(65_536), true, true, Some(0.3), Some(2.5)),
+    ("gemini-2.5-pro", 1_048_576, Some(65_536), true, true, Some(1.25), Some(10.0)),
+    // Meta Llama models, usually run locally, https://github.com/meta-llama/llama-models
+    ("llama3.2", 128_000, None, true, false, None, None),
+    ("llama3.3", 128_000, None, true, false, None, None),
+    // Groq hosted models, https://groq.com/pricing
+    ("llama-3.3-70b-versatile", 131_072, Some(32_768), true, false, Some(0.59), Some(0.79)),
+    ("llama-3.1-8b-instant", 131_072, Some(131_072), true, false, Some(0.05), Some(0.08)),
+];
+
+fn builtin_registry() -> HashMap<String, ModelInfo> {
+    BUILTIN_MODELS
+        .iter()
+        .map(
+            |&(name, context_limit, max_output_tokens, tools, vision, input, output)| {
+                let info = ModelInfo {
+                    name: name.to_string(),
+                    context_limit,
+                    max_output_tokens,
+                    supports_tools: tools,
+                    supports_vision: vision,
+                    input_price_per_million: input,
+                    output_price_per_million: output,
+                };
+                (info.name.clone(), info)
+            },
+        )
+        .collect()
+}
+
+static REGISTRY: Lazy<RwLock<HashMap<String, ModelInfo>>> = Lazy::new(|| {
+    let mut registry = builtin_registry();
+    if let Ok(path) = std::env::var(MODEL_REGISTRY_ENV) {
+        match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| apply_overrides(&mut registry, &contents))
+        {
+            Ok(()) => tracing::debug!(path, "loaded model registry overrides"),
+            Err(e) => tracing::warn!(path, "ignoring model registry overrides: {}", e),
+        }
+    }
+    RwLock::new(registry)
+});
+
+/// Merge a JSON object of overrides into `registry`. New models need a `context_limit`.
+fn apply_overrides(registry: &mut HashMap<String, ModelInfo>, json: &str) -> Result<(), String> {
+    let overrides: HashMap<String, ModelInfoOverride> =
+        serde_json::from_str(json).map_err(|e| e.to_string())?;
+    for (name, entry) in overrides {
+        let name = name.to_lowercase();
+        match entry.apply(&name, registry.get(&name)) {
+            Some(info) => {
+                registry.insert(name, info);
+            }
+            None => tracing::warn!(name, "model registry override needs a context_limit"),
+        }
+    }
+    Ok(())
+}
+
+/// Whether `key` appears in `model_name` at the start or right after a separator, so that
+/// `databricks-claude-3-7-sonnet` matches `claude-3-7-sonnet` but `gpt-4o3` doesn't match `o3`
+fn matches_at_boundary(model_name: &str, key: &str) -> bool {
+    model_name.match_indices(key).any(|(i, _)| {
+        model_name[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_ascii_alphanumeric())
+    })
+}
+
+fn find(registry: &HashMap<String, ModelInfo>, model_name: &str) -> Option<ModelInfo> {
+    let model_name = model_name.to_lowercase();
+    registry.get(&model_name).cloned().or_else(|| {
+        // The longest match is the most specific, gpt-4o-mini over gpt-4o
+        registry
+            .iter()
+            .filter(|(key, _)| matches_at_boundary(&model_name, key))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, info)| info.clone())
+    })
+}
+
+/// Metadata for `model_name`, from the built-in registry and any overrides
+#[uniffi::export]
+pub fn lookup_model_info(model_name: &str) -> Option<ModelInfo> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    find(&registry, model_name)
+}
+
+/// Every known model, sorted by name
+#[uniffi::export]
+pub fn list_model_info() -> Vec<ModelInfo> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    let mut models: Vec<ModelInfo> = registry.values().cloned().collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    models
+}
+
+/// Add or replace a model for the rest of the process, e.g. from an app's own settings
+#[uniffi::export]
+pub fn register_model_info(info: ModelInfo) {
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    let name = info.name.to_lowercase();
+    registry.insert(name.clone(), ModelInfo { name, ..info });
+}
+
+/// Estimated cost in USD of a request to `model_name` with `usage`
+#[uniffi::export]
+pub fn estimate_cost(model_name: &str, usage: Usage) -> Option<f64> {
+    lookup_model_info(model_name)?.estimate_cost(&usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_model_info() {
+        let gpt = lookup_model_info("gpt-4o-mini-2024-07-18").unwrap();
+        assert_eq!(gpt.name, "gpt-4o-mini");
+        assert_eq!(
+            lookup_model_info("databricks-claude-3-7-sonnet")
+                .unwrap()
+                .name,
+            "claude-3-7-sonnet"
+        );
+        assert_eq!(
+            lookup_model_info("anthropic/claude-3-opus-20240229")
+                .unwrap()
+                .context_limit,
+            200_000
+        );
+        assert!(lookup_model_info("gpt-4o3").is_none_or(|info| info.name != "o3"));
+        assert!(lookup_model_info("unknown-model").is_none());
+
+        let cost = gpt
+            .estimate_cost(&Usage::new(Some(1_000_000), Some(500_000), None))
+            .unwrap();
+        assert!((cost - 0.45).abs() < 1e-9);
+        assert_eq!(
+            estimate_cost("llama3.3", Usage::new(Some(10), Some(10), None)),
+            None
+        );
+
+        register_model_info(ModelInfo {
+            name: "Registry-Test-Model".to_string(),
+            context_limit: 32_000,
+            max_output_tokens: None,
+            supports_tools: false,
+            supports_vision: false,
+            input_price_per_million: None,
+            output_price_per_million: None,
+        });
+        let registered = lookup_model_info("registry-test-model-v2").unwrap();
+        assert_eq!(registered.context_limit, 32_000);
+        assert!(!registered.supports_tools);
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let mut registry = builtin_registry();
+        apply_overrides(
+            &mut registry,
+            r#"{
+                "gpt-4o": {"input_price_per_million": 1.0},
+                "local-coder": {"context_limit": 16000},
+                "missing-limit": {"supports_tools": false}
+            }"#,
+        )
+        .unwrap();
+
+        let gpt = find(&registry, "gpt-4o").unwrap();
+        assert_eq!(gpt.input_price_per_million, Some(1.0));
+        assert_eq!(gpt.output_price_per_million, Some(10.0));
+        assert_eq!(gpt.context_limit, 128_000);
+
+        let local = find(&registry, "local-coder:7b").unwrap();
+        assert_eq!(local.context_limit, 16_000);
+        assert!(local.supports_tools);
+        assert!(find(&registry, "missing-limit").is_none());
+
+        assert!(apply_overrides(&mut registry, "[1, 2]").is_err());
+    }
+}
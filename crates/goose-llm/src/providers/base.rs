@@ -3,7 +3,7 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
 use super::errors::ProviderError;
-use crate::{message::Message, types::core::Tool};
+use crate::{message::Message, system_prompt::SystemPrompt, types::core::Tool};
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, uniffi::Record)]
 pub struct Usage {
@@ -83,6 +83,17 @@ pub trait Provider: Send + Sync {
         tools: &[Tool],
     ) -> Result<ProviderCompleteResponse, ProviderError>;
 
+    /// Generate the next message with a system prompt composed of blocks. Providers that support
+    /// prompt caching override this to use the blocks' cache hints, the rest get the rendered text.
+    async fn complete_with_system_prompt(
+        &self,
+        system: &SystemPrompt,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<ProviderCompleteResponse, ProviderError> {
+        self.complete(&system.render(), messages, tools).await
+    }
+
     /// Structured extraction: always JSON‐Schema
     ///
     /// # Arguments
@@ -9,13 +9,17 @@ use url::Url;
 
 use super::{
     errors::ProviderError,
-    formats::databricks::{create_request, get_usage, response_to_message},
+    formats::databricks::{
+        apply_system_cache_hints, create_request, get_usage, response_to_message,
+    },
+    http::{client_builder, HttpConfig},
     utils::{get_env, get_model, ImageFormat},
 };
 use crate::{
     message::Message,
     model::ModelConfig,
     providers::{Provider, ProviderCompleteResponse, ProviderExtractResponse, Usage},
+    system_prompt::SystemPrompt,
     types::core::Tool,
 };
 
@@ -38,6 +42,9 @@ pub struct DatabricksProviderConfig {
     pub image_format: ImageFormat,
     #[serde(default = "default_timeout")]
     pub timeout: u64, // timeout in seconds
+    /// Proxy and TLS options, read from the environment when left out
+    #[serde(default)]
+    pub http: Option<HttpConfig>,
 }
 
 impl DatabricksProviderConfig {
@@ -47,6 +54,7 @@ impl DatabricksProviderConfig {
             token,
             image_format: ImageFormat::OpenAi,
             timeout: default_timeout(),
+            http: None,
         }
     }
 
@@ -83,7 +91,7 @@ impl Default for DatabricksProvider {
 
 impl DatabricksProvider {
     pub fn from_config(config: DatabricksProviderConfig, model: ModelConfig) -> Result<Self> {
-        let client = Client::builder()
+        let client = client_builder(config.http.as_ref())?
             .timeout(Duration::from_secs(config.timeout))
             .build()?;
 
@@ -197,27 +205,11 @@ impl DatabricksProvider {
             }
         }
     }
-}
 
-#[async_trait]
-impl Provider for DatabricksProvider {
-    #[tracing::instrument(
-        skip(self, system, messages, tools),
-        fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
-    )]
-    async fn complete(
+    async fn complete_request(
         &self,
-        system: &str,
-        messages: &[Message],
-        tools: &[Tool],
+        mut payload: Value,
     ) -> Result<ProviderCompleteResponse, ProviderError> {
-        let mut payload = create_request(
-            &self.model,
-            system,
-            messages,
-            tools,
-            &self.config.image_format,
-        )?;
         // Remove the model key which is part of the url with databricks
         payload
             .as_object_mut()
@@ -241,6 +233,46 @@ impl Provider for DatabricksProvider {
 
         Ok(ProviderCompleteResponse::new(message, model, usage))
     }
+}
+
+#[async_trait]
+impl Provider for DatabricksProvider {
+    #[tracing::instrument(
+        skip(self, system, messages, tools),
+        fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
+    )]
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<ProviderCompleteResponse, ProviderError> {
+        let payload = create_request(
+            &self.model,
+            system,
+            messages,
+            tools,
+            &self.config.image_format,
+        )?;
+        self.complete_request(payload).await
+    }
+
+    async fn complete_with_system_prompt(
+        &self,
+        system: &SystemPrompt,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<ProviderCompleteResponse, ProviderError> {
+        let mut payload = create_request(
+            &self.model,
+            &system.render(),
+            messages,
+            tools,
+            &self.config.image_format,
+        )?;
+        apply_system_cache_hints(&mut payload, &self.model, system);
+        self.complete_request(payload).await
+    }
 
     async fn extract(
         &self,
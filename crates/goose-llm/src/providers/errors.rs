@@ -27,6 +27,20 @@ pub enum ProviderError {
     ResponseParseError(String),
 }
 
+impl ProviderError {
+    /// Whether sending the same request again may succeed: rate limits, server and network
+    /// errors, and responses that couldn't be parsed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::RateLimitExceeded(_)
+                | ProviderError::ServerError(_)
+                | ProviderError::ExecutionError(_)
+                | ProviderError::ResponseParseError(_)
+        )
+    }
+}
+
 impl From<anyhow::Error> for ProviderError {
     fn from(error: anyhow::Error) -> Self {
         ProviderError::ExecutionError(error.to_string())
@@ -5,7 +5,9 @@ use anyhow::Result;
 use super::{
     base::Provider,
     databricks::{DatabricksProvider, DatabricksProviderConfig},
+    groq::{GroqProvider, GroqProviderConfig},
     openai::{OpenAiProvider, OpenAiProviderConfig},
+    openrouter::{OpenRouterProvider, OpenRouterProviderConfig},
 };
 use crate::model::ModelConfig;
 
@@ -24,6 +26,14 @@ pub fn create(
             let config: DatabricksProviderConfig = serde_json::from_value(provider_config)?;
             Ok(Arc::new(DatabricksProvider::from_config(config, model)?))
         }
+        "groq" => {
+            let config: GroqProviderConfig = serde_json::from_value(provider_config)?;
+            Ok(Arc::new(GroqProvider::from_config(config, model)?))
+        }
+        "openrouter" => {
+            let config: OpenRouterProviderConfig = serde_json::from_value(provider_config)?;
+            Ok(Arc::new(OpenRouterProvider::from_config(config, model)?))
+        }
         _ => Err(anyhow::anyhow!("Unknown provider: {}", name)),
     }
 }
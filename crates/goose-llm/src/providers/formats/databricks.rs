@@ -7,11 +7,13 @@ use crate::{
     providers::{
         base::Usage,
         errors::ProviderError,
+        formats::repair::parse_tool_arguments,
         utils::{
             convert_image, detect_image_path, is_valid_function_name, load_image_file,
             sanitize_function_name, ImageFormat,
         },
     },
+    system_prompt::SystemPrompt,
     types::core::{Content, Role, Tool, ToolCall, ToolError},
 };
 
@@ -230,6 +232,7 @@ pub fn format_tools(tools: &[Tool]) -> anyhow::Result<Vec<Value>> {
 /// Convert Databricks' API response to internal Message format
 pub fn response_to_message(response: Value) -> anyhow::Result<Message> {
     let original = response["choices"][0]["message"].clone();
+    let model = response["model"].as_str().unwrap_or("unknown");
     let mut content: Vec<MessageContent> = Vec::new();
 
     // Handle array-based content
@@ -302,7 +305,7 @@ pub fn response_to_message(response: Value) -> anyhow::Result<Message> {
                     ));
                     content.push(MessageContent::tool_request(id, Err(error).into()));
                 } else {
-                    match serde_json::from_str::<Value>(&arguments) {
+                    match parse_tool_arguments(&arguments, model) {
                         Ok(params) => {
                             content.push(MessageContent::tool_request(
                                 id,
@@ -535,6 +538,35 @@ pub fn create_request(
     Ok(payload)
 }
 
+/// Split the system message of a Claude request into the prompt's blocks, marking the last block
+/// of the cacheable prefix so Databricks passes the cache point through to Anthropic. Other
+/// models cache prefixes on their own and keep the rendered text.
+pub fn apply_system_cache_hints(
+    payload: &mut Value,
+    model_config: &ModelConfig,
+    system: &SystemPrompt,
+) {
+    let cached = system.cached_blocks();
+    if !model_config.model_name.contains("claude") || cached == 0 {
+        return;
+    }
+    let blocks: Vec<Value> = system
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(i, block)| {
+            let mut text = json!({"type": "text", "text": block.text});
+            if i + 1 == cached {
+                text["cache_control"] = json!({"type": "ephemeral"});
+            }
+            text
+        })
+        .collect();
+    if let Some(system_message) = payload["messages"].get_mut(0) {
+        system_message["content"] = json!(blocks);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -962,6 +994,31 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_apply_system_cache_hints() -> anyhow::Result<()> {
+        let system = SystemPrompt::new()
+            .with_user_override("Prefer tabs.")
+            .with_block(crate::system_prompt::PromptSource::Base, "Base", true)
+            .with_runtime_context();
+
+        let claude = ModelConfig::new("databricks-claude-3-7-sonnet".to_string());
+        let mut payload =
+            create_request(&claude, &system.render(), &[], &[], &ImageFormat::OpenAi)?;
+        apply_system_cache_hints(&mut payload, &claude, &system);
+        let content = payload["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(content.len(), 3);
+        assert_eq!(content[0]["text"], "Base");
+        assert!(content[0].get("cache_control").is_none());
+        assert_eq!(content[1]["cache_control"], json!({"type": "ephemeral"}));
+        assert!(content[2].get("cache_control").is_none());
+
+        let gpt = ModelConfig::new("gpt-4o".to_string());
+        let mut payload = create_request(&gpt, &system.render(), &[], &[], &ImageFormat::OpenAi)?;
+        apply_system_cache_hints(&mut payload, &gpt, &system);
+        assert_eq!(payload["messages"][0]["content"], json!(system.render()));
+        Ok(())
+    }
+
     #[test]
     fn test_create_request_o1_default() -> anyhow::Result<()> {
         // Test default medium reasoning effort for O1 model
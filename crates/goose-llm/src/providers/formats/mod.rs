@@ -1,2 +1,3 @@
 pub mod databricks;
 pub mod openai;
+pub mod repair;
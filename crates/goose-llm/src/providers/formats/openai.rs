@@ -7,6 +7,7 @@ use crate::{
     providers::{
         base::Usage,
         errors::ProviderError,
+        formats::repair::parse_tool_arguments,
         utils::{
             convert_image, detect_image_path, is_valid_function_name, load_image_file,
             sanitize_function_name, ImageFormat,
@@ -180,6 +181,7 @@ pub fn format_tools(tools: &[Tool]) -> anyhow::Result<Vec<Value>> {
 /// Convert OpenAI's API response to internal Message format
 pub fn response_to_message(response: Value) -> anyhow::Result<Message> {
     let original = response["choices"][0]["message"].clone();
+    let model = response["model"].as_str().unwrap_or("unknown");
     let mut content = Vec::new();
 
     if let Some(text) = original.get("content") {
@@ -212,7 +214,7 @@ pub fn response_to_message(response: Value) -> anyhow::Result<Message> {
                     ));
                     content.push(MessageContent::tool_request(id, Err(error).into()));
                 } else {
-                    match serde_json::from_str::<Value>(&arguments) {
+                    match parse_tool_arguments(&arguments, model) {
                         Ok(params) => {
                             content.push(MessageContent::tool_request(
                                 id,
@@ -765,6 +767,24 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_response_to_message_repairs_arguments() -> anyhow::Result<()> {
+        let mut response: Value = serde_json::from_str(OPENAI_TOOL_USE_RESPONSE)?;
+        response["choices"][0]["message"]["tool_calls"][0]["function"]["arguments"] =
+            json!("{'param': 'value',}");
+
+        let message = response_to_message(response)?;
+
+        if let MessageContent::ToolReq(request) = &message.content[0] {
+            let tool_call = request.tool_call.as_result().as_ref().unwrap();
+            assert_eq!(tool_call.arguments, json!({"param": "value"}));
+        } else {
+            panic!("Expected ToolRequest content");
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_response_to_message_json_decode_error() -> anyhow::Result<()> {
         let mut response: Value = serde_json::from_str(OPENAI_TOOL_USE_RESPONSE)?;
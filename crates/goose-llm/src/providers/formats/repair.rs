@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+use crate::{
+    message::{Message, MessageContent},
+    types::core::Tool,
+};
+
+/// Tool calls that needed repairing, by model, since the process started
+static REPAIR_COUNTS: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(Default::default);
+
+fn record_repair(model: &str) {
+    let mut counts = REPAIR_COUNTS.lock().unwrap_or_else(|e| e.into_inner());
+    *counts.entry(model.to_string()).or_default() += 1;
+}
+
+/// How many tool calls each model made with arguments that had to be repaired, to compare how
+/// reliably providers and models follow the tool schemas
+#[uniffi::export]
+pub fn tool_call_repair_counts() -> HashMap<String, u64> {
+    REPAIR_COUNTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Parse tool call arguments, falling back to a lenient parse for the mistakes models commonly
+/// make: trailing commas, single quotes, unquoted keys, comments, Python literals and
+/// unterminated objects. Returns the strict parse error when the repair fails too.
+pub fn parse_tool_arguments(arguments: &str, model: &str) -> Result<Value, serde_json::Error> {
+    match serde_json::from_str(arguments) {
+        Ok(value) => Ok(value),
+        Err(e) => match serde_json::from_str(&repair_json(arguments)) {
+            Ok(value) => {
+                tracing::debug!(model, "repaired malformed tool call arguments");
+                record_repair(model);
+                Ok(value)
+            }
+            Err(_) => Err(e),
+        },
+    }
+}
+
+/// Rewrite almost-JSON into JSON, leaving anything it doesn't recognise alone
+fn repair_json(input: &str) -> String {
+    let input = input.trim();
+    let input = input
+        .strip_prefix("```json")
+        .or_else(|| input.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .unwrap_or(input)
+        .trim();
+
+    let chars: Vec<char> = input.chars().collect();
+    let mut out = String::with_capacity(input.len());
+    let mut closers = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' | '\'' => {
+                i = copy_string(&chars, i, &mut out);
+                continue;
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+                continue;
+            }
+            ',' => {
+                let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+                if !matches!(next, Some('}') | Some(']') | None) {
+                    out.push(',');
+                }
+            }
+            '{' => {
+                closers.push('}');
+                out.push(c);
+            }
+            '[' => {
+                closers.push(']');
+                out.push(c);
+            }
+            '}' | ']' => {
+                if closers.last() == Some(&c) {
+                    closers.pop();
+                }
+                out.push(c);
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let is_key = chars[i..].iter().find(|c| !c.is_whitespace()) == Some(&':');
+                match word.as_str() {
+                    _ if is_key => out.push_str(&format!("\"{}\"", word)),
+                    "True" => out.push_str("true"),
+                    "False" => out.push_str("false"),
+                    "None" => out.push_str("null"),
+                    _ => out.push_str(&word),
+                }
+                continue;
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+
+    // Output cut off part way through, e.g. by the token limit
+    while let Some(closer) = closers.pop() {
+        out.push(closer);
+    }
+    out
+}
+
+/// Copy the string starting at `chars[start]` as a double quoted JSON string, returning the
+/// index after it
+fn copy_string(chars: &[char], start: usize, out: &mut String) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    out.push('"');
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                // \' is not a JSON escape
+                if chars[i + 1] == '\'' {
+                    out.push('\'');
+                } else {
+                    out.push('\\');
+                    out.push(chars[i + 1]);
+                }
+                i += 2;
+                continue;
+            }
+            c if c == quote => {
+                out.push('"');
+                return i + 1;
+            }
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+        i += 1;
+    }
+    // Unterminated
+    out.push('"');
+    i
+}
+
+/// Coerce tool call arguments to the types their tool's schema asks for, such as `"3"` for an
+/// integer or a JSON encoded string for an object
+pub fn coerce_tool_calls(message: &mut Message, tools: &[Tool], model: &str) {
+    for content in message.content.iter_mut() {
+        if let MessageContent::ToolReq(req) = content {
+            if let Ok(call) = &mut req.tool_call.0 {
+                let Some(tool) = tools.iter().find(|tool| tool.name == call.name) else {
+                    continue;
+                };
+                if coerce_value(&mut call.arguments, &tool.input_schema) {
+                    tracing::debug!(
+                        model,
+                        tool = call.name.as_str(),
+                        "coerced tool call arguments"
+                    );
+                    record_repair(model);
+                }
+            }
+        }
+    }
+}
+
+fn schema_types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn matches_type(value: &Value, t: &str) -> bool {
+    match t {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Returns whether anything was changed
+fn coerce_value(value: &mut Value, schema: &Value) -> bool {
+    let types = schema_types(schema);
+    let mut changed = false;
+
+    if !types.is_empty() && !types.iter().any(|t| matches_type(value, t)) {
+        if let Some(coerced) = types.iter().find_map(|t| coerce_to(value, t)) {
+            *value = coerced;
+            changed = true;
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::Object(properties)) = schema.get("properties") {
+                for (key, property) in properties {
+                    if let Some(field) = map.get_mut(key) {
+                        changed |= coerce_value(field, property);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for item in items {
+                    changed |= coerce_value(item, item_schema);
+                }
+            }
+        }
+        _ => {}
+    }
+    changed
+}
+
+fn coerce_to(value: &Value, t: &str) -> Option<Value> {
+    match (value, t) {
+        (Value::String(s), "integer") => s.trim().parse::<i64>().ok().map(Value::from),
+        (Value::String(s), "number") => s.trim().parse::<f64>().ok().map(Value::from),
+        (Value::String(s), "boolean") => match s.trim().to_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        (Value::String(s), "object" | "array") => serde_json::from_str::<Value>(s)
+            .ok()
+            .filter(|parsed| matches_type(parsed, t))
+            .or_else(|| (t == "array").then(|| Value::Array(vec![value.clone()]))),
+        (Value::Number(n), "string") => Some(Value::String(n.to_string())),
+        (Value::Bool(b), "string") => Some(Value::String(b.to_string())),
+        // A single item where a list is expected
+        (value, "array") if !value.is_null() => Some(Value::Array(vec![value.clone()])),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_tool_arguments() {
+        let parse = |s: &str| parse_tool_arguments(s, "test-model").unwrap();
+        assert_eq!(parse(r#"{"a": 1}"#), json!({"a": 1}));
+        assert_eq!(
+            parse(r#"{"a": 1, "b": [1, 2,],}"#),
+            json!({"a": 1, "b": [1, 2]})
+        );
+        assert_eq!(
+            parse(r#"{'path': 'it\'s "here"', recursive: True}"#),
+            json!({"path": "it's \"here\"", "recursive": true})
+        );
+        assert_eq!(
+            parse("```json\n{\"a\": None // unset\n}\n```"),
+            json!({"a": null})
+        );
+        assert_eq!(
+            parse(r#"{"command": "ls", "args": ["-la"#),
+            json!({"command": "ls", "args": ["-la"]})
+        );
+        assert!(parse_tool_arguments("not json at all", "test-model").is_err());
+        assert!(tool_call_repair_counts()["test-model"] >= 4);
+    }
+
+    #[test]
+    fn test_coerce_value() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "count": {"type": "integer"},
+                "force": {"type": "boolean"},
+                "paths": {"type": "array", "items": {"type": "string"}},
+                "options": {"type": "object"},
+                "name": {"type": "string"}
+            }
+        });
+        let mut args = json!({
+            "count": "3",
+            "force": "False",
+            "paths": "src/lib.rs",
+            "options": "{\"depth\": 2}",
+            "name": 42
+        });
+        assert!(coerce_value(&mut args, &schema));
+        assert_eq!(
+            args,
+            json!({
+                "count": 3,
+                "force": false,
+                "paths": ["src/lib.rs"],
+                "options": {"depth": 2},
+                "name": "42"
+            })
+        );
+        // Already valid arguments are left alone
+        assert!(!coerce_value(&mut args, &schema));
+    }
+}
@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{
+    errors::ProviderError,
+    formats::openai::{create_request, get_usage, response_to_message},
+    http::{client_builder, HttpConfig},
+    utils::{emit_debug_trace, get_env, get_model, handle_response_openai_compat, ImageFormat},
+};
+use crate::{
+    message::Message,
+    model::ModelConfig,
+    providers::{Provider, ProviderCompleteResponse, ProviderExtractResponse, Usage},
+    types::core::Tool,
+};
+
+pub const GROQ_DEFAULT_MODEL: &str = "llama-3.3-70b-versatile";
+pub const _GROQ_KNOWN_MODELS: &[&str] = &[
+    "llama-3.3-70b-versatile",
+    "llama-3.1-8b-instant",
+    "deepseek-r1-distill-llama-70b",
+    "qwen-qwq-32b",
+];
+
+fn default_timeout() -> u64 {
+    60
+}
+
+fn default_host() -> String {
+    "https://api.groq.com".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroqProviderConfig {
+    pub api_key: String,
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_timeout")]
+    pub timeout: u64, // timeout in seconds
+    /// Proxy and TLS options, read from the environment when left out
+    #[serde(default)]
+    pub http: Option<HttpConfig>,
+}
+
+impl GroqProviderConfig {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            api_key,
+            host: default_host(),
+            timeout: default_timeout(),
+            http: None,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let api_key = get_env("GROQ_API_KEY").expect("Missing GROQ_API_KEY");
+        Self::new(api_key)
+    }
+}
+
+#[derive(Debug)]
+pub struct GroqProvider {
+    config: GroqProviderConfig,
+    model: ModelConfig,
+    client: Client,
+}
+
+impl Default for GroqProvider {
+    fn default() -> Self {
+        let config = GroqProviderConfig::from_env();
+        let model = ModelConfig::new(GROQ_DEFAULT_MODEL.to_string());
+        GroqProvider::from_config(config, model).expect("Failed to initialize GroqProvider")
+    }
+}
+
+/// Groq reports usage under `x_groq` instead of `usage` in some responses
+fn get_groq_usage(data: &Value) -> Result<Usage, ProviderError> {
+    match data.pointer("/x_groq/usage") {
+        Some(usage) if data.get("usage").is_none() => get_usage(&json!({ "usage": usage })),
+        _ => get_usage(data),
+    }
+}
+
+impl GroqProvider {
+    pub fn from_config(config: GroqProviderConfig, model: ModelConfig) -> Result<Self> {
+        let client = client_builder(config.http.as_ref())?
+            .timeout(Duration::from_secs(config.timeout))
+            .build()?;
+
+        Ok(Self {
+            config,
+            model,
+            client,
+        })
+    }
+
+    async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
+        let base_url = url::Url::parse(&self.config.host)
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+        let url = base_url.join("openai/v1/chat/completions").map_err(|e| {
+            ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
+        })?;
+
+        let response = self
+            .client
+            .post(url)
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .json(&payload)
+            .send()
+            .await?;
+
+        handle_response_openai_compat(response).await
+    }
+}
+
+#[async_trait]
+impl Provider for GroqProvider {
+    #[tracing::instrument(
+        skip(self, system, messages, tools),
+        fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
+    )]
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<ProviderCompleteResponse, ProviderError> {
+        let payload = create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+
+        let response = self.post(payload.clone()).await?;
+
+        let message = response_to_message(response.clone())?;
+        let usage = match get_groq_usage(&response) {
+            Ok(usage) => usage,
+            Err(ProviderError::UsageError(e)) => {
+                tracing::debug!("Failed to get usage data: {}", e);
+                Usage::default()
+            }
+            Err(e) => return Err(e),
+        };
+        let model = get_model(&response);
+        emit_debug_trace(&self.model, &payload, &response, &usage);
+        Ok(ProviderCompleteResponse::new(message, model, usage))
+    }
+
+    async fn extract(
+        &self,
+        system: &str,
+        messages: &[Message],
+        schema: &Value,
+    ) -> Result<ProviderExtractResponse, ProviderError> {
+        // Groq only supports strict JSON schemas on a few models, so ask for a JSON object and
+        // describe the schema in the system prompt instead
+        let system = format!(
+            "{}\n\nRespond with a JSON object matching this JSON schema:\n{}",
+            system, schema
+        );
+        let mut payload =
+            create_request(&self.model, &system, messages, &[], &ImageFormat::OpenAi)?;
+        payload
+            .as_object_mut()
+            .expect("payload must be an object")
+            .insert(
+                "response_format".to_string(),
+                json!({ "type": "json_object" }),
+            );
+
+        let response = self.post(payload.clone()).await?;
+
+        let raw = response["choices"][0]["message"]
+            .get("content")
+            .and_then(|content| content.as_str())
+            .ok_or_else(|| {
+                ProviderError::ResponseParseError("Missing content in extract response".into())
+            })?;
+        let data = serde_json::from_str(raw)
+            .map_err(|e| ProviderError::ResponseParseError(format!("Invalid JSON: {}", e)))?;
+
+        let usage = match get_groq_usage(&response) {
+            Ok(u) => u,
+            Err(ProviderError::UsageError(e)) => {
+                tracing::debug!("Failed to get usage in extract: {}", e);
+                Usage::default()
+            }
+            Err(e) => return Err(e),
+        };
+        let model = get_model(&response);
+
+        Ok(ProviderExtractResponse::new(data, model, usage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_groq_usage() {
+        let usage = get_groq_usage(&json!({
+            "x_groq": {"usage": {"prompt_tokens": 12, "completion_tokens": 3}}
+        }))
+        .unwrap();
+        assert_eq!(usage, Usage::new(Some(12), Some(3), Some(15)));
+
+        let usage = get_groq_usage(&json!({
+            "usage": {"prompt_tokens": 1, "completion_tokens": 2, "total_tokens": 3},
+            "x_groq": {"id": "req_1"}
+        }))
+        .unwrap();
+        assert_eq!(usage, Usage::new(Some(1), Some(2), Some(3)));
+        assert!(get_groq_usage(&json!({})).is_err());
+    }
+}
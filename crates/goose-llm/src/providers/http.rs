@@ -0,0 +1,122 @@
+use anyhow::{Context, Result};
+use reqwest::tls::Version;
+use reqwest::{Certificate, Client, ClientBuilder, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+
+use super::utils::get_env;
+
+/// Proxy and TLS options for the HTTP client of a provider, set as `http` in the provider
+/// config. When it is left out they are read from the GOOSE_HTTP_PROXY, GOOSE_NO_PROXY,
+/// GOOSE_CA_BUNDLE, GOOSE_TLS_MIN_VERSION and GOOSE_TLS_INSECURE environment variables,
+/// the same settings goose uses.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// Proxy for every request, e.g. http://proxy.corp:8080. Without it the standard
+    /// HTTP_PROXY, HTTPS_PROXY and NO_PROXY environment variables apply.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Comma separated hosts that bypass `proxy`, in the NO_PROXY format
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+    /// PEM file with CA certificates to trust in addition to the system's
+    #[serde(default)]
+    pub ca_bundle: Option<String>,
+    /// Lowest TLS version to accept, "1.2" or "1.3"
+    #[serde(default)]
+    pub min_tls_version: Option<String>,
+    /// Skip certificate verification, only as a last resort on trusted networks
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+}
+
+impl HttpConfig {
+    pub fn from_env() -> Self {
+        let non_empty = |key: &str| {
+            get_env(key)
+                .ok()
+                .filter(|value: &String| !value.trim().is_empty())
+        };
+        Self {
+            proxy: non_empty("GOOSE_HTTP_PROXY"),
+            no_proxy: non_empty("GOOSE_NO_PROXY"),
+            ca_bundle: non_empty("GOOSE_CA_BUNDLE"),
+            min_tls_version: non_empty("GOOSE_TLS_MIN_VERSION"),
+            accept_invalid_certs: get_env::<bool>("GOOSE_TLS_INSECURE").unwrap_or(false),
+        }
+    }
+
+    /// Configure a client builder with the proxy and TLS options
+    pub fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder> {
+        if let Some(proxy) = &self.proxy {
+            let proxy = Proxy::all(proxy)
+                .with_context(|| format!("Invalid proxy '{}'", proxy))?
+                .no_proxy(self.no_proxy.as_deref().and_then(NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &self.ca_bundle {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Failed to read CA bundle {}", path))?;
+            let certificates = Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("Invalid certificates in {}", path))?;
+            if certificates.is_empty() {
+                anyhow::bail!("No certificates found in {}", path);
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(version) = &self.min_tls_version {
+            let version = match version.trim() {
+                "1.2" => Version::TLS_1_2,
+                "1.3" => Version::TLS_1_3,
+                other => anyhow::bail!("Unsupported TLS version '{}', use 1.2 or 1.3", other),
+            };
+            builder = builder.min_tls_version(version);
+        }
+
+        if self.accept_invalid_certs {
+            tracing::warn!("Certificate verification is turned off for provider requests");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(builder)
+    }
+}
+
+/// A client builder for a provider, with its HTTP options or those from the environment
+pub fn client_builder(config: Option<&HttpConfig>) -> Result<ClientBuilder> {
+    match config {
+        Some(config) => config.apply(Client::builder()),
+        None => HttpConfig::from_env().apply(Client::builder()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_config() {
+        let config: HttpConfig = serde_json::from_value(serde_json::json!({
+            "proxy": "http://proxy.corp:8080",
+            "no_proxy": "localhost",
+            "min_tls_version": "1.3"
+        }))
+        .unwrap();
+        assert!(client_builder(Some(&config)).unwrap().build().is_ok());
+
+        let bad_proxy = HttpConfig {
+            proxy: Some("::not a url::".to_string()),
+            ..Default::default()
+        };
+        assert!(bad_proxy.apply(Client::builder()).is_err());
+
+        let bad_version = HttpConfig {
+            min_tls_version: Some("1.1".to_string()),
+            ..Default::default()
+        };
+        assert!(bad_version.apply(Client::builder()).is_err());
+    }
+}
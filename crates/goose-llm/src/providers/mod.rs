@@ -3,7 +3,10 @@ pub mod databricks;
 pub mod errors;
 mod factory;
 pub mod formats;
+pub mod groq;
+pub mod http;
 pub mod openai;
+pub mod openrouter;
 pub mod utils;
 
 pub use base::{Provider, ProviderCompleteResponse, ProviderExtractResponse, Usage};
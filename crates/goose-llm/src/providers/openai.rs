@@ -9,6 +9,7 @@ use serde_json::{json, Value};
 use super::{
     errors::ProviderError,
     formats::openai::{create_request, get_usage, response_to_message},
+    http::{client_builder, HttpConfig},
     utils::{emit_debug_trace, get_env, get_model, handle_response_openai_compat, ImageFormat},
 };
 use crate::{
@@ -48,6 +49,9 @@ pub struct OpenAiProviderConfig {
     pub custom_headers: Option<HashMap<String, String>>,
     #[serde(default = "default_timeout")]
     pub timeout: u64, // timeout in seconds
+    /// Proxy and TLS options, read from the environment when left out
+    #[serde(default)]
+    pub http: Option<HttpConfig>,
 }
 
 impl OpenAiProviderConfig {
@@ -60,6 +64,7 @@ impl OpenAiProviderConfig {
             project: None,
             custom_headers: None,
             timeout: 600,
+            http: None,
         }
     }
 
@@ -93,7 +98,7 @@ impl Default for OpenAiProvider {
 
 impl OpenAiProvider {
     pub fn from_config(config: OpenAiProviderConfig, model: ModelConfig) -> Result<Self> {
-        let client = Client::builder()
+        let client = client_builder(config.http.as_ref())?
             .timeout(Duration::from_secs(config.timeout))
             .build()?;
 
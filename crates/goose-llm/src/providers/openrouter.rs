@@ -0,0 +1,295 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{
+    errors::ProviderError,
+    formats::openai::{create_request, get_usage, response_to_message},
+    http::{client_builder, HttpConfig},
+    utils::{emit_debug_trace, get_env, get_model, handle_response_openai_compat, ImageFormat},
+};
+use crate::{
+    message::Message,
+    model::ModelConfig,
+    providers::{Provider, ProviderCompleteResponse, ProviderExtractResponse, Usage},
+    types::core::Tool,
+};
+
+pub const OPENROUTER_DEFAULT_MODEL: &str = "anthropic/claude-3.5-sonnet";
+// OpenRouter can route to many models, we suggest a few
+pub const _OPENROUTER_KNOWN_MODELS: &[&str] = &[
+    "anthropic/claude-3.5-sonnet",
+    "anthropic/claude-3.7-sonnet",
+    "openai/gpt-4o",
+    "google/gemini-2.5-pro",
+    "deepseek/deepseek-r1",
+];
+
+fn default_timeout() -> u64 {
+    60
+}
+
+fn default_host() -> String {
+    "https://openrouter.ai".to_string()
+}
+
+fn default_app_url() -> String {
+    "https://block.github.io/goose".to_string()
+}
+
+fn default_app_title() -> String {
+    "Goose".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenRouterProviderConfig {
+    pub api_key: String,
+    #[serde(default = "default_host")]
+    pub host: String,
+    /// Sent as `HTTP-Referer`, OpenRouter attributes usage to this app in its rankings
+    #[serde(default = "default_app_url")]
+    pub app_url: String,
+    /// Sent as `X-Title`
+    #[serde(default = "default_app_title")]
+    pub app_title: String,
+    #[serde(default = "default_timeout")]
+    pub timeout: u64, // timeout in seconds
+    /// Proxy and TLS options, read from the environment when left out
+    #[serde(default)]
+    pub http: Option<HttpConfig>,
+}
+
+impl OpenRouterProviderConfig {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            api_key,
+            host: default_host(),
+            app_url: default_app_url(),
+            app_title: default_app_title(),
+            timeout: default_timeout(),
+            http: None,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let api_key = get_env("OPENROUTER_API_KEY").expect("Missing OPENROUTER_API_KEY");
+        Self::new(api_key)
+    }
+}
+
+#[derive(Debug)]
+pub struct OpenRouterProvider {
+    config: OpenRouterProviderConfig,
+    model: ModelConfig,
+    client: Client,
+}
+
+impl Default for OpenRouterProvider {
+    fn default() -> Self {
+        let config = OpenRouterProviderConfig::from_env();
+        let model = ModelConfig::new(OPENROUTER_DEFAULT_MODEL.to_string());
+        OpenRouterProvider::from_config(config, model)
+            .expect("Failed to initialize OpenRouterProvider")
+    }
+}
+
+/// OpenRouter reports some failures, such as an upstream provider error or running out of
+/// credits, as an `error` object in a 200 response
+fn check_error(response: Value) -> Result<Value, ProviderError> {
+    let Some(error) = response.get("error") else {
+        return Ok(response);
+    };
+    let message = error
+        .get("message")
+        .and_then(|m| m.as_str())
+        .unwrap_or("Unknown error")
+        .to_string();
+    Err(match error.get("code").and_then(|c| c.as_u64()) {
+        Some(401) | Some(403) => ProviderError::Authentication(message),
+        Some(402) => ProviderError::RequestFailed(format!("Insufficient credits: {}", message)),
+        Some(429) => ProviderError::RateLimitExceeded(message),
+        _ if message.contains("context length") || message.contains("maximum context") => {
+            ProviderError::ContextLengthExceeded(message)
+        }
+        Some(code) if code >= 500 => ProviderError::ServerError(message),
+        _ => ProviderError::RequestFailed(message),
+    })
+}
+
+impl OpenRouterProvider {
+    pub fn from_config(config: OpenRouterProviderConfig, model: ModelConfig) -> Result<Self> {
+        if !model.model_name.contains('/') {
+            return Err(anyhow::anyhow!(
+                "OpenRouter models are named <vendor>/<model>, e.g. {}, got {}",
+                OPENROUTER_DEFAULT_MODEL,
+                model.model_name
+            ));
+        }
+        let client = client_builder(config.http.as_ref())?
+            .timeout(Duration::from_secs(config.timeout))
+            .build()?;
+
+        Ok(Self {
+            config,
+            model,
+            client,
+        })
+    }
+
+    async fn post(&self, mut payload: Value) -> Result<Value, ProviderError> {
+        let base_url = url::Url::parse(&self.config.host)
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+        let url = base_url.join("api/v1/chat/completions").map_err(|e| {
+            ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
+        })?;
+
+        // Token counts are only returned when asked for
+        payload
+            .as_object_mut()
+            .expect("payload must be an object")
+            .insert("usage".to_string(), json!({ "include": true }));
+
+        let response = self
+            .client
+            .post(url)
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .header("HTTP-Referer", &self.config.app_url)
+            .header("X-Title", &self.config.app_title)
+            .json(&payload)
+            .send()
+            .await?;
+
+        check_error(handle_response_openai_compat(response).await?)
+    }
+}
+
+#[async_trait]
+impl Provider for OpenRouterProvider {
+    #[tracing::instrument(
+        skip(self, system, messages, tools),
+        fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
+    )]
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<ProviderCompleteResponse, ProviderError> {
+        let payload = create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+
+        let response = self.post(payload.clone()).await?;
+
+        let message = response_to_message(response.clone())?;
+        let usage = match get_usage(&response) {
+            Ok(usage) => usage,
+            Err(ProviderError::UsageError(e)) => {
+                tracing::debug!("Failed to get usage data: {}", e);
+                Usage::default()
+            }
+            Err(e) => return Err(e),
+        };
+        // The model that served the request, which can differ from the requested one when
+        // OpenRouter falls back
+        let model = get_model(&response);
+        emit_debug_trace(&self.model, &payload, &response, &usage);
+        Ok(ProviderCompleteResponse::new(message, model, usage))
+    }
+
+    async fn extract(
+        &self,
+        system: &str,
+        messages: &[Message],
+        schema: &Value,
+    ) -> Result<ProviderExtractResponse, ProviderError> {
+        let mut payload = create_request(&self.model, system, messages, &[], &ImageFormat::OpenAi)?;
+        payload
+            .as_object_mut()
+            .expect("payload must be an object")
+            .insert(
+                "response_format".to_string(),
+                json!({
+                    "type": "json_schema",
+                    "json_schema": {
+                        "name": "extraction",
+                        "schema": schema,
+                        "strict": true
+                    }
+                }),
+            );
+
+        let response = self.post(payload.clone()).await?;
+
+        let msg = &response["choices"][0]["message"];
+        let raw = msg.get("content").cloned().ok_or_else(|| {
+            ProviderError::ResponseParseError("Missing content in extract response".into())
+        })?;
+        let data = match raw {
+            Value::String(s) => serde_json::from_str(&s)
+                .map_err(|e| ProviderError::ResponseParseError(format!("Invalid JSON: {}", e)))?,
+            Value::Object(_) | Value::Array(_) => raw,
+            other => {
+                return Err(ProviderError::ResponseParseError(format!(
+                    "Unexpected content type: {:?}",
+                    other
+                )))
+            }
+        };
+
+        let usage = match get_usage(&response) {
+            Ok(u) => u,
+            Err(ProviderError::UsageError(e)) => {
+                tracing::debug!("Failed to get usage in extract: {}", e);
+                Usage::default()
+            }
+            Err(e) => return Err(e),
+        };
+        let model = get_model(&response);
+
+        Ok(ProviderExtractResponse::new(data, model, usage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_error() {
+        let ok = json!({"choices": [], "model": "openai/gpt-4o"});
+        assert_eq!(check_error(ok.clone()).unwrap(), ok);
+
+        let credits = json!({"error": {"code": 402, "message": "Add more credits"}});
+        assert!(matches!(
+            check_error(credits),
+            Err(ProviderError::RequestFailed(message)) if message.contains("Add more credits")
+        ));
+        let context = json!({"error": {"code": 400, "message": "This endpoint's maximum context length is 8192 tokens"}});
+        assert!(matches!(
+            check_error(context),
+            Err(ProviderError::ContextLengthExceeded(_))
+        ));
+        let upstream = json!({"error": {"code": 502, "message": "Provider returned error"}});
+        assert!(matches!(
+            check_error(upstream),
+            Err(ProviderError::ServerError(_))
+        ));
+    }
+
+    #[test]
+    fn test_model_naming() {
+        let config = OpenRouterProviderConfig::new("key".to_string());
+        let error =
+            OpenRouterProvider::from_config(config.clone(), ModelConfig::new("gpt-4o".to_string()))
+                .unwrap_err();
+        assert!(error.to_string().contains("<vendor>/<model>"));
+        assert!(OpenRouterProvider::from_config(
+            config,
+            ModelConfig::new("openai/gpt-4o".to_string())
+        )
+        .is_ok());
+    }
+}
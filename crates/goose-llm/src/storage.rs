@@ -0,0 +1,313 @@
+// Persistence for apps embedding goose-llm, so they don't each ship their own.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{message::Message, types::completion::CompletionResponse};
+
+/// The collections kept in a storage backend, an object store each in IndexedDB
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Collection {
+    Conversations,
+    CompletionCache,
+}
+
+impl Collection {
+    pub const ALL: [Collection; 2] = [Collection::Conversations, Collection::CompletionCache];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Collection::Conversations => "conversations",
+            Collection::CompletionCache => "completion_cache",
+        }
+    }
+}
+
+/// A key-value store for conversation history and cached completions.
+///
+/// Values are JSON strings so backends only move text around. Browser backends such as
+/// IndexedDB hand out futures that aren't `Send`, so the trait drops that bound on wasm.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Storage {
+    async fn get(&self, collection: Collection, key: &str) -> Result<Option<String>>;
+    async fn put(&self, collection: Collection, key: &str, value: String) -> Result<()>;
+    async fn delete(&self, collection: Collection, key: &str) -> Result<()>;
+    async fn keys(&self, collection: Collection) -> Result<Vec<String>>;
+}
+
+/// Storage that lives as long as the process, the default outside the browser and in tests
+#[derive(Default)]
+pub struct MemoryStorage {
+    values: Mutex<HashMap<(Collection, String), String>>,
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Storage for MemoryStorage {
+    async fn get(&self, collection: Collection, key: &str) -> Result<Option<String>> {
+        let values = self.values.lock().unwrap();
+        Ok(values.get(&(collection, key.to_string())).cloned())
+    }
+
+    async fn put(&self, collection: Collection, key: &str, value: String) -> Result<()> {
+        let mut values = self.values.lock().unwrap();
+        values.insert((collection, key.to_string()), value);
+        Ok(())
+    }
+
+    async fn delete(&self, collection: Collection, key: &str) -> Result<()> {
+        let mut values = self.values.lock().unwrap();
+        values.remove(&(collection, key.to_string()));
+        Ok(())
+    }
+
+    async fn keys(&self, collection: Collection) -> Result<Vec<String>> {
+        let values = self.values.lock().unwrap();
+        let mut keys: Vec<String> = values
+            .keys()
+            .filter(|(c, _)| *c == collection)
+            .map(|(_, key)| key.clone())
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use indexed_db::IndexedDbStorage;
+
+#[cfg(target_arch = "wasm32")]
+mod indexed_db {
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+    use js_sys::{Array, Function, Promise};
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::{
+        Event, IdbDatabase, IdbFactory, IdbObjectStore, IdbOpenDbRequest, IdbRequest,
+        IdbTransactionMode, Window, WorkerGlobalScope,
+    };
+
+    use super::{Collection, Storage};
+
+    /// Bump when the object stores change, so existing databases get upgraded
+    const DB_VERSION: u32 = 1;
+
+    fn js_error(error: JsValue) -> anyhow::Error {
+        anyhow!("IndexedDB error: {:?}", error)
+    }
+
+    /// The IndexedDB factory of the page or worker this runs in
+    fn factory() -> Result<IdbFactory> {
+        let global = js_sys::global();
+        let factory = if let Some(window) = global.dyn_ref::<Window>() {
+            window.indexed_db()
+        } else if let Some(worker) = global.dyn_ref::<WorkerGlobalScope>() {
+            worker.indexed_db()
+        } else {
+            return Err(anyhow!("IndexedDB is only available in a browser"));
+        };
+        factory
+            .map_err(js_error)?
+            .ok_or_else(|| anyhow!("IndexedDB is not available"))
+    }
+
+    /// Wait for a request to finish, returning its result
+    async fn finish(request: &IdbRequest) -> Result<JsValue> {
+        let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+            let succeeded = request.clone();
+            let on_success = Closure::once_into_js(move |_: Event| {
+                let result = succeeded.result().unwrap_or(JsValue::UNDEFINED);
+                let _ = resolve.call1(&JsValue::UNDEFINED, &result);
+            });
+            let failed = request.clone();
+            let on_error = Closure::once_into_js(move |_: Event| {
+                let error = failed
+                    .error()
+                    .ok()
+                    .flatten()
+                    .map(JsValue::from)
+                    .unwrap_or(JsValue::UNDEFINED);
+                let _ = reject.call1(&JsValue::UNDEFINED, &error);
+            });
+            request.set_onsuccess(Some(on_success.unchecked_ref()));
+            request.set_onerror(Some(on_error.unchecked_ref()));
+        });
+        JsFuture::from(promise).await.map_err(js_error)
+    }
+
+    /// Storage in an IndexedDB database of the browser, with an object store per collection
+    pub struct IndexedDbStorage {
+        db: IdbDatabase,
+    }
+
+    impl IndexedDbStorage {
+        /// Open the database `name`, creating it and its object stores when needed
+        pub async fn open(name: &str) -> Result<Self> {
+            let request: IdbOpenDbRequest = factory()?
+                .open_with_u32(name, DB_VERSION)
+                .map_err(js_error)?;
+            let upgrading = request.clone();
+            let on_upgrade = Closure::once_into_js(move |_: Event| {
+                let Ok(db) = upgrading.result() else {
+                    return;
+                };
+                let db: IdbDatabase = db.unchecked_into();
+                for collection in Collection::ALL {
+                    if !db.object_store_names().contains(collection.name()) {
+                        let _ = db.create_object_store(collection.name());
+                    }
+                }
+            });
+            request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+            let db = finish(&request).await?.unchecked_into();
+            Ok(Self { db })
+        }
+
+        fn store(
+            &self,
+            collection: Collection,
+            mode: IdbTransactionMode,
+        ) -> Result<IdbObjectStore> {
+            self.db
+                .transaction_with_str_and_mode(collection.name(), mode)
+                .and_then(|transaction| transaction.object_store(collection.name()))
+                .map_err(js_error)
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl Storage for IndexedDbStorage {
+        async fn get(&self, collection: Collection, key: &str) -> Result<Option<String>> {
+            let request = self
+                .store(collection, IdbTransactionMode::Readonly)?
+                .get(&JsValue::from_str(key))
+                .map_err(js_error)?;
+            Ok(finish(&request).await?.as_string())
+        }
+
+        async fn put(&self, collection: Collection, key: &str, value: String) -> Result<()> {
+            let request = self
+                .store(collection, IdbTransactionMode::Readwrite)?
+                .put_with_key(&JsValue::from_str(&value), &JsValue::from_str(key))
+                .map_err(js_error)?;
+            finish(&request).await?;
+            Ok(())
+        }
+
+        async fn delete(&self, collection: Collection, key: &str) -> Result<()> {
+            let request = self
+                .store(collection, IdbTransactionMode::Readwrite)?
+                .delete(&JsValue::from_str(key))
+                .map_err(js_error)?;
+            finish(&request).await?;
+            Ok(())
+        }
+
+        async fn keys(&self, collection: Collection) -> Result<Vec<String>> {
+            let request = self
+                .store(collection, IdbTransactionMode::Readonly)?
+                .get_all_keys()
+                .map_err(js_error)?;
+            let keys: Array = finish(&request).await?.unchecked_into();
+            Ok(keys.iter().filter_map(|key| key.as_string()).collect())
+        }
+    }
+}
+
+async fn get_json<S: Storage + ?Sized, T: DeserializeOwned>(
+    storage: &S,
+    collection: Collection,
+    key: &str,
+) -> Result<Option<T>> {
+    match storage.get(collection, key).await? {
+        Some(value) => Ok(Some(serde_json::from_str(&value)?)),
+        None => Ok(None),
+    }
+}
+
+async fn put_json<S: Storage + ?Sized, T: Serialize + ?Sized>(
+    storage: &S,
+    collection: Collection,
+    key: &str,
+    value: &T,
+) -> Result<()> {
+    storage
+        .put(collection, key, serde_json::to_string(value)?)
+        .await
+}
+
+pub async fn save_conversation<S: Storage + ?Sized>(
+    storage: &S,
+    id: &str,
+    messages: &[Message],
+) -> Result<()> {
+    put_json(storage, Collection::Conversations, id, messages).await
+}
+
+/// The messages saved for a conversation, None if it was never saved
+pub async fn load_conversation<S: Storage + ?Sized>(
+    storage: &S,
+    id: &str,
+) -> Result<Option<Vec<Message>>> {
+    get_json(storage, Collection::Conversations, id).await
+}
+
+pub async fn cache_completion<S: Storage + ?Sized>(
+    storage: &S,
+    key: &str,
+    response: &CompletionResponse,
+) -> Result<()> {
+    put_json(storage, Collection::CompletionCache, key, response).await
+}
+
+/// A cached completion, keyed by whatever identifies a request to the caller
+pub async fn cached_completion<S: Storage + ?Sized>(
+    storage: &S,
+    key: &str,
+) -> Result<Option<CompletionResponse>> {
+    get_json(storage, Collection::CompletionCache, key).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_conversation_round_trip() {
+        let storage = MemoryStorage::default();
+        assert!(load_conversation(&storage, "chat").await.unwrap().is_none());
+
+        let messages = vec![
+            Message::user().with_text("hello"),
+            Message::assistant().with_text("hi there"),
+        ];
+        save_conversation(&storage, "chat", &messages)
+            .await
+            .unwrap();
+        assert_eq!(
+            load_conversation(&storage, "chat").await.unwrap(),
+            Some(messages)
+        );
+        assert_eq!(
+            storage.keys(Collection::Conversations).await.unwrap(),
+            vec!["chat"]
+        );
+        assert!(storage
+            .keys(Collection::CompletionCache)
+            .await
+            .unwrap()
+            .is_empty());
+
+        storage
+            .delete(Collection::Conversations, "chat")
+            .await
+            .unwrap();
+        assert!(load_conversation(&storage, "chat").await.unwrap().is_none());
+    }
+}
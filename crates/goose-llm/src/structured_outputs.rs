@@ -1,9 +1,42 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::stream::{self, StreamExt};
+
 use crate::{
-    providers::{create, errors::ProviderError, ProviderExtractResponse},
+    providers::{create, errors::ProviderError, Provider, ProviderExtractResponse},
     types::json_value_ffi::JsonValueFfi,
     Message, ModelConfig,
 };
 
+/// Wait before the first retry of a batch item, doubled for each one after
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Outcome of one input of a batch, the extracted output or the error of its last attempt
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct StructuredOutputResult {
+    /// Position of the input in the batch
+    pub index: u32,
+    pub response: Option<ProviderExtractResponse>,
+    pub error: Option<String>,
+    /// Calls made for this input, retries included
+    pub attempts: u32,
+}
+
+fn extraction_provider(
+    provider_name: &str,
+    provider_config: JsonValueFfi,
+) -> Result<Arc<dyn Provider>, ProviderError> {
+    // Use OpenAI models specifically for this task
+    let model_name = if provider_name == "databricks" {
+        "goose-gpt-4-1"
+    } else {
+        "gpt-4.1"
+    };
+    let model_cfg = ModelConfig::new(model_name.to_string()).with_temperature(Some(0.0));
+    Ok(create(provider_name, provider_config, model_cfg)?)
+}
+
 /// Generates a structured output based on the provided schema,
 /// system prompt and user messages.
 #[uniffi::export(async_runtime = "tokio")]
@@ -14,16 +47,211 @@ pub async fn generate_structured_outputs(
     messages: &[Message],
     schema: JsonValueFfi,
 ) -> Result<ProviderExtractResponse, ProviderError> {
-    // Use OpenAI models specifically for this task
-    let model_name = if provider_name == "databricks" {
-        "goose-gpt-4-1"
-    } else {
-        "gpt-4.1"
-    };
-    let model_cfg = ModelConfig::new(model_name.to_string()).with_temperature(Some(0.0));
-    let provider = create(provider_name, provider_config, model_cfg)?;
+    let provider = extraction_provider(provider_name, provider_config)?;
 
     let resp = provider.extract(system_prompt, messages, &schema).await?;
 
     Ok(resp)
 }
+
+/// Generates a structured output for each of `inputs`, all with the same schema and system
+/// prompt. At most `max_concurrency` requests run at once, and an input whose request fails
+/// with a retryable error is tried again up to `max_retries` times with exponential backoff.
+/// Results come back in the order of `inputs`, a failed input doesn't fail the batch.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn generate_structured_outputs_batch(
+    provider_name: &str,
+    provider_config: JsonValueFfi,
+    system_prompt: &str,
+    inputs: Vec<Vec<Message>>,
+    schema: JsonValueFfi,
+    max_concurrency: u32,
+    max_retries: u32,
+) -> Result<Vec<StructuredOutputResult>, ProviderError> {
+    let provider = extraction_provider(provider_name, provider_config)?;
+
+    Ok(extract_batch(
+        provider,
+        system_prompt,
+        inputs,
+        &schema,
+        max_concurrency,
+        max_retries,
+        RETRY_BASE_DELAY,
+    )
+    .await)
+}
+
+async fn extract_with_retries(
+    provider: &dyn Provider,
+    system_prompt: &str,
+    messages: &[Message],
+    schema: &serde_json::Value,
+    max_retries: u32,
+    base_delay: Duration,
+) -> (Result<ProviderExtractResponse, ProviderError>, u32) {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match provider.extract(system_prompt, messages, schema).await {
+            Err(e) if e.is_retryable() && attempts <= max_retries => {
+                tracing::debug!("Retrying structured output after: {}", e);
+                tokio::time::sleep(base_delay * 2u32.pow((attempts - 1).min(6))).await;
+            }
+            result => return (result, attempts),
+        }
+    }
+}
+
+async fn extract_batch(
+    provider: Arc<dyn Provider>,
+    system_prompt: &str,
+    inputs: Vec<Vec<Message>>,
+    schema: &serde_json::Value,
+    max_concurrency: u32,
+    max_retries: u32,
+    base_delay: Duration,
+) -> Vec<StructuredOutputResult> {
+    let provider = provider.as_ref();
+    let mut results: Vec<StructuredOutputResult> = stream::iter(inputs.into_iter().enumerate())
+        .map(|(index, messages)| async move {
+            let (result, attempts) = extract_with_retries(
+                provider,
+                system_prompt,
+                &messages,
+                schema,
+                max_retries,
+                base_delay,
+            )
+            .await;
+            let (response, error) = match result {
+                Ok(response) => (Some(response), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            StructuredOutputResult {
+                index: index as u32,
+                response,
+                error,
+                attempts,
+            }
+        })
+        .buffer_unordered(max_concurrency.max(1) as usize)
+        .collect()
+        .await;
+    results.sort_by_key(|result| result.index);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{ProviderCompleteResponse, Usage};
+    use crate::types::core::Tool;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Extracts the text of the first message, failing the first calls for each text as told
+    #[derive(Default)]
+    struct FlakyProvider {
+        failures: Mutex<HashMap<String, (u32, bool)>>,
+        running: AtomicUsize,
+        most_running: AtomicUsize,
+    }
+
+    impl FlakyProvider {
+        fn fail(self, text: &str, times: u32, retryable: bool) -> Self {
+            self.failures
+                .lock()
+                .unwrap()
+                .insert(text.to_string(), (times, retryable));
+            self
+        }
+    }
+
+    #[async_trait]
+    impl Provider for FlakyProvider {
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<ProviderCompleteResponse, ProviderError> {
+            unimplemented!()
+        }
+
+        async fn extract(
+            &self,
+            _system: &str,
+            messages: &[Message],
+            _schema: &serde_json::Value,
+        ) -> Result<ProviderExtractResponse, ProviderError> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most_running.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+
+            let text = messages[0].content.concat_text_str();
+            if let Some((times, retryable)) = self.failures.lock().unwrap().get_mut(&text) {
+                if *times > 0 {
+                    *times -= 1;
+                    return Err(if *retryable {
+                        ProviderError::RateLimitExceeded(text)
+                    } else {
+                        ProviderError::Authentication(text)
+                    });
+                }
+            }
+            Ok(ProviderExtractResponse::new(
+                json!({ "text": text }),
+                "mock".to_string(),
+                Usage::default(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extract_batch() {
+        let provider = Arc::new(
+            FlakyProvider::default()
+                .fail("b", 1, true)
+                .fail("c", 5, true)
+                .fail("d", 1, false),
+        );
+        let inputs = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|text| vec![Message::user().with_text(*text)])
+            .collect();
+
+        let results = extract_batch(
+            provider.clone(),
+            "system",
+            inputs,
+            &json!({"type": "object"}),
+            2,
+            2,
+            Duration::from_millis(1),
+        )
+        .await;
+
+        assert_eq!(
+            results.iter().map(|r| r.index).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4]
+        );
+        assert_eq!(
+            results.iter().map(|r| r.attempts).collect::<Vec<_>>(),
+            vec![1, 2, 3, 1, 1]
+        );
+        assert_eq!(results[1].response.as_ref().unwrap().data["text"], "b");
+        assert!(results[2].error.as_ref().unwrap().contains("Rate limit"));
+        assert!(results[3]
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("Authentication"));
+        assert!(results[4].response.is_some());
+        assert!(provider.most_running.load(Ordering::SeqCst) <= 2);
+    }
+}
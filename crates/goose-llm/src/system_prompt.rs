@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    prompt_template,
+    types::completion::{CompletionError, ExtensionConfig},
+};
+
+const DEFAULT_PREAMBLE: &str = "You are a helpful assistant.";
+
+/// Where a block of the system prompt comes from. Blocks are rendered in this order, so the
+/// ones that rarely change come first and can be cached as a prefix.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, uniffi::Enum,
+)]
+pub enum PromptSource {
+    /// The base instructions and response guidelines
+    Base,
+    /// The instructions of the active extensions
+    Extensions,
+    /// Instructions from the user, such as hints files, that refine the base instructions
+    UserOverride,
+    /// Details of the current run such as the date and OS, which change between sessions
+    Runtime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct PromptBlock {
+    pub source: PromptSource,
+    pub text: String,
+    /// Whether providers that support prompt caching may cache the prompt up to this block
+    pub cache: bool,
+}
+
+/// A system prompt composed of ordered blocks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, uniffi::Record)]
+pub struct SystemPrompt {
+    pub blocks: Vec<PromptBlock>,
+}
+
+impl SystemPrompt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a block after the other blocks of the same source. Empty blocks are dropped.
+    pub fn with_block(
+        mut self,
+        source: PromptSource,
+        text: impl Into<String>,
+        cache: bool,
+    ) -> Self {
+        let text = text.into();
+        if text.trim().is_empty() {
+            return self;
+        }
+        let index = self.blocks.partition_point(|block| block.source <= source);
+        self.blocks.insert(
+            index,
+            PromptBlock {
+                source,
+                text: text.trim().to_string(),
+                cache,
+            },
+        );
+        self
+    }
+
+    /// The base instructions from the `system.md` template, starting with `preamble`
+    pub fn with_base(self, preamble: Option<&str>) -> Result<Self, CompletionError> {
+        let mut context: HashMap<&str, Value> = HashMap::new();
+        context.insert(
+            "system_preamble",
+            Value::String(preamble.unwrap_or(DEFAULT_PREAMBLE).to_string()),
+        );
+        let text = prompt_template::render_global_file("system.md", &context)?;
+        Ok(self.with_block(PromptSource::Base, text, true))
+    }
+
+    /// The instructions of `extensions` from the `extensions.md` template
+    pub fn with_extensions(self, extensions: &[ExtensionConfig]) -> Result<Self, CompletionError> {
+        let mut context: HashMap<&str, Value> = HashMap::new();
+        context.insert("extensions", serde_json::to_value(extensions)?);
+        let text = prompt_template::render_global_file("extensions.md", &context)?;
+        Ok(self.with_block(PromptSource::Extensions, text, true))
+    }
+
+    pub fn with_user_override(self, text: impl Into<String>) -> Self {
+        self.with_block(PromptSource::UserOverride, text, true)
+    }
+
+    /// The current date and OS. Never cached since the date changes daily.
+    pub fn with_runtime_context(self) -> Self {
+        let text = format!(
+            "The current date is {}. The operating system is {}.",
+            Utc::now().format("%Y-%m-%d"),
+            std::env::consts::OS
+        );
+        self.with_block(PromptSource::Runtime, text, false)
+    }
+
+    /// The number of leading blocks that can be cached, up to the first block that can't
+    pub fn cached_blocks(&self) -> usize {
+        self.blocks
+            .iter()
+            .position(|block| !block.cache)
+            .unwrap_or(self.blocks.len())
+    }
+
+    pub fn render(&self) -> String {
+        self.blocks
+            .iter()
+            .map(|block| block.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// Compose the default system prompt: the base instructions with `preamble`, the extension
+/// instructions, `user_override` and the runtime context
+#[uniffi::export(default(preamble = None, user_override = None))]
+pub fn create_system_prompt(
+    preamble: Option<String>,
+    user_override: Option<String>,
+    extensions: Vec<ExtensionConfig>,
+) -> Result<SystemPrompt, CompletionError> {
+    let mut prompt = SystemPrompt::new()
+        .with_base(preamble.as_deref())?
+        .with_extensions(&extensions)?;
+    if let Some(text) = user_override {
+        prompt = prompt.with_user_override(text);
+    }
+    Ok(prompt.with_runtime_context())
+}
+
+#[uniffi::export]
+pub fn render_system_prompt(prompt: SystemPrompt) -> String {
+    prompt.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_prompt_blocks() {
+        let extensions = vec![ExtensionConfig::new(
+            "developer".to_string(),
+            Some("Run the tests after editing.".to_string()),
+            vec![],
+        )];
+        let prompt = create_system_prompt(
+            Some("You are a pirate.".to_string()),
+            Some("Prefer tabs.".to_string()),
+            extensions,
+        )
+        .unwrap();
+
+        let sources: Vec<PromptSource> = prompt.blocks.iter().map(|block| block.source).collect();
+        assert_eq!(
+            sources,
+            vec![
+                PromptSource::Base,
+                PromptSource::Extensions,
+                PromptSource::UserOverride,
+                PromptSource::Runtime
+            ]
+        );
+        assert_eq!(prompt.cached_blocks(), 3);
+
+        let text = prompt.render();
+        assert!(text.starts_with("You are a pirate."));
+        assert!(text.contains("## developer"));
+        assert!(text.contains("Run the tests after editing."));
+        assert!(text.find("Prefer tabs.").unwrap() < text.find("The current date is").unwrap());
+        assert!(text.ends_with(&format!("{}.", std::env::consts::OS)));
+    }
+
+    #[test]
+    fn test_system_prompt_block_order() {
+        let prompt = SystemPrompt::new()
+            .with_runtime_context()
+            .with_user_override("second")
+            .with_block(PromptSource::Base, "base", true)
+            .with_user_override("third")
+            .with_user_override("  ");
+        let texts: Vec<&str> = prompt.blocks.iter().map(|b| b.text.as_str()).collect();
+        assert_eq!(texts[..3], ["base", "second", "third"]);
+        assert_eq!(prompt.blocks.len(), 4);
+        assert_eq!(SystemPrompt::new().cached_blocks(), 0);
+    }
+}
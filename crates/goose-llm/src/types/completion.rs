@@ -7,6 +7,9 @@ use thiserror::Error;
 
 use serde::{Deserialize, Serialize};
 
+use crate::model_registry::estimate_cost;
+use crate::system_prompt::SystemPrompt;
+use crate::types::conversation::ConversationError;
 use crate::types::json_value_ffi::JsonValueFfi;
 use crate::{message::Message, providers::Usage};
 use crate::{model::ModelConfig, providers::errors::ProviderError};
@@ -20,6 +23,9 @@ pub struct CompletionRequest {
     pub system_prompt_override: Option<String>,
     pub messages: Vec<Message>,
     pub extensions: Vec<ExtensionConfig>,
+    /// A system prompt composed by the caller, used instead of the preamble and extensions
+    #[serde(default)]
+    pub system_prompt: Option<SystemPrompt>,
 }
 
 impl CompletionRequest {
@@ -40,11 +46,18 @@ impl CompletionRequest {
             system_preamble,
             messages,
             extensions,
+            system_prompt: None,
         }
     }
+
+    pub fn with_system_prompt(mut self, system_prompt: SystemPrompt) -> Self {
+        self.system_prompt = Some(system_prompt);
+        self
+    }
 }
 
-#[uniffi::export(default(system_preamble = None,  system_prompt_override = None))]
+#[allow(clippy::too_many_arguments)]
+#[uniffi::export(default(system_preamble = None,  system_prompt_override = None, system_prompt = None))]
 pub fn create_completion_request(
     provider_name: &str,
     provider_config: JsonValueFfi,
@@ -53,8 +66,9 @@ pub fn create_completion_request(
     system_prompt_override: Option<String>,
     messages: Vec<Message>,
     extensions: Vec<ExtensionConfig>,
+    system_prompt: Option<SystemPrompt>,
 ) -> CompletionRequest {
-    CompletionRequest::new(
+    let request = CompletionRequest::new(
         provider_name.to_string(),
         provider_config,
         model_config,
@@ -62,7 +76,11 @@ pub fn create_completion_request(
         system_prompt_override,
         messages,
         extensions,
-    )
+    );
+    match system_prompt {
+        Some(system_prompt) => request.with_system_prompt(system_prompt),
+        None => request,
+    }
 }
 
 uniffi::custom_type!(CompletionRequest, String, {
@@ -70,7 +88,7 @@ uniffi::custom_type!(CompletionRequest, String, {
         serde_json::to_string(&tc).unwrap()
     },
     try_lift: |s: String| {
-        Ok(serde_json::from_str(&s).unwrap())
+        Ok(serde_json::from_str(&s)?)
     },
 });
 
@@ -92,6 +110,9 @@ pub enum CompletionError {
 
     #[error("tool not found error: {0}")]
     ToolNotFound(String),
+
+    #[error("invalid conversation: {0}")]
+    InvalidConversation(#[from] ConversationError),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
@@ -100,6 +121,9 @@ pub struct CompletionResponse {
     pub model: String,
     pub usage: Usage,
     pub runtime_metrics: RuntimeMetrics,
+    /// Estimated cost in USD from the model registry's pricing, None for unknown models
+    #[serde(default)]
+    pub estimated_cost: Option<f64>,
 }
 
 impl CompletionResponse {
@@ -109,11 +133,13 @@ impl CompletionResponse {
         usage: Usage,
         runtime_metrics: RuntimeMetrics,
     ) -> Self {
+        let estimated_cost = estimate_cost(&model, usage.clone());
         Self {
             message,
             model,
             usage,
             runtime_metrics,
+            estimated_cost,
         }
     }
 }
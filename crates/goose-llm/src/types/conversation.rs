@@ -0,0 +1,292 @@
+// This file defines a validated conversation, a message history that providers will accept.
+
+use std::collections::HashSet;
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::message::{Message, MessageContent, ToolResponseToolResult};
+use crate::types::core::{Role, ToolError};
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ConversationError {
+    #[error("conversation has no messages")]
+    Empty,
+
+    #[error("message {index} is the second {role:?} message in a row, roles must alternate")]
+    ConsecutiveRoles { index: usize, role: Role },
+
+    #[error("message {index} has a tool result for '{id}' without a matching tool request in the previous message")]
+    OrphanToolResponse { index: usize, id: String },
+
+    #[error("tool request '{id}' in message {index} has no tool result in the next message")]
+    MissingToolResponse { index: usize, id: String },
+
+    #[error("message {index} has a tool result in an assistant message")]
+    ToolResponseFromAssistant { index: usize },
+
+    #[error("message {index} has a tool request in a user message")]
+    ToolRequestFromUser { index: usize },
+}
+
+/// A message history whose turn order every provider accepts:
+/// - there is at least one message
+/// - user and assistant messages alternate
+/// - tool requests only come from the assistant and tool results only from the user
+/// - every tool request is answered by a result in the next message, and every result answers
+///   a request in the previous message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<Message>", into = "Vec<Message>")]
+pub struct Conversation(Vec<Message>);
+
+impl Conversation {
+    /// Validate the messages as they are
+    pub fn new(messages: Vec<Message>) -> Result<Self, ConversationError> {
+        validate(&messages)?;
+        Ok(Self(messages))
+    }
+
+    /// Fix up common problems before validating: consecutive user messages are merged and
+    /// tool requests without a result get an error result
+    pub fn fixed(messages: Vec<Message>) -> Result<Self, ConversationError> {
+        let messages = merge_consecutive_user_turns(messages);
+        let messages = add_missing_tool_results(messages);
+        Self::new(messages)
+    }
+
+    pub fn messages(&self) -> &[Message] {
+        &self.0
+    }
+
+    pub fn into_messages(self) -> Vec<Message> {
+        self.0
+    }
+}
+
+impl Deref for Conversation {
+    type Target = [Message];
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl TryFrom<Vec<Message>> for Conversation {
+    type Error = ConversationError;
+    fn try_from(messages: Vec<Message>) -> Result<Self, Self::Error> {
+        Self::new(messages)
+    }
+}
+
+impl From<Conversation> for Vec<Message> {
+    fn from(conversation: Conversation) -> Self {
+        conversation.0
+    }
+}
+
+/// Check the turn order invariants of a `Conversation`
+pub fn validate(messages: &[Message]) -> Result<(), ConversationError> {
+    if messages.is_empty() {
+        return Err(ConversationError::Empty);
+    }
+
+    let mut pending: HashSet<&str> = HashSet::new();
+    let mut pending_index = 0;
+    for (index, message) in messages.iter().enumerate() {
+        if index > 0 && messages[index - 1].role == message.role {
+            return Err(ConversationError::ConsecutiveRoles {
+                index,
+                role: message.role.clone(),
+            });
+        }
+
+        match message.role {
+            Role::User => {
+                if message.contains_tool_call() {
+                    return Err(ConversationError::ToolRequestFromUser { index });
+                }
+                let answered = message.tool_response_ids();
+                if let Some(id) = answered.iter().find(|id| !pending.contains(*id)) {
+                    return Err(ConversationError::OrphanToolResponse {
+                        index,
+                        id: id.to_string(),
+                    });
+                }
+                pending.retain(|id| !answered.contains(id));
+            }
+            Role::Assistant => {
+                if message.contains_tool_response() {
+                    return Err(ConversationError::ToolResponseFromAssistant { index });
+                }
+            }
+        }
+
+        if let Some(id) = pending.iter().next() {
+            return Err(ConversationError::MissingToolResponse {
+                index: pending_index,
+                id: id.to_string(),
+            });
+        }
+        pending = message.tool_request_ids();
+        pending_index = index;
+    }
+
+    match pending.iter().next() {
+        Some(id) => Err(ConversationError::MissingToolResponse {
+            index: pending_index,
+            id: id.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Merge runs of user messages into one, keeping the timestamp of the first
+pub fn merge_consecutive_user_turns(messages: Vec<Message>) -> Vec<Message> {
+    let mut merged: Vec<Message> = Vec::with_capacity(messages.len());
+    for message in messages {
+        match merged.last_mut() {
+            Some(last) if last.role == Role::User && message.role == Role::User => {
+                for content in message.content.iter() {
+                    last.content.push(content.clone());
+                }
+            }
+            _ => merged.push(message),
+        }
+    }
+    merged
+}
+
+/// Answer tool requests that have no result in the following message with an error result,
+/// e.g. when the tool call was interrupted
+pub fn add_missing_tool_results(messages: Vec<Message>) -> Vec<Message> {
+    let mut fixed: Vec<Message> = Vec::with_capacity(messages.len());
+    let mut messages = messages.into_iter().peekable();
+    while let Some(message) = messages.next() {
+        let missing: Vec<String> = message
+            .content
+            .iter()
+            .filter_map(MessageContent::as_tool_request_id)
+            .filter(|id| {
+                messages
+                    .peek()
+                    .is_none_or(|next| !next.tool_response_ids().contains(id))
+            })
+            .map(str::to_string)
+            .collect();
+        let is_assistant = message.role == Role::Assistant;
+        fixed.push(message);
+        if !is_assistant || missing.is_empty() {
+            continue;
+        }
+
+        let results = missing.into_iter().map(|id| {
+            MessageContent::tool_response(
+                id,
+                ToolResponseToolResult(Err(ToolError::ExecutionError(
+                    "The tool call did not complete, no result was recorded".to_string(),
+                ))),
+            )
+        });
+        match messages.peek_mut() {
+            Some(next) if next.role == Role::User => {
+                // Tool results go before anything else the user said
+                let mut content: Vec<MessageContent> = results.collect();
+                content.extend(next.content.iter().cloned());
+                next.content = content.into();
+            }
+            _ => {
+                let mut answer = Message::user();
+                for result in results {
+                    answer.content.push(result);
+                }
+                fixed.push(answer);
+            }
+        }
+    }
+    fixed
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::types::core::{Content, ToolCall};
+
+    fn request(id: &str) -> Message {
+        Message::assistant().with_tool_request(id, Ok(ToolCall::new("shell", json!({}))))
+    }
+
+    fn response(id: &str) -> Message {
+        Message::user().with_tool_response(id, Ok(vec![Content::text("done")]).into())
+    }
+
+    #[test]
+    fn test_validate() {
+        let valid = vec![
+            Message::user().with_text("hi"),
+            request("1"),
+            response("1"),
+            Message::assistant().with_text("done"),
+        ];
+        assert!(Conversation::new(valid).is_ok());
+
+        assert_eq!(Conversation::new(vec![]), Err(ConversationError::Empty));
+        assert_eq!(
+            Conversation::new(vec![
+                Message::assistant().with_text("a"),
+                Message::assistant().with_text("b"),
+            ]),
+            Err(ConversationError::ConsecutiveRoles {
+                index: 1,
+                role: Role::Assistant
+            })
+        );
+        assert_eq!(
+            Conversation::new(vec![request("1"), response("2")]),
+            Err(ConversationError::OrphanToolResponse {
+                index: 1,
+                id: "2".to_string()
+            })
+        );
+        assert_eq!(
+            Conversation::new(vec![
+                Message::user().with_text("hi"),
+                request("1"),
+                Message::user().with_text("never mind"),
+            ]),
+            Err(ConversationError::MissingToolResponse {
+                index: 1,
+                id: "1".to_string()
+            })
+        );
+        assert!(matches!(
+            Conversation::new(vec![Message::user().with_text("hi"), request("1")]),
+            Err(ConversationError::MissingToolResponse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_fixed() {
+        let conversation = Conversation::fixed(vec![
+            Message::user().with_text("one"),
+            Message::user().with_text("two"),
+            request("1"),
+            Message::user().with_text("stop"),
+            request("2"),
+        ])
+        .unwrap();
+
+        assert_eq!(conversation.len(), 5);
+        assert_eq!(conversation[0].content.concat_text_str(), "one\ntwo");
+        // The missing result goes before what the user said next
+        assert!(conversation[2].content[0].is_tool_response());
+        assert_eq!(conversation[2].content.concat_text_str(), "stop");
+        assert_eq!(conversation[4].tool_response_ids(), HashSet::from(["2"]));
+        let result = conversation[4].content[0].as_tool_response().unwrap();
+        assert!(result.tool_result.is_err());
+
+        // Problems that cannot be fixed up are still reported
+        assert!(Conversation::fixed(vec![request("1"), response("2")]).is_err());
+    }
+}
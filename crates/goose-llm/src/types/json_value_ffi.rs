@@ -11,7 +11,7 @@ uniffi::custom_type!(Value, String, {
         serde_json::to_string(&obj).unwrap()
     },
     try_lift: |val| {
-        Ok(serde_json::from_str(&val).unwrap() )
+        Ok(serde_json::from_str(&val)?)
     },
 });
 
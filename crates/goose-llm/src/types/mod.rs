@@ -1,3 +1,4 @@
 pub mod completion;
+pub mod conversation;
 pub mod core;
 pub mod json_value_ffi;
@@ -3,7 +3,7 @@ use dotenv::dotenv;
 use goose_llm::message::{Message, MessageContent};
 use goose_llm::providers::base::Provider;
 use goose_llm::providers::errors::ProviderError;
-use goose_llm::providers::{databricks, openai};
+use goose_llm::providers::{databricks, groq, openai, openrouter};
 use goose_llm::types::core::{Content, Tool};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -373,6 +373,22 @@ async fn databricks_complete() -> Result<()> {
     .await
 }
 
+#[tokio::test]
+async fn groq_complete() -> Result<()> {
+    test_provider("Groq", &["GROQ_API_KEY"], None, groq::GroqProvider::default).await
+}
+
+#[tokio::test]
+async fn openrouter_complete() -> Result<()> {
+    test_provider(
+        "OpenRouter",
+        &["OPENROUTER_API_KEY"],
+        None,
+        openrouter::OpenRouterProvider::default,
+    )
+    .await
+}
+
 // Print the final test report
 #[ctor::dtor]
 fn print_test_report() {
@@ -0,0 +1,145 @@
+use mcp_core::{Content, ToolError};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::platform::SystemAutomation;
+
+/// Comma separated applications that app_automation scripts may target, nothing is allowed
+/// until this is set
+pub const ALLOWED_APPS_ENV: &str = "GOOSE_AUTOMATION_ALLOWED_APPS";
+
+static APPLESCRIPT_TARGETS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\b(?:application|app)\s+(?:id\s+)?"([^"]+)""#).unwrap());
+
+/// AppleScript commands that run code outside of any application, so no allow-list can cover
+/// them
+static APPLESCRIPT_ESCAPES: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b(?:do\s+shell\s+script|run\s+script|load\s+script)\b").unwrap()
+});
+
+static POWERSHELL_TARGETS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)(?:start-process|get-process|stop-process)\s+(?:-(?:filepath|name)\s+)?["']?([\w.\- ]+?)["']?(?:\s|$|;|\|)|-comobject\s+["']?([\w.]+)"#,
+    )
+    .unwrap()
+});
+
+pub fn allowed_apps() -> Vec<String> {
+    std::env::var(ALLOWED_APPS_ENV)
+        .unwrap_or_default()
+        .split(',')
+        .map(|app| app.trim().to_string())
+        .filter(|app| !app.is_empty())
+        .collect()
+}
+
+/// Applications a script addresses: `application "..."` or `app "..."` in AppleScript, and processes
+/// started, queried or stopped, or COM objects created, in PowerShell
+pub fn script_targets(script: &str) -> Vec<String> {
+    let regex = if cfg!(windows) {
+        &POWERSHELL_TARGETS
+    } else {
+        &APPLESCRIPT_TARGETS
+    };
+    let mut targets: Vec<String> = regex
+        .captures_iter(script)
+        .filter_map(|captures| captures.iter().skip(1).flatten().next())
+        .map(|target| target.as_str().trim().to_string())
+        .collect();
+    targets.sort();
+    targets.dedup();
+    targets
+}
+
+fn is_allowed(app: &str, allowed: &[String]) -> bool {
+    // PowerShell process names usually drop the .exe
+    let app = app.trim_end_matches(".exe");
+    allowed
+        .iter()
+        .any(|allowed| allowed.trim_end_matches(".exe").eq_ignore_ascii_case(app))
+}
+
+/// Make sure the script only targets allowed applications
+pub fn check_allowed(app: &str, script: &str, allowed: &[String]) -> Result<(), ToolError> {
+    if allowed.is_empty() {
+        return Err(ToolError::ExecutionError(format!(
+            "No applications are allowed for automation. Ask the user to set {} to a comma separated list of the applications goose may control, e.g. \"Spotify,Slack\".",
+            ALLOWED_APPS_ENV
+        )));
+    }
+
+    if !cfg!(windows) {
+        if let Some(escape) = APPLESCRIPT_ESCAPES.find(script) {
+            return Err(ToolError::ExecutionError(format!(
+                "Scripts may not use '{}', only automate the allowed applications directly.",
+                escape.as_str()
+            )));
+        }
+    }
+
+    let disallowed: Vec<String> = std::iter::once(app.to_string())
+        .chain(script_targets(script))
+        .filter(|target| !is_allowed(target, allowed))
+        .collect();
+    if !disallowed.is_empty() {
+        return Err(ToolError::ExecutionError(format!(
+            "The script targets applications that are not allowed: {}. Allowed applications are: {}. The user can change this with {}.",
+            disallowed.join(", "),
+            allowed.join(", "),
+            ALLOWED_APPS_ENV
+        )));
+    }
+    Ok(())
+}
+
+/// Run a script against an allowed application. The tool always asks the user for
+/// confirmation before it is called, so the script has been approved by then.
+pub fn app_automation(
+    app: &str,
+    script: &str,
+    system_automation: &dyn SystemAutomation,
+) -> Result<Vec<Content>, ToolError> {
+    check_allowed(app, script, &allowed_apps())?;
+
+    let output = system_automation
+        .execute_system_script(script)
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to execute script: {}", e)))?;
+    Ok(vec![Content::text(format!(
+        "Automation of {} completed.\n\nOutput:\n{}",
+        app, output
+    ))])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_applescript_targets_must_be_allowed() {
+        let script = r#"
+            tell application "Spotify" to playpause
+            tell application id "com.tinyspeck.slackmacgap" to activate
+            tell app "Finder" to empty trash
+        "#;
+        assert_eq!(
+            script_targets(script),
+            vec!["Finder", "Spotify", "com.tinyspeck.slackmacgap"]
+        );
+
+        let allowed = vec!["spotify".to_string()];
+        assert!(
+            check_allowed("Spotify", r#"tell application "Spotify" to play"#, &allowed).is_ok()
+        );
+        assert!(check_allowed("Spotify", script, &allowed).is_err());
+        assert!(check_allowed("Finder", "", &allowed).is_err());
+        assert!(check_allowed("Spotify", "", &[]).is_err());
+        assert!(check_allowed("Spotify", r#"do shell script "rm -rf ~""#, &allowed).is_err());
+        assert!(check_allowed(
+            "Spotify",
+            r#"tell application "Spotify" to run script "beep""#,
+            &allowed
+        )
+        .is_err());
+    }
+}
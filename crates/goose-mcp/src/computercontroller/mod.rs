@@ -22,7 +22,9 @@ use mcp_core::{
 use mcp_server::router::CapabilitiesBuilder;
 use mcp_server::Router;
 
+mod app_automation;
 mod docx_tool;
+mod ocr;
 mod pdf_tool;
 mod presentation_tool;
 mod xlsx_tool;
@@ -363,6 +365,38 @@ impl ComputerControllerRouter {
             None,
         );
 
+        let app_automation_tool = Tool::new(
+            "app_automation",
+            indoc! {r#"
+                Automate a desktop application with a script (AppleScript on macOS, PowerShell on Windows),
+                e.g. controlling music playback, creating a calendar event or sending a message.
+
+                The user is always asked to confirm the script before it runs.
+                Only applications the user has allowed in GOOSE_AUTOMATION_ALLOWED_APPS can be targeted.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["app", "script"],
+                "properties": {
+                    "app": {
+                        "type": "string",
+                        "description": "Name of the application the script automates"
+                    },
+                    "script": {
+                        "type": "string",
+                        "description": "The automation script (PowerShell for Windows, AppleScript for macOS)"
+                    }
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Automate an application".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: false,
+                open_world_hint: true,
+            }),
+        );
+
         let make_presentation_tool = Tool::new(
             "make_presentation",
             indoc! {r#"
@@ -500,6 +534,10 @@ impl ComputerControllerRouter {
             computer_control
               - System automation using PowerShell
               - Consider the screenshot tool to work out what is on screen and what to do to help with the control task.
+
+            app_automation
+              - Automate a specific application with PowerShell
+              - Only allowed applications can be targeted, and the user confirms each script before it runs
             "#},
             "macos" => indoc! {r#"
             Here are some extra tools:
@@ -516,6 +554,10 @@ impl ComputerControllerRouter {
               - System automation using AppleScript
               - Consider the screenshot tool to work out what is on screen and what to do to help with the control task.
 
+            app_automation
+              - Automate a specific application with AppleScript
+              - Only allowed applications can be targeted, and the user confirms each script before it runs
+
             When you need to interact with websites or web applications, consider using the computer_control tool with AppleScript, which can automate Safari or other browsers to:
               - Open specific URLs
               - Fill in forms
@@ -572,6 +614,10 @@ impl ComputerControllerRouter {
               - Save as text, JSON, or binary files
               - Content is cached locally for later use
               - This is not optimised for complex websites, so don't use this as the first tool.
+            ocr_screen and find_text_on_screen
+              - Read the text on screen, or find where a piece of text is, with OCR
+              - Useful to click on controls of apps that can't be scripted, using the returned coordinates
+              - Need tesseract to be installed
             cache
               - Manage your cached files
               - List, view, delete files
@@ -584,17 +630,25 @@ impl ComputerControllerRouter {
             cache_dir = cache_dir.display()
         };
 
+        let mut tools = vec![
+            web_scrape_tool,
+            quick_script_tool,
+            computer_control_tool,
+            cache_tool,
+            pdf_tool,
+            docx_tool,
+            xlsx_tool,
+            make_presentation_tool,
+            ocr::ocr_screen_tool(),
+            ocr::find_text_tool(),
+        ];
+        // App automation needs AppleScript or PowerShell
+        if matches!(std::env::consts::OS, "macos" | "windows") {
+            tools.push(app_automation_tool);
+        }
+
         Self {
-            tools: vec![
-                web_scrape_tool,
-                quick_script_tool,
-                computer_control_tool,
-                cache_tool,
-                pdf_tool,
-                docx_tool,
-                xlsx_tool,
-                make_presentation_tool,
-            ],
+            tools,
             cache_dir,
             active_resources: Arc::new(Mutex::new(HashMap::new())),
             http_client: Client::builder().user_agent("Goose/1.0").build().unwrap(),
@@ -859,6 +913,35 @@ impl ComputerControllerRouter {
         Ok(vec![Content::text(result)])
     }
 
+    async fn app_automation(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let app = params
+            .get("app")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'app' parameter".into()))?;
+
+        let script = params
+            .get("script")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'script' parameter".into()))?;
+
+        app_automation::app_automation(app, script, &**self.system_automation)
+    }
+
+    async fn ocr_screen(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let lines = ocr::recognize(&params, &self.get_cache_path("ocr", "png")).await?;
+        Ok(ocr::format_lines(&lines))
+    }
+
+    async fn find_text_on_screen(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let text = params
+            .get("text")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'text' parameter".into()))?;
+
+        let lines = ocr::recognize(&params, &self.get_cache_path("ocr", "png")).await?;
+        Ok(ocr::format_matches(&lines, text))
+    }
+
     async fn xlsx_tool(&self, params: Value) -> Result<Vec<Content>, ToolError> {
         let path = params
             .get("path")
@@ -1164,6 +1247,9 @@ impl Router for ComputerControllerRouter {
                 "web_scrape" => this.web_scrape(arguments).await,
                 "automation_script" => this.quick_script(arguments).await,
                 "computer_control" => this.computer_control(arguments).await,
+                "app_automation" => this.app_automation(arguments).await,
+                ocr::OCR_SCREEN_TOOL => this.ocr_screen(arguments).await,
+                ocr::FIND_TEXT_TOOL => this.find_text_on_screen(arguments).await,
                 "cache" => this.cache(arguments).await,
                 "pdf_tool" => this.pdf_tool(arguments).await,
                 "docx_tool" => this.docx_tool(arguments).await,
@@ -0,0 +1,501 @@
+use indoc::indoc;
+use mcp_core::{
+    tool::{Tool, ToolAnnotations},
+    Content, ToolError,
+};
+use serde_json::{json, Value};
+use std::path::Path;
+use tokio::process::Command;
+use xcap::image::{imageops, RgbaImage};
+use xcap::{Monitor, Window};
+
+pub const OCR_SCREEN_TOOL: &str = "ocr_screen";
+pub const FIND_TEXT_TOOL: &str = "find_text_on_screen";
+
+/// Words tesseract is less sure of than this, out of 100, are dropped by default
+const DEFAULT_MIN_CONFIDENCE: f64 = 30.0;
+
+/// A rectangle in screen coordinates, the ones mouse clicks use
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Bounds {
+    pub fn center(&self) -> (i32, i32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    fn union(&self, other: &Bounds) -> Bounds {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Bounds {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrWord {
+    pub text: String,
+    pub bounds: Bounds,
+    pub confidence: f64,
+}
+
+/// A line of recognized text, with the words in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrLine {
+    pub text: String,
+    pub bounds: Bounds,
+    pub words: Vec<OcrWord>,
+}
+
+pub fn ocr_screen_tool() -> Tool {
+    Tool::new(
+        OCR_SCREEN_TOOL,
+        indoc! {r#"
+            Read the text on screen with OCR. Captures a display, a window or a region of a display
+            and returns each line of text with its bounding box in screen coordinates (x, y, width, height).
+            Use this for apps that can't be automated through accessibility or scripting APIs.
+            Needs tesseract to be installed.
+        "#},
+        json!({
+            "type": "object",
+            "properties": {
+                "display": {
+                    "type": "integer",
+                    "default": 0,
+                    "description": "The display number to capture (0 is main display)"
+                },
+                "window_title": {
+                    "type": "string",
+                    "description": "Optional: the exact title of a window to capture instead of a display"
+                },
+                "region": {
+                    "type": "object",
+                    "description": "Optional: only read this part of the display, in screen coordinates relative to the display",
+                    "required": ["x", "y", "width", "height"],
+                    "properties": {
+                        "x": {"type": "integer"},
+                        "y": {"type": "integer"},
+                        "width": {"type": "integer"},
+                        "height": {"type": "integer"}
+                    }
+                },
+                "language": {
+                    "type": "string",
+                    "default": "eng",
+                    "description": "Tesseract language code(s), e.g. 'eng' or 'eng+deu'"
+                },
+                "min_confidence": {
+                    "type": "number",
+                    "default": DEFAULT_MIN_CONFIDENCE,
+                    "description": "Drop words recognized with less confidence than this, from 0 to 100"
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Read text on screen".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            // The screen changes between calls, so a repeated call can find something else
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn find_text_tool() -> Tool {
+    Tool::new(
+        FIND_TEXT_TOOL,
+        indoc! {r#"
+            Find text on screen with OCR and return the coordinates to click it.
+            Matching ignores case and can span several words, e.g. "Save As".
+            Takes the same display, window_title and region options as ocr_screen.
+        "#},
+        json!({
+            "type": "object",
+            "required": ["text"],
+            "properties": {
+                "text": {
+                    "type": "string",
+                    "description": "The text to look for"
+                },
+                "display": {
+                    "type": "integer",
+                    "default": 0,
+                    "description": "The display number to capture (0 is main display)"
+                },
+                "window_title": {
+                    "type": "string",
+                    "description": "Optional: the exact title of a window to search instead of a display"
+                },
+                "region": {
+                    "type": "object",
+                    "description": "Optional: only search this part of the display, in screen coordinates relative to the display",
+                    "required": ["x", "y", "width", "height"],
+                    "properties": {
+                        "x": {"type": "integer"},
+                        "y": {"type": "integer"},
+                        "width": {"type": "integer"},
+                        "height": {"type": "integer"}
+                    }
+                },
+                "language": {
+                    "type": "string",
+                    "default": "eng",
+                    "description": "Tesseract language code(s), e.g. 'eng' or 'eng+deu'"
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Find text on screen".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+/// A captured image and how its pixels map to screen coordinates
+struct Capture {
+    image: RgbaImage,
+    origin: (i32, i32),
+    /// Image pixels per screen point, 2 on a retina display
+    scale: f64,
+}
+
+impl Capture {
+    fn to_screen(&self, left: f64, top: f64, width: f64, height: f64) -> Bounds {
+        Bounds {
+            x: self.origin.0 + (left / self.scale).round() as i32,
+            y: self.origin.1 + (top / self.scale).round() as i32,
+            width: (width / self.scale).round() as i32,
+            height: (height / self.scale).round() as i32,
+        }
+    }
+}
+
+fn capture(params: &Value) -> Result<Capture, ToolError> {
+    let mut capture = if let Some(window_title) =
+        params.get("window_title").and_then(|v| v.as_str())
+    {
+        let window = Window::all()
+            .map_err(|_| ToolError::ExecutionError("Failed to list windows".into()))?
+            .into_iter()
+            .find(|w| w.title() == window_title)
+            .ok_or_else(|| {
+                ToolError::ExecutionError(format!("No window found with title '{}'", window_title))
+            })?;
+        let image = window.capture_image().map_err(|e| {
+            ToolError::ExecutionError(format!(
+                "Failed to capture window '{}': {}",
+                window_title, e
+            ))
+        })?;
+        Capture {
+            scale: image.width() as f64 / window.width().max(1) as f64,
+            origin: (window.x(), window.y()),
+            image,
+        }
+    } else {
+        let display = params.get("display").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        let monitors = Monitor::all()
+            .map_err(|_| ToolError::ExecutionError("Failed to access monitors".into()))?;
+        let monitor = monitors.get(display).ok_or_else(|| {
+            ToolError::ExecutionError(format!(
+                "{} was not an available monitor, {} found.",
+                display,
+                monitors.len()
+            ))
+        })?;
+        let image = monitor.capture_image().map_err(|e| {
+            ToolError::ExecutionError(format!("Failed to capture display {}: {}", display, e))
+        })?;
+        Capture {
+            scale: image.width() as f64 / monitor.width().max(1) as f64,
+            origin: (monitor.x(), monitor.y()),
+            image,
+        }
+    };
+
+    if let Some(region) = params.get("region") {
+        let value = |key: &str| {
+            region
+                .get(key)
+                .and_then(|v| v.as_i64())
+                .ok_or_else(|| ToolError::InvalidParameters(format!("Missing 'region.{}'", key)))
+        };
+        let (x, y, width, height) = (value("x")?, value("y")?, value("width")?, value("height")?);
+        if x < 0 || y < 0 || width <= 0 || height <= 0 {
+            return Err(ToolError::InvalidParameters(
+                "The region must have a positive size and lie on the display".into(),
+            ));
+        }
+        let scale = capture.scale;
+        let (left, top) = ((x as f64 * scale) as u32, (y as f64 * scale) as u32);
+        if left >= capture.image.width() || top >= capture.image.height() {
+            return Err(ToolError::InvalidParameters(
+                "The region lies outside the captured area".into(),
+            ));
+        }
+        let width = ((width as f64 * scale) as u32).min(capture.image.width() - left);
+        let height = ((height as f64 * scale) as u32).min(capture.image.height() - top);
+        capture.image = imageops::crop_imm(&capture.image, left, top, width, height).to_image();
+        capture.origin = (capture.origin.0 + x as i32, capture.origin.1 + y as i32);
+    }
+    Ok(capture)
+}
+
+/// Run tesseract on the capture, returning its TSV output
+async fn run_tesseract(image_path: &Path, language: &str) -> Result<String, ToolError> {
+    let output = Command::new("tesseract")
+        .arg(image_path)
+        .arg("stdout")
+        .args(["-l", language, "tsv"])
+        .output()
+        .await
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                ToolError::ExecutionError(
+                    "tesseract is not installed. Ask the user to install it, e.g. `brew install tesseract` on macOS, `sudo apt install tesseract-ocr` on Debian/Ubuntu or `choco install tesseract` on Windows.".into(),
+                )
+            } else {
+                ToolError::ExecutionError(format!("Failed to run tesseract: {}", e))
+            }
+        })?;
+    if !output.status.success() {
+        return Err(ToolError::ExecutionError(format!(
+            "tesseract failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Group the words in tesseract's TSV output into lines, dropping unsure words
+fn parse_tsv(
+    tsv: &str,
+    min_confidence: f64,
+    to_screen: impl Fn(f64, f64, f64, f64) -> Bounds,
+) -> Vec<OcrLine> {
+    let mut lines: Vec<(String, OcrLine)> = Vec::new();
+    // level page block par line word left top width height conf text
+    for row in tsv.lines().skip(1) {
+        let fields: Vec<&str> = row.split('\t').collect();
+        if fields.len() < 12 || fields[0] != "5" {
+            continue;
+        }
+        let text = fields[11].trim();
+        let confidence: f64 = fields[10].parse().unwrap_or(-1.0);
+        if text.is_empty() || confidence < min_confidence {
+            continue;
+        }
+        let number = |i: usize| fields[i].parse::<f64>().unwrap_or(0.0);
+        let word = OcrWord {
+            text: text.to_string(),
+            bounds: to_screen(number(6), number(7), number(8), number(9)),
+            confidence,
+        };
+
+        let key = fields[1..5].join("-");
+        match lines.last_mut() {
+            Some((last_key, line)) if *last_key == key => {
+                line.text.push(' ');
+                line.text.push_str(&word.text);
+                line.bounds = line.bounds.union(&word.bounds);
+                line.words.push(word);
+            }
+            _ => lines.push((
+                key,
+                OcrLine {
+                    text: word.text.clone(),
+                    bounds: word.bounds,
+                    words: vec![word],
+                },
+            )),
+        }
+    }
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+/// The shortest runs of words within a line whose text contains `query`
+fn find_text(lines: &[OcrLine], query: &str) -> Vec<(String, Bounds)> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut matches = Vec::new();
+    for line in lines {
+        let words = &line.words;
+        let span = |start: usize, end: usize| {
+            words[start..=end]
+                .iter()
+                .map(|w| w.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let mut start = 0;
+        while start < words.len() {
+            let Some(end) =
+                (start..words.len()).find(|&end| span(start, end).to_lowercase().contains(&query))
+            else {
+                break;
+            };
+            // A later start may match within the same words, keep the shortest run
+            if start < end && span(start + 1, end).to_lowercase().contains(&query) {
+                start += 1;
+                continue;
+            }
+            let bounds = words[start + 1..=end]
+                .iter()
+                .fold(words[start].bounds, |bounds, w| bounds.union(&w.bounds));
+            matches.push((span(start, end), bounds));
+            start = end + 1;
+        }
+    }
+    matches
+}
+
+/// Capture the requested area and recognize the text in it
+pub async fn recognize(params: &Value, scratch_path: &Path) -> Result<Vec<OcrLine>, ToolError> {
+    let language = params
+        .get("language")
+        .and_then(|v| v.as_str())
+        .unwrap_or("eng");
+    let min_confidence = params
+        .get("min_confidence")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(DEFAULT_MIN_CONFIDENCE);
+
+    let capture = capture(params)?;
+    capture
+        .image
+        .save(scratch_path)
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to save screenshot: {}", e)))?;
+    let tsv = run_tesseract(scratch_path, language).await;
+    let _ = std::fs::remove_file(scratch_path);
+
+    Ok(parse_tsv(
+        &tsv?,
+        min_confidence,
+        |left, top, width, height| capture.to_screen(left, top, width, height),
+    ))
+}
+
+fn format_bounds(bounds: &Bounds) -> String {
+    format!(
+        "[x={}, y={}, w={}, h={}]",
+        bounds.x, bounds.y, bounds.width, bounds.height
+    )
+}
+
+pub fn format_lines(lines: &[OcrLine]) -> Vec<Content> {
+    if lines.is_empty() {
+        return vec![Content::text("No text recognized on screen.")];
+    }
+    let text: Vec<String> = lines
+        .iter()
+        .map(|line| format!("{} {}", format_bounds(&line.bounds), line.text))
+        .collect();
+    vec![Content::text(format!(
+        "Recognized {} lines, bounding boxes are in screen coordinates:\n{}",
+        lines.len(),
+        text.join("\n")
+    ))]
+}
+
+pub fn format_matches(lines: &[OcrLine], query: &str) -> Vec<Content> {
+    let matches = find_text(lines, query);
+    if matches.is_empty() {
+        return vec![Content::text(format!(
+            "'{}' was not found among the {} lines of text recognized on screen. Use ocr_screen to see what was recognized.",
+            query,
+            lines.len()
+        ))];
+    }
+    let found: Vec<String> = matches
+        .iter()
+        .enumerate()
+        .map(|(i, (text, bounds))| {
+            let (x, y) = bounds.center();
+            format!(
+                "{}. \"{}\" click at ({}, {}) {}",
+                i + 1,
+                text,
+                x,
+                y,
+                format_bounds(bounds)
+            )
+        })
+        .collect();
+    vec![Content::text(format!(
+        "Found {} matches for '{}':\n{}",
+        matches.len(),
+        query,
+        found.join("\n")
+    ))]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TSV: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t800\t600\t-1\t
+4\t1\t1\t1\t1\t0\t20\t10\t200\t20\t-1\t
+5\t1\t1\t1\t1\t1\t20\t10\t60\t20\t96.1\tFile
+5\t1\t1\t1\t1\t2\t100\t10\t60\t20\t95.0\tSave
+5\t1\t1\t1\t1\t3\t170\t10\t40\t20\t91.3\tAs
+5\t1\t1\t1\t1\t4\t220\t10\t30\t20\t12.0\t~~
+5\t1\t2\t1\t1\t1\t20\t100\t80\t20\t90.0\tsave
+";
+
+    fn lines() -> Vec<OcrLine> {
+        // A retina capture of a region starting at (10, 5) on the display
+        let capture = Capture {
+            image: RgbaImage::new(1, 1),
+            origin: (10, 5),
+            scale: 2.0,
+        };
+        parse_tsv(TSV, DEFAULT_MIN_CONFIDENCE, |l, t, w, h| {
+            capture.to_screen(l, t, w, h)
+        })
+    }
+
+    #[test]
+    fn test_parse_tsv() {
+        let lines = lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].text, "File Save As");
+        assert_eq!(
+            lines[0].bounds,
+            Bounds {
+                x: 20,
+                y: 10,
+                width: 95,
+                height: 10
+            }
+        );
+        assert_eq!(lines[1].words[0].bounds.y, 55);
+    }
+
+    #[test]
+    fn test_find_text() {
+        let lines = lines();
+        let matches = find_text(&lines, "save as");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].0, "Save As");
+        assert_eq!(matches[0].1.center(), (87, 15));
+
+        assert_eq!(find_text(&lines, "SAVE").len(), 2);
+        assert!(find_text(&lines, "Open").is_empty());
+    }
+}
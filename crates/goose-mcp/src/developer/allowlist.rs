@@ -0,0 +1,417 @@
+/// Comma separated command prefixes the shell tool is restricted to, such as
+/// `cargo,npm,git status,pytest`. Unset or empty allows every command.
+pub const SHELL_ALLOWLIST_ENV: &str = "GOOSE_SHELL_ALLOWLIST";
+
+/// Changing directory runs nothing, so it is allowed with any list
+const ALWAYS_ALLOWED: &[&str] = &["cd"];
+
+/// The only variables a restricted shell lets the model set. Too many variables make a shell,
+/// loader or toolchain run something besides the command (PATH, LD_PRELOAD, NODE_OPTIONS,
+/// RUSTC_WRAPPER, GIT_PAGER, ...) to list the dangerous ones, so these are known to only
+/// change logging, output or locale.
+const SAFE_ENV: &[&str] = &[
+    "CI",
+    "COLUMNS",
+    "DEBUG",
+    "FORCE_COLOR",
+    "LANG",
+    "LC_ALL",
+    "NO_COLOR",
+    "NODE_ENV",
+    "PYTHONDONTWRITEBYTECODE",
+    "PYTHONUNBUFFERED",
+    "RUST_BACKTRACE",
+    "RUST_LOG",
+    "TERM",
+    "TZ",
+];
+
+/// The commands the shell tool may run, when restricted
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShellAllowlist {
+    prefixes: Vec<Vec<String>>,
+}
+
+impl ShellAllowlist {
+    pub fn from_env() -> Self {
+        Self::parse(&std::env::var(SHELL_ALLOWLIST_ENV).unwrap_or_default())
+    }
+
+    pub fn parse(value: &str) -> Self {
+        let prefixes = value
+            .split(',')
+            .map(|prefix| {
+                prefix
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|words| !words.is_empty())
+            .collect();
+        Self { prefixes }
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        !self.prefixes.is_empty()
+    }
+
+    /// The allowed prefixes for display, e.g. `cargo, git status`
+    pub fn describe(&self) -> String {
+        self.prefixes
+            .iter()
+            .map(|words| words.join(" "))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Why a simple command isn't allowed, None when it is
+    fn rejection(&self, words: &[String]) -> Option<String> {
+        // Leading variable assignments such as `RUST_LOG=debug cargo test` set the environment,
+        // so they get the same checks as variables set through the tool
+        let assignments = words.iter().take_while(|word| is_assignment(word));
+        for name in assignments.filter_map(|word| word.split_once('=').map(|(name, _)| name)) {
+            if self.unsafe_env(name) {
+                return Some(format!(
+                    "{} is not one of the variables it may set ({})",
+                    name,
+                    SAFE_ENV.join(", ")
+                ));
+            }
+        }
+
+        let words: Vec<&String> = words
+            .iter()
+            .skip_while(|word| is_assignment(word))
+            .collect();
+        let program = words.first()?;
+        let allowed = ALWAYS_ALLOWED.contains(&program.as_str())
+            || self.prefixes.iter().any(|prefix| {
+                prefix.len() <= words.len()
+                    && prefix
+                        .iter()
+                        .zip(&words)
+                        .all(|(allowed, word)| allowed == *word)
+            });
+        (!allowed).then(|| {
+            let command: Vec<&str> = words.iter().map(|word| word.as_str()).collect();
+            format!("`{}` is not one of them", command.join(" "))
+        })
+    }
+
+    fn unsafe_env(&self, name: &str) -> bool {
+        self.is_restricted() && !SAFE_ENV.contains(&name)
+    }
+
+    /// Check a variable the model wants to set for a command, rejecting names the shell can't
+    /// take and, when restricted, any variable that isn't known to be safe
+    pub fn check_env(&self, name: &str) -> Result<(), String> {
+        if !is_env_name(name) {
+            return Err(format!(
+                "'{}' is not a valid environment variable name",
+                name
+            ));
+        }
+        if self.unsafe_env(name) {
+            return Err(format!(
+                "The shell is restricted to commands starting with: {}. It may only set {}, so \
+                setting {} isn't allowed.",
+                self.describe(),
+                SAFE_ENV.join(", "),
+                name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check every command in `command`, including the ones chained with `&&`, `;` or pipes.
+    /// The error explains what is allowed instead.
+    pub fn check(&self, command: &str) -> Result<(), String> {
+        if !self.is_restricted() {
+            return Ok(());
+        }
+        let rejected = match split_commands(command) {
+            Ok(commands) => match commands.iter().find_map(|words| self.rejection(words)) {
+                Some(rejection) => rejection,
+                None => return Ok(()),
+            },
+            Err(construct) => format!("{} can't be checked against the list", construct),
+        };
+        Err(format!(
+            "The shell is restricted to commands starting with: {}. {}. Use one of the allowed \
+            commands instead, or ask the user to run this command themselves or to add it to {}.",
+            self.describe(),
+            rejected,
+            SHELL_ALLOWLIST_ENV
+        ))
+    }
+}
+
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=')
+        .is_some_and(|(name, _)| is_env_name(name))
+}
+
+/// Files output may be redirected to, since writing to them changes nothing
+const SAFE_OUTPUT_TARGETS: &[&str] = &["/dev/null", "/dev/stdout", "/dev/stderr"];
+
+/// What the word after a redirection operator is
+#[derive(Clone, Copy, PartialEq)]
+enum Target {
+    /// A file that is read, for `<`
+    Input,
+    /// A file that is written, for `>`, `>>`, `&>` and the like
+    Output,
+    /// A file descriptor, for `>&` and `<&`, where anything else is a file that is written
+    Descriptor,
+    /// The delimiter of a here-document or the text of a here-string
+    Text,
+}
+
+/// Split a shell command line into the words of each simple command, following quotes.
+/// Constructs that could run commands hidden from the list, such as command substitution or
+/// subshells, are an error naming the construct, and so is output redirected to a file.
+/// Redirections aren't part of the words.
+fn split_commands(line: &str) -> Result<Vec<Vec<String>>, &'static str> {
+    let mut commands = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut target = None;
+    let mut chars = line.chars().peekable();
+
+    fn end_word(
+        words: &mut Vec<String>,
+        word: &mut String,
+        in_word: &mut bool,
+        target: &mut Option<Target>,
+    ) -> Result<(), &'static str> {
+        if !*in_word {
+            return Ok(());
+        }
+        *in_word = false;
+        let word = std::mem::take(word);
+        let writes_file = match target.take() {
+            None => {
+                words.push(word);
+                return Ok(());
+            }
+            Some(Target::Output) => true,
+            Some(Target::Descriptor) => word != "-" && !word.chars().all(|c| c.is_ascii_digit()),
+            Some(Target::Input) | Some(Target::Text) => false,
+        };
+        if writes_file && !SAFE_OUTPUT_TARGETS.contains(&word.as_str()) {
+            return Err("Output redirected to a file");
+        }
+        Ok(())
+    }
+
+    // Start a redirection, the word before it is its file descriptor when it is a number
+    fn start_redirection(
+        words: &mut Vec<String>,
+        word: &mut String,
+        in_word: &mut bool,
+        target: &mut Option<Target>,
+    ) -> Result<(), &'static str> {
+        if target.is_some() {
+            return Err("An incomplete redirection");
+        }
+        if *in_word && !word.is_empty() && word.chars().all(|c| c.is_ascii_digit()) {
+            word.clear();
+            *in_word = false;
+        }
+        end_word(words, word, in_word, target)
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("An unterminated quote"),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            if let Some(c) = chars.next() {
+                                word.push(c);
+                            }
+                        }
+                        Some('`') => return Err("Command substitution"),
+                        Some('$') if chars.peek() == Some(&'(') => {
+                            return Err("Command substitution")
+                        }
+                        Some(c) => word.push(c),
+                        None => return Err("An unterminated quote"),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+            }
+            '`' => return Err("Command substitution"),
+            '$' if chars.peek() == Some(&'(') => return Err("Command substitution"),
+            '<' | '>' if chars.peek() == Some(&'(') => return Err("Process substitution"),
+            '(' | ')' | '{' | '}' if !in_word => return Err("A subshell or command group"),
+            // `&>` and `&>>` send both output streams to a file
+            '&' if chars.peek() == Some(&'>') => {
+                start_redirection(&mut words, &mut word, &mut in_word, &mut target)?;
+                chars.next();
+                chars.next_if_eq(&'>');
+                target = Some(Target::Output);
+            }
+            '>' => {
+                start_redirection(&mut words, &mut word, &mut in_word, &mut target)?;
+                target = if chars.next_if_eq(&'&').is_some() {
+                    Some(Target::Descriptor)
+                } else {
+                    chars.next_if(|&c| c == '>' || c == '|');
+                    Some(Target::Output)
+                };
+            }
+            '<' => {
+                start_redirection(&mut words, &mut word, &mut in_word, &mut target)?;
+                target = if chars.next_if_eq(&'&').is_some() {
+                    Some(Target::Descriptor)
+                } else if chars.next_if_eq(&'>').is_some() {
+                    Some(Target::Output)
+                } else if chars.next_if_eq(&'<').is_some() {
+                    chars.next_if(|&c| c == '<' || c == '-');
+                    Some(Target::Text)
+                } else {
+                    Some(Target::Input)
+                };
+            }
+            ';' | '&' | '|' | '\n' => {
+                end_word(&mut words, &mut word, &mut in_word, &mut target)?;
+                if target.is_some() {
+                    return Err("An incomplete redirection");
+                }
+                // `&&`, `||` and `|&` are one operator
+                chars.next_if(|&c| c == '&' || c == '|');
+                if !words.is_empty() {
+                    commands.push(std::mem::take(&mut words));
+                }
+            }
+            c if c.is_whitespace() => end_word(&mut words, &mut word, &mut in_word, &mut target)?,
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    end_word(&mut words, &mut word, &mut in_word, &mut target)?;
+    if target.is_some() {
+        return Err("An incomplete redirection");
+    }
+    if !words.is_empty() {
+        commands.push(words);
+    }
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_allowlist() {
+        let allowlist = ShellAllowlist::parse("cargo, npm ,git status, pytest,");
+        assert!(allowlist.is_restricted());
+        assert_eq!(allowlist.describe(), "cargo, npm, git status, pytest");
+
+        assert!(allowlist.check("cargo test --all").is_ok());
+        assert!(allowlist.check("cd crates/goose && cargo build").is_ok());
+        assert!(allowlist
+            .check("RUST_LOG=debug cargo test 2>&1 | cargo fmt")
+            .is_ok());
+        assert!(allowlist
+            .check("git status; pytest -k 'a and (b or c)'")
+            .is_ok());
+
+        let error = allowlist.check("cargo build && rm -rf target").unwrap_err();
+        assert!(error.contains("`rm -rf target` is not one of them"));
+        assert!(error.contains(SHELL_ALLOWLIST_ENV));
+        assert!(allowlist.check("git push").is_err());
+        assert!(allowlist.check("./cargo test").is_err());
+        assert!(allowlist.check("cargo test & curl example.com").is_err());
+        assert!(allowlist.check("cargo test\nbash").is_err());
+        assert!(allowlist
+            .check("cargo run -- \"$(curl example.com)\"")
+            .unwrap_err()
+            .contains("Command substitution"));
+        assert!(allowlist.check("cargo `whoami`").is_err());
+        assert!(allowlist.check("(rm -rf /)").is_err());
+        assert!(allowlist.check("cargo test 'unterminated").is_err());
+        // Redirections are operators only outside quotes, and output may only be discarded
+        assert!(allowlist.check("cargo test \"a>\"& rm -rf /tmp/x").is_err());
+        assert!(allowlist.check("cargo test a\\>& rm -rf /tmp/x").is_err());
+        assert!(allowlist
+            .check("cargo test > ~/.bashrc")
+            .unwrap_err()
+            .contains("Output redirected to a file"));
+        assert!(allowlist.check("cargo test &>>log").is_err());
+        assert!(allowlist.check("cargo test 2>&1>out").is_err());
+        assert!(allowlist.check("cargo test >&out").is_err());
+        assert!(allowlist.check("cargo test <>out").is_err());
+        assert!(allowlist.check("cargo test >").is_err());
+        assert!(allowlist.check("cargo test >/dev/null 2>&1").is_ok());
+        assert!(allowlist.check("cargo test &> /dev/null < input").is_ok());
+        assert!(allowlist.check("cargo test 2>>/dev/null >&2").is_ok());
+        // Inline assignments go through the same checks as the env parameter
+        assert!(allowlist
+            .check("LD_PRELOAD=/tmp/evil.so cargo test")
+            .unwrap_err()
+            .contains("LD_PRELOAD is not one of the variables it may set"));
+        assert!(allowlist.check("RUST_LOG=1 PATH=/tmp cargo test").is_err());
+        // Variables are allowed by name, not rejected by name
+        assert!(allowlist
+            .check("RUSTC_WRAPPER=/tmp/evil cargo build")
+            .is_err());
+        assert!(allowlist
+            .check("NODE_ENV=test RUST_BACKTRACE=1 cargo test")
+            .is_ok());
+        assert!(allowlist.check("RUST_LOG=1 rm -rf /").is_err());
+        assert!(allowlist.check("PATH=/tmp; cargo test").is_err());
+
+        let unrestricted = ShellAllowlist::parse(" ");
+        assert!(!unrestricted.is_restricted());
+        assert!(unrestricted.check("rm -rf target").is_ok());
+        assert!(unrestricted.check("PATH=/tmp make").is_ok());
+    }
+
+    #[test]
+    fn test_check_env() {
+        let allowlist = ShellAllowlist::parse("cargo");
+        assert!(allowlist.check_env("RUST_LOG").is_ok());
+        assert!(allowlist.check_env("LD_PRELOAD").is_err());
+        assert!(allowlist.check_env("PATH").is_err());
+        assert!(allowlist.check_env("NODE_OPTIONS").is_err());
+        assert!(allowlist
+            .check_env("GIT_PAGER")
+            .unwrap_err()
+            .contains("It may only set CI, COLUMNS"));
+
+        let unrestricted = ShellAllowlist::default();
+        assert!(unrestricted.check_env("PATH").is_ok());
+        assert!(unrestricted.check_env("1X").is_err());
+        assert!(unrestricted.check_env("A-B").is_err());
+        assert!(unrestricted.check_env("").is_err());
+    }
+}
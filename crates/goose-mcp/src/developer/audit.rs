@@ -0,0 +1,670 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use chrono::{DateTime, Local, Utc};
+use etcetera::{choose_app_strategy, AppStrategy};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tempfile::TempDir;
+
+/// Overrides where edit journals are kept
+pub const EDIT_JOURNAL_DIR_ENV: &str = "GOOSE_EDIT_JOURNAL_DIR";
+/// Set by the CLI to the session an extension was started for
+pub const SESSION_ENV: &str = "GOOSE_LOG_SESSION";
+
+const RUN_FILE: &str = "run.json";
+const JOURNAL_FILE: &str = "journal.jsonl";
+const BACKUP_DIR: &str = "backups";
+/// Prefix of the scratch directories holding the git index and objects of shell snapshots
+const SHELL_SCRATCH_PREFIX: &str = ".shell-";
+
+/// Directory holding one journal per run of the developer extension
+/// - macOS/Linux: ~/.local/share/goose/edit_journal/
+/// - Windows:     ~\AppData\Roaming\Block\goose\data\edit_journal\
+pub fn journal_root() -> PathBuf {
+    if let Some(dir) = std::env::var_os(EDIT_JOURNAL_DIR_ENV) {
+        return PathBuf::from(dir);
+    }
+    choose_app_strategy(crate::APP_STRATEGY.clone())
+        .map(|strategy| strategy.in_data_dir("edit_journal"))
+        .unwrap_or_else(|_| {
+            PathBuf::from(shellexpand::tilde("~/.local/share/goose/edit_journal").to_string())
+        })
+}
+
+/// Stands in for the hash of a file that an edit deleted
+const MISSING_HASH: &str = "missing";
+
+fn hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+fn current_hash(path: &Path) -> String {
+    fs::read(path)
+        .map(|content| hash(&content))
+        .unwrap_or_else(|_| MISSING_HASH.to_string())
+}
+
+/// A run of the developer extension, one goose session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub run_id: String,
+    pub started_at: DateTime<Utc>,
+    pub working_dir: PathBuf,
+    /// The goose session the run belongs to, when it was started by the CLI
+    #[serde(default)]
+    pub session: Option<String>,
+    #[serde(default)]
+    pub rolled_back_at: Option<DateTime<Utc>>,
+}
+
+/// A single file write recorded in a run's journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub seq: usize,
+    pub timestamp: DateTime<Utc>,
+    pub path: PathBuf,
+    pub operation: String,
+    /// Hash of the file before the edit, `None` when the edit created it
+    pub before_hash: Option<String>,
+    pub after_hash: String,
+    /// Copy of the file before the edit, relative to the run directory
+    pub backup: Option<PathBuf>,
+}
+
+/// Journal of the file edits made during one run, so they can be rolled back without git
+pub struct EditJournal {
+    root: PathBuf,
+    run_id: String,
+    /// Set once the run directory exists, nothing is written for runs that edit no files
+    dir: Option<PathBuf>,
+    next_seq: usize,
+    /// Where this run's shell snapshots keep their index and objects, removed with the journal
+    shell_scratch: Option<TempDir>,
+}
+
+impl EditJournal {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            run_id: Local::now().format("%Y%m%d_%H%M%S").to_string(),
+            dir: None,
+            next_seq: 0,
+            shell_scratch: None,
+        }
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    fn run_dir(&mut self) -> io::Result<PathBuf> {
+        if let Some(dir) = &self.dir {
+            return Ok(dir.clone());
+        }
+
+        // Another run may have started in the same second
+        let base = self.run_id.clone();
+        let mut suffix = 2;
+        while self.root.join(&self.run_id).exists() {
+            self.run_id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+
+        let dir = self.root.join(&self.run_id);
+        fs::create_dir_all(dir.join(BACKUP_DIR))?;
+        let info = RunInfo {
+            run_id: self.run_id.clone(),
+            started_at: Utc::now(),
+            working_dir: std::env::current_dir().unwrap_or_default(),
+            session: std::env::var(SESSION_ENV).ok(),
+            rolled_back_at: None,
+        };
+        fs::write(dir.join(RUN_FILE), serde_json::to_string_pretty(&info)?)?;
+        self.dir = Some(dir.clone());
+        Ok(dir)
+    }
+
+    /// Snapshot the git worktree containing `dir` before a shell command runs in it, see
+    /// [`ShellSnapshot`]
+    pub fn shell_snapshot(&mut self, dir: &Path) -> Option<ShellSnapshot> {
+        if self.shell_scratch.is_none() {
+            fs::create_dir_all(&self.root).ok()?;
+            let scratch = tempfile::Builder::new()
+                .prefix(SHELL_SCRATCH_PREFIX)
+                .tempdir_in(&self.root)
+                .ok()?;
+            fs::create_dir(scratch.path().join("objects")).ok()?;
+            self.shell_scratch = Some(scratch);
+        }
+        ShellSnapshot::take(dir, self.shell_scratch.as_ref()?.path())
+    }
+
+    /// Record an edit that was just written to `path`, given the file's content before it.
+    /// The edit may have deleted the file.
+    pub fn record(
+        &mut self,
+        path: &Path,
+        operation: &str,
+        before: Option<&[u8]>,
+    ) -> io::Result<()> {
+        let dir = self.run_dir()?;
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        let backup = match before {
+            Some(content) => {
+                let backup = Path::new(BACKUP_DIR).join(seq.to_string());
+                fs::write(dir.join(&backup), content)?;
+                Some(backup)
+            }
+            None => None,
+        };
+
+        let entry = JournalEntry {
+            seq,
+            timestamp: Utc::now(),
+            path: path.to_path_buf(),
+            operation: operation.to_string(),
+            before_hash: before.map(hash),
+            after_hash: current_hash(path),
+            backup,
+        };
+
+        let mut journal = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(JOURNAL_FILE))?;
+        writeln!(journal, "{}", serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+}
+
+/// Where a snapshot keeps its git state, apart from the repository it snapshots
+struct SnapshotStore {
+    index: PathBuf,
+    objects: PathBuf,
+    /// The repository's own objects, read but never written to
+    alternates: PathBuf,
+}
+
+fn git(repo: &Path, store: &SnapshotStore, args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .env("GIT_INDEX_FILE", &store.index)
+        .env("GIT_OBJECT_DIRECTORY", &store.objects)
+        .env("GIT_ALTERNATE_OBJECT_DIRECTORIES", &store.alternates)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("git {} failed", args.join(" "))));
+    }
+    Ok(output.stdout)
+}
+
+/// The files of a git worktree as they were before a shell command ran, so the files the
+/// command changed can be journaled like any other edit.
+///
+/// Files are staged into a private index, and hashed into a private object directory, in
+/// the run's scratch directory under the journal root, so nothing is written to the user's
+/// repository. Ignored files are left out. Commands run outside a git worktree are not
+/// journaled.
+pub struct ShellSnapshot {
+    repo: PathBuf,
+    store: SnapshotStore,
+    tree: String,
+}
+
+impl ShellSnapshot {
+    /// Snapshot the worktree containing `dir`, if there is one, keeping the git state in
+    /// `scratch`
+    fn take(dir: &Path, scratch: &Path) -> Option<Self> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["rev-parse", "--show-toplevel", "--git-common-dir"])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let output = String::from_utf8_lossy(&output.stdout);
+        let mut lines = output.lines();
+        let repo = PathBuf::from(lines.next()?.trim());
+        // Relative to `dir` unless git gave an absolute path
+        let common_dir = dir.join(lines.next()?.trim());
+        let store = SnapshotStore {
+            // Kept between commands so git only rehashes files whose stat changed
+            index: scratch.join(format!(
+                "{}.index",
+                &hash(repo.to_string_lossy().as_bytes())[..16]
+            )),
+            objects: scratch.join("objects"),
+            alternates: common_dir.join("objects"),
+        };
+        let tree = Self::write_tree(&repo, &store).ok()?;
+        Some(Self { repo, store, tree })
+    }
+
+    fn write_tree(repo: &Path, store: &SnapshotStore) -> io::Result<String> {
+        git(repo, store, &["add", "-A"])?;
+        let tree = git(repo, store, &["write-tree"])?;
+        Ok(String::from_utf8_lossy(&tree).trim().to_string())
+    }
+
+    /// The files changed since the snapshot, each with its content beforehand, `None` for
+    /// files the command created
+    pub fn changes(&self) -> io::Result<Vec<(PathBuf, Option<Vec<u8>>)>> {
+        let after = Self::write_tree(&self.repo, &self.store)?;
+        if after == self.tree {
+            return Ok(Vec::new());
+        }
+        let diff = git(
+            &self.repo,
+            &self.store,
+            &[
+                "diff-tree",
+                "-r",
+                "-z",
+                "--no-renames",
+                "--name-status",
+                &self.tree,
+                &after,
+            ],
+        )?;
+
+        let fields: Vec<String> = diff
+            .split(|b| *b == 0)
+            .filter(|field| !field.is_empty())
+            .map(|field| String::from_utf8_lossy(field).to_string())
+            .collect();
+        let mut changes = Vec::new();
+        for pair in fields.chunks(2) {
+            let [status, path] = pair else {
+                continue;
+            };
+            let before = if status == "A" {
+                None
+            } else {
+                let object = format!("{}:{}", self.tree, path);
+                Some(git(
+                    &self.repo,
+                    &self.store,
+                    &["cat-file", "blob", &object],
+                )?)
+            };
+            changes.push((self.repo.join(path), before));
+        }
+        Ok(changes)
+    }
+}
+
+fn read_run(dir: &Path) -> io::Result<RunInfo> {
+    Ok(serde_json::from_str(&fs::read_to_string(
+        dir.join(RUN_FILE),
+    )?)?)
+}
+
+fn read_entries(dir: &Path) -> io::Result<Vec<JournalEntry>> {
+    let file = fs::File::open(dir.join(JOURNAL_FILE))?;
+    io::BufReader::new(file)
+        .lines()
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
+
+/// A run along with what it touched
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    pub info: RunInfo,
+    pub files: Vec<PathBuf>,
+    pub edits: usize,
+}
+
+/// Every recorded run, most recent first
+pub fn list_runs(root: &Path) -> io::Result<Vec<RunSummary>> {
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut runs = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let dir = entry?.path();
+        let (Ok(info), Ok(entries)) = (read_run(&dir), read_entries(&dir)) else {
+            continue;
+        };
+        let mut files: Vec<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+        files.sort();
+        files.dedup();
+        runs.push(RunSummary {
+            info,
+            files,
+            edits: entries.len(),
+        });
+    }
+    runs.sort_by_key(|run| std::cmp::Reverse(run.info.started_at));
+    Ok(runs)
+}
+
+/// The net change made to one file, from before its first edit to after its last
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: PathBuf,
+    /// The file before its first edit, `None` when an edit created it
+    pub before: Option<Vec<u8>>,
+    pub after_hash: String,
+}
+
+impl FileChange {
+    /// A change that left `path` as it is now, from `before`
+    pub fn new(path: PathBuf, before: Option<Vec<u8>>) -> Self {
+        let after_hash = current_hash(&path);
+        Self {
+            path,
+            before,
+            after_hash,
+        }
+    }
+
+    /// Whether the edits ended up leaving the file as it was
+    pub fn is_noop(&self) -> bool {
+        self.before.as_deref().map(hash).as_ref() == Some(&self.after_hash)
+    }
+
+    /// Whether the file still is as the last edit left it
+    pub fn is_current(&self) -> bool {
+        current_hash(&self.path) == self.after_hash
+    }
+
+    /// Put the file back as it was before its first edit, deleting it if an edit created it
+    pub fn restore(&self) -> io::Result<()> {
+        match &self.before {
+            Some(content) => fs::write(&self.path, content),
+            None if self.path.exists() => fs::remove_file(&self.path),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The files edited for a session since `since`, across every run of the developer extension
+/// started for it that wasn't rolled back, sorted by path
+pub fn session_changes(
+    root: &Path,
+    session: &str,
+    since: DateTime<Utc>,
+) -> io::Result<Vec<FileChange>> {
+    let mut runs: Vec<RunSummary> = list_runs(root)?
+        .into_iter()
+        .filter(|run| {
+            run.info.session.as_deref() == Some(session) && run.info.rolled_back_at.is_none()
+        })
+        .collect();
+    runs.reverse();
+
+    let mut changes: Vec<FileChange> = Vec::new();
+    for run in runs {
+        let dir = root.join(&run.info.run_id);
+        for entry in read_entries(&dir)? {
+            if entry.timestamp < since {
+                continue;
+            }
+            match changes.iter_mut().find(|change| change.path == entry.path) {
+                Some(change) => change.after_hash = entry.after_hash,
+                None => {
+                    let before = match &entry.backup {
+                        Some(backup) => Some(fs::read(dir.join(backup))?),
+                        None => None,
+                    };
+                    changes.push(FileChange {
+                        path: entry.path,
+                        before,
+                        after_hash: entry.after_hash,
+                    });
+                }
+            }
+        }
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+/// What a rollback did
+#[derive(Debug, Default)]
+pub struct RollbackReport {
+    /// Files put back to how they were before the run
+    pub restored: Vec<PathBuf>,
+    /// Files the run created, which were deleted
+    pub removed: Vec<PathBuf>,
+    /// Files changed since the run's last edit, left alone unless forced
+    pub conflicts: Vec<PathBuf>,
+}
+
+/// Restore every file a run touched to its state before the run.
+///
+/// Files that changed after the run's last edit to them are reported as conflicts and left
+/// alone, unless `force` is set.
+pub fn rollback(root: &Path, run_id: &str, force: bool) -> anyhow::Result<RollbackReport> {
+    let dir = root.join(run_id);
+    let mut info = read_run(&dir)
+        .map_err(|e| anyhow::anyhow!("No edit journal for run '{}': {}", run_id, e))?;
+    if let Some(at) = info.rolled_back_at {
+        anyhow::bail!("Run '{}' was already rolled back at {}", run_id, at);
+    }
+
+    // The first edit to a path holds its original state, the last its expected current state
+    let mut files: HashMap<PathBuf, (JournalEntry, String)> = HashMap::new();
+    for entry in read_entries(&dir)? {
+        let after_hash = entry.after_hash.clone();
+        files
+            .entry(entry.path.clone())
+            .and_modify(|(_, last)| *last = after_hash.clone())
+            .or_insert((entry, after_hash));
+    }
+
+    let mut paths: Vec<_> = files.keys().cloned().collect();
+    paths.sort();
+
+    let mut report = RollbackReport::default();
+    for path in paths {
+        let (first, last_hash) = &files[&path];
+        let exists = path.exists();
+        if !exists && first.backup.is_none() {
+            // Created by the run and already deleted since
+            continue;
+        }
+        let unchanged = current_hash(&path) == *last_hash;
+        if !unchanged && !force {
+            report.conflicts.push(path);
+            continue;
+        }
+
+        match &first.backup {
+            Some(backup) => {
+                fs::copy(dir.join(backup), &path)?;
+                report.restored.push(path);
+            }
+            None => {
+                if exists {
+                    fs::remove_file(&path)?;
+                }
+                report.removed.push(path);
+            }
+        }
+    }
+
+    if report.conflicts.is_empty() {
+        info.rolled_back_at = Some(Utc::now());
+        fs::write(dir.join(RUN_FILE), serde_json::to_string_pretty(&info)?)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rollback_restores_edited_and_created_files() {
+        let journal_dir = TempDir::new().unwrap();
+        let work_dir = TempDir::new().unwrap();
+        let edited = work_dir.path().join("edited.txt");
+        let created = work_dir.path().join("created.txt");
+        let changed_later = work_dir.path().join("changed_later.txt");
+        fs::write(&edited, "original").unwrap();
+        fs::write(&changed_later, "original").unwrap();
+
+        let mut journal = EditJournal::new(journal_dir.path().to_path_buf());
+        for content in ["first edit", "second edit"] {
+            let before = fs::read(&edited).unwrap();
+            fs::write(&edited, content).unwrap();
+            journal
+                .record(&edited, "str_replace", Some(&before))
+                .unwrap();
+        }
+        fs::write(&created, "new").unwrap();
+        journal.record(&created, "write", None).unwrap();
+        fs::write(&changed_later, "goose").unwrap();
+        journal
+            .record(&changed_later, "write", Some(b"original"))
+            .unwrap();
+        fs::write(&changed_later, "user").unwrap();
+
+        let run_id = journal.run_id().to_string();
+        let runs = list_runs(journal_dir.path()).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].edits, 4);
+        assert_eq!(runs[0].files.len(), 3);
+
+        // A file changed after goose's edit is not overwritten
+        let report = rollback(journal_dir.path(), &run_id, false).unwrap();
+        assert_eq!(fs::read_to_string(&edited).unwrap(), "original");
+        assert!(!created.exists());
+        assert_eq!(report.conflicts, vec![changed_later.clone()]);
+        assert_eq!(fs::read_to_string(&changed_later).unwrap(), "user");
+
+        let report = rollback(journal_dir.path(), &run_id, true).unwrap();
+        assert!(report.conflicts.is_empty());
+        assert_eq!(fs::read_to_string(&changed_later).unwrap(), "original");
+        assert!(rollback(journal_dir.path(), &run_id, true).is_err());
+    }
+
+    #[test]
+    fn test_session_changes() {
+        let journal_dir = TempDir::new().unwrap();
+        let work_dir = TempDir::new().unwrap();
+        let edited = work_dir.path().join("edited.txt");
+        let created = work_dir.path().join("created.txt");
+        let reverted = work_dir.path().join("reverted.txt");
+        fs::write(&edited, "original").unwrap();
+        fs::write(&reverted, "original").unwrap();
+
+        let since = Utc::now();
+        let mut journal = EditJournal::new(journal_dir.path().to_path_buf());
+        journal.run_dir().unwrap();
+        let mut info = read_run(&journal_dir.path().join(journal.run_id())).unwrap();
+        info.session = Some("review".to_string());
+        fs::write(
+            journal_dir.path().join(journal.run_id()).join(RUN_FILE),
+            serde_json::to_string(&info).unwrap(),
+        )
+        .unwrap();
+
+        for content in ["first edit", "second edit"] {
+            let before = fs::read(&edited).unwrap();
+            fs::write(&edited, content).unwrap();
+            journal
+                .record(&edited, "str_replace", Some(&before))
+                .unwrap();
+        }
+        fs::write(&created, "new").unwrap();
+        journal.record(&created, "write", None).unwrap();
+        fs::write(&reverted, "changed").unwrap();
+        journal
+            .record(&reverted, "write", Some(b"original"))
+            .unwrap();
+        fs::write(&reverted, "original").unwrap();
+        journal
+            .record(&reverted, "write", Some(b"changed"))
+            .unwrap();
+
+        assert!(session_changes(journal_dir.path(), "other", since)
+            .unwrap()
+            .is_empty());
+        let changes = session_changes(journal_dir.path(), "review", since).unwrap();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].path, created);
+        assert_eq!(changes[1].path, edited);
+        assert_eq!(changes[1].before.as_deref(), Some(&b"original"[..]));
+        assert!(changes[1].is_current());
+        assert!(changes[2].is_noop());
+
+        changes[0].restore().unwrap();
+        changes[1].restore().unwrap();
+        assert!(!created.exists());
+        assert_eq!(fs::read_to_string(&edited).unwrap(), "original");
+    }
+
+    #[test]
+    fn test_shell_snapshot_rolls_back_shell_edits() {
+        let journal_dir = TempDir::new().unwrap();
+        let work_dir = TempDir::new().unwrap();
+        let repo = work_dir.path().canonicalize().unwrap();
+        if !Command::new("git")
+            .arg("init")
+            .arg(&repo)
+            .output()
+            .is_ok_and(|output| output.status.success())
+        {
+            // git isn't available
+            return;
+        }
+        let edited = repo.join("edited.txt");
+        let deleted = repo.join("deleted.txt");
+        let created = repo.join("created.txt");
+        fs::write(&edited, "original").unwrap();
+        fs::write(&deleted, "original").unwrap();
+
+        let objects = || {
+            fs::read_dir(repo.join(".git/objects"))
+                .unwrap()
+                .filter(|entry| entry.as_ref().unwrap().file_name().len() == 2)
+                .count()
+        };
+
+        let mut journal = EditJournal::new(journal_dir.path().to_path_buf());
+        let snapshot = journal.shell_snapshot(&repo).unwrap();
+        assert!(snapshot.changes().unwrap().is_empty());
+        fs::write(&edited, "shell edit").unwrap();
+        fs::remove_file(&deleted).unwrap();
+        fs::write(&created, "new").unwrap();
+
+        for (path, before) in snapshot.changes().unwrap() {
+            journal.record(&path, "shell", before.as_deref()).unwrap();
+        }
+        // Snapshots keep their index and objects out of the user's repository
+        assert_eq!(objects(), 0);
+        assert!(!repo.join(".git/index").exists());
+        let report = rollback(journal_dir.path(), journal.run_id(), false).unwrap();
+        assert!(report.conflicts.is_empty());
+        assert_eq!(report.removed, vec![created.clone()]);
+        assert_eq!(fs::read_to_string(&edited).unwrap(), "original");
+        assert_eq!(fs::read_to_string(&deleted).unwrap(), "original");
+        assert!(!created.exists());
+        drop(journal);
+        let leftovers: Vec<_> = fs::read_dir(journal_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name.to_string_lossy().starts_with(SHELL_SCRATCH_PREFIX))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+}
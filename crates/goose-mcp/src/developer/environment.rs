@@ -0,0 +1,287 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::task::JoinSet;
+
+/// How long a single version probe may take, some toolchain shims are slow to start
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Commands that print the version of a toolchain, name then command line
+const TOOLCHAINS: &[(&str, &[&str])] = &[
+    ("rustc", &["rustc", "--version"]),
+    ("cargo", &["cargo", "--version"]),
+    ("node", &["node", "--version"]),
+    ("npm", &["npm", "--version"]),
+    ("pnpm", &["pnpm", "--version"]),
+    ("yarn", &["yarn", "--version"]),
+    ("python", &["python3", "--version"]),
+    ("pip", &["pip3", "--version"]),
+    ("uv", &["uv", "--version"]),
+    ("go", &["go", "version"]),
+    ("java", &["java", "-version"]),
+    ("ruby", &["ruby", "--version"]),
+    ("git", &["git", "--version"]),
+    ("docker", &["docker", "--version"]),
+];
+
+/// Lockfiles and manifests, with the package manager they belong to
+const LOCKFILES: &[(&str, &str)] = &[
+    ("Cargo.lock", "cargo"),
+    ("package-lock.json", "npm"),
+    ("yarn.lock", "yarn"),
+    ("pnpm-lock.yaml", "pnpm"),
+    ("bun.lockb", "bun"),
+    ("uv.lock", "uv"),
+    ("poetry.lock", "poetry"),
+    ("Pipfile.lock", "pipenv"),
+    ("requirements.txt", "pip"),
+    ("go.sum", "go"),
+    ("Gemfile.lock", "bundler"),
+    ("composer.lock", "composer"),
+];
+
+#[derive(Debug, Serialize)]
+pub struct Toolchain {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Lockfile {
+    pub file: String,
+    pub package_manager: String,
+    /// Number of locked packages, unset when the format isn't understood
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub packages: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnvironmentReport {
+    pub os: String,
+    pub arch: String,
+    pub directory: String,
+    pub toolchains: Vec<Toolchain>,
+    /// Toolchains that were looked for and not found
+    pub missing: Vec<String>,
+    pub lockfiles: Vec<Lockfile>,
+    /// Names of the environment variables that are set, never their values
+    pub env_vars: Vec<String>,
+}
+
+/// The schema of the report, declared as the tool's output schema
+pub fn report_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["os", "arch", "directory", "toolchains", "missing", "lockfiles", "env_vars"],
+        "properties": {
+            "os": {"type": "string"},
+            "arch": {"type": "string"},
+            "directory": {"type": "string"},
+            "toolchains": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["name", "version"],
+                    "properties": {
+                        "name": {"type": "string"},
+                        "version": {"type": "string"}
+                    }
+                }
+            },
+            "missing": {"type": "array", "items": {"type": "string"}},
+            "lockfiles": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["file", "package_manager"],
+                    "properties": {
+                        "file": {"type": "string"},
+                        "package_manager": {"type": "string"},
+                        "packages": {"type": "integer"}
+                    }
+                }
+            },
+            "env_vars": {"type": "array", "items": {"type": "string"}}
+        }
+    })
+}
+
+/// The first line a version command printed, java among others prints it to stderr
+async fn probe(args: &'static [&'static str]) -> Option<String> {
+    let output = Command::new(args[0])
+        .args(&args[1..])
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(PROBE_TIMEOUT, output)
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let printed = [&output.stdout, &output.stderr]
+        .into_iter()
+        .map(|out| String::from_utf8_lossy(out).trim().to_string())
+        .find(|out| !out.is_empty())?;
+    printed.lines().next().map(str::to_string)
+}
+
+/// Count the packages a lockfile pins, for the formats simple enough to count by line
+fn count_packages(file: &str, contents: &str) -> Option<usize> {
+    let count_prefix = |prefix: &str| {
+        contents
+            .lines()
+            .filter(|line| line.starts_with(prefix))
+            .count()
+    };
+    match file {
+        "Cargo.lock" | "uv.lock" | "poetry.lock" => Some(count_prefix("[[package]]")),
+        "package-lock.json" | "composer.lock" | "Pipfile.lock" => {
+            let lock: Value = serde_json::from_str(contents).ok()?;
+            match file {
+                // Every entry but the root project itself
+                "package-lock.json" => lock
+                    .get("packages")
+                    .and_then(Value::as_object)
+                    .map(|packages| packages.keys().filter(|key| !key.is_empty()).count()),
+                "composer.lock" => lock.get("packages").and_then(Value::as_array).map(Vec::len),
+                _ => lock
+                    .get("default")
+                    .and_then(Value::as_object)
+                    .map(|packages| packages.len()),
+            }
+        }
+        "yarn.lock" => Some(
+            contents
+                .lines()
+                .filter(|line| !line.starts_with([' ', '#']) && line.trim_end().ends_with(':'))
+                .count(),
+        ),
+        "requirements.txt" => Some(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with(['#', '-']))
+                .count(),
+        ),
+        // Each module appears twice, once for its go.mod
+        "go.sum" => Some(
+            contents
+                .lines()
+                .filter(|line| !line.contains("/go.mod "))
+                .count(),
+        ),
+        "Gemfile.lock" => Some(
+            contents
+                .lines()
+                .filter(|line| line.starts_with("    ") && !line.starts_with("     "))
+                .count(),
+        ),
+        _ => None,
+    }
+}
+
+fn lockfiles(dir: &Path) -> Vec<Lockfile> {
+    LOCKFILES
+        .iter()
+        .filter(|(file, _)| dir.join(file).is_file())
+        .map(|(file, package_manager)| Lockfile {
+            file: file.to_string(),
+            package_manager: package_manager.to_string(),
+            packages: std::fs::read_to_string(dir.join(file))
+                .ok()
+                .and_then(|contents| count_packages(file, &contents)),
+        })
+        .collect()
+}
+
+/// Gather what the agent would otherwise probe for with a dozen shell commands
+pub async fn gather(dir: &Path) -> EnvironmentReport {
+    let mut probes = JoinSet::new();
+    for (index, (_, args)) in TOOLCHAINS.iter().enumerate() {
+        probes.spawn(async move { (index, probe(args).await) });
+    }
+    let mut versions = vec![None; TOOLCHAINS.len()];
+    while let Some(Ok((index, version))) = probes.join_next().await {
+        versions[index] = version;
+    }
+
+    let mut toolchains = Vec::new();
+    let mut missing = Vec::new();
+    for ((name, _), version) in TOOLCHAINS.iter().zip(versions) {
+        match version {
+            Some(version) => toolchains.push(Toolchain {
+                name: name.to_string(),
+                version,
+            }),
+            None => missing.push(name.to_string()),
+        }
+    }
+
+    let mut env_vars: Vec<String> = std::env::vars_os()
+        .map(|(name, _)| name.to_string_lossy().into_owned())
+        .collect();
+    env_vars.sort();
+
+    EnvironmentReport {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        directory: dir.display().to_string(),
+        toolchains,
+        missing,
+        lockfiles: lockfiles(dir),
+        env_vars,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_packages() {
+        let cargo = "version = 3\n\n[[package]]\nname = \"a\"\n\n[[package]]\nname = \"b\"\n";
+        assert_eq!(count_packages("Cargo.lock", cargo), Some(2));
+
+        let npm = r#"{"packages": {"": {}, "node_modules/a": {}, "node_modules/b": {}}}"#;
+        assert_eq!(count_packages("package-lock.json", npm), Some(2));
+
+        let requirements = "# pinned\nrequests==2.31\n\n-r dev.txt\nflask\n";
+        assert_eq!(count_packages("requirements.txt", requirements), Some(2));
+
+        let go = "golang.org/x/a v1.0.0 h1:x=\ngolang.org/x/a v1.0.0/go.mod h1:y=\n";
+        assert_eq!(count_packages("go.sum", go), Some(1));
+        assert_eq!(count_packages("package-lock.json", "not json"), None);
+    }
+
+    #[tokio::test]
+    async fn test_gather_lists_names_not_values() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cargo.lock"), "[[package]]\nname = \"a\"\n").unwrap();
+        std::env::set_var("GOOSE_ENV_REPORT_TEST", "secret-value");
+
+        let report = gather(dir.path()).await;
+        assert_eq!(
+            report.lockfiles,
+            vec![Lockfile {
+                file: "Cargo.lock".to_string(),
+                package_manager: "cargo".to_string(),
+                packages: Some(1),
+            }]
+        );
+        assert!(report
+            .env_vars
+            .contains(&"GOOSE_ENV_REPORT_TEST".to_string()));
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(!json.contains("secret-value"));
+        assert_eq!(
+            report.toolchains.len() + report.missing.len(),
+            TOOLCHAINS.len()
+        );
+        std::env::remove_var("GOOSE_ENV_REPORT_TEST");
+    }
+}
@@ -1,5 +1,10 @@
+pub(crate) mod allowlist;
+pub mod audit;
+mod environment;
 mod lang;
-mod shell;
+pub(crate) mod shell;
+mod syntax;
+mod workspace;
 
 use anyhow::Result;
 use base64::Engine;
@@ -46,7 +51,11 @@ use std::process::Stdio;
 use std::sync::{Arc, Mutex};
 use xcap::{Monitor, Window};
 
-use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::gitignore::Gitignore;
+
+use self::allowlist::ShellAllowlist;
+use self::audit::EditJournal;
+use self::workspace::{build_ignore_patterns, Workspace};
 
 // Embeds the prompts directory to the build
 static PROMPTS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/src/developer/prompts");
@@ -99,7 +108,15 @@ pub struct DeveloperRouter {
     prompts: Arc<HashMap<String, Prompt>>,
     instructions: String,
     file_history: Arc<Mutex<HashMap<PathBuf, Vec<String>>>>,
+    /// Every file edit of this run, for `goose session rollback`
+    edit_journal: Arc<Mutex<EditJournal>>,
+    /// Ignore patterns of the primary root, the directory goose was started in
     ignore_patterns: Arc<Gitignore>,
+    workspace: Arc<Mutex<Workspace>>,
+    /// Command prefixes the shell tool is restricted to, see `GOOSE_SHELL_ALLOWLIST`
+    shell_allowlist: Arc<ShellAllowlist>,
+    /// Directory set with set_cwd that shell commands run in unless they pass `working_dir`
+    cwd: Arc<Mutex<Option<PathBuf>>>,
 }
 
 impl Default for DeveloperRouter {
@@ -135,6 +152,9 @@ impl DeveloperRouter {
                   - To locate content inside files: `findstr /s /i "class Example" *.py`
 
                 Note: Alternative commands may show ignored/hidden files that should be excluded.
+
+                To run a command in another directory pass `working_dir`, and pass variables in `env`.
+                Use the set_cwd tool to change the directory every later command runs in.
             "#},
             _ => indoc! {r#"
                 Execute a command in the shell.
@@ -149,7 +169,11 @@ impl DeveloperRouter {
 
                 **Important**: Each shell command runs in its own process. Things like directory changes or
                 sourcing files do not persist between tool calls. So you may need to repeat them each time by
-                stringing together commands, e.g. `cd example && ls` or `source env/bin/activate && pip install numpy`
+                stringing together commands, e.g. `source env/bin/activate && pip install numpy`
+
+                To run a command in another directory pass `working_dir` instead of prefixing it with `cd`,
+                and pass variables in `env` instead of chaining `export`s. Use the set_cwd tool to change the
+                directory every later command runs in.
 
                 **Important**: Use ripgrep - `rg` - when you need to locate a file or a code reference, other solutions
                 may show ignored or hidden files. For example *do not* use `find` or `ls -r`
@@ -158,26 +182,72 @@ impl DeveloperRouter {
             "#},
         };
 
+        let shell_allowlist = ShellAllowlist::from_env();
+        let shell_tool_desc = if shell_allowlist.is_restricted() {
+            format!(
+                "{}\n**Important**: Only commands starting with one of these are allowed: {}. \
+                Other commands, command substitution and subshells are rejected.\n",
+                shell_tool_desc,
+                shell_allowlist.describe()
+            )
+        } else {
+            shell_tool_desc.to_string()
+        };
+
         let bash_tool = Tool::new(
             "shell".to_string(),
-            shell_tool_desc.to_string(),
+            shell_tool_desc,
             json!({
                 "type": "object",
                 "required": ["command"],
                 "properties": {
-                    "command": {"type": "string"}
+                    "command": {"type": "string"},
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Directory to run the command in, relative paths are resolved against the current one"
+                    },
+                    "env": {
+                        "type": "object",
+                        "additionalProperties": {"type": "string"},
+                        "description": "Environment variables to set for this command only"
+                    }
                 }
             }),
             None,
         );
 
+        let set_cwd_tool = Tool::new(
+            "set_cwd",
+            indoc! {r#"
+                Change the directory shell commands run in, for the rest of the session.
+                Commands can still pass their own `working_dir`.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["path"],
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Directory to run shell commands in, relative paths are resolved against the current one"
+                    }
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Set working directory".to_string()),
+                read_only_hint: false,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
         let text_editor_tool = Tool::new(
             "text_editor".to_string(),
             indoc! {r#"
                 Perform text editing operations on files.
 
                 The `command` parameter specifies the operation to perform. Allowed options are:
-                - `view`: View the content of a file.
+                - `view`: View the content of a file. Images (png, jpg, gif, webp, bmp) are returned as images.
                 - `write`: Create or overwrite a file with the given content
                 - `str_replace`: Replace a string in a file with a new string.
                 - `undo_edit`: Undo the last edit made to a file.
@@ -295,6 +365,30 @@ impl DeveloperRouter {
             }),
         );
 
+        let environment_info_tool = Tool::new(
+            "environment_info",
+            indoc! {r#"
+                Report the environment in one call: the OS, versions of the installed toolchains
+                (rustc, cargo, node, python, go and others), the lockfiles of the active directory
+                with how many packages each pins, and the names of the set environment variables.
+                Values of environment variables are never included.
+
+                Use this once at the start of a task instead of probing with shell commands.
+            "#},
+            json!({
+                "type": "object",
+                "properties": {}
+            }),
+            Some(ToolAnnotations {
+                title: Some("Environment info".to_string()),
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        )
+        .with_output_schema(environment::report_schema());
+
         // Get base instructions and working directory
         let cwd = std::env::current_dir().expect("should have a current working dir");
         let os = std::env::consts::OS;
@@ -374,6 +468,59 @@ impl DeveloperRouter {
             }
         }
 
+        let ignore_patterns = Arc::new(build_ignore_patterns(&cwd));
+        let workspace = Workspace::discover(cwd.clone(), Arc::clone(&ignore_patterns));
+
+        // Each additional workspace root can bring its own hints
+        for root in workspace.roots().iter().skip(1) {
+            if let Ok(root_hints) = std::fs::read_to_string(root.path.join(".goosehints")) {
+                if !hints.is_empty() {
+                    hints.push_str("\n\n");
+                }
+                hints.push_str(&format!(
+                    "### Project Hints for {}\nThe developer extension includes some hints for working on the workspace root at {}.\n",
+                    root.name,
+                    root.path.display()
+                ));
+                hints.push_str(&root_hints);
+            }
+        }
+
+        let mut tools = vec![
+            bash_tool,
+            set_cwd_tool,
+            text_editor_tool,
+            list_windows_tool,
+            screen_capture_tool,
+            image_processor_tool,
+            environment_info_tool,
+        ];
+
+        let base_instructions = if workspace.is_multi_root() {
+            let roots = workspace
+                .roots()
+                .iter()
+                .map(|root| format!("- {}: {}", root.name, root.path.display()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            tools.push(switch_root_tool(&workspace));
+            formatdoc! {r#"
+                {base_instructions}
+                This is a multi-root workspace. Shell commands run in the active root, which is
+                {active} to begin with. Use the switch_workspace_root tool to work in another root.
+
+                workspace roots:
+                {roots}
+
+                "#,
+                base_instructions=base_instructions.trim_end(),
+                active=workspace.active().name,
+                roots=roots,
+            }
+        } else {
+            base_instructions
+        };
+
         // Return base instructions directly when no hints are found
         let instructions = if hints.is_empty() {
             base_instructions
@@ -381,83 +528,140 @@ impl DeveloperRouter {
             format!("{base_instructions}\n{hints}")
         };
 
-        let mut builder = GitignoreBuilder::new(cwd.clone());
-        let mut has_ignore_file = false;
-        // Initialize ignore patterns
-        // - macOS/Linux: ~/.config/goose/
-        // - Windows:     ~\AppData\Roaming\Block\goose\config\
-        let global_ignore_path = choose_app_strategy(crate::APP_STRATEGY.clone())
-            .map(|strategy| strategy.in_config_dir(".gooseignore"))
-            .unwrap_or_else(|_| {
-                PathBuf::from(shellexpand::tilde("~/.config/goose/.gooseignore").to_string())
-            });
+        Self {
+            tools,
+            prompts: Arc::new(load_prompt_files()),
+            instructions,
+            file_history: Arc::new(Mutex::new(HashMap::new())),
+            edit_journal: Arc::new(Mutex::new(EditJournal::new(audit::journal_root()))),
+            ignore_patterns,
+            workspace: Arc::new(Mutex::new(workspace)),
+            shell_allowlist: Arc::new(shell_allowlist),
+            cwd: Arc::new(Mutex::new(None)),
+        }
+    }
 
-        // Create the directory if it doesn't exist
-        let _ = std::fs::create_dir_all(global_ignore_path.parent().unwrap());
+    // Helper method to check if a path should be ignored, using the patterns of the
+    // workspace root the path belongs to
+    fn is_ignored(&self, path: &Path) -> bool {
+        let workspace = self.workspace.lock().unwrap();
+        let patterns = match workspace.root_for(path) {
+            Some(root) if workspace.is_multi_root() => &root.ignore_patterns,
+            _ => &self.ignore_patterns,
+        };
+        patterns.matched(path, path.is_dir()).is_ignore()
+    }
 
-        // Read global ignores if they exist
-        if global_ignore_path.is_file() {
-            let _ = builder.add(global_ignore_path);
-            has_ignore_file = true;
-        }
+    // The directory shell commands run in when the workspace has several roots
+    fn active_root(&self) -> Option<PathBuf> {
+        let workspace = self.workspace.lock().unwrap();
+        workspace
+            .is_multi_root()
+            .then(|| workspace.active().path.clone())
+    }
 
-        // Check for local ignores in current directory
-        let local_ignore_path = cwd.join(".gooseignore");
+    // The directory shell commands run in: the one set with set_cwd, or else the active root
+    fn shell_dir(&self) -> Option<PathBuf> {
+        let cwd = self.cwd.lock().unwrap().clone();
+        cwd.or_else(|| self.active_root())
+    }
 
-        // Read local ignores if they exist
-        if local_ignore_path.is_file() {
-            let _ = builder.add(local_ignore_path);
-            has_ignore_file = true;
+    // Resolve a directory for shell commands against the current one, rejecting directories
+    // that don't exist, are outside the workspace roots or are restricted by .gooseignore
+    fn resolve_dir(&self, dir: &str) -> Result<PathBuf, ToolError> {
+        let expanded = expand_path(dir);
+        let path = if is_absolute_path(&expanded) {
+            PathBuf::from(&expanded)
         } else {
-            // If no .gooseignore exists, check for .gitignore as fallback
-            let gitignore_path = cwd.join(".gitignore");
-            if gitignore_path.is_file() {
-                tracing::debug!(
-                    "No .gooseignore found, using .gitignore as fallback for ignore patterns"
-                );
-                let _ = builder.add(gitignore_path);
-                has_ignore_file = true;
-            }
+            let base = match self.shell_dir() {
+                Some(base) => base,
+                None => {
+                    std::env::current_dir().map_err(|e| ToolError::ExecutionError(e.to_string()))?
+                }
+            };
+            base.join(&expanded)
+        };
+        if !path.is_dir() {
+            return Err(ToolError::InvalidParameters(format!(
+                "'{}' is not a directory",
+                path.display()
+            )));
         }
-
-        // Only use default patterns if no .gooseignore files were found
-        // AND no .gitignore was used as fallback
-        if !has_ignore_file {
-            // Add some sensible defaults
-            let _ = builder.add_line(None, "**/.env");
-            let _ = builder.add_line(None, "**/.env.*");
-            let _ = builder.add_line(None, "**/secrets.*");
+        // Compare resolved paths, so neither `..` nor a symlink leads out of the workspace
+        let resolved = path.canonicalize().unwrap_or_else(|_| path.clone());
+        let workspace = self.workspace.lock().unwrap();
+        let inside = workspace.roots().iter().any(|root| {
+            resolved.starts_with(
+                root.path
+                    .canonicalize()
+                    .unwrap_or_else(|_| root.path.clone()),
+            )
+        });
+        if !inside {
+            let roots: Vec<String> = workspace
+                .roots()
+                .iter()
+                .map(|root| root.path.display().to_string())
+                .collect();
+            return Err(ToolError::InvalidParameters(format!(
+                "'{}' is outside the workspace, shell commands can only run in {}",
+                path.display(),
+                roots.join(", ")
+            )));
         }
-
-        let ignore_patterns = builder.build().expect("Failed to build ignore patterns");
-
-        Self {
-            tools: vec![
-                bash_tool,
-                text_editor_tool,
-                list_windows_tool,
-                screen_capture_tool,
-                image_processor_tool,
-            ],
-            prompts: Arc::new(load_prompt_files()),
-            instructions,
-            file_history: Arc::new(Mutex::new(HashMap::new())),
-            ignore_patterns: Arc::new(ignore_patterns),
+        drop(workspace);
+        if self.is_ignored(&path) {
+            return Err(ToolError::ExecutionError(format!(
+                "'{}' is restricted by .gooseignore",
+                path.display()
+            )));
         }
+        Ok(path)
     }
 
-    // Helper method to check if a path should be ignored
-    fn is_ignored(&self, path: &Path) -> bool {
-        self.ignore_patterns.matched(path, false).is_ignore()
+    // Environment variables for a single shell command, as strings
+    fn shell_env(&self, params: &Value) -> Result<Vec<(String, String)>, ToolError> {
+        let Some(env) = params.get("env").filter(|env| !env.is_null()) else {
+            return Ok(Vec::new());
+        };
+        let env = env.as_object().ok_or_else(|| {
+            ToolError::InvalidParameters("env must map variable names to values".to_string())
+        })?;
+        env.iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    Value::Number(_) | Value::Bool(_) => value.to_string(),
+                    _ => {
+                        return Err(ToolError::InvalidParameters(format!(
+                            "The value of {} in env must be a string",
+                            name
+                        )))
+                    }
+                };
+                self.shell_allowlist
+                    .check_env(name)
+                    .map_err(ToolError::ExecutionError)?;
+                Ok((name.clone(), value))
+            })
+            .collect()
     }
 
     // Helper method to resolve a path relative to cwd with platform-specific handling
     fn resolve_path(&self, path_str: &str) -> Result<PathBuf, ToolError> {
-        let cwd = std::env::current_dir().expect("should have a current working dir");
         let expanded = expand_path(path_str);
         let path = Path::new(&expanded);
 
-        let suggestion = cwd.join(path);
+        let suggestion = {
+            let workspace = self.workspace.lock().unwrap();
+            if workspace.is_multi_root() {
+                workspace.suggest_absolute(path)
+            } else {
+                std::env::current_dir()
+                    .expect("should have a current working dir")
+                    .join(path)
+            }
+        };
 
         match is_absolute_path(&expanded) {
             true => Ok(path.to_path_buf()),
@@ -483,6 +687,16 @@ impl DeveloperRouter {
                     "The command string is required".to_string(),
                 ))?;
 
+        self.shell_allowlist
+            .check(command)
+            .map_err(ToolError::ExecutionError)?;
+
+        let working_dir = match params.get("working_dir").and_then(|v| v.as_str()) {
+            Some(dir) => Some(self.resolve_dir(dir)?),
+            None => self.shell_dir(),
+        };
+        let env = self.shell_env(&params)?;
+
         // Check if command might access ignored files and return early if it does
         let cmd_parts: Vec<&str> = command.split_whitespace().collect();
         for arg in &cmd_parts[1..] {
@@ -491,12 +705,15 @@ impl DeveloperRouter {
                 continue;
             }
             // Skip invalid paths
-            let path = Path::new(arg);
+            let path = match &working_dir {
+                Some(root) => root.join(arg),
+                None => PathBuf::from(arg),
+            };
             if !path.exists() {
                 continue;
             }
 
-            if self.is_ignored(path) {
+            if self.is_ignored(&path) {
                 return Err(ToolError::ExecutionError(format!(
                     "The command attempts to access '{}' which is restricted by .gooseignore",
                     arg
@@ -504,12 +721,23 @@ impl DeveloperRouter {
             }
         }
 
+        // Files the command changes are journaled alongside text_editor edits
+        let snapshot = working_dir
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .and_then(|dir| self.edit_journal.lock().unwrap().shell_snapshot(&dir));
+
         // Get platform-specific shell configuration
         let shell_config = get_shell_config();
         let cmd_str = format_command_for_platform(command);
 
         // Execute the command using platform-specific shell
-        let mut child = Command::new(&shell_config.executable)
+        let mut command_builder = Command::new(&shell_config.executable);
+        if let Some(dir) = &working_dir {
+            command_builder.current_dir(dir);
+        }
+        let mut child = command_builder
+            .envs(env)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .stdin(Stdio::null())
@@ -598,6 +826,17 @@ impl DeveloperRouter {
             .await
             .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
 
+        if let Some(snapshot) = snapshot {
+            match snapshot.changes() {
+                Ok(changes) => {
+                    for (path, before) in changes {
+                        self.record_edit(&path, "shell", before.as_deref());
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to journal shell edits: {}", e),
+            }
+        }
+
         let output_str = match output_task.await {
             Ok(result) => result.map_err(|e| ToolError::ExecutionError(e.to_string()))?,
             Err(e) => return Err(ToolError::ExecutionError(e.to_string())),
@@ -656,7 +895,8 @@ impl DeveloperRouter {
                         ToolError::InvalidParameters("Missing 'file_text' parameter".into())
                     })?;
 
-                self.text_editor_write(&path, file_text).await
+                let result = self.text_editor_write(&path, file_text).await?;
+                Ok(Self::with_syntax_check(&path, result).await)
             }
             "str_replace" => {
                 let old_str = params
@@ -672,7 +912,8 @@ impl DeveloperRouter {
                         ToolError::InvalidParameters("Missing 'new_str' parameter".into())
                     })?;
 
-                self.text_editor_replace(&path, old_str, new_str).await
+                let result = self.text_editor_replace(&path, old_str, new_str).await?;
+                Ok(Self::with_syntax_check(&path, result).await)
             }
             "undo_edit" => self.text_editor_undo(&path).await,
             _ => Err(ToolError::InvalidParameters(format!(
@@ -683,7 +924,9 @@ impl DeveloperRouter {
     }
 
     async fn text_editor_view(&self, path: &PathBuf) -> Result<Vec<Content>, ToolError> {
-        if path.is_file() {
+        if path.is_file() && is_image_file(path) {
+            load_image(path)
+        } else if path.is_file() {
             // Check file size first (400KB limit)
             const MAX_FILE_SIZE: u64 = 400 * 1024; // 400KB in bytes
             const MAX_CHAR_COUNT: usize = 400_000; // 409600 chars = 400KB
@@ -706,8 +949,13 @@ impl DeveloperRouter {
                 .map_err(|_| ToolError::ExecutionError("Invalid file path".into()))?
                 .to_string();
 
-            let content = std::fs::read_to_string(path)
-                .map_err(|e| ToolError::ExecutionError(format!("Failed to read file: {}", e)))?;
+            let content = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+                std::io::ErrorKind::InvalidData => ToolError::ExecutionError(format!(
+                    "File '{}' is a binary file and can't be viewed as text.",
+                    path.display()
+                )),
+                _ => ToolError::ExecutionError(format!("Failed to read file: {}", e)),
+            })?;
 
             let char_count = content.chars().count();
             if char_count > MAX_CHAR_COUNT {
@@ -747,6 +995,17 @@ impl DeveloperRouter {
         }
     }
 
+    /// Add any syntax errors in the edited file to the edit's result, so a broken file shows
+    /// up right away rather than at the next build
+    async fn with_syntax_check(path: &Path, mut result: Vec<Content>) -> Vec<Content> {
+        if syntax::is_enabled() {
+            if let Some(errors) = syntax::check(path).await {
+                result.push(Content::text(syntax::report(path, &errors)));
+            }
+        }
+        result
+    }
+
     async fn text_editor_write(
         &self,
         path: &PathBuf,
@@ -755,9 +1014,12 @@ impl DeveloperRouter {
         // Normalize line endings based on platform
         let normalized_text = normalize_line_endings(file_text);
 
+        let before = std::fs::read(path).ok();
+
         // Write to the file
         std::fs::write(path, normalized_text)
             .map_err(|e| ToolError::ExecutionError(format!("Failed to write file: {}", e)))?;
+        self.record_edit(path, "write", before.as_deref());
 
         // Try to detect the language from the file extension
         let language = lang::get_language_identifier(path);
@@ -821,6 +1083,7 @@ impl DeveloperRouter {
         let normalized_content = normalize_line_endings(&new_content);
         std::fs::write(path, &normalized_content)
             .map_err(|e| ToolError::ExecutionError(format!("Failed to write file: {}", e)))?;
+        self.record_edit(path, "str_replace", Some(content.as_bytes()));
 
         // Try to detect the language from the file extension
         let language = lang::get_language_identifier(path);
@@ -880,10 +1143,12 @@ impl DeveloperRouter {
         let mut history = self.file_history.lock().unwrap();
         if let Some(contents) = history.get_mut(path) {
             if let Some(previous_content) = contents.pop() {
+                let before = std::fs::read(path).ok();
                 // Write previous content back to file
                 std::fs::write(path, previous_content).map_err(|e| {
                     ToolError::ExecutionError(format!("Failed to write file: {}", e))
                 })?;
+                self.record_edit(path, "undo_edit", before.as_deref());
                 Ok(vec![Content::text("Undid the last edit")])
             } else {
                 Err(ToolError::InvalidParameters(
@@ -897,6 +1162,15 @@ impl DeveloperRouter {
         }
     }
 
+    // Journal an edit so the whole run can be rolled back. A failure to journal is logged
+    // rather than failing an edit that has already been made
+    fn record_edit(&self, path: &Path, operation: &str, before: Option<&[u8]>) {
+        let mut journal = self.edit_journal.lock().unwrap();
+        if let Err(e) = journal.record(path, operation, before) {
+            tracing::warn!("Failed to journal edit to {}: {}", path.display(), e);
+        }
+    }
+
     fn save_file_history(&self, path: &PathBuf) -> Result<(), ToolError> {
         let mut history = self.file_history.lock().unwrap();
         let content = if path.exists() {
@@ -992,56 +1266,57 @@ impl DeveloperRouter {
             )));
         }
 
-        // Check file size (10MB limit for image files)
-        const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024; // 10MB in bytes
-        let file_size = std::fs::metadata(&path)
-            .map_err(|e| ToolError::ExecutionError(format!("Failed to get file metadata: {}", e)))?
-            .len();
-
-        if file_size > MAX_FILE_SIZE {
-            return Err(ToolError::ExecutionError(format!(
-                "File '{}' is too large ({:.2}MB). Maximum size is 10MB.",
-                path.display(),
-                file_size as f64 / (1024.0 * 1024.0)
-            )));
-        }
+        load_image(&path)
+    }
 
-        // Open and decode the image
-        let image = xcap::image::open(&path)
-            .map_err(|e| ToolError::ExecutionError(format!("Failed to open image file: {}", e)))?;
+    async fn switch_workspace_root(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let root = params
+            .get("root")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'root' parameter".into()))?;
 
-        // Resize if necessary (same logic as screen_capture)
-        let mut processed_image = image;
-        let max_width = 768;
-        if processed_image.width() > max_width {
-            let scale = max_width as f32 / processed_image.width() as f32;
-            let new_height = (processed_image.height() as f32 * scale) as u32;
-            processed_image = xcap::image::DynamicImage::ImageRgba8(xcap::image::imageops::resize(
-                &processed_image,
-                max_width,
-                new_height,
-                xcap::image::imageops::FilterType::Lanczos3,
-            ));
-        }
+        let mut workspace = self.workspace.lock().unwrap();
+        // Commands follow the new root rather than a directory set in the old one
+        self.cwd.lock().unwrap().take();
+        let available = workspace
+            .roots()
+            .iter()
+            .map(|r| r.name.clone())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let active = workspace.set_active(root).ok_or_else(|| {
+            ToolError::InvalidParameters(format!(
+                "Unknown workspace root '{}', available roots are: {}",
+                root, available
+            ))
+        })?;
 
-        // Convert to PNG and encode as base64
-        let mut bytes: Vec<u8> = Vec::new();
-        processed_image
-            .write_to(&mut Cursor::new(&mut bytes), xcap::image::ImageFormat::Png)
-            .map_err(|e| {
-                ToolError::ExecutionError(format!("Failed to write image buffer: {}", e))
-            })?;
+        Ok(vec![Content::text(format!(
+            "Switched to workspace root {} at {}",
+            active.name,
+            active.path.display()
+        ))])
+    }
 
-        let data = base64::prelude::BASE64_STANDARD.encode(bytes);
+    async fn set_cwd(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'path' parameter".into()))?;
+        let dir = self.resolve_dir(path)?;
+        let message = format!("Shell commands now run in {}", dir.display());
+        *self.cwd.lock().unwrap() = Some(dir);
+        Ok(vec![Content::text(message)])
+    }
 
-        Ok(vec![
-            Content::text(format!(
-                "Successfully processed image from {}",
-                path.display()
-            ))
-            .with_audience(vec![Role::Assistant]),
-            Content::image(data, "image/png").with_priority(0.0),
-        ])
+    async fn environment_info(&self) -> Result<Vec<Content>, ToolError> {
+        let dir = self
+            .shell_dir()
+            .unwrap_or_else(|| self.workspace.lock().unwrap().active().path.clone());
+        let report = environment::gather(&dir).await;
+        let report = serde_json::to_string_pretty(&report)
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to write the report: {}", e)))?;
+        Ok(vec![Content::text(report)])
     }
 
     async fn screen_capture(&self, params: Value) -> Result<Vec<Content>, ToolError> {
@@ -1117,19 +1392,58 @@ impl DeveloperRouter {
     }
 }
 
+fn switch_root_tool(workspace: &Workspace) -> Tool {
+    let names: Vec<String> = workspace.roots().iter().map(|r| r.name.clone()).collect();
+    Tool::new(
+        "switch_workspace_root",
+        indoc! {r#"
+            Switch the active root of a multi-root workspace.
+            Shell commands run in the active root, and relative paths are suggested against it.
+            Each root has its own .goosehints and .gooseignore.
+        "#},
+        json!({
+            "type": "object",
+            "required": ["root"],
+            "properties": {
+                "root": {
+                    "type": "string",
+                    "enum": names,
+                    "description": "Name of the workspace root to switch to"
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Switch workspace root".to_string()),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
+
 impl Router for DeveloperRouter {
     fn name(&self) -> String {
         "developer".to_string()
     }
 
     fn instructions(&self) -> String {
-        self.instructions.clone()
+        // set_cwd and switch_workspace_root move where commands run after startup
+        match self.shell_dir() {
+            Some(dir) => format!(
+                "{}\n\nShell commands currently run in {}",
+                self.instructions.trim_end(),
+                dir.display()
+            ),
+            None => self.instructions.clone(),
+        }
     }
 
     fn capabilities(&self) -> ServerCapabilities {
         CapabilitiesBuilder::new()
             .with_tools(false)
             .with_prompts(false)
+            .with_dynamic_instructions(None, true)
             .build()
     }
 
@@ -1148,10 +1462,13 @@ impl Router for DeveloperRouter {
         Box::pin(async move {
             match tool_name.as_str() {
                 "shell" => this.bash(arguments, notifier).await,
+                "set_cwd" => this.set_cwd(arguments).await,
                 "text_editor" => this.text_editor(arguments).await,
                 "list_windows" => this.list_windows(arguments).await,
                 "screen_capture" => this.screen_capture(arguments).await,
                 "image_processor" => this.image_processor(arguments).await,
+                "switch_workspace_root" => this.switch_workspace_root(arguments).await,
+                "environment_info" => this.environment_info().await,
                 _ => Err(ToolError::NotFound(format!("Tool {} not found", tool_name))),
             }
         })
@@ -1208,20 +1525,98 @@ impl Clone for DeveloperRouter {
             prompts: Arc::clone(&self.prompts),
             instructions: self.instructions.clone(),
             file_history: Arc::clone(&self.file_history),
+            edit_journal: Arc::clone(&self.edit_journal),
             ignore_patterns: Arc::clone(&self.ignore_patterns),
+            workspace: Arc::clone(&self.workspace),
+            shell_allowlist: Arc::clone(&self.shell_allowlist),
+            cwd: Arc::clone(&self.cwd),
         }
     }
 }
 
+/// Extensions of files returned as images rather than text
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+
+fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Read an image file as PNG image content, scaled down to the width screenshots use
+fn load_image(path: &Path) -> Result<Vec<Content>, ToolError> {
+    // Check file size (10MB limit for image files)
+    const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024; // 10MB in bytes
+    let file_size = std::fs::metadata(path)
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to get file metadata: {}", e)))?
+        .len();
+
+    if file_size > MAX_FILE_SIZE {
+        return Err(ToolError::ExecutionError(format!(
+            "File '{}' is too large ({:.2}MB). Maximum size is 10MB.",
+            path.display(),
+            file_size as f64 / (1024.0 * 1024.0)
+        )));
+    }
+
+    // Open and decode the image
+    let image = xcap::image::open(path)
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to open image file: {}", e)))?;
+
+    // Resize if necessary (same logic as screen_capture)
+    let mut processed_image = image;
+    let max_width = 768;
+    if processed_image.width() > max_width {
+        let scale = max_width as f32 / processed_image.width() as f32;
+        let new_height = (processed_image.height() as f32 * scale) as u32;
+        processed_image = xcap::image::DynamicImage::ImageRgba8(xcap::image::imageops::resize(
+            &processed_image,
+            max_width,
+            new_height,
+            xcap::image::imageops::FilterType::Lanczos3,
+        ));
+    }
+
+    // Convert to PNG and encode as base64
+    let mut bytes: Vec<u8> = Vec::new();
+    processed_image
+        .write_to(&mut Cursor::new(&mut bytes), xcap::image::ImageFormat::Png)
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to write image buffer: {}", e)))?;
+
+    let data = base64::prelude::BASE64_STANDARD.encode(bytes);
+
+    Ok(vec![
+        Content::text(format!(
+            "Successfully processed image from {}",
+            path.display()
+        ))
+        .with_audience(vec![Role::Assistant]),
+        Content::image(data, "image/png").with_priority(0.0),
+    ])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ignore::gitignore::GitignoreBuilder;
     use serde_json::json;
     use serial_test::serial;
     use std::fs;
     use tempfile::TempDir;
     use tokio::sync::OnceCell;
 
+    static JOURNAL_DIR: std::sync::OnceLock<TempDir> = std::sync::OnceLock::new();
+
+    // Keeps the edits tests make out of the user's edit journal
+    fn new_router() -> DeveloperRouter {
+        JOURNAL_DIR.get_or_init(|| {
+            let dir = TempDir::new().unwrap();
+            std::env::set_var(audit::EDIT_JOURNAL_DIR_ENV, dir.path());
+            dir
+        });
+        DeveloperRouter::new()
+    }
+
     #[test]
     #[serial]
     fn test_global_goosehints() {
@@ -1243,7 +1638,7 @@ mod tests {
         let dir = TempDir::new().unwrap();
         std::env::set_current_dir(dir.path()).unwrap();
 
-        let router = DeveloperRouter::new();
+        let router = new_router();
         let instructions = router.instructions();
 
         assert!(instructions.contains("### Global Hints"));
@@ -1263,7 +1658,7 @@ mod tests {
         std::env::set_current_dir(dir.path()).unwrap();
 
         fs::write(".goosehints", "Test hint content").unwrap();
-        let router = DeveloperRouter::new();
+        let router = new_router();
         let instructions = router.instructions();
 
         assert!(instructions.contains("Test hint content"));
@@ -1275,7 +1670,7 @@ mod tests {
         let dir = TempDir::new().unwrap();
         std::env::set_current_dir(dir.path()).unwrap();
 
-        let router = DeveloperRouter::new();
+        let router = new_router();
         let instructions = router.instructions();
 
         assert!(!instructions.contains("Project Hints"));
@@ -1284,9 +1679,7 @@ mod tests {
     static DEV_ROUTER: OnceCell<DeveloperRouter> = OnceCell::const_new();
 
     async fn get_router() -> &'static DeveloperRouter {
-        DEV_ROUTER
-            .get_or_init(|| async { DeveloperRouter::new() })
-            .await
+        DEV_ROUTER.get_or_init(|| async { new_router() }).await
     }
 
     fn dummy_sender() -> mpsc::Sender<JsonRpcMessage> {
@@ -1452,6 +1845,52 @@ mod tests {
         temp_dir.close().unwrap();
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_text_editor_view_image() {
+        let router = get_router().await;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::env::set_current_dir(&temp_dir).unwrap();
+        let image_path = temp_dir.path().join("screenshot.PNG");
+        xcap::image::RgbaImage::from_pixel(1024, 512, xcap::image::Rgba([255, 0, 0, 255]))
+            .save_with_format(&image_path, xcap::image::ImageFormat::Png)
+            .unwrap();
+
+        let result = router
+            .call_tool(
+                "text_editor",
+                json!({
+                    "command": "view",
+                    "path": image_path.to_str().unwrap()
+                }),
+                dummy_sender(),
+            )
+            .await
+            .unwrap();
+        assert!(result.iter().any(
+            |content| matches!(content, Content::Image(image) if image.mime_type == "image/png")
+        ));
+
+        // Other binary files get a clear error instead of a UTF-8 one
+        let binary_path = temp_dir.path().join("data.bin");
+        std::fs::write(&binary_path, [0xff, 0xfe, 0x00, 0x80]).unwrap();
+        let err = router
+            .call_tool(
+                "text_editor",
+                json!({
+                    "command": "view",
+                    "path": binary_path.to_str().unwrap()
+                }),
+                dummy_sender(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("binary file"));
+
+        temp_dir.close().unwrap();
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_text_editor_str_replace() {
@@ -1630,7 +2069,16 @@ mod tests {
             prompts: Arc::new(HashMap::new()),
             instructions: String::new(),
             file_history: Arc::new(Mutex::new(HashMap::new())),
+            edit_journal: Arc::new(Mutex::new(EditJournal::new(
+                temp_dir.path().join("journal"),
+            ))),
             ignore_patterns: Arc::new(ignore_patterns),
+            workspace: Arc::new(Mutex::new(Workspace::single(
+                temp_dir.path().to_path_buf(),
+                Arc::new(Gitignore::empty()),
+            ))),
+            shell_allowlist: Arc::new(ShellAllowlist::default()),
+            cwd: Arc::new(Mutex::new(None)),
         };
 
         // Test basic file matching
@@ -1676,11 +2124,20 @@ mod tests {
         let ignore_patterns = builder.build().unwrap();
 
         let router = DeveloperRouter {
-            tools: DeveloperRouter::new().tools, // Reuse default tools
+            tools: new_router().tools, // Reuse default tools
             prompts: Arc::new(HashMap::new()),
             instructions: String::new(),
             file_history: Arc::new(Mutex::new(HashMap::new())),
+            edit_journal: Arc::new(Mutex::new(EditJournal::new(
+                temp_dir.path().join("journal"),
+            ))),
             ignore_patterns: Arc::new(ignore_patterns),
+            workspace: Arc::new(Mutex::new(Workspace::single(
+                temp_dir.path().to_path_buf(),
+                Arc::new(Gitignore::empty()),
+            ))),
+            shell_allowlist: Arc::new(ShellAllowlist::default()),
+            cwd: Arc::new(Mutex::new(None)),
         };
 
         // Try to write to an ignored file
@@ -1735,11 +2192,20 @@ mod tests {
         let ignore_patterns = builder.build().unwrap();
 
         let router = DeveloperRouter {
-            tools: DeveloperRouter::new().tools, // Reuse default tools
+            tools: new_router().tools, // Reuse default tools
             prompts: Arc::new(HashMap::new()),
             instructions: String::new(),
             file_history: Arc::new(Mutex::new(HashMap::new())),
+            edit_journal: Arc::new(Mutex::new(EditJournal::new(
+                temp_dir.path().join("journal"),
+            ))),
             ignore_patterns: Arc::new(ignore_patterns),
+            workspace: Arc::new(Mutex::new(Workspace::single(
+                temp_dir.path().to_path_buf(),
+                Arc::new(Gitignore::empty()),
+            ))),
+            shell_allowlist: Arc::new(ShellAllowlist::default()),
+            cwd: Arc::new(Mutex::new(None)),
         };
 
         // Create an ignored file
@@ -1788,7 +2254,7 @@ mod tests {
         // Create a .gitignore file but no .gooseignore
         std::fs::write(temp_dir.path().join(".gitignore"), "*.log\n*.tmp\n.env").unwrap();
 
-        let router = DeveloperRouter::new();
+        let router = new_router();
 
         // Test that gitignore patterns are respected
         assert!(
@@ -1821,7 +2287,7 @@ mod tests {
         std::fs::write(temp_dir.path().join(".gooseignore"), "*.secret").unwrap();
         std::fs::write(temp_dir.path().join(".gitignore"), "*.log\ntarget/").unwrap();
 
-        let router = DeveloperRouter::new();
+        let router = new_router();
 
         // .gooseignore patterns should be used
         assert!(
@@ -1849,7 +2315,7 @@ mod tests {
         std::env::set_current_dir(&temp_dir).unwrap();
 
         // Don't create any ignore files
-        let router = DeveloperRouter::new();
+        let router = new_router();
 
         // Default patterns should be used
         assert!(
@@ -1881,7 +2347,7 @@ mod tests {
         // Create a .gitignore file but no .gooseignore
         std::fs::write(temp_dir.path().join(".gitignore"), "*.log").unwrap();
 
-        let router = DeveloperRouter::new();
+        let router = new_router();
 
         // Try to write to a file ignored by .gitignore
         let result = router
@@ -1932,7 +2398,7 @@ mod tests {
         // Create a .gitignore file but no .gooseignore
         std::fs::write(temp_dir.path().join(".gitignore"), "*.log").unwrap();
 
-        let router = DeveloperRouter::new();
+        let router = new_router();
 
         // Create a file that would be ignored by .gitignore
         let log_file_path = temp_dir.path().join("test.log");
@@ -1973,4 +2439,131 @@ mod tests {
 
         temp_dir.close().unwrap();
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_multi_root_workspace_from_code_workspace_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        std::env::set_current_dir(&root).unwrap();
+
+        fs::create_dir_all(root.join("api")).unwrap();
+        fs::create_dir_all(root.join("web")).unwrap();
+        fs::write(root.join("web/.goosehints"), "Web hint content").unwrap();
+        fs::write(root.join("web/.gooseignore"), "dist.js").unwrap();
+        fs::write(
+            root.join("mono.code-workspace"),
+            r#"{"folders": [{"path": "."}, {"path": "api"}, {"path": "web", "name": "frontend"}]}"#,
+        )
+        .unwrap();
+
+        let router = new_router();
+        let instructions = router.instructions();
+        assert!(instructions.contains("- frontend:"));
+        assert!(instructions.contains("Web hint content"));
+        assert!(router
+            .list_tools()
+            .iter()
+            .any(|tool| tool.name == "switch_workspace_root"));
+
+        // Ignore patterns come from the root a path belongs to
+        assert!(router.is_ignored(&root.join("web/dist.js")));
+        assert!(!router.is_ignored(&root.join("api/dist.js")));
+
+        let err = router.resolve_path("api/main.rs").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&root.join("api/main.rs").display().to_string()));
+
+        let result = router
+            .call_tool(
+                "switch_workspace_root",
+                json!({ "root": "frontend" }),
+                dummy_sender(),
+            )
+            .await
+            .unwrap();
+        assert!(result[0].as_text().unwrap().contains("frontend"));
+        assert_eq!(router.active_root(), Some(root.join("web")));
+
+        let result = router
+            .call_tool(
+                "switch_workspace_root",
+                json!({ "root": "missing" }),
+                dummy_sender(),
+            )
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+
+        temp_dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[cfg(unix)]
+    async fn test_shell_working_dir_and_env() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::env::set_current_dir(&temp_dir).unwrap();
+        fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        fs::create_dir(temp_dir.path().join("private")).unwrap();
+
+        let mut builder = GitignoreBuilder::new(temp_dir.path().to_path_buf());
+        builder.add_line(None, "private/").unwrap();
+        let router = DeveloperRouter {
+            ignore_patterns: Arc::new(builder.build().unwrap()),
+            ..new_router()
+        };
+
+        let shell = |params: Value| router.call_tool("shell", params, dummy_sender());
+        let text = |result: Vec<Content>| result[0].as_text().unwrap().trim().to_string();
+
+        let result = shell(json!({
+            "command": "pwd && echo $GREETING",
+            "working_dir": "sub",
+            "env": {"GREETING": "hello"}
+        }))
+        .await
+        .unwrap();
+        let output = text(result);
+        assert!(output.contains("sub\nhello"), "{}", output);
+
+        let result = shell(json!({"command": "pwd", "working_dir": "missing"})).await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+        let result = shell(json!({"command": "pwd", "working_dir": "private"})).await;
+        assert!(matches!(result, Err(ToolError::ExecutionError(_))));
+        let result = shell(json!({"command": "pwd", "env": {"NOT-A-NAME": "x"}})).await;
+        assert!(matches!(result, Err(ToolError::ExecutionError(_))));
+
+        // set_cwd changes where later commands run, unless they pass their own directory
+        router
+            .call_tool("set_cwd", json!({"path": "sub"}), dummy_sender())
+            .await
+            .unwrap();
+        let output = text(shell(json!({"command": "pwd"})).await.unwrap());
+        assert!(output.ends_with("sub"), "{}", output);
+        assert!(router.capabilities().instructions.is_some());
+        assert!(router.instructions().contains(&format!(
+            "currently run in {}",
+            temp_dir.path().join("sub").display()
+        )));
+        let output = text(
+            shell(json!({"command": "pwd", "working_dir": ".."}))
+                .await
+                .unwrap(),
+        );
+        assert!(!output.ends_with("sub"), "{}", output);
+
+        // Directories outside the workspace are rejected, however they are reached
+        let result = shell(json!({"command": "pwd", "working_dir": "../.."})).await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+        let result = shell(json!({"command": "pwd", "working_dir": "/"})).await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+        std::os::unix::fs::symlink("/", temp_dir.path().join("escape")).unwrap();
+        let result = router
+            .call_tool("set_cwd", json!({"path": "../escape"}), dummy_sender())
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+
+        temp_dir.close().unwrap();
+    }
 }
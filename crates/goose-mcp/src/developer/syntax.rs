@@ -0,0 +1,170 @@
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::process::Command;
+
+/// Set to false to skip checking the syntax of files after editing them
+pub const SYNTAX_CHECK_ENV: &str = "GOOSE_SYNTAX_CHECK";
+
+/// Checks that take longer are skipped, they run after every edit
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_ERROR_LINES: usize = 20;
+
+pub fn is_enabled() -> bool {
+    std::env::var(SYNTAX_CHECK_ENV)
+        .map(|value| !matches!(value.to_lowercase().as_str(), "false" | "0" | "off"))
+        .unwrap_or(true)
+}
+
+/// A command that parses a file without running it, given the path as its last argument
+struct Checker {
+    program: &'static str,
+    args: &'static [&'static str],
+}
+
+const PYTHON_PARSE: &str =
+    "import ast, sys; ast.parse(open(sys.argv[1], encoding='utf-8').read(), sys.argv[1])";
+
+fn checker_for(path: &Path) -> Option<Checker> {
+    let python = if cfg!(windows) { "python" } else { "python3" };
+    let (program, args): (&'static str, &'static [&'static str]) =
+        match path.extension()?.to_str()? {
+            // rustfmt also fails on formatting differences, only its errors count, see `errors`
+            "rs" => (
+                "rustfmt",
+                &["--check", "--edition", "2021", "--color", "never"],
+            ),
+            "py" => (python, &["-c", PYTHON_PARSE]),
+            "js" | "mjs" | "cjs" => ("node", &["--check"]),
+            "sh" | "bash" => ("bash", &["-n"]),
+            "rb" => ("ruby", &["-c"]),
+            "php" => ("php", &["-l"]),
+            "go" => ("gofmt", &["-e", "-l"]),
+            _ => return None,
+        };
+    Some(Checker { program, args })
+}
+
+/// The syntax errors a checker reported, None when the file parsed
+fn errors(checker: &Checker, success: bool, stdout: &str, stderr: &str) -> Option<String> {
+    let output = if checker.program == "rustfmt" {
+        // Unresolved `mod` declarations are reported as "Error writing files", not syntax errors
+        if !stderr.lines().any(|line| line.starts_with("error")) {
+            return None;
+        }
+        stderr
+    } else if success {
+        return None;
+    } else if stderr.trim().is_empty() {
+        stdout
+    } else {
+        stderr
+    };
+    let lines: Vec<&str> = output.trim_end().lines().collect();
+    // Tracebacks end with the error itself
+    let skip = lines.len().saturating_sub(MAX_ERROR_LINES);
+    Some(lines[skip..].join("\n"))
+}
+
+/// Check the syntax of the file at `path` when there is a checker for its language. Returns
+/// the errors found, or None when the file parsed or couldn't be checked.
+pub async fn check(path: &Path) -> Option<String> {
+    if path.extension().is_some_and(|ext| ext == "json") {
+        let content = std::fs::read_to_string(path).ok()?;
+        return serde_json::from_str::<serde_json::Value>(&content)
+            .err()
+            .map(|e| e.to_string());
+    }
+
+    let checker = checker_for(path)?;
+    let child = Command::new(checker.program)
+        .args(checker.args)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        // The checker isn't installed
+        .ok()?;
+    let output = match tokio::time::timeout(CHECK_TIMEOUT, child.wait_with_output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            tracing::debug!("Syntax check with {} failed: {}", checker.program, e);
+            return None;
+        }
+        Err(_) => {
+            tracing::debug!("Syntax check with {} timed out", checker.program);
+            return None;
+        }
+    };
+    errors(
+        &checker,
+        output.status.success(),
+        &String::from_utf8_lossy(&output.stdout),
+        &String::from_utf8_lossy(&output.stderr),
+    )
+}
+
+/// The note added to an edit's result when the file no longer parses
+pub fn report(path: &Path, errors: &str) -> String {
+    format!(
+        "Syntax check failed for {} after this edit:\n```\n{}\n```\nFix the syntax before \
+        continuing, or undo the edit.",
+        path.display(),
+        errors
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let json = dir.path().join("config.json");
+        std::fs::write(&json, r#"{"a": 1,}"#).unwrap();
+        assert!(check(&json).await.unwrap().contains("line 1"));
+        std::fs::write(&json, r#"{"a": 1}"#).unwrap();
+        assert_eq!(check(&json).await, None);
+
+        let text = dir.path().join("notes.txt");
+        std::fs::write(&text, "{ not checked").unwrap();
+        assert_eq!(check(&text).await, None);
+
+        if cfg!(unix) {
+            let script = dir.path().join("build.sh");
+            std::fs::write(&script, "if true; then\n  echo hi\n").unwrap();
+            assert!(check(&script).await.is_some());
+            std::fs::write(&script, "if true; then\n  echo hi\nfi\n").unwrap();
+            assert_eq!(check(&script).await, None);
+        }
+
+        let rustfmt = Checker {
+            program: "rustfmt",
+            args: &[],
+        };
+        assert_eq!(
+            errors(
+                &rustfmt,
+                false,
+                "Diff in a.rs",
+                "Error writing files: failed to resolve mod `missing`"
+            ),
+            None
+        );
+        assert_eq!(
+            errors(
+                &rustfmt,
+                false,
+                "",
+                "error: expected expression, found `;`\n --> a.rs:2:21\n"
+            )
+            .as_deref(),
+            Some("error: expected expression, found `;`\n --> a.rs:2:21")
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use etcetera::{choose_app_strategy, AppStrategy};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::Deserialize;
+
+/// Additional workspace roots, separated like `PATH` entries
+pub const WORKSPACE_ROOTS_ENV: &str = "GOOSE_WORKSPACE_ROOTS";
+
+/// A directory the developer extension works in
+#[derive(Clone)]
+pub struct WorkspaceRoot {
+    pub name: String,
+    pub path: PathBuf,
+    pub ignore_patterns: Arc<Gitignore>,
+}
+
+/// The roots of a (possibly multi-root) workspace and which one shell commands run in.
+///
+/// The first root is always the directory goose was started in. More roots come from
+/// `GOOSE_WORKSPACE_ROOTS` and from the folders of a VS Code `*.code-workspace` file in
+/// that directory.
+#[derive(Clone)]
+pub struct Workspace {
+    roots: Vec<WorkspaceRoot>,
+    active: usize,
+}
+
+#[derive(Deserialize)]
+struct CodeWorkspace {
+    #[serde(default)]
+    folders: Vec<CodeWorkspaceFolder>,
+}
+
+#[derive(Deserialize)]
+struct CodeWorkspaceFolder {
+    path: String,
+    name: Option<String>,
+}
+
+/// Build the ignore patterns for a root: the global .gooseignore plus the root's own
+/// .gooseignore, falling back to its .gitignore and then to some sensible defaults
+pub fn build_ignore_patterns(root: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    let mut has_ignore_file = false;
+    // Initialize ignore patterns
+    // - macOS/Linux: ~/.config/goose/
+    // - Windows:     ~\AppData\Roaming\Block\goose\config\
+    let global_ignore_path = choose_app_strategy(crate::APP_STRATEGY.clone())
+        .map(|strategy| strategy.in_config_dir(".gooseignore"))
+        .unwrap_or_else(|_| {
+            PathBuf::from(shellexpand::tilde("~/.config/goose/.gooseignore").to_string())
+        });
+
+    // Create the directory if it doesn't exist
+    let _ = std::fs::create_dir_all(global_ignore_path.parent().unwrap());
+
+    // Read global ignores if they exist
+    if global_ignore_path.is_file() {
+        let _ = builder.add(global_ignore_path);
+        has_ignore_file = true;
+    }
+
+    // Check for local ignores in the root
+    let local_ignore_path = root.join(".gooseignore");
+
+    // Read local ignores if they exist
+    if local_ignore_path.is_file() {
+        let _ = builder.add(local_ignore_path);
+        has_ignore_file = true;
+    } else {
+        // If no .gooseignore exists, check for .gitignore as fallback
+        let gitignore_path = root.join(".gitignore");
+        if gitignore_path.is_file() {
+            tracing::debug!(
+                "No .gooseignore found, using .gitignore as fallback for ignore patterns"
+            );
+            let _ = builder.add(gitignore_path);
+            has_ignore_file = true;
+        }
+    }
+
+    // Only use default patterns if no .gooseignore files were found
+    // AND no .gitignore was used as fallback
+    if !has_ignore_file {
+        // Add some sensible defaults
+        let _ = builder.add_line(None, "**/.env");
+        let _ = builder.add_line(None, "**/.env.*");
+        let _ = builder.add_line(None, "**/secrets.*");
+    }
+
+    builder.build().expect("Failed to build ignore patterns")
+}
+
+/// Read the folders of the first `*.code-workspace` file in `dir`, resolved against `dir`
+fn read_code_workspace(dir: &Path) -> Vec<(Option<String>, PathBuf)> {
+    let Some(file) = std::fs::read_dir(dir).ok().and_then(|entries| {
+        entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "code-workspace"))
+            .min()
+    }) else {
+        return Vec::new();
+    };
+
+    let parsed = std::fs::read_to_string(&file)
+        .map_err(|e| e.to_string())
+        .and_then(|content| {
+            serde_json::from_str::<CodeWorkspace>(&content).map_err(|e| e.to_string())
+        });
+    match parsed {
+        Ok(workspace) => workspace
+            .folders
+            .into_iter()
+            .map(|folder| {
+                (
+                    folder.name,
+                    dir.join(shellexpand::tilde(&folder.path).as_ref()),
+                )
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Failed to read workspace file {}: {}", file.display(), e);
+            Vec::new()
+        }
+    }
+}
+
+impl Workspace {
+    /// A workspace with only the given root
+    pub fn single(root: PathBuf, ignore_patterns: Arc<Gitignore>) -> Self {
+        let name = root_name(&root);
+        Self {
+            roots: vec![WorkspaceRoot {
+                name,
+                path: root,
+                ignore_patterns,
+            }],
+            active: 0,
+        }
+    }
+
+    /// Collect the workspace roots for `cwd`, which becomes the primary root
+    pub fn discover(cwd: PathBuf, ignore_patterns: Arc<Gitignore>) -> Self {
+        let mut workspace = Self::single(cwd.clone(), ignore_patterns);
+
+        let configured = std::env::var_os(WORKSPACE_ROOTS_ENV)
+            .map(|roots| {
+                std::env::split_paths(&roots)
+                    .filter(|path| !path.as_os_str().is_empty())
+                    .map(|path| (None, cwd.join(path)))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        for (name, path) in configured.into_iter().chain(read_code_workspace(&cwd)) {
+            workspace.add_root(name, path);
+        }
+        workspace
+    }
+
+    fn add_root(&mut self, name: Option<String>, path: PathBuf) {
+        let Ok(path) = path.canonicalize() else {
+            tracing::warn!(
+                "Skipping workspace root {}, it does not exist",
+                path.display()
+            );
+            return;
+        };
+        if !path.is_dir() || self.roots.iter().any(|root| same_dir(&root.path, &path)) {
+            return;
+        }
+
+        let base = name.unwrap_or_else(|| root_name(&path));
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while self.roots.iter().any(|root| root.name == name) {
+            name = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+
+        let ignore_patterns = Arc::new(build_ignore_patterns(&path));
+        self.roots.push(WorkspaceRoot {
+            name,
+            path,
+            ignore_patterns,
+        });
+    }
+
+    pub fn roots(&self) -> &[WorkspaceRoot] {
+        &self.roots
+    }
+
+    pub fn is_multi_root(&self) -> bool {
+        self.roots.len() > 1
+    }
+
+    /// The root shell commands run in
+    pub fn active(&self) -> &WorkspaceRoot {
+        &self.roots[self.active]
+    }
+
+    /// Make the root with the given name or path the active one
+    pub fn set_active(&mut self, root: &str) -> Option<&WorkspaceRoot> {
+        let path = Path::new(root);
+        let index = self.roots.iter().position(|r| r.name == root).or_else(|| {
+            self.roots
+                .iter()
+                .position(|r| path.is_absolute() && same_dir(&r.path, path))
+        })?;
+        self.active = index;
+        Some(&self.roots[index])
+    }
+
+    /// The innermost root containing `path`
+    pub fn root_for(&self, path: &Path) -> Option<&WorkspaceRoot> {
+        self.roots
+            .iter()
+            .filter(|root| path.starts_with(&root.path))
+            .max_by_key(|root| root.path.components().count())
+    }
+
+    /// Guess the absolute path meant by a relative one: paths starting with a root's name
+    /// resolve into that root, anything else resolves against the active root
+    pub fn suggest_absolute(&self, relative: &Path) -> PathBuf {
+        let mut components = relative.components();
+        if let Some(Component::Normal(first)) = components.next() {
+            if let Some(root) = self.roots.iter().find(|root| first == root.name.as_str()) {
+                return root.path.join(components.as_path());
+            }
+        }
+        self.active().path.join(relative)
+    }
+}
+
+fn root_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    a == b || matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
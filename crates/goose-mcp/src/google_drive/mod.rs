@@ -84,6 +84,93 @@ fn extract_google_drive_id(url: &str) -> Option<&str> {
         .and_then(|caps| caps.get(1).map(|m| m.as_str()))
 }
 
+/// The corpus to search, a shared drive when only a driveId is given and "user" otherwise
+fn search_corpus<'a>(
+    corpora: Option<&'a str>,
+    drive_id: Option<&str>,
+) -> Result<&'a str, ToolError> {
+    let corpus = match (corpora, drive_id) {
+        (Some(c), _) => c,
+        (None, Some(_)) => "drive",
+        (None, None) => "user",
+    };
+    if !["user", "drive", "allDrives"].contains(&corpus) {
+        return Err(ToolError::InvalidParameters(format!(
+            "corpora must be either 'user', 'drive', or 'allDrives', got {}",
+            corpus
+        )));
+    }
+    if corpus == "drive" && drive_id.is_none() {
+        return Err(ToolError::InvalidParameters(
+            "The driveId param is required when searching the 'drive' corpus. Use list_drives to find it."
+                .to_string(),
+        ));
+    }
+    Ok(corpus)
+}
+
+/// Whether the authenticated user can edit a file, from its `capabilities`
+fn can_edit(file: &File) -> bool {
+    file.capabilities
+        .as_ref()
+        .and_then(|c| c.can_edit)
+        .unwrap_or(false)
+}
+
+/// The owners of a file, or the shared drive it is in, since shared drive files have no owners
+fn owned_by(file: &File) -> String {
+    let owners = file
+        .owners
+        .iter()
+        .flatten()
+        .filter_map(|o| o.email_address.clone().or_else(|| o.display_name.clone()))
+        .collect::<Vec<_>>();
+    match (&file.drive_id, owners.is_empty()) {
+        (Some(drive_id), true) => format!("shared drive {}", drive_id),
+        (_, true) => "unknown".to_string(),
+        (_, false) => owners.join(", "),
+    }
+}
+
+/// The access the authenticated user has to a file and who it belongs to, for search results
+fn describe_access(file: &File) -> String {
+    let access = if can_edit(file) {
+        "edit"
+    } else if file
+        .capabilities
+        .as_ref()
+        .and_then(|c| c.can_comment)
+        .unwrap_or(false)
+    {
+        "comment"
+    } else {
+        "view"
+    };
+    format!(
+        "(access: {}) (owner: {}) (shared: {})",
+        access,
+        owned_by(file),
+        file.shared.unwrap_or(false)
+    )
+}
+
+/// Whether the authenticated user can edit a file, and if not who to ask for access
+fn edit_access_summary(file: &File) -> String {
+    let name = file.name.as_deref().unwrap_or_default();
+    let id = file.id.as_deref().unwrap_or_default();
+    if can_edit(file) {
+        format!("You can edit {} (uri: {}).", name, id)
+    } else {
+        format!(
+            "You cannot edit {} (uri: {}), {}. Ask {} for writer access, or create a copy to edit instead.",
+            name,
+            id,
+            describe_access(file),
+            owned_by(file)
+        )
+    }
+}
+
 pub struct GoogleDriveRouter {
     tools: Vec<Tool>,
     instructions: String,
@@ -744,6 +831,31 @@ impl GoogleDriveRouter {
             }),
         );
 
+        let check_edit_access_tool = Tool::new(
+            "check_edit_access".to_string(),
+            indoc! {r#"
+                Check whether you can edit a file, folder, or shared drive item before planning changes to it.
+            "#}
+            .to_string(),
+            json!({
+              "type": "object",
+              "properties": {
+                "fileId": {
+                    "type": "string",
+                    "description": "Id of the file or folder.",
+                }
+              },
+              "required": ["fileId"],
+            }),
+            Some(ToolAnnotations {
+                title: Some("Check edit access".to_string()),
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
         let sharing_tool = Tool::new(
             "sharing".to_string(),
             indoc! {r#"
@@ -818,15 +930,18 @@ impl GoogleDriveRouter {
             10. update_file - Update an existing file's contents or labels
             11. sheets_tool - Work with Google Sheets data using various operations
             12. docs_tool - Work with Google Docs data using various operations
+            13. check_edit_access - Check whether you can edit a file before changing it
 
             ## Available Tools
 
             ### 1. Search Tool
             Search for or list files or labels in Google Drive. Files are
             searched by name and ordered by most recently viewedByMeTime.
-            A corpora parameter controls which corpus is searched.
-            Returns: List of files with their names, MIME types, and IDs or a
-            list of labels and their fields.
+            A corpora parameter controls which corpus is searched. To search a
+            shared drive, pass its driveId (from list_drives).
+            Returns: List of files with their names, MIME types, IDs, your
+            access (edit, comment, or view), owners, and whether they are
+            shared, or a list of labels and their fields.
 
             ### 2. Read File Tool
             Read a file's contents using its ID, and optionally include images as base64 encoded data.
@@ -951,6 +1066,12 @@ impl GoogleDriveRouter {
             - startPosition: The start position for delete_content operation
             - endPosition: The end position for delete_content operation
 
+            ### 13. Check Edit Access Tool
+            Reports whether you can edit a file, and if not, who owns it so the
+            user can ask for access. Use this before planning changes to files
+            that belong to someone else or are in a shared drive, rather than
+            finding out when the update fails.
+
             ## Common Usage Pattern
 
             1. First, search for the file you want to read, searching by name.
@@ -987,6 +1108,7 @@ impl GoogleDriveRouter {
                 manage_comment_tool,
                 list_drives_tool,
                 get_permissions_tool,
+                check_edit_access_tool,
                 sharing_tool,
             ],
             instructions,
@@ -1021,21 +1143,7 @@ impl GoogleDriveRouter {
         let drive_id = params.get("driveId").and_then(|q| q.as_str());
         let parent = params.get("parent").and_then(|q| q.as_str());
 
-        // extract corpora query parameter, validate options, or default to "user"
-        let corpus = params
-            .get("corpora")
-            .and_then(|c| c.as_str())
-            .map(|s| {
-                if ["user", "drive", "allDrives"].contains(&s) {
-                    Ok(s)
-                } else {
-                    Err(ToolError::InvalidParameters(format!(
-                        "corpora must be either 'user', 'drive', or 'allDrives', got {}",
-                        s
-                    )))
-                }
-            })
-            .unwrap_or(Ok("user"))?;
+        let corpus = search_corpus(params.get("corpora").and_then(|c| c.as_str()), drive_id)?;
 
         // extract pageSize, and convert it to an i32, default to 10
         let page_size: i32 = params
@@ -1095,7 +1203,7 @@ impl GoogleDriveRouter {
             .param(
                 "fields",
                 &format!(
-                    "files(id, name, mimeType, modifiedTime, size{})",
+                    "files(id, name, mimeType, modifiedTime, size, driveId, shared, owners(displayName, emailAddress), capabilities(canEdit, canComment){})",
                     if include_labels { ", labelInfo" } else { "" }
                 ),
             )
@@ -1148,10 +1256,11 @@ impl GoogleDriveRouter {
                         .map(|files| {
                             files.into_iter().map(|f| {
                                 format!(
-                                    "{} ({}) (uri: {}){}",
-                                    f.name.unwrap_or_default(),
-                                    f.mime_type.unwrap_or_default(),
-                                    f.id.unwrap_or_default(),
+                                    "{} ({}) (uri: {}) {}{}",
+                                    f.name.clone().unwrap_or_default(),
+                                    f.mime_type.clone().unwrap_or_default(),
+                                    f.id.clone().unwrap_or_default(),
+                                    describe_access(&f),
                                     if include_labels {
                                         format!(" (labels: {:?})", f.label_info.unwrap_or_default())
                                     } else {
@@ -3066,6 +3175,38 @@ impl GoogleDriveRouter {
         Ok(vec![Content::text(results.join("\n"))])
     }
 
+    async fn check_edit_access(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let file_id =
+            params
+                .get("fileId")
+                .and_then(|q| q.as_str())
+                .ok_or(ToolError::InvalidParameters(
+                    "The fileId param is required".to_string(),
+                ))?;
+
+        let result = self
+            .drive
+            .files()
+            .get(file_id)
+            .param(
+                "fields",
+                "id, name, driveId, owners(displayName, emailAddress), capabilities(canEdit, canComment)",
+            )
+            .supports_all_drives(true)
+            .clear_scopes() // Scope::MeetReadonly is the default, remove it
+            .add_scope(GOOGLE_DRIVE_SCOPES)
+            .doit()
+            .await;
+
+        match result {
+            Err(e) => Err(ToolError::ExecutionError(format!(
+                "Failed to execute google drive get, {}.",
+                e
+            ))),
+            Ok(r) => Ok(vec![Content::text(edit_access_summary(&r.1))]),
+        }
+    }
+
     async fn sharing(&self, params: Value) -> Result<Vec<Content>, ToolError> {
         let file_id =
             params
@@ -3300,6 +3441,7 @@ impl Router for GoogleDriveRouter {
                 "get_comments" => this.get_comments(arguments).await,
                 "list_drives" => this.list_drives(arguments).await,
                 "get_permissions" => this.get_permissions(arguments).await,
+                "check_edit_access" => this.check_edit_access(arguments).await,
                 "sharing" => this.sharing(arguments).await,
                 _ => Err(ToolError::NotFound(format!("Tool {} not found", tool_name))),
             }
@@ -3357,6 +3499,7 @@ impl Clone for GoogleDriveRouter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use google_drive3::api::{FileCapabilities, User};
 
     #[test]
     fn test_document_url() {
@@ -3406,6 +3549,50 @@ mod tests {
         assert_eq!(extract_google_drive_id(url), None);
     }
 
+    #[test]
+    fn test_search_corpus() {
+        assert_eq!(search_corpus(None, None).unwrap(), "user");
+        assert_eq!(search_corpus(None, Some("0AB")).unwrap(), "drive");
+        assert_eq!(
+            search_corpus(Some("allDrives"), Some("0AB")).unwrap(),
+            "allDrives"
+        );
+        assert!(search_corpus(Some("drive"), None).is_err());
+        assert!(search_corpus(Some("domain"), None).is_err());
+    }
+
+    #[test]
+    fn test_edit_access_summary() {
+        let mut file = File {
+            id: Some("1abc".to_string()),
+            name: Some("Plan".to_string()),
+            owners: Some(vec![User {
+                email_address: Some("owner@example.com".to_string()),
+                ..Default::default()
+            }]),
+            capabilities: Some(FileCapabilities {
+                can_edit: Some(false),
+                can_comment: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            edit_access_summary(&file),
+            "You cannot edit Plan (uri: 1abc), (access: comment) (owner: owner@example.com) (shared: false). Ask owner@example.com for writer access, or create a copy to edit instead."
+        );
+
+        file.owners = None;
+        file.drive_id = Some("0AB".to_string());
+        assert_eq!(owned_by(&file), "shared drive 0AB");
+
+        file.capabilities = Some(FileCapabilities {
+            can_edit: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(edit_access_summary(&file), "You can edit Plan (uri: 1abc).");
+    }
+
     #[test]
     fn test_no_d_segment() {
         let url =
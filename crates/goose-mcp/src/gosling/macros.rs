@@ -0,0 +1,371 @@
+use etcetera::{choose_app_strategy, AppStrategy};
+use mcp_core::ToolError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Overrides where macros are stored, by default the gosling/macros directory in goose's data dir
+pub const MACROS_DIR_ENV: &str = "GOSLING_MACROS_DIR";
+
+/// `{{ name }}` placeholders in recorded text, filled in on replay
+static PARAMETER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").unwrap());
+
+/// One device interaction, run with `adb shell input` or by waiting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Step {
+    Tap {
+        x: u32,
+        y: u32,
+    },
+    Swipe {
+        x1: u32,
+        y1: u32,
+        x2: u32,
+        y2: u32,
+        #[serde(default = "default_swipe_ms")]
+        duration_ms: u64,
+    },
+    Text {
+        text: String,
+    },
+    Key {
+        keycode: String,
+    },
+    Wait {
+        ms: u64,
+    },
+}
+
+fn default_swipe_ms() -> u64 {
+    300
+}
+
+/// Escape text for `adb shell input text`, which runs through the device shell and reads
+/// %s as a space
+pub fn escape_input_text(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            ' ' => escaped.push_str("%s"),
+            '\\' | '"' | '\'' | '`' | '$' | '&' | '|' | ';' | '<' | '>' | '(' | ')' | '*' | '~'
+            | '#' | '?' | '!' | '%' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl Step {
+    /// Parse a step from tool arguments
+    pub fn from_params(params: &Value) -> Result<Self, ToolError> {
+        let action = params
+            .get("action")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'action' parameter".into()))?;
+        let number = |key: &str| {
+            params.get(key).and_then(|v| v.as_u64()).ok_or_else(|| {
+                ToolError::InvalidParameters(format!("'{}' needs a '{}' parameter", action, key))
+            })
+        };
+        let string = |key: &str| {
+            params
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    ToolError::InvalidParameters(format!(
+                        "'{}' needs a '{}' parameter",
+                        action, key
+                    ))
+                })
+        };
+        Ok(match action {
+            "tap" => Step::Tap {
+                x: number("x")? as u32,
+                y: number("y")? as u32,
+            },
+            "swipe" => Step::Swipe {
+                x1: number("x")? as u32,
+                y1: number("y")? as u32,
+                x2: number("x2")? as u32,
+                y2: number("y2")? as u32,
+                duration_ms: number("duration_ms").unwrap_or_else(|_| default_swipe_ms()),
+            },
+            "text" => Step::Text {
+                text: string("text")?,
+            },
+            "key" => Step::Key {
+                keycode: string("keycode")?,
+            },
+            "wait" => Step::Wait { ms: number("ms")? },
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "Unknown action '{}', use tap, swipe, text, key or wait",
+                    other
+                )))
+            }
+        })
+    }
+
+    /// Arguments for `adb`, None for a wait
+    pub fn adb_args(&self) -> Option<Vec<String>> {
+        let input = |args: Vec<String>| {
+            let mut all = vec!["shell".to_string(), "input".to_string()];
+            all.extend(args);
+            Some(all)
+        };
+        match self {
+            Step::Tap { x, y } => input(vec!["tap".into(), x.to_string(), y.to_string()]),
+            Step::Swipe {
+                x1,
+                y1,
+                x2,
+                y2,
+                duration_ms,
+            } => input(vec![
+                "swipe".into(),
+                x1.to_string(),
+                y1.to_string(),
+                x2.to_string(),
+                y2.to_string(),
+                duration_ms.to_string(),
+            ]),
+            Step::Text { text } => input(vec!["text".into(), escape_input_text(text)]),
+            Step::Key { keycode } => input(vec!["keyevent".into(), keycode.clone()]),
+            Step::Wait { .. } => None,
+        }
+    }
+
+    /// The step with the placeholders in its text filled in
+    pub fn substitute(&self, params: &HashMap<String, String>) -> Result<Step, ToolError> {
+        let fill = |text: &str| -> Result<String, ToolError> {
+            let mut missing = Vec::new();
+            let filled = PARAMETER.replace_all(text, |caps: &regex::Captures| {
+                params.get(&caps[1]).cloned().unwrap_or_else(|| {
+                    missing.push(caps[1].to_string());
+                    String::new()
+                })
+            });
+            if !missing.is_empty() {
+                return Err(ToolError::InvalidParameters(format!(
+                    "Missing macro parameters: {}",
+                    missing.join(", ")
+                )));
+            }
+            Ok(filled.into_owned())
+        };
+        Ok(match self {
+            Step::Text { text } => Step::Text { text: fill(text)? },
+            Step::Key { keycode } => Step::Key {
+                keycode: fill(keycode)?,
+            },
+            other => other.clone(),
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Step::Tap { x, y } => format!("tap ({}, {})", x, y),
+            Step::Swipe {
+                x1,
+                y1,
+                x2,
+                y2,
+                duration_ms,
+            } => format!(
+                "swipe ({}, {}) -> ({}, {}) over {}ms",
+                x1, y1, x2, y2, duration_ms
+            ),
+            Step::Text { text } => format!("type \"{}\"", text),
+            Step::Key { keycode } => format!("key {}", keycode),
+            Step::Wait { ms } => format!("wait {}ms", ms),
+        }
+    }
+}
+
+/// A named sequence of interactions, stored as `<name>.json` in the macros directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub steps: Vec<Step>,
+}
+
+impl Macro {
+    /// The parameters replay needs, in the order they first appear
+    pub fn parameters(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for step in &self.steps {
+            let text = match step {
+                Step::Text { text } => text,
+                Step::Key { keycode } => keycode,
+                _ => continue,
+            };
+            for caps in PARAMETER.captures_iter(text) {
+                if !names.iter().any(|name| name == &caps[1]) {
+                    names.push(caps[1].to_string());
+                }
+            }
+        }
+        names
+    }
+}
+
+/// The placeholder recorded for a parameter, e.g. `{{password}}`
+pub fn placeholder(parameter: &str) -> Result<String, ToolError> {
+    let placeholder = format!("{{{{{}}}}}", parameter);
+    if !PARAMETER.is_match(&placeholder) {
+        return Err(ToolError::InvalidParameters(format!(
+            "Invalid parameter name '{}', use letters, digits and _",
+            parameter
+        )));
+    }
+    Ok(placeholder)
+}
+
+pub fn macros_dir() -> Result<PathBuf, ToolError> {
+    if let Ok(dir) = std::env::var(MACROS_DIR_ENV) {
+        return Ok(PathBuf::from(shellexpand::tilde(&dir).into_owned()));
+    }
+    choose_app_strategy(crate::APP_STRATEGY.clone())
+        .map(|strategy| strategy.in_data_dir("gosling").join("macros"))
+        .map_err(|e| ToolError::ExecutionError(format!("No data directory: {}", e)))
+}
+
+/// Macro names become file names, so only allow characters that are safe there
+pub fn validate_name(name: &str) -> Result<(), ToolError> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        return Err(ToolError::InvalidParameters(format!(
+            "Invalid macro name '{}', use letters, digits, - and _",
+            name
+        )));
+    }
+    Ok(())
+}
+
+pub fn save_macro(dir: &Path, recorded: &Macro) -> Result<PathBuf, ToolError> {
+    validate_name(&recorded.name)?;
+    fs::create_dir_all(dir)
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to create {:?}: {}", dir, e)))?;
+    let path = dir.join(format!("{}.json", recorded.name));
+    let json = serde_json::to_string_pretty(recorded)
+        .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+    fs::write(&path, json)
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to write {:?}: {}", path, e)))?;
+    Ok(path)
+}
+
+pub fn load_macro(dir: &Path, name: &str) -> Result<Macro, ToolError> {
+    validate_name(name)?;
+    let path = dir.join(format!("{}.json", name));
+    let json = fs::read_to_string(&path).map_err(|_| {
+        ToolError::InvalidParameters(format!(
+            "No macro named '{}', see list_macros for the recorded ones",
+            name
+        ))
+    })?;
+    serde_json::from_str(&json)
+        .map_err(|e| ToolError::ExecutionError(format!("Invalid macro file {:?}: {}", path, e)))
+}
+
+/// Every stored macro, sorted by name, skipping files that can't be read
+pub fn list_macros(dir: &Path) -> Vec<Macro> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut macros: Vec<Macro> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect();
+    macros.sort_by(|a, b| a.name.cmp(&b.name));
+    macros
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_steps() {
+        let tap = Step::from_params(&json!({"action": "tap", "x": 10, "y": 20})).unwrap();
+        assert_eq!(
+            tap.adb_args().unwrap(),
+            vec!["shell", "input", "tap", "10", "20"]
+        );
+        let swipe =
+            Step::from_params(&json!({"action": "swipe", "x": 1, "y": 2, "x2": 3, "y2": 4}))
+                .unwrap();
+        assert_eq!(
+            swipe.adb_args().unwrap(),
+            vec!["shell", "input", "swipe", "1", "2", "3", "4", "300"]
+        );
+        assert!(Step::from_params(&json!({"action": "tap", "x": 10})).is_err());
+        assert!(Step::from_params(&json!({"action": "shake"})).is_err());
+        assert!(Step::Wait { ms: 500 }.adb_args().is_none());
+
+        assert_eq!(
+            escape_input_text("it's 5 & up"),
+            "it\\'s%s5%s\\&%sup".to_string()
+        );
+    }
+
+    #[test]
+    fn test_substitute_and_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let login = Macro {
+            name: "login".to_string(),
+            description: "Log in as a test user".to_string(),
+            steps: vec![
+                Step::Tap { x: 100, y: 200 },
+                Step::Text {
+                    text: "{{ user }}".to_string(),
+                },
+                Step::Text {
+                    text: "{{password}}".to_string(),
+                },
+                Step::Key {
+                    keycode: "KEYCODE_ENTER".to_string(),
+                },
+            ],
+        };
+        assert_eq!(login.parameters(), vec!["user", "password"]);
+        assert_eq!(placeholder("password").unwrap(), "{{password}}");
+        assert!(placeholder("pass word").is_err());
+
+        let params = HashMap::from([
+            ("user".to_string(), "qa@example.com".to_string()),
+            ("password".to_string(), "hunter2".to_string()),
+        ]);
+        assert_eq!(
+            login.steps[1].substitute(&params).unwrap(),
+            Step::Text {
+                text: "qa@example.com".to_string()
+            }
+        );
+        assert!(login.steps[2].substitute(&HashMap::new()).is_err());
+
+        save_macro(dir.path(), &login).unwrap();
+        assert_eq!(load_macro(dir.path(), "login").unwrap(), login);
+        assert_eq!(list_macros(dir.path()), vec![login]);
+        assert!(load_macro(dir.path(), "../login").is_err());
+        assert!(load_macro(dir.path(), "missing").is_err());
+    }
+}
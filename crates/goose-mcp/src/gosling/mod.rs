@@ -0,0 +1,934 @@
+use indoc::{formatdoc, indoc};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{process::Command, sync::mpsc};
+
+use mcp_core::{
+    handler::{PromptError, ResourceError, ToolError},
+    prompt::Prompt,
+    protocol::{JsonRpcMessage, ServerCapabilities},
+    resource::Resource,
+    tool::{Tool, ToolAnnotations},
+    Content,
+};
+use mcp_server::router::CapabilitiesBuilder;
+use mcp_server::Router;
+
+mod macros;
+use macros::{Macro, Step, MACROS_DIR_ENV};
+
+/// Overrides the adb binary, which is otherwise looked up on the PATH
+pub const ADB_PATH_ENV: &str = "GOSLING_ADB_PATH";
+
+const DEFAULT_LOGCAT_LINES: usize = 200;
+const MAX_LOGCAT_LINES: usize = 5000;
+const LOG_PRIORITIES: [&str; 7] = ["V", "D", "I", "W", "E", "F", "S"];
+const LOG_BUFFERS: [&str; 6] = ["main", "system", "crash", "events", "radio", "all"];
+/// Pause between replayed steps so the UI can catch up
+const DEFAULT_STEP_DELAY_MS: u64 = 250;
+
+/// What to collect from logcat
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogcatFilter {
+    pub tags: Vec<String>,
+    pub pid: Option<u32>,
+    pub min_priority: String,
+    pub buffer: Option<String>,
+    pub lines: usize,
+}
+
+impl Default for LogcatFilter {
+    fn default() -> Self {
+        Self {
+            tags: Vec::new(),
+            pid: None,
+            min_priority: "V".to_string(),
+            buffer: None,
+            lines: DEFAULT_LOGCAT_LINES,
+        }
+    }
+}
+
+impl LogcatFilter {
+    /// Arguments for a non-blocking `adb logcat` dump of the most recent matching lines
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec!["logcat".to_string(), "-d".to_string()];
+        if let Some(buffer) = &self.buffer {
+            args.extend(["-b".to_string(), buffer.clone()]);
+        }
+        args.extend(["-t".to_string(), self.lines.to_string()]);
+        if let Some(pid) = self.pid {
+            args.push(format!("--pid={}", pid));
+        }
+        if self.tags.is_empty() {
+            args.push(format!("*:{}", self.min_priority));
+        } else {
+            args.extend(
+                self.tags
+                    .iter()
+                    .map(|tag| format!("{}:{}", tag, self.min_priority)),
+            );
+            // Silence everything that is not one of the requested tags
+            args.push("*:S".to_string());
+        }
+        args
+    }
+}
+
+/// Android package names are dot separated identifiers, e.g. com.example.app
+pub fn is_valid_package(package: &str) -> bool {
+    let segments: Vec<&str> = package.split('.').collect();
+    segments.len() >= 2
+        && segments.iter().all(|segment| {
+            segment.starts_with(|c: char| c.is_ascii_alphabetic())
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+/// The last `max` lines of the output, noting how many were dropped
+pub fn tail_lines(output: &str, max: usize) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    if lines.len() <= max {
+        return lines.join("\n");
+    }
+    format!(
+        "[{} earlier lines omitted]\n{}",
+        lines.len() - max,
+        lines[lines.len() - max..].join("\n")
+    )
+}
+
+/// An extension for driving Android devices and emulators over adb, so mobile QA workflows
+/// such as installing a build, exercising it and collecting its logs can be automated.
+pub struct GoslingRouter {
+    tools: Vec<Tool>,
+    instructions: String,
+    adb: String,
+    /// The macro being recorded, device_input steps are added to it
+    recording: Arc<Mutex<Option<Macro>>>,
+}
+
+impl Default for GoslingRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GoslingRouter {
+    pub fn new() -> Self {
+        let device_property = json!({
+            "type": "string",
+            "description": "Serial of the device to use, as listed by `adb devices`. Only needed when more than one device is connected."
+        });
+
+        let install_apk_tool = Tool::new(
+            "install_apk",
+            indoc! {r#"
+                Install an APK on a connected Android device or emulator.
+                By default an existing install of the same package is replaced and keeps its data.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["path"],
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to the APK file on this machine"
+                    },
+                    "device": device_property,
+                    "replace": {
+                        "type": "boolean",
+                        "default": true,
+                        "description": "Replace the app if it is already installed"
+                    },
+                    "grant_permissions": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Grant all runtime permissions listed in the manifest"
+                    }
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Install APK".to_string()),
+                read_only_hint: false,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let uninstall_package_tool = Tool::new(
+            "uninstall_package",
+            indoc! {r#"
+                Uninstall an app from a connected Android device or emulator by its package name.
+                The app's data is removed too unless keep_data is set.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["package"],
+                "properties": {
+                    "package": {
+                        "type": "string",
+                        "description": "Package name of the app, e.g. com.example.app"
+                    },
+                    "device": device_property,
+                    "keep_data": {
+                        "type": "boolean",
+                        "default": false,
+                        "description": "Keep the app's data and cache directories"
+                    }
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Uninstall package".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let capture_logcat_tool = Tool::new(
+            "capture_logcat",
+            formatdoc! {r#"
+                Capture the most recent lines of the device log (logcat) without waiting for new ones.
+                Filter by tag, by process (pid or package name of a running app) and by minimum priority
+                to keep the output focused. Use the crash buffer to collect crash logs.
+                At most {max} lines are returned.
+            "#, max = MAX_LOGCAT_LINES},
+            json!({
+                "type": "object",
+                "properties": {
+                    "device": device_property,
+                    "tags": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Only include lines with these tags, e.g. [\"ActivityManager\", \"AndroidRuntime\"]"
+                    },
+                    "pid": {
+                        "type": "integer",
+                        "description": "Only include lines from this process"
+                    },
+                    "package": {
+                        "type": "string",
+                        "description": "Only include lines from the running process of this package"
+                    },
+                    "min_priority": {
+                        "type": "string",
+                        "enum": LOG_PRIORITIES,
+                        "default": "V",
+                        "description": "Lowest priority to include: Verbose, Debug, Info, Warn, Error, Fatal or Silent"
+                    },
+                    "buffer": {
+                        "type": "string",
+                        "enum": LOG_BUFFERS,
+                        "description": "Log buffer to read, defaults to the main, system and crash buffers"
+                    },
+                    "lines": {
+                        "type": "integer",
+                        "default": DEFAULT_LOGCAT_LINES,
+                        "description": format!("Number of most recent lines to return, at most {}", MAX_LOGCAT_LINES)
+                    }
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Capture logcat".to_string()),
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: false,
+                open_world_hint: false,
+            }),
+        );
+
+        let device_input_tool = Tool::new(
+            "device_input",
+            indoc! {r#"
+                Interact with the device screen: tap, swipe, type text, press a key or wait.
+                Coordinates are in screen pixels. While a macro is being recorded each interaction
+                is added to it.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["action"],
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["tap", "swipe", "text", "key", "wait"]
+                    },
+                    "device": device_property,
+                    "x": {"type": "integer", "description": "x to tap, or to start a swipe from"},
+                    "y": {"type": "integer", "description": "y to tap, or to start a swipe from"},
+                    "x2": {"type": "integer", "description": "x to end a swipe at"},
+                    "y2": {"type": "integer", "description": "y to end a swipe at"},
+                    "duration_ms": {"type": "integer", "default": 300, "description": "How long a swipe takes"},
+                    "text": {"type": "string", "description": "Text to type into the focused field"},
+                    "record_as": {
+                        "type": "string",
+                        "description": "While recording, store the text as a {{record_as}} parameter filled in on replay instead of the literal text, e.g. for user names and passwords"
+                    },
+                    "keycode": {"type": "string", "description": "Key to press, e.g. KEYCODE_ENTER or KEYCODE_BACK"},
+                    "ms": {"type": "integer", "description": "How long to wait"}
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Device input".to_string()),
+                read_only_hint: false,
+                destructive_hint: false,
+                idempotent_hint: false,
+                open_world_hint: false,
+            }),
+        );
+
+        let record_macro_tool = Tool::new(
+            "record_macro",
+            indoc! {r#"
+                Record the device_input interactions that follow into a named macro, which replay_macro
+                can run again later without working out each step. Start recording, perform the flow
+                with device_input, then stop to save it, or cancel to throw it away.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["command"],
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "enum": ["start", "stop", "cancel"]
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Name of the macro to record, letters, digits, - and _. Required to start."
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "What the macro does, e.g. 'Log in and open the settings screen'"
+                    }
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Record macro".to_string()),
+                read_only_hint: false,
+                destructive_hint: false,
+                idempotent_hint: false,
+                open_world_hint: false,
+            }),
+        );
+
+        let replay_macro_tool = Tool::new(
+            "replay_macro",
+            indoc! {r#"
+                Replay a recorded macro on the device. Values for the macro's parameters are passed in params.
+                Check the screen afterwards, replay does not verify that each step had the intended effect.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["name"],
+                "properties": {
+                    "name": {"type": "string", "description": "Name of the macro"},
+                    "device": device_property,
+                    "params": {
+                        "type": "object",
+                        "additionalProperties": {"type": "string"},
+                        "description": "Values for the macro's parameters, as listed by list_macros"
+                    },
+                    "step_delay_ms": {
+                        "type": "integer",
+                        "default": DEFAULT_STEP_DELAY_MS,
+                        "description": "Pause between steps"
+                    }
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Replay macro".to_string()),
+                read_only_hint: false,
+                destructive_hint: false,
+                idempotent_hint: false,
+                open_world_hint: false,
+            }),
+        );
+
+        let list_macros_tool = Tool::new(
+            "list_macros",
+            indoc! {r#"
+                List the recorded macros with their descriptions, parameters and steps.
+            "#},
+            json!({
+                "type": "object",
+                "properties": {}
+            }),
+            Some(ToolAnnotations {
+                title: Some("List macros".to_string()),
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let instructions = formatdoc! {r#"
+            The gosling extension controls Android devices and emulators through adb.
+            install_apk
+              - Install a build on the device, replacing an existing install by default
+            uninstall_package
+              - Remove an app and, unless asked to keep it, its data
+            capture_logcat
+              - Collect recent device logs, filtered by tag, process or priority
+              - After a crash, read the crash buffer or filter on the AndroidRuntime tag
+            device_input
+              - Tap, swipe, type text, press keys or wait on the device
+            record_macro, replay_macro and list_macros
+              - Record a flow that is repeated often, such as logging in or navigating to a screen,
+                and replay it in one call instead of repeating each step
+              - Before performing a common flow step by step, check list_macros for one that does it
+              - Record secrets and other changing text with record_as so they become replay parameters
+              - Macros are stored in {macros_env} when set, otherwise in goose's data directory
+
+            A typical QA loop is to install the build, exercise it (for example with `adb shell am start`
+            or `adb shell input` through the developer extension) and then capture logcat for the app's package.
+            When several devices are connected, pass the device serial to every tool.
+            The adb binary is taken from {adb_env} when set, otherwise from the PATH.
+            "#,
+            adb_env = ADB_PATH_ENV,
+            macros_env = MACROS_DIR_ENV,
+        };
+
+        Self {
+            tools: vec![
+                install_apk_tool,
+                uninstall_package_tool,
+                capture_logcat_tool,
+                device_input_tool,
+                record_macro_tool,
+                replay_macro_tool,
+                list_macros_tool,
+            ],
+            instructions,
+            adb: std::env::var(ADB_PATH_ENV).unwrap_or_else(|_| "adb".to_string()),
+            recording: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    async fn adb(&self, device: Option<&str>, args: &[String]) -> Result<String, ToolError> {
+        let mut command = Command::new(&self.adb);
+        if let Some(device) = device {
+            command.arg("-s").arg(device);
+        }
+        let output = command.args(args).output().await.map_err(|e| {
+            ToolError::ExecutionError(format!(
+                "Failed to run adb ({}): {}. Install the Android platform tools or set {}.",
+                self.adb, e, ADB_PATH_ENV
+            ))
+        })?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(ToolError::ExecutionError(format!(
+                "adb {} failed: {}{}",
+                args.first().map(String::as_str).unwrap_or_default(),
+                stderr.trim(),
+                stdout.trim()
+            )));
+        }
+        Ok(stdout)
+    }
+
+    async fn install_apk(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let path = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'path' parameter".into()))?;
+        let path = shellexpand::tilde(path).into_owned();
+        if !Path::new(&path).is_file() {
+            return Err(ToolError::InvalidParameters(format!(
+                "APK not found: {}",
+                path
+            )));
+        }
+        let device = params.get("device").and_then(|v| v.as_str());
+
+        let mut args = vec!["install".to_string()];
+        if params
+            .get("replace")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true)
+        {
+            args.push("-r".to_string());
+        }
+        if params
+            .get("grant_permissions")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            args.push("-g".to_string());
+        }
+        args.push(path.clone());
+
+        let output = self.adb(device, &args).await?;
+        // Older adb versions exit successfully even when the install fails
+        if output.contains("Failure") {
+            return Err(ToolError::ExecutionError(format!(
+                "Failed to install {}: {}",
+                path,
+                output.trim()
+            )));
+        }
+        Ok(vec![Content::text(format!(
+            "Installed {}\n{}",
+            path,
+            output.trim()
+        ))])
+    }
+
+    async fn uninstall_package(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let package = params
+            .get("package")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'package' parameter".into()))?;
+        if !is_valid_package(package) {
+            return Err(ToolError::InvalidParameters(format!(
+                "'{}' is not a valid package name",
+                package
+            )));
+        }
+        let device = params.get("device").and_then(|v| v.as_str());
+
+        let mut args = vec!["uninstall".to_string()];
+        if params
+            .get("keep_data")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            args.push("-k".to_string());
+        }
+        args.push(package.to_string());
+
+        let output = self.adb(device, &args).await?;
+        if output.contains("Failure") {
+            return Err(ToolError::ExecutionError(format!(
+                "Failed to uninstall {}: {}",
+                package,
+                output.trim()
+            )));
+        }
+        Ok(vec![Content::text(format!("Uninstalled {}", package))])
+    }
+
+    async fn capture_logcat(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let device = params.get("device").and_then(|v| v.as_str());
+
+        let min_priority = params
+            .get("min_priority")
+            .and_then(|v| v.as_str())
+            .unwrap_or("V")
+            .to_uppercase();
+        if !LOG_PRIORITIES.contains(&min_priority.as_str()) {
+            return Err(ToolError::InvalidParameters(format!(
+                "min_priority must be one of {}",
+                LOG_PRIORITIES.join(", ")
+            )));
+        }
+
+        let buffer = params
+            .get("buffer")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        if let Some(buffer) = &buffer {
+            if !LOG_BUFFERS.contains(&buffer.as_str()) {
+                return Err(ToolError::InvalidParameters(format!(
+                    "buffer must be one of {}",
+                    LOG_BUFFERS.join(", ")
+                )));
+            }
+        }
+
+        let tags = params
+            .get("tags")
+            .and_then(|v| v.as_array())
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|tag| tag.as_str())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let pid = match (
+            params.get("pid").and_then(|v| v.as_u64()),
+            params.get("package").and_then(|v| v.as_str()),
+        ) {
+            (Some(pid), _) => Some(pid as u32),
+            (None, Some(package)) => Some(self.package_pid(device, package).await?),
+            (None, None) => None,
+        };
+
+        let lines = params
+            .get("lines")
+            .and_then(|v| v.as_u64())
+            .map(|lines| lines as usize)
+            .unwrap_or(DEFAULT_LOGCAT_LINES)
+            .clamp(1, MAX_LOGCAT_LINES);
+
+        let filter = LogcatFilter {
+            tags,
+            pid,
+            min_priority,
+            buffer,
+            lines,
+        };
+        let output = self.adb(device, &filter.args()).await?;
+        let output = tail_lines(&output, lines);
+        if output.trim().is_empty() {
+            return Ok(vec![Content::text("No matching log lines")]);
+        }
+        Ok(vec![Content::text(output)])
+    }
+
+    /// Run one step on the device
+    async fn run_step(&self, device: Option<&str>, step: &Step) -> Result<(), ToolError> {
+        match step.adb_args() {
+            Some(args) => self.adb(device, &args).await.map(|_| ()),
+            None => {
+                if let Step::Wait { ms } = step {
+                    tokio::time::sleep(Duration::from_millis(*ms)).await;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    async fn device_input(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let device = params.get("device").and_then(|v| v.as_str());
+        let record_as = params.get("record_as").and_then(|v| v.as_str());
+        let placeholder = record_as.map(macros::placeholder).transpose()?;
+        let step = Step::from_params(&params)?;
+        self.run_step(device, &step).await?;
+
+        let mut recording = self.recording.lock().unwrap();
+        let Some(recorded) = recording.as_mut() else {
+            return Ok(vec![Content::text(format!("Done: {}", step.describe()))]);
+        };
+        let step = match (step, placeholder) {
+            (Step::Text { .. }, Some(text)) => Step::Text { text },
+            (step, _) => step,
+        };
+        let description = step.describe();
+        recorded.steps.push(step);
+        Ok(vec![Content::text(format!(
+            "Done: {} (step {} of macro '{}')",
+            description,
+            recorded.steps.len(),
+            recorded.name
+        ))])
+    }
+
+    async fn record_macro(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let command = params
+            .get("command")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'command' parameter".into()))?;
+
+        let mut recording = self.recording.lock().unwrap();
+        match command {
+            "start" => {
+                let name = params.get("name").and_then(|v| v.as_str()).ok_or_else(|| {
+                    ToolError::InvalidParameters("Missing 'name' parameter".into())
+                })?;
+                macros::validate_name(name)?;
+                if let Some(current) = recording.as_ref() {
+                    return Err(ToolError::ExecutionError(format!(
+                        "Already recording macro '{}', stop or cancel it first",
+                        current.name
+                    )));
+                }
+                *recording = Some(Macro {
+                    name: name.to_string(),
+                    description: params
+                        .get("description")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                    steps: Vec::new(),
+                });
+                Ok(vec![Content::text(format!(
+                    "Recording macro '{}', perform the flow with device_input and stop when done",
+                    name
+                ))])
+            }
+            "stop" => {
+                let recorded = recording.take().ok_or_else(|| {
+                    ToolError::ExecutionError("No macro is being recorded".into())
+                })?;
+                if recorded.steps.is_empty() {
+                    return Err(ToolError::ExecutionError(format!(
+                        "Macro '{}' has no steps and was not saved",
+                        recorded.name
+                    )));
+                }
+                let path = macros::save_macro(&macros::macros_dir()?, &recorded)?;
+                let parameters = recorded.parameters();
+                Ok(vec![Content::text(format!(
+                    "Saved macro '{}' with {} steps to {}{}",
+                    recorded.name,
+                    recorded.steps.len(),
+                    path.display(),
+                    if parameters.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", replay it with params: {}", parameters.join(", "))
+                    }
+                ))])
+            }
+            "cancel" => match recording.take() {
+                Some(recorded) => Ok(vec![Content::text(format!(
+                    "Discarded macro '{}'",
+                    recorded.name
+                ))]),
+                None => Ok(vec![Content::text("No macro is being recorded")]),
+            },
+            other => Err(ToolError::InvalidParameters(format!(
+                "Unknown command '{}', use start, stop or cancel",
+                other
+            ))),
+        }
+    }
+
+    async fn replay_macro(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let name = params
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'name' parameter".into()))?;
+        let device = params.get("device").and_then(|v| v.as_str());
+        let values: HashMap<String, String> = params
+            .get("params")
+            .and_then(|v| v.as_object())
+            .map(|values| {
+                values
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = value
+                            .as_str()
+                            .map(str::to_string)
+                            .unwrap_or_else(|| value.to_string());
+                        (key.clone(), value)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let delay = Duration::from_millis(
+            params
+                .get("step_delay_ms")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(DEFAULT_STEP_DELAY_MS),
+        );
+
+        let recorded = macros::load_macro(&macros::macros_dir()?, name)?;
+        // Fill in every step first so a missing parameter doesn't stop the flow half way
+        let steps = recorded
+            .steps
+            .iter()
+            .map(|step| step.substitute(&values))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (i, step) in steps.iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(delay).await;
+            }
+            self.run_step(device, step).await.map_err(|e| {
+                ToolError::ExecutionError(format!(
+                    "Macro '{}' failed at step {} ({}): {}",
+                    name,
+                    i + 1,
+                    recorded.steps[i].describe(),
+                    e
+                ))
+            })?;
+        }
+        Ok(vec![Content::text(format!(
+            "Replayed macro '{}', {} steps",
+            name,
+            steps.len()
+        ))])
+    }
+
+    async fn list_macros(&self) -> Result<Vec<Content>, ToolError> {
+        let macros = macros::list_macros(&macros::macros_dir()?);
+        if macros.is_empty() {
+            return Ok(vec![Content::text(
+                "No macros recorded yet, record one with record_macro",
+            )]);
+        }
+        let listed: Vec<String> = macros
+            .iter()
+            .map(|recorded| {
+                let mut text = recorded.name.clone();
+                if !recorded.description.is_empty() {
+                    text.push_str(&format!(": {}", recorded.description));
+                }
+                let parameters = recorded.parameters();
+                if !parameters.is_empty() {
+                    text.push_str(&format!("\n  params: {}", parameters.join(", ")));
+                }
+                for step in &recorded.steps {
+                    text.push_str(&format!("\n  - {}", step.describe()));
+                }
+                text
+            })
+            .collect();
+        Ok(vec![Content::text(listed.join("\n"))])
+    }
+
+    /// The pid of a package's running process
+    async fn package_pid(&self, device: Option<&str>, package: &str) -> Result<u32, ToolError> {
+        if !is_valid_package(package) {
+            return Err(ToolError::InvalidParameters(format!(
+                "'{}' is not a valid package name",
+                package
+            )));
+        }
+        let args = ["shell", "pidof", "-s", package].map(String::from);
+        // pidof exits with an error when the process is not running
+        let output = self.adb(device, &args).await.unwrap_or_default();
+        output.trim().parse().map_err(|_| {
+            ToolError::ExecutionError(format!(
+                "{} is not running, start it first or capture logs by tag instead",
+                package
+            ))
+        })
+    }
+}
+
+impl Router for GoslingRouter {
+    fn name(&self) -> String {
+        "gosling".to_string()
+    }
+
+    fn instructions(&self) -> String {
+        self.instructions.clone()
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        CapabilitiesBuilder::new().with_tools(false).build()
+    }
+
+    fn list_tools(&self) -> Vec<Tool> {
+        self.tools.clone()
+    }
+
+    fn call_tool(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        _notifier: mpsc::Sender<JsonRpcMessage>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>> {
+        let this = self.clone();
+        let tool_name = tool_name.to_string();
+        Box::pin(async move {
+            match tool_name.as_str() {
+                "install_apk" => this.install_apk(arguments).await,
+                "uninstall_package" => this.uninstall_package(arguments).await,
+                "capture_logcat" => this.capture_logcat(arguments).await,
+                "device_input" => this.device_input(arguments).await,
+                "record_macro" => this.record_macro(arguments).await,
+                "replay_macro" => this.replay_macro(arguments).await,
+                "list_macros" => this.list_macros().await,
+                _ => Err(ToolError::NotFound(format!("Tool {} not found", tool_name))),
+            }
+        })
+    }
+
+    fn list_resources(&self) -> Vec<Resource> {
+        Vec::new()
+    }
+
+    fn read_resource(
+        &self,
+        _uri: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
+        Box::pin(async move { Ok("".to_string()) })
+    }
+
+    fn list_prompts(&self) -> Vec<Prompt> {
+        vec![]
+    }
+
+    fn get_prompt(
+        &self,
+        prompt_name: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>> {
+        let prompt_name = prompt_name.to_string();
+        Box::pin(async move {
+            Err(PromptError::NotFound(format!(
+                "Prompt {} not found",
+                prompt_name
+            )))
+        })
+    }
+}
+
+impl Clone for GoslingRouter {
+    fn clone(&self) -> Self {
+        Self {
+            tools: self.tools.clone(),
+            instructions: self.instructions.clone(),
+            adb: self.adb.clone(),
+            recording: Arc::clone(&self.recording),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logcat_args() {
+        assert_eq!(
+            LogcatFilter::default().args(),
+            vec!["logcat", "-d", "-t", "200", "*:V"]
+        );
+
+        let filter = LogcatFilter {
+            tags: vec!["AndroidRuntime".to_string(), "MyApp".to_string()],
+            pid: Some(1234),
+            min_priority: "E".to_string(),
+            buffer: Some("crash".to_string()),
+            lines: 50,
+        };
+        assert_eq!(
+            filter.args(),
+            vec![
+                "logcat",
+                "-d",
+                "-b",
+                "crash",
+                "-t",
+                "50",
+                "--pid=1234",
+                "AndroidRuntime:E",
+                "MyApp:E",
+                "*:S"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_package_names_and_tail() {
+        assert!(is_valid_package("com.example.app"));
+        assert!(is_valid_package("com.example.app_debug2"));
+        assert!(!is_valid_package("app"));
+        assert!(!is_valid_package("com.example.1app"));
+        assert!(!is_valid_package("com.example; reboot"));
+
+        assert_eq!(tail_lines("a\nb\nc", 5), "a\nb\nc");
+        assert_eq!(tail_lines("a\nb\nc", 2), "[1 earlier lines omitted]\nb\nc");
+    }
+}
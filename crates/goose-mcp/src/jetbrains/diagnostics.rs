@@ -0,0 +1,171 @@
+use indoc::indoc;
+use mcp_core::tool::{Tool, ToolAnnotations};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub const DIAGNOSTICS_TOOL: &str = "get_diagnostics";
+pub const QUICK_FIX_TOOL: &str = "apply_quick_fix";
+
+/// IDE endpoints the diagnostics tools are built on
+pub const IDE_OPEN_FILE: &str = "open_file_in_editor";
+pub const IDE_FILE_PROBLEMS: &str = "get_current_file_errors";
+pub const IDE_PROJECT_PROBLEMS: &str = "get_project_problems";
+pub const IDE_APPLY_QUICK_FIX: &str = "apply_quick_fix";
+
+/// A problem reported by the IDE's inspections
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    #[serde(default)]
+    pub severity: String,
+    #[serde(alias = "message")]
+    pub description: String,
+    #[serde(default, alias = "file")]
+    pub file_path: Option<String>,
+    #[serde(default, alias = "lineNumber")]
+    pub line: Option<u32>,
+    #[serde(default)]
+    pub column: Option<u32>,
+    #[serde(default, alias = "fixes")]
+    pub quick_fixes: Vec<QuickFix>,
+}
+
+/// A fix the IDE offers for a diagnostic, applied by id with `apply_quick_fix`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct QuickFix {
+    pub id: String,
+    #[serde(alias = "text")]
+    pub name: String,
+}
+
+pub fn diagnostics_tool() -> Tool {
+    Tool::new(
+        DIAGNOSTICS_TOOL,
+        indoc! {r#"
+            Get the problems the IDE's inspections currently report, for one file or the whole project.
+            Each problem lists the quick fixes the IDE offers for it, which can be applied with apply_quick_fix.
+            Prefer these over guessing at errors from the file text. Checking a file opens it in the editor.
+        "#},
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the file to check, relative to the project root. Omit to get the problems of the whole project."
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Get IDE diagnostics".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn quick_fix_tool() -> Tool {
+    Tool::new(
+        QUICK_FIX_TOOL,
+        indoc! {r#"
+            Apply a quick fix offered by the IDE for a problem reported by get_diagnostics.
+            Run get_diagnostics again afterwards, fix ids are only valid until the file changes.
+        "#},
+        json!({
+            "type": "object",
+            "required": ["path", "fixId"],
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the file the problem is in, relative to the project root."
+                },
+                "fixId": {
+                    "type": "string",
+                    "description": "Id of the quick fix, as listed by get_diagnostics."
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Apply IDE quick fix".to_string()),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+/// Parse the problems the IDE returned, None if they are not in a format we know
+pub fn parse_diagnostics(text: &str) -> Option<Vec<Diagnostic>> {
+    let value: Value = serde_json::from_str(text).ok()?;
+    // Some plugin versions wrap the list in an object
+    let list = match value {
+        Value::Object(mut map) => map.remove("problems")?,
+        other => other,
+    };
+    serde_json::from_value(list).ok()
+}
+
+/// One line per problem, `path:line:column [severity] description`, followed by its fixes
+pub fn format_diagnostics(diagnostics: &[Diagnostic], default_path: Option<&str>) -> String {
+    if diagnostics.is_empty() {
+        return "No problems found.".to_string();
+    }
+    let mut lines = Vec::new();
+    for diagnostic in diagnostics {
+        let mut location = diagnostic
+            .file_path
+            .as_deref()
+            .or(default_path)
+            .unwrap_or("<unknown>")
+            .to_string();
+        if let Some(line) = diagnostic.line {
+            location.push_str(&format!(":{}", line));
+            if let Some(column) = diagnostic.column {
+                location.push_str(&format!(":{}", column));
+            }
+        }
+        let severity = if diagnostic.severity.is_empty() {
+            "PROBLEM"
+        } else {
+            diagnostic.severity.as_str()
+        };
+        lines.push(format!(
+            "{} [{}] {}",
+            location, severity, diagnostic.description
+        ));
+        for fix in &diagnostic.quick_fixes {
+            lines.push(format!("  quick fix {}: {}", fix.id, fix.name));
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_diagnostics() {
+        let text = r#"[
+            {"severity": "ERROR", "description": "Cannot resolve symbol 'foo'", "line": 12, "column": 5,
+             "quickFixes": [{"id": "fix-1", "name": "Create local variable 'foo'"}]},
+            {"severity": "WARNING", "message": "Unused import", "filePath": "src/Other.java", "lineNumber": 3}
+        ]"#;
+        let diagnostics = parse_diagnostics(text).unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].quick_fixes[0].id, "fix-1");
+        assert_eq!(
+            format_diagnostics(&diagnostics, Some("src/Main.java")),
+            "src/Main.java:12:5 [ERROR] Cannot resolve symbol 'foo'\n  \
+             quick fix fix-1: Create local variable 'foo'\n\
+             src/Other.java:3 [WARNING] Unused import"
+        );
+
+        let wrapped = r#"{"problems": [{"description": "Typo"}]}"#;
+        assert_eq!(parse_diagnostics(wrapped).unwrap()[0].description, "Typo");
+        assert!(parse_diagnostics("No open file").is_none());
+        assert_eq!(format_diagnostics(&[], None), "No problems found.");
+    }
+}
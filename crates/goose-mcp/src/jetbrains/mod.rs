@@ -1,3 +1,4 @@
+mod diagnostics;
 mod proxy;
 
 use anyhow::Result;
@@ -12,7 +13,7 @@ use mcp_core::{
 };
 use mcp_server::router::CapabilitiesBuilder;
 use mcp_server::Router;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -20,6 +21,10 @@ use tokio::sync::{mpsc, Mutex};
 use tokio::time::{sleep, Duration};
 use tracing::error;
 
+use self::diagnostics::{
+    diagnostics_tool, format_diagnostics, parse_diagnostics, quick_fix_tool, DIAGNOSTICS_TOOL,
+    IDE_APPLY_QUICK_FIX, IDE_FILE_PROBLEMS, IDE_OPEN_FILE, IDE_PROJECT_PROBLEMS, QUICK_FIX_TOOL,
+};
 use self::proxy::JetBrainsProxy;
 
 pub struct JetBrainsRouter {
@@ -38,7 +43,7 @@ impl JetBrainsRouter {
     pub fn new() -> Self {
         let tools = Arc::new(Mutex::new(Vec::new()));
         let proxy = Arc::new(JetBrainsProxy::new());
-        let instructions = "JetBrains IDE integration".to_string();
+        let instructions = "JetBrains IDE integration. Use get_diagnostics to see the problems the IDE's inspections report and apply_quick_fix to apply the fixes it offers for them.".to_string();
 
         // Initialize the proxy
         let proxy_clone = Arc::clone(&proxy);
@@ -97,6 +102,70 @@ impl JetBrainsRouter {
         Ok(contents)
     }
 
+    /// Call an IDE endpoint, turning a reported error into a tool error
+    async fn call_ide(&self, name: &str, arguments: Value) -> Result<String, ToolError> {
+        let result = self
+            .proxy
+            .call_tool(name, arguments)
+            .await
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        let text = result
+            .content
+            .iter()
+            .filter_map(|content| content.as_text())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if result.is_error {
+            return Err(ToolError::ExecutionError(text));
+        }
+        Ok(text)
+    }
+
+    async fn ide_has_tool(&self, name: &str) -> bool {
+        self.tools.lock().await.iter().any(|tool| tool.name == name)
+    }
+
+    async fn get_diagnostics(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        let path = arguments.get("path").and_then(|p| p.as_str());
+        let text = match path {
+            // The IDE only reports file problems for the file open in the editor
+            Some(path) => {
+                self.call_ide(IDE_OPEN_FILE, json!({ "filePath": path }))
+                    .await?;
+                self.call_ide(IDE_FILE_PROBLEMS, json!({})).await?
+            }
+            None => self.call_ide(IDE_PROJECT_PROBLEMS, json!({})).await?,
+        };
+        let output = match parse_diagnostics(&text) {
+            Some(diagnostics) => format_diagnostics(&diagnostics, path),
+            None => text,
+        };
+        Ok(vec![Content::text(output)])
+    }
+
+    async fn apply_quick_fix(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        let path = arguments
+            .get("path")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("The path is required".to_string()))?;
+        let fix_id = arguments
+            .get("fixId")
+            .and_then(|f| f.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("The fixId is required".to_string()))?;
+        if !self.ide_has_tool(IDE_APPLY_QUICK_FIX).await {
+            return Err(ToolError::ExecutionError(
+                "The IDE plugin does not support applying quick fixes, update the MCP Server plugin or make the change with another tool.".to_string(),
+            ));
+        }
+        let text = self
+            .call_ide(
+                IDE_APPLY_QUICK_FIX,
+                json!({ "filePath": path, "fixId": fix_id }),
+            )
+            .await?;
+        Ok(vec![Content::text(text)])
+    }
+
     async fn ensure_tools(&self) -> Result<(), ToolError> {
         let mut retry_count = 0;
         let max_retries = 50; // 5 second total wait time
@@ -139,17 +208,21 @@ impl Router for JetBrainsRouter {
                 .unwrap();
             rt.block_on(async {
                 let tools = self.tools.lock().await;
-                if tools.is_empty() {
+                let mut tools = if tools.is_empty() {
                     drop(tools);
                     if let Err(e) = self.ensure_tools().await {
                         error!("Failed to ensure tools: {}", e);
-                        vec![]
-                    } else {
-                        self.tools.lock().await.clone()
+                        return vec![];
                     }
+                    self.tools.lock().await.clone()
                 } else {
                     tools.clone()
-                }
+                };
+                // Our own tools wrap the IDE's, replacing any it has with the same name
+                tools.retain(|tool| tool.name != DIAGNOSTICS_TOOL && tool.name != QUICK_FIX_TOOL);
+                tools.push(diagnostics_tool());
+                tools.push(quick_fix_tool());
+                tools
             })
         })
     }
@@ -164,7 +237,11 @@ impl Router for JetBrainsRouter {
         let tool_name = tool_name.to_string();
         Box::pin(async move {
             this.ensure_tools().await?;
-            this.call_proxy_tool(tool_name, arguments).await
+            match tool_name.as_str() {
+                DIAGNOSTICS_TOOL => this.get_diagnostics(arguments).await,
+                QUICK_FIX_TOOL => this.apply_quick_fix(arguments).await,
+                _ => this.call_proxy_tool(tool_name, arguments).await,
+            }
         })
     }
 
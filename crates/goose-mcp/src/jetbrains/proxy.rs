@@ -238,6 +238,7 @@ impl JetBrainsProxy {
                         description: first_sentence,
                         input_schema,
                         annotations: None,
+                        output_schema: None,
                     })
                 } else {
                     debug!("Skipping invalid tool entry: {:?}", t);
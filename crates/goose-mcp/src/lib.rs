@@ -10,13 +10,25 @@ pub static APP_STRATEGY: Lazy<AppStrategyArgs> = Lazy::new(|| AppStrategyArgs {
 pub mod computercontroller;
 mod developer;
 pub mod google_drive;
+mod gosling;
 mod jetbrains;
 mod memory;
+mod notebook;
+mod pim;
+pub mod secrets;
+mod speech;
+mod tracker;
 mod tutorial;
 
 pub use computercontroller::ComputerControllerRouter;
-pub use developer::DeveloperRouter;
+pub use developer::{audit as edit_journal, DeveloperRouter};
 pub use google_drive::GoogleDriveRouter;
+pub use gosling::GoslingRouter;
 pub use jetbrains::JetBrainsRouter;
-pub use memory::MemoryRouter;
+pub use memory::{transfer as memory_transfer, MemoryRouter, MEMORY_DIR_ENV};
+pub use notebook::NotebookRouter;
+pub use pim::PimRouter;
+pub use secrets::SecretsRouter;
+pub use speech::SpeechRouter;
+pub use tracker::TrackerRouter;
 pub use tutorial::TutorialRouter;
@@ -1,13 +1,13 @@
 use async_trait::async_trait;
 use etcetera::{choose_app_strategy, AppStrategy};
-use indoc::formatdoc;
+use indoc::{formatdoc, indoc};
 use serde_json::{json, Value};
 use std::{
     collections::HashMap,
     fs,
     future::Future,
     io::{self, Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     pin::Pin,
 };
 use tokio::sync::mpsc;
@@ -23,6 +23,16 @@ use mcp_core::{
 use mcp_server::router::CapabilitiesBuilder;
 use mcp_server::Router;
 
+pub mod retention;
+pub mod transfer;
+
+use retention::{RetentionPolicy, RetentionSummary, UsageIndex};
+use transfer::{ConflictStrategy, ImportSummary, MemoryExport};
+
+/// Where global memories are kept instead of the config directory, so a server can give each
+/// of its users their own
+pub const MEMORY_DIR_ENV: &str = "GOOSE_MEMORY_DIR";
+
 // MemoryRouter implementation
 #[derive(Clone)]
 pub struct MemoryRouter {
@@ -30,6 +40,7 @@ pub struct MemoryRouter {
     instructions: String,
     global_memory_dir: PathBuf,
     local_memory_dir: PathBuf,
+    retention: RetentionPolicy,
 }
 
 impl Default for MemoryRouter {
@@ -42,14 +53,21 @@ impl MemoryRouter {
     pub fn new() -> Self {
         let remember_memory = Tool::new(
             "remember_memory",
-            "Stores a memory with optional tags in a specified category",
+            indoc! {r#"
+                Stores a memory with optional tags in a specified category.
+                Set 'ephemeral' for information that is only useful for a while, such as what is being worked on
+                right now. Ephemeral memories are dropped after 'ttl_hours', by default $GOOSE_MEMORY_EPHEMERAL_TTL_HOURS
+                or 24 hours.
+            "#},
             json!({
                 "type": "object",
                 "properties": {
                     "category": {"type": "string"},
                     "data": {"type": "string"},
                     "tags": {"type": "array", "items": {"type": "string"}},
-                    "is_global": {"type": "boolean"}
+                    "is_global": {"type": "boolean"},
+                    "ephemeral": {"type": "boolean"},
+                    "ttl_hours": {"type": "number", "description": "Hours an ephemeral memory is kept"}
                 },
                 "required": ["category", "data", "is_global"]
             }),
@@ -123,6 +141,98 @@ impl MemoryRouter {
             }),
         );
 
+        let export_memories = Tool::new(
+            "export_memories",
+            "Exports all global and local memories, with their categories and tags, to a single JSON file",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": {"type": "string", "description": "Path of the file to write"}
+                },
+                "required": ["path"]
+            }),
+            Some(ToolAnnotations {
+                title: Some("Export Memories".to_string()),
+                read_only_hint: false,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let import_memories = Tool::new(
+            "import_memories",
+            indoc! {r#"
+                Imports memories from a file written by export_memories.
+                The strategy decides what happens to categories that already exist: 'merge' (default) adds
+                new memories and merges tags, 'overwrite' replaces the category, 'skip' leaves it unchanged.
+            "#},
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": {"type": "string", "description": "Path of the file to import"},
+                    "strategy": {"type": "string", "enum": ["merge", "overwrite", "skip"]}
+                },
+                "required": ["path"]
+            }),
+            Some(ToolAnnotations {
+                title: Some("Import Memories".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let sync_memories = Tool::new(
+            "sync_memories",
+            indoc! {r#"
+                Syncs memories through a git repository, so they can be shared between machines.
+                Memories in the repository are merged into the local store, then the merged store is committed
+                and pushed. The repository defaults to $GOOSE_MEMORY_SYNC_REPO. If it does not exist yet, it is
+                cloned from 'remote' when given, or created as a local repository.
+            "#},
+            json!({
+                "type": "object",
+                "properties": {
+                    "repo": {"type": "string", "description": "Path of the local git repository"},
+                    "remote": {"type": "string", "description": "URL to clone the repository from"}
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Sync Memories".to_string()),
+                read_only_hint: false,
+                destructive_hint: false,
+                idempotent_hint: false,
+                open_world_hint: true,
+            }),
+        );
+
+        let compact_memories = Tool::new(
+            "compact_memories",
+            indoc! {r#"
+                Merges near-duplicate memories, keeping the longer text and the tags of both.
+                Memories are merged when they share at least 'threshold' of their words, 0.8 by default.
+                Only the given category is compacted, or every category when it is left out.
+            "#},
+            json!({
+                "type": "object",
+                "properties": {
+                    "category": {"type": "string"},
+                    "is_global": {"type": "boolean"},
+                    "threshold": {"type": "number", "minimum": 0, "maximum": 1}
+                },
+                "required": ["is_global"]
+            }),
+            Some(ToolAnnotations {
+                title: Some("Compact Memories".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
         let instructions = formatdoc! {r#"
              This extension allows storage and retrieval of categorized information with tagging support. It's designed to help
              manage important information across sessions in a systematic and organized manner.
@@ -131,6 +241,9 @@ impl MemoryRouter {
              2. Search memories by content or specific tags to find relevant information.
              3. List all available memory categories for easy navigation.
              4. Remove entire categories of memories when they are no longer needed.
+             5. Export memories to a file, import them on another machine, or sync them through a git repository.
+             6. Store ephemeral memories that expire, and compact near-duplicate memories. The least recently used
+                memories are removed when a category or the store grows past its limits.
              When to call memory tools:
              - These are examples where the assistant should proactively call the memory tool because the user is providing recurring preferences, project details, or workflow habits that they may expect to be remembered.
              - Preferred Development Tools & Conventions
@@ -237,9 +350,12 @@ impl MemoryRouter {
         // - macOS/Linux: ~/.config/goose/memory/
         // - Windows:     ~\AppData\Roaming\Block\goose\config\memory
         // if it fails, fall back to `.config/goose/memory` (relative to the current dir)
-        let global_memory_dir = choose_app_strategy(crate::APP_STRATEGY.clone())
-            .map(|strategy| strategy.in_config_dir("memory"))
-            .unwrap_or_else(|_| PathBuf::from(".config/goose/memory"));
+        let global_memory_dir = match std::env::var(MEMORY_DIR_ENV) {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => choose_app_strategy(crate::APP_STRATEGY.clone())
+                .map(|strategy| strategy.in_config_dir("memory"))
+                .unwrap_or_else(|_| PathBuf::from(".config/goose/memory")),
+        };
 
         fs::create_dir_all(&global_memory_dir).unwrap();
         fs::create_dir_all(&local_memory_dir).unwrap();
@@ -250,12 +366,21 @@ impl MemoryRouter {
                 retrieve_memories,
                 remove_memory_category,
                 remove_specific_memory,
+                export_memories,
+                import_memories,
+                sync_memories,
+                compact_memories,
             ],
             instructions: instructions.clone(),
             global_memory_dir,
             local_memory_dir,
+            retention: RetentionPolicy::from_env(),
         };
 
+        // Drop expired memories before they end up in the instructions
+        let _ = memory_router.enforce_retention(true);
+        let _ = memory_router.enforce_retention(false);
+
         let retrieved_global_memories = memory_router.retrieve_all(true);
         let retrieved_local_memories = memory_router.retrieve_all(false);
 
@@ -330,7 +455,10 @@ impl MemoryRouter {
         if base_dir.exists() {
             for entry in fs::read_dir(base_dir)? {
                 let entry = entry?;
-                if entry.file_type()?.is_file() {
+                let path = entry.path();
+                // Skip the usage index and anything else that isn't a category
+                if entry.file_type()?.is_file() && path.extension().is_some_and(|ext| ext == "txt")
+                {
                     let category = entry.file_name().to_string_lossy().replace(".txt", "");
                     let category_memories = self.retrieve(&category, is_global)?;
                     memories.insert(
@@ -350,7 +478,8 @@ impl MemoryRouter {
         data: &str,
         tags: &[&str],
         is_global: bool,
-    ) -> io::Result<()> {
+        ttl: Option<chrono::Duration>,
+    ) -> io::Result<RetentionSummary> {
         let memory_file_path = self.get_memory_file(category, is_global);
 
         let mut file = fs::OpenOptions::new()
@@ -361,8 +490,53 @@ impl MemoryRouter {
             writeln!(file, "# {}", tags.join(" "))?;
         }
         writeln!(file, "{}\n", data)?;
+        drop(file);
 
-        Ok(())
+        let dir = self.memory_dir(is_global);
+        let mut index = UsageIndex::load(dir);
+        index.record(category, data, chrono::Utc::now(), ttl);
+        index.save(dir)?;
+        self.enforce_retention(is_global)
+    }
+
+    /// Drop expired memories and evict the least recently used ones over the limits
+    pub fn enforce_retention(&self, is_global: bool) -> io::Result<RetentionSummary> {
+        retention::enforce(
+            self.memory_dir(is_global),
+            &self.retention,
+            chrono::Utc::now(),
+        )
+    }
+
+    /// Record that the memories of a category were retrieved, so they are evicted last
+    fn touch_category(&self, category: &str, is_global: bool) -> io::Result<()> {
+        let path = self.get_memory_file(category, is_global);
+        if !path.exists() {
+            return Ok(());
+        }
+        let dir = self.memory_dir(is_global);
+        let mut index = UsageIndex::load(dir);
+        index.touch(
+            category,
+            &transfer::read_entries(&path)?,
+            chrono::Utc::now(),
+        );
+        index.save(dir)
+    }
+
+    /// Merge near-duplicate memories, returning how many were merged into others
+    pub fn compact(
+        &self,
+        category: Option<&str>,
+        is_global: bool,
+        threshold: f64,
+    ) -> io::Result<usize> {
+        retention::compact(
+            self.memory_dir(is_global),
+            category,
+            threshold,
+            chrono::Utc::now(),
+        )
     }
 
     pub fn retrieve(
@@ -450,6 +624,79 @@ impl MemoryRouter {
         Ok(())
     }
 
+    fn memory_dir(&self, is_global: bool) -> &Path {
+        if is_global {
+            &self.global_memory_dir
+        } else {
+            &self.local_memory_dir
+        }
+    }
+
+    /// The whole memory store, global and local
+    pub fn export(&self) -> io::Result<MemoryExport> {
+        Ok(MemoryExport {
+            version: transfer::EXPORT_VERSION,
+            exported_at: chrono::Utc::now(),
+            global: transfer::export_dir(self.memory_dir(true))?,
+            local: transfer::export_dir(self.memory_dir(false))?,
+        })
+    }
+
+    pub fn export_to_file(&self, path: &Path) -> io::Result<()> {
+        transfer::write_export(path, &self.export()?)
+    }
+
+    pub fn import(
+        &self,
+        export: MemoryExport,
+        strategy: ConflictStrategy,
+    ) -> io::Result<ImportSummary> {
+        let global = transfer::import_into_dir(self.memory_dir(true), export.global, strategy)?;
+        let local = transfer::import_into_dir(self.memory_dir(false), export.local, strategy)?;
+        Ok(ImportSummary {
+            added: global.added + local.added,
+            updated: global.updated + local.updated,
+            skipped: global.skipped + local.skipped,
+        })
+    }
+
+    pub fn import_from_file(
+        &self,
+        path: &Path,
+        strategy: ConflictStrategy,
+    ) -> io::Result<ImportSummary> {
+        self.import(transfer::read_export(path)?, strategy)
+    }
+
+    /// Merge the memories in a git repository into the store, then commit the merged store
+    /// back to it and push
+    pub async fn sync(&self, repo: &Path, remote: Option<&str>) -> io::Result<String> {
+        transfer::prepare_sync_repo(repo, remote).await?;
+        let has_remote = transfer::pull_sync_repo(repo).await?;
+
+        let sync_file = repo.join(transfer::SYNC_FILE);
+        let summary = if sync_file.exists() {
+            self.import_from_file(&sync_file, ConflictStrategy::Merge)?
+        } else {
+            ImportSummary::default()
+        };
+        self.export_to_file(&sync_file)?;
+        let changed = transfer::push_sync_repo(repo, has_remote).await?;
+
+        Ok(format!(
+            "Synced memories with {}: {}. {}",
+            repo.display(),
+            summary,
+            match (changed, has_remote) {
+                (false, _) => "The repository was already up to date.",
+                (true, true) => "Committed and pushed the merged memories.",
+                (true, false) => {
+                    "Committed the merged memories, the repository has no remote to push to."
+                }
+            }
+        ))
+    }
+
     async fn execute_tool_call(&self, tool_call: ToolCall) -> Result<String, io::Error> {
         match tool_call.name.as_str() {
             "remember_memory" => {
@@ -460,16 +707,52 @@ impl MemoryRouter {
                         "Data must exist when remembering a memory",
                     )
                 })?;
-                self.remember("context", args.category, data, &args.tags, args.is_global)?;
-                Ok(format!("Stored memory in category: {}", args.category))
+                let ttl = match tool_call
+                    .arguments
+                    .get("ttl_hours")
+                    .and_then(|t| t.as_f64())
+                {
+                    Some(hours) if hours > 0.0 => {
+                        Some(chrono::Duration::seconds((hours * 3600.0) as i64))
+                    }
+                    _ if tool_call.arguments.get("ephemeral") == Some(&Value::Bool(true)) => {
+                        Some(self.retention.ephemeral_ttl)
+                    }
+                    _ => None,
+                };
+                let summary = self.remember(
+                    "context",
+                    args.category,
+                    data,
+                    &args.tags,
+                    args.is_global,
+                    ttl,
+                )?;
+                if summary.is_empty() {
+                    Ok(format!("Stored memory in category: {}", args.category))
+                } else {
+                    Ok(format!(
+                        "Stored memory in category: {}, and {}",
+                        args.category, summary
+                    ))
+                }
             }
             "retrieve_memories" => {
                 let args = MemoryArgs::from_value(&tool_call.arguments)?;
+                self.enforce_retention(args.is_global)?;
                 let memories = if args.category == "*" {
                     self.retrieve_all(args.is_global)?
                 } else {
                     self.retrieve(args.category, args.is_global)?
                 };
+                let categories: Vec<String> = if args.category == "*" {
+                    memories.keys().cloned().collect()
+                } else {
+                    vec![args.category.to_string()]
+                };
+                for category in categories {
+                    self.touch_category(&category, args.is_global)?;
+                }
                 Ok(format!("Retrieved memories: {:?}", memories))
             }
             "remove_memory_category" => {
@@ -494,11 +777,85 @@ impl MemoryRouter {
                     args.category
                 ))
             }
+            "export_memories" => {
+                let path = path_arg(&tool_call.arguments, "path")?;
+                self.export_to_file(&path)?;
+                Ok(format!("Exported memories to {}", path.display()))
+            }
+            "import_memories" => {
+                let path = path_arg(&tool_call.arguments, "path")?;
+                let strategy = match tool_call.arguments.get("strategy").and_then(|s| s.as_str()) {
+                    Some(strategy) => strategy.parse()?,
+                    None => ConflictStrategy::default(),
+                };
+                let summary = self.import_from_file(&path, strategy)?;
+                Ok(format!(
+                    "Imported memories from {}: {}",
+                    path.display(),
+                    summary
+                ))
+            }
+            "compact_memories" => {
+                let is_global = tool_call.arguments.get("is_global") == Some(&Value::Bool(true));
+                let category = tool_call
+                    .arguments
+                    .get("category")
+                    .and_then(|c| c.as_str())
+                    .filter(|c| !c.is_empty() && *c != "*");
+                let threshold = tool_call
+                    .arguments
+                    .get("threshold")
+                    .and_then(|t| t.as_f64())
+                    .unwrap_or(retention::DEFAULT_SIMILARITY);
+                if !(0.0..=1.0).contains(&threshold) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "threshold must be between 0 and 1",
+                    ));
+                }
+                let merged = self.compact(category, is_global, threshold)?;
+                Ok(format!(
+                    "Merged {} near-duplicate {} memories",
+                    merged,
+                    if is_global { "global" } else { "local" }
+                ))
+            }
+            "sync_memories" => {
+                let repo = match tool_call.arguments.get("repo") {
+                    Some(_) => path_arg(&tool_call.arguments, "repo")?,
+                    None => std::env::var(transfer::SYNC_REPO_ENV)
+                        .map(PathBuf::from)
+                        .map_err(|_| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                format!(
+                                    "No repository given and {} is not set",
+                                    transfer::SYNC_REPO_ENV
+                                ),
+                            )
+                        })?,
+                };
+                let remote = tool_call.arguments.get("remote").and_then(|r| r.as_str());
+                self.sync(&repo, remote).await
+            }
             _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "Unknown tool")),
         }
     }
 }
 
+fn path_arg(args: &Value, name: &str) -> io::Result<PathBuf> {
+    args.get(name)
+        .and_then(|p| p.as_str())
+        .filter(|p| !p.is_empty())
+        .map(|p| PathBuf::from(shellexpand::tilde(p).into_owned()))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} must be a non-empty string", name),
+            )
+        })
+}
+
 #[async_trait]
 impl Router for MemoryRouter {
     fn name(&self) -> String {
@@ -0,0 +1,499 @@
+// Keeping the memory store from growing without bound. When each memory was stored and last
+// retrieved is tracked in an index next to the category files, expired ephemeral memories are
+// dropped, and the least recently used ones are evicted once a category or the whole store is
+// over its budget.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::Path,
+};
+
+use super::transfer::{export_dir, write_entries, MemoryEntry};
+
+/// Env var capping the memories kept per category, 0 for no limit
+pub const MAX_ENTRIES_ENV: &str = "GOOSE_MEMORY_MAX_ENTRIES";
+/// Env var capping the total size in bytes of the global or of the local memories, 0 for no limit
+pub const MAX_BYTES_ENV: &str = "GOOSE_MEMORY_MAX_BYTES";
+/// Env var with how many hours ephemeral memories are kept unless a ttl is given
+pub const EPHEMERAL_TTL_ENV: &str = "GOOSE_MEMORY_EPHEMERAL_TTL_HOURS";
+/// Index of when each memory was stored and last used, kept in each memory directory
+pub const USAGE_FILE: &str = ".usage.json";
+/// Share of words two memories need in common to be merged by compaction
+pub const DEFAULT_SIMILARITY: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_entries_per_category: Option<usize>,
+    pub max_total_bytes: Option<usize>,
+    pub ephemeral_ttl: Duration,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_entries_per_category: Some(200),
+            max_total_bytes: Some(64 * 1024),
+            ephemeral_ttl: Duration::hours(24),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let limit = |name: &str, default: Option<usize>| match std::env::var(name)
+            .ok()
+            .and_then(|value| value.trim().parse::<usize>().ok())
+        {
+            Some(0) => None,
+            Some(limit) => Some(limit),
+            None => default,
+        };
+        Self {
+            max_entries_per_category: limit(MAX_ENTRIES_ENV, default.max_entries_per_category),
+            max_total_bytes: limit(MAX_BYTES_ENV, default.max_total_bytes),
+            ephemeral_ttl: std::env::var(EPHEMERAL_TTL_ENV)
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .filter(|hours| *hours > 0)
+                .map(Duration::hours)
+                .unwrap_or(default.ephemeral_ttl),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryUsage {
+    pub created: DateTime<Utc>,
+    pub last_used: DateTime<Utc>,
+    /// When an ephemeral memory is dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl EntryUsage {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            created: now,
+            last_used: now,
+            expires_at: None,
+        }
+    }
+
+    /// The usage of two memories merged into one, which only expires if both did
+    fn merge(self, other: Self) -> Self {
+        Self {
+            created: self.created.min(other.created),
+            last_used: self.last_used.max(other.last_used),
+            expires_at: match (self.expires_at, other.expires_at) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Identifies a memory by its content, so the category files keep their format
+pub fn entry_key(data: &str) -> String {
+    Sha256::digest(data.trim().as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Usage of the memories in one directory, by category and entry key
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UsageIndex {
+    #[serde(default)]
+    categories: BTreeMap<String, BTreeMap<String, EntryUsage>>,
+}
+
+impl UsageIndex {
+    /// The index of a directory, empty when it has none yet or it can't be read
+    pub fn load(dir: &Path) -> Self {
+        fs::read_to_string(dir.join(USAGE_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        fs::write(dir.join(USAGE_FILE), serde_json::to_string_pretty(self)?)
+    }
+
+    /// Usage of a memory, memories stored before tracking started count as used now
+    pub fn usage(&mut self, category: &str, data: &str, now: DateTime<Utc>) -> EntryUsage {
+        *self
+            .categories
+            .entry(category.to_string())
+            .or_default()
+            .entry(entry_key(data))
+            .or_insert_with(|| EntryUsage::new(now))
+    }
+
+    /// Record that a memory was stored, expiring after `ttl` for an ephemeral one. Storing a
+    /// memory again without a ttl makes it permanent.
+    pub fn record(
+        &mut self,
+        category: &str,
+        data: &str,
+        now: DateTime<Utc>,
+        ttl: Option<Duration>,
+    ) {
+        let usage = self
+            .categories
+            .entry(category.to_string())
+            .or_default()
+            .entry(entry_key(data))
+            .or_insert_with(|| EntryUsage::new(now));
+        usage.last_used = now;
+        usage.expires_at = ttl.map(|ttl| now + ttl);
+    }
+
+    /// Record that memories were retrieved
+    pub fn touch(&mut self, category: &str, entries: &[MemoryEntry], now: DateTime<Utc>) {
+        for entry in entries {
+            let usage = self
+                .categories
+                .entry(category.to_string())
+                .or_default()
+                .entry(entry_key(&entry.data))
+                .or_insert_with(|| EntryUsage::new(now));
+            usage.last_used = now;
+        }
+    }
+
+    fn set(&mut self, category: &str, data: &str, usage: EntryUsage) {
+        self.categories
+            .entry(category.to_string())
+            .or_default()
+            .insert(entry_key(data), usage);
+    }
+
+    /// Forget the usage of memories that are no longer stored
+    fn prune(&mut self, categories: &BTreeMap<String, Vec<MemoryEntry>>) {
+        self.categories.retain(|category, usages| {
+            let Some(entries) = categories.get(category) else {
+                return false;
+            };
+            let keys: HashSet<String> = entries.iter().map(|e| entry_key(&e.data)).collect();
+            usages.retain(|key, _| keys.contains(key));
+            !usages.is_empty()
+        });
+    }
+}
+
+/// What enforcing the retention policy removed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionSummary {
+    pub expired: usize,
+    pub evicted: usize,
+}
+
+impl RetentionSummary {
+    pub fn is_empty(&self) -> bool {
+        self.expired == 0 && self.evicted == 0
+    }
+}
+
+impl std::fmt::Display for RetentionSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "removed {} expired and {} least recently used memories to stay within the retention limits",
+            self.expired, self.evicted
+        )
+    }
+}
+
+fn entry_size(entry: &MemoryEntry) -> usize {
+    entry.data.len() + entry.tags.iter().map(|tag| tag.len() + 1).sum::<usize>()
+}
+
+fn remove_positions(entries: &mut Vec<MemoryEntry>, positions: &HashSet<usize>) {
+    let mut position = 0;
+    entries.retain(|_| {
+        let keep = !positions.contains(&position);
+        position += 1;
+        keep
+    });
+}
+
+/// Write back the changed categories, removing the files of emptied ones
+fn write_categories(
+    dir: &Path,
+    categories: &BTreeMap<String, Vec<MemoryEntry>>,
+    changed: &HashSet<String>,
+) -> io::Result<()> {
+    for category in changed {
+        let path = dir.join(format!("{}.txt", category));
+        match categories.get(category) {
+            Some(entries) if !entries.is_empty() => write_entries(&path, entries)?,
+            _ if path.exists() => fs::remove_file(&path)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Drop the expired memories of a directory, then evict the least recently used ones from
+/// categories over their limit and from the directory while it is over its size budget
+pub fn enforce(
+    dir: &Path,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> io::Result<RetentionSummary> {
+    let mut summary = RetentionSummary::default();
+    if !dir.exists() {
+        return Ok(summary);
+    }
+    let mut categories = export_dir(dir)?;
+    let mut index = UsageIndex::load(dir);
+    let mut changed = HashSet::new();
+
+    for (category, entries) in categories.iter_mut() {
+        let before = entries.len();
+        entries.retain(|entry| {
+            index
+                .usage(category, &entry.data, now)
+                .expires_at
+                .is_none_or(|expires_at| expires_at > now)
+        });
+        if entries.len() != before {
+            summary.expired += before - entries.len();
+            changed.insert(category.clone());
+        }
+    }
+
+    if let Some(max) = policy.max_entries_per_category {
+        for (category, entries) in categories.iter_mut() {
+            if entries.len() <= max {
+                continue;
+            }
+            // Stable, so of memories used at the same time the ones stored first go first
+            let mut order: Vec<usize> = (0..entries.len()).collect();
+            order.sort_by_key(|&position| {
+                index
+                    .usage(category, &entries[position].data, now)
+                    .last_used
+            });
+            let evict: HashSet<usize> = order.into_iter().take(entries.len() - max).collect();
+            summary.evicted += evict.len();
+            remove_positions(entries, &evict);
+            changed.insert(category.clone());
+        }
+    }
+
+    if let Some(max_bytes) = policy.max_total_bytes {
+        let mut total: usize = categories.values().flatten().map(entry_size).sum();
+        if total > max_bytes {
+            let mut candidates: Vec<(DateTime<Utc>, String, usize)> = Vec::new();
+            for (category, entries) in &categories {
+                for (position, entry) in entries.iter().enumerate() {
+                    let last_used = index.usage(category, &entry.data, now).last_used;
+                    candidates.push((last_used, category.clone(), position));
+                }
+            }
+            candidates.sort_by_key(|(last_used, _, _)| *last_used);
+
+            let mut evict: BTreeMap<String, HashSet<usize>> = BTreeMap::new();
+            for (_, category, position) in candidates {
+                if total <= max_bytes {
+                    break;
+                }
+                total -= entry_size(&categories[&category][position]);
+                evict.entry(category).or_default().insert(position);
+            }
+            for (category, positions) in evict {
+                summary.evicted += positions.len();
+                if let Some(entries) = categories.get_mut(&category) {
+                    remove_positions(entries, &positions);
+                }
+                changed.insert(category);
+            }
+        }
+    }
+
+    write_categories(dir, &categories, &changed)?;
+    index.prune(&categories);
+    index.save(dir)?;
+    Ok(summary)
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Share of words two memories have in common, from 0 to 1
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (words(a), words(b));
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+}
+
+/// Merge the memories of a category, or of every category, whose words overlap at least
+/// `threshold`. The longer text is kept along with the tags of both. Returns how many
+/// memories were merged into others.
+pub fn compact(
+    dir: &Path,
+    category: Option<&str>,
+    threshold: f64,
+    now: DateTime<Utc>,
+) -> io::Result<usize> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut categories = export_dir(dir)?;
+    let mut index = UsageIndex::load(dir);
+    let mut changed = HashSet::new();
+    let mut merged = 0;
+
+    for (name, entries) in categories.iter_mut() {
+        if category.is_some_and(|category| category != name) {
+            continue;
+        }
+        let mut kept: Vec<MemoryEntry> = Vec::new();
+        for entry in entries.drain(..) {
+            let usage = index.usage(name, &entry.data, now);
+            let Some(existing) = kept
+                .iter_mut()
+                .find(|existing| similarity(&existing.data, &entry.data) >= threshold)
+            else {
+                kept.push(entry);
+                continue;
+            };
+            let usage = usage.merge(index.usage(name, &existing.data, now));
+            if entry.data.len() > existing.data.len() {
+                existing.data = entry.data;
+            }
+            for tag in entry.tags {
+                if !existing.tags.contains(&tag) {
+                    existing.tags.push(tag);
+                }
+            }
+            index.set(name, &existing.data, usage);
+            merged += 1;
+            changed.insert(name.clone());
+        }
+        *entries = kept;
+    }
+
+    write_categories(dir, &categories, &changed)?;
+    index.prune(&categories);
+    index.save(dir)?;
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::transfer::read_entries;
+    use tempfile::tempdir;
+
+    fn entry(tags: &[&str], data: &str) -> MemoryEntry {
+        MemoryEntry {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_expiry_and_lru_eviction() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("development.txt");
+        let start = Utc::now();
+        let entries = [
+            entry(&[], "Uses black for formatting"),
+            entry(&[], "Deploys on fridays"),
+            entry(&[], "Currently debugging the login flow"),
+        ];
+        write_entries(&path, &entries)?;
+
+        let mut index = UsageIndex::default();
+        index.record("development", &entries[0].data, start, None);
+        index.record("development", &entries[1].data, start, None);
+        index.record(
+            "development",
+            &entries[2].data,
+            start,
+            Some(Duration::hours(1)),
+        );
+        // The oldest memory was retrieved since, so the second is the least recently used
+        index.touch("development", &entries[..1], start + Duration::minutes(5));
+        index.save(dir.path())?;
+
+        let policy = RetentionPolicy {
+            max_entries_per_category: Some(1),
+            max_total_bytes: None,
+            ephemeral_ttl: Duration::hours(1),
+        };
+        let summary = enforce(dir.path(), &policy, start + Duration::hours(2))?;
+        assert_eq!(
+            summary,
+            RetentionSummary {
+                expired: 1,
+                evicted: 1
+            }
+        );
+        assert_eq!(read_entries(&path)?, vec![entries[0].clone()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_budget_across_categories() -> io::Result<()> {
+        let dir = tempdir()?;
+        write_entries(&dir.path().join("a.txt"), &[entry(&[], "first memory")])?;
+        write_entries(&dir.path().join("b.txt"), &[entry(&[], "second memory")])?;
+        let now = Utc::now();
+        let mut index = UsageIndex::default();
+        index.record("a", "first memory", now - Duration::days(1), None);
+        index.record("b", "second memory", now, None);
+        index.save(dir.path())?;
+
+        let policy = RetentionPolicy {
+            max_entries_per_category: None,
+            max_total_bytes: Some(20),
+            ephemeral_ttl: Duration::hours(1),
+        };
+        assert_eq!(enforce(dir.path(), &policy, now)?.evicted, 1);
+        // The emptied category is removed
+        assert!(!dir.path().join("a.txt").exists());
+        assert_eq!(export_dir(dir.path())?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_merges_near_duplicates() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("preferences.txt");
+        write_entries(
+            &path,
+            &[
+                entry(&["editor"], "Prefers vim for editing"),
+                entry(&["tools"], "Prefers vim for editing."),
+                entry(&[], "Works in UTC"),
+            ],
+        )?;
+
+        assert!(similarity("Prefers vim", "prefers VIM!") > 0.99);
+        assert_eq!(
+            compact(dir.path(), None, DEFAULT_SIMILARITY, Utc::now())?,
+            1
+        );
+        let entries = read_entries(&path)?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].data, "Prefers vim for editing.");
+        assert_eq!(entries[0].tags, vec!["editor", "tools"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,370 @@
+// Moving the memory store between machines: export to and import from a single JSON file,
+// and syncing that file through a git repository.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+};
+use tokio::process::Command;
+
+/// Version of the export format, bumped on incompatible changes
+pub const EXPORT_VERSION: u32 = 1;
+/// Name of the export file kept in a sync repository
+pub const SYNC_FILE: &str = "goose-memories.json";
+/// Env var holding the path of the git repository used to sync memories
+pub const SYNC_REPO_ENV: &str = "GOOSE_MEMORY_SYNC_REPO";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub data: String,
+}
+
+/// The whole memory store, by scope and category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryExport {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    #[serde(default)]
+    pub global: BTreeMap<String, Vec<MemoryEntry>>,
+    #[serde(default)]
+    pub local: BTreeMap<String, Vec<MemoryEntry>>,
+}
+
+/// How an imported category is combined with one that already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// Add memories that are not stored yet, merging the tags of ones that are
+    #[default]
+    Merge,
+    /// Replace the category with the imported memories
+    Overwrite,
+    /// Keep the category as it is
+    Skip,
+}
+
+impl FromStr for ConflictStrategy {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "merge" => Ok(Self::Merge),
+            "overwrite" => Ok(Self::Overwrite),
+            "skip" => Ok(Self::Skip),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown conflict strategy '{}', expected merge, overwrite or skip",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+impl std::fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} added, {} updated, {} unchanged or skipped",
+            self.added, self.updated, self.skipped
+        )
+    }
+}
+
+/// Parse a category file, entries are separated by a blank line and may start with a
+/// `# tag tag` line
+pub fn read_entries(path: &Path) -> io::Result<Vec<MemoryEntry>> {
+    let content = fs::read_to_string(path)?;
+    let entries = content
+        .split("\n\n")
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let mut lines = entry.lines();
+            let first = lines.next().unwrap_or_default();
+            match first.strip_prefix('#') {
+                Some(tags) => MemoryEntry {
+                    tags: tags.split_whitespace().map(String::from).collect(),
+                    data: lines.collect::<Vec<_>>().join("\n"),
+                },
+                None => MemoryEntry {
+                    tags: Vec::new(),
+                    data: entry.to_string(),
+                },
+            }
+        })
+        .collect();
+    Ok(entries)
+}
+
+pub fn write_entries(path: &Path, entries: &[MemoryEntry]) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    for entry in entries {
+        if !entry.tags.is_empty() {
+            writeln!(file, "# {}", entry.tags.join(" "))?;
+        }
+        writeln!(file, "{}\n", entry.data)?;
+    }
+    Ok(())
+}
+
+/// All categories stored in a memory directory
+pub fn export_dir(dir: &Path) -> io::Result<BTreeMap<String, Vec<MemoryEntry>>> {
+    let mut categories = BTreeMap::new();
+    if !dir.exists() {
+        return Ok(categories);
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "txt") {
+            if let Some(category) = path.file_stem().and_then(|s| s.to_str()) {
+                categories.insert(category.to_string(), read_entries(&path)?);
+            }
+        }
+    }
+    Ok(categories)
+}
+
+// Categories become file names, so they can't be allowed to point outside the store
+fn validate_category(category: &str) -> io::Result<()> {
+    if category.is_empty()
+        || category.contains(['/', '\\'])
+        || category.starts_with('.')
+        || category.contains("..")
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid memory category '{}'", category),
+        ));
+    }
+    Ok(())
+}
+
+fn merge_entries(
+    existing: &mut Vec<MemoryEntry>,
+    incoming: Vec<MemoryEntry>,
+    summary: &mut ImportSummary,
+) {
+    for entry in incoming {
+        match existing
+            .iter_mut()
+            .find(|e| e.data.trim() == entry.data.trim())
+        {
+            Some(stored) => {
+                let new_tags: Vec<String> = entry
+                    .tags
+                    .into_iter()
+                    .filter(|tag| !stored.tags.contains(tag))
+                    .collect();
+                if new_tags.is_empty() {
+                    summary.skipped += 1;
+                } else {
+                    stored.tags.extend(new_tags);
+                    summary.updated += 1;
+                }
+            }
+            None => {
+                existing.push(entry);
+                summary.added += 1;
+            }
+        }
+    }
+}
+
+/// Import categories into a memory directory
+pub fn import_into_dir(
+    dir: &Path,
+    categories: BTreeMap<String, Vec<MemoryEntry>>,
+    strategy: ConflictStrategy,
+) -> io::Result<ImportSummary> {
+    fs::create_dir_all(dir)?;
+    let mut summary = ImportSummary::default();
+    for (category, incoming) in categories {
+        validate_category(&category)?;
+        let path = dir.join(format!("{}.txt", category));
+        let mut entries = if path.exists() {
+            read_entries(&path)?
+        } else {
+            Vec::new()
+        };
+
+        match strategy {
+            ConflictStrategy::Skip if !entries.is_empty() => {
+                summary.skipped += incoming.len();
+                continue;
+            }
+            ConflictStrategy::Overwrite => {
+                summary.updated += entries.len().min(incoming.len());
+                summary.added += incoming.len().saturating_sub(entries.len());
+                entries = incoming;
+            }
+            _ => merge_entries(&mut entries, incoming, &mut summary),
+        }
+        write_entries(&path, &entries)?;
+    }
+    Ok(summary)
+}
+
+pub fn read_export(path: &Path) -> io::Result<MemoryExport> {
+    let export: MemoryExport = serde_json::from_str(&fs::read_to_string(path)?)?;
+    if export.version > EXPORT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} was exported by a newer version of goose (format {})",
+                path.display(),
+                export.version
+            ),
+        ));
+    }
+    Ok(export)
+}
+
+pub fn write_export(path: &Path, export: &MemoryExport) -> io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(export)?)
+}
+
+async fn git(repo: &Path, args: &[&str]) -> io::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Make sure `repo` is a git repository, cloning `remote` into it when it does not exist yet
+pub async fn prepare_sync_repo(repo: &Path, remote: Option<&str>) -> io::Result<()> {
+    if repo.join(".git").exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(repo)?;
+    match remote {
+        Some(remote) => git(repo, &["clone", remote, "."]).await.map(|_| ()),
+        None => git(repo, &["init"]).await.map(|_| ()),
+    }
+}
+
+/// Pull the latest export from the repository's remote, if it has one with any branches
+pub async fn pull_sync_repo(repo: &Path) -> io::Result<bool> {
+    let has_remote = !git(repo, &["remote"]).await?.is_empty();
+    if !has_remote
+        || git(repo, &["ls-remote", "--heads", "origin"])
+            .await?
+            .is_empty()
+    {
+        return Ok(has_remote);
+    }
+    git(repo, &["pull", "--rebase", "origin", "HEAD"]).await?;
+    Ok(true)
+}
+
+/// Commit the export file and push it when there is a remote, returns whether anything changed
+pub async fn push_sync_repo(repo: &Path, has_remote: bool) -> io::Result<bool> {
+    git(repo, &["add", SYNC_FILE]).await?;
+    if git(repo, &["status", "--porcelain", SYNC_FILE])
+        .await?
+        .is_empty()
+    {
+        return Ok(false);
+    }
+    git(repo, &["commit", "-m", "Update goose memories"]).await?;
+    if has_remote {
+        git(repo, &["push", "origin", "HEAD"]).await?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn entry(tags: &[&str], data: &str) -> MemoryEntry {
+        MemoryEntry {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_export_import_round_trip() -> io::Result<()> {
+        let source = tempdir()?;
+        let stored = vec![
+            entry(&["editor"], "Prefers vim"),
+            entry(&[], "Uses\nmultiple lines"),
+        ];
+        write_entries(&source.path().join("preferences.txt"), &stored)?;
+
+        let exported = export_dir(source.path())?;
+        assert_eq!(exported["preferences"], stored);
+
+        let target = tempdir()?;
+        write_entries(
+            &target.path().join("preferences.txt"),
+            &[entry(&["tools"], "Prefers vim"), entry(&[], "Works late")],
+        )?;
+        let summary = import_into_dir(target.path(), exported.clone(), ConflictStrategy::Merge)?;
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 1,
+                updated: 1,
+                skipped: 0
+            }
+        );
+        let merged = read_entries(&target.path().join("preferences.txt"))?;
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].tags, vec!["tools", "editor"]);
+
+        // Importing again changes nothing
+        let summary = import_into_dir(target.path(), exported.clone(), ConflictStrategy::Merge)?;
+        assert_eq!(summary.skipped, 2);
+
+        let summary = import_into_dir(target.path(), exported.clone(), ConflictStrategy::Skip)?;
+        assert_eq!(summary.skipped, 2);
+        assert_eq!(
+            read_entries(&target.path().join("preferences.txt"))?.len(),
+            3
+        );
+
+        import_into_dir(target.path(), exported, ConflictStrategy::Overwrite)?;
+        assert_eq!(
+            read_entries(&target.path().join("preferences.txt"))?,
+            stored
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_rejects_unsafe_categories() {
+        let target = tempdir().unwrap();
+        let categories = BTreeMap::from([("../escape".to_string(), vec![entry(&[], "x")])]);
+        assert!(import_into_dir(target.path(), categories, ConflictStrategy::Merge).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+"""Relays requests from the notebook extension to a Jupyter kernel.
+
+Started with a JSON object of options as its only argument, it starts a kernel or attaches to a
+running one and then answers one JSON request per line on stdin with one JSON reply per line on
+stdout. Outputs are returned in the nbformat shape, so they can be stored in notebooks as they are.
+"""
+
+import json
+import os
+import queue
+import sys
+import time
+
+
+# Replies get their own copy of stdout, anything the kernel or a library prints goes to stderr
+protocol = os.fdopen(os.dup(1), "w")
+os.dup2(2, 1)
+
+
+def reply(**fields):
+    protocol.write(json.dumps(fields) + "\n")
+    protocol.flush()
+
+
+def execute(client, manager, code, timeout):
+    msg_id = client.execute(code, store_history=True, allow_stdin=False)
+    deadline = time.monotonic() + timeout if timeout else None
+    outputs = []
+    status = "ok"
+    execution_count = None
+    while True:
+        remaining = None if deadline is None else max(0.0, deadline - time.monotonic())
+        try:
+            msg = client.get_iopub_msg(timeout=remaining)
+        except queue.Empty:
+            status = "timeout"
+            # Leave the kernel ready for the next request rather than busy with this one
+            if manager is not None:
+                manager.interrupt_kernel()
+            break
+        if msg.get("parent_header", {}).get("msg_id") != msg_id:
+            continue
+        kind = msg["msg_type"]
+        content = msg["content"]
+        if kind == "status" and content.get("execution_state") == "idle":
+            break
+        if kind == "execute_input":
+            execution_count = content.get("execution_count")
+        elif kind == "stream":
+            outputs.append({"output_type": "stream", "name": content["name"], "text": content["text"]})
+        elif kind in ("execute_result", "display_data"):
+            output = {
+                "output_type": kind,
+                "data": content.get("data", {}),
+                "metadata": content.get("metadata", {}),
+            }
+            if kind == "execute_result":
+                output["execution_count"] = content.get("execution_count")
+            outputs.append(output)
+        elif kind == "error":
+            status = "error"
+            outputs.append(
+                {
+                    "output_type": "error",
+                    "ename": content.get("ename", ""),
+                    "evalue": content.get("evalue", ""),
+                    "traceback": content.get("traceback", []),
+                }
+            )
+        elif kind == "clear_output":
+            outputs = []
+    return {"status": status, "execution_count": execution_count, "outputs": outputs}
+
+
+def main():
+    options = json.loads(sys.argv[1])
+    try:
+        from jupyter_client import BlockingKernelClient, KernelManager
+    except ImportError:
+        reply(ok=False, error="jupyter_client is not installed, install it with: pip install jupyter_client ipykernel")
+        return
+
+    manager = None
+    try:
+        if options.get("connection_file"):
+            client = BlockingKernelClient(connection_file=options["connection_file"])
+            client.load_connection_file()
+        else:
+            manager = KernelManager(kernel_name=options.get("kernel_name") or "python3")
+            manager.start_kernel(cwd=options.get("cwd"))
+            client = manager.client()
+        client.start_channels()
+        client.wait_for_ready(timeout=options.get("startup_timeout", 60))
+    except Exception as e:
+        if manager is not None and manager.has_kernel:
+            manager.shutdown_kernel(now=True)
+        reply(ok=False, error=f"Failed to start the kernel: {e}")
+        return
+
+    reply(ok=True, kernel_name=manager.kernel_name if manager else None, owned=manager is not None)
+    shutdown = False
+    try:
+        for line in sys.stdin:
+            request = json.loads(line)
+            op = request.get("op")
+            try:
+                if op == "execute":
+                    reply(ok=True, **execute(client, manager, request["code"], request.get("timeout")))
+                elif op == "restart" and manager is None:
+                    reply(ok=False, error="Can't restart a kernel goose attached to, only one it started")
+                elif op == "restart":
+                    manager.restart_kernel()
+                    client.wait_for_ready(timeout=60)
+                    reply(ok=True)
+                elif op == "shutdown":
+                    shutdown = True
+                    break
+                else:
+                    reply(ok=False, error=f"Unknown request {op}")
+            except Exception as e:
+                reply(ok=False, error=str(e))
+    finally:
+        client.stop_channels()
+        # Kernels goose attached to belong to someone else and keep running
+        if manager is not None:
+            manager.shutdown_kernel()
+    if shutdown:
+        reply(ok=True)
+
+
+if __name__ == "__main__":
+    main()
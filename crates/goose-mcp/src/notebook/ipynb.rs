@@ -0,0 +1,488 @@
+use std::path::{Path, PathBuf};
+
+use mcp_core::Content;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+/// Text of one output shown to the model, longer output is cut in the middle
+const MAX_OUTPUT_CHARS: usize = 10_000;
+/// Text of the outputs of one cell when reading a notebook
+const MAX_READ_OUTPUT_CHARS: usize = 2_000;
+
+/// A Jupyter notebook in the nbformat 4 layout, edited as JSON so fields goose doesn't know
+/// about are kept as they are
+pub struct Notebook {
+    path: PathBuf,
+    value: Value,
+}
+
+impl Notebook {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let value: Value = serde_json::from_str(&text)
+            .map_err(|e| format!("{} is not a notebook: {}", path.display(), e))?;
+        if value.get("nbformat").and_then(Value::as_i64) != Some(4) {
+            return Err(format!(
+                "{} isn't in the nbformat 4 layout, convert it with jupyter nbconvert --to notebook",
+                path.display()
+            ));
+        }
+        if !value.get("cells").is_some_and(Value::is_array) {
+            return Err(format!("{} has no list of cells", path.display()));
+        }
+        Ok(Notebook {
+            path: path.to_path_buf(),
+            value,
+        })
+    }
+
+    /// An empty notebook for `kernel_name`, not written until saved
+    pub fn create(path: &Path, kernel_name: &str) -> Self {
+        let language = if kernel_name.starts_with("python") {
+            "python"
+        } else {
+            kernel_name
+        };
+        Notebook {
+            path: path.to_path_buf(),
+            value: json!({
+                "cells": [],
+                "metadata": {
+                    "kernelspec": {
+                        "display_name": kernel_name,
+                        "language": language,
+                        "name": kernel_name
+                    },
+                    "language_info": {"name": language}
+                },
+                "nbformat": 4,
+                "nbformat_minor": 5
+            }),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Written the way Jupyter writes notebooks, so saving doesn't reformat the whole file
+    pub fn save(&self) -> Result<(), String> {
+        let mut out = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+        let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+        self.value
+            .serialize(&mut serializer)
+            .map_err(|e| e.to_string())?;
+        out.push(b'\n');
+        std::fs::write(&self.path, out)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    pub fn kernel_name(&self) -> Option<&str> {
+        self.value
+            .pointer("/metadata/kernelspec/name")
+            .and_then(Value::as_str)
+    }
+
+    fn cells(&self) -> &Vec<Value> {
+        self.value["cells"].as_array().expect("checked on open")
+    }
+
+    fn cells_mut(&mut self) -> &mut Vec<Value> {
+        self.value["cells"].as_array_mut().expect("checked on open")
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.cells().len()
+    }
+
+    fn cell(&self, index: usize) -> Result<&Value, String> {
+        self.cells().get(index).ok_or_else(|| {
+            format!(
+                "There is no cell {}, the notebook has {} cells",
+                index,
+                self.cell_count()
+            )
+        })
+    }
+
+    pub fn cell_type(&self, index: usize) -> Result<&str, String> {
+        Ok(self
+            .cell(index)?
+            .get("cell_type")
+            .and_then(Value::as_str)
+            .unwrap_or("code"))
+    }
+
+    pub fn source(&self, index: usize) -> Result<String, String> {
+        Ok(multiline(self.cell(index)?.get("source")))
+    }
+
+    /// Cell ids are required from nbformat 4.5 on
+    fn new_id(&self, source: &str) -> Option<String> {
+        let minor = self.value.get("nbformat_minor").and_then(Value::as_i64)?;
+        if minor < 5 {
+            return None;
+        }
+        let taken: Vec<&str> = self
+            .cells()
+            .iter()
+            .filter_map(|cell| cell.get("id").and_then(Value::as_str))
+            .collect();
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+        (0u32..)
+            .map(|attempt| {
+                let digest = Sha256::digest(format!("{}{}{}", nanos, attempt, source));
+                format!("{:x}", digest)[..8].to_string()
+            })
+            .find(|id| !taken.contains(&id.as_str()))
+    }
+
+    /// Add a cell before `index`, or at the end when it is None. Returns where it went.
+    pub fn insert(
+        &mut self,
+        index: Option<usize>,
+        cell_type: &str,
+        source: &str,
+    ) -> Result<usize, String> {
+        let index = index.unwrap_or(self.cell_count());
+        if index > self.cell_count() {
+            return Err(format!(
+                "Can't insert at {}, the notebook has {} cells",
+                index,
+                self.cell_count()
+            ));
+        }
+        let mut cell = Map::new();
+        cell.insert("cell_type".to_string(), json!(cell_type));
+        if let Some(id) = self.new_id(source) {
+            cell.insert("id".to_string(), json!(id));
+        }
+        cell.insert("metadata".to_string(), json!({}));
+        cell.insert("source".to_string(), json!(split_lines(source)));
+        if cell_type == "code" {
+            cell.insert("execution_count".to_string(), Value::Null);
+            cell.insert("outputs".to_string(), json!([]));
+        }
+        self.cells_mut().insert(index, Value::Object(cell));
+        Ok(index)
+    }
+
+    /// Replace the source of a cell, clearing the outputs it no longer matches
+    pub fn replace(
+        &mut self,
+        index: usize,
+        source: &str,
+        cell_type: Option<&str>,
+    ) -> Result<(), String> {
+        self.cell(index)?;
+        let cell = self.cells_mut()[index]
+            .as_object_mut()
+            .ok_or_else(|| format!("Cell {} is not an object", index))?;
+        cell.insert("source".to_string(), json!(split_lines(source)));
+        if let Some(cell_type) = cell_type {
+            cell.insert("cell_type".to_string(), json!(cell_type));
+        }
+        if cell.get("cell_type").and_then(Value::as_str) == Some("code") {
+            cell.insert("execution_count".to_string(), Value::Null);
+            cell.insert("outputs".to_string(), json!([]));
+        } else {
+            cell.remove("execution_count");
+            cell.remove("outputs");
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, index: usize) -> Result<(), String> {
+        self.cell(index)?;
+        self.cells_mut().remove(index);
+        Ok(())
+    }
+
+    pub fn set_outputs(
+        &mut self,
+        index: usize,
+        outputs: &[Value],
+        execution_count: Option<i64>,
+    ) -> Result<(), String> {
+        self.cell(index)?;
+        if let Some(cell) = self.cells_mut()[index].as_object_mut() {
+            cell.insert("outputs".to_string(), json!(outputs));
+            cell.insert("execution_count".to_string(), json!(execution_count));
+        }
+        Ok(())
+    }
+
+    /// The cells from `start` up to `end`, numbered, with a summary of their outputs
+    pub fn render(&self, start: usize, end: usize, outputs: bool) -> String {
+        let mut out = format!("{} ({} cells)\n", self.path.display(), self.cell_count());
+        for (index, cell) in self.cells().iter().enumerate().take(end).skip(start) {
+            let cell_type = cell
+                .get("cell_type")
+                .and_then(Value::as_str)
+                .unwrap_or("code");
+            let count = match cell.get("execution_count").and_then(Value::as_i64) {
+                Some(count) => format!(" [{}]", count),
+                None => String::new(),
+            };
+            out.push_str(&format!("\n## Cell {}: {}{}\n", index, cell_type, count));
+            out.push_str(&multiline(cell.get("source")));
+            out.push('\n');
+
+            let cell_outputs = cell
+                .get("outputs")
+                .and_then(Value::as_array)
+                .filter(|cell_outputs| outputs && !cell_outputs.is_empty());
+            if let Some(cell_outputs) = cell_outputs {
+                out.push_str("### Output\n");
+                out.push_str(&truncate(
+                    &outputs_text(cell_outputs, true),
+                    MAX_READ_OUTPUT_CHARS,
+                ));
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+/// nbformat stores text as a string or a list of lines
+fn multiline(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Lines keeping their line endings, the way Jupyter stores sources
+fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text.to_string();
+    }
+    let head: String = text.chars().take(max_chars / 2).collect();
+    let tail: String = text.chars().skip(count - max_chars / 2).collect();
+    format!(
+        "{}\n[... {} characters cut ...]\n{}",
+        head,
+        count - max_chars,
+        tail
+    )
+}
+
+/// Tracebacks are colored with terminal escape codes
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+const IMAGE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif"];
+
+/// The readable text of one output: markdown or plain text of results, with images and other
+/// rich data named
+fn output_text(output: &Value, name_images: bool) -> String {
+    match output.get("output_type").and_then(Value::as_str) {
+        Some("stream") => multiline(output.get("text")),
+        Some("error") => {
+            let traceback = output
+                .get("traceback")
+                .and_then(Value::as_array)
+                .map(|lines| {
+                    lines
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .unwrap_or_default();
+            if traceback.is_empty() {
+                format!(
+                    "{}: {}",
+                    multiline(output.get("ename")),
+                    multiline(output.get("evalue"))
+                )
+            } else {
+                strip_ansi(&traceback)
+            }
+        }
+        Some("execute_result") | Some("display_data") => {
+            let Some(data) = output.get("data").and_then(Value::as_object) else {
+                return String::new();
+            };
+            let mut parts = Vec::new();
+            if name_images {
+                parts.extend(
+                    IMAGE_TYPES
+                        .iter()
+                        .filter(|mime| data.contains_key(**mime))
+                        .map(|mime| format!("[{}]", mime)),
+                );
+            }
+            // Tables from pandas come as html and plain text, the text is easier to read
+            if let Some(text) = data.get("text/markdown").or_else(|| data.get("text/plain")) {
+                parts.push(multiline(Some(text)));
+            } else if let Some(json) = data.get("application/json") {
+                parts.push(json.to_string());
+            } else if data.contains_key("text/html") {
+                parts.push("[text/html]".to_string());
+            }
+            parts.join("\n")
+        }
+        _ => String::new(),
+    }
+}
+
+fn outputs_text(outputs: &[Value], name_images: bool) -> String {
+    outputs
+        .iter()
+        .map(|output| output_text(output, name_images))
+        .map(|text| text.trim_end_matches('\n').to_string())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// What running a cell returns to the model: the text of every output, and plots and other
+/// images as images
+pub fn output_contents(outputs: &[Value]) -> Vec<Content> {
+    let mut contents = Vec::new();
+    let text = outputs_text(outputs, false);
+    if !text.is_empty() {
+        contents.push(Content::text(truncate(&text, MAX_OUTPUT_CHARS)));
+    }
+    for data in outputs
+        .iter()
+        .filter_map(|output| output.get("data").and_then(Value::as_object))
+    {
+        for mime in IMAGE_TYPES {
+            if let Some(image) = data.get(*mime) {
+                // nbformat allows line breaks in base64
+                let image: String = multiline(Some(image))
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect();
+                contents.push(Content::image(image, *mime));
+            }
+        }
+    }
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_notebook() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("analysis.ipynb");
+        let mut notebook = Notebook::create(&path, "python3");
+        notebook.insert(None, "markdown", "# Sales").unwrap();
+        notebook
+            .insert(
+                None,
+                "code",
+                "import pandas as pd\ndf = pd.read_csv('sales.csv')",
+            )
+            .unwrap();
+        assert_eq!(notebook.insert(Some(1), "code", "x = 1").unwrap(), 1);
+        assert!(notebook.insert(Some(9), "code", "y = 2").is_err());
+        notebook
+            .set_outputs(
+                2,
+                &[json!({"output_type": "stream", "name": "stdout", "text": "loaded\n"})],
+                Some(3),
+            )
+            .unwrap();
+        notebook.save().unwrap();
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.starts_with("{\n \"cells\": ["));
+        let mut notebook = Notebook::open(&path).unwrap();
+        assert_eq!(notebook.cell_count(), 3);
+        assert_eq!(notebook.kernel_name(), Some("python3"));
+        assert_eq!(
+            notebook.source(2).unwrap(),
+            "import pandas as pd\ndf = pd.read_csv('sales.csv')"
+        );
+        let rendered = notebook.render(0, notebook.cell_count(), true);
+        assert!(rendered.contains("## Cell 2: code [3]"));
+        assert!(rendered.contains("loaded"));
+
+        notebook.replace(2, "df = None", None).unwrap();
+        assert!(!notebook.render(0, 3, true).contains("loaded"));
+        notebook.replace(1, "Notes", Some("markdown")).unwrap();
+        assert_eq!(notebook.cell_type(1).unwrap(), "markdown");
+        assert!(notebook.cells()[1].get("outputs").is_none());
+        notebook.delete(0).unwrap();
+        assert_eq!(notebook.source(0).unwrap(), "Notes");
+        assert!(notebook.delete(5).is_err());
+
+        let ids: Vec<_> = notebook
+            .cells()
+            .iter()
+            .filter_map(|cell| cell["id"].as_str())
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn test_output_contents() {
+        let outputs = [
+            json!({"output_type": "stream", "name": "stdout", "text": ["rows: ", "3\n"]}),
+            json!({"output_type": "execute_result", "execution_count": 4, "metadata": {}, "data": {
+                "text/plain": "   a  b\n0  1  2",
+                "text/html": "<table></table>"
+            }}),
+            json!({"output_type": "display_data", "metadata": {}, "data": {
+                "image/png": "iVBORw0\nKGgo=\n",
+                "text/plain": "<Figure size 640x480 with 1 Axes>"
+            }}),
+            json!({"output_type": "error", "ename": "KeyError", "evalue": "'c'", "traceback": [
+                "\u{1b}[0;31mKeyError\u{1b}[0m: 'c'"
+            ]}),
+        ];
+        let contents = output_contents(&outputs);
+        assert_eq!(contents.len(), 2);
+        let text = contents[0].as_text().unwrap();
+        assert!(text.starts_with("rows: 3\n"));
+        assert!(text.contains("0  1  2"));
+        assert!(text.contains("<Figure size"));
+        assert!(text.ends_with("KeyError: 'c'"));
+        assert_eq!(
+            contents[1],
+            Content::image("iVBORw0KGgo=".to_string(), "image/png")
+        );
+
+        assert!(outputs_text(&outputs, true).contains("[image/png]"));
+        assert_eq!(
+            truncate("abcdefgh", 4),
+            "ab\n[... 4 characters cut ...]\ngh"
+        );
+    }
+}
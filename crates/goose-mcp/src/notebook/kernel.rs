@@ -0,0 +1,199 @@
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+/// Python interpreter with jupyter_client installed, `python3` by default
+pub const NOTEBOOK_PYTHON_ENV: &str = "GOOSE_NOTEBOOK_PYTHON";
+
+const BRIDGE: &str = include_str!("bridge.py");
+
+/// Kernels can take a while to import their packages on a cold start
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(90);
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(60);
+/// Extra time given to the bridge over the execution timeout, which it enforces itself
+const REPLY_GRACE: Duration = Duration::from_secs(15);
+
+pub fn python() -> String {
+    std::env::var(NOTEBOOK_PYTHON_ENV)
+        .ok()
+        .filter(|python| !python.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "python".to_string()
+            } else {
+                "python3".to_string()
+            }
+        })
+}
+
+/// Which kernel to talk to
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KernelOptions {
+    /// Kernel spec to start, such as `python3` or `ir`
+    pub kernel_name: Option<String>,
+    /// Connection file of a running kernel to attach to instead of starting one
+    pub connection_file: Option<PathBuf>,
+    /// Directory a started kernel runs in
+    pub cwd: Option<PathBuf>,
+}
+
+/// The outcome of running code, with outputs in the nbformat shape
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct Execution {
+    /// `ok`, `error` or `timeout`
+    pub status: String,
+    pub execution_count: Option<i64>,
+    pub outputs: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    ok: bool,
+    error: Option<String>,
+    #[serde(flatten)]
+    fields: Value,
+}
+
+/// A Jupyter kernel, reached through a Python process running jupyter_client since the kernel
+/// protocol needs ZeroMQ
+pub struct Kernel {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    /// Set when a reply went missing, later replies can't be matched to their requests
+    broken: bool,
+    /// Started by goose rather than attached to, so it can be restarted and is shut down with it
+    pub owned: bool,
+    pub description: String,
+}
+
+impl Kernel {
+    pub async fn start(options: &KernelOptions) -> Result<Self, String> {
+        let python = python();
+        let bridge_options = json!({
+            "kernel_name": options.kernel_name,
+            "connection_file": options.connection_file,
+            "cwd": options.cwd,
+            "startup_timeout": STARTUP_TIMEOUT.as_secs(),
+        });
+        // Once stdin closes, the bridge shuts down a kernel it started
+        let mut child = Command::new(&python)
+            .arg("-c")
+            .arg(BRIDGE)
+            .arg(bridge_options.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", python, e))?;
+        let stdin = child.stdin.take().ok_or("The kernel bridge has no stdin")?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or("The kernel bridge has no stdout")?;
+
+        let mut kernel = Kernel {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            broken: false,
+            owned: false,
+            description: String::new(),
+        };
+        // The startup margin on top of the bridge's own covers starting Python itself
+        let ready = kernel.read_reply(STARTUP_TIMEOUT + REPLY_GRACE).await?;
+        kernel.owned = ready.get("owned").and_then(Value::as_bool).unwrap_or(false);
+        kernel.description = match (&options.connection_file, ready.get("kernel_name")) {
+            (Some(file), _) => format!("the kernel of {}", file.display()),
+            (None, Some(Value::String(name))) => format!("a {} kernel", name),
+            (None, _) => "a kernel".to_string(),
+        };
+        Ok(kernel)
+    }
+
+    /// The kernel can't be used anymore and has to be started again
+    pub fn broken(&self) -> bool {
+        self.broken
+    }
+
+    async fn read_reply(&mut self, timeout: Duration) -> Result<Value, String> {
+        let line = match tokio::time::timeout(timeout, self.stdout.next_line()).await {
+            Ok(Ok(Some(line))) => line,
+            Ok(Ok(None)) => {
+                self.broken = true;
+                return Err("The kernel bridge exited, is jupyter_client installed?".to_string());
+            }
+            Ok(Err(e)) => {
+                self.broken = true;
+                return Err(format!("Failed to read from the kernel: {}", e));
+            }
+            Err(_) => {
+                self.broken = true;
+                return Err(format!(
+                    "The kernel didn't answer within {}s",
+                    timeout.as_secs()
+                ));
+            }
+        };
+        let reply: Reply = serde_json::from_str(&line).map_err(|e| {
+            self.broken = true;
+            format!("Unexpected reply from the kernel bridge: {}", e)
+        })?;
+        if !reply.ok {
+            return Err(reply
+                .error
+                .unwrap_or_else(|| "The kernel request failed".to_string()));
+        }
+        Ok(reply.fields)
+    }
+
+    async fn request(&mut self, request: Value, timeout: Duration) -> Result<Value, String> {
+        let line = format!("{}\n", request);
+        let sent = match self.stdin.write_all(line.as_bytes()).await {
+            Ok(()) => self.stdin.flush().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            self.broken = true;
+            return Err(format!(
+                "Failed to send to the kernel, it may have exited: {}",
+                e
+            ));
+        }
+        self.read_reply(timeout).await
+    }
+
+    pub async fn execute(&mut self, code: &str, timeout: Duration) -> Result<Execution, String> {
+        let reply = self
+            .request(
+                json!({"op": "execute", "code": code, "timeout": timeout.as_secs()}),
+                timeout + REPLY_GRACE,
+            )
+            .await?;
+        serde_json::from_value(reply).map_err(|e| format!("Unexpected execution result: {}", e))
+    }
+
+    /// Start the kernel over, losing every variable
+    pub async fn restart(&mut self) -> Result<(), String> {
+        self.request(json!({"op": "restart"}), CONTROL_TIMEOUT)
+            .await
+            .map(|_| ())
+    }
+
+    /// Stop a kernel goose started, or detach from one it attached to
+    pub async fn shutdown(mut self) -> Result<(), String> {
+        let result = self
+            .request(json!({"op": "shutdown"}), CONTROL_TIMEOUT)
+            .await
+            .map(|_| ());
+        if result.is_err() {
+            let _ = self.child.kill().await;
+        }
+        result
+    }
+}
@@ -0,0 +1,662 @@
+mod ipynb;
+mod kernel;
+
+use indoc::{formatdoc, indoc};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{future::Future, pin::Pin};
+use tokio::sync::{mpsc, Mutex, MutexGuard};
+
+use mcp_core::{
+    handler::{PromptError, ResourceError, ToolError},
+    prompt::Prompt,
+    protocol::{JsonRpcMessage, ServerCapabilities},
+    resource::Resource,
+    tool::{Tool, ToolAnnotations},
+    Content,
+};
+use mcp_server::router::CapabilitiesBuilder;
+use mcp_server::Router;
+
+use ipynb::{output_contents, Notebook};
+use kernel::{python, Execution, Kernel, KernelOptions, NOTEBOOK_PYTHON_ENV};
+
+const DEFAULT_KERNEL: &str = "python3";
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const CELL_TYPES: &[&str] = &["code", "markdown", "raw"];
+
+/// An extension that runs code in a Jupyter kernel and reads and edits notebooks cell by cell,
+/// so analysis can be built up step by step with the variables of earlier steps kept around
+#[derive(Clone)]
+pub struct NotebookRouter {
+    tools: Vec<Tool>,
+    instructions: String,
+    /// One kernel at a time, shared by every notebook
+    kernel: Arc<Mutex<Option<Kernel>>>,
+}
+
+impl Default for NotebookRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotebookRouter {
+    pub fn new() -> Self {
+        let kernel = Tool::new(
+            "kernel",
+            indoc! {r#"
+                Manage the Jupyter kernel code runs in. Code and notebook cells start a python3 kernel on
+                their own, use this to start another kind of kernel, attach to a kernel that is already
+                running through its connection file, or start over with a restart. Only one kernel is
+                used at a time, starting or attaching replaces the current one.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["action"],
+                "properties": {
+                    "action": {"type": "string", "enum": ["start", "restart", "shutdown", "status"]},
+                    "kernel_name": {"type": "string", "description": "Kernel spec to start, such as python3 or ir, python3 by default"},
+                    "connection_file": {"type": "string", "description": "Absolute path of a running kernel's connection file (kernel-*.json) to attach to"},
+                    "cwd": {"type": "string", "description": "Absolute path of the directory a started kernel runs in"}
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Manage Jupyter Kernel".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: false,
+                open_world_hint: false,
+            }),
+        );
+
+        let execute_code = Tool::new(
+            "execute_code",
+            indoc! {r#"
+                Run code in the kernel and return what it printed, its result, tables and plots. Variables
+                stay defined between calls, so load data once and explore it over several calls rather
+                than writing scripts that start from scratch.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["code"],
+                "properties": {
+                    "code": {"type": "string"},
+                    "timeout_secs": {"type": "integer", "description": format!("Interrupt the code after this long, {} by default", DEFAULT_TIMEOUT_SECS)}
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Execute Code".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: false,
+                open_world_hint: true,
+            }),
+        );
+
+        let notebook_read = Tool::new(
+            "notebook_read",
+            indoc! {r#"
+                Read the cells of a .ipynb notebook with their index, type, source and a summary of their
+                outputs.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["path"],
+                "properties": {
+                    "path": {"type": "string", "description": "Absolute path of the notebook"},
+                    "start": {"type": "integer", "description": "First cell to read, 0 by default"},
+                    "end": {"type": "integer", "description": "Cell to stop before, the end by default"},
+                    "outputs": {"type": "boolean", "default": true, "description": "Include the outputs of the cells"}
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Read Notebook".to_string()),
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let notebook_edit = Tool::new(
+            "notebook_edit",
+            indoc! {r#"
+                Change one cell of a .ipynb notebook. `insert` adds a cell before `index`, or at the end
+                without one, and creates the notebook when it doesn't exist. `replace` swaps the source of
+                the cell at `index` and clears its outputs. `delete` removes the cell at `index`.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["path", "command"],
+                "properties": {
+                    "path": {"type": "string", "description": "Absolute path of the notebook"},
+                    "command": {"type": "string", "enum": ["insert", "replace", "delete"]},
+                    "index": {"type": "integer"},
+                    "source": {"type": "string", "description": "Source of the cell for insert and replace"},
+                    "cell_type": {"type": "string", "enum": CELL_TYPES, "description": "code by default when inserting"}
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Edit Notebook".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: false,
+                open_world_hint: false,
+            }),
+        );
+
+        let notebook_run = Tool::new(
+            "notebook_run",
+            indoc! {r#"
+                Run code cells of a .ipynb notebook in the kernel in order, saving their outputs to the
+                notebook as Jupyter would. Runs the cell at `index`, or every cell from `index` through
+                `end_index`, and stops at the first cell that fails. A kernel started for it runs in the
+                notebook's directory with the notebook's kernel spec.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["path", "index"],
+                "properties": {
+                    "path": {"type": "string", "description": "Absolute path of the notebook"},
+                    "index": {"type": "integer"},
+                    "end_index": {"type": "integer", "description": "Last cell to run, included"},
+                    "timeout_secs": {"type": "integer", "description": format!("Interrupt a cell after this long, {} by default", DEFAULT_TIMEOUT_SECS)}
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Run Notebook Cells".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: false,
+                open_world_hint: true,
+            }),
+        );
+
+        let instructions = formatdoc! {r#"
+            The notebook extension runs code in a Jupyter kernel and works on .ipynb notebooks. It needs
+            jupyter_client and a kernel such as ipykernel installed for {python}, set {env} to use
+            another interpreter.
+
+            - Work in small steps: load data once, then look at it, clean it and plot it in separate
+              calls, checking each result before the next step.
+            - Variables live in the kernel until it is restarted, whichever tool ran the code. Every
+              notebook shares the one kernel.
+            - Keep notebooks the user asked for up to date with notebook_edit and notebook_run, so the
+              analysis can be run again from the notebook.
+            - Plots are returned as images, print summaries of large tables rather than whole tables.
+            "#,
+            python = python(),
+            env = NOTEBOOK_PYTHON_ENV,
+        };
+
+        Self {
+            tools: vec![
+                kernel,
+                execute_code,
+                notebook_read,
+                notebook_edit,
+                notebook_run,
+            ],
+            instructions,
+            kernel: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The running kernel, starting one with `options` when there is none. Also returns a note
+    /// when a kernel was started, since it has none of the variables defined before.
+    async fn running_kernel(
+        &self,
+        options: KernelOptions,
+    ) -> Result<(MutexGuard<'_, Option<Kernel>>, Option<String>), ToolError> {
+        let mut kernel = self.kernel.lock().await;
+        let lost = kernel.as_ref().is_some_and(Kernel::broken);
+        if lost {
+            *kernel = None;
+        }
+        let mut note = None;
+        if kernel.is_none() {
+            let started = Kernel::start(&options)
+                .await
+                .map_err(ToolError::ExecutionError)?;
+            note = Some(if lost {
+                format!(
+                    "The kernel stopped responding, started {} in its place. Earlier variables are gone.",
+                    started.description
+                )
+            } else {
+                format!("Started {}.", started.description)
+            });
+            *kernel = Some(started);
+        }
+        Ok((kernel, note))
+    }
+
+    async fn kernel(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        let action = string_arg(&arguments, "action")
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'action' parameter".into()))?;
+        let mut kernel = self.kernel.lock().await;
+        let message = match action {
+            "start" => {
+                let options = KernelOptions {
+                    kernel_name: string_arg(&arguments, "kernel_name").map(str::to_string),
+                    connection_file: absolute_path(&arguments, "connection_file")?,
+                    cwd: absolute_path(&arguments, "cwd")?,
+                };
+                if let Some(previous) = kernel.take() {
+                    let _ = previous.shutdown().await;
+                }
+                let started = Kernel::start(&options)
+                    .await
+                    .map_err(ToolError::ExecutionError)?;
+                let message = if started.owned {
+                    format!("Started {}", started.description)
+                } else {
+                    format!("Attached to {}", started.description)
+                };
+                *kernel = Some(started);
+                message
+            }
+            "restart" => match kernel.as_mut() {
+                Some(running) if running.owned => {
+                    running.restart().await.map_err(ToolError::ExecutionError)?;
+                    "Restarted the kernel, every variable is gone".to_string()
+                }
+                Some(_) => {
+                    return Err(ToolError::ExecutionError(
+                        "The kernel was attached to rather than started by goose, restart it where it runs".into(),
+                    ))
+                }
+                None => "No kernel is running, one starts with the next code".to_string(),
+            },
+            "shutdown" => match kernel.take() {
+                Some(running) => {
+                    let description = running.description.clone();
+                    let owned = running.owned;
+                    running
+                        .shutdown()
+                        .await
+                        .map_err(ToolError::ExecutionError)?;
+                    if owned {
+                        format!("Shut down {}", description)
+                    } else {
+                        format!("Detached from {}, it keeps running", description)
+                    }
+                }
+                None => "No kernel is running".to_string(),
+            },
+            "status" => match kernel.as_ref() {
+                Some(running) if running.broken() => {
+                    "The kernel stopped responding, one starts with the next code".to_string()
+                }
+                Some(running) => format!("Using {}", running.description),
+                None => "No kernel is running, one starts with the next code".to_string(),
+            },
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "Unknown action '{}', use start, restart, shutdown or status",
+                    other
+                )))
+            }
+        };
+        Ok(vec![Content::text(message)])
+    }
+
+    async fn execute_code(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        let code = string_arg(&arguments, "code")
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'code' parameter".into()))?;
+        let timeout = timeout_arg(&arguments);
+
+        let (mut kernel, note) = self.running_kernel(KernelOptions::default()).await?;
+        let execution = kernel
+            .as_mut()
+            .expect("started above")
+            .execute(code, timeout)
+            .await
+            .map_err(ToolError::ExecutionError)?;
+
+        let mut contents: Vec<Content> = note.into_iter().map(Content::text).collect();
+        contents.extend(execution_contents(&execution, timeout));
+        Ok(contents)
+    }
+
+    async fn notebook_read(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        let notebook =
+            Notebook::open(&notebook_path(&arguments)?).map_err(ToolError::ExecutionError)?;
+        let start = index_arg(&arguments, "start")?.unwrap_or(0);
+        let end = index_arg(&arguments, "end")?
+            .unwrap_or(notebook.cell_count())
+            .min(notebook.cell_count());
+        let outputs = arguments
+            .get("outputs")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+        Ok(vec![Content::text(notebook.render(start, end, outputs))])
+    }
+
+    async fn notebook_edit(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        let path = notebook_path(&arguments)?;
+        let command = string_arg(&arguments, "command")
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'command' parameter".into()))?;
+        let index = index_arg(&arguments, "index")?;
+        let cell_type = string_arg(&arguments, "cell_type");
+        if let Some(cell_type) = cell_type {
+            if !CELL_TYPES.contains(&cell_type) {
+                return Err(ToolError::InvalidParameters(format!(
+                    "Unknown cell type '{}', use code, markdown or raw",
+                    cell_type
+                )));
+            }
+        }
+        let source = || {
+            arguments
+                .get("source")
+                .and_then(Value::as_str)
+                .ok_or_else(|| ToolError::InvalidParameters("Missing 'source' parameter".into()))
+        };
+        let index_required = || {
+            index.ok_or_else(|| ToolError::InvalidParameters("Missing 'index' parameter".into()))
+        };
+
+        let mut notebook = if command == "insert" && !path.exists() {
+            Notebook::create(&path, DEFAULT_KERNEL)
+        } else {
+            Notebook::open(&path).map_err(ToolError::ExecutionError)?
+        };
+        let message = match command {
+            "insert" => {
+                let index = notebook
+                    .insert(index, cell_type.unwrap_or("code"), source()?)
+                    .map_err(ToolError::InvalidParameters)?;
+                format!("Inserted cell {}", index)
+            }
+            "replace" => {
+                let index = index_required()?;
+                notebook
+                    .replace(index, source()?, cell_type)
+                    .map_err(ToolError::InvalidParameters)?;
+                format!("Replaced cell {}", index)
+            }
+            "delete" => {
+                let index = index_required()?;
+                notebook
+                    .delete(index)
+                    .map_err(ToolError::InvalidParameters)?;
+                format!("Deleted cell {}", index)
+            }
+            other => {
+                return Err(ToolError::InvalidParameters(format!(
+                    "Unknown command '{}', use insert, replace or delete",
+                    other
+                )))
+            }
+        };
+        notebook.save().map_err(ToolError::ExecutionError)?;
+        Ok(vec![Content::text(format!(
+            "{} of {}, it has {} cells now",
+            message,
+            notebook.path().display(),
+            notebook.cell_count()
+        ))])
+    }
+
+    async fn notebook_run(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        let path = notebook_path(&arguments)?;
+        let mut notebook = Notebook::open(&path).map_err(ToolError::ExecutionError)?;
+        let start = index_arg(&arguments, "index")?
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'index' parameter".into()))?;
+        let end = index_arg(&arguments, "end_index")?.unwrap_or(start);
+        if end < start || end >= notebook.cell_count() {
+            return Err(ToolError::InvalidParameters(format!(
+                "Can't run cells {} through {}, the notebook has {} cells",
+                start,
+                end,
+                notebook.cell_count()
+            )));
+        }
+        let timeout = timeout_arg(&arguments);
+
+        let options = KernelOptions {
+            kernel_name: notebook.kernel_name().map(str::to_string),
+            connection_file: None,
+            cwd: path.parent().map(Path::to_path_buf),
+        };
+        let (mut kernel, note) = self.running_kernel(options).await?;
+        let kernel = kernel.as_mut().expect("started above");
+
+        let mut contents: Vec<Content> = note.into_iter().map(Content::text).collect();
+        for index in start..=end {
+            if notebook
+                .cell_type(index)
+                .map_err(ToolError::ExecutionError)?
+                != "code"
+            {
+                continue;
+            }
+            let source = notebook.source(index).map_err(ToolError::ExecutionError)?;
+            let execution = kernel
+                .execute(&source, timeout)
+                .await
+                .map_err(ToolError::ExecutionError)?;
+            notebook
+                .set_outputs(index, &execution.outputs, execution.execution_count)
+                .map_err(ToolError::ExecutionError)?;
+            // Saved after every cell so a failure part way keeps what already ran
+            notebook.save().map_err(ToolError::ExecutionError)?;
+
+            contents.push(Content::text(format!("## Cell {}", index)));
+            contents.extend(execution_contents(&execution, timeout));
+            if execution.status != "ok" {
+                contents.push(Content::text(format!(
+                    "Stopped at cell {}, later cells didn't run",
+                    index
+                )));
+                break;
+            }
+        }
+        Ok(contents)
+    }
+}
+
+/// The outputs of a run, with a note when it failed or ran out of time
+fn execution_contents(execution: &Execution, timeout: Duration) -> Vec<Content> {
+    let mut contents = output_contents(&execution.outputs);
+    match execution.status.as_str() {
+        "error" => contents.insert(0, Content::text("The code raised an error:")),
+        "timeout" => contents.push(Content::text(format!(
+            "The code didn't finish within {}s and was interrupted",
+            timeout.as_secs()
+        ))),
+        _ if contents.is_empty() => contents.push(Content::text("Ran without output")),
+        _ => {}
+    }
+    contents
+}
+
+fn string_arg<'a>(arguments: &'a Value, name: &str) -> Option<&'a str> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .filter(|value| !value.trim().is_empty())
+}
+
+fn index_arg(arguments: &Value, name: &str) -> Result<Option<usize>, ToolError> {
+    match arguments.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .map(|index| Some(index as usize))
+            .ok_or_else(|| {
+                ToolError::InvalidParameters(format!("'{}' must be a cell index", name))
+            }),
+    }
+}
+
+fn timeout_arg(arguments: &Value) -> Duration {
+    Duration::from_secs(
+        arguments
+            .get("timeout_secs")
+            .and_then(Value::as_u64)
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_TIMEOUT_SECS),
+    )
+}
+
+fn absolute_path(arguments: &Value, name: &str) -> Result<Option<PathBuf>, ToolError> {
+    let Some(path) = string_arg(arguments, name) else {
+        return Ok(None);
+    };
+    let path = PathBuf::from(shellexpand::tilde(path).into_owned());
+    if !path.is_absolute() {
+        return Err(ToolError::InvalidParameters(format!(
+            "'{}' must be an absolute path",
+            name
+        )));
+    }
+    Ok(Some(path))
+}
+
+fn notebook_path(arguments: &Value) -> Result<PathBuf, ToolError> {
+    let path = absolute_path(arguments, "path")?
+        .ok_or_else(|| ToolError::InvalidParameters("Missing 'path' parameter".into()))?;
+    if path.extension().and_then(|extension| extension.to_str()) != Some("ipynb") {
+        return Err(ToolError::InvalidParameters(format!(
+            "{} is not a .ipynb notebook",
+            path.display()
+        )));
+    }
+    Ok(path)
+}
+
+impl Router for NotebookRouter {
+    fn name(&self) -> String {
+        "notebook".to_string()
+    }
+
+    fn instructions(&self) -> String {
+        self.instructions.clone()
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        CapabilitiesBuilder::new().with_tools(false).build()
+    }
+
+    fn list_tools(&self) -> Vec<Tool> {
+        self.tools.clone()
+    }
+
+    fn call_tool(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        _notifier: mpsc::Sender<JsonRpcMessage>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>> {
+        let this = self.clone();
+        let tool_name = tool_name.to_string();
+
+        Box::pin(async move {
+            match tool_name.as_str() {
+                "kernel" => this.kernel(arguments).await,
+                "execute_code" => this.execute_code(arguments).await,
+                "notebook_read" => this.notebook_read(arguments).await,
+                "notebook_edit" => this.notebook_edit(arguments).await,
+                "notebook_run" => this.notebook_run(arguments).await,
+                _ => Err(ToolError::NotFound(format!("Tool {} not found", tool_name))),
+            }
+        })
+    }
+
+    fn list_resources(&self) -> Vec<Resource> {
+        Vec::new()
+    }
+
+    fn read_resource(
+        &self,
+        _uri: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
+        Box::pin(async move { Ok("".to_string()) })
+    }
+
+    fn list_prompts(&self) -> Vec<Prompt> {
+        vec![]
+    }
+
+    fn get_prompt(
+        &self,
+        prompt_name: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>> {
+        let prompt_name = prompt_name.to_string();
+        Box::pin(async move {
+            Err(PromptError::NotFound(format!(
+                "Prompt {} not found",
+                prompt_name
+            )))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_edit_and_read_notebook() {
+        let router = NotebookRouter::new();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("analysis.ipynb");
+        let path_arg = path.to_string_lossy().to_string();
+        let (tx, _rx) = mpsc::channel(1);
+
+        for (source, cell_type) in [("# Analysis", "markdown"), ("1 + 1", "code")] {
+            router
+                .call_tool(
+                    "notebook_edit",
+                    json!({"path": path_arg, "command": "insert", "source": source, "cell_type": cell_type}),
+                    tx.clone(),
+                )
+                .await
+                .unwrap();
+        }
+        router
+            .call_tool(
+                "notebook_edit",
+                json!({"path": path_arg, "command": "replace", "index": 1, "source": "2 + 2"}),
+                tx.clone(),
+            )
+            .await
+            .unwrap();
+
+        let read = router
+            .call_tool("notebook_read", json!({"path": path_arg}), tx.clone())
+            .await
+            .unwrap();
+        let text = read[0].as_text().unwrap();
+        assert!(text.contains("## Cell 0: markdown\n# Analysis"));
+        assert!(text.contains("## Cell 1: code\n2 + 2"));
+
+        let result = router
+            .call_tool(
+                "notebook_edit",
+                json!({"path": path_arg, "command": "delete", "index": 4}),
+                tx.clone(),
+            )
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+
+        let result = router
+            .call_tool(
+                "notebook_run",
+                json!({"path": path_arg, "index": 0, "end_index": 2}),
+                tx.clone(),
+            )
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+
+        let result = router
+            .call_tool("notebook_read", json!({"path": "notes.ipynb"}), tx)
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+    }
+}
@@ -0,0 +1,540 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use mcp_core::handler::ToolError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::{Client, Method, RequestBuilder};
+
+use super::mail::{env, EMAIL_PASSWORD_ENV, EMAIL_USER_ENV};
+
+/// The calendar collection to read and write, e.g.
+/// `https://caldav.fastmail.com/dav/calendars/user/me@example.com/Default/`
+pub const CALDAV_URL_ENV: &str = "GOOSE_PIM_CALDAV_URL";
+/// Defaults to the email user
+pub const CALDAV_USER_ENV: &str = "GOOSE_PIM_CALDAV_USER";
+/// Defaults to the email password
+pub const CALDAV_PASSWORD_ENV: &str = "GOOSE_PIM_CALDAV_PASSWORD";
+
+/// iCalendar lines are folded at 75 octets
+const MAX_LINE_OCTETS: usize = 75;
+const ICAL_UTC_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+static CALENDAR_DATA: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<(?:[A-Za-z0-9]+:)?calendar-data[^>]*>(.*?)</(?:[A-Za-z0-9]+:)?calendar-data>")
+        .unwrap()
+});
+
+/// A CalDAV calendar
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calendar {
+    url: String,
+    user: Option<String>,
+    password: Option<String>,
+}
+
+impl Calendar {
+    pub fn from_env() -> Result<Self, String> {
+        let url = env(CALDAV_URL_ENV).ok_or_else(|| {
+            format!(
+                "{} is not set, it should point at a CalDAV calendar collection",
+                CALDAV_URL_ENV
+            )
+        })?;
+        Ok(Self {
+            url: format!("{}/", url.trim_end_matches('/')),
+            user: env(CALDAV_USER_ENV).or_else(|| env(EMAIL_USER_ENV)),
+            password: env(CALDAV_PASSWORD_ENV).or_else(|| env(EMAIL_PASSWORD_ENV)),
+        })
+    }
+
+    fn request(&self, client: &Client, method: Method, url: &str) -> RequestBuilder {
+        let request = client.request(method, url);
+        match &self.user {
+            Some(user) => request.basic_auth(user, self.password.as_ref()),
+            None => request,
+        }
+    }
+
+    async fn send(request: RequestBuilder) -> Result<String, ToolError> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Calendar request failed: {}", e)))?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            let hint = match status.as_u16() {
+                401 | 403 => format!(", check {} and {}", CALDAV_USER_ENV, CALDAV_PASSWORD_ENV),
+                404 => format!(", check {}", CALDAV_URL_ENV),
+                412 => ", an event with that UID already exists".to_string(),
+                _ => String::new(),
+            };
+            return Err(ToolError::ExecutionError(format!(
+                "Calendar server returned {}{}: {}",
+                status,
+                hint,
+                body.chars().take(500).collect::<String>()
+            )));
+        }
+        Ok(body)
+    }
+
+    /// Events overlapping `start..end`, recurring events expanded by the server
+    pub async fn list_events(
+        &self,
+        client: &Client,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Event>, ToolError> {
+        let range = format!(
+            r#"start="{}" end="{}""#,
+            start.format(ICAL_UTC_FORMAT),
+            end.format(ICAL_UTC_FORMAT)
+        );
+        let query = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <c:calendar-data><c:expand {range}/></c:calendar-data>
+  </d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VEVENT"><c:time-range {range}/></c:comp-filter>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#,
+            range = range
+        );
+        let method = Method::from_bytes(b"REPORT").expect("REPORT is a valid method");
+        let response = Self::send(
+            self.request(client, method, &self.url)
+                .header("Depth", "1")
+                .header("Content-Type", "application/xml; charset=utf-8")
+                .body(query),
+        )
+        .await?;
+        let mut events = parse_events(&response);
+        events.sort_by(|a, b| a.sort_key.cmp(&b.sort_key));
+        Ok(events)
+    }
+
+    /// Store `event` as a new resource in the calendar, returning its URL
+    pub async fn create_event(
+        &self,
+        client: &Client,
+        event: &NewEvent,
+    ) -> Result<String, ToolError> {
+        let url = format!("{}{}.ics", self.url, urlencoding::encode(&event.uid));
+        Self::send(
+            self.request(client, Method::PUT, &url)
+                .header("Content-Type", "text/calendar; charset=utf-8")
+                // Never overwrite an existing event
+                .header("If-None-Match", "*")
+                .body(event.to_ics(Utc::now())),
+        )
+        .await?;
+        Ok(url)
+    }
+}
+
+/// A calendar event, with times formatted for display
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub summary: String,
+    pub start: String,
+    pub end: String,
+    pub location: String,
+    pub description: String,
+    sort_key: String,
+}
+
+impl Event {
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} - {}  {}", self.start, self.end, self.summary);
+        if !self.location.is_empty() {
+            text.push_str(&format!(" @ {}", self.location));
+        }
+        let description: String = self
+            .description
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !description.is_empty() {
+            text.push_str("\n    ");
+            text.push_str(&description.chars().take(200).collect::<String>());
+        }
+        text
+    }
+}
+
+fn xml_unescape(text: &str) -> String {
+    let text = text
+        .trim()
+        .trim_start_matches("<![CDATA[")
+        .trim_end_matches("]]>");
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&amp;", "&")
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// A content line split into its name, parameters and value. Parameter values may be
+/// quoted and contain `:`.
+fn split_property(line: &str) -> Option<(String, &str, &str)> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => return Some(i),
+            _ => {}
+        }
+        None
+    })?;
+    let (name_and_params, value) = (&line[..colon], &line[colon + 1..]);
+    let (name, params) = name_and_params
+        .split_once(';')
+        .unwrap_or((name_and_params, ""));
+    Some((name.to_uppercase(), params, value))
+}
+
+/// A DTSTART or DTEND for display, and a key that sorts it roughly chronologically
+fn format_time(params: &str, value: &str) -> (String, String) {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        return (
+            format!("{} (all day)", date.format("%Y-%m-%d")),
+            format!("{}T000000", value),
+        );
+    }
+    if let Ok(utc) = NaiveDateTime::parse_from_str(value, ICAL_UTC_FORMAT) {
+        let local = Utc.from_utc_datetime(&utc).with_timezone(&Local);
+        return (
+            local.format("%Y-%m-%d %H:%M").to_string(),
+            local.format("%Y%m%dT%H%M%S").to_string(),
+        );
+    }
+    if let Ok(floating) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        let tzid = params
+            .split(';')
+            .find_map(|param| param.strip_prefix("TZID="))
+            .map(|tzid| format!(" ({})", tzid.trim_matches('"')))
+            .unwrap_or_default();
+        return (
+            format!("{}{}", floating.format("%Y-%m-%d %H:%M"), tzid),
+            value.to_string(),
+        );
+    }
+    (value.to_string(), value.to_string())
+}
+
+/// The events in iCalendar text, ignoring nested components such as alarms
+pub(crate) fn parse_ics(ics: &str) -> Vec<Event> {
+    let unfolded = ics
+        .replace("\r\n ", "")
+        .replace("\r\n\t", "")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut events = Vec::new();
+    let mut current: Option<Event> = None;
+    let mut nested = 0;
+    for line in unfolded.lines() {
+        let line = line.trim_end_matches('\r');
+        match line {
+            "BEGIN:VEVENT" => {
+                current = Some(Event {
+                    summary: String::new(),
+                    start: String::new(),
+                    end: String::new(),
+                    location: String::new(),
+                    description: String::new(),
+                    sort_key: String::new(),
+                });
+                nested = 0;
+                continue;
+            }
+            "END:VEVENT" => {
+                events.extend(current.take());
+                continue;
+            }
+            _ => {}
+        }
+        let Some(event) = current.as_mut() else {
+            continue;
+        };
+        if line.starts_with("BEGIN:") {
+            nested += 1;
+            continue;
+        }
+        if line.starts_with("END:") {
+            nested -= 1;
+            continue;
+        }
+        if nested > 0 {
+            continue;
+        }
+        let Some((name, params, value)) = split_property(line) else {
+            continue;
+        };
+        match name.as_str() {
+            "SUMMARY" => event.summary = unescape_text(value),
+            "LOCATION" => event.location = unescape_text(value),
+            "DESCRIPTION" => event.description = unescape_text(value),
+            "DTSTART" => (event.start, event.sort_key) = format_time(params, value),
+            "DTEND" => event.end = format_time(params, value).0,
+            _ => {}
+        }
+    }
+    events
+}
+
+/// The events in a CalDAV multistatus response
+pub(crate) fn parse_events(multistatus: &str) -> Vec<Event> {
+    CALENDAR_DATA
+        .captures_iter(multistatus)
+        .flat_map(|captures| parse_ics(&xml_unescape(&captures[1])))
+        .collect()
+}
+
+/// When an event starts or ends
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventTime {
+    /// All day
+    Date(NaiveDate),
+    At(DateTime<Utc>),
+}
+
+impl EventTime {
+    /// `YYYY-MM-DD` for a whole day, RFC 3339 or a local `YYYY-MM-DDTHH:MM[:SS]` otherwise
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Ok(Self::Date(date));
+        }
+        if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+            return Ok(Self::At(time.with_timezone(&Utc)));
+        }
+        ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+            .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+            .map(|local| Self::At(local.with_timezone(&Utc)))
+            .ok_or_else(|| {
+                format!(
+                    "Can't read '{}' as a time, use YYYY-MM-DD or YYYY-MM-DDTHH:MM",
+                    value
+                )
+            })
+    }
+
+    /// The start of this time, dates begin at local midnight
+    pub fn to_utc(self) -> DateTime<Utc> {
+        match self {
+            Self::Date(date) => Local
+                .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+                .earliest()
+                .map(|local| local.with_timezone(&Utc))
+                .unwrap_or_default(),
+            Self::At(time) => time,
+        }
+    }
+
+    /// A day after a date, an hour after a time
+    pub fn default_end(self) -> Self {
+        match self {
+            Self::Date(date) => Self::Date(date + Duration::days(1)),
+            Self::At(time) => Self::At(time + Duration::hours(1)),
+        }
+    }
+
+    fn to_property(self, name: &str) -> String {
+        match self {
+            Self::Date(date) => format!("{};VALUE=DATE:{}", name, date.format("%Y%m%d")),
+            Self::At(time) => format!("{}:{}", name, time.format(ICAL_UTC_FORMAT)),
+        }
+    }
+}
+
+/// An event to add to the calendar
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewEvent {
+    pub uid: String,
+    pub summary: String,
+    pub start: EventTime,
+    pub end: EventTime,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    pub organizer: Option<String>,
+    /// Addresses to invite, servers that handle scheduling email them
+    pub attendees: Vec<String>,
+}
+
+impl NewEvent {
+    pub fn new(summary: &str, start: EventTime, end: EventTime) -> Self {
+        Self {
+            uid: format!(
+                "goose-{}-{}",
+                Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+                std::process::id()
+            ),
+            summary: summary.to_string(),
+            start,
+            end,
+            location: None,
+            description: None,
+            organizer: None,
+            attendees: Vec::new(),
+        }
+    }
+
+    pub fn to_ics(&self, now: DateTime<Utc>) -> String {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//Block//goose//EN".to_string(),
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", self.uid),
+            format!("DTSTAMP:{}", now.format(ICAL_UTC_FORMAT)),
+            self.start.to_property("DTSTART"),
+            self.end.to_property("DTEND"),
+            format!("SUMMARY:{}", escape_text(&self.summary)),
+        ];
+        if let Some(location) = &self.location {
+            lines.push(format!("LOCATION:{}", escape_text(location)));
+        }
+        if let Some(description) = &self.description {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        if let Some(organizer) = &self.organizer {
+            lines.push(format!("ORGANIZER:mailto:{}", organizer));
+        }
+        for attendee in &self.attendees {
+            lines.push(format!(
+                "ATTENDEE;RSVP=TRUE;PARTSTAT=NEEDS-ACTION:mailto:{}",
+                attendee
+            ));
+        }
+        lines.push("END:VEVENT".to_string());
+        lines.push("END:VCALENDAR".to_string());
+
+        lines
+            .iter()
+            .map(|line| fold(line))
+            .collect::<Vec<_>>()
+            .join("\r\n")
+            + "\r\n"
+    }
+}
+
+/// Split a content line into continuation lines of at most 75 octets
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the continuation line
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events() {
+        let multistatus = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response><d:propstat><d:prop><cal:calendar-data>BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:b
+DTSTART;TZID="Europe/Paris":20250603T090000
+DTEND;TZID="Europe/Paris":20250603T100000
+SUMMARY:Standup\, daily
+DESCRIPTION:Agenda:\nupdates &amp; blockers
+BEGIN:VALARM
+DESCRIPTION:Reminder
+END:VALARM
+END:VEVENT
+BEGIN:VEVENT
+UID:a
+DTSTART;VALUE=DATE:20250602
+DTEND;VALUE=DATE:20250603
+SUMMARY:Offsite
+LOCATION:Lisb
+ on
+END:VEVENT
+END:VCALENDAR
+</cal:calendar-data></d:prop></d:propstat></d:response>
+</d:multistatus>"#;
+        let mut events = parse_events(multistatus);
+        events.sort_by(|a, b| a.sort_key.cmp(&b.sort_key));
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary, "Offsite");
+        assert_eq!(events[0].location, "Lisbon");
+        assert_eq!(events[0].start, "2025-06-02 (all day)");
+        assert_eq!(events[1].summary, "Standup, daily");
+        assert_eq!(events[1].start, "2025-06-03 09:00 (Europe/Paris)");
+        assert_eq!(events[1].description, "Agenda:\nupdates & blockers");
+    }
+
+    #[test]
+    fn test_new_event_ics() {
+        let start = EventTime::parse("2025-06-02T09:30:00Z").unwrap();
+        assert_eq!(
+            start.default_end(),
+            EventTime::parse("2025-06-02T10:30:00+00:00").unwrap()
+        );
+        assert_eq!(
+            EventTime::parse("2025-06-02").unwrap().default_end(),
+            EventTime::Date(NaiveDate::from_ymd_opt(2025, 6, 3).unwrap())
+        );
+        assert!(EventTime::parse("tomorrow").is_err());
+
+        let mut event = NewEvent::new("Review; plan, ship", start, start.default_end());
+        event.description = Some("x".repeat(100));
+        event.attendees = vec!["bob@example.com".to_string()];
+        let ics = event.to_ics(Utc::now());
+        assert!(ics.contains("\r\nDTSTART:20250602T093000Z\r\nDTEND:20250602T103000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Review\\; plan\\, ship\r\n"));
+        assert!(ics.contains("ATTENDEE;RSVP=TRUE;PARTSTAT=NEEDS-ACTION:mailto:bob@example.com"));
+        assert!(ics.lines().all(|line| line.len() <= MAX_LINE_OCTETS));
+
+        let parsed = parse_ics(&ics);
+        assert_eq!(parsed[0].description, "x".repeat(100));
+        assert_eq!(parsed[0].summary, "Review; plan, ship");
+    }
+}
@@ -0,0 +1,966 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Local};
+use mcp_core::handler::ToolError;
+use once_cell::sync::Lazy;
+use regex::{bytes::Regex as BytesRegex, Regex};
+use std::{io::Write, process::Stdio};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+/// IMAP server, e.g. `imaps://imap.fastmail.com`
+pub const IMAP_URL_ENV: &str = "GOOSE_PIM_IMAP_URL";
+/// SMTP server used to send approved drafts, e.g. `smtps://smtp.fastmail.com:465`
+pub const SMTP_URL_ENV: &str = "GOOSE_PIM_SMTP_URL";
+pub const EMAIL_USER_ENV: &str = "GOOSE_PIM_EMAIL_USER";
+/// Usually an app password. When unset, credentials are read from `~/.netrc`
+pub const EMAIL_PASSWORD_ENV: &str = "GOOSE_PIM_EMAIL_PASSWORD";
+/// Address drafts are written from, defaults to the user name
+pub const EMAIL_FROM_ENV: &str = "GOOSE_PIM_EMAIL_FROM";
+/// Mailbox drafts are saved to, `Drafts` by default. Gmail calls it `[Gmail]/Drafts`
+pub const DRAFTS_MAILBOX_ENV: &str = "GOOSE_PIM_DRAFTS_MAILBOX";
+
+const DEFAULT_DRAFTS_MAILBOX: &str = "Drafts";
+/// How much of each message body a search fetches to build its snippet
+const SNIPPET_FETCH_BYTES: usize = 1024;
+const SNIPPET_CHARS: usize = 200;
+const CURL_TIMEOUT_SECS: &str = "60";
+
+static LITERAL: Lazy<BytesRegex> = Lazy::new(|| BytesRegex::new(r"\{(\d+)\}\r?\n").unwrap());
+static FETCH_START: Lazy<BytesRegex> =
+    Lazy::new(|| BytesRegex::new(r"(?m)^\* \d+ FETCH \(").unwrap());
+static UID: Lazy<BytesRegex> = Lazy::new(|| BytesRegex::new(r"UID (\d+)").unwrap());
+static ENCODED_WORD: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"=\?([^?]+)\?([BbQq])\?([^?]*)\?=").unwrap());
+static ENCODED_WORD_GAP: Lazy<Regex> = Lazy::new(|| Regex::new(r"\?=\s+=\?").unwrap());
+static ADDRESS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z0-9._%+\-=']+@[A-Za-z0-9.\-]+[A-Za-z0-9]").unwrap());
+static HTML_IGNORED: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<(style|script|head)\b.*?</(style|script|head)>").unwrap());
+static HTML_BREAK: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)<br\s*/?>|</p>|</div>|</tr>|</li>|</h\d>").unwrap());
+static HTML_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+static BLANK_LINES: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n\s*\n(\s*\n)+").unwrap());
+
+pub(crate) fn env(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// An email account reached over IMAP, and SMTP for sending, through `curl`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MailAccount {
+    imap_url: String,
+    smtp_url: Option<String>,
+    user: String,
+    password: Option<String>,
+    pub from: String,
+    pub drafts: String,
+}
+
+impl MailAccount {
+    pub fn from_env() -> Result<Self, String> {
+        let imap_url = env(IMAP_URL_ENV)
+            .ok_or_else(|| format!("{} is not set, e.g. imaps://imap.example.com", IMAP_URL_ENV))?;
+        let user = env(EMAIL_USER_ENV)
+            .ok_or_else(|| format!("{} must be set to read email", EMAIL_USER_ENV))?;
+        Ok(Self {
+            imap_url: imap_url.trim_end_matches('/').to_string(),
+            smtp_url: env(SMTP_URL_ENV),
+            password: env(EMAIL_PASSWORD_ENV),
+            from: env(EMAIL_FROM_ENV).unwrap_or_else(|| user.clone()),
+            drafts: env(DRAFTS_MAILBOX_ENV).unwrap_or_else(|| DEFAULT_DRAFTS_MAILBOX.to_string()),
+            user,
+        })
+    }
+
+    pub fn can_send(&self) -> bool {
+        self.smtp_url.is_some()
+    }
+
+    fn mailbox_url(&self, mailbox: &str) -> String {
+        format!("{}/{}", self.imap_url, urlencoding::encode(mailbox))
+    }
+
+    /// Run curl against `url`. Credentials go through stdin so they never show up in the
+    /// process list, an upload goes through a temporary file.
+    async fn curl(
+        &self,
+        url: &str,
+        args: &[String],
+        upload: Option<&[u8]>,
+    ) -> Result<Vec<u8>, ToolError> {
+        let mut command = Command::new("curl");
+        command
+            .args(["--silent", "--show-error", "--ssl-reqd"])
+            .args(["--max-time", CURL_TIMEOUT_SECS, "--url", url])
+            .args(args);
+
+        let upload_file = match upload {
+            Some(data) => {
+                let mut file = tempfile::NamedTempFile::new().map_err(|e| {
+                    ToolError::ExecutionError(format!("Failed to write the message: {}", e))
+                })?;
+                file.write_all(data).map_err(|e| {
+                    ToolError::ExecutionError(format!("Failed to write the message: {}", e))
+                })?;
+                command.arg("--upload-file").arg(file.path());
+                Some(file)
+            }
+            None => None,
+        };
+        match &self.password {
+            Some(_) => command.args(["--config", "-"]),
+            None => command.args(["--netrc", "--user", self.user.as_str()]),
+        };
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to run curl: {}", e)))?;
+        if let (Some(mut stdin), Some(password)) = (child.stdin.take(), &self.password) {
+            stdin
+                .write_all(curl_user_config(&self.user, password).as_bytes())
+                .await
+                .map_err(|e| ToolError::ExecutionError(format!("Failed to run curl: {}", e)))?;
+        }
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to run curl: {}", e)))?;
+        drop(upload_file);
+
+        if !output.status.success() {
+            return Err(ToolError::ExecutionError(format!(
+                "Mail request failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(output.stdout)
+    }
+
+    async fn imap(&self, mailbox: &str, command: String) -> Result<Vec<u8>, ToolError> {
+        self.curl(
+            &self.mailbox_url(mailbox),
+            &["--request".to_string(), command],
+            None,
+        )
+        .await
+    }
+
+    /// The newest `limit` messages in `mailbox` matching `query`, with a snippet of each
+    pub async fn search(
+        &self,
+        mailbox: &str,
+        query: &str,
+        unread_only: bool,
+        limit: usize,
+    ) -> Result<Vec<Email>, ToolError> {
+        let output = self
+            .imap(
+                mailbox,
+                format!("UID SEARCH {}", search_criteria(query, unread_only)),
+            )
+            .await?;
+        let mut uids = parse_search(&String::from_utf8_lossy(&output));
+        uids.sort_unstable_by(|a, b| b.cmp(a));
+        uids.truncate(limit);
+        if uids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let set = uids
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        // PEEK so searching doesn't mark anything as read
+        let output = self
+            .imap(
+                mailbox,
+                format!(
+                    "UID FETCH {} (UID BODY.PEEK[HEADER.FIELDS (FROM TO CC SUBJECT DATE CONTENT-TYPE CONTENT-TRANSFER-ENCODING)] BODY.PEEK[TEXT]<0.{}>)",
+                    set, SNIPPET_FETCH_BYTES
+                ),
+            )
+            .await?;
+        let mut emails: Vec<Email> = parse_fetch(&output)
+            .iter()
+            .map(Email::from_summary)
+            .collect();
+        emails.sort_by(|a, b| b.uid.cmp(&a.uid));
+        Ok(emails)
+    }
+
+    /// The raw RFC 5322 message, without marking it as read
+    async fn fetch_raw(&self, mailbox: &str, uid: u32) -> Result<Vec<u8>, ToolError> {
+        let output = self
+            .imap(mailbox, format!("UID FETCH {} (UID BODY.PEEK[])", uid))
+            .await?;
+        parse_fetch(&output)
+            .into_iter()
+            .find(|fetched| fetched.uid == uid)
+            .map(|fetched| fetched.body)
+            .ok_or_else(|| {
+                ToolError::InvalidParameters(format!("No message with uid {} in {}", uid, mailbox))
+            })
+    }
+
+    pub async fn read(&self, mailbox: &str, uid: u32) -> Result<Email, ToolError> {
+        Ok(Email::parse(uid, &self.fetch_raw(mailbox, uid).await?))
+    }
+
+    /// Save `draft` to the drafts mailbox, it is not sent
+    pub async fn save_draft(&self, draft: &Draft) -> Result<String, ToolError> {
+        let message = draft.to_rfc5322(&self.from, Local::now());
+        self.curl(
+            &self.mailbox_url(&self.drafts),
+            &[],
+            Some(message.as_bytes()),
+        )
+        .await?;
+        Ok(message)
+    }
+
+    /// Send the draft with `uid` from the drafts mailbox over SMTP
+    pub async fn send_draft(&self, uid: u32) -> Result<String, ToolError> {
+        let smtp_url = self.smtp_url.as_deref().ok_or_else(|| {
+            ToolError::ExecutionError(format!("{} is not set, drafts can't be sent", SMTP_URL_ENV))
+        })?;
+        let raw = self.fetch_raw(&self.drafts, uid).await?;
+        let draft = Email::parse(uid, &raw);
+        let recipients: Vec<String> = ["to", "cc", "bcc"]
+            .iter()
+            .flat_map(|name| addresses(&draft.header(name)))
+            .collect();
+        if recipients.is_empty() {
+            return Err(ToolError::InvalidParameters(format!(
+                "Draft {} has no recipients",
+                uid
+            )));
+        }
+        let sender = addresses(&self.from)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| self.from.clone());
+
+        let mut args = vec!["--mail-from".to_string(), sender];
+        for recipient in &recipients {
+            args.push("--mail-rcpt".to_string());
+            args.push(recipient.clone());
+        }
+        let message = strip_header(&String::from_utf8_lossy(&raw), "bcc");
+        self.curl(smtp_url, &args, Some(message.as_bytes())).await?;
+        Ok(format!(
+            "Sent '{}' to {}. The draft is still in {}.",
+            draft.header("subject"),
+            recipients.join(", "),
+            self.drafts
+        ))
+    }
+}
+
+fn curl_user_config(user: &str, password: &str) -> String {
+    let escape = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("user = \"{}:{}\"\n", escape(user), escape(password))
+}
+
+fn imap_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// IMAP SEARCH criteria for space separated words, each of which must match. `from:`,
+/// `to:` and `subject:` prefixes narrow a word to that header.
+pub(crate) fn search_criteria(query: &str, unread_only: bool) -> String {
+    let mut criteria: Vec<String> = Vec::new();
+    if unread_only {
+        criteria.push("UNSEEN".to_string());
+    }
+    for word in query.split_whitespace() {
+        let (key, value) = match word.split_once(':') {
+            Some((prefix, value)) if !value.is_empty() => match prefix.to_lowercase().as_str() {
+                "from" => ("FROM", value),
+                "to" => ("TO", value),
+                "subject" => ("SUBJECT", value),
+                _ => ("TEXT", word),
+            },
+            _ => ("TEXT", word),
+        };
+        criteria.push(format!("{} {}", key, imap_quote(value)));
+    }
+    if criteria.is_empty() {
+        criteria.push("ALL".to_string());
+    }
+    let charset = if query.is_ascii() {
+        ""
+    } else {
+        "CHARSET UTF-8 "
+    };
+    format!("{}{}", charset, criteria.join(" "))
+}
+
+/// UIDs from the untagged `* SEARCH` response
+pub(crate) fn parse_search(output: &str) -> Vec<u32> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("* SEARCH"))
+        .flat_map(|uids| uids.split_whitespace().filter_map(|uid| uid.parse().ok()))
+        .collect()
+}
+
+/// One message of a `UID FETCH` response
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Fetched {
+    pub uid: u32,
+    /// A `HEADER.FIELDS` section
+    pub header: Vec<u8>,
+    /// A `TEXT` or whole message section
+    pub body: Vec<u8>,
+}
+
+/// Split a `UID FETCH` response into messages. Sections come back as `{length}` literals,
+/// and servers put the UID before or after them.
+pub(crate) fn parse_fetch(output: &[u8]) -> Vec<Fetched> {
+    fn set_uid(message: Option<&mut Fetched>, text: &[u8]) {
+        if let (Some(message), Some(uid)) = (message, UID.captures(text)) {
+            if let Some(uid) = std::str::from_utf8(&uid[1])
+                .ok()
+                .and_then(|uid| uid.parse().ok())
+            {
+                message.uid = uid;
+            }
+        }
+    }
+
+    let mut messages: Vec<Fetched> = Vec::new();
+    let mut pos = 0;
+    loop {
+        let literal = LITERAL.captures_at(output, pos);
+        let text_end = literal
+            .as_ref()
+            .and_then(|captures| captures.get(0))
+            .map_or(output.len(), |m| m.start());
+        let text = &output[pos..text_end];
+
+        // The text before a literal can finish one message and start the next
+        let starts: Vec<usize> = FETCH_START.find_iter(text).map(|m| m.start()).collect();
+        let first_start = starts.first().copied().unwrap_or(text.len());
+        set_uid(messages.last_mut(), &text[..first_start]);
+        let mut section = &text[..first_start];
+        for (i, start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(text.len());
+            messages.push(Fetched::default());
+            section = &text[*start..end];
+            set_uid(messages.last_mut(), section);
+        }
+
+        let Some(literal) = literal else {
+            break;
+        };
+        let length: usize = std::str::from_utf8(&literal[1])
+            .ok()
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0);
+        let start = literal.get(0).map_or(text_end, |m| m.end());
+        let end = (start + length).min(output.len());
+        if let Some(message) = messages.last_mut() {
+            let data = output[start..end].to_vec();
+            if section.windows(6).any(|window| window == b"HEADER") {
+                message.header = data;
+            } else {
+                message.body = data;
+            }
+        }
+        pos = end;
+    }
+    messages
+}
+
+type Headers = Vec<(String, String)>;
+
+/// Split a message or MIME part into its unfolded headers, names lowercased, and its body
+fn split_headers(raw: &str) -> (Headers, &str) {
+    let (head, body) = ["\r\n\r\n", "\n\n"]
+        .iter()
+        .filter_map(|separator| raw.find(separator).map(|i| (i, separator.len())))
+        .min()
+        .map_or((raw, ""), |(i, len)| (&raw[..i], &raw[i + len..]));
+
+    let mut headers: Headers = Vec::new();
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    (headers, body)
+}
+
+fn header<'a>(headers: &'a Headers, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// A parameter of a structured header, e.g. the `boundary` of a `Content-Type`
+fn param(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|part| {
+        let (key, value) = part.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn mime_type(headers: &Headers) -> String {
+    header(headers, "content-type")
+        .unwrap_or("text/plain")
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// The parts of a multipart body, without the preamble and epilogue
+fn split_multipart<'a>(body: &'a str, boundary: &str) -> impl Iterator<Item = &'a str> {
+    let delimiter = format!("--{}", boundary);
+    body.split(delimiter.as_str())
+        .skip(1)
+        .take_while(|part| !part.starts_with("--"))
+        .map(|part| {
+            part.strip_prefix("\r\n")
+                .or_else(|| part.strip_prefix('\n'))
+                .unwrap_or(part)
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+fn decode_quoted_printable(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' {
+            if bytes[i + 1..].starts_with(b"\r\n") {
+                i += 3;
+                continue;
+            }
+            if bytes[i + 1..].starts_with(b"\n") {
+                i += 2;
+                continue;
+            }
+            if let Some(byte) = bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    decoded
+}
+
+fn decode_base64(text: &str) -> Vec<u8> {
+    let mut compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    // A partial fetch can cut the body mid-quantum
+    if !compact.ends_with('=') {
+        compact.truncate(compact.len() / 4 * 4);
+    }
+    STANDARD.decode(compact).unwrap_or_default()
+}
+
+fn decode_transfer(body: &str, encoding: &str) -> String {
+    match encoding.trim().to_lowercase().as_str() {
+        "base64" => String::from_utf8_lossy(&decode_base64(body)).into_owned(),
+        "quoted-printable" => String::from_utf8_lossy(&decode_quoted_printable(body)).into_owned(),
+        _ => body.to_string(),
+    }
+}
+
+/// Decode RFC 2047 encoded words, e.g. `=?UTF-8?B?...?=` in a subject
+pub(crate) fn decode_header_value(value: &str) -> String {
+    let joined = ENCODED_WORD_GAP.replace_all(value, "?==?");
+    ENCODED_WORD
+        .replace_all(&joined, |captures: &regex::Captures| {
+            let bytes = if captures[2].eq_ignore_ascii_case("b") {
+                decode_base64(&captures[3])
+            } else {
+                decode_quoted_printable(&captures[3].replace('_', " "))
+            };
+            String::from_utf8_lossy(&bytes).into_owned()
+        })
+        .into_owned()
+}
+
+/// A header value on one line. Decoded encoded-words and tool arguments can hold line breaks,
+/// which would otherwise start headers of their own, such as a Bcc.
+pub(crate) fn single_line(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+fn encode_header_value(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", STANDARD.encode(value))
+    }
+}
+
+pub(crate) fn html_to_text(html: &str) -> String {
+    let text = HTML_IGNORED.replace_all(html, "");
+    let text = HTML_BREAK.replace_all(&text, "\n");
+    let text = HTML_TAG.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    BLANK_LINES.replace_all(&text, "\n\n").trim().to_string()
+}
+
+/// Every address in an address header, without display names
+pub(crate) fn addresses(value: &str) -> Vec<String> {
+    ADDRESS
+        .find_iter(value)
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+fn strip_header(message: &str, name: &str) -> String {
+    let mut skipping = false;
+    let mut in_headers = true;
+    let mut kept = Vec::new();
+    for line in message.split_inclusive('\n') {
+        if in_headers {
+            if line.trim().is_empty() {
+                in_headers = false;
+            } else if line.starts_with([' ', '\t']) {
+                if skipping {
+                    continue;
+                }
+            } else {
+                skipping = line
+                    .split_once(':')
+                    .is_some_and(|(key, _)| key.trim().eq_ignore_ascii_case(name));
+                if skipping {
+                    continue;
+                }
+            }
+        }
+        kept.push(line);
+    }
+    kept.concat()
+}
+
+#[derive(Default)]
+struct Parts {
+    plain: Option<String>,
+    html: Option<String>,
+    attachments: Vec<String>,
+}
+
+impl Parts {
+    fn collect(&mut self, headers: &Headers, body: &str) {
+        let content_type = header(headers, "content-type").unwrap_or_default();
+        let mime = mime_type(headers);
+        if mime.starts_with("multipart/") {
+            if let Some(boundary) = param(content_type, "boundary") {
+                for part in split_multipart(body, &boundary) {
+                    let (headers, body) = split_headers(part);
+                    self.collect(&headers, body);
+                }
+            }
+            return;
+        }
+
+        let disposition = header(headers, "content-disposition").unwrap_or_default();
+        let filename = param(disposition, "filename").or_else(|| param(content_type, "name"));
+        if disposition.to_lowercase().starts_with("attachment")
+            || (filename.is_some() && !mime.starts_with("text/"))
+        {
+            self.attachments.push(format!(
+                "{} ({})",
+                decode_header_value(&filename.unwrap_or_else(|| "unnamed".to_string())),
+                mime
+            ));
+            return;
+        }
+
+        let encoding = header(headers, "content-transfer-encoding").unwrap_or_default();
+        match mime.as_str() {
+            "text/plain" if self.plain.is_none() => {
+                self.plain = Some(decode_transfer(body, encoding))
+            }
+            "text/html" if self.html.is_none() => self.html = Some(decode_transfer(body, encoding)),
+            _ => {}
+        }
+    }
+}
+
+/// An email, with its full text when read or a snippet when found by a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub uid: u32,
+    headers: Headers,
+    pub body: String,
+    pub attachments: Vec<String>,
+}
+
+impl Email {
+    /// From a whole message, preferring its plain text part
+    pub(crate) fn parse(uid: u32, raw: &[u8]) -> Self {
+        let raw = String::from_utf8_lossy(raw);
+        let (headers, body) = split_headers(&raw);
+        let mut parts = Parts::default();
+        parts.collect(&headers, body);
+        let body = parts
+            .plain
+            .or_else(|| parts.html.map(|html| html_to_text(&html)))
+            .unwrap_or_default();
+        Self {
+            uid,
+            headers,
+            body: body.trim().to_string(),
+            attachments: parts.attachments,
+        }
+    }
+
+    /// From the header fields and the start of the body that a search fetches
+    pub(crate) fn from_summary(fetched: &Fetched) -> Self {
+        let (headers, _) = split_headers(&String::from_utf8_lossy(&fetched.header));
+
+        // Descend into the first part of multipart bodies, usually the plain text
+        let mut part_headers = headers.clone();
+        let mut body = String::from_utf8_lossy(&fetched.body).into_owned();
+        while mime_type(&part_headers).starts_with("multipart/") {
+            let Some(part) = header(&part_headers, "content-type")
+                .and_then(|content_type| param(content_type, "boundary"))
+                .and_then(|boundary| split_multipart(&body, &boundary).next().map(str::to_string))
+            else {
+                break;
+            };
+            let (next_headers, next_body) = split_headers(&part);
+            part_headers = next_headers;
+            body = next_body.to_string();
+        }
+
+        let text = decode_transfer(
+            &body,
+            header(&part_headers, "content-transfer-encoding").unwrap_or_default(),
+        );
+        let text = if mime_type(&part_headers) == "text/html" {
+            html_to_text(&text)
+        } else {
+            text
+        };
+        let mut snippet: String = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if snippet.chars().count() > SNIPPET_CHARS {
+            snippet = snippet.chars().take(SNIPPET_CHARS).collect::<String>() + "...";
+        }
+
+        Self {
+            uid: fetched.uid,
+            headers,
+            body: snippet,
+            attachments: Vec::new(),
+        }
+    }
+
+    /// A decoded header, empty when missing
+    pub fn header(&self, name: &str) -> String {
+        header(&self.headers, name)
+            .map(decode_header_value)
+            .unwrap_or_default()
+    }
+
+    pub fn summary_line(&self) -> String {
+        format!(
+            "[uid {}] {} | {} | {}\n    {}",
+            self.uid,
+            self.header("date"),
+            self.header("from"),
+            self.header("subject"),
+            self.body
+        )
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "UID: {}\nFrom: {}\nTo: {}\n",
+            self.uid,
+            self.header("from"),
+            self.header("to")
+        );
+        let cc = self.header("cc");
+        if !cc.is_empty() {
+            text.push_str(&format!("Cc: {}\n", cc));
+        }
+        text.push_str(&format!(
+            "Date: {}\nSubject: {}\n",
+            self.header("date"),
+            self.header("subject")
+        ));
+        if !self.attachments.is_empty() {
+            text.push_str(&format!("Attachments: {}\n", self.attachments.join(", ")));
+        }
+        text.push('\n');
+        text.push_str(&self.body);
+        text
+    }
+}
+
+/// A message to save to the drafts mailbox
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Draft {
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub subject: String,
+    pub body: String,
+    pub in_reply_to: Option<String>,
+    pub references: Option<String>,
+}
+
+impl Draft {
+    /// A reply to `original` from `own_address`, quoting it below `body`
+    pub fn reply(original: &Email, own_address: &str, reply_all: bool, body: &str) -> Self {
+        let reply_to = original.header("reply-to");
+        let to = if reply_to.is_empty() {
+            vec![original.header("from")]
+        } else {
+            vec![reply_to]
+        };
+
+        let mut cc = Vec::new();
+        if reply_all {
+            let own = addresses(own_address);
+            let already: Vec<String> = to.iter().flat_map(|to| addresses(to)).collect();
+            for address in addresses(&original.header("to"))
+                .into_iter()
+                .chain(addresses(&original.header("cc")))
+            {
+                let lower = address.to_lowercase();
+                let seen = own
+                    .iter()
+                    .chain(&already)
+                    .chain(&cc)
+                    .any(|other| other.to_lowercase() == lower);
+                if !seen {
+                    cc.push(address);
+                }
+            }
+        }
+
+        let subject = original.header("subject");
+        let subject = if subject.to_lowercase().starts_with("re:") {
+            subject
+        } else {
+            format!("Re: {}", subject)
+        };
+
+        let quoted = original
+            .body
+            .lines()
+            .map(|line| format!("> {}", line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let date = original.header("date");
+        let attribution = if date.is_empty() {
+            format!("{} wrote:", original.header("from"))
+        } else {
+            format!("On {}, {} wrote:", date, original.header("from"))
+        };
+        let body = format!("{}\n\n{}\n{}", body.trim_end(), attribution, quoted);
+
+        let message_id = original.header("message-id");
+        let references = [original.header("references"), message_id.clone()]
+            .into_iter()
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Self {
+            to,
+            cc,
+            subject,
+            body,
+            in_reply_to: Some(message_id).filter(|id| !id.is_empty()),
+            references: Some(references).filter(|references| !references.is_empty()),
+        }
+    }
+
+    pub fn to_rfc5322(&self, from: &str, now: DateTime<Local>) -> String {
+        let domain = addresses(from)
+            .first()
+            .and_then(|address| {
+                address
+                    .split_once('@')
+                    .map(|(_, domain)| domain.to_string())
+            })
+            .unwrap_or_else(|| "localhost".to_string());
+        let mut headers = vec![
+            format!("From: {}", single_line(from)),
+            format!("To: {}", single_line(&self.to.join(", "))),
+        ];
+        if !self.cc.is_empty() {
+            headers.push(format!("Cc: {}", single_line(&self.cc.join(", "))));
+        }
+        headers.push(format!(
+            "Subject: {}",
+            encode_header_value(&single_line(&self.subject))
+        ));
+        headers.push(format!("Date: {}", now.to_rfc2822()));
+        headers.push(format!(
+            "Message-ID: <{}.{}@{}>",
+            now.timestamp_nanos_opt().unwrap_or_default(),
+            std::process::id(),
+            domain
+        ));
+        if let Some(in_reply_to) = &self.in_reply_to {
+            headers.push(format!("In-Reply-To: {}", single_line(in_reply_to)));
+        }
+        if let Some(references) = &self.references {
+            headers.push(format!("References: {}", single_line(references)));
+        }
+        headers.push("MIME-Version: 1.0".to_string());
+        headers.push("Content-Type: text/plain; charset=utf-8".to_string());
+        headers.push("Content-Transfer-Encoding: 8bit".to_string());
+
+        let body = self
+            .body
+            .replace("\r\n", "\n")
+            .split('\n')
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        format!("{}\r\n\r\n{}\r\n", headers.join("\r\n"), body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_and_fetch_parsing() {
+        assert_eq!(
+            search_criteria("from:alice invoice", true),
+            "UNSEEN FROM \"alice\" TEXT \"invoice\""
+        );
+        assert_eq!(search_criteria("", false), "ALL");
+        assert_eq!(
+            search_criteria("café", false),
+            "CHARSET UTF-8 TEXT \"café\""
+        );
+        assert_eq!(parse_search("* SEARCH 3 7 12\r\n"), vec![3, 7, 12]);
+
+        let header = "From: Alice <alice@example.com>\r\nSubject: =?UTF-8?B?SMOpbGxv?=\r\nContent-Type: text/plain\r\nContent-Transfer-Encoding: quoted-printable\r\n\r\n";
+        let text = "Caf=C3=A9 at noon?=\r\n See you";
+        let output = format!(
+            "* 1 FETCH (UID 7 BODY[HEADER.FIELDS (FROM SUBJECT)] {{{}}}\r\n{} BODY[TEXT]<0> {{{}}}\r\n{})\r\n* 2 FETCH (BODY[HEADER.FIELDS (FROM SUBJECT)] {{2}}\r\n\r\n BODY[TEXT]<0> {{0}}\r\n UID 9)\r\n",
+            header.len(),
+            header,
+            text.len(),
+            text
+        );
+        let fetched = parse_fetch(output.as_bytes());
+        assert_eq!(fetched.len(), 2);
+        assert_eq!(fetched[0].uid, 7);
+        assert_eq!(fetched[1].uid, 9);
+
+        let email = Email::from_summary(&fetched[0]);
+        assert_eq!(email.header("subject"), "Héllo");
+        assert_eq!(email.body, "Café at noon? See you");
+        assert_eq!(addresses(&email.header("from")), vec!["alice@example.com"]);
+    }
+
+    #[test]
+    fn test_multipart_message_and_reply() {
+        let raw = concat!(
+            "From: Bob <bob@example.com>\r\n",
+            "To: me@example.com, carol@example.com\r\n",
+            "Subject: Launch\r\n",
+            "Date: Mon, 2 Jun 2025 10:00:00 +0000\r\n",
+            "Message-ID: <abc@example.com>\r\n",
+            "Content-Type: multipart/mixed; boundary=\"outer\"\r\n",
+            "\r\n",
+            "--outer\r\n",
+            "Content-Type: multipart/alternative; boundary=inner\r\n",
+            "\r\n",
+            "--inner\r\n",
+            "Content-Type: text/html\r\n",
+            "\r\n",
+            "<p>Ship <b>it</b></p>\r\n",
+            "--inner--\r\n",
+            "--outer\r\n",
+            "Content-Type: application/pdf; name=\"plan.pdf\"\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "\r\n",
+            "JVBERi0=\r\n",
+            "--outer--\r\n",
+        );
+        let email = Email::parse(4, raw.as_bytes());
+        assert_eq!(email.body, "Ship it");
+        assert_eq!(email.attachments, vec!["plan.pdf (application/pdf)"]);
+
+        let draft = Draft::reply(&email, "Me <me@example.com>", true, "Done");
+        assert_eq!(draft.to, vec!["Bob <bob@example.com>"]);
+        assert_eq!(draft.cc, vec!["carol@example.com"]);
+        assert_eq!(draft.subject, "Re: Launch");
+        assert_eq!(draft.references.as_deref(), Some("<abc@example.com>"));
+        assert_eq!(
+            draft.body,
+            "Done\n\nOn Mon, 2 Jun 2025 10:00:00 +0000, Bob <bob@example.com> wrote:\n> Ship it"
+        );
+
+        let message = draft.to_rfc5322("Me <me@example.com>", Local::now());
+        assert!(message.contains("In-Reply-To: <abc@example.com>\r\n"));
+        assert!(message.contains("@example.com>\r\nIn-Reply-To"));
+
+        let with_bcc = "To: a@example.com\r\nBcc: secret@example.com,\r\n other@example.com\r\nSubject: x\r\n\r\nBcc: kept\r\n";
+        assert_eq!(
+            strip_header(with_bcc, "bcc"),
+            "To: a@example.com\r\nSubject: x\r\n\r\nBcc: kept\r\n"
+        );
+    }
+
+    #[test]
+    fn test_reply_headers_stay_on_one_line() {
+        let raw = concat!(
+            "From: =?UTF-8?Q?Eve_<eve@example.com>=0D=0ABcc:_leak@example.com?=\r\n",
+            "Subject: =?UTF-8?B?SGkNCkJjYzogbGVha0BleGFtcGxlLmNvbQ==?=\r\n",
+            "Message-ID: <x@example.com>\r\n",
+            "\r\n",
+            "Hello\r\n",
+        );
+        let email = Email::parse(1, raw.as_bytes());
+        assert!(email.header("subject").contains('\n'));
+
+        let mut draft = Draft::reply(&email, "me@example.com", false, "Hi");
+        draft.cc = vec!["a@example.com\r\nBcc: other@example.com".to_string()];
+        let message = draft.to_rfc5322("Me <me@example.com>", Local::now());
+        let saved = Email::parse(2, message.as_bytes());
+        assert_eq!(saved.header("bcc"), "");
+        assert_eq!(saved.header("subject"), "Re: Hi  Bcc: leak@example.com");
+        assert_eq!(
+            addresses(&saved.header("to")),
+            vec!["eve@example.com", "leak@example.com"]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use mcp_core::handler::ToolError;
+use serde_json::Value;
+use tokio::process::Command;
+
+/// Which secrets manager to use: `vault` or `1password`
+pub const SECRETS_BACKEND_ENV: &str = "GOOSE_SECRETS_BACKEND";
+/// KV path listed and read from in Vault, e.g. `secret/goose`
+pub const VAULT_PATH_ENV: &str = "GOOSE_SECRETS_VAULT_PATH";
+/// 1Password vault secrets are read from. Full `op://` references must point into it when set.
+pub const OP_VAULT_ENV: &str = "GOOSE_SECRETS_OP_VAULT";
+
+const DEFAULT_VAULT_FIELD: &str = "value";
+const DEFAULT_OP_FIELD: &str = "password";
+
+/// A secrets manager, accessed through its CLI so the user's existing login is reused
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretsBackend {
+    /// HashiCorp Vault through `vault`, secrets are `key` or `key#field` under `path`
+    Vault { path: String },
+    /// 1Password through `op`, secrets are `item`, `item/field` or `op://` references, which
+    /// must be in `vault` when it's set
+    OnePassword { vault: Option<String> },
+}
+
+impl SecretsBackend {
+    pub fn from_env() -> Result<Self, String> {
+        let backend = std::env::var(SECRETS_BACKEND_ENV).map_err(|_| {
+            format!(
+                "{} is not set, set it to 'vault' or '1password'",
+                SECRETS_BACKEND_ENV
+            )
+        })?;
+        match backend.to_lowercase().as_str() {
+            "vault" => Ok(Self::Vault {
+                path: std::env::var(VAULT_PATH_ENV)
+                    .map(|path| path.trim_end_matches('/').to_string())
+                    .map_err(|_| format!("{} must be set for the vault backend", VAULT_PATH_ENV))?,
+            }),
+            "1password" | "onepassword" | "op" => Ok(Self::OnePassword {
+                vault: std::env::var(OP_VAULT_ENV).ok(),
+            }),
+            _ => Err(format!(
+                "Unknown secrets backend '{}', expected 'vault' or '1password'",
+                backend
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Vault { .. } => "vault",
+            Self::OnePassword { .. } => "1password",
+        }
+    }
+
+    /// Names of the secrets that can be injected
+    pub async fn list(&self) -> Result<Vec<String>, ToolError> {
+        match self {
+            Self::Vault { path } => {
+                let output = run(
+                    "vault",
+                    &[
+                        "kv".into(),
+                        "list".into(),
+                        "-format=json".into(),
+                        path.clone(),
+                    ],
+                )
+                .await?;
+                let keys: Vec<String> = serde_json::from_str(&output).map_err(|e| {
+                    ToolError::ExecutionError(format!("Unexpected output from vault: {}", e))
+                })?;
+                // Keys ending in / are folders
+                Ok(keys.into_iter().filter(|key| !key.ends_with('/')).collect())
+            }
+            Self::OnePassword { vault } => {
+                let mut args = vec!["item".into(), "list".into(), "--format=json".into()];
+                if let Some(vault) = vault {
+                    args.push(format!("--vault={}", vault));
+                }
+                let output = run("op", &args).await?;
+                let items: Vec<Value> = serde_json::from_str(&output).map_err(|e| {
+                    ToolError::ExecutionError(format!("Unexpected output from op: {}", e))
+                })?;
+                Ok(items
+                    .iter()
+                    .filter_map(|item| item.get("title").and_then(Value::as_str))
+                    .map(String::from)
+                    .collect())
+            }
+        }
+    }
+
+    /// The CLI arguments that read `name`, which can't reach outside the configured Vault path
+    /// or 1Password vault
+    pub fn read_args(&self, name: &str) -> Result<(&'static str, Vec<String>), ToolError> {
+        let path = name.strip_prefix("op://").unwrap_or(name);
+        if name.is_empty()
+            || name.starts_with('-')
+            || name.chars().any(char::is_control)
+            || path
+                .split('/')
+                .any(|segment| segment == "." || segment == "..")
+        {
+            return Err(ToolError::InvalidParameters(format!(
+                "Invalid secret name '{}'",
+                name
+            )));
+        }
+        match self {
+            Self::Vault { path } => {
+                let (key, field) = name.split_once('#').unwrap_or((name, DEFAULT_VAULT_FIELD));
+                Ok((
+                    "vault",
+                    vec![
+                        "kv".into(),
+                        "get".into(),
+                        format!("-field={}", field),
+                        format!("{}/{}", path, key.trim_start_matches('/')),
+                    ],
+                ))
+            }
+            Self::OnePassword { vault } => {
+                let reference = if let Some(path) = name.strip_prefix("op://") {
+                    let in_vault = path.split('/').next();
+                    if let Some(vault) = vault.as_ref().filter(|vault| in_vault != Some(vault)) {
+                        return Err(ToolError::InvalidParameters(format!(
+                            "'{}' is outside the {} vault that {} allows",
+                            name, vault, OP_VAULT_ENV
+                        )));
+                    }
+                    name.to_string()
+                } else {
+                    let vault = vault.as_ref().ok_or_else(|| {
+                        ToolError::InvalidParameters(format!(
+                            "Set {} or pass a full op:// reference for '{}'",
+                            OP_VAULT_ENV, name
+                        ))
+                    })?;
+                    match name.split_once('/') {
+                        Some((item, field)) => format!("op://{}/{}/{}", vault, item, field),
+                        None => format!("op://{}/{}/{}", vault, name, DEFAULT_OP_FIELD),
+                    }
+                };
+                Ok(("op", vec!["read".into(), "--no-newline".into(), reference]))
+            }
+        }
+    }
+
+    /// The value of `name`, which must never be returned to the model
+    pub async fn read(&self, name: &str) -> Result<String, ToolError> {
+        let (program, args) = self.read_args(name)?;
+        let value = run(program, &args).await?;
+        let value = value.trim_end_matches(['\r', '\n']).to_string();
+        if value.is_empty() {
+            return Err(ToolError::ExecutionError(format!(
+                "Secret '{}' is empty",
+                name
+            )));
+        }
+        Ok(value)
+    }
+}
+
+async fn run(program: &str, args: &[String]) -> Result<String, ToolError> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to run {}: {}", program, e)))?;
+    if !output.status.success() {
+        // Errors from these CLIs describe the failure, they don't contain secret values
+        return Err(ToolError::ExecutionError(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_args() {
+        let vault = SecretsBackend::Vault {
+            path: "secret/goose".to_string(),
+        };
+        assert_eq!(
+            vault.read_args("stripe#api_key").unwrap().1,
+            vec!["kv", "get", "-field=api_key", "secret/goose/stripe"]
+        );
+        assert_eq!(
+            vault.read_args("stripe").unwrap().1,
+            vec!["kv", "get", "-field=value", "secret/goose/stripe"]
+        );
+        assert!(vault.read_args("-address=evil").is_err());
+        assert!(vault.read_args("../../sys/config").is_err());
+        assert!(vault.read_args("team/../../other#token").is_err());
+
+        let op = SecretsBackend::OnePassword {
+            vault: Some("Dev".to_string()),
+        };
+        assert_eq!(
+            op.read_args("GitHub/token").unwrap().1[2],
+            "op://Dev/GitHub/token"
+        );
+        assert_eq!(
+            op.read_args("op://Dev/AWS/secret").unwrap().1[2],
+            "op://Dev/AWS/secret"
+        );
+        assert!(op.read_args("op://Ops/AWS/secret").is_err());
+        assert!(op.read_args("op://Dev/../Ops/AWS/secret").is_err());
+        assert!(op.read_args("../Ops/password").is_err());
+        let no_vault = SecretsBackend::OnePassword { vault: None };
+        assert!(no_vault.read_args("GitHub").is_err());
+        assert_eq!(
+            no_vault.read_args("op://Ops/AWS/secret").unwrap().1[2],
+            "op://Ops/AWS/secret"
+        );
+    }
+}
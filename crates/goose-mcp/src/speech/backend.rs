@@ -0,0 +1,479 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use mcp_core::handler::ToolError;
+use reqwest::Client;
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Path to a whisper.cpp ggml model, enables local transcription
+pub const WHISPER_MODEL_ENV: &str = "GOOSE_SPEECH_WHISPER_MODEL";
+/// The whisper.cpp command line program, `whisper-cli` by default
+pub const WHISPER_BIN_ENV: &str = "GOOSE_SPEECH_WHISPER_BIN";
+/// Key for an OpenAI compatible audio API, OPENAI_API_KEY is used when unset
+pub const SPEECH_API_KEY_ENV: &str = "GOOSE_SPEECH_API_KEY";
+/// Root of the audio API, defaults to OpenAI
+pub const SPEECH_API_HOST_ENV: &str = "GOOSE_SPEECH_API_HOST";
+/// Model used by the API for transcription, `whisper-1` by default
+pub const TRANSCRIBE_MODEL_ENV: &str = "GOOSE_SPEECH_TRANSCRIBE_MODEL";
+/// Model used by the API for speech, `tts-1` by default
+pub const SPEECH_MODEL_ENV: &str = "GOOSE_SPEECH_TTS_MODEL";
+
+const OPENAI_API_KEY_ENV: &str = "OPENAI_API_KEY";
+const DEFAULT_API_HOST: &str = "https://api.openai.com";
+const DEFAULT_WHISPER_BIN: &str = "whisper-cli";
+const DEFAULT_TRANSCRIBE_MODEL: &str = "whisper-1";
+const DEFAULT_SPEECH_MODEL: &str = "tts-1";
+pub const DEFAULT_VOICE: &str = "alloy";
+
+/// The largest file the transcription API takes
+pub const MAX_API_AUDIO_BYTES: u64 = 25 * 1024 * 1024;
+/// The longest text the speech API takes
+pub const MAX_SPEECH_CHARS: usize = 4096;
+
+/// Local transcription of long recordings is slow on a CPU
+const LOCAL_TIMEOUT: Duration = Duration::from_secs(600);
+const API_TIMEOUT: Duration = Duration::from_secs(300);
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// The mime type of an audio file, from its extension
+pub fn mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    Some(match extension.as_str() {
+        "mp3" | "mpga" | "mpeg" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" | "oga" | "opus" => "audio/ogg",
+        "flac" => "audio/flac",
+        "aac" => "audio/aac",
+        "m4a" | "mp4" => "audio/mp4",
+        "webm" => "audio/webm",
+        "aif" | "aiff" => "audio/aiff",
+        _ => return None,
+    })
+}
+
+/// The first `name` on the PATH
+pub fn find_program(name: &str) -> Option<PathBuf> {
+    if name.contains(std::path::MAIN_SEPARATOR) {
+        let path = PathBuf::from(name);
+        return path.is_file().then_some(path);
+    }
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| {
+        let candidate = dir.join(name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        let exe = candidate.with_extension("exe");
+        exe.is_file().then_some(exe)
+    })
+}
+
+async fn run(
+    program: &Path,
+    args: &[&std::ffi::OsStr],
+    stdin: Option<&str>,
+) -> Result<Vec<u8>, ToolError> {
+    let name = program
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to run {}: {}", name, e)))?;
+    if let (Some(text), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(text.as_bytes()).await.map_err(|e| {
+            ToolError::ExecutionError(format!("Failed to write to {}: {}", name, e))
+        })?;
+    }
+
+    let output = tokio::time::timeout(LOCAL_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| ToolError::ExecutionError(format!("{} timed out", name)))?
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to run {}: {}", name, e)))?;
+    if !output.status.success() {
+        return Err(ToolError::ExecutionError(format!(
+            "{} failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Transcription with whisper.cpp on this machine
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalWhisper {
+    program: PathBuf,
+    model: PathBuf,
+}
+
+impl LocalWhisper {
+    pub fn from_env() -> Result<Self, String> {
+        let model = env(WHISPER_MODEL_ENV)
+            .map(|model| PathBuf::from(shellexpand::tilde(&model).into_owned()))
+            .ok_or_else(|| format!("{} is not set", WHISPER_MODEL_ENV))?;
+        if !model.is_file() {
+            return Err(format!(
+                "The whisper model {} does not exist",
+                model.display()
+            ));
+        }
+        let bin = env(WHISPER_BIN_ENV).unwrap_or_else(|| DEFAULT_WHISPER_BIN.to_string());
+        let program = find_program(&bin).ok_or_else(|| {
+            format!(
+                "{} was not found, install whisper.cpp or set {}",
+                bin, WHISPER_BIN_ENV
+            )
+        })?;
+        Ok(Self { program, model })
+    }
+
+    pub async fn transcribe(
+        &self,
+        path: &Path,
+        language: Option<&str>,
+    ) -> Result<String, ToolError> {
+        // whisper.cpp reads 16kHz wav reliably, other formats only in some builds
+        let converted = tempfile::Builder::new()
+            .suffix(".wav")
+            .tempfile()
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        let is_wav = mime_type(path) == Some("audio/wav");
+        let input = match find_program("ffmpeg") {
+            Some(ffmpeg) if !is_wav => {
+                run(
+                    &ffmpeg,
+                    &[
+                        "-y".as_ref(),
+                        "-loglevel".as_ref(),
+                        "error".as_ref(),
+                        "-i".as_ref(),
+                        path.as_os_str(),
+                        "-ar".as_ref(),
+                        "16000".as_ref(),
+                        "-ac".as_ref(),
+                        "1".as_ref(),
+                        "-c:a".as_ref(),
+                        "pcm_s16le".as_ref(),
+                        converted.path().as_os_str(),
+                    ],
+                    None,
+                )
+                .await?;
+                converted.path()
+            }
+            _ => path,
+        };
+
+        let mut args: Vec<&std::ffi::OsStr> = vec![
+            "-m".as_ref(),
+            self.model.as_os_str(),
+            "-f".as_ref(),
+            input.as_os_str(),
+            // Plain text without timestamps or progress output
+            "-nt".as_ref(),
+            "-np".as_ref(),
+        ];
+        if let Some(language) = language {
+            args.push("-l".as_ref());
+            args.push(language.as_ref());
+        }
+        let stdout = run(&self.program, &args, None).await?;
+        Ok(clean_transcript(&String::from_utf8_lossy(&stdout)))
+    }
+}
+
+/// Join the lines whisper.cpp prints per segment into one paragraph
+fn clean_transcript(output: &str) -> String {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// An OpenAI compatible audio API
+#[derive(Debug, Clone)]
+pub struct SpeechApi {
+    host: String,
+    key: String,
+    transcribe_model: String,
+    speech_model: String,
+    client: Client,
+}
+
+impl SpeechApi {
+    pub fn from_env() -> Result<Self, String> {
+        let key = env(SPEECH_API_KEY_ENV)
+            .or_else(|| env(OPENAI_API_KEY_ENV))
+            .ok_or_else(|| {
+                format!(
+                    "neither {} nor {} is set",
+                    SPEECH_API_KEY_ENV, OPENAI_API_KEY_ENV
+                )
+            })?;
+        let host = env(SPEECH_API_HOST_ENV).unwrap_or_else(|| DEFAULT_API_HOST.to_string());
+        Ok(Self {
+            host: host.trim_end_matches('/').to_string(),
+            key,
+            transcribe_model: env(TRANSCRIBE_MODEL_ENV)
+                .unwrap_or_else(|| DEFAULT_TRANSCRIBE_MODEL.to_string()),
+            speech_model: env(SPEECH_MODEL_ENV).unwrap_or_else(|| DEFAULT_SPEECH_MODEL.to_string()),
+            client: Client::builder()
+                .timeout(API_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    async fn error(response: reqwest::Response) -> ToolError {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|body| body["error"]["message"].as_str().map(str::to_string))
+            .unwrap_or(body);
+        ToolError::ExecutionError(format!("The audio API returned {}: {}", status, message))
+    }
+
+    pub async fn transcribe(
+        &self,
+        path: &Path,
+        language: Option<&str>,
+    ) -> Result<String, ToolError> {
+        let size = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| {
+                ToolError::ExecutionError(format!("Failed to read {}: {}", path.display(), e))
+            })?
+            .len();
+        if size > MAX_API_AUDIO_BYTES {
+            return Err(ToolError::InvalidParameters(format!(
+                "{} is {} MB, the API takes files up to {} MB",
+                path.display(),
+                size / (1024 * 1024),
+                MAX_API_AUDIO_BYTES / (1024 * 1024)
+            )));
+        }
+        let audio = tokio::fs::read(path).await.map_err(|e| {
+            ToolError::ExecutionError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "audio".to_string());
+        let mut fields = vec![("model", self.transcribe_model.as_str())];
+        if let Some(language) = language {
+            fields.push(("language", language));
+        }
+        let boundary = format!(
+            "goose-speech-{}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        );
+        let body = multipart_body(
+            &boundary,
+            &fields,
+            &filename,
+            mime_type(path).unwrap_or("application/octet-stream"),
+            &audio,
+        );
+
+        let response = self
+            .client
+            .post(format!("{}/v1/audio/transcriptions", self.host))
+            .bearer_auth(&self.key)
+            .header(
+                "Content-Type",
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| {
+                ToolError::ExecutionError(format!("Failed to reach the audio API: {}", e))
+            })?;
+        if !response.status().is_success() {
+            return Err(Self::error(response).await);
+        }
+        let result: Value = response.json().await.map_err(|e| {
+            ToolError::ExecutionError(format!("Unexpected transcription response: {}", e))
+        })?;
+        result["text"]
+            .as_str()
+            .map(|text| text.trim().to_string())
+            .ok_or_else(|| {
+                ToolError::ExecutionError("The transcription response has no text".into())
+            })
+    }
+
+    /// Speak `text`, returning the audio in `format`
+    pub async fn synthesize(
+        &self,
+        text: &str,
+        voice: &str,
+        format: &str,
+    ) -> Result<Vec<u8>, ToolError> {
+        let response = self
+            .client
+            .post(format!("{}/v1/audio/speech", self.host))
+            .bearer_auth(&self.key)
+            .json(&json!({
+                "model": self.speech_model,
+                "input": text,
+                "voice": voice,
+                "response_format": format,
+            }))
+            .send()
+            .await
+            .map_err(|e| {
+                ToolError::ExecutionError(format!("Failed to reach the audio API: {}", e))
+            })?;
+        if !response.status().is_success() {
+            return Err(Self::error(response).await);
+        }
+        let audio = response
+            .bytes()
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to read the speech: {}", e)))?;
+        Ok(audio.to_vec())
+    }
+}
+
+/// A `multipart/form-data` body with text fields and one file
+fn multipart_body(
+    boundary: &str,
+    fields: &[(&str, &str)],
+    filename: &str,
+    content_type: &str,
+    data: &[u8],
+) -> Vec<u8> {
+    let mut body = Vec::with_capacity(data.len() + 512);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary, name, value
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+            boundary,
+            filename.replace('"', "'"),
+            content_type
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+/// Speech with the system's own voice, `say` on macOS and espeak-ng elsewhere. Always wav.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalVoice {
+    program: PathBuf,
+}
+
+impl LocalVoice {
+    pub fn find() -> Result<Self, String> {
+        let name = if cfg!(target_os = "macos") {
+            "say"
+        } else {
+            "espeak-ng"
+        };
+        find_program(name)
+            .map(|program| Self { program })
+            .ok_or_else(|| format!("{} was not found", name))
+    }
+
+    pub async fn synthesize(
+        &self,
+        text: &str,
+        voice: Option<&str>,
+        output: &Path,
+    ) -> Result<(), ToolError> {
+        // The text goes through stdin so that it is never read as options
+        let mut args: Vec<&std::ffi::OsStr> = if cfg!(target_os = "macos") {
+            vec![
+                "-o".as_ref(),
+                output.as_os_str(),
+                "--data-format=LEI16@22050".as_ref(),
+                "-f".as_ref(),
+                "-".as_ref(),
+            ]
+        } else {
+            vec!["-w".as_ref(), output.as_os_str(), "--stdin".as_ref()]
+        };
+        if let Some(voice) = voice {
+            args.push("-v".as_ref());
+            args.push(voice.as_ref());
+        }
+        run(&self.program, &args, Some(text)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mime_type() {
+        assert_eq!(mime_type(Path::new("note.MP3")), Some("audio/mpeg"));
+        assert_eq!(mime_type(Path::new("/tmp/voice.m4a")), Some("audio/mp4"));
+        assert_eq!(mime_type(Path::new("memo.opus")), Some("audio/ogg"));
+        assert_eq!(mime_type(Path::new("notes.txt")), None);
+        assert_eq!(mime_type(Path::new("no_extension")), None);
+    }
+
+    #[test]
+    fn test_multipart_body() {
+        let body = multipart_body(
+            "b",
+            &[("model", "whisper-1")],
+            "memo.wav",
+            "audio/wav",
+            b"RIFF",
+        );
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "--b\r\nContent-Disposition: form-data; name=\"model\"\r\n\r\nwhisper-1\r\n\
+             --b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"memo.wav\"\r\n\
+             Content-Type: audio/wav\r\n\r\nRIFF\r\n--b--\r\n"
+        );
+    }
+
+    #[test]
+    fn test_clean_transcript() {
+        assert_eq!(
+            clean_transcript("\n Hello there.\n  How are you?\n\n"),
+            "Hello there. How are you?"
+        );
+    }
+}
@@ -0,0 +1,665 @@
+use mcp_core::handler::ToolError;
+use reqwest::{Client, RequestBuilder};
+use serde_json::{json, Value};
+
+/// GitHub token, public repositories can be read without one
+pub const GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
+/// GitHub API root, for GitHub Enterprise
+pub const GITHUB_API_URL_ENV: &str = "GITHUB_API_URL";
+/// GitLab token, setting it or GITLAB_URL enables the GitLab tracker
+pub const GITLAB_TOKEN_ENV: &str = "GITLAB_TOKEN";
+/// GitLab instance, defaults to gitlab.com
+pub const GITLAB_URL_ENV: &str = "GITLAB_URL";
+/// Jira site such as `https://example.atlassian.net`, enables the Jira tracker
+pub const JIRA_URL_ENV: &str = "JIRA_URL";
+/// Jira Cloud account email, used with JIRA_API_TOKEN for basic auth
+pub const JIRA_EMAIL_ENV: &str = "JIRA_EMAIL";
+/// Jira Cloud API token, or a personal access token when JIRA_EMAIL is not set
+pub const JIRA_API_TOKEN_ENV: &str = "JIRA_API_TOKEN";
+/// Issue type used when creating Jira issues, defaults to Task
+pub const JIRA_ISSUE_TYPE_ENV: &str = "GOOSE_TRACKER_JIRA_ISSUE_TYPE";
+
+const DEFAULT_GITHUB_API_URL: &str = "https://api.github.com";
+const DEFAULT_GITLAB_URL: &str = "https://gitlab.com";
+const DEFAULT_JIRA_ISSUE_TYPE: &str = "Task";
+
+/// Whether to search issues, pull requests or both
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Issue,
+    PullRequest,
+    Any,
+}
+
+/// Open, closed or either
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemState {
+    Open,
+    Closed,
+    Any,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub author: String,
+    pub created_at: String,
+    pub body: String,
+}
+
+/// An issue or pull request, the same shape for every tracker
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    /// How the tracker refers to it, `#12` on GitHub, `#12` or `!12` on GitLab, `KEY-12` on Jira
+    pub id: String,
+    pub title: String,
+    pub state: String,
+    pub author: String,
+    pub url: String,
+    pub labels: Vec<String>,
+    pub body: String,
+    pub is_pull_request: bool,
+    pub comments: Vec<Comment>,
+}
+
+impl Item {
+    pub fn summary_line(&self) -> String {
+        let kind = if self.is_pull_request { " [PR]" } else { "" };
+        format!(
+            "{}{} {} ({}) {}",
+            self.id, kind, self.title, self.state, self.url
+        )
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{}\nAuthor: {}\nLabels: {}\n\n{}",
+            self.summary_line(),
+            self.author,
+            if self.labels.is_empty() {
+                "none".to_string()
+            } else {
+                self.labels.join(", ")
+            },
+            if self.body.trim().is_empty() {
+                "(no description)"
+            } else {
+                self.body.trim()
+            }
+        );
+        for comment in &self.comments {
+            text.push_str(&format!(
+                "\n\n--- {} at {}\n{}",
+                comment.author,
+                comment.created_at,
+                comment.body.trim()
+            ));
+        }
+        text
+    }
+}
+
+/// An issue tracker and how to authenticate with it
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackerBackend {
+    GitHub {
+        api_url: String,
+        token: Option<String>,
+    },
+    GitLab {
+        url: String,
+        token: Option<String>,
+    },
+    Jira {
+        url: String,
+        email: Option<String>,
+        token: Option<String>,
+        issue_type: String,
+    },
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+impl TrackerBackend {
+    /// Every tracker with configuration in the environment. GitHub is always available since
+    /// public repositories can be read anonymously.
+    pub fn all_from_env() -> Vec<Self> {
+        let mut backends = vec![Self::GitHub {
+            api_url: env(GITHUB_API_URL_ENV)
+                .unwrap_or_else(|| DEFAULT_GITHUB_API_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            token: env(GITHUB_TOKEN_ENV),
+        }];
+        if env(GITLAB_TOKEN_ENV).is_some() || env(GITLAB_URL_ENV).is_some() {
+            backends.push(Self::GitLab {
+                url: env(GITLAB_URL_ENV)
+                    .unwrap_or_else(|| DEFAULT_GITLAB_URL.to_string())
+                    .trim_end_matches('/')
+                    .to_string(),
+                token: env(GITLAB_TOKEN_ENV),
+            });
+        }
+        if let Some(url) = env(JIRA_URL_ENV) {
+            backends.push(Self::Jira {
+                url: url.trim_end_matches('/').to_string(),
+                email: env(JIRA_EMAIL_ENV),
+                token: env(JIRA_API_TOKEN_ENV),
+                issue_type: env(JIRA_ISSUE_TYPE_ENV)
+                    .unwrap_or_else(|| DEFAULT_JIRA_ISSUE_TYPE.to_string()),
+            });
+        }
+        backends
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::GitHub { .. } => "github",
+            Self::GitLab { .. } => "gitlab",
+            Self::Jira { .. } => "jira",
+        }
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        match self {
+            Self::GitHub { token, .. } | Self::GitLab { token, .. } | Self::Jira { token, .. } => {
+                token.is_some()
+            }
+        }
+    }
+
+    fn get_request(&self, client: &Client, url: &str) -> RequestBuilder {
+        self.authorize(client.get(url))
+    }
+
+    fn post_request(&self, client: &Client, url: &str) -> RequestBuilder {
+        self.authorize(client.post(url))
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            Self::GitHub { token, .. } => {
+                let request = request
+                    .header("Accept", "application/vnd.github+json")
+                    .header("User-Agent", "goose");
+                match token {
+                    Some(token) => request.bearer_auth(token),
+                    None => request,
+                }
+            }
+            Self::GitLab { token, .. } => match token {
+                Some(token) => request.header("PRIVATE-TOKEN", token),
+                None => request,
+            },
+            Self::Jira { email, token, .. } => match (email, token) {
+                (Some(email), Some(token)) => request.basic_auth(email, Some(token)),
+                (None, Some(token)) => request.bearer_auth(token),
+                _ => request,
+            },
+        }
+    }
+
+    /// The API path of a GitLab project, `group/project` URL encoded
+    fn gitlab_project(url: &str, project: &str) -> String {
+        format!(
+            "{}/api/v4/projects/{}",
+            url,
+            urlencoding::encode(project.trim_matches('/'))
+        )
+    }
+
+    pub async fn search(
+        &self,
+        client: &Client,
+        project: &str,
+        query: &str,
+        kind: ItemKind,
+        state: ItemState,
+        limit: usize,
+    ) -> Result<Vec<Item>, ToolError> {
+        match self {
+            Self::GitHub { api_url, .. } => {
+                let mut q = format!("{} repo:{}", query, project);
+                match kind {
+                    ItemKind::Issue => q.push_str(" is:issue"),
+                    ItemKind::PullRequest => q.push_str(" is:pr"),
+                    ItemKind::Any => {}
+                }
+                match state {
+                    ItemState::Open => q.push_str(" state:open"),
+                    ItemState::Closed => q.push_str(" state:closed"),
+                    ItemState::Any => {}
+                }
+                let url = format!(
+                    "{}/search/issues?q={}&per_page={}",
+                    api_url,
+                    urlencoding::encode(q.trim()),
+                    limit
+                );
+                let response = send_json(self.get_request(client, &url)).await?;
+                Ok(response["items"]
+                    .as_array()
+                    .map(|items| items.iter().map(github_item).collect())
+                    .unwrap_or_default())
+            }
+            Self::GitLab { url, .. } => {
+                let base = Self::gitlab_project(url, project);
+                let state = match state {
+                    ItemState::Open => "&state=opened",
+                    ItemState::Closed => "&state=closed",
+                    ItemState::Any => "",
+                };
+                let mut items = Vec::new();
+                for (path, is_mr) in [("issues", false), ("merge_requests", true)] {
+                    if (is_mr && kind == ItemKind::Issue)
+                        || (!is_mr && kind == ItemKind::PullRequest)
+                    {
+                        continue;
+                    }
+                    let url = format!(
+                        "{}/{}?search={}&per_page={}{}",
+                        base,
+                        path,
+                        urlencoding::encode(query),
+                        limit,
+                        state
+                    );
+                    let response = send_json(self.get_request(client, &url)).await?;
+                    if let Some(found) = response.as_array() {
+                        items.extend(found.iter().map(|item| gitlab_item(item, is_mr)));
+                    }
+                }
+                items.truncate(limit);
+                Ok(items)
+            }
+            Self::Jira { url, .. } => {
+                let mut jql = format!("project = \"{}\"", project);
+                if !query.trim().is_empty() {
+                    jql.push_str(&format!(" AND text ~ \"{}\"", query.replace('"', "\\\"")));
+                }
+                match state {
+                    ItemState::Open => jql.push_str(" AND statusCategory != Done"),
+                    ItemState::Closed => jql.push_str(" AND statusCategory = Done"),
+                    ItemState::Any => {}
+                }
+                jql.push_str(" ORDER BY updated DESC");
+                let url = format!(
+                    "{}/rest/api/2/search?jql={}&maxResults={}&fields=summary,status,labels,reporter,description",
+                    url,
+                    urlencoding::encode(&jql),
+                    limit
+                );
+                let response = send_json(self.get_request(client, &url)).await?;
+                let base = self.jira_browse_url();
+                Ok(response["issues"]
+                    .as_array()
+                    .map(|issues| issues.iter().map(|issue| jira_item(issue, &base)).collect())
+                    .unwrap_or_default())
+            }
+        }
+    }
+
+    /// An issue or pull request with its comments
+    pub async fn get(&self, client: &Client, project: &str, id: &str) -> Result<Item, ToolError> {
+        match self {
+            Self::GitHub { api_url, .. } => {
+                let number = github_number(id)?;
+                let base = format!("{}/repos/{}/issues/{}", api_url, project, number);
+                let mut item = github_item(&send_json(self.get_request(client, &base)).await?);
+                let comments =
+                    send_json(self.get_request(client, &format!("{}/comments", base))).await?;
+                item.comments = comments
+                    .as_array()
+                    .map(|comments| {
+                        comments
+                            .iter()
+                            .map(|c| Comment {
+                                author: str_at(c, "/user/login"),
+                                created_at: str_at(c, "/created_at"),
+                                body: str_at(c, "/body"),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Ok(item)
+            }
+            Self::GitLab { url, .. } => {
+                let (path, iid, is_mr) = gitlab_ref(id)?;
+                let base = format!("{}/{}/{}", Self::gitlab_project(url, project), path, iid);
+                let mut item =
+                    gitlab_item(&send_json(self.get_request(client, &base)).await?, is_mr);
+                let notes =
+                    send_json(self.get_request(client, &format!("{}/notes?sort=asc", base)))
+                        .await?;
+                item.comments = notes
+                    .as_array()
+                    .map(|notes| {
+                        notes
+                            .iter()
+                            // System notes record label and state changes
+                            .filter(|note| note["system"] != json!(true))
+                            .map(|note| Comment {
+                                author: str_at(note, "/author/username"),
+                                created_at: str_at(note, "/created_at"),
+                                body: str_at(note, "/body"),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Ok(item)
+            }
+            Self::Jira { url, .. } => {
+                let endpoint = format!(
+                    "{}/rest/api/2/issue/{}?fields=summary,status,labels,reporter,description,comment",
+                    url,
+                    urlencoding::encode(id.trim())
+                );
+                let issue = send_json(self.get_request(client, &endpoint)).await?;
+                let mut item = jira_item(&issue, &self.jira_browse_url());
+                item.comments = issue
+                    .pointer("/fields/comment/comments")
+                    .and_then(Value::as_array)
+                    .map(|comments| {
+                        comments
+                            .iter()
+                            .map(|c| Comment {
+                                author: str_at(c, "/author/displayName"),
+                                created_at: str_at(c, "/created"),
+                                body: str_at(c, "/body"),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Ok(item)
+            }
+        }
+    }
+
+    /// The unified diff of a pull or merge request
+    pub async fn diff(
+        &self,
+        client: &Client,
+        project: &str,
+        id: &str,
+    ) -> Result<String, ToolError> {
+        match self {
+            Self::GitHub { api_url, .. } => {
+                let url = format!("{}/repos/{}/pulls/{}", api_url, project, github_number(id)?);
+                let response = self
+                    .get(client, &url)
+                    .header("Accept", "application/vnd.github.v3.diff")
+                    .send()
+                    .await
+                    .map_err(request_error)?;
+                let status = response.status();
+                let text = response.text().await.map_err(request_error)?;
+                if !status.is_success() {
+                    return Err(api_error(status, &text));
+                }
+                Ok(text)
+            }
+            Self::GitLab { url, .. } => {
+                let (_, iid, is_mr) = gitlab_ref(id)?;
+                if !is_mr {
+                    return Err(ToolError::InvalidParameters(
+                        "Only merge requests have a diff, refer to them as !<number>".into(),
+                    ));
+                }
+                let url = format!(
+                    "{}/merge_requests/{}/changes",
+                    Self::gitlab_project(url, project),
+                    iid
+                );
+                let response = send_json(self.get_request(client, &url)).await?;
+                Ok(gitlab_diff(&response))
+            }
+            Self::Jira { .. } => Err(ToolError::InvalidParameters(
+                "Jira issues have no diff, read the linked pull request instead".into(),
+            )),
+        }
+    }
+
+    pub async fn comment(
+        &self,
+        client: &Client,
+        project: &str,
+        id: &str,
+        body: &str,
+    ) -> Result<String, ToolError> {
+        let (url, payload) = match self {
+            Self::GitHub { api_url, .. } => (
+                format!(
+                    "{}/repos/{}/issues/{}/comments",
+                    api_url,
+                    project,
+                    github_number(id)?
+                ),
+                json!({"body": body}),
+            ),
+            Self::GitLab { url, .. } => {
+                let (path, iid, _) = gitlab_ref(id)?;
+                (
+                    format!(
+                        "{}/{}/{}/notes",
+                        Self::gitlab_project(url, project),
+                        path,
+                        iid
+                    ),
+                    json!({"body": body}),
+                )
+            }
+            Self::Jira { url, .. } => (
+                format!(
+                    "{}/rest/api/2/issue/{}/comment",
+                    url,
+                    urlencoding::encode(id.trim())
+                ),
+                json!({"body": body}),
+            ),
+        };
+        let response = send_json(self.post_request(client, &url).json(&payload)).await?;
+        let link = ["/html_url", "/web_url", "/self"]
+            .iter()
+            .map(|pointer| str_at(&response, pointer))
+            .find(|link| !link.is_empty())
+            .unwrap_or_default();
+        Ok(format!("Commented on {} {}", id, link).trim().to_string())
+    }
+
+    pub async fn create_issue(
+        &self,
+        client: &Client,
+        project: &str,
+        title: &str,
+        body: &str,
+        labels: &[String],
+    ) -> Result<String, ToolError> {
+        let (url, payload) = match self {
+            Self::GitHub { api_url, .. } => (
+                format!("{}/repos/{}/issues", api_url, project),
+                json!({"title": title, "body": body, "labels": labels}),
+            ),
+            Self::GitLab { url, .. } => (
+                format!("{}/issues", Self::gitlab_project(url, project)),
+                json!({"title": title, "description": body, "labels": labels.join(",")}),
+            ),
+            Self::Jira {
+                url, issue_type, ..
+            } => (
+                format!("{}/rest/api/2/issue", url),
+                json!({"fields": {
+                    "project": {"key": project},
+                    "summary": title,
+                    "description": body,
+                    "issuetype": {"name": issue_type},
+                    "labels": labels,
+                }}),
+            ),
+        };
+        let response = send_json(self.post_request(client, &url).json(&payload)).await?;
+        let created = match self {
+            Self::GitHub { .. } => {
+                format!("#{} {}", response["number"], str_at(&response, "/html_url"))
+            }
+            Self::GitLab { .. } => {
+                format!("#{} {}", response["iid"], str_at(&response, "/web_url"))
+            }
+            Self::Jira { .. } => {
+                let key = str_at(&response, "/key");
+                format!("{} {}/{}", key, self.jira_browse_url(), key)
+            }
+        };
+        Ok(format!("Created {}", created))
+    }
+
+    fn jira_browse_url(&self) -> String {
+        match self {
+            Self::Jira { url, .. } => format!("{}/browse", url),
+            _ => String::new(),
+        }
+    }
+}
+
+fn request_error(e: reqwest::Error) -> ToolError {
+    ToolError::ExecutionError(format!("Request to the tracker failed: {}", e))
+}
+
+fn api_error(status: reqwest::StatusCode, body: &str) -> ToolError {
+    let detail = serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|body| {
+            ["/message", "/error", "/errorMessages/0"]
+                .iter()
+                .map(|pointer| str_at(&body, pointer))
+                .find(|message| !message.is_empty())
+        })
+        .unwrap_or_else(|| body.chars().take(200).collect());
+    let hint = match status.as_u16() {
+        401 | 403 => " Check the tracker's token and its permissions.",
+        404 => " Check the project or repository name, private ones need a token.",
+        _ => "",
+    };
+    ToolError::ExecutionError(format!("Tracker returned {}: {}.{}", status, detail, hint))
+}
+
+async fn send_json(request: RequestBuilder) -> Result<Value, ToolError> {
+    let response = request.send().await.map_err(request_error)?;
+    let status = response.status();
+    let text = response.text().await.map_err(request_error)?;
+    if !status.is_success() {
+        return Err(api_error(status, &text));
+    }
+    serde_json::from_str(&text).map_err(|e| {
+        ToolError::ExecutionError(format!("Unexpected response from the tracker: {}", e))
+    })
+}
+
+fn str_at(value: &Value, pointer: &str) -> String {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn github_number(id: &str) -> Result<u64, ToolError> {
+    id.trim()
+        .trim_start_matches('#')
+        .parse()
+        .map_err(|_| ToolError::InvalidParameters(format!("'{}' is not an issue number", id)))
+}
+
+/// GitLab issues are `#12` or `12`, merge requests `!12`
+fn gitlab_ref(id: &str) -> Result<(&'static str, u64, bool), ToolError> {
+    let id = id.trim();
+    let (path, number, is_mr) = match id.strip_prefix('!') {
+        Some(number) => ("merge_requests", number, true),
+        None => ("issues", id.trim_start_matches('#'), false),
+    };
+    let iid = number.parse().map_err(|_| {
+        ToolError::InvalidParameters(format!(
+            "'{}' is not an issue (#12) or merge request (!12)",
+            id
+        ))
+    })?;
+    Ok((path, iid, is_mr))
+}
+
+fn label_names(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|labels| {
+            labels
+                .iter()
+                .filter_map(|label| label.as_str().or_else(|| label["name"].as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) fn github_item(value: &Value) -> Item {
+    Item {
+        id: format!("#{}", value["number"]),
+        title: str_at(value, "/title"),
+        state: str_at(value, "/state"),
+        author: str_at(value, "/user/login"),
+        url: str_at(value, "/html_url"),
+        labels: label_names(value.get("labels")),
+        body: str_at(value, "/body"),
+        is_pull_request: value.get("pull_request").is_some(),
+        comments: Vec::new(),
+    }
+}
+
+pub(crate) fn gitlab_item(value: &Value, is_mr: bool) -> Item {
+    Item {
+        id: format!("{}{}", if is_mr { "!" } else { "#" }, value["iid"]),
+        title: str_at(value, "/title"),
+        state: str_at(value, "/state"),
+        author: str_at(value, "/author/username"),
+        url: str_at(value, "/web_url"),
+        labels: label_names(value.get("labels")),
+        body: str_at(value, "/description"),
+        is_pull_request: is_mr,
+        comments: Vec::new(),
+    }
+}
+
+pub(crate) fn jira_item(value: &Value, browse_url: &str) -> Item {
+    let key = str_at(value, "/key");
+    Item {
+        url: format!("{}/{}", browse_url, key),
+        id: key,
+        title: str_at(value, "/fields/summary"),
+        state: str_at(value, "/fields/status/name"),
+        author: str_at(value, "/fields/reporter/displayName"),
+        labels: label_names(value.pointer("/fields/labels")),
+        body: str_at(value, "/fields/description"),
+        is_pull_request: false,
+        comments: Vec::new(),
+    }
+}
+
+pub(crate) fn gitlab_diff(changes: &Value) -> String {
+    changes["changes"]
+        .as_array()
+        .map(|changes| {
+            changes
+                .iter()
+                .map(|change| {
+                    format!(
+                        "diff --git a/{old} b/{new}\n--- a/{old}\n+++ b/{new}\n{diff}",
+                        old = str_at(change, "/old_path"),
+                        new = str_at(change, "/new_path"),
+                        diff = str_at(change, "/diff")
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,622 @@
+mod backend;
+
+use indoc::{formatdoc, indoc};
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::{future::Future, pin::Pin, time::Duration};
+use tokio::{process::Command, sync::mpsc};
+
+use mcp_core::{
+    handler::{PromptError, ResourceError, ToolError},
+    prompt::Prompt,
+    protocol::{JsonRpcMessage, ServerCapabilities},
+    resource::Resource,
+    tool::{Tool, ToolAnnotations},
+    Content,
+};
+use mcp_server::router::CapabilitiesBuilder;
+use mcp_server::Router;
+
+use backend::{Item, ItemKind, ItemState, TrackerBackend};
+
+/// Set to `false` to let the agent comment on and create issues, trackers are read-only
+/// otherwise
+pub const TRACKER_READ_ONLY_ENV: &str = "GOOSE_TRACKER_READ_ONLY";
+/// Which tracker tools use when none is named: `github`, `gitlab` or `jira`
+pub const TRACKER_DEFAULT_ENV: &str = "GOOSE_TRACKER";
+/// Repository, GitLab project path or Jira project key used when none is given. GitHub and
+/// GitLab fall back to the working directory's `origin` remote.
+pub const TRACKER_PROJECT_ENV: &str = "GOOSE_TRACKER_PROJECT";
+
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+const MAX_SEARCH_LIMIT: usize = 100;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `owner/repo` or `group/project` from a git remote URL on `host`
+fn project_from_remote(remote: &str, host: &str) -> Option<String> {
+    let remote = remote.trim();
+    let path = if let Some(rest) = remote.strip_prefix("git@") {
+        rest.strip_prefix(host)?.strip_prefix(':')?
+    } else {
+        let without_scheme = remote.split_once("://")?.1;
+        let without_user = without_scheme
+            .split_once('@')
+            .map_or(without_scheme, |(_, rest)| rest);
+        without_user.strip_prefix(host)?.strip_prefix('/')?
+    };
+    let path = path.trim_end_matches('/').trim_end_matches(".git");
+    path.contains('/').then(|| path.to_string())
+}
+
+fn host_of(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme.split('/').next().unwrap_or_default()
+}
+
+/// An extension for reading and, when allowed, writing to GitHub, GitLab and Jira
+#[derive(Clone)]
+pub struct TrackerRouter {
+    tools: Vec<Tool>,
+    instructions: String,
+    backends: Vec<TrackerBackend>,
+    default_backend: String,
+    default_project: Option<String>,
+    read_only: bool,
+    client: Client,
+}
+
+impl Default for TrackerRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrackerRouter {
+    pub fn new() -> Self {
+        let read_only = std::env::var(TRACKER_READ_ONLY_ENV)
+            .map(|value| !matches!(value.trim().to_lowercase().as_str(), "false" | "0" | "no"))
+            .unwrap_or(true);
+        Self::with_backends(
+            TrackerBackend::all_from_env(),
+            std::env::var(TRACKER_DEFAULT_ENV).ok(),
+            std::env::var(TRACKER_PROJECT_ENV).ok(),
+            read_only,
+        )
+    }
+
+    pub fn with_backends(
+        backends: Vec<TrackerBackend>,
+        default_backend: Option<String>,
+        default_project: Option<String>,
+        read_only: bool,
+    ) -> Self {
+        let names: Vec<&str> = backends.iter().map(|backend| backend.name()).collect();
+        // Configuring another tracker is a stronger signal than GitHub's anonymous access
+        let default_backend = default_backend
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| names.contains(&name.as_str()))
+            .or_else(|| {
+                names
+                    .iter()
+                    .find(|name| **name != "github")
+                    .map(|n| n.to_string())
+            })
+            .unwrap_or_else(|| "github".to_string());
+
+        let target_properties = json!({
+            "tracker": {
+                "type": "string",
+                "enum": names,
+                "description": format!("Which tracker to use, defaults to {}", default_backend)
+            },
+            "project": {
+                "type": "string",
+                "description": "GitHub owner/repo, GitLab group/project or Jira project key. Defaults to the configured project or the repository's origin remote"
+            }
+        });
+        let with_target = |mut properties: Value| {
+            if let (Some(properties), Some(target)) =
+                (properties.as_object_mut(), target_properties.as_object())
+            {
+                properties.extend(target.clone());
+            }
+            properties
+        };
+        let read_only_annotations = |title: &str| {
+            Some(ToolAnnotations {
+                title: Some(title.to_string()),
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: true,
+            })
+        };
+        let write_annotations = |title: &str| {
+            Some(ToolAnnotations {
+                title: Some(title.to_string()),
+                read_only_hint: false,
+                destructive_hint: false,
+                idempotent_hint: false,
+                open_world_hint: true,
+            })
+        };
+
+        let search_issues = Tool::new(
+            "search_issues",
+            indoc! {r#"
+                Search a project's issues and pull requests (merge requests on GitLab) by text. Returns one line
+                per result with its id, title, state and link, use get_issue to read one.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["query"],
+                "properties": with_target(json!({
+                    "query": {"type": "string", "description": "Words to search for, may be empty to list recent items"},
+                    "kind": {"type": "string", "enum": ["issue", "pr", "any"], "default": "any"},
+                    "state": {"type": "string", "enum": ["open", "closed", "any"], "default": "open"},
+                    "limit": {"type": "integer", "default": DEFAULT_SEARCH_LIMIT, "maximum": MAX_SEARCH_LIMIT}
+                }))
+            }),
+            read_only_annotations("Search Issues"),
+        );
+
+        let get_issue = Tool::new(
+            "get_issue",
+            "Read an issue or pull request with its description and comments.",
+            json!({
+                "type": "object",
+                "required": ["id"],
+                "properties": with_target(json!({
+                    "id": {"type": "string", "description": "#12 on GitHub, #12 or !12 (merge request) on GitLab, KEY-12 on Jira"}
+                }))
+            }),
+            read_only_annotations("Read Issue"),
+        );
+
+        let get_pr_diff = Tool::new(
+            "get_pr_diff",
+            "Fetch the unified diff of a GitHub pull request or GitLab merge request.",
+            json!({
+                "type": "object",
+                "required": ["id"],
+                "properties": with_target(json!({
+                    "id": {"type": "string", "description": "#12 on GitHub, !12 on GitLab"}
+                }))
+            }),
+            read_only_annotations("Pull Request Diff"),
+        );
+
+        let comment_on_issue = Tool::new(
+            "comment_on_issue",
+            "Post a comment on an issue or pull request. The comment is public, draft it carefully.",
+            json!({
+                "type": "object",
+                "required": ["id", "body"],
+                "properties": with_target(json!({
+                    "id": {"type": "string", "description": "The issue or pull request to comment on"},
+                    "body": {"type": "string", "description": "The comment, in the tracker's markup"}
+                }))
+            }),
+            write_annotations("Comment On Issue"),
+        );
+
+        let create_issue = Tool::new(
+            "create_issue",
+            "Open a new issue. Search first to avoid creating a duplicate.",
+            json!({
+                "type": "object",
+                "required": ["title", "body"],
+                "properties": with_target(json!({
+                    "title": {"type": "string"},
+                    "body": {"type": "string", "description": "The description, in the tracker's markup"},
+                    "labels": {"type": "array", "items": {"type": "string"}}
+                }))
+            }),
+            write_annotations("Create Issue"),
+        );
+
+        let mut tools = vec![search_issues, get_issue, get_pr_diff];
+        if !read_only {
+            tools.push(comment_on_issue);
+            tools.push(create_issue);
+        }
+
+        let trackers = backends
+            .iter()
+            .map(|backend| {
+                let auth = if backend.is_authenticated() {
+                    "authenticated"
+                } else {
+                    "anonymous, public projects only"
+                };
+                format!("- {} ({})", backend.name(), auth)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mode = if read_only {
+            format!(
+                "The trackers are read-only. Draft comments and issues in your reply for the user to post, \
+                or ask them to set {}=false to let you post them.",
+                TRACKER_READ_ONLY_ENV
+            )
+        } else {
+            "You can comment on and create issues. Everything you post is public and attributed to the \
+            user, so confirm the wording with them before posting."
+                .to_string()
+        };
+        let instructions = formatdoc! {r#"
+            The tracker extension reads issues and pull requests so you can triage bugs and draft fixes
+            with the real context. Available trackers:
+            {trackers}
+
+            The default tracker is {default_backend}. {mode}
+            "#,
+            trackers = trackers,
+            default_backend = default_backend,
+            mode = mode,
+        };
+
+        Self {
+            tools,
+            instructions,
+            backends,
+            default_backend,
+            default_project: default_project.filter(|project| !project.trim().is_empty()),
+            read_only,
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    fn backend(&self, arguments: &Value) -> Result<&TrackerBackend, ToolError> {
+        let name = arguments
+            .get("tracker")
+            .and_then(Value::as_str)
+            .map(|name| name.trim().to_lowercase())
+            .unwrap_or_else(|| self.default_backend.clone());
+        self.backends
+            .iter()
+            .find(|backend| backend.name() == name)
+            .ok_or_else(|| {
+                ToolError::InvalidParameters(format!(
+                    "The {} tracker is not configured, available: {}",
+                    name,
+                    self.backends
+                        .iter()
+                        .map(|backend| backend.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            })
+    }
+
+    async fn project(
+        &self,
+        backend: &TrackerBackend,
+        arguments: &Value,
+    ) -> Result<String, ToolError> {
+        if let Some(project) = arguments
+            .get("project")
+            .and_then(Value::as_str)
+            .filter(|project| !project.trim().is_empty())
+        {
+            return Ok(project.trim().to_string());
+        }
+        if let Some(project) = &self.default_project {
+            return Ok(project.trim().to_string());
+        }
+        let host = match backend {
+            TrackerBackend::GitHub { api_url, .. } => {
+                // The API of github.com lives on its own host, Enterprise serves it under /api
+                match host_of(api_url) {
+                    "api.github.com" => "github.com",
+                    host => host,
+                }
+            }
+            TrackerBackend::GitLab { url, .. } => host_of(url),
+            TrackerBackend::Jira { .. } => "",
+        };
+        if !host.is_empty() {
+            let output = Command::new("git")
+                .args(["remote", "get-url", "origin"])
+                .output()
+                .await;
+            if let Some(project) = output
+                .ok()
+                .filter(|output| output.status.success())
+                .and_then(|output| {
+                    project_from_remote(&String::from_utf8_lossy(&output.stdout), host)
+                })
+            {
+                return Ok(project);
+            }
+        }
+        Err(ToolError::InvalidParameters(format!(
+            "No project given, pass 'project' or set {}",
+            TRACKER_PROJECT_ENV
+        )))
+    }
+
+    fn ensure_writable(&self) -> Result<(), ToolError> {
+        if self.read_only {
+            return Err(ToolError::ExecutionError(format!(
+                "The trackers are read-only, set {}=false to allow posting",
+                TRACKER_READ_ONLY_ENV
+            )));
+        }
+        Ok(())
+    }
+
+    async fn search_issues(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        let backend = self.backend(&arguments)?;
+        let project = self.project(backend, &arguments).await?;
+        let query = arguments
+            .get("query")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let kind = match arguments.get("kind").and_then(Value::as_str) {
+            Some("issue") => ItemKind::Issue,
+            Some("pr") => ItemKind::PullRequest,
+            _ => ItemKind::Any,
+        };
+        let state = match arguments.get("state").and_then(Value::as_str) {
+            Some("closed") => ItemState::Closed,
+            Some("any") => ItemState::Any,
+            _ => ItemState::Open,
+        };
+        let limit = arguments
+            .get("limit")
+            .and_then(Value::as_u64)
+            .map_or(DEFAULT_SEARCH_LIMIT, |limit| limit as usize)
+            .clamp(1, MAX_SEARCH_LIMIT);
+
+        let items = backend
+            .search(&self.client, &project, query, kind, state, limit)
+            .await?;
+        let text = if items.is_empty() {
+            format!("Nothing in {} matched", project)
+        } else {
+            items
+                .iter()
+                .map(Item::summary_line)
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        Ok(vec![Content::text(text)])
+    }
+
+    async fn get_issue(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        let backend = self.backend(&arguments)?;
+        let project = self.project(backend, &arguments).await?;
+        let item = backend
+            .get(&self.client, &project, required_str(&arguments, "id")?)
+            .await?;
+        Ok(vec![Content::text(item.to_text())])
+    }
+
+    async fn get_pr_diff(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        let backend = self.backend(&arguments)?;
+        let project = self.project(backend, &arguments).await?;
+        let diff = backend
+            .diff(&self.client, &project, required_str(&arguments, "id")?)
+            .await?;
+        Ok(vec![Content::text(diff)])
+    }
+
+    async fn comment_on_issue(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        self.ensure_writable()?;
+        let backend = self.backend(&arguments)?;
+        let project = self.project(backend, &arguments).await?;
+        let result = backend
+            .comment(
+                &self.client,
+                &project,
+                required_str(&arguments, "id")?,
+                required_str(&arguments, "body")?,
+            )
+            .await?;
+        Ok(vec![Content::text(result)])
+    }
+
+    async fn create_issue(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        self.ensure_writable()?;
+        let backend = self.backend(&arguments)?;
+        let project = self.project(backend, &arguments).await?;
+        let labels: Vec<String> = arguments
+            .get("labels")
+            .and_then(Value::as_array)
+            .map(|labels| {
+                labels
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let result = backend
+            .create_issue(
+                &self.client,
+                &project,
+                required_str(&arguments, "title")?,
+                required_str(&arguments, "body")?,
+                &labels,
+            )
+            .await?;
+        Ok(vec![Content::text(result)])
+    }
+}
+
+fn required_str<'a>(arguments: &'a Value, name: &str) -> Result<&'a str, ToolError> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| ToolError::InvalidParameters(format!("Missing '{}' parameter", name)))
+}
+
+impl Router for TrackerRouter {
+    fn name(&self) -> String {
+        "tracker".to_string()
+    }
+
+    fn instructions(&self) -> String {
+        self.instructions.clone()
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        CapabilitiesBuilder::new().with_tools(false).build()
+    }
+
+    fn list_tools(&self) -> Vec<Tool> {
+        self.tools.clone()
+    }
+
+    fn call_tool(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        _notifier: mpsc::Sender<JsonRpcMessage>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>> {
+        let this = self.clone();
+        let tool_name = tool_name.to_string();
+
+        Box::pin(async move {
+            match tool_name.as_str() {
+                "search_issues" => this.search_issues(arguments).await,
+                "get_issue" => this.get_issue(arguments).await,
+                "get_pr_diff" => this.get_pr_diff(arguments).await,
+                "comment_on_issue" => this.comment_on_issue(arguments).await,
+                "create_issue" => this.create_issue(arguments).await,
+                _ => Err(ToolError::NotFound(format!("Tool {} not found", tool_name))),
+            }
+        })
+    }
+
+    fn list_resources(&self) -> Vec<Resource> {
+        Vec::new()
+    }
+
+    fn read_resource(
+        &self,
+        _uri: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
+        Box::pin(async move { Ok("".to_string()) })
+    }
+
+    fn list_prompts(&self) -> Vec<Prompt> {
+        vec![]
+    }
+
+    fn get_prompt(
+        &self,
+        prompt_name: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>> {
+        let prompt_name = prompt_name.to_string();
+        Box::pin(async move {
+            Err(PromptError::NotFound(format!(
+                "Prompt {} not found",
+                prompt_name
+            )))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backend::{github_item, gitlab_diff, gitlab_item, jira_item};
+    use super::*;
+
+    fn github() -> TrackerBackend {
+        TrackerBackend::GitHub {
+            api_url: "https://api.github.com".to_string(),
+            token: None,
+        }
+    }
+
+    #[test]
+    fn test_project_from_remote() {
+        assert_eq!(
+            project_from_remote("git@github.com:block/goose.git\n", "github.com").as_deref(),
+            Some("block/goose")
+        );
+        assert_eq!(
+            project_from_remote(
+                "https://token@gitlab.example.com/group/sub/app",
+                "gitlab.example.com"
+            )
+            .as_deref(),
+            Some("group/sub/app")
+        );
+        assert_eq!(
+            project_from_remote("https://github.com/block/goose", "gitlab.com"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_items_from_each_tracker() {
+        let issue = github_item(&json!({
+            "number": 12,
+            "title": "Crash on start",
+            "state": "open",
+            "user": {"login": "alice"},
+            "html_url": "https://github.com/block/goose/pull/12",
+            "labels": [{"name": "bug"}],
+            "body": "It crashes",
+            "pull_request": {}
+        }));
+        assert_eq!(issue.id, "#12");
+        assert!(issue.is_pull_request);
+        assert_eq!(issue.labels, vec!["bug"]);
+        assert!(issue.to_text().contains("Author: alice"));
+
+        let mr = gitlab_item(&json!({"iid": 3, "title": "Fix", "labels": ["ci"]}), true);
+        assert_eq!(mr.id, "!3");
+        assert_eq!(mr.labels, vec!["ci"]);
+
+        let jira = jira_item(
+            &json!({"key": "APP-7", "fields": {"summary": "Slow", "status": {"name": "To Do"}}}),
+            "https://example.atlassian.net/browse",
+        );
+        assert_eq!(jira.url, "https://example.atlassian.net/browse/APP-7");
+        assert_eq!(jira.state, "To Do");
+
+        let diff = gitlab_diff(&json!({"changes": [
+            {"old_path": "a.rs", "new_path": "a.rs", "diff": "@@ -1 +1 @@\n-a\n+b\n"}
+        ]}));
+        assert!(diff.starts_with("diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n@@"));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_by_default() {
+        let router =
+            TrackerRouter::with_backends(vec![github()], None, Some("block/goose".into()), true);
+        let names: Vec<String> = router.list_tools().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, vec!["search_issues", "get_issue", "get_pr_diff"]);
+        assert!(router.instructions().contains("read-only"));
+
+        let (tx, _rx) = mpsc::channel(1);
+        let result = router
+            .call_tool(
+                "create_issue",
+                json!({"title": "t", "body": "b"}),
+                tx.clone(),
+            )
+            .await;
+        assert!(
+            matches!(result, Err(ToolError::ExecutionError(e)) if e.contains(TRACKER_READ_ONLY_ENV))
+        );
+
+        let result = router
+            .call_tool("get_issue", json!({"id": "1", "tracker": "jira"}), tx)
+            .await;
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+
+        let writable =
+            TrackerRouter::with_backends(vec![github()], Some("gitlab".into()), None, false);
+        assert_eq!(writable.list_tools().len(), 5);
+        assert_eq!(writable.default_backend, "github");
+    }
+}
@@ -1,6 +1,7 @@
 use goose_server::openapi;
 use std::env;
 use std::fs;
+use std::process;
 
 fn main() {
     let schema = openapi::generate_schema();
@@ -9,6 +10,20 @@ fn main() {
     let current_dir = env::current_dir().unwrap();
     let output_path = current_dir.join("ui").join("desktop").join("openapi.json");
 
+    // With --check, only verify the committed schema matches the server, for CI
+    if env::args().any(|arg| arg == "--check") {
+        let existing = fs::read_to_string(&output_path).unwrap_or_default();
+        if existing != schema {
+            eprintln!(
+                "{} is out of date, run `just generate-openapi` to regenerate it and the TypeScript client",
+                output_path.display()
+            );
+            process::exit(1);
+        }
+        println!("OpenAPI schema at {} is up to date", output_path.display());
+        return;
+    }
+
     // Ensure parent directory exists
     if let Some(parent) = output_path.parent() {
         fs::create_dir_all(parent).unwrap();
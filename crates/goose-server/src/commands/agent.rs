@@ -1,28 +1,80 @@
 use std::sync::Arc;
 
+use crate::commands::daemon::{shutdown_signal, PidFile};
 use crate::configuration;
 use crate::state;
+use crate::state_store::{clear_stale_approvals, APPROVALS};
+use crate::webhooks::WebhookDispatcher;
 use anyhow::Result;
+use axum::middleware;
 use etcetera::{choose_app_strategy, AppStrategy};
 use goose::agents::Agent;
 use goose::config::APP_STRATEGY;
 use goose::scheduler::Scheduler as GooseScheduler;
-use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use std::net::SocketAddr;
+use tracing::{info, warn};
 
-pub async fn run() -> Result<()> {
+/// Run the agent server until it is told to shut down. A `daemonized` server was started by
+/// `goosed agent --daemon`: it logs to rotating files and records its PID.
+pub async fn run(daemonized: bool) -> Result<()> {
     // Initialize logging
-    crate::logging::setup_logging(Some("goosed"))?;
+    if daemonized {
+        crate::logging::setup_daemon_logging()?;
+    } else {
+        crate::logging::setup_logging(Some("goosed"))?;
+    }
+    let _pid_file = if daemonized {
+        Some(PidFile::create()?)
+    } else {
+        None
+    };
 
     let settings = configuration::Settings::new()?;
 
-    let secret_key =
-        std::env::var("GOOSE_SERVER__SECRET_KEY").unwrap_or_else(|_| "test".to_string());
+    let secret_key = if daemonized {
+        crate::commands::daemon::require_secret_key()?
+    } else {
+        std::env::var(crate::commands::daemon::SECRET_KEY_ENV)
+            .unwrap_or_else(|_| "test".to_string())
+    };
 
     let new_agent = Agent::new();
     let agent_ref = Arc::new(new_agent);
 
-    let app_state = state::AppState::new(agent_ref.clone(), secret_key.clone()).await;
+    let store = settings.state_store.build().await?;
+    info!(
+        "server state kept in {:?} store",
+        settings.state_store.backend
+    );
+    let users = settings.users.load()?;
+    if !users.is_empty() {
+        info!("serving {} users besides the owner", users.len());
+    }
+    let webhooks = WebhookDispatcher::new(&settings.webhooks);
+    let app_state = state::AppState::with_store(
+        agent_ref.clone(),
+        secret_key.clone(),
+        store,
+        users,
+        webhooks,
+    )
+    .await;
+
+    // Approvals left by an earlier run can never be answered. Other servers sharing the store
+    // may still be waiting on theirs, so a shared store is left alone.
+    if !settings.state_store.backend.is_shared() {
+        let namespaces: Vec<String> = app_state
+            .users
+            .all()
+            .iter()
+            .map(|user| user.namespace(APPROVALS))
+            .collect();
+        match clear_stale_approvals(&app_state.store, &namespaces, &app_state.server_id).await {
+            Ok(0) => {}
+            Ok(cleared) => info!("cleared {} approvals left by the last run", cleared),
+            Err(e) => warn!("Failed to clear stale approvals: {}", e),
+        }
+    }
 
     let schedule_file_path = choose_app_strategy(APP_STRATEGY.clone())?
         .data_dir()
@@ -31,15 +83,23 @@ pub async fn run() -> Result<()> {
     let scheduler_instance = GooseScheduler::new(schedule_file_path).await?;
     app_state.set_scheduler(scheduler_instance).await;
 
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
-
-    let app = crate::routes::configure(app_state).layer(cors);
+    let loopback = settings.is_loopback();
+    let mut app = crate::routes::configure(app_state.clone(), &settings.body_limit);
+    if let Some(limiter) = settings.rate_limit.limiter(loopback) {
+        app = app.layer(middleware::from_fn_with_state(
+            (limiter, app_state),
+            crate::limits::rate_limit,
+        ));
+    }
+    let app = app.layer(settings.cors.layer(loopback));
 
     let listener = tokio::net::TcpListener::bind(settings.socket_addr()).await?;
     info!("listening on {}", listener.local_addr()?);
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
     Ok(())
 }
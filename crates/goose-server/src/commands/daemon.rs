@@ -0,0 +1,384 @@
+use anyhow::{bail, Context, Result};
+use etcetera::{choose_app_strategy, AppStrategy};
+use goose::config::APP_STRATEGY;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+const PID_FILENAME: &str = "goosed.pid";
+/// How long `start` waits to see whether the server comes up before reporting success
+const STARTUP_GRACE: Duration = Duration::from_millis(500);
+/// How long `stop` waits for the server to exit after asking it to
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SYSTEMD_UNIT: &str = "goosed.service";
+const LAUNCHD_LABEL: &str = "com.block.goose.goosed";
+
+pub const SECRET_KEY_ENV: &str = "GOOSE_SERVER__SECRET_KEY";
+/// What the server falls back to without a secret key, fine for a server run by the desktop
+/// app but never for one left running in the background
+const DEFAULT_SECRET_KEY: &str = "test";
+
+/// The secret key set in the environment. A server that runs unattended must have one of its
+/// own, anyone who can reach it could use the default.
+pub fn require_secret_key() -> Result<String> {
+    match std::env::var(SECRET_KEY_ENV) {
+        Ok(key) if !key.trim().is_empty() && key != DEFAULT_SECRET_KEY => Ok(key),
+        _ => bail!(
+            "{} must be set to a secret of your own to run goosed in the background",
+            SECRET_KEY_ENV
+        ),
+    }
+}
+
+/// Where the PID of the background server is kept, in the app data directory
+pub fn pid_path() -> Result<PathBuf> {
+    Ok(choose_app_strategy(APP_STRATEGY.clone())?
+        .data_dir()
+        .join(PID_FILENAME))
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// The PID of the background server if it is running. A PID file left behind by a server
+/// that died is removed.
+pub fn running_pid() -> Result<Option<u32>> {
+    let path = pid_path()?;
+    let Some(pid) = read_pid(&path) else {
+        return Ok(None);
+    };
+    if is_alive(pid) {
+        Ok(Some(pid))
+    } else {
+        let _ = fs::remove_file(&path);
+        Ok(None)
+    }
+}
+
+/// Removes the PID file when the server shuts down
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Record this process as the background server
+    pub fn create() -> Result<Self> {
+        let path = pid_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, std::process::id().to_string())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(PidFile { path })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Only remove the file if it still names this process
+        if read_pid(&self.path) == Some(std::process::id()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Start the agent server as a detached background process
+pub fn start() -> Result<()> {
+    require_secret_key()?;
+    if let Some(pid) = running_pid()? {
+        bail!("goosed is already running with PID {}", pid);
+    }
+
+    let exe = std::env::current_exe().context("Failed to get current executable path")?;
+    let mut command = Command::new(exe);
+    command
+        .args(["agent", "--daemon-child"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    detach(&mut command);
+    let mut child = command.spawn().context("Failed to start goosed")?;
+
+    std::thread::sleep(STARTUP_GRACE);
+    if let Some(status) = child.try_wait()? {
+        bail!(
+            "goosed exited straight away ({}), see the logs in {}",
+            status,
+            crate::logging::daemon_log_directory()?.display()
+        );
+    }
+
+    println!("goosed started with PID {}", child.id());
+    println!(
+        "Logs are written to {}",
+        crate::logging::daemon_log_directory()?.display()
+    );
+    Ok(())
+}
+
+/// Stop the background server and wait for it to exit
+pub fn stop() -> Result<()> {
+    let Some(pid) = running_pid()? else {
+        println!("goosed is not running");
+        return Ok(());
+    };
+
+    terminate(pid)?;
+    let started = std::time::Instant::now();
+    while is_alive(pid) {
+        if started.elapsed() > STOP_TIMEOUT {
+            bail!(
+                "goosed (PID {}) did not exit within {:?}",
+                pid,
+                STOP_TIMEOUT
+            );
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    let _ = fs::remove_file(pid_path()?);
+    println!("goosed (PID {}) stopped", pid);
+    Ok(())
+}
+
+pub fn status() -> Result<()> {
+    match running_pid()? {
+        Some(pid) => println!("goosed is running with PID {}", pid),
+        None => println!("goosed is not running"),
+    }
+    Ok(())
+}
+
+/// Resolves when the server should shut down, on Ctrl-C or, on Unix, SIGTERM from `stop`
+/// or the service manager
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("shutting down");
+}
+
+#[cfg(unix)]
+fn detach(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    // A process group of its own keeps it running when the terminal that started it closes
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn detach(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(windows)]
+fn is_alive(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+}
+
+#[cfg(unix)]
+fn terminate(pid: u32) -> Result<()> {
+    let status = Command::new("kill")
+        .args(["-TERM", &pid.to_string()])
+        .status()
+        .context("Failed to run kill")?;
+    if !status.success() {
+        bail!("Failed to stop goosed (PID {})", pid);
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn terminate(pid: u32) -> Result<()> {
+    let status = Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/F"])
+        .status()
+        .context("Failed to run taskkill")?;
+    if !status.success() {
+        bail!("Failed to stop goosed (PID {})", pid);
+    }
+    Ok(())
+}
+
+/// A systemd user unit running the server in the foreground, restarted when it fails
+pub fn systemd_unit(exe: &Path, secret_key: &str) -> String {
+    // Quoted for systemd, which also expands % specifiers
+    let secret_key = secret_key
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!(
+        r#"[Unit]
+Description=goose agent server
+After=network-online.target
+
+[Service]
+ExecStart="{exe}" agent
+Restart=on-failure
+RestartSec=5
+Environment="{env}={secret_key}"
+# Environment=GOOSE_PORT=3000
+
+[Install]
+WantedBy=default.target
+"#,
+        exe = exe.display(),
+        env = SECRET_KEY_ENV,
+    )
+}
+
+/// A launchd agent running the server in the foreground, started at login and kept alive
+pub fn launchd_plist(exe: &Path, log_dir: &Path, secret_key: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{exe}</string>
+        <string>agent</string>
+    </array>
+    <key>EnvironmentVariables</key>
+    <dict>
+        <key>{env}</key>
+        <string>{secret_key}</string>
+    </dict>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardErrorPath</key>
+    <string>{stderr}</string>
+</dict>
+</plist>
+"#,
+        label = LAUNCHD_LABEL,
+        exe = xml_escape(&exe.display().to_string()),
+        stderr = xml_escape(&log_dir.join("launchd.err.log").display().to_string()),
+        env = SECRET_KEY_ENV,
+        secret_key = xml_escape(secret_key),
+    )
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Write a service definition for this platform's service manager, or only print it. The
+/// definition carries the secret key from the environment, so it is only readable by the user.
+pub fn install_service(print_only: bool) -> Result<()> {
+    let secret_key = require_secret_key()?;
+    let exe = std::env::current_exe().context("Failed to get current executable path")?;
+    let home = dirs::home_dir().context("Could not determine the home directory")?;
+
+    let (path, contents, enable) = if cfg!(target_os = "macos") {
+        let log_dir = crate::logging::daemon_log_directory()?;
+        let path = home
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", LAUNCHD_LABEL));
+        let enable = format!("launchctl load -w {}", path.display());
+        (path, launchd_plist(&exe, &log_dir, &secret_key), enable)
+    } else if cfg!(target_os = "linux") {
+        let path = home.join(".config/systemd/user").join(SYSTEMD_UNIT);
+        let enable = format!(
+            "systemctl --user daemon-reload && systemctl --user enable --now {}",
+            SYSTEMD_UNIT
+        );
+        (path, systemd_unit(&exe, &secret_key), enable)
+    } else {
+        bail!("install-service supports systemd on Linux and launchd on macOS, use `goosed agent --daemon` instead");
+    };
+
+    if print_only {
+        print!("{}", contents);
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    }
+    println!("Wrote {}", path.display());
+    println!("Enable it with:");
+    println!("  {}", enable);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_templates() {
+        let exe = Path::new("/opt/goose & co/goosed");
+        let unit = systemd_unit(exe, "s3cr\"t%");
+        assert!(unit.contains("ExecStart=\"/opt/goose & co/goosed\" agent"));
+        assert!(unit.contains("Restart=on-failure"));
+        assert!(unit.contains("Environment=\"GOOSE_SERVER__SECRET_KEY=s3cr\\\"t%%\""));
+
+        let plist = launchd_plist(exe, Path::new("/tmp/logs"), "a<b");
+        assert!(plist.contains("<string>/opt/goose &amp; co/goosed</string>"));
+        assert!(plist.contains("<string>a&lt;b</string>"));
+        assert!(plist.contains(LAUNCHD_LABEL));
+        assert!(plist.contains("/tmp/logs/launchd.err.log"));
+    }
+
+    #[test]
+    fn test_background_server_needs_its_own_secret_key() {
+        std::env::remove_var(SECRET_KEY_ENV);
+        assert!(require_secret_key().is_err());
+        std::env::set_var(SECRET_KEY_ENV, DEFAULT_SECRET_KEY);
+        assert!(require_secret_key().is_err());
+        std::env::set_var(SECRET_KEY_ENV, "s3cret");
+        assert_eq!(require_secret_key().unwrap(), "s3cret");
+        std::env::remove_var(SECRET_KEY_ENV);
+    }
+}
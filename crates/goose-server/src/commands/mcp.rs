@@ -1,10 +1,11 @@
 use anyhow::Result;
 use goose_mcp::{
-    ComputerControllerRouter, DeveloperRouter, GoogleDriveRouter, JetBrainsRouter, MemoryRouter,
+    ComputerControllerRouter, DeveloperRouter, GoogleDriveRouter, GoslingRouter, JetBrainsRouter,
+    MemoryRouter, NotebookRouter, PimRouter, SecretsRouter, SpeechRouter, TrackerRouter,
     TutorialRouter,
 };
 use mcp_server::router::RouterService;
-use mcp_server::{BoundedService, ByteTransport, Server};
+use mcp_server::{BoundedService, ByteTransport, Server, TraceConfig, TraceService};
 use tokio::io::{stdin, stdout};
 
 pub async fn run(name: &str) -> Result<()> {
@@ -20,13 +21,21 @@ pub async fn run(name: &str) -> Result<()> {
             let router = GoogleDriveRouter::new().await;
             Some(Box::new(RouterService(router)))
         }
+        "gosling" => Some(Box::new(RouterService(GoslingRouter::new()))),
         "memory" => Some(Box::new(RouterService(MemoryRouter::new()))),
+        "notebook" => Some(Box::new(RouterService(NotebookRouter::new()))),
+        "pim" => Some(Box::new(RouterService(PimRouter::new()))),
+        "secrets" => Some(Box::new(RouterService(SecretsRouter::new()))),
+        "speech" => Some(Box::new(RouterService(SpeechRouter::new()))),
+        "tracker" => Some(Box::new(RouterService(TrackerRouter::new()))),
         "tutorial" => Some(Box::new(RouterService(TutorialRouter::new()))),
         _ => None,
     };
 
     // Create and run the server
-    let server = Server::new(router.unwrap_or_else(|| panic!("Unknown server requested {}", name)));
+    // Wrap the router so requests are logged with durations and slow tool calls are flagged
+    let router = router.unwrap_or_else(|| panic!("Unknown server requested {}", name));
+    let server = Server::new(TraceService::new(router, TraceConfig::from_env(name)));
     let transport = ByteTransport::new(stdin(), stdout());
 
     tracing::info!("Server initialized and ready to handle requests");
@@ -1,4 +1,8 @@
 use crate::error::{to_env_var, ConfigError};
+use crate::limits::{BodyLimitSettings, CorsSettings, RateLimitSettings};
+use crate::state_store::StateStoreSettings;
+use crate::users::UserSettings;
+use crate::webhooks::WebhookSettings;
 use config::{Config, Environment};
 use serde::Deserialize;
 use std::net::SocketAddr;
@@ -9,6 +13,18 @@ pub struct Settings {
     pub host: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    #[serde(default)]
+    pub state_store: StateStoreSettings,
+    #[serde(default)]
+    pub cors: CorsSettings,
+    #[serde(default)]
+    pub rate_limit: RateLimitSettings,
+    #[serde(default)]
+    pub body_limit: BodyLimitSettings,
+    #[serde(default)]
+    pub users: UserSettings,
+    #[serde(default)]
+    pub webhooks: WebhookSettings,
 }
 
 impl Settings {
@@ -18,6 +34,12 @@ impl Settings {
             .expect("Failed to parse socket address")
     }
 
+    /// Whether the server is only reachable from this machine, which relaxes the defaults
+    /// for CORS and rate limiting
+    pub fn is_loopback(&self) -> bool {
+        crate::limits::is_loopback(&self.host)
+    }
+
     pub fn new() -> Result<Self, ConfigError> {
         Self::load_and_validate()
     }
@@ -83,6 +105,7 @@ mod tests {
         let server_settings = Settings {
             host: "127.0.0.1".to_string(),
             port: 3000,
+            ..Default::default()
         };
         let addr = server_settings.socket_addr();
         assert_eq!(addr.to_string(), "127.0.0.1:3000");
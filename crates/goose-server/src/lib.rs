@@ -1,6 +1,11 @@
+pub mod limits;
 pub mod openapi;
 pub mod routes;
 pub mod state;
+pub mod state_store;
+pub mod streams;
+pub mod users;
+pub mod webhooks;
 
 // Re-export commonly used items
 pub use openapi::*;
@@ -0,0 +1,313 @@
+use crate::state::AppState;
+use crate::users::User;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+/// Requests per minute allowed for each client when listening beyond localhost and no limit
+/// is configured
+const DEFAULT_REMOTE_REQUESTS_PER_MINUTE: u32 = 120;
+/// How often buckets that have refilled are dropped
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Whether the server only accepts connections from this machine
+pub fn is_loopback(host: &str) -> bool {
+    host == "localhost"
+        || host
+            .parse::<IpAddr>()
+            .map(|ip| ip.is_loopback())
+            .unwrap_or(false)
+}
+
+/// Origins allowed to call the server from a browser, set with `GOOSE_CORS__ALLOWED_ORIGINS`
+/// as a comma separated list, or `*` for any origin
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CorsSettings {
+    pub allowed_origins: Option<String>,
+}
+
+impl CorsSettings {
+    pub fn origins(&self) -> Vec<String> {
+        self.allowed_origins
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|origin| origin.trim().trim_end_matches('/').to_string())
+            .filter(|origin| !origin.is_empty())
+            .collect()
+    }
+
+    /// Without a configured list any origin may call a server on localhost, which the desktop
+    /// app relies on, while a server reachable from other machines allows none
+    pub fn layer(&self, loopback: bool) -> CorsLayer {
+        let origins = self.origins();
+        let cors = CorsLayer::new().allow_methods(Any).allow_headers(Any);
+        if origins.iter().any(|origin| origin == "*") || (origins.is_empty() && loopback) {
+            return cors.allow_origin(Any);
+        }
+        if origins.is_empty() {
+            tracing::warn!(
+                "GOOSE_CORS__ALLOWED_ORIGINS is not set, cross-origin requests are rejected"
+            );
+        }
+        let origins: Vec<HeaderValue> = origins
+            .iter()
+            .filter_map(|origin| match origin.parse() {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    tracing::warn!("Ignoring invalid CORS origin {}", origin);
+                    None
+                }
+            })
+            .collect();
+        cors.allow_origin(AllowOrigin::list(origins))
+    }
+}
+
+/// Per client request limits, set with `GOOSE_RATE_LIMIT__REQUESTS_PER_MINUTE` and
+/// `GOOSE_RATE_LIMIT__BURST`. Clients are told apart by the user they authenticate as, or
+/// their IP address when they don't send valid credentials.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RateLimitSettings {
+    /// 0 turns rate limiting off, unset means off on localhost and 120 otherwise
+    pub requests_per_minute: Option<u32>,
+    /// Requests a client can make at once before being limited, defaults to the per minute rate
+    pub burst: Option<u32>,
+}
+
+impl RateLimitSettings {
+    pub fn limiter(&self, loopback: bool) -> Option<Arc<RateLimiter>> {
+        let per_minute = match self.requests_per_minute {
+            Some(0) => return None,
+            Some(per_minute) => per_minute,
+            None if loopback => return None,
+            None => DEFAULT_REMOTE_REQUESTS_PER_MINUTE,
+        };
+        Some(Arc::new(RateLimiter::new(
+            per_minute,
+            self.burst.unwrap_or(per_minute).max(1),
+        )))
+    }
+}
+
+/// Request body limits, set with `GOOSE_BODY_LIMIT__MAX_BYTES` for most routes and
+/// `GOOSE_BODY_LIMIT__MAX_UPLOAD_BYTES` for routes that take a conversation, which can
+/// include images
+#[derive(Debug, Clone, Deserialize)]
+pub struct BodyLimitSettings {
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: usize,
+    #[serde(default = "default_max_upload_bytes")]
+    pub max_upload_bytes: usize,
+}
+
+impl Default for BodyLimitSettings {
+    fn default() -> Self {
+        Self {
+            max_bytes: default_max_bytes(),
+            max_upload_bytes: default_max_upload_bytes(),
+        }
+    }
+}
+
+fn default_max_bytes() -> usize {
+    2 * 1024 * 1024
+}
+
+fn default_max_upload_bytes() -> usize {
+    32 * 1024 * 1024
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+struct Buckets {
+    by_client: HashMap<u64, Bucket>,
+    pruned: Instant,
+}
+
+/// Token bucket per client, refilled continuously at the configured rate
+pub struct RateLimiter {
+    per_second: f64,
+    burst: f64,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32, burst: u32) -> Self {
+        Self {
+            per_second: requests_per_minute as f64 / 60.0,
+            burst: burst as f64,
+            buckets: Mutex::new(Buckets {
+                by_client: HashMap::new(),
+                pruned: Instant::now(),
+            }),
+        }
+    }
+
+    /// How long an empty bucket takes to fill up, after which it is the same as no bucket
+    fn refill_time(&self) -> Duration {
+        Duration::from_secs_f64(self.burst / self.per_second)
+    }
+
+    /// Take a request from the client's bucket, or how long until one is available
+    pub fn check(&self, client: &str, now: Instant) -> Result<(), Duration> {
+        let mut hasher = DefaultHasher::new();
+        client.hash(&mut hasher);
+        let key = hasher.finish();
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if now.duration_since(buckets.pruned) >= PRUNE_INTERVAL {
+            let refill_time = self.refill_time();
+            buckets
+                .by_client
+                .retain(|_, bucket| now.duration_since(bucket.updated) < refill_time);
+            buckets.pruned = now;
+        }
+        let bucket = buckets.by_client.entry(key).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.per_second,
+            ))
+        }
+    }
+}
+
+/// Who a request counts against. Requests without valid credentials count against their
+/// address, so a client can't get a fresh bucket by making up keys.
+fn client_key(request: &Request, state: &AppState) -> String {
+    match state
+        .users
+        .authenticate(request.headers(), &state.secret_key)
+    {
+        Ok(User::Owner) => "owner".to_string(),
+        Ok(User::Member(id)) => format!("user:{}", id),
+        Err(_) => match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+            None => "unknown".to_string(),
+        },
+    }
+}
+
+/// Middleware rejecting clients over their limit with 429 and a Retry-After header
+pub async fn rate_limit(
+    State((limiter, state)): State<(Arc<RateLimiter>, Arc<AppState>)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = client_key(&request, &state);
+
+    match limiter.check(&client, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(
+                header::RETRY_AFTER,
+                retry_after.as_secs().max(1).to_string(),
+            )],
+            "Too many requests",
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(60, 2);
+        let start = Instant::now();
+        assert!(limiter.check("ip:10.0.0.1", start).is_ok());
+        assert!(limiter.check("ip:10.0.0.1", start).is_ok());
+        let retry = limiter.check("ip:10.0.0.1", start).unwrap_err();
+        assert_eq!(retry.as_secs(), 1);
+
+        // Other clients have their own bucket
+        assert!(limiter.check("ip:10.0.0.2", start).is_ok());
+        // One request a second refills
+        assert!(limiter
+            .check("ip:10.0.0.1", start + Duration::from_secs(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_refilled_buckets_are_dropped() {
+        let limiter = RateLimiter::new(60, 2);
+        let start = limiter.buckets.lock().unwrap().pruned;
+        assert!(limiter.check("ip:10.0.0.1", start).is_ok());
+        assert!(limiter
+            .check("ip:10.0.0.2", start + Duration::from_secs(59))
+            .is_ok());
+
+        limiter
+            .check("ip:10.0.0.3", start + PRUNE_INTERVAL)
+            .unwrap();
+        // The first client's bucket had refilled, the second's had not
+        assert_eq!(limiter.buckets.lock().unwrap().by_client.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_clients_are_keyed_by_user() {
+        let state =
+            AppState::new(Arc::new(goose::agents::Agent::new()), "secret".to_string()).await;
+        let request = |key: &str| {
+            Request::builder()
+                .header("X-Secret-Key", key)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        assert_eq!(client_key(&request("secret"), &state), "owner");
+        // Made up keys all count against the caller's address
+        assert_eq!(client_key(&request("guess-1"), &state), "unknown");
+        assert_eq!(client_key(&request("guess-2"), &state), "unknown");
+    }
+
+    #[test]
+    fn test_secure_defaults() {
+        assert!(is_loopback("127.0.0.1"));
+        assert!(is_loopback("localhost"));
+        assert!(!is_loopback("0.0.0.0"));
+
+        let rate_limit = RateLimitSettings::default();
+        assert!(rate_limit.limiter(true).is_none());
+        assert!(rate_limit.limiter(false).is_some());
+        let off = RateLimitSettings {
+            requests_per_minute: Some(0),
+            burst: None,
+        };
+        assert!(off.limiter(false).is_none());
+
+        let cors = CorsSettings {
+            allowed_origins: Some("https://a.example, https://b.example/,".to_string()),
+        };
+        assert_eq!(
+            cors.origins(),
+            vec!["https://a.example", "https://b.example"]
+        );
+    }
+}
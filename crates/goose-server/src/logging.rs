@@ -2,7 +2,7 @@ use anyhow::{Context, Result};
 use etcetera::{choose_app_strategy, AppStrategy};
 use std::fs;
 use std::path::PathBuf;
-use tracing_appender::rolling::Rotation;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{
     filter::LevelFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
     Registry,
@@ -11,19 +11,13 @@ use tracing_subscriber::{
 use goose::config::APP_STRATEGY;
 use goose::tracing::langfuse_layer;
 
+/// Daily daemon log files kept before the oldest is deleted
+const DAEMON_LOG_FILES: usize = 7;
+
 /// Returns the directory where log files should be stored.
 /// Creates the directory structure if it doesn't exist.
 fn get_log_directory() -> Result<PathBuf> {
-    // choose_app_strategy().state_dir()
-    // - macOS/Linux: ~/.local/state/goose/logs/server
-    // - Windows:     ~\AppData\Roaming\Block\goose\data\logs\server
-    // - Windows has no convention for state_dir, use data_dir instead
-    let home_dir =
-        choose_app_strategy(APP_STRATEGY.clone()).context("HOME environment variable not set")?;
-
-    let base_log_dir = home_dir
-        .in_state_dir("logs/server")
-        .unwrap_or_else(|| home_dir.in_data_dir("logs/server"));
+    let base_log_dir = get_base_log_directory()?;
 
     // Create date-based subdirectory
     let now = chrono::Local::now();
@@ -35,6 +29,24 @@ fn get_log_directory() -> Result<PathBuf> {
     Ok(date_dir)
 }
 
+fn get_base_log_directory() -> Result<PathBuf> {
+    // choose_app_strategy().state_dir()
+    // - macOS/Linux: ~/.local/state/goose/logs/server
+    // - Windows:     ~\AppData\Roaming\Block\goose\data\logs\server
+    // - Windows has no convention for state_dir, use data_dir instead
+    let home_dir =
+        choose_app_strategy(APP_STRATEGY.clone()).context("HOME environment variable not set")?;
+
+    Ok(home_dir
+        .in_state_dir("logs/server")
+        .unwrap_or_else(|| home_dir.in_data_dir("logs/server")))
+}
+
+/// Where the daemon writes its logs, rotated daily
+pub fn daemon_log_directory() -> Result<PathBuf> {
+    Ok(get_base_log_directory()?.join("daemon"))
+}
+
 /// Sets up the logging infrastructure for the application.
 /// This includes:
 /// - File-based logging with JSON formatting (DEBUG level)
@@ -53,9 +65,30 @@ pub fn setup_logging(name: Option<&str>) -> Result<()> {
     };
 
     // Create non-rolling file appender for detailed logs
-    let file_appender =
-        tracing_appender::rolling::RollingFileAppender::new(Rotation::NEVER, log_dir, log_filename);
+    let file_appender = RollingFileAppender::new(Rotation::NEVER, log_dir, log_filename);
+
+    init_subscriber(file_appender, true)
+}
 
+/// Sets up logging for a server running in the background. There is no console to write to,
+/// and the process runs for a long time, so logs go to one file per day and only the last
+/// `DAEMON_LOG_FILES` are kept.
+pub fn setup_daemon_logging() -> Result<()> {
+    let log_dir = daemon_log_directory()?;
+    fs::create_dir_all(&log_dir).context("Failed to create log directory")?;
+
+    let file_appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("goosed")
+        .filename_suffix("log")
+        .max_log_files(DAEMON_LOG_FILES)
+        .build(log_dir)
+        .context("Failed to create log file")?;
+
+    init_subscriber(file_appender, false)
+}
+
+fn init_subscriber(file_appender: RollingFileAppender, console: bool) -> Result<()> {
     // Create JSON file logging layer
     let file_layer = fmt::layer()
         .with_target(true)
@@ -65,13 +98,15 @@ pub fn setup_logging(name: Option<&str>) -> Result<()> {
         .with_file(true);
 
     // Create console logging layer for development - INFO and above only
-    let console_layer = fmt::layer()
-        .with_target(true)
-        .with_level(true)
-        .with_ansi(true)
-        .with_file(true)
-        .with_line_number(true)
-        .pretty();
+    let console_layer = console.then(|| {
+        fmt::layer()
+            .with_target(true)
+            .with_level(true)
+            .with_ansi(true)
+            .with_file(true)
+            .with_line_number(true)
+            .pretty()
+    });
 
     // Base filter for all logging
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
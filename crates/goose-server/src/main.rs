@@ -1,10 +1,15 @@
 mod commands;
 mod configuration;
 mod error;
+mod limits;
 mod logging;
 mod openapi;
 mod routes;
 mod state;
+mod state_store;
+mod streams;
+mod users;
+mod webhooks;
 
 use clap::{Parser, Subcommand};
 
@@ -19,7 +24,24 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Run the agent server
-    Agent,
+    Agent {
+        /// Run in the background, see `stop` and `status`
+        #[arg(long)]
+        daemon: bool,
+        /// Set on the background process started by --daemon
+        #[arg(long, hide = true)]
+        daemon_child: bool,
+    },
+    /// Stop the agent server started with `agent --daemon`
+    Stop,
+    /// Show whether the agent server started with `agent --daemon` is running
+    Status,
+    /// Install a systemd (Linux) or launchd (macOS) user service that keeps the agent server running
+    InstallService {
+        /// Print the service definition instead of installing it
+        #[arg(long)]
+        print: bool,
+    },
     /// Run the MCP server
     Mcp {
         /// Name of the MCP server type
@@ -32,9 +54,18 @@ async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match &cli.command {
-        Commands::Agent => {
-            commands::agent::run().await?;
+        Commands::Agent {
+            daemon: true,
+            daemon_child: false,
+        } => {
+            commands::daemon::start()?;
+        }
+        Commands::Agent { daemon_child, .. } => {
+            commands::agent::run(*daemon_child).await?;
         }
+        Commands::Stop => commands::daemon::stop()?,
+        Commands::Status => commands::daemon::status()?,
+        Commands::InstallService { print } => commands::daemon::install_service(*print)?,
         Commands::Mcp { name } => {
             commands::mcp::run(name).await?;
         }
@@ -8,9 +8,9 @@ use goose::message::{
     SummarizationRequested, ThinkingContent, ToolConfirmationRequest, ToolRequest, ToolResponse,
 };
 use goose::permission::permission_confirmation::PrincipalType;
-use goose::providers::base::{ConfigKey, ModelInfo, ProviderMetadata};
+use goose::providers::base::{ConfigKey, ModelInfo, ProviderMetadata, ResponseTiming};
 use goose::session::info::SessionInfo;
-use goose::session::SessionMetadata;
+use goose::session::{MessageUsage, SessionMetadata};
 use mcp_core::content::{Annotations, Content, EmbeddedResource, ImageContent, TextContent};
 use mcp_core::handler::ToolResultSchema;
 use mcp_core::resource::ResourceContents;
@@ -33,11 +33,30 @@ use utoipa::OpenApi;
         super::routes::config_management::read_all_config,
         super::routes::config_management::providers,
         super::routes::config_management::upsert_permissions,
+        super::routes::agent::get_versions,
+        super::routes::agent::list_providers,
+        super::routes::agent::extend_prompt,
         super::routes::agent::get_tools,
+        super::routes::agent::update_agent_provider,
+        super::routes::extension::add_extension,
+        super::routes::extension::remove_extension,
+        super::routes::extension::reload_extension,
+        super::routes::health::status,
+        super::routes::recipe::create_recipe,
+        super::routes::reply::handler,
+        super::routes::reply::resume_reply,
+        super::routes::reply::ask_handler,
         super::routes::reply::confirm_permission,
+        super::routes::reply::pending_approvals,
+        super::routes::reply::submit_tool_result,
         super::routes::context::manage_context,
+        super::routes::context::list_pinned,
+        super::routes::context::pin_path,
+        super::routes::context::unpin_path,
         super::routes::session::list_sessions,
         super::routes::session::get_session_history,
+        super::routes::session::pause_session,
+        super::routes::session::get_usage,
         super::routes::schedule::create_schedule,
         super::routes::schedule::list_schedules,
         super::routes::schedule::delete_schedule,
@@ -47,7 +66,10 @@ use utoipa::OpenApi;
         super::routes::schedule::unpause_schedule,
         super::routes::schedule::kill_running_job,
         super::routes::schedule::inspect_running_job,
-        super::routes::schedule::sessions_handler
+        super::routes::schedule::sessions_handler,
+        super::routes::webhooks::create_webhook,
+        super::routes::webhooks::list_webhooks,
+        super::routes::webhooks::delete_webhook
     ),
     components(schemas(
         super::routes::config_management::UpsertConfigQuery,
@@ -59,11 +81,38 @@ use utoipa::OpenApi;
         super::routes::config_management::ExtensionQuery,
         super::routes::config_management::ToolPermission,
         super::routes::config_management::UpsertPermissionsQuery,
+        super::routes::agent::VersionsResponse,
+        super::routes::agent::ExtendPromptRequest,
+        super::routes::agent::ExtendPromptResponse,
+        super::routes::agent::AgentProviderDetails,
+        super::routes::agent::ProviderList,
+        super::routes::agent::UpdateProviderRequest,
+        super::routes::extension::ExtensionConfigRequest,
+        super::routes::extension::ExtensionActionResponse,
+        super::routes::health::StatusResponse,
+        super::routes::health::StatusDetails,
+        super::routes::health::ProviderStatus,
+        super::routes::health::ExtensionStatus,
+        super::routes::health::ResourceUsage,
+        super::routes::recipe::CreateRecipeRequest,
+        super::routes::recipe::AuthorRequest,
+        super::routes::recipe::CreateRecipeResponse,
+        super::routes::reply::ChatRequest,
+        super::routes::reply::MessageEvent,
+        super::routes::reply::AskRequest,
+        super::routes::reply::AskResponse,
+        super::routes::reply::ToolResultRequest,
         super::routes::reply::PermissionConfirmationRequest,
+        super::state_store::PendingApproval,
+        super::state_store::SessionActivity,
+        super::state_store::SessionUsage,
         super::routes::context::ContextManageRequest,
         super::routes::context::ContextManageResponse,
+        super::routes::context::PinRequest,
+        super::routes::context::PinnedResponse,
         super::routes::session::SessionListResponse,
         super::routes::session::SessionHistoryResponse,
+        super::routes::session::UsageResponse,
         Message,
         MessageContent,
         Content,
@@ -95,15 +144,29 @@ use utoipa::OpenApi;
         ModelInfo,
         SessionInfo,
         SessionMetadata,
+        MessageUsage,
+        ResponseTiming,
         super::routes::schedule::CreateScheduleRequest,
         super::routes::schedule::UpdateScheduleRequest,
         super::routes::schedule::KillJobResponse,
         super::routes::schedule::InspectJobResponse,
         goose::scheduler::ScheduledJob,
+        goose::agents::timeouts::TimeoutEvent,
+        goose::agents::timeouts::TimeoutKind,
+        goose::agents::timeouts::TimeoutAction,
+        goose::agents::guardrails::GuardrailEvent,
+        goose::agents::guardrails::GuardrailFinding,
+        goose::config::extension_health::ExtensionDisabledEvent,
+        goose::agents::context_recovery::ContextRecoveryEvent,
+        goose::agents::context_recovery::RecoveryStrategy,
         super::routes::schedule::RunNowResponse,
         super::routes::schedule::ListSchedulesResponse,
         super::routes::schedule::SessionsQuery,
         super::routes::schedule::SessionDisplayInfo,
+        super::routes::webhooks::CreateWebhookRequest,
+        super::webhooks::Webhook,
+        super::webhooks::WebhookEvent,
+        super::webhooks::WebhookPayload,
     ))
 )]
 pub struct ApiDoc;
@@ -113,3 +176,67 @@ pub fn generate_schema() -> String {
     let api_doc = ApiDoc::openapi();
     serde_json::to_string_pretty(&api_doc).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_route_is_documented() {
+        let api_doc = ApiDoc::openapi();
+        let documented: Vec<&str> = api_doc.paths.paths.keys().map(String::as_str).collect();
+
+        // Keep in sync with the routers in `routes`
+        let routes = [
+            "/agent/versions",
+            "/agent/providers",
+            "/agent/prompt",
+            "/agent/tools",
+            "/agent/update_provider",
+            "/config",
+            "/config/upsert",
+            "/config/remove",
+            "/config/read",
+            "/config/extensions",
+            "/config/extensions/{name}",
+            "/config/providers",
+            "/config/init",
+            "/config/backup",
+            "/config/permissions",
+            "/context/manage",
+            "/context/pinned",
+            "/context/pin",
+            "/context/unpin",
+            "/extensions/add",
+            "/extensions/remove",
+            "/extensions/reload",
+            "/status",
+            "/recipe/create",
+            "/reply",
+            "/ask",
+            "/confirm",
+            "/confirm/pending",
+            "/tool_result",
+            "/sessions",
+            "/sessions/{session_id}",
+            "/sessions/{session_id}/pause",
+            "/schedule/create",
+            "/schedule/list",
+            "/schedule/delete/{id}",
+            "/schedule/{id}",
+            "/schedule/{id}/run_now",
+            "/schedule/{id}/pause",
+            "/schedule/{id}/unpause",
+            "/schedule/{id}/kill",
+            "/schedule/{id}/inspect",
+            "/schedule/{id}/sessions",
+            "/webhooks",
+            "/webhooks/{id}",
+        ];
+        let missing: Vec<_> = routes
+            .iter()
+            .filter(|route| !documented.contains(route))
+            .collect();
+        assert!(missing.is_empty(), "Undocumented routes: {:?}", missing);
+    }
+}
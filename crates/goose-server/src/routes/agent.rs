@@ -17,20 +17,21 @@ use goose::{
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
-struct VersionsResponse {
+#[derive(Serialize, ToSchema)]
+pub struct VersionsResponse {
     available_versions: Vec<String>,
     default_version: String,
 }
 
-#[derive(Deserialize)]
-struct ExtendPromptRequest {
+#[derive(Deserialize, ToSchema)]
+pub struct ExtendPromptRequest {
     extension: String,
 }
 
-#[derive(Serialize)]
-struct ExtendPromptResponse {
+#[derive(Serialize, ToSchema)]
+pub struct ExtendPromptResponse {
     success: bool,
 }
 
@@ -42,22 +43,22 @@ struct ProviderFile {
     required_keys: Vec<String>,
 }
 
-#[derive(Serialize)]
-struct ProviderDetails {
+#[derive(Serialize, ToSchema)]
+pub struct AgentProviderDetails {
     name: String,
     description: String,
     models: Vec<String>,
     required_keys: Vec<String>,
 }
 
-#[derive(Serialize)]
-struct ProviderList {
+#[derive(Serialize, ToSchema)]
+pub struct ProviderList {
     id: String,
-    details: ProviderDetails,
+    details: AgentProviderDetails,
 }
 
-#[derive(Deserialize)]
-struct UpdateProviderRequest {
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateProviderRequest {
     provider: String,
     model: Option<String>,
 }
@@ -67,6 +68,13 @@ pub struct GetToolsQuery {
     extension_name: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/agent/versions",
+    responses(
+        (status = 200, description = "Available agent versions", body = VersionsResponse)
+    )
+)]
 async fn get_versions() -> Json<VersionsResponse> {
     let versions = ["goose".to_string()];
     let default_version = "goose".to_string();
@@ -77,6 +85,16 @@ async fn get_versions() -> Json<VersionsResponse> {
     })
 }
 
+#[utoipa::path(
+    post,
+    path = "/agent/prompt",
+    request_body = ExtendPromptRequest,
+    responses(
+        (status = 200, description = "System prompt extended", body = ExtendPromptResponse),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 412, description = "Agent not initialized")
+    )
+)]
 async fn extend_prompt(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
@@ -84,14 +102,20 @@ async fn extend_prompt(
 ) -> Result<Json<ExtendPromptResponse>, StatusCode> {
     verify_secret_key(&headers, &state)?;
 
-    let agent = state
-        .get_agent()
+    state
+        .extend_system_prompt(payload.extension.clone())
         .await
         .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
-    agent.extend_system_prompt(payload.extension.clone()).await;
     Ok(Json(ExtendPromptResponse { success: true }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/agent/providers",
+    responses(
+        (status = 200, description = "Providers and the keys they require", body = Vec<ProviderList>)
+    )
+)]
 async fn list_providers() -> Json<Vec<ProviderList>> {
     let contents = include_str!("providers_and_keys.json");
 
@@ -102,7 +126,7 @@ async fn list_providers() -> Json<Vec<ProviderList>> {
         .into_iter()
         .map(|(id, provider)| ProviderList {
             id,
-            details: ProviderDetails {
+            details: AgentProviderDetails {
                 name: provider.name,
                 description: provider.description,
                 models: provider.models,
@@ -124,7 +148,7 @@ async fn list_providers() -> Json<Vec<ProviderList>> {
     responses(
         (status = 200, description = "Tools retrieved successfully", body = Vec<ToolInfo>),
         (status = 401, description = "Unauthorized - invalid secret key"),
-        (status = 424, description = "Agent not initialized"),
+        (status = 412, description = "Agent not initialized"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -176,8 +200,11 @@ async fn get_tools(
 #[utoipa::path(
     post,
     path = "/agent/update_provider",
+    request_body = UpdateProviderRequest,
     responses(
         (status = 200, description = "Update provider completed", body = String),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 412, description = "Agent not initialized"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -210,9 +237,15 @@ async fn update_agent_provider(
     let model_config = ModelConfig::new(model);
     let new_provider = create(&payload.provider, model_config).unwrap();
     agent
-        .update_provider(new_provider)
+        .update_provider(new_provider.clone())
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    for agent in state.member_agents().await {
+        agent
+            .update_provider(new_provider.clone())
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
 
     Ok(StatusCode::OK)
 }
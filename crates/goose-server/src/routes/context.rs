@@ -1,13 +1,17 @@
-use super::utils::verify_secret_key;
+use super::utils::authenticate;
 use crate::state::AppState;
+use crate::users::User;
 use axum::{
-    extract::State,
+    extract::{Query, State},
     http::{HeaderMap, StatusCode},
-    routing::post,
+    routing::{get, post},
     Json, Router,
 };
+use goose::context_mgmt::pinned::is_within;
 use goose::message::Message;
+use goose::session::{self, SessionMetadata};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::sync::Arc;
 use utoipa::ToSchema;
 
@@ -31,6 +35,50 @@ pub struct ContextManageResponse {
     pub token_counts: Vec<usize>,
 }
 
+/// A file or directory to keep in, or drop from, the context of a session's requests
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PinRequest {
+    /// The session the pin belongs to
+    pub session_id: String,
+    /// Absolute path of the file or directory, inside the session's working directory
+    pub path: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PinnedQuery {
+    session_id: String,
+}
+
+/// The files and directories kept in the context of a session's requests
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PinnedResponse {
+    pub pinned: Vec<String>,
+}
+
+/// The file and metadata of one of the user's sessions, pins are saved with the session so
+/// each user only ever sees and changes the pins of their own sessions
+fn session_metadata(
+    user: &User,
+    session_id: &str,
+) -> Result<(PathBuf, SessionMetadata), StatusCode> {
+    let session_path = user.session_path(session_id)?;
+    if !session_path.exists() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let metadata =
+        session::read_metadata(&session_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok((session_path, metadata))
+}
+
+fn pinned_response(metadata: &SessionMetadata) -> Json<PinnedResponse> {
+    let pinned = metadata
+        .pinned
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    Json(PinnedResponse { pinned })
+}
+
 #[utoipa::path(
     post,
     path = "/context/manage",
@@ -51,10 +99,10 @@ async fn manage_context(
     headers: HeaderMap,
     Json(request): Json<ContextManageRequest>,
 ) -> Result<Json<ContextManageResponse>, StatusCode> {
-    verify_secret_key(&headers, &state)?;
+    let user = authenticate(&headers, &state)?;
 
     let agent = state
-        .get_agent()
+        .agent_for(&user)
         .await
         .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
 
@@ -79,9 +127,112 @@ async fn manage_context(
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/context/pinned",
+    params(
+        ("session_id" = String, Query, description = "The session whose pins to list")
+    ),
+    responses(
+        (status = 200, description = "Pinned paths retrieved successfully", body = PinnedResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Not found - The user has no such session")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Context Management"
+)]
+async fn list_pinned(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<PinnedQuery>,
+) -> Result<Json<PinnedResponse>, StatusCode> {
+    let user = authenticate(&headers, &state)?;
+    let (_, metadata) = session_metadata(&user, &query.session_id)?;
+    Ok(pinned_response(&metadata))
+}
+
+#[utoipa::path(
+    post,
+    path = "/context/pin",
+    request_body = PinRequest,
+    responses(
+        (status = 200, description = "Path pinned, or already pinned", body = PinnedResponse),
+        (status = 400, description = "Bad request - The path is relative or doesn't exist"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 403, description = "Forbidden - The path is outside the session's working directory"),
+        (status = 404, description = "Not found - The user has no such session")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Context Management"
+)]
+async fn pin_path(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<PinRequest>,
+) -> Result<Json<PinnedResponse>, StatusCode> {
+    let user = authenticate(&headers, &state)?;
+    let (session_path, mut metadata) = session_metadata(&user, &request.session_id)?;
+
+    let path = PathBuf::from(&request.path);
+    if !path.is_absolute() || !path.exists() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if !is_within(&path, &metadata.working_dir) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if !metadata.pinned.contains(&path) {
+        metadata.pinned.push(path);
+        session::update_metadata(&session_path, &metadata)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+    Ok(pinned_response(&metadata))
+}
+
+#[utoipa::path(
+    post,
+    path = "/context/unpin",
+    request_body = PinRequest,
+    responses(
+        (status = 200, description = "Path unpinned", body = PinnedResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Not found - The user has no such session, or the path isn't pinned")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Context Management"
+)]
+async fn unpin_path(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<PinRequest>,
+) -> Result<Json<PinnedResponse>, StatusCode> {
+    let user = authenticate(&headers, &state)?;
+    let (session_path, mut metadata) = session_metadata(&user, &request.session_id)?;
+
+    let path = PathBuf::from(&request.path);
+    let before = metadata.pinned.len();
+    metadata.pinned.retain(|pinned| pinned != &path);
+    if metadata.pinned.len() == before {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    session::update_metadata(&session_path, &metadata)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(pinned_response(&metadata))
+}
+
 // Configure routes for this module
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/context/manage", post(manage_context))
+        .route("/context/pinned", get(list_pinned))
+        .route("/context/pin", post(pin_path))
+        .route("/context/unpin", post(unpin_path))
         .with_state(state)
 }
@@ -10,11 +10,12 @@ use goose::agents::{extension::Envs, ExtensionConfig};
 use http::{HeaderMap, StatusCode};
 use serde::{Deserialize, Serialize};
 use tracing;
+use utoipa::ToSchema;
 
 /// Enum representing the different types of extension configuration requests.
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(tag = "type")]
-enum ExtensionConfigRequest {
+pub enum ExtensionConfigRequest {
     /// Server-Sent Events (SSE) extension.
     #[serde(rename = "sse")]
     Sse {
@@ -47,6 +48,15 @@ enum ExtensionConfigRequest {
         #[serde(default)]
         env_keys: Vec<String>,
         timeout: Option<u64>,
+        /// Directory to run the command in.
+        #[serde(default)]
+        cwd: Option<String>,
+        /// Environment variables inherited from goose, everything when unset.
+        #[serde(default)]
+        inherit_env: Option<Vec<String>>,
+        /// Directories added to the front of PATH.
+        #[serde(default)]
+        path_prepend: Vec<String>,
     },
     /// Built-in extension that is part of the goose binary.
     #[serde(rename = "builtin")]
@@ -72,18 +82,29 @@ enum ExtensionConfigRequest {
 ///
 /// - `error`: Indicates whether an error occurred (`true`) or not (`false`).
 /// - `message`: Provides detailed error information when `error` is `true`.
-#[derive(Serialize)]
-struct ExtensionResponse {
+#[derive(Serialize, ToSchema)]
+pub struct ExtensionActionResponse {
     error: bool,
     message: Option<String>,
 }
 
 /// Handler for adding a new extension configuration.
+#[utoipa::path(
+    post,
+    path = "/extensions/add",
+    request_body = ExtensionConfigRequest,
+    responses(
+        (status = 200, description = "Extension add attempted, see the response for errors", body = ExtensionActionResponse),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 412, description = "Agent not initialized"),
+        (status = 422, description = "Invalid extension configuration")
+    )
+)]
 async fn add_extension(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     raw: axum::extract::Json<serde_json::Value>,
-) -> Result<Json<ExtensionResponse>, StatusCode> {
+) -> Result<Json<ExtensionActionResponse>, StatusCode> {
     verify_secret_key(&headers, &state)?;
 
     // Log the raw request for debugging
@@ -136,7 +157,7 @@ async fn add_extension(
                             "Failed to install Node.js: {}",
                             String::from_utf8_lossy(&output.stderr)
                         );
-                        return Ok(Json(ExtensionResponse {
+                        return Ok(Json(ExtensionActionResponse {
                             error: true,
                             message: Some(format!(
                                 "Failed to install Node.js: {}",
@@ -150,7 +171,7 @@ async fn add_extension(
                         "Node.js installer script not found at: {}",
                         install_script.display()
                     );
-                    return Ok(Json(ExtensionResponse {
+                    return Ok(Json(ExtensionActionResponse {
                         error: true,
                         message: Some("Node.js installer script not found".to_string()),
                     }));
@@ -183,10 +204,13 @@ async fn add_extension(
             envs,
             env_keys,
             timeout,
+            cwd,
+            inherit_env,
+            path_prepend,
         } => {
             // TODO: We can uncomment once bugs are fixed. Check allowlist for Stdio extensions
             // if !is_command_allowed(&cmd, &args) {
-            //     return Ok(Json(ExtensionResponse {
+            //     return Ok(Json(ExtensionActionResponse {
             //         error: true,
             //         message: Some(format!(
             //             "Extension '{}' is not in the allowed extensions list. Command: '{} {}'. If you require access please ask your administrator to update the allowlist.",
@@ -205,6 +229,9 @@ async fn add_extension(
                 env_keys,
                 timeout,
                 bundled: None,
+                cwd,
+                inherit_env,
+                path_prepend,
             }
         }
         ExtensionConfigRequest::Builtin {
@@ -234,17 +261,24 @@ async fn add_extension(
         .get_agent()
         .await
         .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
-    let response = agent.add_extension(extension_config).await;
+    let response = agent.add_extension(extension_config.clone()).await;
+    if response.is_ok() {
+        for agent in state.member_agents().await {
+            if let Err(e) = agent.add_extension(extension_config.clone()).await {
+                tracing::warn!("Failed to add extension for a member: {:?}", e);
+            }
+        }
+    }
 
     // Respond with the result.
     match response {
-        Ok(_) => Ok(Json(ExtensionResponse {
+        Ok(_) => Ok(Json(ExtensionActionResponse {
             error: false,
             message: None,
         })),
         Err(e) => {
             eprintln!("Failed to add extension configuration: {:?}", e);
-            Ok(Json(ExtensionResponse {
+            Ok(Json(ExtensionActionResponse {
                 error: true,
                 message: Some(format!(
                     "Failed to add extension configuration, error: {:?}",
@@ -256,11 +290,21 @@ async fn add_extension(
 }
 
 /// Handler for removing an extension by name
+#[utoipa::path(
+    post,
+    path = "/extensions/remove",
+    request_body(content = String, description = "Name of the extension to remove"),
+    responses(
+        (status = 200, description = "Extension removal attempted, see the response for errors", body = ExtensionActionResponse),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 412, description = "Agent not initialized")
+    )
+)]
 async fn remove_extension(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     Json(name): Json<String>,
-) -> Result<Json<ExtensionResponse>, StatusCode> {
+) -> Result<Json<ExtensionActionResponse>, StatusCode> {
     verify_secret_key(&headers, &state)?;
 
     // Get a reference to the agent
@@ -268,23 +312,70 @@ async fn remove_extension(
         .get_agent()
         .await
         .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
-    match agent.remove_extension(&name).await {
-        Ok(_) => Ok(Json(ExtensionResponse {
+    let response = agent.remove_extension(&name).await;
+    for agent in state.member_agents().await {
+        if let Err(e) = agent.remove_extension(&name).await {
+            tracing::warn!("Failed to remove extension for a member: {:?}", e);
+        }
+    }
+    match response {
+        Ok(_) => Ok(Json(ExtensionActionResponse {
             error: false,
             message: None,
         })),
-        Err(e) => Ok(Json(ExtensionResponse {
+        Err(e) => Ok(Json(ExtensionActionResponse {
             error: true,
             message: Some(format!("Failed to remove extension: {:?}", e)),
         })),
     }
 }
 
+/// Handler for restarting a running extension to pick up changes to its server or configuration
+#[utoipa::path(
+    post,
+    path = "/extensions/reload",
+    request_body(content = String, description = "Name of the extension to reload"),
+    responses(
+        (status = 200, description = "Extension reload attempted, see the response for errors", body = ExtensionActionResponse),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 412, description = "Agent not initialized")
+    )
+)]
+async fn reload_extension(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(name): Json<String>,
+) -> Result<Json<ExtensionActionResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+    let response = agent.reload_extension(&name).await;
+    for agent in state.member_agents().await {
+        if let Err(e) = agent.reload_extension(&name).await {
+            tracing::warn!("Failed to reload extension for a member: {:?}", e);
+        }
+    }
+    match response {
+        Ok(_) => Ok(Json(ExtensionActionResponse {
+            error: false,
+            message: None,
+        })),
+        Err(e) => Ok(Json(ExtensionActionResponse {
+            error: true,
+            message: Some(format!("Failed to reload extension: {}", e)),
+        })),
+    }
+}
+
 /// Registers the extension management routes with the Axum router.
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/extensions/add", post(add_extension))
         .route("/extensions/remove", post(remove_extension))
+        .route("/extensions/reload", post(reload_extension))
         .with_state(state)
 }
 
@@ -1,17 +1,270 @@
-use axum::{routing::get, Json, Router};
-use serde::Serialize;
+use super::utils::verify_secret_key;
+use crate::state::AppState;
+use crate::state_store::{PendingApproval, APPROVALS, SESSIONS};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::get,
+    Json, Router,
+};
+use goose::config::Config;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
-struct StatusResponse {
+/// How long a provider probe may take before the provider counts as unreachable
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize)]
+pub struct StatusQuery {
+    /// Make a request to the provider to check that it is reachable
+    #[serde(default)]
+    probe: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StatusResponse {
+    /// "ok", or "degraded" when the state store or a probed provider is unreachable
+    #[schema(value_type = String)]
     status: &'static str,
+    version: String,
+    uptime_secs: u64,
+    /// Only included for requests with the secret key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<StatusDetails>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StatusDetails {
+    provider: ProviderStatus,
+    extensions: Vec<ExtensionStatus>,
+    /// Sessions the server has replied to, None when the state store is unreachable
+    sessions: Option<usize>,
+    pending_approvals: Option<usize>,
+    scheduler_running: bool,
+    goose_mode: String,
+    resources: ResourceUsage,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProviderStatus {
+    name: Option<String>,
+    model: Option<String>,
+    /// "not_configured", "configured", "reachable" or "unreachable", the last two only
+    /// when probed
+    #[schema(value_type = String)]
+    connectivity: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
 }
 
-/// Simple status endpoint that returns 200 OK when the server is running
-async fn status() -> Json<StatusResponse> {
-    Json(StatusResponse { status: "ok" })
+#[derive(Serialize, ToSchema)]
+pub struct ExtensionStatus {
+    name: String,
+    tool_count: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ResourceUsage {
+    pid: u32,
+    /// Resident memory, only available on Linux
+    memory_rss_bytes: Option<u64>,
+}
+
+fn memory_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+async fn provider_status(state: &AppState, probe: bool) -> ProviderStatus {
+    let name = Config::global().get_param::<String>("GOOSE_PROVIDER").ok();
+    let provider = match state.get_agent().await {
+        Ok(agent) => agent.provider().await.ok(),
+        Err(_) => None,
+    };
+    let Some(provider) = provider else {
+        return ProviderStatus {
+            name,
+            model: None,
+            connectivity: "not_configured",
+            error: None,
+        };
+    };
+    let model = Some(provider.get_model_config().model_name);
+    if !probe {
+        return ProviderStatus {
+            name,
+            model,
+            connectivity: "configured",
+            error: None,
+        };
+    }
+
+    let (connectivity, error) =
+        match tokio::time::timeout(PROBE_TIMEOUT, provider.fetch_supported_models_async()).await {
+            Ok(Ok(Some(_))) => ("reachable", None),
+            // The provider can't list models, so there is nothing cheap to probe with
+            Ok(Ok(None)) => ("configured", None),
+            Ok(Err(e)) => ("unreachable", Some(e.to_string())),
+            Err(_) => (
+                "unreachable",
+                Some(format!("no response within {}s", PROBE_TIMEOUT.as_secs())),
+            ),
+        };
+    ProviderStatus {
+        name,
+        model,
+        connectivity,
+        error,
+    }
+}
+
+/// Server health, version and uptime. Requests with the secret key also get the provider,
+/// extensions and resource usage. Responds 503 when degraded, so `?probe=true` makes load
+/// balancers take a server with an unreachable provider out of rotation. Probing sends the
+/// provider a request, so it needs the secret key too.
+#[utoipa::path(
+    get,
+    path = "/status",
+    params(
+        ("probe" = Option<bool>, Query, description = "Check that the provider is reachable")
+    ),
+    responses(
+        (status = 200, description = "Server is running", body = StatusResponse),
+        (status = 401, description = "Probing without the secret key"),
+        (status = 503, description = "The state store or the probed provider is unreachable", body = StatusResponse)
+    )
+)]
+async fn status(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<StatusQuery>,
+) -> Result<(StatusCode, Json<StatusResponse>), StatusCode> {
+    let authorized = verify_secret_key(&headers, &state).is_ok();
+    if query.probe && !authorized {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let sessions = state.store.list(SESSIONS).await;
+    let provider = if authorized {
+        Some(provider_status(&state, query.probe).await)
+    } else {
+        None
+    };
+
+    let degraded = sessions.is_err()
+        || provider
+            .as_ref()
+            .is_some_and(|provider| provider.connectivity == "unreachable");
+    let details = match provider {
+        Some(provider) => {
+            let mut extensions = Vec::new();
+            if let Ok(agent) = state.get_agent().await {
+                for name in agent.list_extensions().await {
+                    let tool_count = agent.list_tools(Some(name.clone())).await.len();
+                    extensions.push(ExtensionStatus { name, tool_count });
+                }
+            }
+            Some(StatusDetails {
+                provider,
+                extensions,
+                sessions: sessions.as_ref().ok().map(Vec::len),
+                pending_approvals: state
+                    .store
+                    .list_as::<PendingApproval>(APPROVALS)
+                    .await
+                    .ok()
+                    .map(|approvals| {
+                        approvals
+                            .iter()
+                            .filter(|(_, approval)| approval.server_id == state.server_id)
+                            .count()
+                    }),
+                scheduler_running: state.scheduler().await.is_ok(),
+                goose_mode: Config::global()
+                    .get_param("GOOSE_MODE")
+                    .unwrap_or("auto".to_string()),
+                resources: ResourceUsage {
+                    pid: std::process::id(),
+                    memory_rss_bytes: memory_rss_bytes(),
+                },
+            })
+        }
+        _ => None,
+    };
+
+    let code = if degraded {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    Ok((
+        code,
+        Json(StatusResponse {
+            status: if degraded { "degraded" } else { "ok" },
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_secs: state.started_at.elapsed().as_secs(),
+            details,
+        }),
+    ))
 }
 
 /// Configure health check routes
-pub fn routes() -> Router {
-    Router::new().route("/status", get(status))
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use goose::agents::Agent;
+    use tower::ServiceExt;
+
+    async fn get_status(
+        app: Router,
+        uri: &str,
+        secret: Option<&str>,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut request = Request::builder().uri(uri);
+        if let Some(secret) = secret {
+            request = request.header("x-secret-key", secret);
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let code = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (code, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_status_details_need_secret_key() {
+        let state = AppState::new(Arc::new(Agent::new()), "test-secret".to_string()).await;
+
+        let (code, body) = get_status(routes(state.clone()), "/status", None).await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert!(body.get("details").is_none());
+
+        // Probing calls the provider, so it needs the key as well
+        let (code, _) = get_status(routes(state.clone()), "/status?probe=true", None).await;
+        assert_eq!(code, StatusCode::UNAUTHORIZED);
+
+        let (code, body) = get_status(routes(state), "/status", Some("test-secret")).await;
+        assert_eq!(code, StatusCode::OK);
+        let details = &body["details"];
+        assert_eq!(details["provider"]["connectivity"], "not_configured");
+        assert_eq!(details["sessions"], 0);
+        assert_eq!(details["scheduler_running"], false);
+        assert_eq!(details["resources"]["pid"], std::process::id());
+    }
 }
@@ -9,20 +9,31 @@ pub mod reply;
 pub mod schedule;
 pub mod session;
 pub mod utils;
+pub mod webhooks;
 use std::sync::Arc;
 
+use axum::extract::DefaultBodyLimit;
 use axum::Router;
 
+use crate::limits::BodyLimitSettings;
+
 // Function to configure all routes
-pub fn configure(state: Arc<crate::state::AppState>) -> Router {
-    Router::new()
-        .merge(health::routes())
+pub fn configure(state: Arc<crate::state::AppState>, body_limit: &BodyLimitSettings) -> Router {
+    // Routes that take a whole conversation, which can include images
+    let uploads = Router::new()
         .merge(reply::routes(state.clone()))
-        .merge(agent::routes(state.clone()))
         .merge(context::routes(state.clone()))
+        .merge(recipe::routes(state.clone()))
+        .layer(DefaultBodyLimit::max(body_limit.max_upload_bytes));
+
+    Router::new()
+        .merge(health::routes(state.clone()))
+        .merge(uploads)
+        .merge(agent::routes(state.clone()))
         .merge(extension::routes(state.clone()))
         .merge(config_management::routes(state.clone()))
-        .merge(recipe::routes(state.clone()))
         .merge(session::routes(state.clone()))
         .merge(schedule::routes(state.clone()))
+        .merge(webhooks::routes(state.clone()))
+        .layer(DefaultBodyLimit::max(body_limit.max_bytes))
 }
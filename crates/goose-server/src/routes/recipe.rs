@@ -4,10 +4,11 @@ use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
 use goose::message::Message;
 use goose::recipe::Recipe;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::state::AppState;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateRecipeRequest {
     messages: Vec<Message>,
     // Required metadata
@@ -20,7 +21,7 @@ pub struct CreateRecipeRequest {
     author: Option<AuthorRequest>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct AuthorRequest {
     #[serde(default)]
     contact: Option<String>,
@@ -28,13 +29,24 @@ pub struct AuthorRequest {
     metadata: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CreateRecipeResponse {
+    #[schema(value_type = Option<Object>)]
     recipe: Option<Recipe>,
     error: Option<String>,
 }
 
 /// Create a Recipe configuration from the current state of an agent
+#[utoipa::path(
+    post,
+    path = "/recipe/create",
+    request_body = CreateRecipeRequest,
+    responses(
+        (status = 200, description = "Recipe created", body = CreateRecipeResponse),
+        (status = 400, description = "Recipe could not be created", body = CreateRecipeResponse),
+        (status = 412, description = "Agent not initialized", body = CreateRecipeResponse)
+    )
+)]
 async fn create_recipe(
     State(state): State<Arc<AppState>>,
     Json(request): Json<CreateRecipeRequest>,
@@ -1,16 +1,27 @@
-use super::utils::verify_secret_key;
+use super::utils::authenticate;
 use crate::state::AppState;
+use crate::state_store::{
+    PendingApproval, SessionActivity, SessionUsage, StateStore, APPROVALS, SESSIONS, TOOL_REQUESTS,
+    USAGE,
+};
+use crate::streams::{last_event_id, ReplyStream, Watchers, HEARTBEAT_INTERVAL};
+use crate::users::User;
+use crate::webhooks::WebhookEvent;
 use axum::{
-    extract::State,
+    extract::{Path, Query, State},
     http::{self, HeaderMap, StatusCode},
     response::IntoResponse,
-    routing::post,
+    routing::{get, post},
     Json, Router,
 };
 use bytes::Bytes;
 use futures::{stream::StreamExt, Stream};
 use goose::{
-    agents::{AgentEvent, SessionConfig},
+    agents::{
+        context_recovery::ContextRecoveryEvent, guardrails::GuardrailEvent, timeouts::TimeoutEvent,
+        AgentEvent, SessionConfig,
+    },
+    config::ExtensionDisabledEvent,
     message::{Message, MessageContent},
     permission::permission_confirmation::PrincipalType,
 };
@@ -30,13 +41,13 @@ use std::{
     task::{Context, Poll},
     time::Duration,
 };
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tokio::time::timeout;
 use tokio_stream::wrappers::ReceiverStream;
 use utoipa::ToSchema;
 
-#[derive(Debug, Deserialize)]
-struct ChatRequest {
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChatRequest {
     messages: Vec<Message>,
     session_id: Option<String>,
     session_working_dir: String,
@@ -44,11 +55,12 @@ struct ChatRequest {
 
 pub struct SseResponse {
     rx: ReceiverStream<String>,
+    session_id: String,
 }
 
 impl SseResponse {
-    fn new(rx: ReceiverStream<String>) -> Self {
-        Self { rx }
+    fn new(rx: ReceiverStream<String>, session_id: String) -> Self {
+        Self { rx, session_id }
     }
 }
 
@@ -64,11 +76,12 @@ impl Stream for SseResponse {
 
 impl IntoResponse for SseResponse {
     fn into_response(self) -> axum::response::Response {
-        let stream = self;
-        let body = axum::body::Body::from_stream(stream);
+        let session_id = self.session_id.clone();
+        let body = axum::body::Body::from_stream(self);
 
         http::Response::builder()
             .header("Content-Type", "text/event-stream")
+            .header("X-Session-Id", session_id)
             .header("Cache-Control", "no-cache")
             .header("Connection", "keep-alive")
             .body(body)
@@ -76,9 +89,10 @@ impl IntoResponse for SseResponse {
     }
 }
 
-#[derive(Debug, Serialize)]
+/// Event sent on the `/reply` stream, each as a `data:` line of JSON
+#[derive(Debug, Serialize, ToSchema)]
 #[serde(tag = "type")]
-enum MessageEvent {
+pub enum MessageEvent {
     Message {
         message: Message,
     },
@@ -90,81 +104,279 @@ enum MessageEvent {
     },
     Notification {
         request_id: String,
+        #[schema(value_type = Object)]
         message: JsonRpcMessage,
     },
+    /// The run stopped waiting on the user for an approval or a frontend tool result
+    Timeout {
+        timeout: TimeoutEvent,
+    },
+    /// Older messages were replaced by a summary, the client should replace its history
+    HistoryReplaced {
+        messages: Vec<Message>,
+    },
+    /// Likely secrets or personal data were masked in the message that follows
+    Guardrail {
+        guardrail: GuardrailEvent,
+    },
+    /// An extension kept failing and was removed from the session, the user can turn it back
+    /// on once it is fixed
+    ExtensionDisabled {
+        extension: ExtensionDisabledEvent,
+    },
+    /// The context window overflowed and older messages were dropped or summarized to retry,
+    /// a `HistoryReplaced` with the new history follows
+    ContextRecovered {
+        recovery: ContextRecoveryEvent,
+    },
+    /// Sent without an id while the stream is idle, so clients can tell a quiet reply from a
+    /// dropped connection
+    Heartbeat,
 }
 
-async fn stream_event(
-    event: MessageEvent,
-    tx: &mpsc::Sender<String>,
-) -> Result<(), mpsc::error::SendError<String>> {
-    let json = serde_json::to_string(&event).unwrap_or_else(|e| {
+fn event_json(event: &MessageEvent) -> String {
+    serde_json::to_string(event).unwrap_or_else(|e| {
         format!(
             r#"{{"type":"Error","error":"Failed to serialize event: {}"}}"#,
             e
         )
+    })
+}
+
+/// Add an event to the reply's stream, a Finish event ends it
+fn stream_event(event: MessageEvent, stream: &ReplyStream) {
+    let last = matches!(event, MessageEvent::Finish { .. });
+    stream.publish(event_json(&event), last);
+}
+
+/// Send a reply's events after `last_event_id` to a client, then the new ones as they come,
+/// with heartbeats in between
+fn subscribe(
+    stream: Arc<ReplyStream>,
+    last_event_id: Option<u64>,
+    session_id: String,
+) -> Result<SseResponse, StatusCode> {
+    let (mut backlog, mut live) = stream
+        .subscribe(last_event_id)
+        .map_err(|_| StatusCode::GONE)?;
+    let (tx, rx) = mpsc::channel(100);
+
+    tokio::spawn(async move {
+        let heartbeat = format!("data: {}\n\n", event_json(&MessageEvent::Heartbeat));
+        let mut last_sent = last_event_id.unwrap_or(0);
+        let mut ticks = tokio::time::interval(HEARTBEAT_INTERVAL);
+        ticks.tick().await;
+        loop {
+            for frame in backlog.drain(..) {
+                if frame.id <= last_sent {
+                    continue;
+                }
+                if tx.send(frame.to_sse()).await.is_err() {
+                    return;
+                }
+                last_sent = frame.id;
+                if frame.last {
+                    return;
+                }
+            }
+            tokio::select! {
+                frame = live.recv() => match frame {
+                    Ok(frame) => backlog.push(frame),
+                    // Fell behind the live events, catch up from the buffer
+                    Err(broadcast::error::RecvError::Lagged(_)) => match stream.subscribe(Some(last_sent)) {
+                        Ok((missed, receiver)) => {
+                            backlog = missed;
+                            live = receiver;
+                        }
+                        Err(_) => {
+                            let error = MessageEvent::Error {
+                                error: "Events were dropped, reload the session history".to_string(),
+                            };
+                            let _ = tx.send(format!("data: {}\n\n", event_json(&error))).await;
+                            return;
+                        }
+                    },
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = ticks.tick() => {
+                    if tx.send(heartbeat.clone()).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
     });
-    tx.send(format!("data: {}\n\n", json)).await
+
+    Ok(SseResponse::new(ReceiverStream::new(rx), session_id))
+}
+
+/// Keep the server's record of a session, and of any tool calls in the message waiting for
+/// the user's approval or for the client to run them, up to date
+async fn record_message(
+    store: &Arc<dyn StateStore>,
+    server_id: &str,
+    user: &User,
+    session_id: &str,
+    working_dir: &str,
+    message: &Message,
+    message_count: usize,
+) {
+    let activity = SessionActivity {
+        session_id: session_id.to_string(),
+        working_dir: working_dir.to_string(),
+        message_count,
+        updated_at: chrono::Utc::now(),
+    };
+    if let Err(e) = store
+        .put_as(&user.namespace(SESSIONS), session_id, &activity)
+        .await
+    {
+        tracing::warn!("Failed to record session activity: {}", e);
+    }
+
+    for content in &message.content {
+        // Recorded so only the user whose session made the call can send its result
+        if let MessageContent::FrontendToolRequest(request) = content {
+            if let Err(e) = store
+                .put(
+                    &user.namespace(TOOL_REQUESTS),
+                    &request.id,
+                    json!({ "session_id": session_id }),
+                )
+                .await
+            {
+                tracing::warn!("Failed to record pending tool request: {}", e);
+            }
+        }
+        if let MessageContent::ToolConfirmationRequest(request) = content {
+            let approval = PendingApproval {
+                id: request.id.clone(),
+                session_id: session_id.to_string(),
+                tool_name: request.tool_name.clone(),
+                arguments: request.arguments.clone(),
+                prompt: request.prompt.clone(),
+                diff: request.diff.clone(),
+                requested_at: chrono::Utc::now(),
+                server_id: server_id.to_string(),
+            };
+            if let Err(e) = store
+                .put_as(&user.namespace(APPROVALS), &request.id, &approval)
+                .await
+            {
+                tracing::warn!("Failed to record pending approval: {}", e);
+            }
+        }
+    }
+}
+
+/// Keep the user's record of what the session used, from the totals the agent keeps in the
+/// session file
+async fn record_usage(
+    store: &Arc<dyn StateStore>,
+    user: &User,
+    session_id: &str,
+    session_path: &std::path::Path,
+) {
+    let metadata = match session::read_metadata(session_path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            tracing::warn!("Failed to read session usage: {}", e);
+            return;
+        }
+    };
+    let usage = SessionUsage::from_metadata(session_id, &metadata);
+    if let Err(e) = store
+        .put_as(&user.namespace(USAGE), session_id, &usage)
+        .await
+    {
+        tracing::warn!("Failed to record session usage: {}", e);
+    }
 }
 
+#[utoipa::path(
+    post,
+    path = "/reply",
+    request_body = ChatRequest,
+    params(
+        ("Last-Event-ID" = Option<u64>, Header, description = "Resume the session's running reply after this event instead of starting a new one")
+    ),
+    responses(
+        (status = 200, description = "Stream of server-sent events, each carrying a MessageEvent. The X-Session-Id header holds the session to resume", body = MessageEvent, content_type = "text/event-stream"),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 410, description = "The events after Last-Event-ID are no longer buffered, reload the session history instead")
+    )
+)]
 async fn handler(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     Json(request): Json<ChatRequest>,
 ) -> Result<SseResponse, StatusCode> {
-    verify_secret_key(&headers, &state)?;
-
-    let (tx, rx) = mpsc::channel(100);
-    let stream = ReceiverStream::new(rx);
+    let user = authenticate(&headers, &state)?;
 
     let messages = request.messages;
-    let session_working_dir = request.session_working_dir;
+    let session_working_dir = state.users.confine(&user, &request.session_working_dir)?;
 
     let session_id = request
         .session_id
         .unwrap_or_else(session::generate_session_id);
+    let session_path = user.session_path(&session_id)?;
+    let key = user.namespace(&session_id);
 
-    tokio::spawn(async move {
-        let agent = state.get_agent().await;
+    // A client retrying with the last event it saw picks up the running reply instead
+    if let Some(last_event_id) = last_event_id(&headers) {
+        if let Some(stream) = state.streams.get(&key) {
+            return subscribe(stream, Some(last_event_id), session_id);
+        }
+    }
+
+    let reply = state.streams.start(&key);
+    let response = subscribe(reply.clone(), None, session_id.clone())?;
+
+    let store = state.store.clone();
+    let server_id = state.server_id.clone();
+    let streams = state.streams.clone();
+    let webhooks = state.webhooks.clone();
+    let working_dir = session_working_dir.clone();
+    let events = reply.clone();
+
+    let producer = tokio::spawn(async move {
+        let reply = events;
+        let agent = state.agent_for(&user).await;
         let agent = match agent {
             Ok(agent) => {
                 let provider = agent.provider().await;
                 match provider {
                     Ok(_) => agent,
                     Err(_) => {
-                        let _ = stream_event(
+                        stream_event(
                             MessageEvent::Error {
                                 error: "No provider configured".to_string(),
                             },
-                            &tx,
-                        )
-                        .await;
-                        let _ = stream_event(
+                            &reply,
+                        );
+                        stream_event(
                             MessageEvent::Finish {
                                 reason: "error".to_string(),
                             },
-                            &tx,
-                        )
-                        .await;
+                            &reply,
+                        );
                         return;
                     }
                 }
             }
             Err(_) => {
-                let _ = stream_event(
+                stream_event(
                     MessageEvent::Error {
                         error: "No agent configured".to_string(),
                     },
-                    &tx,
-                )
-                .await;
-                let _ = stream_event(
+                    &reply,
+                );
+                stream_event(
                     MessageEvent::Finish {
                         reason: "error".to_string(),
                     },
-                    &tx,
-                )
-                .await;
+                    &reply,
+                );
                 return;
             }
         };
@@ -175,7 +387,7 @@ async fn handler(
             .reply(
                 &messages,
                 Some(SessionConfig {
-                    id: session::Identifier::Name(session_id.clone()),
+                    id: session::Identifier::Path(session_path.clone()),
                     working_dir: PathBuf::from(session_working_dir),
                     schedule_id: None,
                 }),
@@ -185,26 +397,43 @@ async fn handler(
             Ok(stream) => stream,
             Err(e) => {
                 tracing::error!("Failed to start reply stream: {:?}", e);
-                let _ = stream_event(
+                webhooks
+                    .notify(
+                        &store,
+                        &user,
+                        &session_id,
+                        WebhookEvent::RunFailed,
+                        json!({"error": e.to_string()}),
+                    )
+                    .await;
+                stream_event(
                     MessageEvent::Error {
                         error: e.to_string(),
                     },
-                    &tx,
-                )
-                .await;
-                let _ = stream_event(
+                    &reply,
+                );
+                stream_event(
                     MessageEvent::Finish {
                         reason: "error".to_string(),
                     },
-                    &tx,
-                )
-                .await;
+                    &reply,
+                );
                 return;
             }
         };
+        webhooks
+            .notify(
+                &store,
+                &user,
+                &session_id,
+                WebhookEvent::RunStarted,
+                json!({"working_dir": working_dir}),
+            )
+            .await;
 
         let mut all_messages = messages.clone();
-        let session_path = session::get_path(session::Identifier::Name(session_id.clone()));
+        let mut watchers = Watchers::default();
+        let mut failure = None;
 
         loop {
             tokio::select! {
@@ -212,17 +441,32 @@ async fn handler(
                     match response {
                         Ok(Some(Ok(AgentEvent::Message(message)))) => {
                             all_messages.push(message.clone());
-                            if let Err(e) = stream_event(MessageEvent::Message { message }, &tx).await {
-                                tracing::error!("Error sending message through channel: {}", e);
-                                let _ = stream_event(
-                                    MessageEvent::Error {
-                                        error: e.to_string(),
-                                    },
-                                    &tx,
-                                ).await;
-                                break;
+                            record_message(
+                                &store,
+                                &server_id,
+                                &user,
+                                &session_id,
+                                &working_dir,
+                                &message,
+                                all_messages.len(),
+                            )
+                            .await;
+                            for content in &message.content {
+                                if let MessageContent::ToolConfirmationRequest(request) = content {
+                                    webhooks.notify(
+                                        &store,
+                                        &user,
+                                        &session_id,
+                                        WebhookEvent::ApprovalNeeded,
+                                        json!({
+                                            "request_id": request.id,
+                                            "tool_name": request.tool_name,
+                                            "prompt": request.prompt,
+                                        }),
+                                    ).await;
+                                }
                             }
-
+                            stream_event(MessageEvent::Message { message }, &reply);
 
                             let session_path = session_path.clone();
                             let messages = all_messages.clone();
@@ -234,34 +478,51 @@ async fn handler(
                             });
                         }
                         Ok(Some(Ok(AgentEvent::McpNotification((request_id, n))))) => {
-                            if let Err(e) = stream_event(MessageEvent::Notification{
+                            stream_event(MessageEvent::Notification{
                                 request_id: request_id.clone(),
                                 message: n,
-                            }, &tx).await {
-                                tracing::error!("Error sending message through channel: {}", e);
-                                let _ = stream_event(
-                                    MessageEvent::Error {
-                                        error: e.to_string(),
-                                    },
-                                    &tx,
-                                ).await;
+                            }, &reply);
+                        }
+                        Ok(Some(Ok(AgentEvent::HistoryReplaced(history)))) => {
+                            all_messages = history;
+                            if let Err(e) = session::persist_messages(&session_path, &all_messages, None).await {
+                                tracing::error!("Failed to store session history: {:?}", e);
+                            }
+                            stream_event(MessageEvent::HistoryReplaced { messages: all_messages.clone() }, &reply);
+                        }
+                        Ok(Some(Ok(AgentEvent::Guardrail(guardrail)))) => {
+                            stream_event(MessageEvent::Guardrail { guardrail }, &reply);
+                        }
+                        Ok(Some(Ok(AgentEvent::ExtensionDisabled(extension)))) => {
+                            stream_event(MessageEvent::ExtensionDisabled { extension }, &reply);
+                        }
+                        Ok(Some(Ok(AgentEvent::ContextRecovered(recovery)))) => {
+                            stream_event(MessageEvent::ContextRecovered { recovery }, &reply);
+                        }
+                        Ok(Some(Ok(AgentEvent::Timeout(timeout)))) => {
+                            if let Err(e) = store.delete(&user.namespace(APPROVALS), &timeout.request_id).await {
+                                tracing::warn!("Failed to clear pending approval: {}", e);
                             }
+                            stream_event(MessageEvent::Timeout { timeout }, &reply);
                         }
                         Ok(Some(Err(e))) => {
                             tracing::error!("Error processing message: {}", e);
-                            let _ = stream_event(
+                            failure = Some(e.to_string());
+                            stream_event(
                                 MessageEvent::Error {
                                     error: e.to_string(),
                                 },
-                                &tx,
-                            ).await;
+                                &reply,
+                            );
                             break;
                         }
                         Ok(None) => {
                             break;
                         }
-                        Err(_) => { // Heartbeat, used to detect disconnected clients
-                            if tx.is_closed() {
+                        Err(_) => { // Heartbeat, used to stop replies no client came back for
+                            if watchers.abandoned(&reply) {
+                                tracing::info!("Stopping reply for session {}, no client reconnected", session_id);
+                                failure = Some("stopped because no client reconnected".to_string());
                                 break;
                             }
                             continue;
@@ -271,45 +532,110 @@ async fn handler(
             }
         }
 
-        let _ = stream_event(
+        record_usage(&store, &user, &session_id, &session_path).await;
+
+        // A provider error can end the reply early without failing the stream
+        let (event, data) = match failure.or(agent.last_error().await) {
+            Some(error) => (WebhookEvent::RunFailed, json!({"error": error})),
+            None => (
+                WebhookEvent::RunFinished,
+                json!({"message_count": all_messages.len()}),
+            ),
+        };
+        webhooks
+            .notify(&store, &user, &session_id, event, data)
+            .await;
+    });
+
+    tokio::spawn(async move {
+        if let Err(e) = producer.await {
+            tracing::error!("Reply task failed: {}", e);
+            stream_event(
+                MessageEvent::Error {
+                    error: "The reply stopped unexpectedly".to_string(),
+                },
+                &reply,
+            );
+        }
+        // Ignored when the reply already finished with an error
+        stream_event(
             MessageEvent::Finish {
                 reason: "stop".to_string(),
             },
-            &tx,
-        )
-        .await;
+            &reply,
+        );
+        streams.expire(key, reply);
     });
 
-    Ok(SseResponse::new(stream))
+    Ok(response)
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct AskRequest {
+#[utoipa::path(
+    get,
+    path = "/reply/{session_id}/events",
+    params(
+        ("session_id" = String, Path, description = "Session of the running reply"),
+        ("Last-Event-ID" = Option<u64>, Header, description = "Id of the last event the client received, the events after it are sent again")
+    ),
+    responses(
+        (status = 200, description = "The rest of the reply's stream of server-sent events, each carrying a MessageEvent", body = MessageEvent, content_type = "text/event-stream"),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 404, description = "No running or recently finished reply for the session"),
+        (status = 410, description = "The events after Last-Event-ID are no longer buffered, reload the session history instead")
+    )
+)]
+pub async fn resume_reply(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> Result<SseResponse, StatusCode> {
+    let user = authenticate(&headers, &state)?;
+    let stream = state
+        .streams
+        .get(&user.namespace(&session_id))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    subscribe(stream, last_event_id(&headers), session_id)
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct AskRequest {
     prompt: String,
     session_id: Option<String>,
     session_working_dir: String,
 }
 
-#[derive(Debug, Serialize)]
-struct AskResponse {
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AskResponse {
     response: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/ask",
+    request_body = AskRequest,
+    responses(
+        (status = 200, description = "The agent's reply to the prompt", body = AskResponse),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 412, description = "Agent not initialized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
 async fn ask_handler(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     Json(request): Json<AskRequest>,
 ) -> Result<Json<AskResponse>, StatusCode> {
-    verify_secret_key(&headers, &state)?;
+    let user = authenticate(&headers, &state)?;
 
-    let session_working_dir = request.session_working_dir;
+    let session_working_dir = state.users.confine(&user, &request.session_working_dir)?;
 
     let session_id = request
         .session_id
         .unwrap_or_else(session::generate_session_id);
+    let session_path = user.session_path(&session_id)?;
 
     let agent = state
-        .get_agent()
+        .agent_for(&user)
         .await
         .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
 
@@ -322,7 +648,7 @@ async fn ask_handler(
         .reply(
             &messages,
             Some(SessionConfig {
-                id: session::Identifier::Name(session_id.clone()),
+                id: session::Identifier::Path(session_path.clone()),
                 working_dir: PathBuf::from(session_working_dir),
                 schedule_id: None,
             }),
@@ -356,6 +682,21 @@ async fn ask_handler(
                 // Handle notifications if needed
                 tracing::info!("Received notification: {:?}", n);
             }
+            Ok(AgentEvent::Timeout(timeout)) => {
+                tracing::warn!("Stopped waiting for the user: {:?}", timeout);
+            }
+            Ok(AgentEvent::HistoryReplaced(history)) => {
+                all_messages = history;
+            }
+            Ok(AgentEvent::Guardrail(event)) => {
+                tracing::warn!("{}", event.summary());
+            }
+            Ok(AgentEvent::ExtensionDisabled(event)) => {
+                tracing::warn!("{}", event.summary());
+            }
+            Ok(AgentEvent::ContextRecovered(event)) => {
+                tracing::info!("{}", event.summary());
+            }
             Err(e) => {
                 tracing::error!("Error processing as_ai message: {}", e);
                 return Err(StatusCode::INTERNAL_SERVER_ERROR);
@@ -366,10 +707,8 @@ async fn ask_handler(
     if !response_message.content.is_empty() {
         all_messages.push(response_message);
     }
+    record_usage(&state.store, &user, &session_id, &session_path).await;
 
-    let session_path = session::get_path(session::Identifier::Name(session_id.clone()));
-
-    let session_path = session_path.clone();
     let messages = all_messages.clone();
     let provider = Arc::clone(provider.as_ref().unwrap());
     tokio::spawn(async move {
@@ -402,6 +741,7 @@ fn default_principal_type() -> PrincipalType {
     responses(
         (status = 200, description = "Permission action is confirmed", body = Value),
         (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 404, description = "No pending approval with this id for the user"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -410,10 +750,22 @@ pub async fn confirm_permission(
     headers: HeaderMap,
     Json(request): Json<PermissionConfirmationRequest>,
 ) -> Result<Json<Value>, StatusCode> {
-    verify_secret_key(&headers, &state)?;
+    let user = authenticate(&headers, &state)?;
+    let approvals = user.namespace(APPROVALS);
+    // Members can only answer the approvals of their own sessions
+    if let User::Member(_) = user {
+        let pending = state
+            .store
+            .get(&approvals, &request.id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if pending.is_none() {
+            return Err(StatusCode::NOT_FOUND);
+        }
+    }
 
     let agent = state
-        .get_agent()
+        .agent_for(&user)
         .await
         .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
 
@@ -433,21 +785,81 @@ pub async fn confirm_permission(
             },
         )
         .await;
+    if let Err(e) = state.store.delete(&approvals, &request.id).await {
+        tracing::warn!("Failed to clear pending approval: {}", e);
+    }
     Ok(Json(Value::Object(serde_json::Map::new())))
 }
 
 #[derive(Debug, Deserialize)]
-struct ToolResultRequest {
+pub struct PendingApprovalsQuery {
+    session_id: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/confirm/pending",
+    params(
+        ("session_id" = Option<String>, Query, description = "Only list approvals for this session")
+    ),
+    responses(
+        (status = 200, description = "Tool calls waiting for the user's approval, oldest first", body = [PendingApproval]),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn pending_approvals(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<PendingApprovalsQuery>,
+) -> Result<Json<Vec<PendingApproval>>, StatusCode> {
+    let user = authenticate(&headers, &state)?;
+
+    let mut approvals: Vec<PendingApproval> = state
+        .store
+        .list_as(&user.namespace(APPROVALS))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|(_, approval)| approval)
+        .filter(|approval: &PendingApproval| {
+            // A shared store also holds approvals that only other servers can answer
+            approval.server_id == state.server_id
+                && query
+                    .session_id
+                    .as_ref()
+                    .is_none_or(|id| &approval.session_id == id)
+        })
+        .collect();
+    approvals.sort_by_key(|approval| approval.requested_at);
+    Ok(Json(approvals))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ToolResultRequest {
     id: String,
+    #[schema(value_type = Object)]
     result: ToolResult<Vec<Content>>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/tool_result",
+    request_body = ToolResultRequest,
+    responses(
+        (status = 200, description = "Tool result delivered to the agent", body = Value),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 404, description = "No pending tool call with this id for the user"),
+        (status = 412, description = "Agent not initialized"),
+        (status = 422, description = "Invalid tool result")
+    )
+)]
 async fn submit_tool_result(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     raw: axum::extract::Json<serde_json::Value>,
 ) -> Result<Json<Value>, StatusCode> {
-    verify_secret_key(&headers, &state)?;
+    let user = authenticate(&headers, &state)?;
 
     tracing::info!(
         "Received tool result request: {}",
@@ -466,19 +878,39 @@ async fn submit_tool_result(
         }
     };
 
+    let requests = user.namespace(TOOL_REQUESTS);
+    // Members can only answer the tool calls of their own sessions
+    if let User::Member(_) = user {
+        let pending = state
+            .store
+            .get(&requests, &payload.id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if pending.is_none() {
+            return Err(StatusCode::NOT_FOUND);
+        }
+    }
+
     let agent = state
-        .get_agent()
+        .agent_for(&user)
         .await
         .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
-    agent.handle_tool_result(payload.id, payload.result).await;
+    agent
+        .handle_tool_result(payload.id.clone(), payload.result)
+        .await;
+    if let Err(e) = state.store.delete(&requests, &payload.id).await {
+        tracing::warn!("Failed to clear pending tool request: {}", e);
+    }
     Ok(Json(json!({"status": "ok"})))
 }
 
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/reply", post(handler))
+        .route("/reply/{session_id}/events", get(resume_reply))
         .route("/ask", post(ask_handler))
         .route("/confirm", post(confirm_permission))
+        .route("/confirm/pending", get(pending_approvals))
         .route("/tool_result", post(submit_tool_result))
         .with_state(state)
 }
@@ -567,5 +999,51 @@ mod tests {
 
             assert_eq!(response.status(), StatusCode::OK);
         }
+
+        #[tokio::test]
+        async fn test_members_only_answer_their_own_tool_calls() {
+            let dir = tempfile::tempdir().unwrap();
+            let users_file = dir.path().join("users.yaml");
+            std::fs::write(
+                &users_file,
+                format!(
+                    "users:\n  - id: alice\n    token: alice-token\n    working_dir: {}\n",
+                    dir.path().display()
+                ),
+            )
+            .unwrap();
+            let state = AppState::with_store(
+                Arc::new(Agent::new()),
+                "test-secret".to_string(),
+                Arc::new(crate::state_store::InMemoryStore::default()),
+                crate::users::UserDirectory::from_file(&users_file).unwrap(),
+                crate::webhooks::WebhookDispatcher::default(),
+            )
+            .await;
+            let requests = User::Member("alice".to_string()).namespace(TOOL_REQUESTS);
+            state
+                .store
+                .put(&requests, "mine", json!({ "session_id": "s1" }))
+                .await
+                .unwrap();
+
+            let submit = |id: &str| {
+                Request::builder()
+                    .uri("/tool_result")
+                    .method("POST")
+                    .header("content-type", "application/json")
+                    .header("x-secret-key", "alice-token")
+                    .body(Body::from(
+                        json!({ "id": id, "result": { "Ok": [] } }).to_string(),
+                    ))
+                    .unwrap()
+            };
+            let app = routes(state.clone());
+            let response = app.clone().oneshot(submit("theirs")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            let response = app.oneshot(submit("mine")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(state.store.get(&requests, "mine").await.unwrap().is_none());
+        }
     }
 }
@@ -1,16 +1,17 @@
-use super::utils::verify_secret_key;
+use super::utils::authenticate;
 use std::sync::Arc;
 
 use crate::state::AppState;
+use crate::state_store::{SessionUsage, USAGE};
 use axum::{
     extract::{Path, State},
     http::{HeaderMap, StatusCode},
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
 use goose::message::Message;
 use goose::session;
-use goose::session::info::{get_session_info, SessionInfo, SortOrder};
+use goose::session::info::{get_session_info_in, SessionInfo, SortOrder};
 use goose::session::SessionMetadata;
 use serde::Serialize;
 use utoipa::ToSchema;
@@ -33,6 +34,17 @@ pub struct SessionHistoryResponse {
     messages: Vec<Message>,
 }
 
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageResponse {
+    /// Tokens and estimated cost of every session of the user
+    sessions: Vec<SessionUsage>,
+    input_tokens: i64,
+    output_tokens: i64,
+    total_tokens: i64,
+    cost_usd: f64,
+}
+
 #[utoipa::path(
     get,
     path = "/sessions",
@@ -46,15 +58,18 @@ pub struct SessionHistoryResponse {
     ),
     tag = "Session Management"
 )]
-// List all available sessions
+// List all available sessions of the user
 async fn list_sessions(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
 ) -> Result<Json<SessionListResponse>, StatusCode> {
-    verify_secret_key(&headers, &state)?;
+    let user = authenticate(&headers, &state)?;
 
-    let sessions =
-        get_session_info(SortOrder::Descending).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let session_dir = user
+        .session_dir()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let sessions = get_session_info_in(&session_dir, SortOrder::Descending)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(SessionListResponse { sessions }))
 }
@@ -82,9 +97,9 @@ async fn get_session_history(
     headers: HeaderMap,
     Path(session_id): Path<String>,
 ) -> Result<Json<SessionHistoryResponse>, StatusCode> {
-    verify_secret_key(&headers, &state)?;
+    let user = authenticate(&headers, &state)?;
 
-    let session_path = session::get_path(session::Identifier::Name(session_id.clone()));
+    let session_path = user.session_path(&session_id)?;
 
     // Read metadata
     let metadata = session::read_metadata(&session_path).map_err(|_| StatusCode::NOT_FOUND)?;
@@ -104,10 +119,88 @@ async fn get_session_history(
     }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/pause",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "The run working on the session was asked to pause"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Ask a headless `goose run` on the session to checkpoint and stop, so it can be continued
+// interactively with `goose session --resume --from-run`
+async fn pause_session(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let user = authenticate(&headers, &state)?;
+
+    let session_path = user.session_path(&session_id)?;
+    if !session_path.exists() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    session::checkpoint::request_pause(&session_path).map_err(|e| {
+        tracing::error!("Failed to request a pause: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/usage",
+    responses(
+        (status = 200, description = "What the user's sessions have used", body = UsageResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Tokens and cost of the user's sessions, recorded after each reply
+async fn get_usage(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<UsageResponse>, StatusCode> {
+    let user = authenticate(&headers, &state)?;
+
+    let mut sessions: Vec<SessionUsage> = state
+        .store
+        .list_as::<SessionUsage>(&user.namespace(USAGE))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|(_, usage)| usage)
+        .collect();
+    sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+
+    Ok(Json(UsageResponse {
+        input_tokens: sessions.iter().map(|usage| usage.input_tokens).sum(),
+        output_tokens: sessions.iter().map(|usage| usage.output_tokens).sum(),
+        total_tokens: sessions.iter().map(|usage| usage.total_tokens).sum(),
+        cost_usd: sessions.iter().map(|usage| usage.cost_usd).sum(),
+        sessions,
+    }))
+}
+
 // Configure routes for this module
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/sessions", get(list_sessions))
         .route("/sessions/{session_id}", get(get_session_history))
+        .route("/sessions/{session_id}/pause", post(pause_session))
+        .route("/usage", get(get_usage))
         .with_state(state)
 }
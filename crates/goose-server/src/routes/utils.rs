@@ -1,4 +1,5 @@
 use crate::state::AppState;
+use crate::users::User;
 use goose::config::Config;
 use goose::providers::base::{ConfigKey, ProviderMetadata};
 use http::{HeaderMap, StatusCode};
@@ -37,6 +38,12 @@ pub fn verify_secret_key(headers: &HeaderMap, state: &AppState) -> Result<Status
     }
 }
 
+/// The user a request was made by. Routes that hold a user's data use this, those that manage
+/// the server itself use `verify_secret_key` and are only open to the owner.
+pub fn authenticate(headers: &HeaderMap, state: &AppState) -> Result<User, StatusCode> {
+    state.users.authenticate(headers, &state.secret_key)
+}
+
 /// Inspects a configuration key to determine if it's set, its location, and value (for non-secret keys)
 #[allow(dead_code)]
 pub fn inspect_key(key_name: &str, is_secret: bool) -> Result<KeyInfo, Box<dyn Error>> {
@@ -0,0 +1,152 @@
+use super::utils::authenticate;
+use crate::state::AppState;
+use crate::state_store::WEBHOOKS;
+use crate::webhooks::{Webhook, WebhookEvent};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{delete, get},
+    Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// A URL to send the lifecycle events of one session, or of every session, to
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    /// http or https URL that receives a POST for each event
+    pub url: String,
+    /// Only send the events of this session
+    pub session_id: Option<String>,
+    /// The events to send, all of them when left out
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    /// Key to sign the deliveries with, see the X-Goose-Signature header
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhooksQuery {
+    session_id: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook registered, without its secret", body = Webhook),
+        (status = 400, description = "Bad request - The URL is not an http or https URL"),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn create_webhook(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<CreateWebhookRequest>,
+) -> Result<Json<Webhook>, StatusCode> {
+    let user = authenticate(&headers, &state)?;
+
+    let url = reqwest::Url::parse(&request.url).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let webhook = Webhook::new(
+        request.url,
+        request.session_id,
+        request.events,
+        request.secret.filter(|secret| !secret.is_empty()),
+    );
+    state
+        .store
+        .put_as(&user.namespace(WEBHOOKS), &webhook.id, &webhook)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(webhook.redacted()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    params(
+        ("session_id" = Option<String>, Query, description = "Only list the webhooks that get this session's events")
+    ),
+    responses(
+        (status = 200, description = "Registered webhooks without their secrets, oldest first", body = [Webhook]),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn list_webhooks(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<WebhooksQuery>,
+) -> Result<Json<Vec<Webhook>>, StatusCode> {
+    let user = authenticate(&headers, &state)?;
+
+    let mut webhooks: Vec<Webhook> = state
+        .store
+        .list_as(&user.namespace(WEBHOOKS))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|(_, webhook): (String, Webhook)| webhook.redacted())
+        .filter(|webhook| {
+            query.session_id.as_ref().is_none_or(|id| {
+                webhook
+                    .session_id
+                    .as_ref()
+                    .is_none_or(|webhook_id| webhook_id == id)
+            })
+        })
+        .collect();
+    webhooks.sort_by_key(|webhook| webhook.created_at);
+    Ok(Json(webhooks))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    params(
+        ("id" = String, Path, description = "ID of the webhook to remove")
+    ),
+    responses(
+        (status = 204, description = "Webhook removed"),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 404, description = "No webhook with this ID"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let user = authenticate(&headers, &state)?;
+
+    let namespace = user.namespace(WEBHOOKS);
+    let existing = state
+        .store
+        .get(&namespace, &id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if existing.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    state
+        .store
+        .delete(&namespace, &id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route("/webhooks/{id}", delete(delete_webhook))
+        .with_state(state)
+}
@@ -1,6 +1,12 @@
+use crate::state_store::{new_server_id, InMemoryStore, StateStore};
+use crate::streams::StreamRegistry;
+use crate::users::{User, UserDirectory};
+use crate::webhooks::WebhookDispatcher;
 use goose::agents::Agent;
 use goose::scheduler::Scheduler;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Mutex;
 
 pub type AgentRef = Arc<Agent>;
@@ -10,14 +16,55 @@ pub struct AppState {
     agent: Option<AgentRef>,
     pub secret_key: String,
     pub scheduler: Arc<Mutex<Option<Arc<Scheduler>>>>,
+    /// Session metadata and pending approvals, kept in the configured backend
+    pub store: Arc<dyn StateStore>,
+    /// When the server started, reported as uptime by /status
+    pub started_at: Instant,
+    /// Team members who can use the server besides the holder of the secret key
+    pub users: Arc<UserDirectory>,
+    /// Running and recently finished reply streams, for clients that reconnect
+    pub streams: Arc<StreamRegistry>,
+    /// Sends the lifecycle events of runs to webhooks
+    pub webhooks: Arc<WebhookDispatcher>,
+    /// Tells this server's pending approvals apart from those of other servers sharing the store
+    pub server_id: String,
+    /// The agents of team members, keyed by member id, created on their first request
+    member_agents: Arc<Mutex<HashMap<String, AgentRef>>>,
+    /// What the owner added to the system prompt, so member agents get it too
+    prompt_extensions: Arc<Mutex<Vec<String>>>,
 }
 
 impl AppState {
     pub async fn new(agent: AgentRef, secret_key: String) -> Arc<AppState> {
+        Self::with_store(
+            agent,
+            secret_key,
+            Arc::new(InMemoryStore::default()),
+            UserDirectory::default(),
+            WebhookDispatcher::default(),
+        )
+        .await
+    }
+
+    pub async fn with_store(
+        agent: AgentRef,
+        secret_key: String,
+        store: Arc<dyn StateStore>,
+        users: UserDirectory,
+        webhooks: WebhookDispatcher,
+    ) -> Arc<AppState> {
         Arc::new(Self {
             agent: Some(agent.clone()),
             secret_key,
             scheduler: Arc::new(Mutex::new(None)),
+            store,
+            started_at: Instant::now(),
+            users: Arc::new(users),
+            streams: Arc::new(StreamRegistry::default()),
+            webhooks: Arc::new(webhooks),
+            server_id: new_server_id(),
+            member_agents: Arc::new(Mutex::new(HashMap::new())),
+            prompt_extensions: Arc::new(Mutex::new(Vec::new())),
         })
     }
 
@@ -27,6 +74,56 @@ impl AppState {
             .ok_or_else(|| anyhow::anyhow!("Agent needs to be created first."))
     }
 
+    /// The agent that serves a user. Members each get their own, with the owner's provider,
+    /// extensions and prompt, whose extensions run in the member's working directory.
+    pub async fn agent_for(&self, user: &User) -> Result<AgentRef, anyhow::Error> {
+        let owner = self.get_agent().await?;
+        let User::Member(id) = user else {
+            return Ok(owner);
+        };
+        let mut agents = self.member_agents.lock().await;
+        if let Some(agent) = agents.get(id) {
+            return Ok(agent.clone());
+        }
+
+        let agent = Arc::new(Agent::new());
+        if let Ok(provider) = owner.provider().await {
+            agent.update_provider(provider).await?;
+        }
+        if let Some(working_dir) = self.users.working_dir(user)? {
+            agent
+                .set_extension_environment(working_dir, self.users.extension_env(user)?)
+                .await;
+        }
+        for extension in owner.extension_configs().await {
+            agent.add_extension(extension).await?;
+        }
+        for instruction in self.prompt_extensions.lock().await.iter() {
+            agent.extend_system_prompt(instruction.clone()).await;
+        }
+        agents.insert(id.clone(), agent.clone());
+        Ok(agent)
+    }
+
+    /// Extend the owner's system prompt, and that of every member's agent
+    pub async fn extend_system_prompt(&self, instruction: String) -> Result<(), anyhow::Error> {
+        self.get_agent()
+            .await?
+            .extend_system_prompt(instruction.clone())
+            .await;
+        for agent in self.member_agents().await {
+            agent.extend_system_prompt(instruction.clone()).await;
+        }
+        self.prompt_extensions.lock().await.push(instruction);
+        Ok(())
+    }
+
+    /// The agents members have so far, for applying the owner's changes to the provider and
+    /// extensions to them as well
+    pub async fn member_agents(&self) -> Vec<AgentRef> {
+        self.member_agents.lock().await.values().cloned().collect()
+    }
+
     pub async fn set_scheduler(&self, sched: Arc<Scheduler>) {
         let mut guard = self.scheduler.lock().await;
         *guard = Some(sched);
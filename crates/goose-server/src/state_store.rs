@@ -0,0 +1,503 @@
+#[cfg(feature = "redis")]
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use etcetera::{choose_app_strategy, AppStrategy};
+use goose::config::APP_STRATEGY;
+use goose::session::SessionMetadata;
+#[cfg(feature = "redis")]
+use redis::AsyncCommands;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use utoipa::ToSchema;
+
+/// Metadata of the sessions the server has replied to, keyed by session id
+pub const SESSIONS: &str = "sessions";
+/// Tool calls waiting for the user to approve or deny them, keyed by tool request id
+pub const APPROVALS: &str = "approvals";
+/// Tool calls waiting for the client to run them and send the result, keyed by tool request id
+pub const TOOL_REQUESTS: &str = "tool_requests";
+/// Long running task state, keyed by task id
+pub const TASKS: &str = "tasks";
+/// Webhooks registered through the API, keyed by webhook id
+pub const WEBHOOKS: &str = "webhooks";
+/// Tokens and cost of the sessions the server has replied to, keyed by session id
+pub const USAGE: &str = "usage";
+
+/// What the server knows about a session it replied to, stored under `SESSIONS`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SessionActivity {
+    pub session_id: String,
+    pub working_dir: String,
+    pub message_count: usize,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What a session has used so far, stored under `USAGE`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SessionUsage {
+    pub session_id: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub total_tokens: i64,
+    /// Estimated from the responses whose model price is known
+    pub cost_usd: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SessionUsage {
+    pub fn from_metadata(session_id: &str, metadata: &SessionMetadata) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            input_tokens: metadata.accumulated_input_tokens.unwrap_or(0).into(),
+            output_tokens: metadata.accumulated_output_tokens.unwrap_or(0).into(),
+            total_tokens: metadata.accumulated_total_tokens.unwrap_or(0).into(),
+            cost_usd: metadata
+                .message_usage
+                .iter()
+                .filter_map(|usage| usage.cost_usd)
+                .sum(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// A tool call waiting for the user's decision, stored under `APPROVALS`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PendingApproval {
+    pub id: String,
+    pub session_id: String,
+    pub tool_name: String,
+    #[schema(value_type = Object)]
+    pub arguments: Value,
+    pub prompt: Option<String>,
+    /// A unified diff of the change the tool call would make to a file, when it edits one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    /// The server whose agent is waiting on the approval, see [`new_server_id`]
+    #[serde(default)]
+    pub server_id: String,
+}
+
+/// Identifies one run of the server. Approvals can only be answered by the server that
+/// recorded them, their tool calls wait in its memory.
+pub fn new_server_id() -> String {
+    format!("{}-{}", std::process::id(), Utc::now().timestamp_millis())
+}
+
+/// Drop the approvals an earlier run of the server left in the given namespaces. Their tool
+/// calls died with that run, so they can never be answered.
+pub async fn clear_stale_approvals(
+    store: &Arc<dyn StateStore>,
+    namespaces: &[String],
+    server_id: &str,
+) -> Result<usize> {
+    let mut cleared = 0;
+    for namespace in namespaces {
+        for (key, approval) in store.list_as::<PendingApproval>(namespace).await? {
+            if approval.server_id != server_id {
+                store.delete(namespace, &key).await?;
+                cleared += 1;
+            }
+        }
+    }
+    Ok(cleared)
+}
+
+/// Server state that outlives a single request, so it can survive restarts or be shared by
+/// several server instances depending on the backend.
+///
+/// Values are grouped into namespaces such as `SESSIONS` and `APPROVALS`.
+#[async_trait]
+pub trait StateStore: Send + Sync {
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<Value>>;
+    async fn put(&self, namespace: &str, key: &str, value: Value) -> Result<()>;
+    async fn delete(&self, namespace: &str, key: &str) -> Result<()>;
+    /// Every entry in the namespace, in no particular order
+    async fn list(&self, namespace: &str) -> Result<Vec<(String, Value)>>;
+}
+
+impl dyn StateStore {
+    pub async fn get_as<T: DeserializeOwned>(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<T>> {
+        self.get(namespace, key)
+            .await?
+            .map(|value| Ok(serde_json::from_value(value)?))
+            .transpose()
+    }
+
+    pub async fn put_as<T: Serialize>(&self, namespace: &str, key: &str, value: &T) -> Result<()> {
+        self.put(namespace, key, serde_json::to_value(value)?).await
+    }
+
+    pub async fn list_as<T: DeserializeOwned>(&self, namespace: &str) -> Result<Vec<(String, T)>> {
+        self.list(namespace)
+            .await?
+            .into_iter()
+            .map(|(key, value)| Ok((key, serde_json::from_value(value)?)))
+            .collect()
+    }
+}
+
+/// Keeps everything in memory, state is lost when the server stops
+#[derive(Default)]
+pub struct InMemoryStore {
+    namespaces: RwLock<HashMap<String, HashMap<String, Value>>>,
+}
+
+#[async_trait]
+impl StateStore for InMemoryStore {
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<Value>> {
+        let namespaces = self.namespaces.read().unwrap();
+        Ok(namespaces
+            .get(namespace)
+            .and_then(|ns| ns.get(key))
+            .cloned())
+    }
+
+    async fn put(&self, namespace: &str, key: &str, value: Value) -> Result<()> {
+        let mut namespaces = self.namespaces.write().unwrap();
+        namespaces
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(key.to_string(), value);
+        Ok(())
+    }
+
+    async fn delete(&self, namespace: &str, key: &str) -> Result<()> {
+        let mut namespaces = self.namespaces.write().unwrap();
+        if let Some(ns) = namespaces.get_mut(namespace) {
+            ns.remove(key);
+        }
+        Ok(())
+    }
+
+    async fn list(&self, namespace: &str) -> Result<Vec<(String, Value)>> {
+        let namespaces = self.namespaces.read().unwrap();
+        Ok(namespaces
+            .get(namespace)
+            .map(|ns| ns.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default())
+    }
+}
+
+/// Keeps each entry in its own JSON file, `<root>/<namespace>/<key>.json`, so state survives
+/// restarts of a single server
+pub struct FileStore {
+    root: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// The server's data directory
+    /// - macOS/Linux: ~/.local/share/goose/server_state/
+    /// - Windows:     ~\AppData\Roaming\Block\goose\data\server_state\
+    pub fn default_root() -> Result<PathBuf> {
+        Ok(choose_app_strategy(APP_STRATEGY.clone())?.in_data_dir("server_state"))
+    }
+
+    fn path(&self, namespace: &str, key: &str) -> PathBuf {
+        self.root
+            .join(encode_key(namespace))
+            .join(format!("{}.json", encode_key(key)))
+    }
+}
+
+/// Escape characters that are not safe in file names, keys can be any string
+fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn decode_key(encoded: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut chars = encoded.bytes();
+    while let Some(b) = chars.next() {
+        if b == b'%' {
+            let hex = [chars.next()?, chars.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[async_trait]
+impl StateStore for FileStore {
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<Value>> {
+        match tokio::fs::read(self.path(namespace, key)).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put(&self, namespace: &str, key: &str, value: Value) -> Result<()> {
+        let path = self.path(namespace, key);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        // Write then rename so a crash never leaves a half written entry behind
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec(&value)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, namespace: &str, key: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.path(namespace, key)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn list(&self, namespace: &str) -> Result<Vec<(String, Value)>> {
+        let dir = self.root.join(encode_key(namespace));
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut values = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Some(key) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(decode_key)
+            else {
+                continue;
+            };
+            let bytes = tokio::fs::read(&path).await?;
+            values.push((key, serde_json::from_slice(&bytes)?));
+        }
+        Ok(values)
+    }
+}
+
+/// Keeps each namespace in a Redis hash, `goose:<namespace>`, so several server instances
+/// can share state. Needs the `redis` feature.
+#[cfg(feature = "redis")]
+pub struct RedisStore {
+    connection: redis::aio::MultiplexedConnection,
+}
+
+#[cfg(feature = "redis")]
+impl RedisStore {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url).context("Invalid Redis URL")?;
+        let connection = client
+            .get_multiplexed_async_connection()
+            .await
+            .with_context(|| format!("Failed to connect to Redis at {}", url))?;
+        Ok(Self { connection })
+    }
+
+    fn hash(namespace: &str) -> String {
+        format!("goose:{}", namespace)
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl StateStore for RedisStore {
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<Value>> {
+        let mut connection = self.connection.clone();
+        let value: Option<String> = connection.hget(Self::hash(namespace), key).await?;
+        Ok(value.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    async fn put(&self, namespace: &str, key: &str, value: Value) -> Result<()> {
+        let mut connection = self.connection.clone();
+        let _: () = connection
+            .hset(Self::hash(namespace), key, value.to_string())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, namespace: &str, key: &str) -> Result<()> {
+        let mut connection = self.connection.clone();
+        let _: () = connection.hdel(Self::hash(namespace), key).await?;
+        Ok(())
+    }
+
+    async fn list(&self, namespace: &str) -> Result<Vec<(String, Value)>> {
+        let mut connection = self.connection.clone();
+        let values: HashMap<String, String> = connection.hgetall(Self::hash(namespace)).await?;
+        values
+            .into_iter()
+            .map(|(key, value)| Ok((key, serde_json::from_str(&value)?)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateStoreBackend {
+    #[default]
+    Memory,
+    File,
+    Redis,
+}
+
+impl StateStoreBackend {
+    /// Whether several servers can use the store at once
+    pub fn is_shared(&self) -> bool {
+        *self == StateStoreBackend::Redis
+    }
+}
+
+/// Which backend holds server state, set with `GOOSE_STATE_STORE__BACKEND` and, for the file
+/// and Redis backends, `GOOSE_STATE_STORE__PATH` or `GOOSE_STATE_STORE__URL`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StateStoreSettings {
+    #[serde(default)]
+    pub backend: StateStoreBackend,
+    /// Directory for the file backend, defaults to the server's data directory
+    pub path: Option<PathBuf>,
+    /// Connection URL for the Redis backend, e.g. redis://127.0.0.1:6379
+    pub url: Option<String>,
+}
+
+impl StateStoreSettings {
+    pub async fn build(&self) -> Result<Arc<dyn StateStore>> {
+        Ok(match self.backend {
+            StateStoreBackend::Memory => Arc::new(InMemoryStore::default()),
+            StateStoreBackend::File => {
+                let root = match &self.path {
+                    Some(path) => path.clone(),
+                    None => FileStore::default_root()?,
+                };
+                Arc::new(FileStore::new(root))
+            }
+            #[cfg(feature = "redis")]
+            StateStoreBackend::Redis => {
+                let url = self.url.as_deref().context(
+                    "GOOSE_STATE_STORE__URL must be set to use the redis state store backend",
+                )?;
+                Arc::new(RedisStore::connect(url).await?)
+            }
+            #[cfg(not(feature = "redis"))]
+            StateStoreBackend::Redis => anyhow::bail!(
+                "goosed was built without the redis state store, rebuild it with --features redis"
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn check_store(store: Arc<dyn StateStore>) {
+        assert_eq!(store.get(SESSIONS, "missing").await.unwrap(), None);
+
+        store.put(SESSIONS, "a", json!({"n": 1})).await.unwrap();
+        store.put(SESSIONS, "a", json!({"n": 2})).await.unwrap();
+        store
+            .put(SESSIONS, "b/../c d", json!("odd key"))
+            .await
+            .unwrap();
+        store.put(APPROVALS, "a", json!(true)).await.unwrap();
+
+        assert_eq!(
+            store.get(SESSIONS, "a").await.unwrap(),
+            Some(json!({"n": 2}))
+        );
+        let mut listed = store.list(SESSIONS).await.unwrap();
+        listed.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            listed,
+            vec![
+                ("a".to_string(), json!({"n": 2})),
+                ("b/../c d".to_string(), json!("odd key")),
+            ]
+        );
+
+        store.delete(SESSIONS, "a").await.unwrap();
+        store.delete(SESSIONS, "a").await.unwrap();
+        assert_eq!(store.get(SESSIONS, "a").await.unwrap(), None);
+        assert_eq!(store.list(SESSIONS).await.unwrap().len(), 1);
+        assert_eq!(
+            store.get_as::<bool>(APPROVALS, "a").await.unwrap(),
+            Some(true)
+        );
+        assert!(store.list(TASKS).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store() {
+        check_store(Arc::new(InMemoryStore::default())).await;
+    }
+
+    #[tokio::test]
+    async fn test_clear_stale_approvals() {
+        let dir = tempfile::tempdir().unwrap();
+        let store: Arc<dyn StateStore> = Arc::new(FileStore::new(dir.path().to_path_buf()));
+        let approval = |id: &str, server_id: &str| PendingApproval {
+            id: id.to_string(),
+            session_id: "session".to_string(),
+            tool_name: "developer__shell".to_string(),
+            arguments: json!({}),
+            prompt: None,
+            diff: None,
+            requested_at: Utc::now(),
+            server_id: server_id.to_string(),
+        };
+        let members = "users/alice/approvals".to_string();
+        store
+            .put_as(APPROVALS, "old", &approval("old", "earlier"))
+            .await
+            .unwrap();
+        store
+            .put_as(&members, "old", &approval("old", "earlier"))
+            .await
+            .unwrap();
+        store
+            .put_as(APPROVALS, "new", &approval("new", "current"))
+            .await
+            .unwrap();
+
+        let cleared =
+            clear_stale_approvals(&store, &[APPROVALS.to_string(), members.clone()], "current")
+                .await
+                .unwrap();
+        assert_eq!(cleared, 2);
+        let left = store.list_as::<PendingApproval>(APPROVALS).await.unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].1.server_id, "current");
+        assert!(store.list(&members).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_store() {
+        let dir = tempfile::tempdir().unwrap();
+        check_store(Arc::new(FileStore::new(dir.path().to_path_buf()))).await;
+
+        // Entries are still there for the next server
+        let store = FileStore::new(dir.path().to_path_buf());
+        assert_eq!(
+            store.get(SESSIONS, "b/../c d").await.unwrap(),
+            Some(json!("odd key"))
+        );
+    }
+}
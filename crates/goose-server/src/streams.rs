@@ -0,0 +1,226 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::http::HeaderMap;
+use tokio::sync::broadcast;
+
+/// Events kept per stream for clients that reconnect
+pub const REPLAY_BUFFER_EVENTS: usize = 512;
+/// How long a finished stream can still be resumed
+pub const FINISHED_STREAM_TTL: Duration = Duration::from_secs(60);
+/// How long a reply keeps running without a connected client before it is stopped
+pub const RESUME_GRACE_PERIOD: Duration = Duration::from_secs(60);
+/// Interval of the heartbeat events sent on idle streams
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// One SSE event with its id, ready to write to the response
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub id: u64,
+    pub data: String,
+    /// The last event of the stream
+    pub last: bool,
+}
+
+impl Frame {
+    pub fn to_sse(&self) -> String {
+        format!("id: {}\ndata: {}\n\n", self.id, self.data)
+    }
+}
+
+/// Why a stream can't be resumed from the requested event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeError {
+    /// Events after the requested one were already dropped from the buffer
+    Expired,
+}
+
+struct Buffer {
+    frames: VecDeque<Frame>,
+    next_id: u64,
+    finished: bool,
+}
+
+/// The events of a running reply, buffered so a client that lost its connection can pick up
+/// where it left off
+pub struct ReplyStream {
+    buffer: Mutex<Buffer>,
+    sender: broadcast::Sender<Frame>,
+    capacity: usize,
+}
+
+impl ReplyStream {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            buffer: Mutex::new(Buffer {
+                frames: VecDeque::new(),
+                next_id: 1,
+                finished: false,
+            }),
+            sender,
+            capacity,
+        }
+    }
+
+    /// Add an event, returning its id. Nothing is added once the stream is finished.
+    pub fn publish(&self, data: String, last: bool) -> Option<u64> {
+        let mut buffer = self.buffer.lock().unwrap();
+        if buffer.finished {
+            return None;
+        }
+        let frame = Frame {
+            id: buffer.next_id,
+            data,
+            last,
+        };
+        buffer.next_id += 1;
+        buffer.finished = last;
+        if buffer.frames.len() == self.capacity {
+            buffer.frames.pop_front();
+        }
+        buffer.frames.push_back(frame.clone());
+        // Sent under the lock so subscribers see every event exactly once
+        let _ = self.sender.send(frame.clone());
+        Some(frame.id)
+    }
+
+    /// The buffered events after `last_event_id` and a receiver for the ones that follow
+    pub fn subscribe(
+        &self,
+        last_event_id: Option<u64>,
+    ) -> Result<(Vec<Frame>, broadcast::Receiver<Frame>), ResumeError> {
+        let buffer = self.buffer.lock().unwrap();
+        let after = last_event_id.unwrap_or(0);
+        let oldest = buffer
+            .frames
+            .front()
+            .map_or(buffer.next_id, |frame| frame.id);
+        if after + 1 < oldest {
+            return Err(ResumeError::Expired);
+        }
+        let backlog = buffer
+            .frames
+            .iter()
+            .filter(|frame| frame.id > after)
+            .cloned()
+            .collect();
+        Ok((backlog, self.sender.subscribe()))
+    }
+
+    /// Number of clients currently reading the stream
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+/// The reply streams of the server, keyed by the user's session
+#[derive(Default)]
+pub struct StreamRegistry {
+    streams: Mutex<HashMap<String, Arc<ReplyStream>>>,
+}
+
+impl StreamRegistry {
+    /// Start a stream for a new reply, replacing the previous one of the session
+    pub fn start(&self, key: &str) -> Arc<ReplyStream> {
+        let stream = Arc::new(ReplyStream::new(REPLAY_BUFFER_EVENTS));
+        self.streams
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), stream.clone());
+        stream
+    }
+
+    pub fn get(&self, key: &str) -> Option<Arc<ReplyStream>> {
+        self.streams.lock().unwrap().get(key).cloned()
+    }
+
+    /// Forget a finished stream once clients had time to reconnect to it
+    pub fn expire(self: &Arc<Self>, key: String, stream: Arc<ReplyStream>) {
+        let registry = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(FINISHED_STREAM_TTL).await;
+            let mut streams = registry.streams.lock().unwrap();
+            if streams
+                .get(&key)
+                .is_some_and(|current| Arc::ptr_eq(current, &stream))
+            {
+                streams.remove(&key);
+            }
+        });
+    }
+}
+
+/// The id from the Last-Event-ID header a reconnecting EventSource sends
+pub fn last_event_id(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Tracks how long a reply has gone without a connected client
+#[derive(Default)]
+pub struct Watchers {
+    unwatched_since: Option<Instant>,
+}
+
+impl Watchers {
+    /// Whether the reply has had no client for longer than `RESUME_GRACE_PERIOD`
+    pub fn abandoned(&mut self, stream: &ReplyStream) -> bool {
+        if stream.subscribers() > 0 {
+            self.unwatched_since = None;
+            return false;
+        }
+        let since = *self.unwatched_since.get_or_insert_with(Instant::now);
+        since.elapsed() > RESUME_GRACE_PERIOD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_from_last_event() {
+        let stream = ReplyStream::new(3);
+        for i in 0..4 {
+            stream.publish(format!("event {}", i), false);
+        }
+
+        // Only the last three events are kept
+        assert_eq!(stream.subscribe(Some(0)).unwrap_err(), ResumeError::Expired);
+        let (backlog, _) = stream.subscribe(Some(2)).unwrap();
+        assert_eq!(
+            backlog.iter().map(|frame| frame.id).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+        let (backlog, _) = stream.subscribe(Some(4)).unwrap();
+        assert!(backlog.is_empty());
+    }
+
+    #[test]
+    fn test_live_events_follow_backlog() {
+        let stream = ReplyStream::new(10);
+        stream.publish("first".to_string(), false);
+        let (backlog, mut live) = stream.subscribe(None).unwrap();
+        assert_eq!(backlog.len(), 1);
+
+        assert_eq!(stream.publish("done".to_string(), true), Some(2));
+        let frame = live.try_recv().unwrap();
+        assert_eq!(frame.id, 2);
+        assert!(frame.last);
+        assert_eq!(frame.to_sse(), "id: 2\ndata: done\n\n");
+
+        assert_eq!(stream.publish("late".to_string(), false), None);
+    }
+
+    #[test]
+    fn test_last_event_id_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(last_event_id(&headers), None);
+        headers.insert("Last-Event-ID", "42".parse().unwrap());
+        assert_eq!(last_event_id(&headers), Some(42));
+    }
+}
@@ -0,0 +1,319 @@
+use anyhow::{Context, Result};
+use axum::http::{HeaderMap, StatusCode};
+use etcetera::{choose_app_strategy, AppStrategy};
+use goose::config::APP_STRATEGY;
+use goose_mcp::MEMORY_DIR_ENV;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Who a request was made by, from the token it carries in `X-Secret-Key`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum User {
+    /// Holds the server's secret key, sees the server's own sessions and manages its settings
+    Owner,
+    /// A team member from the users file, only sees the sessions, pins, approvals and tool
+    /// calls of their own sessions
+    Member(String),
+}
+
+impl User {
+    /// The state store namespace holding this user's entries of `namespace`
+    pub fn namespace(&self, namespace: &str) -> String {
+        match self {
+            User::Owner => namespace.to_string(),
+            User::Member(id) => format!("users/{}/{}", id, namespace),
+        }
+    }
+
+    /// Where this user's sessions are kept, members each get a directory under the
+    /// session directory which the owner's session list skips
+    pub fn session_dir(&self) -> Result<PathBuf> {
+        let session_dir = goose::session::ensure_session_dir()?;
+        Ok(match self {
+            User::Owner => session_dir,
+            User::Member(id) => session_dir.join("users").join(id),
+        })
+    }
+
+    /// The file of one of this user's sessions, rejecting ids that would reach outside the
+    /// user's session directory
+    pub fn session_path(&self, session_id: &str) -> Result<PathBuf, StatusCode> {
+        if session_id.is_empty() || session_id.starts_with('.') || session_id.contains(['/', '\\'])
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let dir = self
+            .session_dir()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        std::fs::create_dir_all(&dir).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        Ok(dir.join(format!("{}.jsonl", session_id)))
+    }
+}
+
+/// Where a member's own files are kept, their default working directory and their memories
+fn member_dir(id: &str) -> Result<PathBuf> {
+    Ok(choose_app_strategy(APP_STRATEGY.clone())?
+        .in_data_dir("users")
+        .join(id))
+}
+
+/// User ids become directory names, so only allow characters that are safe there
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+}
+
+/// Where team members are configured, set with `GOOSE_USERS__PATH`. Without it the server
+/// has a single user, the holder of the secret key.
+///
+/// Each member gets an agent of their own with the owner's provider and extensions, which
+/// works in the member's working directory and keeps the member's memories apart.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UserSettings {
+    /// A YAML file listing each member's id and token
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserEntry {
+    id: String,
+    token: String,
+    /// The only directory the member's sessions can work in, a directory of their own under
+    /// the server's data directory when not set
+    #[serde(default)]
+    working_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsersFile {
+    users: Vec<UserEntry>,
+}
+
+/// The members who can use the server, looked up by token
+#[derive(Debug, Default)]
+pub struct UserDirectory {
+    by_token: HashMap<String, String>,
+    working_dirs: HashMap<String, PathBuf>,
+}
+
+impl UserSettings {
+    pub fn load(&self) -> Result<UserDirectory> {
+        match &self.path {
+            Some(path) => UserDirectory::from_file(path),
+            None => Ok(UserDirectory::default()),
+        }
+    }
+}
+
+impl UserDirectory {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read users file {}", path.display()))?;
+        Self::parse(&text)
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let file: UsersFile = serde_yaml::from_str(text).context("Invalid users file")?;
+        let mut by_token = HashMap::new();
+        let mut working_dirs = HashMap::new();
+        for entry in file.users {
+            if !is_valid_id(&entry.id) {
+                anyhow::bail!(
+                    "Invalid user id '{}', use letters, digits, - and _",
+                    entry.id
+                );
+            }
+            if entry.token.is_empty() {
+                anyhow::bail!("User '{}' has an empty token", entry.id);
+            }
+            if by_token.insert(entry.token, entry.id.clone()).is_some() {
+                anyhow::bail!("User '{}' shares a token with another user", entry.id);
+            }
+            if let Some(dir) = entry.working_dir {
+                working_dirs.insert(entry.id, dir);
+            }
+        }
+        Ok(Self {
+            by_token,
+            working_dirs,
+        })
+    }
+
+    /// Every user, the owner first
+    pub fn all(&self) -> Vec<User> {
+        let mut members: Vec<String> = self.by_token.values().cloned().collect();
+        members.sort();
+        std::iter::once(User::Owner)
+            .chain(members.into_iter().map(User::Member))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_token.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_token.is_empty()
+    }
+
+    /// The user a request was made by, the owner for the secret key and a member for one of
+    /// the tokens in the users file
+    pub fn authenticate(&self, headers: &HeaderMap, secret_key: &str) -> Result<User, StatusCode> {
+        let token = headers
+            .get("X-Secret-Key")
+            .and_then(|value| value.to_str().ok())
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+        if token == secret_key {
+            return Ok(User::Owner);
+        }
+        self.by_token
+            .get(token)
+            .map(|id| User::Member(id.clone()))
+            .ok_or(StatusCode::UNAUTHORIZED)
+    }
+
+    /// The directory a member's sessions and extensions work in, created when missing. The
+    /// owner isn't confined to one.
+    pub fn working_dir(&self, user: &User) -> Result<Option<PathBuf>> {
+        let User::Member(id) = user else {
+            return Ok(None);
+        };
+        let dir = match self.working_dirs.get(id) {
+            Some(dir) => dir.clone(),
+            None => member_dir(id)?.join("workspace"),
+        };
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create working directory {}", dir.display()))?;
+        Ok(Some(dir.canonicalize()?))
+    }
+
+    /// The working directory a session asked for. A member's is resolved against their own
+    /// working directory and refused when it leads outside of it.
+    pub fn confine(&self, user: &User, requested: &str) -> Result<String, StatusCode> {
+        let Some(root) = self
+            .working_dir(user)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        else {
+            return Ok(requested.to_string());
+        };
+        let dir = root
+            .join(requested)
+            .canonicalize()
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        if !dir.starts_with(&root) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        Ok(dir.to_string_lossy().into_owned())
+    }
+
+    /// Environment for a member's extensions, pointing the memory extension at their own
+    /// memories
+    pub fn extension_env(&self, user: &User) -> Result<HashMap<String, String>> {
+        let User::Member(id) = user else {
+            return Ok(HashMap::new());
+        };
+        let memory_dir = member_dir(id)?.join("memory");
+        Ok(HashMap::from([(
+            MEMORY_DIR_ENV.to_string(),
+            memory_dir.to_string_lossy().into_owned(),
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Secret-Key", token.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let users = UserDirectory::parse(
+            "users:\n  - id: alice\n    token: alice-token\n  - id: bob\n    token: bob-token\n",
+        )
+        .unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!(
+            users.authenticate(&headers("secret"), "secret"),
+            Ok(User::Owner)
+        );
+        assert_eq!(
+            users.authenticate(&headers("bob-token"), "secret"),
+            Ok(User::Member("bob".to_string()))
+        );
+        assert_eq!(
+            users.authenticate(&headers("guess"), "secret"),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            users.authenticate(&HeaderMap::new(), "secret"),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+    }
+
+    #[test]
+    fn test_invalid_users_file() {
+        assert!(UserDirectory::parse("users:\n  - id: ../alice\n    token: t\n").is_err());
+        assert!(UserDirectory::parse(
+            "users:\n  - id: alice\n    token: t\n  - id: bob\n    token: t\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_isolation() {
+        let alice = User::Member("alice".to_string());
+        assert_eq!(alice.namespace("sessions"), "users/alice/sessions");
+        assert_eq!(User::Owner.namespace("sessions"), "sessions");
+        assert_eq!(
+            alice.session_path("../bob/20250101_120000"),
+            Err(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    #[test]
+    fn test_confine_working_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("project")).unwrap();
+        let users = UserDirectory::parse(&format!(
+            "users:\n  - id: alice\n    token: t\n    working_dir: {}\n",
+            root.path().display()
+        ))
+        .unwrap();
+        let alice = User::Member("alice".to_string());
+        let root = root.path().canonicalize().unwrap();
+
+        assert_eq!(
+            users.confine(&alice, "project"),
+            Ok(root.join("project").to_string_lossy().into_owned())
+        );
+        assert_eq!(
+            users.confine(&alice, ""),
+            Ok(root.to_string_lossy().into_owned())
+        );
+        assert_eq!(
+            users.confine(&alice, "project/../.."),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            users.confine(&alice, &outside.path().to_string_lossy()),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            users.confine(&alice, "missing"),
+            Err(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            users.confine(&User::Owner, "/anywhere"),
+            Ok("/anywhere".to_string())
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use crate::state_store::{StateStore, WEBHOOKS};
+use crate::users::User;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Header holding `sha256=<hex HMAC-SHA256 of the body>`, sent when the webhook has a secret
+pub const SIGNATURE_HEADER: &str = "X-Goose-Signature";
+/// Header holding the event, so receivers can route deliveries without parsing the body
+pub const EVENT_HEADER: &str = "X-Goose-Event";
+/// How long a receiver has to answer a delivery
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Webhooks that receive the events of every session, set with `GOOSE_WEBHOOKS__URLS` as a
+/// comma separated list. Deliveries are signed with `GOOSE_WEBHOOKS__SECRET` when it is set.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WebhookSettings {
+    pub urls: Option<String>,
+    pub secret: Option<String>,
+}
+
+impl WebhookSettings {
+    pub fn urls(&self) -> Vec<String> {
+        self.urls
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .collect()
+    }
+}
+
+/// Something that happened in a session's run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    RunStarted,
+    RunFinished,
+    RunFailed,
+    /// A tool call is waiting for the user to approve it
+    ApprovalNeeded,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::RunStarted => "run_started",
+            WebhookEvent::RunFinished => "run_finished",
+            WebhookEvent::RunFailed => "run_failed",
+            WebhookEvent::ApprovalNeeded => "approval_needed",
+        }
+    }
+}
+
+/// A webhook registered through the API, stored under `WEBHOOKS`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// Only send the events of this session, of every session when unset
+    pub session_id: Option<String>,
+    /// The events to send, all of them when empty
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    /// Key the deliveries are signed with, left out of API responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub fn new(
+        url: String,
+        session_id: Option<String>,
+        events: Vec<WebhookEvent>,
+        secret: Option<String>,
+    ) -> Self {
+        let created_at = Utc::now();
+        let digest = Sha256::digest(format!("{}{}", url, created_at.to_rfc3339()));
+        Self {
+            id: to_hex(&digest[..8]),
+            url,
+            session_id,
+            events,
+            secret,
+            created_at,
+        }
+    }
+
+    pub fn wants(&self, session_id: &str, event: WebhookEvent) -> bool {
+        self.session_id.as_deref().is_none_or(|id| id == session_id)
+            && (self.events.is_empty() || self.events.contains(&event))
+    }
+
+    /// The webhook without its secret, to return from the API
+    pub fn redacted(mut self) -> Self {
+        self.secret = None;
+        self
+    }
+}
+
+/// The body of a delivery
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WebhookPayload {
+    pub event: WebhookEvent,
+    pub session_id: String,
+    pub timestamp: DateTime<Utc>,
+    /// Details of the event, such as the error of a failed run or the tool waiting for approval
+    #[schema(value_type = Object)]
+    pub data: Value,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The signature of a delivery's body, as sent in `SIGNATURE_HEADER`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body);
+    format!("sha256={}", to_hex(&mac.finalize().into_bytes()))
+}
+
+/// Sends the lifecycle events of sessions to the configured and the registered webhooks, so
+/// other systems can react to runs without polling
+#[derive(Default)]
+pub struct WebhookDispatcher {
+    urls: Vec<String>,
+    secret: Option<String>,
+    client: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    pub fn new(settings: &WebhookSettings) -> Self {
+        Self {
+            urls: settings.urls(),
+            secret: settings.secret.clone(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Deliver `event` to every webhook that wants it. Deliveries happen in the background and
+    /// failures are only logged, a slow receiver never holds up the run.
+    pub async fn notify(
+        &self,
+        store: &Arc<dyn StateStore>,
+        user: &User,
+        session_id: &str,
+        event: WebhookEvent,
+        data: Value,
+    ) {
+        let mut targets: Vec<(String, Option<String>)> = self
+            .urls
+            .iter()
+            .map(|url| (url.clone(), self.secret.clone()))
+            .collect();
+        match store.list_as::<Webhook>(&user.namespace(WEBHOOKS)).await {
+            Ok(webhooks) => targets.extend(
+                webhooks
+                    .into_iter()
+                    .map(|(_, webhook)| webhook)
+                    .filter(|webhook| webhook.wants(session_id, event))
+                    .map(|webhook| (webhook.url, webhook.secret)),
+            ),
+            Err(e) => tracing::warn!("Failed to load webhooks: {}", e),
+        }
+        if targets.is_empty() {
+            return;
+        }
+
+        let payload = WebhookPayload {
+            event,
+            session_id: session_id.to_string(),
+            timestamp: Utc::now(),
+            data,
+        };
+        let body = match serde_json::to_vec(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::warn!("Failed to serialize webhook payload: {}", e);
+                return;
+            }
+        };
+
+        for (url, secret) in targets {
+            let client = self.client.clone();
+            let body = body.clone();
+            tokio::spawn(async move {
+                let mut request = client
+                    .post(&url)
+                    .timeout(DELIVERY_TIMEOUT)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header(EVENT_HEADER, event.as_str());
+                if let Some(secret) = &secret {
+                    request = request.header(SIGNATURE_HEADER, sign(secret, &body));
+                }
+                match request.body(body).send().await {
+                    Ok(response) if response.status().is_success() => {}
+                    Ok(response) => tracing::warn!(
+                        "Webhook {} answered {} to {}",
+                        url,
+                        response.status(),
+                        event.as_str()
+                    ),
+                    Err(e) => tracing::warn!("Failed to deliver webhook to {}: {}", url, e),
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_wants() {
+        let mut webhook = Webhook::new(
+            "https://example.com/hook".to_string(),
+            Some("s1".to_string()),
+            vec![WebhookEvent::RunFailed],
+            Some("secret".to_string()),
+        );
+        assert!(webhook.wants("s1", WebhookEvent::RunFailed));
+        assert!(!webhook.wants("s1", WebhookEvent::RunStarted));
+        assert!(!webhook.wants("s2", WebhookEvent::RunFailed));
+
+        webhook.session_id = None;
+        webhook.events.clear();
+        assert!(webhook.wants("s2", WebhookEvent::ApprovalNeeded));
+        assert_eq!(webhook.id.len(), 16);
+        assert!(webhook.redacted().secret.is_none());
+
+        let settings = WebhookSettings {
+            urls: Some("https://a.example, ,https://b.example".to_string()),
+            secret: None,
+        };
+        assert_eq!(
+            settings.urls(),
+            vec!["https://a.example", "https://b.example"]
+        );
+    }
+}
@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 use std::future::Future;
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use futures::stream::BoxStream;
@@ -10,8 +12,12 @@ use futures_util::stream;
 use futures_util::stream::StreamExt;
 use mcp_core::protocol::JsonRpcMessage;
 
-use crate::config::{Config, ExtensionConfigManager, PermissionManager};
-use crate::message::Message;
+use crate::config::{
+    Config, ExtensionConfigManager, ExtensionDisabledEvent, KeyManager, PermissionManager,
+};
+use crate::context_mgmt::collapse::DEFAULT_KEEP_RECENT;
+use crate::context_mgmt::pinned::PinnedContext;
+use crate::message::{Message, ToolRequest};
 use crate::permission::permission_judge::check_tool_permissions;
 use crate::permission::PermissionConfirmation;
 use crate::providers::base::Provider;
@@ -20,24 +26,36 @@ use crate::recipe::{Author, Recipe};
 use crate::tool_monitor::{ToolCall, ToolMonitor};
 use regex::Regex;
 use serde_json::Value;
-use tokio::sync::{mpsc, Mutex};
-use tracing::{debug, error, instrument};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tracing::{debug, error, instrument, warn};
 
+use crate::agents::citations::citations_enabled;
+use crate::agents::context_recovery::{overflow_retries, ContextRecoveryEvent, RecoveryStrategy};
+use crate::agents::dry_run::DryRunMode;
 use crate::agents::extension::{ExtensionConfig, ExtensionError, ExtensionResult, ToolInfo};
-use crate::agents::extension_manager::{get_parameter_names, ExtensionManager};
+use crate::agents::extension_manager::{get_parameter_names, normalize, ExtensionManager};
+use crate::agents::guardrails::{GuardrailEvent, Guardrails};
 use crate::agents::platform_tools::{
-    PLATFORM_LIST_RESOURCES_TOOL_NAME, PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME,
-    PLATFORM_READ_RESOURCE_TOOL_NAME, PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME,
+    PLATFORM_ASK_USER_TOOL_NAME, PLATFORM_LIST_RESOURCES_TOOL_NAME,
+    PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME, PLATFORM_READ_RESOURCE_TOOL_NAME,
+    PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME, PLATFORM_SUMMARIZE_CONVERSATION_TOOL_NAME,
 };
+use crate::agents::prefetch::prefetch_enabled;
 use crate::agents::prompt_manager::PromptManager;
 use crate::agents::router_tool_selector::{
     create_tool_selector, RouterToolSelectionStrategy, RouterToolSelector,
 };
 use crate::agents::router_tools::ROUTER_VECTOR_SEARCH_TOOL_NAME;
+use crate::agents::timeouts::{TimeoutAction, TimeoutConfig, TimeoutEvent};
+use crate::agents::tool_cache::ToolResultCache;
+use crate::agents::tool_compression::{CompressionStats, ToolCompressor};
+use crate::agents::tool_errors::ToolErrorClassifier;
 use crate::agents::tool_router_index_manager::ToolRouterIndexManager;
+use crate::agents::tool_streaming::{notification_text, EarlyCancel, PartialOutput};
 use crate::agents::tool_vectordb::generate_table_id;
 use crate::agents::types::SessionConfig;
 use crate::agents::types::{FrontendTool, ToolResultReceiver};
+use crate::agents::verification::{format_failures, run_verification_hooks, VerificationConfig};
 use mcp_core::{
     prompt::Prompt, protocol::GetPromptResult, tool::Tool, Content, ToolError, ToolResult,
 };
@@ -49,7 +67,7 @@ use super::tool_execution::{ToolCallResult, CHAT_MODE_TOOL_SKIPPED_RESPONSE, DEC
 /// The main goose Agent
 pub struct Agent {
     pub(super) provider: Mutex<Option<Arc<dyn Provider>>>,
-    pub(super) extension_manager: Mutex<ExtensionManager>,
+    pub(super) extension_manager: Arc<Mutex<ExtensionManager>>,
     pub(super) frontend_tools: Mutex<HashMap<String, FrontendTool>>,
     pub(super) frontend_instructions: Mutex<Option<String>>,
     pub(super) prompt_manager: Mutex<PromptManager>,
@@ -59,12 +77,46 @@ pub struct Agent {
     pub(super) tool_result_rx: ToolResultReceiver,
     pub(super) tool_monitor: Mutex<Option<ToolMonitor>>,
     pub(super) router_tool_selector: Mutex<Option<Arc<Box<dyn RouterToolSelector>>>>,
+    pub(super) tool_cache: Arc<Mutex<ToolResultCache>>,
+    pub(super) tool_compressor: Mutex<ToolCompressor>,
+    pub(super) tool_error_classifier: Mutex<Option<ToolErrorClassifier>>,
+    pub(super) pinned_context: Mutex<PinnedContext>,
+    /// Pinned files of each session replied to, by session file
+    pub(super) session_pins: Mutex<HashMap<PathBuf, PinnedContext>>,
+    pub(super) dry_run: Mutex<DryRunMode>,
+    pub(super) provider_retries: Mutex<u32>,
+    /// Whether the client answers ask_user calls, see `enable_ask_user`
+    pub(super) ask_user: Mutex<bool>,
+    pub(super) last_error: Mutex<Option<String>>,
+    /// Background run of the calls predicted to follow the last reply, see `prefetch`
+    pub(super) prefetch: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+/// Config key for how many times a provider call is retried after a transient failure such as
+/// a rate limit, off by default
+pub const PROVIDER_RETRIES_KEY: &str = "GOOSE_PROVIDER_RETRIES";
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Exponential backoff between provider retries, starting at 2s
+fn provider_retry_delay(attempt: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(attempt + 1)).min(MAX_RETRY_DELAY)
 }
 
 #[derive(Clone, Debug)]
 pub enum AgentEvent {
     Message(Message),
     McpNotification((String, JsonRpcMessage)),
+    /// The run stopped waiting on the user, see `TimeoutConfig`
+    Timeout(TimeoutEvent),
+    /// Older messages were collapsed into a summary, this is the whole history from now on
+    HistoryReplaced(Vec<Message>),
+    /// Likely secrets or personal data were masked in the assistant message that follows
+    Guardrail(GuardrailEvent),
+    /// An extension failed too often and was removed from the session
+    ExtensionDisabled(ExtensionDisabledEvent),
+    /// The context window overflowed and older messages made room for a retry, followed by
+    /// `HistoryReplaced` with the smaller history
+    ContextRecovered(ContextRecoveryEvent),
 }
 
 impl Agent {
@@ -75,7 +127,7 @@ impl Agent {
 
         Self {
             provider: Mutex::new(None),
-            extension_manager: Mutex::new(ExtensionManager::new()),
+            extension_manager: Arc::new(Mutex::new(ExtensionManager::new())),
             frontend_tools: Mutex::new(HashMap::new()),
             frontend_instructions: Mutex::new(None),
             prompt_manager: Mutex::new(PromptManager::new()),
@@ -85,6 +137,62 @@ impl Agent {
             tool_result_rx: Arc::new(Mutex::new(tool_rx)),
             tool_monitor: Mutex::new(None),
             router_tool_selector: Mutex::new(None),
+            tool_cache: Arc::new(Mutex::new(ToolResultCache::from_config())),
+            tool_compressor: Mutex::new(ToolCompressor::from_config()),
+            tool_error_classifier: Mutex::new(ToolErrorClassifier::from_config()),
+            pinned_context: Mutex::new(PinnedContext::default()),
+            session_pins: Mutex::new(HashMap::new()),
+            dry_run: Mutex::new(DryRunMode::from_config()),
+            provider_retries: Mutex::new(
+                Config::global()
+                    .get_param(PROVIDER_RETRIES_KEY)
+                    .unwrap_or(0),
+            ),
+            ask_user: Mutex::new(false),
+            last_error: Mutex::new(None),
+            prefetch: Mutex::new(None),
+        }
+    }
+
+    /// Intercept tool calls that aren't read-only instead of running them, see [`DryRunMode`]
+    pub async fn set_dry_run(&self, mode: DryRunMode) {
+        *self.dry_run.lock().await = mode;
+    }
+
+    /// Retry provider calls that fail with a transient error up to `retries` times
+    pub async fn set_provider_retries(&self, retries: u32) {
+        *self.provider_retries.lock().await = retries;
+    }
+
+    /// Offer the model the ask_user tool. Only clients that answer its calls, which arrive as
+    /// frontend tool requests, should turn this on, others would leave the run waiting on
+    /// questions nobody sees.
+    pub async fn enable_ask_user(&self) {
+        *self.ask_user.lock().await = true;
+    }
+
+    /// The provider error that ended the most recent reply early, None when it finished
+    pub async fn last_error(&self) -> Option<String> {
+        self.last_error.lock().await.clone()
+    }
+
+    /// Tokens saved by compressing tool descriptions in the last request, if compression is on
+    pub async fn tool_compression_stats(&self) -> Option<CompressionStats> {
+        let compressor = self.tool_compressor.lock().await;
+        compressor.is_enabled().then_some(compressor.last_stats())
+    }
+
+    /// Classify errors from an extension's tools with the rules it advertised when it was
+    /// added, or with none once it's gone. Called whenever an extension is added or removed.
+    /// Ignored when hints are turned off.
+    async fn sync_tool_error_rules(&self, extension: &str) {
+        let rules = self
+            .extension_manager
+            .lock()
+            .await
+            .tool_error_rules(extension);
+        if let Some(classifier) = self.tool_error_classifier.lock().await.as_mut() {
+            classifier.set_extension_rules(&normalize(extension.to_string()), rules);
         }
     }
 
@@ -182,12 +290,14 @@ impl Agent {
         Ok(tools)
     }
 
-    /// Dispatch a single tool call to the appropriate client
+    /// Dispatch a single tool call to the appropriate client, reusing the cached results of the
+    /// session `session_key`
     #[instrument(skip(self, tool_call, request_id), fields(input, output))]
     pub(super) async fn dispatch_tool_call(
         &self,
         tool_call: mcp_core::tool::ToolCall,
         request_id: String,
+        session_key: &str,
     ) -> (String, Result<ToolCallResult, ToolError>) {
         // Check if this tool call should be allowed based on repetition monitoring
         if let Some(monitor) = self.tool_monitor.lock().await.as_mut() {
@@ -216,6 +326,11 @@ impl Agent {
                 .and_then(|v| v.as_str())
                 .unwrap_or("")
                 .to_string();
+            // Extensions changing can change what any cached result would be
+            self.tool_cache
+                .lock()
+                .await
+                .invalidate(&tool_call.arguments);
             let (request_id, result) = self
                 .manage_extensions(action, extension_name, request_id)
                 .await;
@@ -254,13 +369,24 @@ impl Agent {
                 ))
             })
         } else {
-            // Clone the result to ensure no references to extension_manager are returned
-            let result = extension_manager
-                .dispatch_tool_call(tool_call.clone())
-                .await;
-            match result {
-                Ok(call_result) => call_result,
-                Err(e) => ToolCallResult::from(Err(ToolError::ExecutionError(e.to_string()))),
+            let mut cache = self.tool_cache.lock().await;
+            if let Some(cached) = cache.get(session_key, &tool_call.name, &tool_call.arguments) {
+                ToolCallResult::from(Ok(cached))
+            } else {
+                let cacheable = cache.is_cacheable(&tool_call.name);
+                drop(cache);
+
+                // Clone the result to ensure no references to extension_manager are returned
+                let result = extension_manager
+                    .dispatch_tool_call(tool_call.clone())
+                    .await;
+                match result {
+                    Ok(call_result) if cacheable => {
+                        self.cache_tool_result(session_key, &tool_call, call_result)
+                    }
+                    Ok(call_result) => self.invalidate_after(&tool_call, call_result),
+                    Err(e) => ToolCallResult::from(Err(ToolError::ExecutionError(e.to_string()))),
+                }
             }
         };
 
@@ -277,6 +403,53 @@ impl Agent {
         )
     }
 
+    /// Store the result of a read-only tool call once it completes
+    fn cache_tool_result(
+        &self,
+        session_key: &str,
+        tool_call: &mcp_core::tool::ToolCall,
+        call_result: ToolCallResult,
+    ) -> ToolCallResult {
+        let cache = Arc::clone(&self.tool_cache);
+        let session_key = session_key.to_string();
+        let name = tool_call.name.clone();
+        let arguments = tool_call.arguments.clone();
+        let result = call_result.result;
+        ToolCallResult {
+            notification_stream: call_result.notification_stream,
+            result: Box::new(Box::pin(async move {
+                let result = result.await;
+                if let Ok(content) = &result {
+                    cache
+                        .lock()
+                        .await
+                        .insert(&session_key, &name, &arguments, content.clone());
+                }
+                result
+            })),
+        }
+    }
+
+    /// Drop the cached results a call may have made stale once it completes, so reads made
+    /// while it was still running are not kept either
+    fn invalidate_after(
+        &self,
+        tool_call: &mcp_core::tool::ToolCall,
+        call_result: ToolCallResult,
+    ) -> ToolCallResult {
+        let cache = Arc::clone(&self.tool_cache);
+        let arguments = tool_call.arguments.clone();
+        let result = call_result.result;
+        ToolCallResult {
+            notification_stream: call_result.notification_stream,
+            result: Box::new(Box::pin(async move {
+                let result = result.await;
+                cache.lock().await.invalidate(&arguments);
+                result
+            })),
+        }
+    }
+
     pub(super) async fn manage_extensions(
         &self,
         action: String,
@@ -296,6 +469,8 @@ impl Agent {
                     ))]
                 })
                 .map_err(|e| ToolError::ExecutionError(e.to_string()));
+            drop(extension_manager);
+            self.sync_tool_error_rules(&extension_name).await;
             return (request_id, result);
         }
 
@@ -331,6 +506,8 @@ impl Agent {
                 ))]
             })
             .map_err(|e| ToolError::ExecutionError(e.to_string()));
+        drop(extension_manager);
+        self.sync_tool_error_rules(&extension_name).await;
 
         // Update vector index if operation was successful and vector routing is enabled
         if result.is_ok() {
@@ -391,8 +568,12 @@ impl Agent {
                 }
             }
             _ => {
-                let mut extension_manager = self.extension_manager.lock().await;
-                extension_manager.add_extension(extension.clone()).await?;
+                self.extension_manager
+                    .lock()
+                    .await
+                    .add_extension(extension.clone())
+                    .await?;
+                self.sync_tool_error_rules(&extension.name()).await;
             }
         };
 
@@ -432,6 +613,10 @@ impl Agent {
             // Add platform tools
             prefixed_tools.push(platform_tools::search_available_extensions_tool());
             prefixed_tools.push(platform_tools::manage_extensions_tool());
+            prefixed_tools.push(platform_tools::summarize_conversation_tool());
+            if *self.ask_user.lock().await {
+                prefixed_tools.push(platform_tools::ask_user_tool());
+            }
 
             // Add resource tools if supported
             if extension_manager.supports_resources() {
@@ -479,8 +664,12 @@ impl Agent {
     }
 
     pub async fn remove_extension(&self, name: &str) -> Result<()> {
-        let mut extension_manager = self.extension_manager.lock().await;
-        extension_manager.remove_extension(name).await?;
+        self.extension_manager
+            .lock()
+            .await
+            .remove_extension(name)
+            .await?;
+        self.sync_tool_error_rules(name).await;
 
         // If vector tool selection is enabled, remove tools from the index
         let selector = self.router_tool_selector.lock().await.clone();
@@ -500,6 +689,80 @@ impl Agent {
         Ok(())
     }
 
+    /// Remove the extensions that failed too often during the last tool calls, returning
+    /// what to tell the user about each
+    async fn take_suspended_extensions(&self) -> Vec<ExtensionDisabledEvent> {
+        let events = self.extension_manager.lock().await.take_suspended().await;
+        for event in &events {
+            self.sync_tool_error_rules(&event.extension).await;
+            self.tool_cache
+                .lock()
+                .await
+                .invalidate_extension(&normalize(event.extension.clone()));
+        }
+        events
+    }
+
+    /// Restart a running extension to pick up changes to its server or its configuration
+    /// without restarting the session. The configuration is re-read from the config file when
+    /// the extension is saved there, and the old one is restored if the new one fails to start.
+    /// Returns the tools of the restarted extension.
+    pub async fn reload_extension(&self, name: &str) -> ExtensionResult<Vec<Tool>> {
+        let current = self
+            .extension_manager
+            .lock()
+            .await
+            .get_config(name)
+            .ok_or_else(|| {
+                ExtensionError::SetupError(format!("Extension {} is not running", name))
+            })?;
+        let config = ExtensionConfigManager::get_config_by_name(&current.name())
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| current.clone());
+
+        self.remove_extension(name)
+            .await
+            .map_err(|e| ExtensionError::SetupError(e.to_string()))?;
+        if let Err(e) = self.add_extension(config.clone()).await {
+            if serde_json::to_value(&config).ok() != serde_json::to_value(&current).ok() {
+                if let Err(restore_error) = self.add_extension(current).await {
+                    warn!(
+                        "Failed to restore extension {} after a failed reload: {}",
+                        name, restore_error
+                    );
+                }
+            }
+            return Err(e);
+        }
+
+        let key = normalize(config.key());
+        self.tool_cache.lock().await.invalidate_extension(&key);
+        self.extension_manager
+            .lock()
+            .await
+            .get_prefixed_tools(Some(key))
+            .await
+    }
+
+    /// Run the processes of the extensions added from now on in `working_dir` with `envs` set,
+    /// see `ExtensionManager::set_environment`
+    pub async fn set_extension_environment(
+        &self,
+        working_dir: PathBuf,
+        envs: HashMap<String, String>,
+    ) {
+        self.extension_manager
+            .lock()
+            .await
+            .set_environment(working_dir, envs);
+    }
+
+    /// The configurations of the running extensions, to start the same ones elsewhere
+    pub async fn extension_configs(&self) -> Vec<ExtensionConfig> {
+        self.extension_manager.lock().await.configs()
+    }
+
     pub async fn list_extensions(&self) -> Vec<String> {
         let extension_manager = self.extension_manager.lock().await;
         extension_manager
@@ -527,18 +790,62 @@ impl Agent {
     ) -> anyhow::Result<BoxStream<'_, anyhow::Result<AgentEvent>>> {
         let mut messages = messages.to_vec();
         let reply_span = tracing::Span::current();
+        self.cancel_prefetch().await;
+
+        let session_file = session
+            .as_ref()
+            .map(|session_config| crate::session::get_path(session_config.id.clone()));
+        // Cached tool results are kept per session, under its file like the tiered budget
+        let session_key = session_file
+            .as_ref()
+            .map(|session_file| session_file.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.tool_cache.lock().await.begin_turn(&session_key);
+
+        // The tiered budget is kept per session, starting from what the session already spent
+        if let (Some(session_file), Ok(provider)) = (&session_file, self.provider().await) {
+            if let Some(tiered) = provider.as_tiered() {
+                let usage = crate::session::storage::read_metadata(session_file)
+                    .map(|metadata| metadata.message_usage)
+                    .unwrap_or_default();
+                tiered
+                    .start_session(&session_file.to_string_lossy(), &usage)
+                    .await;
+            }
+        }
 
         // Load settings from config
         let config = Config::global();
 
+        // Pick up dynamic extension instructions that went stale since the last reply
+        self.extension_manager
+            .lock()
+            .await
+            .refresh_instructions(&[])
+            .await;
+
         // Setup tools and prompt
         let (mut tools, mut toolshim_tools, mut system_prompt) =
             self.prepare_tools_and_prompt().await?;
 
         let goose_mode = config.get_param("GOOSE_MODE").unwrap_or("auto".to_string());
 
-        let (tools_with_readonly_annotation, tools_without_annotation) =
-            Self::categorize_tools_by_annotation(&tools);
+        let tool_annotations = Self::categorize_tools_by_annotation(&tools);
+
+        let verification = VerificationConfig::from_config();
+        let verification_dir = session.as_ref().map(|s| s.working_dir.clone());
+        let mut verification_attempts = 0;
+        let mut used_tools = false;
+        let timeouts = TimeoutConfig::from_config();
+        let guardrails = Guardrails::from_config();
+        let dry_run = *self.dry_run.lock().await;
+        let provider_retries = *self.provider_retries.lock().await;
+        *self.last_error.lock().await = None;
+        // Older messages make room instead of giving up when the context fills up, a few
+        // times per provider call
+        let overflow_strategy = RecoveryStrategy::from_config();
+        let overflow_retries = overflow_retries();
+        let mut overflow_attempts = 0;
 
         if let Some(content) = messages
             .last()
@@ -551,14 +858,62 @@ impl Agent {
         Ok(Box::pin(async_stream::try_stream! {
             let _ = reply_span.enter();
             loop {
-                match Self::generate_response_from_provider(
-                    self.provider().await?,
-                    &system_prompt,
-                    &messages,
-                    &tools,
-                    &toolshim_tools,
-                ).await {
+                let mut attempt = 0;
+                // Pinned files may have changed since the last call, by the tools among others
+                let request_prompt = self
+                    .with_pinned_context(&system_prompt, session_file.as_deref())
+                    .await;
+                let response = loop {
+                    let response = Self::generate_response_from_provider(
+                        self.provider().await?,
+                        &request_prompt,
+                        &messages,
+                        &tools,
+                        &toolshim_tools,
+                    );
+                    // Journal streamed responses so they survive a crash part way through
+                    let response = match &session_file {
+                        Some(session_file) => {
+                            crate::session::partial::with_stream_journal(
+                                session_file.clone(),
+                                response,
+                            )
+                            .await
+                        }
+                        None => response.await,
+                    };
+                    match response {
+                        Err(e) if e.is_transient() && attempt < provider_retries => {
+                            let delay = provider_retry_delay(attempt);
+                            attempt += 1;
+                            warn!(
+                                "Provider call failed ({}), retry {} of {} in {}s",
+                                e,
+                                attempt,
+                                provider_retries,
+                                delay.as_secs()
+                            );
+                            tokio::time::sleep(delay).await;
+                        }
+                        response => break response,
+                    }
+                };
+                match response {
                     Ok((response, usage)) => {
+                        overflow_attempts = 0;
+                        // Mask what shouldn't end up in the transcript before it is shown. The
+                        // history sent back to the model keeps the response as it was written.
+                        let shown = match &guardrails {
+                            Some(guardrails) => {
+                                let (shown, event) = guardrails.scan_message(response.clone());
+                                if let Some(event) = event {
+                                    warn!("{}", event.summary());
+                                    yield AgentEvent::Guardrail(event);
+                                }
+                                shown
+                            }
+                            None => response.clone(),
+                        };
                         // record usage for the session in the session file
                         if let Some(session_config) = session.clone() {
                             Self::update_session_metrics(session_config, &usage, messages.len()).await?;
@@ -568,7 +923,7 @@ impl Agent {
                         let (frontend_requests,
                             remaining_requests,
                             filtered_response) =
-                            self.categorize_tool_requests(&response).await;
+                            self.categorize_tool_requests(&shown).await;
 
                         // Record tool calls in the router selector
                         let selector = self.router_tool_selector.lock().await.clone();
@@ -590,30 +945,168 @@ impl Agent {
                                 }
                             }
                         }
-                        // Yield the assistant's response with frontend tool requests filtered out
-                        yield AgentEvent::Message(filtered_response.clone());
-
-                        tokio::task::yield_now().await;
-
                         let num_tool_requests = frontend_requests.len() + remaining_requests.len();
-                        if num_tool_requests == 0 {
+                        if num_tool_requests > 0 {
+                            // Yield the assistant's response with frontend tool requests filtered out
+                            yield AgentEvent::Message(filtered_response.clone());
+                            tokio::task::yield_now().await;
+                        } else {
+                            // The final answer is held back until it is checked, so notes about it
+                            // join the same message rather than following as another assistant turn
+                            let mut final_response = filtered_response.clone();
+                            // The agent considers the work done, check it before handing back
+                            if used_tools && verification.is_enabled() {
+                                let failures = run_verification_hooks(
+                                    &verification.hooks,
+                                    verification_dir.as_deref(),
+                                ).await;
+                                if !failures.is_empty() {
+                                    if verification_attempts < verification.max_retries {
+                                        verification_attempts += 1;
+                                        let feedback = Message::user().with_text(format_failures(
+                                            &failures,
+                                            verification_attempts,
+                                            verification.max_retries,
+                                        ));
+                                        yield AgentEvent::Message(filtered_response.clone());
+                                        yield AgentEvent::Message(feedback.clone());
+                                        messages.push(response);
+                                        messages.push(feedback);
+                                        continue;
+                                    }
+                                    let names: Vec<&str> = failures.iter().map(|f| f.hook.name.as_str()).collect();
+                                    final_response = final_response.with_text(format!(
+                                        "Verification is still failing after {} attempts: {}",
+                                        verification.max_retries,
+                                        names.join(", ")
+                                    ));
+                                }
+                            }
+                            // Back the answer with the tool results it rests on
+                            if used_tools && citations_enabled() {
+                                if let Some(cited) = self.cite_evidence(&messages, &shown).await {
+                                    final_response = final_response.with_text(cited.render());
+                                }
+                            }
+                            yield AgentEvent::Message(final_response);
+                            // Tools can't run unattended in approve or chat mode
+                            if prefetch_enabled() && matches!(goose_mode.as_str(), "auto" | "smart_approve") {
+                                let mut history = messages.clone();
+                                history.push(response.clone());
+                                self.start_prefetch(&request_prompt, &history, &tools, &session_key).await;
+                            }
                             break;
                         }
+                        used_tools = true;
 
                         // Process tool requests depending on frontend tools and then goose_mode
                         let message_tool_response = Arc::new(Mutex::new(Message::user()));
+                        let mut timed_out = false;
+                        let mut parked = false;
+
+                        // Summarizing rewrites the history itself, so it is handled here rather
+                        // than dispatched like other tools
+                        let (summarize_requests, remaining_requests): (Vec<ToolRequest>, Vec<ToolRequest>) =
+                            remaining_requests.into_iter().partition(|request| {
+                                request.tool_call.as_ref().is_ok_and(|call| {
+                                    call.name == PLATFORM_SUMMARIZE_CONVERSATION_TOOL_NAME
+                                })
+                            });
+                        for request in &summarize_requests {
+                            let arguments = request
+                                .tool_call
+                                .as_ref()
+                                .map(|call| call.arguments.clone())
+                                .unwrap_or_default();
+                            let keep_recent = arguments
+                                .get("keep_recent")
+                                .and_then(Value::as_u64)
+                                .map_or(DEFAULT_KEEP_RECENT, |n| n as usize);
+                            let focus = arguments.get("focus").and_then(Value::as_str);
+                            let result = match self
+                                .collapse_history(&messages, keep_recent, focus, session.as_ref())
+                                .await
+                            {
+                                Ok(Some((collapsed, count))) => {
+                                    messages = collapsed;
+                                    let mut history = messages.clone();
+                                    history.push(filtered_response.clone());
+                                    yield AgentEvent::HistoryReplaced(history);
+                                    Ok(vec![Content::text(format!(
+                                        "Replaced {} earlier messages with a summary.",
+                                        count
+                                    ))])
+                                }
+                                Ok(None) => Ok(vec![Content::text(
+                                    "There are not enough earlier messages to summarize.",
+                                )]),
+                                Err(e) => Err(ToolError::ExecutionError(format!(
+                                    "Failed to summarize the conversation: {}",
+                                    e
+                                ))),
+                            };
+                            let mut response = message_tool_response.lock().await;
+                            *response = response.clone().with_tool_response(request.id.clone(), result);
+                        }
+
+                        // Questions for the user are answered by the client, like frontend tools
+                        let (ask_user_requests, remaining_requests): (Vec<ToolRequest>, Vec<ToolRequest>) =
+                            remaining_requests.into_iter().partition(|request| {
+                                request.tool_call.as_ref().is_ok_and(|call| {
+                                    call.name == PLATFORM_ASK_USER_TOOL_NAME
+                                })
+                            });
+                        let mut ask_user_stream = self.handle_ask_user_requests(
+                            &ask_user_requests,
+                            message_tool_response.clone(),
+                            &timeouts,
+                        );
+                        while let Some(event) = ask_user_stream.try_next().await? {
+                            if let AgentEvent::Timeout(timeout) = &event {
+                                timed_out = true;
+                                parked |= timeout.action == TimeoutAction::Park;
+                            }
+                            yield event;
+                        }
+
+                        // In a dry run anything that could change something gets a stand-in
+                        // result, read-only tools still run below
+                        let (dry_run_requests, remaining_requests): (Vec<ToolRequest>, Vec<ToolRequest>) =
+                            remaining_requests.into_iter().partition(|request| {
+                                request.tool_call.as_ref().is_ok_and(|call| {
+                                    dry_run.intercepts(&call.name, &tool_annotations)
+                                })
+                            });
+                        let mut dry_run_stream = self.handle_dry_run_requests(
+                            dry_run,
+                            &dry_run_requests,
+                            message_tool_response.clone(),
+                            &timeouts,
+                        );
+                        while let Some(event) = dry_run_stream.try_next().await? {
+                            if let AgentEvent::Timeout(timeout) = &event {
+                                timed_out = true;
+                                parked |= timeout.action == TimeoutAction::Park;
+                            }
+                            yield event;
+                        }
 
                         // First handle any frontend tool requests
                         let mut frontend_tool_stream = self.handle_frontend_tool_requests(
                             &frontend_requests,
-                            message_tool_response.clone()
+                            message_tool_response.clone(),
+                            &timeouts,
                         );
 
                         // we have a stream of frontend tools to handle, inside the stream
-                        // execution is yeield back to this reply loop, and is of the same Message
+                        // execution is yeield back to this reply loop, and is of the same AgentEvent
                         // type, so we can yield that back up to be handled
-                        while let Some(msg) = frontend_tool_stream.try_next().await? {
-                            yield AgentEvent::Message(msg);
+                        while let Some(event) = frontend_tool_stream.try_next().await? {
+                            if let AgentEvent::Timeout(timeout) = &event {
+                                timed_out = true;
+                                parked |= timeout.action == TimeoutAction::Park;
+                            }
+                            yield event;
                         }
 
                         // Clone goose_mode once before the match to avoid move issues
@@ -635,8 +1128,7 @@ impl Agent {
                             let (permission_check_result, enable_extension_request_ids) = check_tool_permissions(
                                 &remaining_requests,
                                 &mode,
-                                tools_with_readonly_annotation.clone(),
-                                tools_without_annotation.clone(),
+                                &tool_annotations,
                                 &mut permission_manager,
                                 self.provider().await?).await;
 
@@ -646,7 +1138,7 @@ impl Agent {
                             // Skip the confirmation for approved tools
                             for request in &permission_check_result.approved {
                                 if let Ok(tool_call) = request.tool_call.clone() {
-                                    let (req_id, tool_result) = self.dispatch_tool_call(tool_call, request.id.clone()).await;
+                                    let (req_id, tool_result) = self.dispatch_tool_call(tool_call, request.id.clone(), &session_key).await;
 
                                     tool_futures.push((req_id, match tool_result {
                                         Ok(result) => tool_stream(
@@ -675,17 +1167,24 @@ impl Agent {
                             // Process tools requiring approval (enable extension, regular tool calls)
                             let mut tool_approval_stream = self.handle_approval_tool_requests(
                                 &permission_check_result.needs_approval,
+                                &tool_annotations.destructive,
                                 tool_futures_arc.clone(),
                                 &mut permission_manager,
-                                message_tool_response.clone()
+                                message_tool_response.clone(),
+                                &timeouts,
+                                &session_key,
                             );
 
                             // We have a stream of tool_approval_requests to handle
-                            // Execution is yielded back to this reply loop, and is of the same Message
-                            // type, so we can yield the Message back up to be handled and grab any
+                            // Execution is yielded back to this reply loop, and is of the same AgentEvent
+                            // type, so we can yield it back up to be handled and grab any
                             // confirmations or denials
-                            while let Some(msg) = tool_approval_stream.try_next().await? {
-                                yield AgentEvent::Message(msg);
+                            while let Some(event) = tool_approval_stream.try_next().await? {
+                                if let AgentEvent::Timeout(timeout) = &event {
+                                    timed_out = true;
+                                    parked |= timeout.action == TimeoutAction::Park;
+                                }
+                                yield event;
                             }
 
                             tool_futures = {
@@ -695,16 +1194,34 @@ impl Agent {
                                 futures_lock.drain(..).collect::<Vec<_>>()
                             };
 
+                            // Each stream can be stopped on its own. Dropping the tool call has the client send the
+                            // server notifications/cancelled for it.
+                            let early_cancel = EarlyCancel::from_config();
+                            let mut cancel_senders = HashMap::new();
+                            let mut partial_outputs: HashMap<String, PartialOutput> = HashMap::new();
                             let with_id = tool_futures
                                 .into_iter()
                                 .map(|(request_id, stream)| {
-                                    stream.map(move |item| (request_id.clone(), item))
+                                    let (cancel_tx, cancel_rx) = oneshot::channel::<()>();
+                                    cancel_senders.insert(request_id.clone(), cancel_tx);
+                                    stream
+                                        .take_until(cancel_rx)
+                                        .map(move |item| (request_id.clone(), item))
                                 })
                                 .collect::<Vec<_>>();
 
                             let mut combined = stream::select_all(with_id);
 
                             let mut all_install_successful = true;
+                            let tool_names: HashMap<String, String> = remaining_requests
+                                .iter()
+                                .filter_map(|request| {
+                                    let tool_call = request.tool_call.as_ref().ok()?;
+                                    Some((request.id.clone(), tool_call.name.clone()))
+                                })
+                                .collect();
+                            // A copy, so adding or removing extensions isn't blocked while the tools run
+                            let tool_error_classifier = self.tool_error_classifier.lock().await.clone();
 
                             while let Some((request_id, item)) = combined.next().await {
                                 match item {
@@ -712,17 +1229,61 @@ impl Agent {
                                         if enable_extension_request_ids.contains(&request_id) && output.is_err(){
                                             all_install_successful = false;
                                         }
+                                        let output = match (tool_error_classifier.as_ref(), tool_names.get(&request_id)) {
+                                            (Some(classifier), Some(tool_name)) => classifier.annotate(tool_name, output),
+                                            _ => output,
+                                        };
                                         let mut response = message_tool_response.lock().await;
                                         *response = response.clone().with_tool_response(request_id, output);
                                     },
                                     ToolStreamItem::Message(msg) => {
-                                        yield AgentEvent::McpNotification((request_id, msg))
+                                        let failure = match notification_text(&msg) {
+                                            Some(chunk) if early_cancel.is_enabled() => {
+                                                let partial = partial_outputs.entry(request_id.clone()).or_default();
+                                                partial
+                                                    .push(&chunk)
+                                                    .into_iter()
+                                                    .find(|line| early_cancel.is_failure(line))
+                                                    .map(|line| partial.cancelled_result(&line))
+                                            }
+                                            _ => None,
+                                        };
+                                        yield AgentEvent::McpNotification((request_id.clone(), msg));
+
+                                        if let Some(output) = failure {
+                                            if let Some(cancel_tx) = cancel_senders.remove(&request_id) {
+                                                debug!("stopping tool call {} early after a failure in its output", request_id);
+                                                let _ = cancel_tx.send(());
+                                                let mut response = message_tool_response.lock().await;
+                                                *response = response.clone().with_tool_response(request_id, output);
+                                            }
+                                        }
                                     }
                                 }
                             }
 
-                            // Update system prompt and tools if installations were successful
-                            if all_install_successful {
+                            // Refresh dynamic extension instructions affected by this round of tool calls
+                            let called_tools: Vec<String> = remaining_requests
+                                .iter()
+                                .filter_map(|request| request.tool_call.as_ref().ok())
+                                .map(|tool_call| tool_call.name.clone())
+                                .collect();
+                            let instructions_changed = self
+                                .extension_manager
+                                .lock()
+                                .await
+                                .refresh_instructions(&called_tools)
+                                .await;
+
+                            let suspended = self.take_suspended_extensions().await;
+                            let extensions_suspended = !suspended.is_empty();
+                            for event in suspended {
+                                yield AgentEvent::ExtensionDisabled(event);
+                            }
+
+                            // Update system prompt and tools if installations were successful,
+                            // extension instructions changed or a failing extension was removed
+                            if all_install_successful || instructions_changed || extensions_suspended {
                                 (tools, toolshim_tools, system_prompt) = self.prepare_tools_and_prompt().await?;
                             }
                         }
@@ -732,19 +1293,72 @@ impl Agent {
 
                         messages.push(response);
                         messages.push(final_message_tool_resp);
+
+                        if timed_out {
+                            if let Some(session_config) = &session {
+                                if let Err(e) = Self::checkpoint_session(session_config, &messages).await {
+                                    warn!("Failed to checkpoint session after a timeout: {}", e);
+                                }
+                            }
+                            // A parked run ends here, a new message from the user picks it up again
+                            if parked {
+                                break;
+                            }
+                        }
                     },
-                    Err(ProviderError::ContextLengthExceeded(_)) => {
-                        // At this point, the last message should be a user message
-                        // because call to provider led to context length exceeded error
-                        // Immediately yield a special message and break
-                        yield AgentEvent::Message(Message::assistant().with_context_length_exceeded(
-                            "The context length of the model has been exceeded. Please start a new session and try again.",
-                        ));
+                    Err(ProviderError::ContextLengthExceeded(e)) => {
+                        let recovered = match overflow_strategy {
+                            Some(strategy) if overflow_attempts < overflow_retries => {
+                                overflow_attempts += 1;
+                                self.recover_from_overflow(
+                                    &messages,
+                                    strategy,
+                                    overflow_attempts,
+                                    overflow_retries,
+                                    session.as_ref(),
+                                )
+                                .await
+                            }
+                            _ => None,
+                        };
+                        match recovered {
+                            Some((history, event)) => {
+                                warn!("{}", event.summary());
+                                messages = history;
+                                yield AgentEvent::ContextRecovered(event);
+                                yield AgentEvent::HistoryReplaced(messages.clone());
+                            }
+                            None => {
+                                *self.last_error.lock().await = Some(format!("Context length exceeded: {}", e));
+                                // At this point, the last message should be a user message
+                                // because call to provider led to context length exceeded error
+                                // Immediately yield a special message and break
+                                yield AgentEvent::Message(Message::assistant().with_context_length_exceeded(
+                                    "The context length of the model has been exceeded. Please start a new session and try again.",
+                                ));
+                                break;
+                            }
+                        }
+                    },
+                    Err(ProviderError::Authentication(e)) => {
+                        *self.last_error.lock().await = Some(format!("Authentication error: {}", e));
+                        // Remember the rejected keys so the next session can warn about them
+                        if let Ok(provider_name) = Config::global().get_param::<String>("GOOSE_PROVIDER") {
+                            let keys = KeyManager::provider_keys(&provider_name);
+                            if let Err(e) = KeyManager::mark_auth_failed(&keys) {
+                                warn!("Failed to record rejected provider keys: {}", e);
+                            }
+                        }
+                        error!("Authentication error: {}", e);
+                        yield AgentEvent::Message(Message::assistant().with_text(format!(
+                            "The provider rejected the configured credentials: {e}\n\nUpdate them with `goose keys rotate <KEY>` or `goose configure`, then retry."
+                        )));
                         break;
                     },
                     Err(e) => {
                         // Create an error message & terminate the stream
                         error!("Error: {}", e);
+                        *self.last_error.lock().await = Some(e.to_string());
                         yield AgentEvent::Message(Message::assistant().with_text(format!("Ran into this error: {e}.\n\nPlease retry if you think this is a transient or recoverable error.")));
                         break;
                     }
@@ -869,13 +1483,15 @@ impl Agent {
         let model_name = &model_config.model_name;
 
         let prompt_manager = self.prompt_manager.lock().await;
-        let system_prompt = prompt_manager.build_system_prompt(
-            extensions_info,
-            self.frontend_instructions.lock().await.clone(),
-            extension_manager.suggest_disable_extensions_prompt().await,
-            Some(model_name),
-            None,
-        );
+        let system_prompt = prompt_manager
+            .build_system_prompt(
+                extensions_info,
+                self.frontend_instructions.lock().await.clone(),
+                extension_manager.suggest_disable_extensions_prompt().await,
+                Some(model_name),
+                None,
+            )
+            .render();
 
         let recipe_prompt = prompt_manager.get_recipe_prompt().await;
         let tools = extension_manager.get_prefixed_tools(None).await?;
@@ -0,0 +1,191 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_stream::try_stream;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use mcp_core::{Content, ToolCall, ToolError};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::message::{Message, ToolRequest};
+
+use super::platform_tools::PLATFORM_ASK_USER_TOOL_NAME;
+use super::timeouts::{wait_for, TimeoutConfig, TimeoutEvent, TimeoutKind, WaitError};
+use crate::agents::{Agent, AgentEvent};
+
+/// Config key holding how questions are answered when nobody can answer them, `pause` or
+/// `default`
+pub const ASK_USER_POLICY_KEY: &str = "GOOSE_ASK_USER_POLICY";
+
+/// How long to wait for an answer when no inactivity timeout is set, after which the agent
+/// goes on as if nobody was there to answer
+pub const DEFAULT_QUESTION_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+pub const NO_ANSWER_RESPONSE: &str = "Nobody is available to answer right now. Go with the most \
+    reasonable interpretation and say which assumption you made in your final answer.";
+
+/// How a headless client answers the agent's questions, interactive clients ask the user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AskUserPolicy {
+    /// Stop the run, so it can be resumed once the question is answered
+    #[default]
+    Pause,
+    /// Answer with the default the agent suggested, or have it pick the most reasonable option
+    Default,
+}
+
+impl AskUserPolicy {
+    pub fn from_config() -> Self {
+        Config::global()
+            .get_param::<String>(ASK_USER_POLICY_KEY)
+            .ok()
+            .and_then(|policy| policy.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl std::str::FromStr for AskUserPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "pause" | "" => Ok(AskUserPolicy::Pause),
+            "default" => Ok(AskUserPolicy::Default),
+            other => Err(format!(
+                "Unknown ask user policy '{}', expected pause or default",
+                other
+            )),
+        }
+    }
+}
+
+/// A clarification question from the agent, the arguments of an ask_user call
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct UserQuestion {
+    pub question: String,
+    /// Answers to pick from, the user may still answer freely when there are none
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// The answer the agent would go with if nobody answers
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+impl UserQuestion {
+    /// The question asked by `call`, None for calls of other tools or malformed arguments
+    pub fn from_call(call: &ToolCall) -> Option<Self> {
+        if call.name != PLATFORM_ASK_USER_TOOL_NAME {
+            return None;
+        }
+        serde_json::from_value(call.arguments.clone()).ok()
+    }
+
+    /// What a headless client answers under the `Default` policy
+    pub fn default_answer(&self) -> String {
+        match &self.default {
+            Some(default) => format!(
+                "Nobody is available to answer right now, go with your default: {}",
+                default
+            ),
+            None => NO_ANSWER_RESPONSE.to_string(),
+        }
+    }
+}
+
+/// The answer recorded for a question, `text` as typed by the user
+pub fn user_answer(text: &str) -> Vec<Content> {
+    vec![Content::text(format!("The user answered: {}", text.trim()))]
+}
+
+impl Agent {
+    /// Hand clarification questions to the client and wait for the answers, the same way as
+    /// frontend tools. Unanswered questions get the default answer once the inactivity
+    /// timeout, or `DEFAULT_QUESTION_TIMEOUT` when it is unset, has passed.
+    pub(crate) fn handle_ask_user_requests<'a>(
+        &'a self,
+        tool_requests: &'a [ToolRequest],
+        message_tool_response: Arc<Mutex<Message>>,
+        timeouts: &'a TimeoutConfig,
+    ) -> BoxStream<'a, anyhow::Result<AgentEvent>> {
+        try_stream! {
+            let enabled = *self.ask_user.lock().await;
+            let limit = timeouts.inactivity.unwrap_or(DEFAULT_QUESTION_TIMEOUT);
+            for request in tool_requests {
+                let Ok(tool_call) = request.tool_call.clone() else {
+                    continue;
+                };
+                let result = match UserQuestion::from_call(&tool_call) {
+                    _ if !enabled => Err(ToolError::NotFound(
+                        "This client can't ask the user questions, go with the most reasonable interpretation instead".to_string(),
+                    )),
+                    None => Err(ToolError::InvalidParameters(
+                        "ask_user needs a question".to_string(),
+                    )),
+                    Some(question) => {
+                        yield AgentEvent::Message(Message::assistant().with_frontend_tool_request(
+                            request.id.clone(),
+                            Ok(tool_call.clone()),
+                        ));
+                        let mut rx = self.tool_result_rx.lock().await;
+                        match wait_for(&mut rx, &request.id, Some(limit)).await {
+                            Ok(result) => result,
+                            Err(WaitError::TimedOut) => {
+                                yield AgentEvent::Timeout(TimeoutEvent {
+                                    kind: TimeoutKind::FrontendTool,
+                                    request_id: request.id.clone(),
+                                    tool_name: tool_call.name.clone(),
+                                    waited_secs: limit.as_secs(),
+                                    action: timeouts.action,
+                                });
+                                Ok(vec![Content::text(question.default_answer())])
+                            }
+                            Err(WaitError::Closed) => continue,
+                        }
+                    }
+                };
+                let mut response = message_tool_response.lock().await;
+                *response = response.clone().with_tool_response(request.id.clone(), result);
+            }
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_user_question() {
+        let call = ToolCall::new(
+            PLATFORM_ASK_USER_TOOL_NAME,
+            json!({"question": "Which database?", "options": ["postgres", "sqlite"], "default": "sqlite"}),
+        );
+        let question = UserQuestion::from_call(&call).unwrap();
+        assert_eq!(question.options, vec!["postgres", "sqlite"]);
+        assert!(question.default_answer().ends_with("sqlite"));
+
+        let no_default = ToolCall::new(PLATFORM_ASK_USER_TOOL_NAME, json!({"question": "Why?"}));
+        assert_eq!(
+            UserQuestion::from_call(&no_default)
+                .unwrap()
+                .default_answer(),
+            NO_ANSWER_RESPONSE
+        );
+        assert!(UserQuestion::from_call(&ToolCall::new("developer__shell", json!({}))).is_none());
+        assert!(
+            UserQuestion::from_call(&ToolCall::new(PLATFORM_ASK_USER_TOOL_NAME, json!({})))
+                .is_none()
+        );
+
+        assert_eq!(
+            "Default".parse::<AskUserPolicy>(),
+            Ok(AskUserPolicy::Default)
+        );
+        assert!("guess".parse::<AskUserPolicy>().is_err());
+    }
+}
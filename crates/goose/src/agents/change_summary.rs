@@ -0,0 +1,403 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::Config;
+use crate::message::Message;
+
+use super::edit_preview::unified_diff;
+use crate::agents::Agent;
+
+/// Config key turning off the change summary appended to the final reply of a run that
+/// edited files in a git repository, on by default
+pub const CHANGE_SUMMARY_KEY: &str = "GOOSE_CHANGE_SUMMARY";
+
+/// Cap on the diff sent when asking for a commit message, the stat covers the rest
+const MAX_PROMPT_DIFF_CHARS: usize = 12_000;
+
+/// Widest the +/- bar of a stat line gets, as with git
+const MAX_STAT_BAR: usize = 40;
+
+const COMMIT_MESSAGE_PROMPT: &str = "Write a git commit message for the diff below. Use a \
+    subject line of at most 72 characters in the imperative mood, then, only if the change \
+    needs explaining, a blank line and a short body. Reply with the commit message only, \
+    without quotes or code fences.";
+
+pub fn change_summary_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>(CHANGE_SUMMARY_KEY)
+        .unwrap_or(true)
+}
+
+/// The root of the git repository holding `dir`, None outside of one
+pub fn git_root(dir: &Path) -> Option<PathBuf> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+/// A file a run changed, as it was before the run and as it is now. Either side is `None`
+/// when the file didn't exist.
+#[derive(Debug, Clone)]
+pub struct ChangedFile {
+    pub path: PathBuf,
+    pub before: Option<Vec<u8>>,
+    pub after: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl FileStatus {
+    fn label(self) -> &'static str {
+        match self {
+            FileStatus::Added => "added",
+            FileStatus::Modified => "modified",
+            FileStatus::Deleted => "deleted",
+        }
+    }
+}
+
+/// The size of the change to one file, counted in lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStat {
+    /// Relative to the repository root
+    pub path: String,
+    pub status: FileStatus,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub binary: bool,
+}
+
+/// What a run changed in a repository, appended to its final reply
+#[derive(Debug, Clone)]
+pub struct ChangeSummary {
+    pub files: Vec<FileStat>,
+    /// The unified diff of every text file, in the order of `files`
+    pub diff: String,
+    pub commit_message: String,
+}
+
+impl ChangeSummary {
+    /// Diff the changed files that are inside `root`, None when none of them are. The commit
+    /// message is left empty.
+    pub fn from_changes(root: &Path, changes: &[ChangedFile]) -> Option<Self> {
+        let mut files = Vec::new();
+        let mut diff = String::new();
+        for change in changes {
+            let Ok(relative) = change.path.strip_prefix(root) else {
+                continue;
+            };
+            let name = relative.to_string_lossy().replace('\\', "/");
+            let status = match (&change.before, &change.after) {
+                (None, None) => continue,
+                (None, Some(_)) => FileStatus::Added,
+                (Some(_), None) => FileStatus::Deleted,
+                (Some(before), Some(after)) if before == after => continue,
+                (Some(_), Some(_)) => FileStatus::Modified,
+            };
+
+            let before = change.before.as_deref().unwrap_or_default();
+            let after = change.after.as_deref().unwrap_or_default();
+            let mut stat = FileStat {
+                path: name.clone(),
+                status,
+                insertions: 0,
+                deletions: 0,
+                binary: false,
+            };
+            match (std::str::from_utf8(before), std::str::from_utf8(after)) {
+                (Ok(before), Ok(after)) => {
+                    let old_name = match status {
+                        FileStatus::Added => "/dev/null".to_string(),
+                        _ => format!("a/{}", name),
+                    };
+                    let new_name = match status {
+                        FileStatus::Deleted => "/dev/null".to_string(),
+                        _ => format!("b/{}", name),
+                    };
+                    let file_diff = unified_diff(before, after, &old_name, &new_name);
+                    // The first two lines are the file headers, which a removed line starting
+                    // with `--` would otherwise be mistaken for
+                    for line in file_diff.lines().skip(2) {
+                        if line.starts_with('+') {
+                            stat.insertions += 1;
+                        } else if line.starts_with('-') {
+                            stat.deletions += 1;
+                        }
+                    }
+                    diff.push_str(&file_diff);
+                }
+                _ => stat.binary = true,
+            }
+            files.push(stat);
+        }
+
+        (!files.is_empty()).then(|| ChangeSummary {
+            files,
+            diff,
+            commit_message: String::new(),
+        })
+    }
+
+    /// The lines of `git diff --stat`, closed by the totals line
+    pub fn diff_stat(&self) -> String {
+        let width = self
+            .files
+            .iter()
+            .map(|file| file.path.chars().count())
+            .max()
+            .unwrap_or(0);
+        let most = self
+            .files
+            .iter()
+            .map(|file| file.insertions + file.deletions)
+            .max()
+            .unwrap_or(0);
+
+        let mut out = String::new();
+        for file in &self.files {
+            let changes = file.insertions + file.deletions;
+            let detail = if file.binary {
+                "Bin".to_string()
+            } else {
+                // Scale the bar down to fit, keeping at least one mark for each kind of change
+                let (plus, minus) = if most > MAX_STAT_BAR {
+                    let scale = |n: usize| match n {
+                        0 => 0,
+                        n => (n * MAX_STAT_BAR / most).max(1),
+                    };
+                    (scale(file.insertions), scale(file.deletions))
+                } else {
+                    (file.insertions, file.deletions)
+                };
+                format!("{} {}{}", changes, "+".repeat(plus), "-".repeat(minus))
+            };
+            out.push_str(&format!(" {:<width$} | {}\n", file.path, detail.trim_end()));
+        }
+
+        let insertions: usize = self.files.iter().map(|file| file.insertions).sum();
+        let deletions: usize = self.files.iter().map(|file| file.deletions).sum();
+        let plural =
+            |n: usize, one: &str, many: &str| format!("{} {}", n, if n == 1 { one } else { many });
+        out.push_str(&format!(
+            " {}, {}, {}",
+            plural(self.files.len(), "file changed", "files changed"),
+            plural(insertions, "insertion(+)", "insertions(+)"),
+            plural(deletions, "deletion(-)", "deletions(-)")
+        ));
+        out
+    }
+
+    /// A commit message to use when the provider can't write one
+    pub fn fallback_commit_message(&self) -> String {
+        let names: Vec<&str> = self
+            .files
+            .iter()
+            .map(|file| file.path.rsplit('/').next().unwrap_or(&file.path))
+            .collect();
+        let verb = if self
+            .files
+            .iter()
+            .all(|file| file.status == FileStatus::Added)
+        {
+            "Add"
+        } else if self
+            .files
+            .iter()
+            .all(|file| file.status == FileStatus::Deleted)
+        {
+            "Remove"
+        } else {
+            "Update"
+        };
+        match names.as_slice() {
+            [one] => format!("{} {}", verb, one),
+            [first, second] => format!("{} {} and {}", verb, first, second),
+            [first, rest @ ..] => format!("{} {} and {} other files", verb, first, rest.len()),
+            [] => String::new(),
+        }
+    }
+
+    /// The summary as markdown, to be appended to a reply
+    pub fn render(&self) -> String {
+        let mut out = String::from("---\n**Changes made in this run**\n\n");
+        for file in &self.files {
+            out.push_str(&format!("- `{}` ({})\n", file.path, file.status.label()));
+        }
+        out.push_str(&format!("\n```\n{}\n```\n", self.diff_stat()));
+        if !self.commit_message.is_empty() {
+            out.push_str(&format!(
+                "\nSuggested commit message:\n\n```\n{}\n```\n",
+                self.commit_message
+            ));
+        }
+        out
+    }
+}
+
+/// Cut `text` to at most `max_chars`, on a line boundary where there is one
+fn truncate_diff(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = match cut.rfind('\n') {
+        Some(end) => &cut[..end],
+        None => cut.as_str(),
+    };
+    format!("{}\n[diff truncated]", cut)
+}
+
+impl Agent {
+    /// Summarize what a run changed inside the repository at `root`, with a commit message
+    /// written by the provider from the diff. None when no change falls inside the repository.
+    pub async fn summarize_changes(
+        &self,
+        root: &Path,
+        changes: &[ChangedFile],
+    ) -> Option<ChangeSummary> {
+        let mut summary = ChangeSummary::from_changes(root, changes)?;
+        summary.commit_message = self
+            .suggest_commit_message(&summary)
+            .await
+            .unwrap_or_else(|| summary.fallback_commit_message());
+        Some(summary)
+    }
+
+    async fn suggest_commit_message(&self, summary: &ChangeSummary) -> Option<String> {
+        let provider = self.provider().await.ok()?;
+        let request = format!(
+            "{}\n\n{}",
+            summary.diff_stat(),
+            truncate_diff(&summary.diff, MAX_PROMPT_DIFF_CHARS)
+        );
+        match provider
+            .complete(
+                COMMIT_MESSAGE_PROMPT,
+                &[Message::user().with_text(request)],
+                &[],
+            )
+            .await
+        {
+            Ok((response, _)) => {
+                // Models tend to fence the message despite being asked not to
+                let text = response.as_concat_text();
+                let message = text
+                    .lines()
+                    .filter(|line| !line.trim_start().starts_with("```"))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let message = message.trim();
+                (!message.is_empty()).then(|| message.to_string())
+            }
+            Err(e) => {
+                tracing::warn!("Failed to suggest a commit message: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(path: &str, before: Option<&str>, after: Option<&str>) -> ChangedFile {
+        ChangedFile {
+            path: PathBuf::from(path),
+            before: before.map(|text| text.as_bytes().to_vec()),
+            after: after.map(|text| text.as_bytes().to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_from_changes() {
+        let changes = vec![
+            change("/repo/src/lib.rs", Some("a\nb\nc\n"), Some("a\nB\nc\nd\n")),
+            change("/repo/notes.md", None, Some("-- dashes\n")),
+            change("/repo/old.sql", Some("-- dropped\n"), None),
+            change("/repo/same.txt", Some("x\n"), Some("x\n")),
+            change("/elsewhere/tmp.txt", None, Some("y\n")),
+            ChangedFile {
+                path: PathBuf::from("/repo/logo.png"),
+                before: None,
+                after: Some(vec![0xff, 0xfe, 0x00]),
+            },
+        ];
+        let summary = ChangeSummary::from_changes(Path::new("/repo"), &changes).unwrap();
+
+        let stats: Vec<(&str, FileStatus, usize, usize, bool)> = summary
+            .files
+            .iter()
+            .map(|f| {
+                (
+                    f.path.as_str(),
+                    f.status,
+                    f.insertions,
+                    f.deletions,
+                    f.binary,
+                )
+            })
+            .collect();
+        assert_eq!(
+            stats,
+            vec![
+                ("src/lib.rs", FileStatus::Modified, 2, 1, false),
+                ("notes.md", FileStatus::Added, 1, 0, false),
+                ("old.sql", FileStatus::Deleted, 0, 1, false),
+                ("logo.png", FileStatus::Added, 0, 0, true),
+            ]
+        );
+        assert!(summary.diff.contains("+++ b/src/lib.rs"));
+        assert!(!summary.diff.contains("tmp.txt"));
+
+        let stat = summary.diff_stat();
+        assert!(stat.contains(" src/lib.rs | 3 ++-\n"));
+        assert!(stat.contains(" logo.png   | Bin\n"));
+        assert!(stat.ends_with("4 files changed, 3 insertions(+), 2 deletions(-)"));
+
+        assert!(ChangeSummary::from_changes(Path::new("/other"), &changes).is_none());
+    }
+
+    #[test]
+    fn test_fallback_commit_message_and_render() {
+        let changes = vec![
+            change("/repo/a.rs", None, Some("a\n")),
+            change("/repo/src/b.rs", None, Some("b\n")),
+        ];
+        let mut summary = ChangeSummary::from_changes(Path::new("/repo"), &changes).unwrap();
+        assert_eq!(summary.fallback_commit_message(), "Add a.rs and b.rs");
+
+        summary.files[1].status = FileStatus::Modified;
+        summary.files.push(summary.files[0].clone());
+        assert_eq!(
+            summary.fallback_commit_message(),
+            "Update a.rs and 2 other files"
+        );
+
+        summary.commit_message = "Add a and b".to_string();
+        let rendered = summary.render();
+        assert!(rendered.contains("- `src/b.rs` (modified)"));
+        assert!(rendered.contains("Suggested commit message:\n\n```\nAdd a and b\n```"));
+    }
+
+    #[test]
+    fn test_truncate_diff() {
+        assert_eq!(truncate_diff("short", 10), "short");
+        assert_eq!(
+            truncate_diff("line one\nline two\n", 12),
+            "line one\n[diff truncated]"
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+
+use mcp_core::role::Role;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+
+use crate::agents::Agent;
+
+/// Config key that ends the final reply of a run that used tools with the claims it makes,
+/// the tool results backing each one and how confident the answer is, off by default
+pub const CITATIONS_KEY: &str = "GOOSE_CITATIONS";
+
+/// Cap on the text of a single tool result sent when asking for citations
+const MAX_EVIDENCE_CHARS: usize = 2_000;
+
+/// Cap on all tool results sent, the oldest ones are left out first
+const MAX_PROMPT_EVIDENCE_CHARS: usize = 30_000;
+
+const CITATION_PROMPT: &str = "You check an assistant's final answer against the tool results \
+    it was based on. Split the answer into its factual claims, and for each one list the ids of \
+    the tool results that support it and how likely the claim is to be correct given those \
+    results, from 0 to 1. Claims that no tool result supports get no ids and a low confidence. \
+    Also rate the answer as a whole from 0 to 1. Reply with JSON only, in the form \
+    {\"confidence\": 0.8, \"citations\": [{\"claim\": \"...\", \"sources\": [\"<id>\"], \
+    \"confidence\": 0.9}]}";
+
+pub fn citations_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>(CITATIONS_KEY)
+        .unwrap_or(false)
+}
+
+/// A tool result of the run, as the model saw it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evidence {
+    /// Id of the tool call, shared by its request and response
+    pub id: String,
+    pub tool: String,
+    pub text: String,
+}
+
+/// The tool results since the last message the user typed, oldest first
+pub fn collect_evidence(messages: &[Message]) -> Vec<Evidence> {
+    let start = messages
+        .iter()
+        .rposition(|message| {
+            message.role == Role::User
+                && message
+                    .content
+                    .iter()
+                    .any(|content| matches!(content, MessageContent::Text(_)))
+        })
+        .unwrap_or(0);
+
+    let mut tools = HashMap::new();
+    let mut evidence = Vec::new();
+    for content in messages[start..].iter().flat_map(|m| &m.content) {
+        match content {
+            MessageContent::ToolRequest(request) => {
+                if let Ok(call) = &request.tool_call {
+                    tools.insert(request.id.clone(), call.name.clone());
+                }
+            }
+            MessageContent::ToolResponse(response) => {
+                let text = match &response.tool_result {
+                    Ok(contents) => contents
+                        .iter()
+                        .filter(|content| {
+                            content
+                                .audience()
+                                .is_none_or(|audience| audience.contains(&Role::Assistant))
+                        })
+                        .filter_map(|content| content.as_text())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    Err(e) => format!("Error: {}", e),
+                };
+                evidence.push(Evidence {
+                    id: response.id.clone(),
+                    tool: tools.get(&response.id).cloned().unwrap_or_default(),
+                    text,
+                });
+            }
+            _ => {}
+        }
+    }
+    evidence
+}
+
+/// A tool result backing a claim
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    pub id: String,
+    pub tool: String,
+}
+
+/// A claim of the final answer and the tool results it rests on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    pub claim: String,
+    /// Empty when no tool result supports the claim
+    pub sources: Vec<Source>,
+    /// How likely the claim is to be correct, from 0 to 1
+    pub confidence: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CitedAnswer {
+    /// How likely the answer as a whole is to be correct, from 0 to 1
+    pub confidence: f32,
+    pub citations: Vec<Citation>,
+}
+
+#[derive(Deserialize)]
+struct RawCitation {
+    claim: String,
+    #[serde(default)]
+    sources: Vec<String>,
+    confidence: f32,
+}
+
+#[derive(Deserialize)]
+struct RawCitedAnswer {
+    confidence: f32,
+    citations: Vec<RawCitation>,
+}
+
+impl CitedAnswer {
+    /// Read the provider's reply, dropping sources that aren't one of the tool results
+    pub fn parse(reply: &str, evidence: &[Evidence]) -> Option<Self> {
+        // Models tend to fence the JSON or say something around it
+        let json = reply.get(reply.find('{')?..=reply.rfind('}')?)?;
+        let raw: RawCitedAnswer = serde_json::from_str(json).ok()?;
+
+        let citations = raw
+            .citations
+            .into_iter()
+            .filter(|citation| !citation.claim.trim().is_empty())
+            .map(|citation| Citation {
+                claim: citation.claim.trim().to_string(),
+                sources: citation
+                    .sources
+                    .iter()
+                    .filter_map(|id| evidence.iter().find(|e| &e.id == id))
+                    .map(|e| Source {
+                        id: e.id.clone(),
+                        tool: e.tool.clone(),
+                    })
+                    .collect(),
+                confidence: citation.confidence.clamp(0.0, 1.0),
+            })
+            .collect::<Vec<_>>();
+        (!citations.is_empty()).then(|| CitedAnswer {
+            confidence: raw.confidence.clamp(0.0, 1.0),
+            citations,
+        })
+    }
+
+    /// Markdown listing each claim with its confidence and sources
+    pub fn render(&self) -> String {
+        let mut out = format!("**Sources** (confidence {:.0}%)\n", self.confidence * 100.0);
+        for (index, citation) in self.citations.iter().enumerate() {
+            let sources = if citation.sources.is_empty() {
+                "no supporting tool result".to_string()
+            } else {
+                citation
+                    .sources
+                    .iter()
+                    .map(|source| format!("`{}` `{}`", source.tool, source.id))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            out.push_str(&format!(
+                "{}. {} ({:.0}%): {}\n",
+                index + 1,
+                citation.claim,
+                citation.confidence * 100.0,
+                sources
+            ));
+        }
+        out.trim_end().to_string()
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}\n[truncated]", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// The tool results as sent to the provider, newest first until the cap is reached
+fn format_evidence(evidence: &[Evidence]) -> String {
+    let mut sections = Vec::new();
+    let mut total = 0;
+    for e in evidence.iter().rev() {
+        let section = format!(
+            "[{}] {}\n{}",
+            e.id,
+            e.tool,
+            truncate(&e.text, MAX_EVIDENCE_CHARS)
+        );
+        total += section.len();
+        if total > MAX_PROMPT_EVIDENCE_CHARS && !sections.is_empty() {
+            break;
+        }
+        sections.push(section);
+    }
+    sections.reverse();
+    sections.join("\n\n")
+}
+
+impl Agent {
+    /// Have the provider link the claims of `answer` to the tool results of the run in
+    /// `messages` that support them. None when the run used no tools or the reply can't be read.
+    pub async fn cite_evidence(
+        &self,
+        messages: &[Message],
+        answer: &Message,
+    ) -> Option<CitedAnswer> {
+        let answer_text = answer.as_concat_text();
+        let evidence = collect_evidence(messages);
+        if answer_text.trim().is_empty() || evidence.is_empty() {
+            return None;
+        }
+
+        let provider = self.provider().await.ok()?;
+        let request = format!(
+            "Tool results:\n\n{}\n\nFinal answer:\n\n{}",
+            format_evidence(&evidence),
+            answer_text
+        );
+        match provider
+            .complete(CITATION_PROMPT, &[Message::user().with_text(request)], &[])
+            .await
+        {
+            Ok((response, _)) => {
+                let cited = CitedAnswer::parse(&response.as_concat_text(), &evidence);
+                if cited.is_none() {
+                    tracing::warn!("Failed to read the citations for the final answer");
+                }
+                cited
+            }
+            Err(e) => {
+                tracing::warn!("Failed to cite the evidence for the final answer: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::{Content, ToolCall, ToolError};
+    use serde_json::json;
+
+    fn run() -> Vec<Message> {
+        vec![
+            Message::user().with_text("an earlier question"),
+            Message::assistant().with_tool_request(
+                "old",
+                Ok(ToolCall::new("developer__shell", json!({"command": "ls"}))),
+            ),
+            Message::user().with_tool_response("old", Ok(vec![Content::text("old output")])),
+            Message::user().with_text("which rust version is installed?"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "rustc -V"}),
+                )),
+            ),
+            Message::user().with_tool_response(
+                "1",
+                Ok(vec![
+                    Content::text("rustc 1.87.0").with_audience(vec![Role::Assistant]),
+                    Content::text("hidden from the model").with_audience(vec![Role::User]),
+                ]),
+            ),
+            Message::assistant().with_tool_request(
+                "2",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "cargo -V"}),
+                )),
+            ),
+            Message::user()
+                .with_tool_response("2", Err(ToolError::ExecutionError("not found".to_string()))),
+        ]
+    }
+
+    #[test]
+    fn test_collect_evidence() {
+        let evidence = collect_evidence(&run());
+        assert_eq!(evidence.len(), 2);
+        assert_eq!(evidence[0].id, "1");
+        assert_eq!(evidence[0].tool, "developer__shell");
+        assert_eq!(evidence[0].text, "rustc 1.87.0");
+        assert!(evidence[1].text.starts_with("Error:"));
+    }
+
+    #[test]
+    fn test_parse_and_render() {
+        let evidence = collect_evidence(&run());
+        let reply = r#"```json
+            {"confidence": 1.4, "citations": [
+                {"claim": "Rust 1.87.0 is installed", "sources": ["1", "made-up"], "confidence": 0.95},
+                {"claim": "Cargo is missing", "sources": [], "confidence": 0.4},
+                {"claim": " ", "sources": ["2"], "confidence": 0.5}
+            ]}
+            ```"#;
+        let cited = CitedAnswer::parse(reply, &evidence).unwrap();
+        assert_eq!(cited.confidence, 1.0);
+        assert_eq!(cited.citations.len(), 2);
+        assert_eq!(
+            cited.citations[0].sources,
+            vec![Source {
+                id: "1".to_string(),
+                tool: "developer__shell".to_string()
+            }]
+        );
+
+        let rendered = cited.render();
+        assert!(rendered.starts_with("**Sources** (confidence 100%)"));
+        assert!(rendered.contains("1. Rust 1.87.0 is installed (95%): `developer__shell` `1`"));
+        assert!(rendered.contains("2. Cargo is missing (40%): no supporting tool result"));
+
+        assert!(CitedAnswer::parse("I can't do that", &evidence).is_none());
+    }
+}
@@ -1,13 +1,18 @@
 use anyhow::Ok;
+use std::path::{Path, PathBuf};
 
 use crate::message::Message;
 use crate::token_counter::TokenCounter;
 
+use crate::context_mgmt::breakdown::{context_breakdown, ContextBreakdown};
+use crate::context_mgmt::collapse::{collapse_split, summarize_range, summary_message};
+use crate::context_mgmt::pinned::is_within;
 use crate::context_mgmt::summarize::summarize_messages;
 use crate::context_mgmt::truncate::{truncate_messages, OldestFirstTruncation};
 use crate::context_mgmt::{estimate_target_context_limit, get_messages_token_counts};
 
 use super::super::agents::Agent;
+use super::types::SessionConfig;
 
 impl Agent {
     /// Public API to truncate oldest messages so that the conversation's token count is within the allowed context limit.
@@ -36,6 +41,94 @@ impl Agent {
         Ok((new_messages, new_token_counts))
     }
 
+    /// Where the tokens of the next request to the provider would go with `messages`: the
+    /// system prompt, each extension's instructions and tools, and the conversation
+    pub async fn context_breakdown(
+        &self,
+        messages: &[Message],
+    ) -> Result<ContextBreakdown, anyhow::Error> {
+        let (tools, _toolshim_tools, system_prompt) = self.prepare_tools_and_prompt().await?;
+        let system_prompt = self.with_pinned_context(&system_prompt, None).await;
+        let extensions = self
+            .extension_manager
+            .lock()
+            .await
+            .get_extensions_info()
+            .await;
+        let provider = self.provider().await?;
+        let model_config = provider.get_model_config();
+        let token_counter = TokenCounter::new(model_config.tokenizer_name());
+
+        Ok(context_breakdown(
+            &token_counter,
+            &system_prompt,
+            &extensions,
+            &tools,
+            messages,
+            model_config.context_limit(),
+        ))
+    }
+
+    /// Keep a file, or every file in a directory, in the context of each request. Returns
+    /// false if the path was already pinned.
+    pub async fn pin_path(&self, path: PathBuf) -> Result<bool, anyhow::Error> {
+        self.pinned_context.lock().await.pin(path)
+    }
+
+    /// Returns false if the path wasn't pinned
+    pub async fn unpin_path(&self, path: &Path) -> bool {
+        self.pinned_context.lock().await.unpin(path)
+    }
+
+    pub async fn pinned_paths(&self) -> Vec<PathBuf> {
+        self.pinned_context.lock().await.paths().to_vec()
+    }
+
+    /// Restore the pinned paths, such as those saved with a resumed session
+    pub async fn set_pinned_paths(&self, paths: Vec<PathBuf>) {
+        self.pinned_context.lock().await.set_paths(paths);
+    }
+
+    /// The system prompt with the current content of the pinned files appended. With a
+    /// session the pins saved in its metadata are used, so sessions sharing the agent, such
+    /// as those of different users of one server, each see only their own. Pins outside the
+    /// session's working directory are skipped.
+    pub(crate) async fn with_pinned_context(
+        &self,
+        system_prompt: &str,
+        session_file: Option<&Path>,
+    ) -> String {
+        let pinned = match session_file {
+            Some(session_file) => {
+                let paths: Vec<PathBuf> = crate::session::read_metadata(session_file)
+                    .map(|metadata| {
+                        metadata
+                            .pinned
+                            .into_iter()
+                            .filter(|path| is_within(path, &metadata.working_dir))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let mut sessions = self.session_pins.lock().await;
+                if paths.is_empty() {
+                    sessions.remove(session_file);
+                    None
+                } else {
+                    let context = sessions.entry(session_file.to_path_buf()).or_default();
+                    if context.paths() != paths.as_slice() {
+                        context.set_paths(paths);
+                    }
+                    context.render()
+                }
+            }
+            None => self.pinned_context.lock().await.render(),
+        };
+        match pinned {
+            Some(pinned) => format!("{}\n\n{}", system_prompt, pinned),
+            None => system_prompt.to_string(),
+        }
+    }
+
     /// Public API to summarize the conversation so that its token count is within the allowed context limit.
     pub async fn summarize_context(
         &self,
@@ -60,4 +153,41 @@ impl Agent {
 
         Ok((new_messages, new_token_counts))
     }
+
+    /// Replace all but the last `keep_recent` messages with a summary.
+    ///
+    /// With a session the replaced messages are archived next to it and the summary refers to
+    /// them by id. Returns the new history and how many messages were collapsed, or `None` when
+    /// there aren't enough older messages to collapse.
+    pub async fn collapse_history(
+        &self,
+        messages: &[Message],
+        keep_recent: usize,
+        focus: Option<&str>,
+        session: Option<&SessionConfig>,
+    ) -> Result<Option<(Vec<Message>, usize)>, anyhow::Error> {
+        let Some(split) = collapse_split(messages, keep_recent) else {
+            return Ok(None);
+        };
+        let (older, recent) = messages.split_at(split);
+
+        let provider = self.provider().await?;
+        let summary = summarize_range(&provider, older, focus).await?;
+
+        let archive_id = match session {
+            Some(session) => {
+                let session_file = crate::session::get_path(session.id.clone());
+                Some(crate::session::archive_messages(&session_file, older)?)
+            }
+            None => None,
+        };
+
+        let mut collapsed = vec![summary_message(
+            older.len(),
+            archive_id.as_deref(),
+            &summary,
+        )];
+        collapsed.extend_from_slice(recent);
+        Ok(Some((collapsed, older.len())))
+    }
 }
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::context_mgmt::collapse::{collapse_split, DEFAULT_KEEP_RECENT};
+use crate::context_mgmt::get_messages_token_counts;
+use crate::message::Message;
+use crate::token_counter::TokenCounter;
+
+use super::types::SessionConfig;
+use crate::agents::Agent;
+
+/// Config key for how many times one provider call is retried with a smaller history after
+/// the context window overflows, 0 leaves the overflow to the client as before
+pub const CONTEXT_OVERFLOW_RETRIES_KEY: &str = "GOOSE_CONTEXT_OVERFLOW_RETRIES";
+
+const DEFAULT_OVERFLOW_RETRIES: u32 = 3;
+
+/// How older messages make room when the context window overflows in the middle of a reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RecoveryStrategy {
+    /// Replace them with a summary written by the provider
+    Summarize,
+    /// Drop them
+    Truncate,
+}
+
+impl RecoveryStrategy {
+    /// The strategy matching `GOOSE_CONTEXT_STRATEGY`, summarize when it is unset. `clear` and
+    /// `prompt` need the user, so those overflows still end the reply for the client to handle.
+    pub fn from_config() -> Option<Self> {
+        if overflow_retries() == 0 {
+            return None;
+        }
+        match Config::global()
+            .get_param::<String>("GOOSE_CONTEXT_STRATEGY")
+            .as_deref()
+        {
+            Ok("summarize") | Err(_) => Some(RecoveryStrategy::Summarize),
+            Ok("truncate") => Some(RecoveryStrategy::Truncate),
+            Ok(_) => None,
+        }
+    }
+}
+
+pub fn overflow_retries() -> u32 {
+    Config::global()
+        .get_param::<u32>(CONTEXT_OVERFLOW_RETRIES_KEY)
+        .unwrap_or(DEFAULT_OVERFLOW_RETRIES)
+}
+
+/// Sent when the history was shrunk to retry a request that overflowed the context window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ContextRecoveryEvent {
+    pub strategy: RecoveryStrategy,
+    /// Which retry of the request this is, starting at 1
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub dropped_messages: usize,
+    /// Estimated size of the dropped messages
+    pub dropped_tokens: usize,
+}
+
+impl ContextRecoveryEvent {
+    pub fn summary(&self) -> String {
+        let action = match self.strategy {
+            RecoveryStrategy::Summarize => "summarized",
+            RecoveryStrategy::Truncate => "dropped",
+        };
+        format!(
+            "The context window was full, so {} earlier messages (about {} tokens) were {} and the request was retried ({} of {}).",
+            self.dropped_messages, self.dropped_tokens, action, self.attempt, self.max_attempts
+        )
+    }
+}
+
+/// Messages kept as they are on the `attempt`th retry, fewer each time
+fn keep_recent(attempt: u32) -> usize {
+    (DEFAULT_KEEP_RECENT >> attempt.saturating_sub(1).min(8)).max(1)
+}
+
+/// Drop all but the last `keep_recent` messages, leaving a note in their place. Returns the
+/// new history and how many messages were dropped.
+pub fn drop_oldest(messages: &[Message], keep_recent: usize) -> Option<(Vec<Message>, usize)> {
+    // Keeping at least one message makes sure the request itself survives
+    let split = collapse_split(messages, keep_recent.max(1))?;
+    let mut kept = vec![Message::user().with_text(format!(
+        "[{} earlier messages were dropped to fit the context window]",
+        split
+    ))];
+    kept.extend_from_slice(&messages[split..]);
+    Some((kept, split))
+}
+
+impl Agent {
+    /// Shrink `messages` after the provider reported them as too long for its context window,
+    /// each attempt keeping fewer recent messages. None when nothing more can be dropped.
+    pub(super) async fn recover_from_overflow(
+        &self,
+        messages: &[Message],
+        strategy: RecoveryStrategy,
+        attempt: u32,
+        max_attempts: u32,
+        session: Option<&SessionConfig>,
+    ) -> Option<(Vec<Message>, ContextRecoveryEvent)> {
+        let keep = keep_recent(attempt);
+        let (history, count, strategy) = match strategy {
+            RecoveryStrategy::Summarize => {
+                match self.collapse_history(messages, keep, None, session).await {
+                    Ok(Some((history, count))) => (history, count, strategy),
+                    Ok(None) => return None,
+                    // The summary request can overflow too, dropping still gets the reply going
+                    Err(e) => {
+                        warn!("Failed to summarize after the context overflowed: {}", e);
+                        let (history, count) = drop_oldest(messages, keep)?;
+                        (history, count, RecoveryStrategy::Truncate)
+                    }
+                }
+            }
+            RecoveryStrategy::Truncate => {
+                let (history, count) = drop_oldest(messages, keep)?;
+                (history, count, strategy)
+            }
+        };
+
+        let dropped_tokens = match self.provider().await {
+            Ok(provider) => {
+                let token_counter = TokenCounter::new(provider.get_model_config().tokenizer_name());
+                get_messages_token_counts(&token_counter, &messages[..count])
+                    .iter()
+                    .sum()
+            }
+            Err(_) => 0,
+        };
+
+        Some((
+            history,
+            ContextRecoveryEvent {
+                strategy,
+                attempt,
+                max_attempts,
+                dropped_messages: count,
+                dropped_tokens,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::{Content, Role, ToolCall};
+    use serde_json::json;
+
+    fn conversation() -> Vec<Message> {
+        let mut messages = Vec::new();
+        for i in 0..4 {
+            let id = i.to_string();
+            messages.push(Message::user().with_text(format!("step {}", i)));
+            messages.push(Message::assistant().with_tool_request(
+                &id,
+                Ok(ToolCall::new("developer__shell", json!({"command": "ls"}))),
+            ));
+            messages.push(Message::user().with_tool_response(&id, Ok(vec![Content::text("out")])));
+        }
+        messages
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let messages = conversation();
+        let (kept, dropped) = drop_oldest(&messages, 2).unwrap();
+        // The kept messages start at an assistant message so tool responses keep their request
+        assert_eq!(dropped, 10);
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[0].role, Role::User);
+        assert!(kept[0].as_concat_text().contains("10 earlier messages"));
+        assert_eq!(kept[1].role, Role::Assistant);
+        assert_eq!(kept.last(), messages.last());
+
+        // The request itself is always kept
+        let (kept, _) = drop_oldest(&messages, 0).unwrap();
+        assert_eq!(kept.last(), messages.last());
+
+        assert!(drop_oldest(&messages[..2], 1).is_none());
+    }
+
+    #[test]
+    fn test_keep_recent_shrinks() {
+        assert_eq!(keep_recent(1), DEFAULT_KEEP_RECENT);
+        assert!(keep_recent(2) < keep_recent(1));
+        assert_eq!(keep_recent(20), 1);
+    }
+
+    #[test]
+    fn test_summary() {
+        let event = ContextRecoveryEvent {
+            strategy: RecoveryStrategy::Truncate,
+            attempt: 1,
+            max_attempts: 3,
+            dropped_messages: 12,
+            dropped_tokens: 34000,
+        };
+        let summary = event.summary();
+        assert!(summary.contains("12 earlier messages"));
+        assert!(summary.contains("were dropped"));
+        assert!(summary.contains("(1 of 3)"));
+    }
+}
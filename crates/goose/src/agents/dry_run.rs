@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use async_stream::try_stream;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use mcp_core::{Content, ToolCall};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::message::{Message, ToolRequest};
+use crate::permission::permission_judge::ToolAnnotationCategories;
+use crate::providers::base::Provider;
+
+use super::timeouts::{
+    wait_for, TimeoutConfig, TimeoutEvent, TimeoutKind, WaitError, FRONTEND_TOOL_TIMED_OUT_RESPONSE,
+};
+use crate::agents::{Agent, AgentEvent};
+
+/// Config key holding the dry run mode, `off`, `simulate` or `prompt`
+pub const DRY_RUN_KEY: &str = "GOOSE_DRY_RUN";
+
+/// Prefix on every simulated result so neither the model nor the user mistakes it for real output
+pub const DRY_RUN_PREFIX: &str = "[dry run]";
+
+const SIMULATE_PROMPT: &str = "You stand in for a tool while someone tests an agent workflow \
+    without side effects. Given a tool call, reply with the output the tool would most \
+    plausibly return if it succeeded. Reply with the output only, no commentary.";
+
+/// How tool calls that could change something are handled, read-only tools always run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DryRunMode {
+    /// Run every tool for real
+    #[default]
+    Off,
+    /// Have the provider make up a plausible result
+    Simulate,
+    /// Ask the user to type the result, through the frontend tool request flow
+    Prompt,
+}
+
+impl DryRunMode {
+    pub fn from_config() -> Self {
+        Config::global()
+            .get_param::<String>(DRY_RUN_KEY)
+            .ok()
+            .and_then(|mode| mode.parse().ok())
+            .unwrap_or_default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        *self != DryRunMode::Off
+    }
+
+    /// Whether a call to `tool_name` is intercepted rather than run. Platform tools only
+    /// touch the agent itself, so they run like read-only tools do.
+    pub fn intercepts(&self, tool_name: &str, annotations: &ToolAnnotationCategories) -> bool {
+        self.is_enabled()
+            && !tool_name.starts_with("platform__")
+            && !annotations.read_only.contains(tool_name)
+            && !annotations.trusted_read_only.contains(tool_name)
+    }
+}
+
+impl std::str::FromStr for DryRunMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" | "false" | "" => Ok(DryRunMode::Off),
+            "simulate" | "true" => Ok(DryRunMode::Simulate),
+            "prompt" => Ok(DryRunMode::Prompt),
+            other => Err(format!(
+                "Unknown dry run mode '{}', expected off, simulate or prompt",
+                other
+            )),
+        }
+    }
+}
+
+/// Ask the provider what `call` would most likely have returned
+async fn simulate_result(provider: &Arc<dyn Provider>, call: &ToolCall) -> String {
+    let request = Message::user().with_text(format!(
+        "Tool: {}\nArguments: {}",
+        call.name, call.arguments
+    ));
+    match provider.complete(SIMULATE_PROMPT, &[request], &[]).await {
+        Ok((response, _)) if !response.as_concat_text().trim().is_empty() => {
+            format!("{} {}", DRY_RUN_PREFIX, response.as_concat_text().trim())
+        }
+        Ok(_) | Err(_) => format!(
+            "{} {} was not run, assume it succeeded.",
+            DRY_RUN_PREFIX, call.name
+        ),
+    }
+}
+
+impl Agent {
+    /// Answer intercepted tool calls without running them, according to `mode`
+    pub(crate) fn handle_dry_run_requests<'a>(
+        &'a self,
+        mode: DryRunMode,
+        tool_requests: &'a [ToolRequest],
+        message_tool_response: Arc<Mutex<Message>>,
+        timeouts: &'a TimeoutConfig,
+    ) -> BoxStream<'a, anyhow::Result<AgentEvent>> {
+        try_stream! {
+            for request in tool_requests {
+                let Ok(tool_call) = request.tool_call.clone() else {
+                    continue;
+                };
+                let result = match mode {
+                    DryRunMode::Prompt => {
+                        // The client shows the call and sends back what the user typed
+                        yield AgentEvent::Message(Message::assistant().with_frontend_tool_request(
+                            request.id.clone(),
+                            Ok(tool_call.clone()),
+                        ));
+                        let mut rx = self.tool_result_rx.lock().await;
+                        match wait_for(&mut rx, &request.id, timeouts.inactivity).await {
+                            Ok(Ok(contents)) => {
+                                let text = contents
+                                    .iter()
+                                    .filter_map(|content| content.as_text())
+                                    .collect::<Vec<_>>()
+                                    .join("\n");
+                                Ok(vec![Content::text(format!("{} {}", DRY_RUN_PREFIX, text))])
+                            }
+                            Ok(Err(e)) => Err(e),
+                            Err(WaitError::TimedOut) => {
+                                yield AgentEvent::Timeout(TimeoutEvent {
+                                    kind: TimeoutKind::FrontendTool,
+                                    request_id: request.id.clone(),
+                                    tool_name: tool_call.name.clone(),
+                                    waited_secs: timeouts.inactivity.unwrap_or_default().as_secs(),
+                                    action: timeouts.action,
+                                });
+                                Err(mcp_core::ToolError::ExecutionError(
+                                    FRONTEND_TOOL_TIMED_OUT_RESPONSE.to_string(),
+                                ))
+                            }
+                            Err(WaitError::Closed) => continue,
+                        }
+                    }
+                    DryRunMode::Simulate | DryRunMode::Off => {
+                        let provider = self.provider().await?;
+                        Ok(vec![Content::text(simulate_result(&provider, &tool_call).await)])
+                    }
+                };
+                let mut response = message_tool_response.lock().await;
+                *response = response.clone().with_tool_response(request.id.clone(), result);
+            }
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intercepts() {
+        let mut annotations = ToolAnnotationCategories::default();
+        annotations
+            .read_only
+            .insert("developer__list_windows".to_string());
+
+        let mode = DryRunMode::Simulate;
+        assert!(mode.intercepts("developer__shell", &annotations));
+        assert!(!mode.intercepts("developer__list_windows", &annotations));
+        assert!(!mode.intercepts("platform__read_resource", &annotations));
+        assert!(!DryRunMode::Off.intercepts("developer__shell", &annotations));
+
+        assert_eq!("Prompt".parse::<DryRunMode>(), Ok(DryRunMode::Prompt));
+        assert_eq!("".parse::<DryRunMode>(), Ok(DryRunMode::Off));
+        assert!("maybe".parse::<DryRunMode>().is_err());
+    }
+}
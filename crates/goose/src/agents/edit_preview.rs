@@ -0,0 +1,248 @@
+use std::path::Path;
+
+use mcp_core::ToolCall;
+
+/// Files larger than this are not previewed, reading them would hold up the approval prompt
+const MAX_PREVIEW_BYTES: u64 = 1024 * 1024;
+
+/// Cap on the lines compared by the diff, the rest of a larger change is shown as replaced
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+const CONTEXT_LINES: usize = 3;
+
+/// A unified diff of the change a text editor `write` or `str_replace` call would make to the
+/// file on disk, so it can be shown when asking the user to approve the call.
+///
+/// Returns `None` for other tools and commands, or when the change can't be worked out, in
+/// which case the tool reports the problem itself when it runs.
+pub fn preview_edit(tool_call: &ToolCall) -> Option<String> {
+    if !tool_call.name.ends_with("text_editor") {
+        return None;
+    }
+    let arguments = &tool_call.arguments;
+    let path = Path::new(arguments.get("path")?.as_str()?);
+    if !path.is_absolute() {
+        return None;
+    }
+
+    let current = match std::fs::metadata(path) {
+        Ok(metadata) if metadata.len() > MAX_PREVIEW_BYTES => return None,
+        Ok(_) => Some(std::fs::read_to_string(path).ok()?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(_) => return None,
+    };
+
+    let proposed = match arguments.get("command")?.as_str()? {
+        "write" => arguments.get("file_text")?.as_str()?.to_string(),
+        "str_replace" => {
+            let content = current.as_deref()?;
+            let old_str = arguments.get("old_str")?.as_str()?;
+            let new_str = arguments.get("new_str")?.as_str()?;
+            if content.matches(old_str).count() != 1 {
+                return None;
+            }
+            content.replacen(old_str, new_str, 1)
+        }
+        _ => return None,
+    };
+
+    let path = path.to_string_lossy();
+    let old_name = match current {
+        Some(_) => format!("a{}", path),
+        None => "/dev/null".to_string(),
+    };
+    let diff = unified_diff(
+        current.as_deref().unwrap_or_default(),
+        &proposed,
+        &old_name,
+        &format!("b{}", path),
+    );
+    (!diff.is_empty()).then_some(diff)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// A unified diff between two texts with three lines of context, empty if they are the same
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&i| ops[i].0 != Op::Equal).collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // Line numbers in the old and new text before each op
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut old_line, mut new_line) = (0, 0);
+    for (op, _) in &ops {
+        positions.push((old_line, new_line));
+        match op {
+            Op::Equal => {
+                old_line += 1;
+                new_line += 1;
+            }
+            Op::Delete => old_line += 1,
+            Op::Insert => new_line += 1,
+        }
+    }
+    positions.push((old_line, new_line));
+
+    // Group changes whose context would overlap into the same hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changes {
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let end = (i + 1 + CONTEXT_LINES).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for (start, end) in hunks {
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        ));
+        for (op, line) in &ops[start..end] {
+            let prefix = match op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            out.push(prefix);
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        // An empty range names the line before it
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// The edits that turn `old` into `new`, from the longest common subsequence of their lines
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<(Op, &str)> = old[..prefix].iter().map(|l| (Op::Equal, *l)).collect();
+    if a.len().saturating_mul(b.len()) > MAX_DIFF_CELLS {
+        ops.extend(a.iter().map(|l| (Op::Delete, *l)));
+        ops.extend(b.iter().map(|l| (Op::Insert, *l)));
+    } else {
+        // lcs[i][j] is the length of the longest common subsequence of a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lcs = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * width + j] = if a[i] == b[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                ops.push((Op::Equal, a[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+                ops.push((Op::Delete, a[i]));
+                i += 1;
+            } else {
+                ops.push((Op::Insert, b[j]));
+                j += 1;
+            }
+        }
+        ops.extend(a[i..].iter().map(|l| (Op::Delete, *l)));
+        ops.extend(b[j..].iter().map(|l| (Op::Insert, *l)));
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|l| (Op::Equal, *l)));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        assert_eq!(
+            unified_diff(old, new, "a/x", "b/x"),
+            "--- a/x\n+++ b/x\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+             @@ -8,3 +8,4 @@\n h\n i\n j\n+k\n"
+        );
+        assert_eq!(unified_diff(old, old, "a/x", "b/x"), "");
+        assert_eq!(
+            unified_diff("", "one\n", "/dev/null", "b/x"),
+            "--- /dev/null\n+++ b/x\n@@ -0,0 +1 @@\n+one\n"
+        );
+    }
+
+    #[test]
+    fn test_preview_edit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("main.rs");
+        let path_str = path.to_str().unwrap();
+
+        let write = ToolCall::new(
+            "developer__text_editor",
+            json!({"command": "write", "path": path_str, "file_text": "fn main() {}\n"}),
+        );
+        let diff = preview_edit(&write).unwrap();
+        assert!(diff.starts_with("--- /dev/null\n"));
+        assert!(diff.ends_with("+fn main() {}\n"));
+
+        std::fs::write(&path, "fn main() {\n    println!(\"hi\");\n}\n").unwrap();
+        let replace = ToolCall::new(
+            "developer__text_editor",
+            json!({"command": "str_replace", "path": path_str, "old_str": "hi", "new_str": "bye"}),
+        );
+        let diff = preview_edit(&replace).unwrap();
+        assert!(diff.contains("-    println!(\"hi\");\n+    println!(\"bye\");\n"));
+
+        let missing = ToolCall::new(
+            "developer__text_editor",
+            json!({"command": "str_replace", "path": path_str, "old_str": "nope", "new_str": ""}),
+        );
+        assert!(preview_edit(&missing).is_none());
+        let view = ToolCall::new(
+            "developer__text_editor",
+            json!({"command": "view", "path": path_str}),
+        );
+        assert!(preview_edit(&view).is_none());
+        let shell = ToolCall::new("developer__shell", json!({"command": "ls"}));
+        assert!(preview_edit(&shell).is_none());
+    }
+}
@@ -28,6 +28,8 @@ pub enum ExtensionError {
     SetupError(String),
     #[error("Join error occurred during task execution: {0}")]
     TaskJoinError(#[from] tokio::task::JoinError),
+    #[error("Extension `{0}` was disabled after failing repeatedly, run `goose extensions enable {0}` once it is fixed")]
+    Suspended(String),
 }
 
 pub type ExtensionResult<T> = Result<T, ExtensionError>;
@@ -156,6 +158,16 @@ pub enum ExtensionConfig {
         /// Whether this extension is bundled with Goose
         #[serde(default)]
         bundled: Option<bool>,
+        /// Directory to run the server in, defaults to goose's working directory
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cwd: Option<String>,
+        /// Variables the server inherits from goose's environment, entries ending in `*` match
+        /// a prefix. Defaults to `GOOSE_MCP_INHERIT_ENV`, and to everything when that is unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        inherit_env: Option<Vec<String>>,
+        /// Directories added to the front of the server's PATH
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        path_prepend: Vec<String>,
     },
     /// Built-in extension that is part of the goose binary
     #[serde(rename = "builtin")]
@@ -222,36 +234,24 @@ impl ExtensionConfig {
             description: Some(description.into()),
             timeout: Some(timeout.into()),
             bundled: None,
+            cwd: None,
+            inherit_env: None,
+            path_prepend: Vec::new(),
         }
     }
 
-    pub fn with_args<I, S>(self, args: I) -> Self
+    pub fn with_args<I, S>(mut self, args: I) -> Self
     where
         I: IntoIterator<Item = S>,
         S: Into<String>,
     {
-        match self {
-            Self::Stdio {
-                name,
-                cmd,
-                envs,
-                env_keys,
-                timeout,
-                description,
-                bundled,
-                ..
-            } => Self::Stdio {
-                name,
-                cmd,
-                envs,
-                env_keys,
-                args: args.into_iter().map(Into::into).collect(),
-                description,
-                timeout,
-                bundled,
-            },
-            other => other,
+        if let Self::Stdio {
+            args: stdio_args, ..
+        } = &mut self
+        {
+            *stdio_args = args.into_iter().map(Into::into).collect();
         }
+        self
     }
 
     pub fn key(&self) -> String {
@@ -4,21 +4,26 @@ use futures::stream::{FuturesUnordered, StreamExt};
 use futures::{future, FutureExt};
 use mcp_core::protocol::GetPromptResult;
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::LazyLock;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tokio::task;
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::{error, warn};
 
 use super::extension::{ExtensionConfig, ExtensionError, ExtensionInfo, ExtensionResult, ToolInfo};
+use super::tool_errors::ToolErrorRule;
 use super::tool_execution::ToolCallResult;
 use crate::agents::extension::Envs;
-use crate::config::{Config, ExtensionConfigManager};
+use crate::config::{
+    Config, ExtensionConfigManager, ExtensionDisabledEvent, ExtensionHealthManager,
+};
 use crate::prompt_template;
 use mcp_client::client::{ClientCapabilities, ClientInfo, McpClient, McpClientTrait};
 use mcp_client::transport::{SseTransport, StdioTransport, Transport};
+use mcp_client::OutputValidation;
 use mcp_core::{prompt::Prompt, Content, Tool, ToolCall, ToolError};
 use serde_json::Value;
 
@@ -29,11 +34,54 @@ static DEFAULT_TIMESTAMP: LazyLock<DateTime<Utc>> =
 
 type McpClientBox = Arc<Mutex<Box<dyn McpClientTrait>>>;
 
+/// Config key holding the environment variables stdio extensions inherit, for extensions that
+/// don't set `inherit_env` themselves. Everything is inherited when unset.
+pub const INHERIT_ENV_KEY: &str = "GOOSE_MCP_INHERIT_ENV";
+
+/// Config key holding how tool results are checked against the output schemas extensions
+/// declare, `off`, `warn` (the default) or `strict` to fail calls with malformed results
+pub const TOOL_OUTPUT_VALIDATION_KEY: &str = "GOOSE_TOOL_OUTPUT_VALIDATION";
+
+fn output_validation() -> OutputValidation {
+    Config::global()
+        .get_param::<String>(TOOL_OUTPUT_VALIDATION_KEY)
+        .ok()
+        .and_then(|validation| validation.parse().ok())
+        .unwrap_or_default()
+}
+
 /// Manages Goose extensions / MCP clients and their interactions
 pub struct ExtensionManager {
     clients: HashMap<String, McpClientBox>,
+    configs: HashMap<String, ExtensionConfig>,
     instructions: HashMap<String, String>,
+    instruction_refreshes: HashMap<String, InstructionRefresh>,
     resource_capable_extensions: HashSet<String>,
+    /// Rules the extensions advertised for classifying errors from their tools
+    tool_error_rules: HashMap<String, Vec<ToolErrorRule>>,
+    /// Extensions disabled by a failing tool call, to be removed once the calls are done
+    suspended: Arc<Mutex<Vec<ExtensionDisabledEvent>>>,
+    /// Where extension processes run when their configuration doesn't say, see `set_environment`
+    working_dir: Option<PathBuf>,
+    /// Variables set for every extension process on top of its own
+    envs: HashMap<String, String>,
+}
+
+/// Refresh policy for an extension that marked its instructions as dynamic
+#[derive(Debug, Clone)]
+struct InstructionRefresh {
+    interval: Option<Duration>,
+    on_tool_call: bool,
+    last_refreshed: Instant,
+}
+
+impl InstructionRefresh {
+    fn is_due(&self, now: Instant, tool_called: bool) -> bool {
+        (self.on_tool_call && tool_called)
+            || self
+                .interval
+                .is_some_and(|interval| now.duration_since(self.last_refreshed) >= interval)
+    }
 }
 
 /// A flattened representation of a resource used by the agent to prepare inference
@@ -69,9 +117,22 @@ impl ResourceItem {
     }
 }
 
+/// Record how a launch or tool call of an extension went, returning an event when the
+/// failure disabled it. Tracking is best effort, a config that can't be written is only logged.
+fn record_health(name: &str, error: Option<String>) -> Option<ExtensionDisabledEvent> {
+    let outcome = match error {
+        Some(error) => ExtensionHealthManager::record_failure(name, &error),
+        None => ExtensionHealthManager::record_success(name).map(|_| None),
+    };
+    outcome.unwrap_or_else(|e| {
+        warn!("Failed to record the health of extension {}: {}", name, e);
+        None
+    })
+}
+
 /// Sanitizes a string by replacing invalid characters with underscores.
 /// Valid characters match [a-zA-Z0-9_-]
-fn normalize(input: String) -> String {
+pub(crate) fn normalize(input: String) -> String {
     let mut result = String::with_capacity(input.len());
     for c in input.chars() {
         result.push(match c {
@@ -102,18 +163,47 @@ impl ExtensionManager {
     pub fn new() -> Self {
         Self {
             clients: HashMap::new(),
+            configs: HashMap::new(),
             instructions: HashMap::new(),
+            instruction_refreshes: HashMap::new(),
             resource_capable_extensions: HashSet::new(),
+            tool_error_rules: HashMap::new(),
+            suspended: Arc::new(Mutex::new(Vec::new())),
+            working_dir: None,
+            envs: HashMap::new(),
         }
     }
 
+    /// Start the processes of extensions added from now on in `working_dir`, unless their
+    /// configuration names another directory, and with `envs` set. Keeps the extensions of
+    /// agents serving different users in one process apart.
+    pub fn set_environment(&mut self, working_dir: PathBuf, envs: HashMap<String, String>) {
+        self.working_dir = Some(working_dir);
+        self.envs = envs;
+    }
+
     pub fn supports_resources(&self) -> bool {
         !self.resource_capable_extensions.is_empty()
     }
 
-    /// Add a new MCP extension based on the provided client type
-    // TODO IMPORTANT need to ensure this times out if the extension command is broken!
+    /// Add a new MCP extension based on the provided client type. Extensions disabled for
+    /// failing too often are refused until re-enabled with `goose extensions enable`.
     pub async fn add_extension(&mut self, config: ExtensionConfig) -> ExtensionResult<()> {
+        let name = config.name();
+        if ExtensionHealthManager::is_disabled(&name) {
+            return Err(ExtensionError::Suspended(name));
+        }
+
+        let result = self.launch_extension(config).await;
+        let error = result.as_ref().err().map(|e| e.to_string());
+        if let Some(event) = record_health(&name, error) {
+            warn!("{}", event.summary());
+        }
+        result
+    }
+
+    // TODO IMPORTANT need to ensure this times out if the extension command is broken!
+    async fn launch_extension(&mut self, config: ExtensionConfig) -> ExtensionResult<()> {
         let config_name = config.key().to_string();
         let sanitized_name = normalize(config_name.clone());
 
@@ -192,7 +282,8 @@ impl ExtensionManager {
                             timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
                         ),
                     )
-                    .await?,
+                    .await?
+                    .with_output_validation(output_validation()),
                 )
             }
             ExtensionConfig::Stdio {
@@ -201,10 +292,25 @@ impl ExtensionManager {
                 envs,
                 env_keys,
                 timeout,
+                cwd,
+                inherit_env,
+                path_prepend,
                 ..
             } => {
-                let all_envs = merge_environments(envs, env_keys, &sanitized_name).await?;
-                let transport = StdioTransport::new(cmd, args.to_vec(), all_envs);
+                let mut all_envs = merge_environments(envs, env_keys, &sanitized_name).await?;
+                all_envs.extend(self.envs.clone());
+                let mut transport = StdioTransport::new(cmd, args.to_vec(), all_envs)
+                    .with_name(&sanitized_name)
+                    .with_path_prepend(path_prepend.iter().map(PathBuf::from).collect());
+                if let Some(cwd) = cwd.as_ref().map(PathBuf::from).or(self.working_dir.clone()) {
+                    transport = transport.with_cwd(cwd);
+                }
+                let inherit_env = inherit_env
+                    .clone()
+                    .or_else(|| Config::global().get_param(INHERIT_ENV_KEY).ok());
+                if let Some(allow_list) = inherit_env {
+                    transport = transport.with_inherited_env(allow_list);
+                }
                 let handle = transport.start().await?;
                 Box::new(
                     McpClient::connect(
@@ -213,7 +319,8 @@ impl ExtensionManager {
                             timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
                         ),
                     )
-                    .await?,
+                    .await?
+                    .with_output_validation(output_validation()),
                 )
             }
             ExtensionConfig::Builtin {
@@ -227,11 +334,15 @@ impl ExtensionManager {
                     .to_str()
                     .expect("should resolve executable to string path")
                     .to_string();
-                let transport = StdioTransport::new(
+                let mut transport = StdioTransport::new(
                     &cmd,
                     vec!["mcp".to_string(), name.clone()],
-                    HashMap::new(),
-                );
+                    self.envs.clone(),
+                )
+                .with_name(&sanitized_name);
+                if let Some(working_dir) = &self.working_dir {
+                    transport = transport.with_cwd(working_dir);
+                }
                 let handle = transport.start().await?;
                 Box::new(
                     McpClient::connect(
@@ -240,7 +351,8 @@ impl ExtensionManager {
                             timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
                         ),
                     )
-                    .await?,
+                    .await?
+                    .with_output_validation(output_validation()),
                 )
             }
             _ => unreachable!(),
@@ -263,6 +375,32 @@ impl ExtensionManager {
                 .insert(sanitized_name.clone(), instructions);
         }
 
+        if let Some(capability) = init_result.capabilities.instructions {
+            self.instruction_refreshes.insert(
+                sanitized_name.clone(),
+                InstructionRefresh {
+                    interval: capability.refresh_interval_secs.map(Duration::from_secs),
+                    on_tool_call: capability.refresh_on_tool_call.unwrap_or(false),
+                    last_refreshed: Instant::now(),
+                },
+            );
+        }
+
+        if let Some(capability) = init_result.capabilities.tool_errors {
+            let rules = capability
+                .rules
+                .into_iter()
+                .filter_map(|rule| match serde_json::from_value::<ToolErrorRule>(rule) {
+                    Ok(rule) => Some(rule),
+                    Err(e) => {
+                        warn!("Ignoring a tool error rule of {}: {}", sanitized_name, e);
+                        None
+                    }
+                })
+                .collect();
+            self.tool_error_rules.insert(sanitized_name.clone(), rules);
+        }
+
         if init_result.capabilities.resources.is_some() {
             self.resource_capable_extensions
                 .insert(sanitized_name.clone());
@@ -270,10 +408,34 @@ impl ExtensionManager {
 
         self.clients
             .insert(sanitized_name.clone(), Arc::new(Mutex::new(client)));
+        self.configs.insert(sanitized_name, config);
 
         Ok(())
     }
 
+    /// The rules an extension advertised for classifying errors from its tools
+    pub fn tool_error_rules(&self, name: &str) -> Vec<ToolErrorRule> {
+        self.tool_error_rules
+            .get(&normalize(name.to_string()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The configuration a running extension was started with
+    pub fn get_config(&self, name: &str) -> Option<ExtensionConfig> {
+        self.configs.get(&normalize(name.to_string())).cloned()
+    }
+
+    /// The configurations of every running extension, ordered by name
+    pub fn configs(&self) -> Vec<ExtensionConfig> {
+        let mut names: Vec<&String> = self.configs.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| self.configs[name].clone())
+            .collect()
+    }
+
     /// Get extensions info
     pub async fn get_extensions_info(&self) -> Vec<ExtensionInfo> {
         self.clients
@@ -291,11 +453,60 @@ impl ExtensionManager {
         let sanitized_name = normalize(name.to_string());
 
         self.clients.remove(&sanitized_name);
+        self.configs.remove(&sanitized_name);
         self.instructions.remove(&sanitized_name);
+        self.instruction_refreshes.remove(&sanitized_name);
         self.resource_capable_extensions.remove(&sanitized_name);
+        self.tool_error_rules.remove(&sanitized_name);
         Ok(())
     }
 
+    /// Remove the extensions that tool calls disabled since the last call, returning what
+    /// the user should be told about each
+    pub async fn take_suspended(&mut self) -> Vec<ExtensionDisabledEvent> {
+        let events = std::mem::take(&mut *self.suspended.lock().await);
+        for event in &events {
+            let _ = self.remove_extension(&event.extension).await;
+        }
+        events
+    }
+
+    /// Re-fetch instructions from extensions that marked them as dynamic and are due,
+    /// either because their refresh interval elapsed or because one of their tools was
+    /// among `called_tools`. Returns true if any instructions changed, in which case the
+    /// system prompt should be rebuilt.
+    pub async fn refresh_instructions(&mut self, called_tools: &[String]) -> bool {
+        let now = Instant::now();
+        let mut changed = false;
+
+        for (name, refresh) in self.instruction_refreshes.iter_mut() {
+            let prefix = format!("{}__", name);
+            let tool_called = called_tools.iter().any(|tool| tool.starts_with(&prefix));
+            if !refresh.is_due(now, tool_called) {
+                continue;
+            }
+
+            let Some(client) = self.clients.get(name) else {
+                continue;
+            };
+
+            match client.lock().await.get_instructions().await {
+                Ok(result) => {
+                    refresh.last_refreshed = now;
+                    if self.instructions.get(name) != Some(&result.instructions) {
+                        self.instructions.insert(name.clone(), result.instructions);
+                        changed = true;
+                    }
+                }
+                Err(e) => {
+                    warn!(ext_name = %name, error = %e, "Failed to refresh extension instructions");
+                }
+            }
+        }
+
+        changed
+    }
+
     pub async fn suggest_disable_extensions_prompt(&self) -> Value {
         let enabled_extensions_count = self.clients.len();
 
@@ -634,14 +845,26 @@ impl ExtensionManager {
         let arguments = tool_call.arguments.clone();
         let client = client.clone();
         let notifications_receiver = client.lock().await.subscribe().await;
+        let extension_name = self.configs.get(client_name).map(|config| config.name());
+        let suspended = Arc::clone(&self.suspended);
 
         let fut = async move {
             let client_guard = client.lock().await;
-            client_guard
+            let result = client_guard
                 .call_tool(&tool_name, arguments)
                 .await
                 .map(|call| call.content)
-                .map_err(|e| ToolError::ExecutionError(e.to_string()))
+                .map_err(|e| ToolError::ExecutionError(e.to_string()));
+            drop(client_guard);
+
+            if let Some(name) = extension_name {
+                let error = result.as_ref().err().map(|e| e.to_string());
+                if let Some(event) = record_health(&name, error) {
+                    warn!("{}", event.summary());
+                    suspended.lock().await.push(event);
+                }
+            }
+            result
         };
 
         Ok(ToolCallResult {
@@ -805,8 +1028,8 @@ mod tests {
     use mcp_client::client::Error;
     use mcp_client::client::McpClientTrait;
     use mcp_core::protocol::{
-        CallToolResult, GetPromptResult, InitializeResult, JsonRpcMessage, ListPromptsResult,
-        ListResourcesResult, ListToolsResult, ReadResourceResult,
+        CallToolResult, GetInstructionsResult, GetPromptResult, InitializeResult, JsonRpcMessage,
+        ListPromptsResult, ListResourcesResult, ListToolsResult, ReadResourceResult,
     };
     use serde_json::json;
     use tokio::sync::mpsc;
@@ -842,6 +1065,7 @@ mod tests {
             match name {
                 "tool" | "test__tool" => Ok(CallToolResult {
                     content: vec![],
+                    structured_content: None,
                     is_error: None,
                 }),
                 _ => Err(Error::NotInitialized),
@@ -863,6 +1087,12 @@ mod tests {
             Err(Error::NotInitialized)
         }
 
+        async fn get_instructions(&self) -> Result<GetInstructionsResult, Error> {
+            Ok(GetInstructionsResult {
+                instructions: "refreshed instructions".to_string(),
+            })
+        }
+
         async fn subscribe(&self) -> mpsc::Receiver<JsonRpcMessage> {
             mpsc::channel(1).1
         }
@@ -1013,4 +1243,70 @@ mod tests {
             panic!("Expected ToolError::NotFound");
         }
     }
+
+    #[tokio::test]
+    async fn test_refresh_instructions() {
+        let mut extension_manager = ExtensionManager::new();
+
+        for name in ["on_call", "interval", "static"] {
+            extension_manager.clients.insert(
+                name.to_string(),
+                Arc::new(Mutex::new(Box::new(MockClient {}))),
+            );
+            extension_manager
+                .instructions
+                .insert(name.to_string(), "initial instructions".to_string());
+        }
+
+        extension_manager.instruction_refreshes.insert(
+            "on_call".to_string(),
+            InstructionRefresh {
+                interval: None,
+                on_tool_call: true,
+                last_refreshed: Instant::now(),
+            },
+        );
+        extension_manager.instruction_refreshes.insert(
+            "interval".to_string(),
+            InstructionRefresh {
+                interval: Some(Duration::from_secs(3600)),
+                on_tool_call: false,
+                last_refreshed: Instant::now(),
+            },
+        );
+
+        // Nothing is due without a tool call and before the interval elapses
+        assert!(!extension_manager.refresh_instructions(&[]).await);
+
+        // Calling a tool on another extension does not refresh
+        let called = vec!["static__tool".to_string()];
+        assert!(!extension_manager.refresh_instructions(&called).await);
+
+        let called = vec!["on_call__tool".to_string()];
+        assert!(extension_manager.refresh_instructions(&called).await);
+        assert_eq!(
+            extension_manager.instructions.get("on_call").unwrap(),
+            "refreshed instructions"
+        );
+        assert_eq!(
+            extension_manager.instructions.get("interval").unwrap(),
+            "initial instructions"
+        );
+
+        // Unchanged instructions on a second refresh do not require a prompt rebuild
+        assert!(!extension_manager.refresh_instructions(&called).await);
+    }
+
+    #[test]
+    fn test_instruction_refresh_is_due() {
+        let now = Instant::now();
+        let refresh = InstructionRefresh {
+            interval: Some(Duration::from_secs(60)),
+            on_tool_call: false,
+            last_refreshed: now,
+        };
+
+        assert!(!refresh.is_due(now, true));
+        assert!(refresh.is_due(now + Duration::from_secs(60), false));
+    }
 }
@@ -1,20 +1,37 @@
 mod agent;
+pub mod ask_user;
+pub mod change_summary;
+pub mod citations;
 mod context;
+pub mod context_recovery;
+pub mod dry_run;
+pub mod edit_preview;
 pub mod extension;
 pub mod extension_manager;
+pub mod guardrails;
 mod large_response_handler;
 pub mod platform_tools;
+pub mod prefetch;
 pub mod prompt_manager;
 mod reply_parts;
 mod router_tool_selector;
 mod router_tools;
+pub mod system_prompt;
+pub mod timeouts;
+pub mod tool_cache;
+pub mod tool_compression;
+pub mod tool_errors;
 mod tool_execution;
 mod tool_router_index_manager;
+pub mod tool_streaming;
 pub(crate) mod tool_vectordb;
 mod types;
+pub mod verification;
+pub mod workspace;
 
 pub use agent::{Agent, AgentEvent};
 pub use extension::ExtensionConfig;
 pub use extension_manager::ExtensionManager;
 pub use prompt_manager::PromptManager;
+pub use system_prompt::{PromptBlock, PromptSource, SystemPrompt};
 pub use types::{FrontendTool, SessionConfig};
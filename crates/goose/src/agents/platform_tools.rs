@@ -7,6 +7,8 @@ pub const PLATFORM_LIST_RESOURCES_TOOL_NAME: &str = "platform__list_resources";
 pub const PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME: &str =
     "platform__search_available_extensions";
 pub const PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME: &str = "platform__manage_extensions";
+pub const PLATFORM_SUMMARIZE_CONVERSATION_TOOL_NAME: &str = "platform__summarize_conversation";
+pub const PLATFORM_ASK_USER_TOOL_NAME: &str = "platform__ask_user";
 
 pub fn read_resource_tool() -> Tool {
     Tool::new(
@@ -112,3 +114,63 @@ pub fn manage_extensions_tool() -> Tool {
         }),
     )
 }
+
+pub fn summarize_conversation_tool() -> Tool {
+    Tool::new(
+        PLATFORM_SUMMARIZE_CONVERSATION_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Replace the older part of this conversation with a summary to free up context.
+
+            Use this in long sessions once earlier messages are no longer needed word for word,
+            for example after finishing a subtask. The most recent messages are kept as they are,
+            everything before them is collapsed into one summary message. The original messages
+            are archived with the session, not deleted.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "keep_recent": {"type": "integer", "description": "How many of the most recent messages to keep verbatim, defaults to 6"},
+                "focus": {"type": "string", "description": "Optional details the summary must preserve, such as open tasks or file names"}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Summarize the conversation".to_string()),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn ask_user_tool() -> Tool {
+    Tool::new(
+        PLATFORM_ASK_USER_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Ask the user a clarification question and wait for the answer.
+
+            Use this when the instructions are ambiguous and a wrong guess would waste work or
+            change something the user did not intend, rather than guessing. Ask one specific
+            question at a time. Offer the likely answers as options and give the answer you
+            would go with as the default, which is used when nobody is available to answer.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["question"],
+            "properties": {
+                "question": {"type": "string", "description": "The question to ask"},
+                "options": {"type": "array", "items": {"type": "string"}, "description": "Optional answers for the user to pick from"},
+                "default": {"type": "string", "description": "The answer to go with if nobody answers"}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Ask the user".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
@@ -0,0 +1,203 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use mcp_core::{Tool, ToolCall};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::config::Config;
+use crate::message::Message;
+use crate::providers::base::Provider;
+
+use super::extension_manager::ExtensionManager;
+use super::tool_cache::ToolResultCache;
+use crate::agents::Agent;
+
+/// Config key turning on speculative prefetching. Off by default, since it spends tokens on
+/// predictions that may not pan out. Meant for interactive sessions, where the user reads
+/// each reply before answering.
+pub const SPECULATIVE_PREFETCH_KEY: &str = "GOOSE_SPECULATIVE_PREFETCH";
+
+/// The most calls run ahead of time after one reply
+const MAX_PREFETCH_CALLS: usize = 3;
+
+/// How long a prefetched result waits for the user's next message
+const PREFETCH_TTL: Duration = Duration::from_secs(300);
+
+const PREFETCH_PROMPT: &str = "Your last reply has been shown to the user, who is now reading \
+    it. Predict the read-only tool calls you are most likely to make first once they answer, \
+    such as the one you said you would run next, and make them now. Only call tools you are \
+    confident about, no more than three. If there is nothing worth looking up, reply without \
+    calling any tools.";
+
+pub fn prefetch_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>(SPECULATIVE_PREFETCH_KEY)
+        .unwrap_or(false)
+}
+
+/// The calls of a prediction worth running, those to cacheable tools and at most
+/// `MAX_PREFETCH_CALLS` of them
+fn prefetchable_calls(prediction: &Message, cache: &ToolResultCache) -> Vec<ToolCall> {
+    prediction
+        .content
+        .iter()
+        .filter_map(|content| content.as_tool_request())
+        .filter_map(|request| request.tool_call.clone().ok())
+        .filter(|call| cache.is_cacheable(&call.name))
+        .take(MAX_PREFETCH_CALLS)
+        .collect()
+}
+
+/// Ask the provider for the read-only calls that will likely follow, run them and keep their
+/// results in the tool cache, where an identical call from the model picks them up
+async fn prefetch(
+    provider: Arc<dyn Provider>,
+    system_prompt: String,
+    mut messages: Vec<Message>,
+    tools: Vec<Tool>,
+    extension_manager: Arc<Mutex<ExtensionManager>>,
+    cache: Arc<Mutex<ToolResultCache>>,
+    session_key: String,
+) {
+    messages.push(Message::user().with_text(PREFETCH_PROMPT));
+    let prediction = match provider.complete(&system_prompt, &messages, &tools).await {
+        Ok((prediction, usage)) => {
+            debug!(
+                "Prefetch prediction used {:?} tokens",
+                usage.usage.total_tokens
+            );
+            prediction
+        }
+        Err(e) => {
+            debug!("Skipping prefetch, the prediction failed: {}", e);
+            return;
+        }
+    };
+
+    let calls = prefetchable_calls(&prediction, &*cache.lock().await);
+    for call in calls {
+        let result = extension_manager
+            .lock()
+            .await
+            .dispatch_tool_call(call.clone())
+            .await;
+        let content = match result {
+            Ok(result) => result.result.await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match content {
+            Ok(content) => {
+                debug!("Prefetched {}", call.name);
+                cache.lock().await.insert_prefetched(
+                    &session_key,
+                    &call.name,
+                    &call.arguments,
+                    content,
+                    PREFETCH_TTL,
+                );
+            }
+            Err(e) => debug!("Prefetching {} failed: {}", call.name, e),
+        }
+    }
+}
+
+impl Agent {
+    /// Stop a prefetch still running from the previous reply, the user has answered
+    pub(super) async fn cancel_prefetch(&self) {
+        if let Some(handle) = self.prefetch.lock().await.take() {
+            handle.abort();
+        }
+    }
+
+    /// Once a reply is done, predict and run the read-only calls likely to come next in the
+    /// background, for the session `session_key`. Results from its previous prediction that
+    /// went unused are dropped first.
+    pub(super) async fn start_prefetch(
+        &self,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[Tool],
+        session_key: &str,
+    ) {
+        self.tool_cache.lock().await.discard_prefetched(session_key);
+        let provider = match self.provider().await {
+            Ok(provider) => provider,
+            Err(_) => return,
+        };
+        let read_only_tools: Vec<Tool> = {
+            let cache = self.tool_cache.lock().await;
+            tools
+                .iter()
+                .filter(|tool| cache.is_cacheable(&tool.name))
+                .cloned()
+                .collect()
+        };
+        if read_only_tools.is_empty() {
+            return;
+        }
+
+        let handle: JoinHandle<()> = tokio::spawn(prefetch(
+            provider,
+            system_prompt.to_string(),
+            messages.to_vec(),
+            read_only_tools,
+            Arc::clone(&self.extension_manager),
+            Arc::clone(&self.tool_cache),
+            session_key.to_string(),
+        ));
+        if let Some(previous) = self.prefetch.lock().await.replace(handle) {
+            previous.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::tool::ToolAnnotations;
+    use serde_json::json;
+
+    fn tool(name: &str, read_only: bool, idempotent: bool) -> Tool {
+        Tool::new(
+            name,
+            "",
+            json!({"type": "object"}),
+            Some(ToolAnnotations {
+                title: None,
+                read_only_hint: read_only,
+                destructive_hint: false,
+                idempotent_hint: idempotent,
+                open_world_hint: false,
+            }),
+        )
+    }
+
+    #[test]
+    fn test_prefetchable_calls() {
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        // A screenshot reads but differs every time, so it isn't prefetched either
+        cache.set_tools(&[
+            tool("view", true, true),
+            tool("shell", false, false),
+            tool("screenshot", true, false),
+        ]);
+
+        let mut prediction = Message::assistant()
+            .with_text("I'll look at the tests next")
+            .with_tool_request("1", Ok(ToolCall::new("shell", json!({"command": "rm x"}))))
+            .with_tool_request("2", Ok(ToolCall::new("screenshot", json!({}))));
+        for i in 0..5 {
+            prediction = prediction.with_tool_request(
+                format!("v{}", i),
+                Ok(ToolCall::new("view", json!({"path": format!("f{}", i)}))),
+            );
+        }
+
+        let calls = prefetchable_calls(&prediction, &cache);
+        assert_eq!(calls.len(), MAX_PREFETCH_CALLS);
+        assert!(calls.iter().all(|call| call.name == "view"));
+        assert_eq!(calls[0].arguments, json!({"path": "f0"}));
+    }
+}
@@ -5,6 +5,7 @@ use std::collections::HashMap;
 use crate::agents::extension::ExtensionInfo;
 use crate::agents::router_tool_selector::RouterToolSelectionStrategy;
 use crate::agents::router_tools::vector_search_tool_prompt;
+use crate::agents::system_prompt::{PromptSource, SystemPrompt};
 use crate::providers::base::get_current_model;
 use crate::{config::Config, prompt_template};
 
@@ -59,7 +60,7 @@ impl PromptManager {
         "system.md"
     }
 
-    /// Build the final system prompt
+    /// Build the system prompt from the base template, the added instructions and the mode
     ///
     /// * `extensions_info` – extension information for each extension/MCP
     /// * `frontend_instructions` – instructions for the "frontend" tool
@@ -70,7 +71,7 @@ impl PromptManager {
         suggest_disable_extensions_prompt: Value,
         model_name: Option<&str>,
         tool_selection_strategy: Option<RouterToolSelectionStrategy>,
-    ) -> String {
+    ) -> SystemPrompt {
         let mut context: HashMap<&str, Value> = HashMap::new();
         let mut extensions_info = extensions_info.clone();
 
@@ -130,28 +131,29 @@ impl PromptManager {
                 .expect("Prompt should render")
         };
 
-        let mut system_prompt_extras = self.system_prompt_extras.clone();
-        let config = Config::global();
-        let goose_mode = config.get_param("GOOSE_MODE").unwrap_or("auto".to_string());
-        if goose_mode == "chat" {
-            system_prompt_extras.push(
-                "Right now you are in the chat only mode, no access to any tool use and system."
-                    .to_string(),
+        let mut system_prompt =
+            SystemPrompt::new().with_block(PromptSource::Base, base_prompt, true);
+        if !self.system_prompt_extras.is_empty() {
+            system_prompt = system_prompt.with_block(
+                PromptSource::UserOverride,
+                format!(
+                    "# Additional Instructions:\n\n{}",
+                    self.system_prompt_extras.join("\n\n")
+                ),
+                true,
             );
-        } else {
-            system_prompt_extras
-                .push("Right now you are *NOT* in the chat only mode and have access to tool use and system.".to_string());
         }
 
-        if system_prompt_extras.is_empty() {
-            base_prompt
+        // The mode is fixed for the session, so this block stays cacheable
+        let goose_mode = Config::global()
+            .get_param("GOOSE_MODE")
+            .unwrap_or("auto".to_string());
+        let mode = if goose_mode == "chat" {
+            "Right now you are in the chat only mode, no access to any tool use and system."
         } else {
-            format!(
-                "{}\n\n# Additional Instructions:\n\n{}",
-                base_prompt,
-                system_prompt_extras.join("\n\n")
-            )
-        }
+            "Right now you are *NOT* in the chat only mode and have access to tool use and system."
+        };
+        system_prompt.with_block(PromptSource::Runtime, mode, true)
     }
 
     /// Get the recipe prompt
@@ -202,6 +204,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_system_prompt_blocks() {
+        let mut manager = PromptManager::new();
+        manager.set_system_prompt_override("You are a pirate.".to_string());
+        manager.add_system_prompt_extra("Prefer tabs.".to_string());
+        let prompt = manager.build_system_prompt(vec![], None, Value::Null, None, None);
+
+        let sources: Vec<PromptSource> = prompt.blocks.iter().map(|block| block.source).collect();
+        assert_eq!(
+            sources,
+            vec![
+                PromptSource::Base,
+                PromptSource::UserOverride,
+                PromptSource::Runtime
+            ]
+        );
+        let text = prompt.render();
+        assert!(text.starts_with("You are a pirate.\n\n# Additional Instructions:\n\nPrefer tabs."));
+        assert!(prompt.blocks[2].text.contains("chat only mode"));
+    }
+
     #[test]
     fn test_model_prompt_map_none() {
         // should return system.md for unrecognized/unsupported model names
@@ -1,12 +1,15 @@
 use anyhow::Result;
-use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Instant;
 
 use crate::agents::router_tool_selector::RouterToolSelectionStrategy;
+use crate::config::permission::AnnotationPolicy;
 use crate::config::Config;
 use crate::message::{Message, MessageContent, ToolRequest};
-use crate::providers::base::{Provider, ProviderUsage};
+use crate::permission::permission_judge::ToolAnnotationCategories;
+use crate::providers::base::{Provider, ProviderUsage, ResponseTiming};
 use crate::providers::errors::ProviderError;
+use crate::providers::schema::{lower_tools, strip_optional_nulls};
 use crate::providers::toolshim::{
     augment_message_with_tool_calls, convert_tool_messages_to_text,
     modify_system_prompt_for_tool_json, OllamaInterpreter,
@@ -40,29 +43,41 @@ impl Agent {
             }
             _ => self.list_tools(None).await,
         };
+        self.tool_cache.lock().await.set_tools(&tools);
+
         // Add frontend tools
         let frontend_tools = self.frontend_tools.lock().await;
         for frontend_tool in frontend_tools.values() {
             tools.push(frontend_tool.tool.clone());
         }
 
+        let provider = self.provider().await?;
+
+        // Shorten verbose tool descriptions if configured
+        let mut compressor = self.tool_compressor.lock().await;
+        if compressor.is_enabled() {
+            tools = compressor.compress(tools, &provider).await;
+        }
+        drop(compressor);
+
         // Prepare system prompt
         let extension_manager = self.extension_manager.lock().await;
         let extensions_info = extension_manager.get_extensions_info().await;
 
         // Get model name from provider
-        let provider = self.provider().await?;
         let model_config = provider.get_model_config();
         let model_name = &model_config.model_name;
 
         let prompt_manager = self.prompt_manager.lock().await;
-        let mut system_prompt = prompt_manager.build_system_prompt(
-            extensions_info,
-            self.frontend_instructions.lock().await.clone(),
-            extension_manager.suggest_disable_extensions_prompt().await,
-            Some(model_name),
-            tool_selection_strategy,
-        );
+        let mut system_prompt = prompt_manager
+            .build_system_prompt(
+                extensions_info,
+                self.frontend_instructions.lock().await.clone(),
+                extension_manager.suggest_disable_extensions_prompt().await,
+                Some(model_name),
+                tool_selection_strategy,
+            )
+            .render();
 
         // Handle toolshim if enabled
         let mut toolshim_tools = vec![];
@@ -78,26 +93,10 @@ impl Agent {
         Ok((tools, toolshim_tools, system_prompt))
     }
 
-    /// Categorize tools based on their annotations
-    /// Returns:
-    /// - read_only_tools: Tools with read-only annotations
-    /// - non_read_tools: Tools without read-only annotations
-    pub(crate) fn categorize_tools_by_annotation(
-        tools: &[Tool],
-    ) -> (HashSet<String>, HashSet<String>) {
-        tools
-            .iter()
-            .fold((HashSet::new(), HashSet::new()), |mut acc, tool| {
-                match &tool.annotations {
-                    Some(annotations) if annotations.read_only_hint => {
-                        acc.0.insert(tool.name.clone());
-                    }
-                    _ => {
-                        acc.1.insert(tool.name.clone());
-                    }
-                }
-                acc
-            })
+    /// Categorize tools based on their annotations, applying the annotation
+    /// policy configured for the extension that owns each tool
+    pub(crate) fn categorize_tools_by_annotation(tools: &[Tool]) -> ToolAnnotationCategories {
+        ToolAnnotationCategories::from_tools(tools, AnnotationPolicy::for_extension)
     }
 
     /// Generate a response from the LLM provider
@@ -118,11 +117,29 @@ impl Agent {
             messages.to_vec()
         };
 
+        // Rewrite tool schemas into what the provider accepts
+        let schema_capabilities = provider.schema_capabilities();
+        let provider_tools = lower_tools(tools, &schema_capabilities);
+
         // Call the provider to get a response
-        let (mut response, usage) = provider
-            .complete(system_prompt, &messages_for_provider, tools)
+        let start = Instant::now();
+        let (mut response, mut usage) = provider
+            .complete(system_prompt, &messages_for_provider, &provider_tools)
             .await?;
 
+        if schema_capabilities.strict {
+            strip_optional_nulls(&mut response, tools);
+        }
+
+        // Providers that stream report their own timing, including time to first token
+        if usage.timing.is_none() {
+            usage.timing = Some(ResponseTiming::new(
+                None,
+                start.elapsed(),
+                usage.usage.output_tokens,
+            ));
+        }
+
         // Store the model information in the global store
         crate::providers::base::set_current_model(&usage.model);
 
@@ -227,6 +244,15 @@ impl Agent {
         metadata.output_tokens = usage.usage.output_tokens;
 
         metadata.message_count = messages_length + 1;
+        metadata.last_response_timing = usage.timing;
+        metadata.message_usage.push(session::storage::MessageUsage {
+            message_index: messages_length,
+            model: usage.model.clone(),
+            input_tokens: usage.usage.input_tokens,
+            output_tokens: usage.usage.output_tokens,
+            cost_usd: usage.cost_usd,
+            saved_usd: usage.saved_usd,
+        });
 
         let accumulate = |a: Option<i32>, b: Option<i32>| -> Option<i32> {
             match (a, b) {
@@ -247,4 +273,16 @@ impl Agent {
 
         Ok(())
     }
+
+    /// Save the conversation so far, so a run that stopped waiting on the user can be resumed
+    /// from where it left off
+    pub(crate) async fn checkpoint_session(
+        session_config: &crate::agents::types::SessionConfig,
+        messages: &[Message],
+    ) -> Result<()> {
+        let session_file_path = session::storage::get_path(session_config.id.clone());
+        let metadata = session::storage::read_metadata(&session_file_path)?;
+        session::storage::save_messages_with_metadata(&session_file_path, &metadata, messages)?;
+        Ok(())
+    }
 }
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+/// Where a block of the system prompt comes from. Blocks are rendered in this order, so the
+/// ones that rarely change come first and can be cached as a prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PromptSource {
+    /// The base instructions, rendered from the system template or its override, with the
+    /// instructions of the active extensions
+    Base,
+    /// Instructions added to the agent, such as hints files and recipe instructions
+    UserOverride,
+    /// Details of the current run, such as whether tools are available
+    Runtime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptBlock {
+    pub source: PromptSource,
+    pub text: String,
+    /// Whether providers that support prompt caching may cache the prompt up to this block
+    pub cache: bool,
+}
+
+/// A system prompt composed of ordered blocks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SystemPrompt {
+    pub blocks: Vec<PromptBlock>,
+}
+
+impl SystemPrompt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a block after the other blocks of the same source. Empty blocks are dropped.
+    pub fn with_block(
+        mut self,
+        source: PromptSource,
+        text: impl Into<String>,
+        cache: bool,
+    ) -> Self {
+        let text = text.into();
+        if text.trim().is_empty() {
+            return self;
+        }
+        let index = self.blocks.partition_point(|block| block.source <= source);
+        self.blocks.insert(
+            index,
+            PromptBlock {
+                source,
+                text: text.trim().to_string(),
+                cache,
+            },
+        );
+        self
+    }
+
+    /// The number of leading blocks that can be cached, up to the first block that can't
+    pub fn cached_blocks(&self) -> usize {
+        self.blocks
+            .iter()
+            .position(|block| !block.cache)
+            .unwrap_or(self.blocks.len())
+    }
+
+    pub fn render(&self) -> String {
+        self.blocks
+            .iter()
+            .map(|block| block.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_prompt_block_order() {
+        let prompt = SystemPrompt::new()
+            .with_block(PromptSource::Runtime, "runtime", false)
+            .with_block(PromptSource::UserOverride, "second", true)
+            .with_block(PromptSource::Base, "base", true)
+            .with_block(PromptSource::UserOverride, "third", true)
+            .with_block(PromptSource::UserOverride, "  ", true);
+        let texts: Vec<&str> = prompt.blocks.iter().map(|b| b.text.as_str()).collect();
+        assert_eq!(texts, ["base", "second", "third", "runtime"]);
+        assert_eq!(prompt.cached_blocks(), 3);
+        assert_eq!(prompt.render(), "base\n\nsecond\n\nthird\n\nruntime");
+        assert_eq!(SystemPrompt::new().cached_blocks(), 0);
+    }
+}
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+
+/// Config key holding how many seconds to wait for the user to approve a tool call
+pub const APPROVAL_TIMEOUT_KEY: &str = "GOOSE_APPROVAL_TIMEOUT";
+/// Config key holding how many seconds to wait for the client to return a frontend tool result
+pub const INACTIVITY_TIMEOUT_KEY: &str = "GOOSE_INACTIVITY_TIMEOUT";
+/// Config key holding what to do when either timeout is exceeded, `deny` or `park`
+pub const TIMEOUT_ACTION_KEY: &str = "GOOSE_TIMEOUT_ACTION";
+
+pub const APPROVAL_TIMED_OUT_RESPONSE: &str = "The user did not respond to the request to run \
+    this tool in time, so it was not run. Do not call it again unless the user asks you to.";
+
+pub const PARKED_RESPONSE: &str = "The user did not respond to the request to run this tool in \
+    time, so it was not run and the session was paused. When the user returns, ask whether to \
+    run it now.";
+
+pub const FRONTEND_TOOL_TIMED_OUT_RESPONSE: &str = "The client did not return a result for this \
+    tool in time.";
+
+/// What to do with a run that has been waiting on the user for too long
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeoutAction {
+    /// Treat the request as declined and let the agent carry on
+    #[default]
+    Deny,
+    /// Stop the run after checkpointing it, so it can be resumed with a new message
+    Park,
+}
+
+/// What a run was waiting for when it timed out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutKind {
+    Approval,
+    FrontendTool,
+}
+
+/// Emitted when a run stops waiting on the user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TimeoutEvent {
+    pub kind: TimeoutKind,
+    pub request_id: String,
+    pub tool_name: String,
+    pub waited_secs: u64,
+    pub action: TimeoutAction,
+}
+
+/// Limits on how long a run waits for the user, unset limits wait forever
+#[derive(Debug, Clone, Default)]
+pub struct TimeoutConfig {
+    pub approval: Option<Duration>,
+    pub inactivity: Option<Duration>,
+    pub action: TimeoutAction,
+}
+
+impl TimeoutConfig {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let secs = |key| {
+            config
+                .get_param::<u64>(key)
+                .ok()
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+        };
+        Self {
+            approval: secs(APPROVAL_TIMEOUT_KEY),
+            inactivity: secs(INACTIVITY_TIMEOUT_KEY),
+            action: config.get_param(TIMEOUT_ACTION_KEY).unwrap_or_default(),
+        }
+    }
+
+    /// The response recorded for a tool call the user did not approve in time
+    pub fn approval_response(&self) -> &'static str {
+        match self.action {
+            TimeoutAction::Deny => APPROVAL_TIMED_OUT_RESPONSE,
+            TimeoutAction::Park => PARKED_RESPONSE,
+        }
+    }
+}
+
+/// Why waiting for a reply on a channel ended without one
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum WaitError {
+    TimedOut,
+    Closed,
+}
+
+/// Wait for the item for `id` on `rx`, skipping items for other requests
+pub(crate) async fn wait_for<T>(
+    rx: &mut mpsc::Receiver<(String, T)>,
+    id: &str,
+    timeout: Option<Duration>,
+) -> Result<T, WaitError> {
+    let wait = async {
+        while let Some((item_id, item)) = rx.recv().await {
+            if item_id == id {
+                return Ok(item);
+            }
+        }
+        Err(WaitError::Closed)
+    };
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, wait)
+            .await
+            .unwrap_or(Err(WaitError::TimedOut)),
+        None => wait.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_for() {
+        let (tx, mut rx) = mpsc::channel(4);
+        tx.send(("other".to_string(), 1)).await.unwrap();
+        tx.send(("mine".to_string(), 2)).await.unwrap();
+        assert_eq!(wait_for(&mut rx, "mine", None).await, Ok(2));
+
+        let timeout = Some(Duration::from_millis(10));
+        assert_eq!(
+            wait_for(&mut rx, "mine", timeout).await,
+            Err(WaitError::TimedOut)
+        );
+
+        drop(tx);
+        assert_eq!(
+            wait_for(&mut rx, "mine", timeout).await,
+            Err(WaitError::Closed)
+        );
+    }
+}
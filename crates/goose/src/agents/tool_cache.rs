@@ -0,0 +1,483 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use mcp_core::role::Role;
+use mcp_core::{Content, Tool};
+use serde_json::Value;
+
+use crate::config::Config;
+
+/// Config key for how long, in seconds, read-only and idempotent tool results are reused.
+/// 0 disables caching.
+pub const TOOL_CACHE_TTL_KEY: &str = "GOOSE_TOOL_CACHE_TTL";
+
+const DEFAULT_TTL_SECS: u64 = 30;
+
+struct CachedResult {
+    content: Vec<Content>,
+    stored_at: Instant,
+    ttl: Duration,
+    paths: Vec<PathBuf>,
+    /// Run ahead of time by the prefetcher rather than because the model asked for it
+    prefetched: bool,
+    /// The user turn a prefetched result was run for, it is dropped once a later one starts
+    for_turn: Option<u64>,
+}
+
+/// Results of read-only tool calls, reused when the model repeats a call with identical
+/// arguments in the same session. Sessions never see each other's results.
+///
+/// Only tools annotated with both `read_only_hint` and `idempotent_hint` are cached, tools such
+/// as screen captures read state that changes on its own. Once any other tool call completes it
+/// invalidates the entries that mention the paths it was given, or every entry when it was given
+/// none, since there is no telling what it changed. That holds across sessions, which share the
+/// files the tools read.
+pub struct ToolResultCache {
+    ttl: Duration,
+    cacheable_tools: HashSet<String>,
+    /// Keyed by session, then by call
+    entries: HashMap<(String, String), CachedResult>,
+    /// Counts the user turns of each session, see `begin_turn`
+    turns: HashMap<String, u64>,
+}
+
+/// String arguments that name files or directories, found under keys such as `path`
+fn path_arguments(arguments: &Value) -> Vec<String> {
+    fn collect(value: &Value, in_path_key: bool, paths: &mut Vec<String>) {
+        match value {
+            Value::String(s) if in_path_key => paths.push(s.clone()),
+            Value::Array(items) => items
+                .iter()
+                .for_each(|item| collect(item, in_path_key, paths)),
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let key = key.to_lowercase();
+                    let is_path_key = ["path", "file", "dir"].iter().any(|k| key.contains(k));
+                    collect(value, is_path_key, paths);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut paths = Vec::new();
+    collect(arguments, false, &mut paths);
+    paths
+}
+
+/// An absolute path without `.`, `..` or symlinks, so the same file named two ways compares
+/// equal. Paths that don't exist yet keep the part after their nearest existing ancestor.
+fn normalize_path(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+    let mut lexical = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                lexical.pop();
+            }
+            other => lexical.push(other),
+        }
+    }
+    let mut missing = Vec::new();
+    let mut existing = lexical.as_path();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return missing
+                .iter()
+                .rev()
+                .fold(canonical, |path, part| path.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return lexical,
+        }
+    }
+}
+
+fn paths_overlap(a: &Path, b: &Path) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+impl ToolResultCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cacheable_tools: HashSet::new(),
+            entries: HashMap::new(),
+            turns: HashMap::new(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        let ttl = Config::global()
+            .get_param(TOOL_CACHE_TTL_KEY)
+            .unwrap_or(DEFAULT_TTL_SECS);
+        Self::new(Duration::from_secs(ttl))
+    }
+
+    /// Remember which of the available tools are read-only and idempotent
+    pub fn set_tools(&mut self, tools: &[Tool]) {
+        self.cacheable_tools = tools
+            .iter()
+            .filter(|tool| {
+                tool.annotations
+                    .as_ref()
+                    .is_some_and(|a| a.read_only_hint && a.idempotent_hint)
+            })
+            .map(|tool| tool.name.clone())
+            .collect();
+    }
+
+    pub fn is_cacheable(&self, tool_name: &str) -> bool {
+        !self.ttl.is_zero() && self.cacheable_tools.contains(tool_name)
+    }
+
+    fn key(session: &str, tool_name: &str, arguments: &Value) -> (String, String) {
+        (session.to_string(), format!("{}:{}", tool_name, arguments))
+    }
+
+    /// A previous result of the same call in `session`, marked as cached so the model knows it
+    /// was not re-run
+    pub fn get(
+        &mut self,
+        session: &str,
+        tool_name: &str,
+        arguments: &Value,
+    ) -> Option<Vec<Content>> {
+        if !self.is_cacheable(tool_name) {
+            return None;
+        }
+        let key = Self::key(session, tool_name, arguments);
+        let entry = self.entries.get_mut(&key)?;
+        let age = entry.stored_at.elapsed();
+        if age > entry.ttl {
+            self.entries.remove(&key);
+            return None;
+        }
+
+        let note = if entry.prefetched {
+            // It was wanted after all, keep it like any other result from now on
+            entry.prefetched = false;
+            format!(
+                "[prefetched] This call was run {}s ago, ahead of time while the user read your \
+                 last reply, and was not run again.",
+                age.as_secs()
+            )
+        } else {
+            format!(
+                "[cached] This is the result of an identical call made {}s ago, the tool was not run again.",
+                age.as_secs()
+            )
+        };
+        let mut content = vec![Content::text(note).with_audience(vec![Role::Assistant])];
+        content.extend(entry.content.iter().cloned());
+        Some(content)
+    }
+
+    pub fn insert(
+        &mut self,
+        session: &str,
+        tool_name: &str,
+        arguments: &Value,
+        content: Vec<Content>,
+    ) {
+        if !self.is_cacheable(tool_name) {
+            return;
+        }
+        self.entries.insert(
+            Self::key(session, tool_name, arguments),
+            CachedResult {
+                content,
+                stored_at: Instant::now(),
+                ttl: self.ttl,
+                paths: path_arguments(arguments)
+                    .iter()
+                    .map(|path| normalize_path(path))
+                    .collect(),
+                prefetched: false,
+                for_turn: None,
+            },
+        );
+    }
+
+    /// Store the result of a call run ahead of time for the next user turn of `session`, kept
+    /// for `ttl` rather than the cache's TTL since the user may take a while to read the reply
+    pub fn insert_prefetched(
+        &mut self,
+        session: &str,
+        tool_name: &str,
+        arguments: &Value,
+        content: Vec<Content>,
+        ttl: Duration,
+    ) {
+        if !self.is_cacheable(tool_name) {
+            return;
+        }
+        let for_turn = self.turns.get(session).copied().unwrap_or_default() + 1;
+        self.entries.insert(
+            Self::key(session, tool_name, arguments),
+            CachedResult {
+                content,
+                stored_at: Instant::now(),
+                ttl,
+                paths: path_arguments(arguments)
+                    .iter()
+                    .map(|path| normalize_path(path))
+                    .collect(),
+                prefetched: true,
+                for_turn: Some(for_turn),
+            },
+        );
+    }
+
+    /// Start a user turn of `session`, dropping the results prefetched for its earlier turns
+    /// whether they were used or not. The files they read may have changed since.
+    pub fn begin_turn(&mut self, session: &str) {
+        let turn = self.turns.entry(session.to_string()).or_default();
+        *turn += 1;
+        let turn = *turn;
+        self.entries.retain(|(key_session, _), entry| {
+            key_session != session || entry.for_turn.is_none_or(|for_turn| for_turn >= turn)
+        });
+    }
+
+    /// Drop the results prefetched for `session` that nothing used, once the conversation went
+    /// another way
+    pub fn discard_prefetched(&mut self, session: &str) {
+        self.entries
+            .retain(|(key_session, _), entry| key_session != session || !entry.prefetched);
+    }
+
+    /// Drop the results of an extension's tools, after its server was restarted
+    pub fn invalidate_extension(&mut self, extension: &str) {
+        let prefix = format!("{}__", extension);
+        self.entries.retain(|(_, key), _| !key.starts_with(&prefix));
+    }
+
+    /// Drop the entries a call to a tool that is not read-only may have made stale, once the
+    /// call has completed
+    pub fn invalidate(&mut self, arguments: &Value) {
+        let written: Vec<PathBuf> = path_arguments(arguments)
+            .iter()
+            .map(|path| normalize_path(path))
+            .collect();
+        if written.is_empty() {
+            self.entries.clear();
+            return;
+        }
+        self.entries.retain(|_, entry| {
+            !entry
+                .paths
+                .iter()
+                .any(|path| written.iter().any(|w| paths_overlap(path, w)))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::tool::ToolAnnotations;
+    use serde_json::json;
+
+    const S: &str = "session";
+
+    fn annotated(name: &str, read_only: bool, idempotent: bool) -> Tool {
+        Tool::new(
+            name,
+            "",
+            json!({"type": "object"}),
+            Some(ToolAnnotations {
+                title: None,
+                read_only_hint: read_only,
+                destructive_hint: false,
+                idempotent_hint: idempotent,
+                open_world_hint: false,
+            }),
+        )
+    }
+
+    fn tool(name: &str, read_only: bool) -> Tool {
+        annotated(name, read_only, read_only)
+    }
+
+    #[test]
+    fn test_cache_hits_and_invalidation() {
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        cache.set_tools(&[tool("view", true), tool("write", false)]);
+
+        let a = json!({"path": "/repo/src/a.rs"});
+        let b = json!({"path": "/repo/docs/b.md"});
+        cache.insert(S, "view", &a, vec![Content::text("a")]);
+        cache.insert(S, "view", &b, vec![Content::text("b")]);
+        cache.insert(S, "write", &a, vec![Content::text("not cached")]);
+
+        let hit = cache.get(S, "view", &a).unwrap();
+        assert!(hit[0].as_text().unwrap().starts_with("[cached]"));
+        assert_eq!(hit[1].as_text(), Some("a"));
+        assert!(cache.get(S, "write", &a).is_none());
+        assert!(cache
+            .get(S, "view", &json!({"path": "/repo/other"}))
+            .is_none());
+
+        // Writing under /repo/src only invalidates results for that part of the tree
+        cache.invalidate(&json!({"path": "/repo/src"}));
+        assert!(cache.get(S, "view", &a).is_none());
+        assert!(cache.get(S, "view", &b).is_some());
+
+        // A write without any path could have changed anything
+        cache.invalidate(&json!({"command": "make clean"}));
+        assert!(cache.get(S, "view", &b).is_none());
+    }
+
+    #[test]
+    fn test_only_idempotent_tools_are_cached() {
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        cache.set_tools(&[annotated("screen_capture", true, false)]);
+        cache.insert(S, "screen_capture", &json!({}), vec![Content::text("a")]);
+        assert!(!cache.is_cacheable("screen_capture"));
+        assert!(cache.get(S, "screen_capture", &json!({})).is_none());
+    }
+
+    #[test]
+    fn test_invalidation_matches_paths_named_differently() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let file = dir.path().join("src").join("a.rs");
+        std::fs::write(&file, "").unwrap();
+
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        cache.set_tools(&[tool("view", true)]);
+        let read = json!({"path": file.to_string_lossy()});
+        cache.insert(S, "view", &read, vec![Content::text("a")]);
+
+        let dotted = dir.path().join("src").join("..").join("src").join("a.rs");
+        cache.invalidate(&json!({"path": dotted.to_string_lossy()}));
+        assert!(cache.get(S, "view", &read).is_none());
+    }
+
+    #[test]
+    fn test_invalidate_extension() {
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        cache.set_tools(&[tool("git__log", true), tool("github__list", true)]);
+        cache.insert(S, "git__log", &json!({}), vec![Content::text("a")]);
+        cache.insert(S, "github__list", &json!({}), vec![Content::text("b")]);
+
+        cache.invalidate_extension("git");
+        assert!(cache.get(S, "git__log", &json!({})).is_none());
+        assert!(cache.get(S, "github__list", &json!({})).is_some());
+    }
+
+    #[test]
+    fn test_cache_disabled_and_expired() {
+        let mut disabled = ToolResultCache::new(Duration::ZERO);
+        disabled.set_tools(&[tool("view", true)]);
+        disabled.insert(S, "view", &json!({}), vec![Content::text("a")]);
+        assert!(disabled.get(S, "view", &json!({})).is_none());
+
+        let mut cache = ToolResultCache::new(Duration::from_millis(1));
+        cache.set_tools(&[tool("view", true)]);
+        cache.insert(S, "view", &json!({}), vec![Content::text("a")]);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.get(S, "view", &json!({})).is_none());
+    }
+
+    #[test]
+    fn test_prefetched_results() {
+        let mut cache = ToolResultCache::new(Duration::from_millis(1));
+        cache.set_tools(&[tool("view", true)]);
+        let (a, b) = (json!({"path": "a"}), json!({"path": "b"}));
+        cache.insert_prefetched(
+            S,
+            "view",
+            &a,
+            vec![Content::text("a")],
+            Duration::from_secs(60),
+        );
+        cache.insert_prefetched(
+            S,
+            "view",
+            &b,
+            vec![Content::text("b")],
+            Duration::from_secs(60),
+        );
+        std::thread::sleep(Duration::from_millis(5));
+
+        // Prefetched results outlive the cache's own TTL
+        let hit = cache.get(S, "view", &a).unwrap();
+        assert!(hit[0].as_text().unwrap().starts_with("[prefetched]"));
+        assert!(cache.get(S, "view", &a).unwrap()[0]
+            .as_text()
+            .unwrap()
+            .starts_with("[cached]"));
+
+        // Only the one that was never used is discarded
+        cache.discard_prefetched(S);
+        assert!(cache.get(S, "view", &b).is_none());
+        assert!(cache.get(S, "view", &a).is_some());
+    }
+
+    #[test]
+    fn test_prefetched_results_last_one_turn() {
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        cache.set_tools(&[tool("view", true)]);
+        let (a, b) = (json!({"path": "a"}), json!({"path": "b"}));
+        cache.begin_turn(S);
+        cache.insert(S, "view", &b, vec![Content::text("b")]);
+        cache.insert_prefetched(
+            S,
+            "view",
+            &a,
+            vec![Content::text("a")],
+            Duration::from_secs(60),
+        );
+
+        // The user answers, the turn the result was prefetched for can use it
+        cache.begin_turn(S);
+        assert!(cache.get(S, "view", &a).is_some());
+
+        // The turn after can't, even though it was used
+        cache.begin_turn(S);
+        assert!(cache.get(S, "view", &a).is_none());
+        assert!(cache.get(S, "view", &b).is_some());
+    }
+
+    #[test]
+    fn test_sessions_keep_their_own_results() {
+        let mut cache = ToolResultCache::new(Duration::from_secs(60));
+        cache.set_tools(&[tool("view", true)]);
+        let (a, b) = (json!({"path": "/repo/a"}), json!({"path": "/repo/b"}));
+        cache.insert("alice", "view", &a, vec![Content::text("a")]);
+        cache.insert_prefetched(
+            "alice",
+            "view",
+            &b,
+            vec![Content::text("b")],
+            Duration::from_secs(60),
+        );
+        assert!(cache.get("bob", "view", &a).is_none());
+
+        // Another session's turns leave the prefetched result alone
+        cache.begin_turn("bob");
+        cache.begin_turn("bob");
+        cache.discard_prefetched("bob");
+        cache.begin_turn("alice");
+        assert!(cache.get("alice", "view", &b).is_some());
+
+        // Writes invalidate every session's results, they share the files
+        cache.insert("bob", "view", &a, vec![Content::text("a")]);
+        cache.invalidate(&json!({"path": "/repo/a"}));
+        assert!(cache.get("alice", "view", &a).is_none());
+        assert!(cache.get("bob", "view", &a).is_none());
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use mcp_core::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::message::Message;
+use crate::providers::base::Provider;
+use crate::token_counter::TokenCounter;
+
+/// Config key for how tool descriptions are compressed: `off` (default), `rules` or `llm`
+pub const TOOL_COMPRESSION_KEY: &str = "GOOSE_TOOL_COMPRESSION";
+/// Config key for the longest tool description, in characters, sent to the model
+pub const TOOL_DESCRIPTION_MAX_CHARS_KEY: &str = "GOOSE_TOOL_DESCRIPTION_MAX_CHARS";
+
+const DEFAULT_MAX_CHARS: usize = 400;
+const PARAM_DESCRIPTION_MAX_CHARS: usize = 160;
+
+const SUMMARIZE_PROMPT: &str = "You shorten descriptions of tools that are given to an AI \
+    assistant. Rewrite the description you are given so it is as short as possible while keeping \
+    what the tool does, when to use it and every constraint on how to call it. Drop examples, \
+    repetition and formatting. Reply with the new description only.";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionMode {
+    #[default]
+    Off,
+    /// Strip examples and whitespace, then truncate at a sentence boundary
+    Rules,
+    /// Ask the model to summarize long descriptions, falling back to the rules if that fails
+    Llm,
+}
+
+/// Token counts of the tools in the most recent request, before and after compression
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionStats {
+    pub tools: usize,
+    pub tools_compressed: usize,
+    pub original_tokens: usize,
+    pub compressed_tokens: usize,
+}
+
+impl CompressionStats {
+    pub fn tokens_saved(&self) -> usize {
+        self.original_tokens.saturating_sub(self.compressed_tokens)
+    }
+}
+
+struct CompressedTool {
+    description: String,
+    input_schema: Value,
+    original_tokens: usize,
+    compressed_tokens: usize,
+}
+
+/// Shortens tool descriptions before they are sent to the provider.
+///
+/// Results are cached by a hash of the tool, so each tool is compressed, and summarized by the
+/// model in `llm` mode, only once while its definition stays the same.
+pub struct ToolCompressor {
+    mode: CompressionMode,
+    max_chars: usize,
+    cache: HashMap<u64, CompressedTool>,
+    last_stats: CompressionStats,
+}
+
+impl ToolCompressor {
+    pub fn new(mode: CompressionMode, max_chars: usize) -> Self {
+        Self {
+            mode,
+            max_chars,
+            cache: HashMap::new(),
+            last_stats: CompressionStats::default(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self::new(
+            config.get_param(TOOL_COMPRESSION_KEY).unwrap_or_default(),
+            config
+                .get_param(TOOL_DESCRIPTION_MAX_CHARS_KEY)
+                .unwrap_or(DEFAULT_MAX_CHARS),
+        )
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.mode != CompressionMode::Off
+    }
+
+    pub fn last_stats(&self) -> CompressionStats {
+        self.last_stats
+    }
+
+    /// Compress the tools and record how many tokens that saved
+    pub async fn compress(&mut self, tools: Vec<Tool>, provider: &Arc<dyn Provider>) -> Vec<Tool> {
+        let mut counter = None;
+        let mut stats = CompressionStats::default();
+        let mut compressed = Vec::with_capacity(tools.len());
+
+        for mut tool in tools {
+            let key = tool_hash(&tool);
+            if !self.cache.contains_key(&key) {
+                let description = self.compress_description(&tool, provider).await;
+                let input_schema = compress_schema(&tool.input_schema);
+                let counter = counter.get_or_insert_with(|| {
+                    TokenCounter::new(provider.get_model_config().tokenizer_name())
+                });
+                let original_tokens = counter.count_tokens_for_tools(&[tool.clone()]);
+                let compressed_tokens = counter.count_tokens_for_tools(&[Tool {
+                    description: description.clone(),
+                    input_schema: input_schema.clone(),
+                    ..tool.clone()
+                }]);
+                self.cache.insert(
+                    key,
+                    CompressedTool {
+                        description,
+                        input_schema,
+                        original_tokens,
+                        compressed_tokens,
+                    },
+                );
+            }
+
+            let entry = &self.cache[&key];
+            stats.tools += 1;
+            stats.original_tokens += entry.original_tokens;
+            stats.compressed_tokens += entry.compressed_tokens;
+            if entry.compressed_tokens < entry.original_tokens {
+                stats.tools_compressed += 1;
+            }
+            tool.description = entry.description.clone();
+            tool.input_schema = entry.input_schema.clone();
+            compressed.push(tool);
+        }
+
+        if stats.tokens_saved() > 0 && stats != self.last_stats {
+            tracing::info!(
+                tools = stats.tools,
+                tools_compressed = stats.tools_compressed,
+                original_tokens = stats.original_tokens,
+                compressed_tokens = stats.compressed_tokens,
+                "compressed tool descriptions"
+            );
+        }
+        self.last_stats = stats;
+        compressed
+    }
+
+    async fn compress_description(&self, tool: &Tool, provider: &Arc<dyn Provider>) -> String {
+        let rules = compress_text(&tool.description, self.max_chars);
+        if self.mode != CompressionMode::Llm || tool.description.chars().count() <= self.max_chars {
+            return rules;
+        }
+
+        let request = format!(
+            "Tool `{}`, shorten to at most {} characters:\n\n{}",
+            tool.name, self.max_chars, tool.description
+        );
+        match provider
+            .complete(SUMMARIZE_PROMPT, &[Message::user().with_text(request)], &[])
+            .await
+        {
+            Ok((response, _)) => {
+                let summary = response.as_concat_text().trim().to_string();
+                if summary.is_empty() || summary.len() >= tool.description.len() {
+                    rules
+                } else {
+                    summary
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to summarize description of {}: {}", tool.name, e);
+                rules
+            }
+        }
+    }
+}
+
+fn tool_hash(tool: &Tool) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    tool.name.hash(&mut hasher);
+    tool.description.hash(&mut hasher);
+    tool.input_schema.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Drop fenced code blocks, blank lines and repeated whitespace, then truncate to `max_chars`,
+/// preferring to cut at the end of a sentence
+pub fn compress_text(text: &str, max_chars: usize) -> String {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if !in_fence && !line.is_empty() {
+            lines.push(line.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+    }
+    let text = if lines.is_empty() {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    } else {
+        lines.join("\n")
+    };
+
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let cut: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    let sentence_end = cut
+        .rmatch_indices(['.', '!', '?'])
+        .map(|(i, _)| i + 1)
+        .find(|&end| end == cut.len() || cut[end..].starts_with(char::is_whitespace));
+    match sentence_end {
+        Some(end) if end >= cut.len() / 2 => cut[..end].to_string(),
+        _ => {
+            let end = cut.rfind(char::is_whitespace).unwrap_or(cut.len());
+            format!("{}…", cut[..end].trim_end())
+        }
+    }
+}
+
+/// Compress the descriptions of the parameters in a JSON schema
+fn compress_schema(schema: &Value) -> Value {
+    match schema {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(text) if key == "description" => {
+                            Value::String(compress_text(text, PARAM_DESCRIPTION_MAX_CHARS))
+                        }
+                        _ => compress_schema(value),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(compress_schema).collect()),
+        _ => schema.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compress_text() {
+        assert_eq!(compress_text("Reads a file.", 100), "Reads a file.");
+
+        let text = "Runs a command.\n\n  Example:\n```\nls   -la\n```\nOutput   is truncated.";
+        assert_eq!(
+            compress_text(text, 100),
+            "Runs a command.\nExample:\nOutput is truncated."
+        );
+
+        // Cut at the last sentence that fits
+        let text = "Edits files in place. Supports view, create and replace commands on any path.";
+        assert_eq!(compress_text(text, 40), "Edits files in place.");
+
+        // Without a sentence boundary cut at a word
+        let text = "Searches the workspace for symbols matching a pattern across every root";
+        assert_eq!(compress_text(text, 30), "Searches the workspace for…");
+    }
+
+    #[test]
+    fn test_compress_schema() {
+        let long = "word ".repeat(100);
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": long},
+                "description": {"type": "string", "description": "Kept as is."}
+            }
+        });
+        let compressed = compress_schema(&schema);
+        let path = compressed["properties"]["path"]["description"]
+            .as_str()
+            .unwrap();
+        assert!(path.chars().count() <= PARAM_DESCRIPTION_MAX_CHARS);
+        // A property named description is a schema, not a description
+        assert_eq!(
+            compressed["properties"]["description"]["description"],
+            "Kept as is."
+        );
+        assert_eq!(compressed["type"], "object");
+    }
+}
@@ -0,0 +1,398 @@
+use mcp_core::{Content, ToolError, ToolResult};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::Config;
+
+/// Config key turning remediation hints on tool errors off when set to false
+pub const TOOL_ERROR_HINTS_KEY: &str = "GOOSE_TOOL_ERROR_HINTS";
+/// Config key holding extra rules, a list of `{pattern, kind, hint, extension, match_output}`.
+/// They are checked before the built-in rules, so they can also override them.
+pub const TOOL_ERROR_RULES_KEY: &str = "GOOSE_TOOL_ERROR_RULES";
+
+/// What went wrong in a failed tool call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolErrorKind {
+    MissingDependency,
+    PermissionDenied,
+    NotFound,
+    Timeout,
+    #[default]
+    Other,
+}
+
+impl ToolErrorKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ToolErrorKind::MissingDependency => "missing dependency",
+            ToolErrorKind::PermissionDenied => "permission denied",
+            ToolErrorKind::NotFound => "not found",
+            ToolErrorKind::Timeout => "timeout",
+            ToolErrorKind::Other => "error",
+        }
+    }
+}
+
+/// A rule matching tool errors, as it is written in the config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolErrorRule {
+    /// Regex matched against the error. `$1`, `$name` and so on in the hint are replaced by
+    /// its capture groups.
+    pub pattern: String,
+    #[serde(default)]
+    pub kind: ToolErrorKind,
+    pub hint: String,
+    /// Only apply to the tools of this extension
+    #[serde(default)]
+    pub extension: Option<String>,
+    /// Also match the output of calls that succeeded, for tools such as the shell that report
+    /// a failed command in their output
+    #[serde(default)]
+    pub match_output: bool,
+}
+
+impl ToolErrorRule {
+    pub fn new(pattern: &str, kind: ToolErrorKind, hint: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            kind,
+            hint: hint.to_string(),
+            extension: None,
+            match_output: false,
+        }
+    }
+
+    pub fn in_output(mut self) -> Self {
+        self.match_output = true;
+        self
+    }
+
+    pub fn for_extension(mut self, extension: &str) -> Self {
+        self.extension = Some(extension.to_string());
+        self
+    }
+}
+
+/// Commands that are often missing, with what to do instead
+const KNOWN_COMMANDS: &[(&str, &str)] = &[
+    ("rg", "install ripgrep, or fall back to `grep -rn`"),
+    ("fd", "install fd, or fall back to `find`"),
+    ("jq", "install jq, or parse the JSON with python3 -c"),
+    ("tree", "install tree, or fall back to `find . -maxdepth 2`"),
+    ("python", "try python3, which is the name on most systems"),
+    ("pip", "try `python3 -m pip`"),
+];
+
+fn builtin_rules() -> Vec<ToolErrorRule> {
+    let mut rules: Vec<ToolErrorRule> = KNOWN_COMMANDS
+        .iter()
+        .map(|(command, hint)| {
+            ToolErrorRule::new(
+                &format!(
+                    r"(?m)(?:\b{0}: command not found|command not found: {0}$|'{0}' is not recognized)",
+                    regex::escape(command)
+                ),
+                ToolErrorKind::MissingDependency,
+                &format!("`{}` is not installed: {}.", command, hint),
+            )
+            .in_output()
+        })
+        .collect();
+    rules.extend([
+        ToolErrorRule::new(
+            r"(?m)(?:\b([\w.+-]+): command not found$|command not found: ([\w.+-]+)$|'([\w.+-]+)' is not recognized as)",
+            ToolErrorKind::MissingDependency,
+            "`$1$2$3` is not installed. Check for an alternative that is, or ask the user whether to install it, instead of retrying.",
+        )
+        .in_output(),
+        ToolErrorRule::new(
+            r"(?i)permission denied|operation not permitted|EACCES|access is denied",
+            ToolErrorKind::PermissionDenied,
+            "Retrying will fail the same way. Check the ownership and permissions with `ls -l`, work in a location you can write to, or ask the user for access. Don't use sudo without asking.",
+        ),
+        ToolErrorRule::new(
+            r"(?i)no such file or directory|ENOENT|cannot find the (?:file|path)|does not exist",
+            ToolErrorKind::NotFound,
+            "Check the path before retrying: list the parent directory or search for the file by name, and pass the absolute path rather than a relative one.",
+        ),
+        ToolErrorRule::new(
+            r"(?i)timed out|timeout|deadline exceeded",
+            ToolErrorKind::Timeout,
+            "Don't repeat the same call unchanged. Narrow it down (fewer files, a smaller query), run long commands in the background, or check whether the service is reachable.",
+        ),
+    ]);
+    rules
+}
+
+#[derive(Clone)]
+struct CompiledRule {
+    rule: ToolErrorRule,
+    regex: Regex,
+    /// The extension that advertised the rule, if it didn't come from the config
+    contributed_by: Option<String>,
+}
+
+/// How a tool error was classified, and what to try next
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Classification {
+    pub kind: ToolErrorKind,
+    pub hint: String,
+}
+
+/// Classifies failed tool calls with a table of rules and adds a remediation hint to the
+/// result, so the model changes course instead of retrying the same call
+#[derive(Clone)]
+pub struct ToolErrorClassifier {
+    rules: Vec<CompiledRule>,
+}
+
+impl Default for ToolErrorClassifier {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl ToolErrorClassifier {
+    /// The configured classifier, or None when hints are turned off
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        if !config
+            .get_param::<bool>(TOOL_ERROR_HINTS_KEY)
+            .unwrap_or(true)
+        {
+            return None;
+        }
+        Some(Self::new(
+            config.get_param(TOOL_ERROR_RULES_KEY).unwrap_or_default(),
+        ))
+    }
+
+    /// A classifier with `rules` checked before the built-in ones
+    pub fn new(rules: Vec<ToolErrorRule>) -> Self {
+        let mut classifier = Self { rules: Vec::new() };
+        classifier.add_rules(builtin_rules());
+        classifier.add_rules(rules);
+        classifier
+    }
+
+    /// Add rules ahead of the existing ones
+    pub fn add_rules(&mut self, rules: Vec<ToolErrorRule>) {
+        self.insert(rules, None);
+    }
+
+    /// Replace the rules an extension advertised, scoping them to the tools of that extension
+    pub fn set_extension_rules(&mut self, extension: &str, rules: Vec<ToolErrorRule>) {
+        self.rules
+            .retain(|compiled| compiled.contributed_by.as_deref() != Some(extension));
+        let rules = rules
+            .into_iter()
+            .map(|rule| rule.for_extension(extension))
+            .collect();
+        self.insert(rules, Some(extension));
+    }
+
+    fn insert(&mut self, rules: Vec<ToolErrorRule>, contributed_by: Option<&str>) {
+        let compiled: Vec<CompiledRule> = rules
+            .into_iter()
+            .filter_map(|rule| match Regex::new(&rule.pattern) {
+                Ok(regex) => Some(CompiledRule {
+                    rule,
+                    regex,
+                    contributed_by: contributed_by.map(str::to_string),
+                }),
+                Err(e) => {
+                    warn!("Ignoring invalid tool error rule {}: {}", rule.pattern, e);
+                    None
+                }
+            })
+            .collect();
+        self.rules.splice(0..0, compiled);
+    }
+
+    /// The first rule matching `text` from a call of `tool_name`
+    pub fn classify(&self, tool_name: &str, text: &str, is_error: bool) -> Option<Classification> {
+        let extension = tool_name.split_once("__").map(|(extension, _)| extension);
+        self.rules.iter().find_map(|compiled| {
+            let rule = &compiled.rule;
+            if !is_error && !rule.match_output {
+                return None;
+            }
+            if rule.extension.is_some() && rule.extension.as_deref() != extension {
+                return None;
+            }
+            let captures = compiled.regex.captures(text)?;
+            let mut hint = String::new();
+            captures.expand(&rule.hint, &mut hint);
+            Some(Classification {
+                kind: rule.kind,
+                hint,
+            })
+        })
+    }
+
+    /// The result with a hint added when it is a classified failure
+    pub fn annotate(
+        &self,
+        tool_name: &str,
+        result: ToolResult<Vec<Content>>,
+    ) -> ToolResult<Vec<Content>> {
+        let hint = |classification: Classification| {
+            format!(
+                "Hint ({}): {}",
+                classification.kind.label(),
+                classification.hint
+            )
+        };
+        match result {
+            Err(error) => {
+                let (message, rebuild): (String, fn(String) -> ToolError) = match error {
+                    ToolError::InvalidParameters(m) => (m, ToolError::InvalidParameters),
+                    ToolError::ExecutionError(m) => (m, ToolError::ExecutionError),
+                    ToolError::SchemaError(m) => (m, ToolError::SchemaError),
+                    ToolError::NotFound(m) => (m, ToolError::NotFound),
+                    error => return Err(error),
+                };
+                match self.classify(tool_name, &message, true) {
+                    Some(classification) => {
+                        Err(rebuild(format!("{}\n\n{}", message, hint(classification))))
+                    }
+                    None => Err(rebuild(message)),
+                }
+            }
+            Ok(mut contents) => {
+                let output: Vec<&str> = contents.iter().filter_map(|c| c.as_text()).collect();
+                if let Some(classification) = self.classify(tool_name, &output.join("\n"), false) {
+                    contents.push(Content::text(hint(classification)));
+                }
+                Ok(contents)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_builtin() {
+        let classifier = ToolErrorClassifier::default();
+
+        let missing = classifier
+            .classify(
+                "developer__shell",
+                "bash: line 1: rg: command not found",
+                false,
+            )
+            .unwrap();
+        assert_eq!(missing.kind, ToolErrorKind::MissingDependency);
+        assert!(missing.hint.contains("grep -rn"));
+
+        let unknown = classifier
+            .classify("developer__shell", "zsh: command not found: kubectl", false)
+            .unwrap();
+        assert!(unknown.hint.starts_with("`kubectl` is not installed"));
+
+        assert_eq!(
+            classifier
+                .classify(
+                    "developer__text_editor",
+                    "Permission denied (os error 13)",
+                    true
+                )
+                .unwrap()
+                .kind,
+            ToolErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            classifier
+                .classify("developer__text_editor", "No such file or directory", true)
+                .unwrap()
+                .kind,
+            ToolErrorKind::NotFound
+        );
+        // Only missing commands are looked for in successful output
+        assert!(classifier
+            .classify(
+                "developer__shell",
+                "grep: a.txt: No such file or directory",
+                false
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn test_custom_rules_and_annotate() {
+        let classifier = ToolErrorClassifier::new(vec![ToolErrorRule::new(
+            r"quota (\w+) exceeded",
+            ToolErrorKind::Other,
+            "Quota $1 is used up, wait for it to reset.",
+        )
+        .for_extension("gdrive")]);
+
+        let result = classifier.annotate(
+            "gdrive__search",
+            Err(ToolError::ExecutionError(
+                "quota daily exceeded".to_string(),
+            )),
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            ToolError::ExecutionError(
+                "quota daily exceeded\n\nHint (error): Quota daily is used up, wait for it to reset."
+                    .to_string()
+            )
+        );
+        // Scoped to another extension
+        assert!(classifier
+            .classify("slack__search", "quota daily exceeded", true)
+            .is_none());
+
+        let output = classifier
+            .annotate(
+                "developer__shell",
+                Ok(vec![Content::text("sh: 1: jq: command not found")]),
+            )
+            .unwrap();
+        assert_eq!(output.len(), 2);
+        assert!(output[1].as_text().unwrap().contains("missing dependency"));
+
+        let clean = classifier
+            .annotate("developer__shell", Ok(vec![Content::text("all good")]))
+            .unwrap();
+        assert_eq!(clean.len(), 1);
+    }
+
+    #[test]
+    fn test_extension_rules() {
+        let mut classifier = ToolErrorClassifier::default();
+        let rule = || ToolErrorRule::new("rate limited", ToolErrorKind::Timeout, "Wait a minute.");
+
+        classifier.set_extension_rules("slack", vec![rule()]);
+        let classification = classifier
+            .classify("slack__post", "rate limited", true)
+            .unwrap();
+        assert_eq!(classification.kind, ToolErrorKind::Timeout);
+        assert!(classifier
+            .classify("github__search", "rate limited", true)
+            .is_none());
+
+        // Setting the rules again replaces them rather than adding to them
+        classifier.set_extension_rules("slack", vec![rule()]);
+        let contributed = |classifier: &ToolErrorClassifier| {
+            classifier
+                .rules
+                .iter()
+                .filter(|compiled| compiled.contributed_by.as_deref() == Some("slack"))
+                .count()
+        };
+        assert_eq!(contributed(&classifier), 1);
+        classifier.set_extension_rules("slack", Vec::new());
+        assert_eq!(contributed(&classifier), 0);
+        assert!(classifier
+            .classify("slack__post", "rate limited", true)
+            .is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::future::Future;
 use std::sync::Arc;
 
@@ -9,9 +10,9 @@ use tokio::sync::Mutex;
 
 use crate::config::permission::PermissionLevel;
 use crate::config::PermissionManager;
-use crate::message::{Message, ToolRequest};
+use crate::message::{Message, MessageContent, ToolConfirmationRequest, ToolRequest};
 use crate::permission::Permission;
-use mcp_core::{Content, ToolResult};
+use mcp_core::{Content, ToolError, ToolResult};
 
 // ToolCallResult combines the result of a tool call with an optional notification stream that
 // can be used to receive notifications from the tool.
@@ -30,12 +31,21 @@ impl From<ToolResult<Vec<Content>>> for ToolCallResult {
 }
 
 use super::agent::{tool_stream, ToolStream};
-use crate::agents::Agent;
+use super::edit_preview::preview_edit;
+use super::timeouts::{
+    wait_for, TimeoutConfig, TimeoutEvent, TimeoutKind, WaitError, FRONTEND_TOOL_TIMED_OUT_RESPONSE,
+};
+use crate::agents::{Agent, AgentEvent};
 
 pub const DECLINED_RESPONSE: &str = "The user has declined to run this tool. \
     DO NOT attempt to call this tool again. \
     If there are no alternative methods to proceed, clearly explain the situation and STOP.";
 
+pub const TOOL_CONFIRMATION_PROMPT: &str = "Goose would like to call the above tool. Allow? (y/n):";
+
+pub const DESTRUCTIVE_TOOL_CONFIRMATION_PROMPT: &str =
+    "Goose would like to call the above tool, which is marked as destructive. Allow? (y/n):";
+
 pub const CHAT_MODE_TOOL_SKIPPED_RESPONSE: &str = "Let the user know the tool call was skipped in Goose chat mode. \
                                         DO NOT apologize for skipping the tool call. DO NOT say sorry. \
                                         Provide an explanation of what the tool call would do, structured as a \
@@ -49,26 +59,50 @@ impl Agent {
     pub(crate) fn handle_approval_tool_requests<'a>(
         &'a self,
         tool_requests: &'a [ToolRequest],
+        destructive_tools: &'a HashSet<String>,
         tool_futures: Arc<Mutex<Vec<(String, ToolStream)>>>,
         permission_manager: &'a mut PermissionManager,
         message_tool_response: Arc<Mutex<Message>>,
-    ) -> BoxStream<'a, anyhow::Result<Message>> {
+        timeouts: &'a TimeoutConfig,
+        session_key: &'a str,
+    ) -> BoxStream<'a, anyhow::Result<AgentEvent>> {
         try_stream! {
+            // Once the user has not answered one request, don't wait on the rest
+            let mut timed_out = false;
             for request in tool_requests {
                 if let Ok(tool_call) = request.tool_call.clone() {
-                    let confirmation = Message::user().with_tool_confirmation_request(
-                        request.id.clone(),
-                        tool_call.name.clone(),
-                        tool_call.arguments.clone(),
-                        Some("Goose would like to call the above tool. Allow? (y/n):".to_string()),
+                    if timed_out {
+                        let mut response = message_tool_response.lock().await;
+                        *response = response.clone().with_tool_response(
+                            request.id.clone(),
+                            Ok(vec![Content::text(timeouts.approval_response())]),
+                        );
+                        continue;
+                    }
+
+                    let destructive = destructive_tools.contains(&tool_call.name);
+                    let prompt = if destructive {
+                        DESTRUCTIVE_TOOL_CONFIRMATION_PROMPT
+                    } else {
+                        TOOL_CONFIRMATION_PROMPT
+                    };
+                    let confirmation = Message::user().with_content(
+                        MessageContent::ToolConfirmationRequest(ToolConfirmationRequest {
+                            id: request.id.clone(),
+                            tool_name: tool_call.name.clone(),
+                            arguments: tool_call.arguments.clone(),
+                            prompt: Some(prompt.to_string()),
+                            diff: preview_edit(&tool_call),
+                            destructive,
+                        }),
                     );
-                    yield confirmation;
+                    yield AgentEvent::Message(confirmation);
 
                     let mut rx = self.confirmation_rx.lock().await;
-                    while let Some((req_id, confirmation)) = rx.recv().await {
-                        if req_id == request.id {
+                    match wait_for(&mut rx, &request.id, timeouts.approval).await {
+                        Ok(confirmation) => {
                             if confirmation.permission == Permission::AllowOnce || confirmation.permission == Permission::AlwaysAllow {
-                                let (req_id, tool_result) = self.dispatch_tool_call(tool_call.clone(), request.id.clone()).await;
+                                let (req_id, tool_result) = self.dispatch_tool_call(tool_call.clone(), request.id.clone(), session_key).await;
                                 let mut futures = tool_futures.lock().await;
 
                                 futures.push((req_id, match tool_result {
@@ -93,8 +127,23 @@ impl Agent {
                                     Ok(vec![Content::text(DECLINED_RESPONSE)]),
                                 );
                             }
-                            break; // Exit the loop once the matching `req_id` is found
                         }
+                        Err(WaitError::TimedOut) => {
+                            timed_out = true;
+                            let mut response = message_tool_response.lock().await;
+                            *response = response.clone().with_tool_response(
+                                request.id.clone(),
+                                Ok(vec![Content::text(timeouts.approval_response())]),
+                            );
+                            yield AgentEvent::Timeout(TimeoutEvent {
+                                kind: TimeoutKind::Approval,
+                                request_id: request.id.clone(),
+                                tool_name: tool_call.name.clone(),
+                                waited_secs: timeouts.approval.unwrap_or_default().as_secs(),
+                                action: timeouts.action,
+                            });
+                        }
+                        Err(WaitError::Closed) => {}
                     }
                 }
             }
@@ -105,20 +154,39 @@ impl Agent {
         &'a self,
         tool_requests: &'a [ToolRequest],
         message_tool_response: Arc<Mutex<Message>>,
-    ) -> BoxStream<'a, anyhow::Result<Message>> {
+        timeouts: &'a TimeoutConfig,
+    ) -> BoxStream<'a, anyhow::Result<AgentEvent>> {
         try_stream! {
             for request in tool_requests {
                 if let Ok(tool_call) = request.tool_call.clone() {
                     if self.is_frontend_tool(&tool_call.name).await {
                         // Send frontend tool request and wait for response
-                        yield Message::assistant().with_frontend_tool_request(
+                        yield AgentEvent::Message(Message::assistant().with_frontend_tool_request(
                             request.id.clone(),
                             Ok(tool_call.clone())
-                        );
+                        ));
 
-                        if let Some((id, result)) = self.tool_result_rx.lock().await.recv().await {
-                            let mut response = message_tool_response.lock().await;
-                            *response = response.clone().with_tool_response(id, result);
+                        let mut rx = self.tool_result_rx.lock().await;
+                        match wait_for(&mut rx, &request.id, timeouts.inactivity).await {
+                            Ok(result) => {
+                                let mut response = message_tool_response.lock().await;
+                                *response = response.clone().with_tool_response(request.id.clone(), result);
+                            }
+                            Err(WaitError::TimedOut) => {
+                                let mut response = message_tool_response.lock().await;
+                                *response = response.clone().with_tool_response(
+                                    request.id.clone(),
+                                    Err(ToolError::ExecutionError(FRONTEND_TOOL_TIMED_OUT_RESPONSE.to_string())),
+                                );
+                                yield AgentEvent::Timeout(TimeoutEvent {
+                                    kind: TimeoutKind::FrontendTool,
+                                    request_id: request.id.clone(),
+                                    tool_name: tool_call.name.clone(),
+                                    waited_secs: timeouts.inactivity.unwrap_or_default().as_secs(),
+                                    action: timeouts.action,
+                                });
+                            }
+                            Err(WaitError::Closed) => {}
                         }
                     }
                 }
@@ -0,0 +1,200 @@
+use mcp_core::protocol::{JsonRpcMessage, JsonRpcNotification};
+use mcp_core::{Content, ToolError, ToolResult};
+use regex::Regex;
+use serde_json::Value;
+
+use crate::config::Config;
+
+/// Config key holding regexes that stop a streaming tool call early when a line of its output
+/// matches one, e.g. `["^FAILED", "error\\[E\\d+\\]"]`. Unset never stops a call early.
+pub const CANCEL_PATTERNS_KEY: &str = "GOOSE_TOOL_CANCEL_PATTERNS";
+
+/// How much of a tool's streamed output is kept to return when it is stopped early
+const PARTIAL_OUTPUT_LIMIT: usize = 16 * 1024;
+
+/// The output carried by a tool's `notifications/message` or `notifications/progress`
+/// notification, if it has any
+pub fn notification_text(message: &JsonRpcMessage) -> Option<String> {
+    let JsonRpcMessage::Notification(JsonRpcNotification {
+        method,
+        params: Some(params),
+        ..
+    }) = message
+    else {
+        return None;
+    };
+    match method.as_str() {
+        "notifications/message" => match params.get("data")? {
+            Value::String(s) => Some(s.clone()),
+            Value::Object(data) => data
+                .get("output")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            _ => None,
+        },
+        "notifications/progress" => params
+            .get("message")
+            .and_then(Value::as_str)
+            .map(|message| format!("{}\n", message)),
+        _ => None,
+    }
+}
+
+/// Patterns marking output that shows a tool call has already failed
+#[derive(Debug, Clone, Default)]
+pub struct EarlyCancel {
+    patterns: Vec<Regex>,
+}
+
+impl EarlyCancel {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let patterns = config
+            .get_param::<Vec<String>>(CANCEL_PATTERNS_KEY)
+            .or_else(|_| {
+                config
+                    .get_param::<String>(CANCEL_PATTERNS_KEY)
+                    .map(|patterns| patterns.lines().map(str::to_string).collect())
+            })
+            .unwrap_or_default();
+        Self::new(&patterns)
+    }
+
+    /// Invalid patterns are skipped with a warning
+    pub fn new(patterns: &[String]) -> Self {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| pattern.trim())
+            .filter(|pattern| !pattern.is_empty())
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    tracing::warn!(
+                        "Ignoring invalid {} '{}': {}",
+                        CANCEL_PATTERNS_KEY,
+                        pattern,
+                        e
+                    );
+                    None
+                }
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.patterns.is_empty()
+    }
+
+    /// Whether a complete line of output matches a pattern
+    pub fn is_failure(&self, line: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(line))
+    }
+}
+
+/// The output a tool call has streamed so far, keeping the most recent part
+#[derive(Debug, Clone, Default)]
+pub struct PartialOutput {
+    text: String,
+    truncated: bool,
+    /// The start of a line whose end hasn't arrived yet
+    pending_line: String,
+}
+
+impl PartialOutput {
+    /// Add a chunk of output, returning the lines it completes. Chunks are split wherever the
+    /// server flushed, so a line is only returned once its newline arrives.
+    pub fn push(&mut self, chunk: &str) -> Vec<String> {
+        self.text.push_str(chunk);
+        if self.text.len() > PARTIAL_OUTPUT_LIMIT {
+            self.text.drain(..Self::cut(&self.text));
+            self.truncated = true;
+        }
+
+        self.pending_line.push_str(chunk);
+        let mut lines = Vec::new();
+        while let Some(end) = self.pending_line.find('\n') {
+            let line: String = self.pending_line.drain(..=end).collect();
+            lines.push(line.trim_end_matches(['\r', '\n']).to_string());
+        }
+        if self.pending_line.len() > PARTIAL_OUTPUT_LIMIT {
+            self.pending_line.drain(..Self::cut(&self.pending_line));
+        }
+        lines
+    }
+
+    /// Where to cut `text` to keep only the last `PARTIAL_OUTPUT_LIMIT` bytes
+    fn cut(text: &str) -> usize {
+        let mut start = text.len() - PARTIAL_OUTPUT_LIMIT;
+        while !text.is_char_boundary(start) {
+            start += 1;
+        }
+        start
+    }
+
+    /// The response recorded for a call stopped because `line` matched a failure pattern
+    pub fn cancelled_result(&self, line: &str) -> ToolResult<Vec<Content>> {
+        let output = if self.truncated {
+            format!("...\n{}", self.text)
+        } else {
+            self.text.clone()
+        };
+        Err(ToolError::ExecutionError(format!(
+            "Stopped waiting for this tool early because its output showed it had failed: {}\n\nOutput so far:\n{}",
+            line.trim(),
+            output
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn notification(method: &str, params: Value) -> JsonRpcMessage {
+        JsonRpcMessage::Notification(JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: Some(params),
+        })
+    }
+
+    #[test]
+    fn test_early_cancel() {
+        let shell = notification(
+            "notifications/message",
+            json!({"data": {"type": "shell", "stream": "stdout", "output": "test a ... FAILED\n"}}),
+        );
+        let progress = notification(
+            "notifications/progress",
+            json!({"progressToken": "prog-1", "progress": 1, "message": "compiling"}),
+        );
+        assert_eq!(
+            notification_text(&shell).as_deref(),
+            Some("test a ... FAILED\n")
+        );
+        assert_eq!(notification_text(&progress).as_deref(), Some("compiling\n"));
+
+        let cancel = EarlyCancel::new(&["FAILED$".to_string(), "(unclosed".to_string()]);
+        assert!(cancel.is_enabled());
+        assert!(cancel.is_failure("test a ... FAILED"));
+        assert!(!cancel.is_failure("test b ... ok"));
+        assert!(!EarlyCancel::new(&[]).is_enabled());
+
+        // A line only counts once it is complete, even when it arrives in pieces
+        let mut partial = PartialOutput::default();
+        assert_eq!(partial.push("compiling\ntest a ... FAI"), vec!["compiling"]);
+        assert_eq!(partial.push("LED"), Vec::<String>::new());
+        assert_eq!(partial.push("\r\ntest b"), vec!["test a ... FAILED"]);
+        let Err(ToolError::ExecutionError(message)) = partial.cancelled_result("test a ... FAILED")
+        else {
+            panic!("expected an execution error");
+        };
+        assert!(message.contains("compiling\ntest a ... FAILED"));
+
+        partial.push(&"x".repeat(PARTIAL_OUTPUT_LIMIT));
+        assert_eq!(partial.text.len(), PARTIAL_OUTPUT_LIMIT);
+        assert!(partial.truncated);
+    }
+}
@@ -0,0 +1,187 @@
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::config::Config;
+
+/// Config key holding the list of verification hooks
+pub const VERIFICATION_HOOKS_KEY: &str = "GOOSE_VERIFICATION_HOOKS";
+/// Config key holding how many times failed verification is fed back to the agent
+pub const VERIFICATION_MAX_RETRIES_KEY: &str = "GOOSE_VERIFICATION_MAX_RETRIES";
+
+const DEFAULT_MAX_RETRIES: usize = 2;
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+/// Only the tail of a failing hook's output is sent back, that is where errors usually are
+const MAX_OUTPUT_CHARS: usize = 4000;
+
+/// A command that checks the agent's work, such as running tests or a linter
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VerificationHook {
+    pub name: String,
+    pub command: String,
+    /// Timeout in seconds, defaults to 300
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+/// A hook that did not pass, with the output to show to the agent
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerificationFailure {
+    pub hook: VerificationHook,
+    pub reason: String,
+    pub output: String,
+}
+
+/// Verification hooks that run whenever the agent finishes a turn in which it used tools
+#[derive(Debug, Clone, Default)]
+pub struct VerificationConfig {
+    pub hooks: Vec<VerificationHook>,
+    pub max_retries: usize,
+}
+
+impl VerificationConfig {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self {
+            hooks: config.get_param(VERIFICATION_HOOKS_KEY).unwrap_or_default(),
+            max_retries: config
+                .get_param(VERIFICATION_MAX_RETRIES_KEY)
+                .unwrap_or(DEFAULT_MAX_RETRIES),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.hooks.is_empty() && self.max_retries > 0
+    }
+}
+
+fn tail(text: &str, max_chars: usize) -> String {
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text.to_string();
+    }
+    let tail: String = text.chars().skip(count - max_chars).collect();
+    format!(
+        "[... {} characters truncated ...]\n{}",
+        count - max_chars,
+        tail
+    )
+}
+
+async fn run_hook(
+    hook: &VerificationHook,
+    working_dir: Option<&Path>,
+) -> Option<VerificationFailure> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut command = Command::new(shell);
+    command
+        .arg(flag)
+        .arg(&hook.command)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    if let Some(dir) = working_dir {
+        command.current_dir(dir);
+    }
+
+    let timeout = Duration::from_secs(hook.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let failure = |reason: String, output: String| {
+        Some(VerificationFailure {
+            hook: hook.clone(),
+            reason,
+            output: tail(&output, MAX_OUTPUT_CHARS),
+        })
+    };
+
+    match tokio::time::timeout(timeout, command.output()).await {
+        Ok(Ok(output)) if output.status.success() => None,
+        Ok(Ok(output)) => {
+            let mut combined = String::from_utf8_lossy(&output.stdout).into_owned();
+            combined.push_str(&String::from_utf8_lossy(&output.stderr));
+            failure(format!("exited with {}", output.status), combined)
+        }
+        Ok(Err(e)) => failure(format!("could not be started: {}", e), String::new()),
+        Err(_) => failure(
+            format!("timed out after {} seconds", timeout.as_secs()),
+            String::new(),
+        ),
+    }
+}
+
+/// Run every hook in order and collect the ones that failed
+pub async fn run_verification_hooks(
+    hooks: &[VerificationHook],
+    working_dir: Option<&Path>,
+) -> Vec<VerificationFailure> {
+    let mut failures = Vec::new();
+    for hook in hooks {
+        tracing::info!(hook = %hook.name, "Running verification hook");
+        if let Some(failure) = run_hook(hook, working_dir).await {
+            tracing::warn!(hook = %hook.name, reason = %failure.reason, "Verification hook failed");
+            failures.push(failure);
+        }
+    }
+    failures
+}
+
+/// Describe the failures so the agent can fix them
+pub fn format_failures(failures: &[VerificationFailure], attempt: usize, max: usize) -> String {
+    let mut text = format!(
+        "Verification failed after you finished (attempt {} of {}). Fix the problems below, then finish again.\n",
+        attempt, max
+    );
+    for failure in failures {
+        text.push_str(&format!(
+            "\n### {}: `{}` {}\n",
+            failure.hook.name, failure.hook.command, failure.reason
+        ));
+        if !failure.output.trim().is_empty() {
+            text.push_str(&format!("```\n{}\n```\n", failure.output.trim_end()));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(name: &str, command: &str) -> VerificationHook {
+        VerificationHook {
+            name: name.to_string(),
+            command: command.to_string(),
+            timeout: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_verification_hooks_reports_failures() {
+        let hooks = vec![
+            hook("passes", "echo ok"),
+            hook("fails", "echo broken && exit 3"),
+        ];
+        let failures = run_verification_hooks(&hooks, None).await;
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].hook.name, "fails");
+        assert!(failures[0].output.contains("broken"));
+
+        let feedback = format_failures(&failures, 1, 2);
+        assert!(feedback.contains("attempt 1 of 2"));
+        assert!(feedback.contains("### fails"));
+    }
+
+    #[test]
+    fn test_tail_keeps_end_of_output() {
+        assert_eq!(tail("short", 10), "short");
+        let truncated = tail("abcdefghij", 4);
+        assert!(truncated.ends_with("ghij"));
+        assert!(truncated.contains("6 characters truncated"));
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Config key that makes isolated workspaces the default for `goose run`
+pub const WORKSPACE_ISOLATION_KEY: &str = "GOOSE_WORKSPACE_ISOLATION";
+
+/// How the scratch copy of the project was created
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsolationBackend {
+    /// A detached `git worktree` of the project's repository
+    GitWorktree,
+    /// A plain copy of the directory, tracked by a throwaway git repository
+    Copy,
+}
+
+/// A scratch copy of a project directory that the agent can modify freely.
+///
+/// The copy starts out identical to the project, including uncommitted and untracked
+/// files, and its state at that point is recorded as a git tree. [`IsolatedWorkspace::diff`]
+/// returns every change made since as a patch that can be reviewed and applied to the real
+/// checkout with `git apply`.
+///
+/// The scratch directory is removed, and the worktree unregistered, when the workspace is
+/// dropped, unless it was kept with [`IsolatedWorkspace::keep`].
+pub struct IsolatedWorkspace {
+    backend: IsolationBackend,
+    /// Repository (or copied directory) root inside the scratch directory
+    root: PathBuf,
+    /// Directory corresponding to the source directory the workspace was created from
+    path: PathBuf,
+    /// Directory the patch applies to: the repository root or the copied directory
+    source_root: PathBuf,
+    /// Tree hash of the initial state, used as the base for the patch
+    baseline: String,
+    /// None once the workspace was kept or cleaned up
+    scratch: Option<TempDir>,
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git, is it installed?")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Stage everything in the workspace and return the resulting tree hash
+fn snapshot(root: &Path) -> Result<String> {
+    git(root, &["add", "-A"])?;
+    Ok(git(root, &["write-tree"])?.trim().to_string())
+}
+
+fn copy_dir(src: &Path, dst: &Path, skip_git: bool) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let name = entry.file_name();
+        if skip_git && name == ".git" {
+            continue;
+        }
+        let target = dst.join(&name);
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target, false)?;
+        } else if file_type.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+impl IsolatedWorkspace {
+    /// Create an isolated copy of `source`, using a git worktree when `source` is inside a
+    /// git repository and a plain copy otherwise
+    pub fn create(source: &Path) -> Result<Self> {
+        let source = source
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", source.display()))?;
+        let scratch = tempfile::Builder::new()
+            .prefix("goose-workspace-")
+            .tempdir()
+            .context("Failed to create scratch directory")?;
+        let root = scratch.path().join("workspace");
+
+        match git(&source, &["rev-parse", "--show-toplevel"]) {
+            Ok(toplevel) => {
+                let repo = PathBuf::from(toplevel.trim());
+                let relative = source.strip_prefix(&repo).unwrap_or(Path::new(""));
+
+                let root_str = root.to_string_lossy();
+                git(&repo, &["worktree", "add", "--detach", &root_str, "HEAD"])?;
+
+                // Bring over uncommitted and untracked changes so the agent sees the
+                // project exactly as the user left it
+                let changes = git(&repo, &["diff", "HEAD", "--binary"])?;
+                if !changes.is_empty() {
+                    let patch = scratch.path().join("uncommitted.patch");
+                    fs::write(&patch, changes)?;
+                    git(&root, &["apply", "--binary", &patch.to_string_lossy()])?;
+                }
+                let untracked = git(&repo, &["ls-files", "--others", "--exclude-standard", "-z"])?;
+                for file in untracked.split('\0').filter(|f| !f.is_empty()) {
+                    let target = root.join(file);
+                    if let Some(parent) = target.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::copy(repo.join(file), target)?;
+                }
+
+                let baseline = snapshot(&root)?;
+                Ok(Self {
+                    backend: IsolationBackend::GitWorktree,
+                    path: root.join(relative),
+                    root,
+                    source_root: repo,
+                    baseline,
+                    scratch: Some(scratch),
+                })
+            }
+            Err(_) => {
+                copy_dir(&source, &root, true)?;
+                git(&root, &["init", "--quiet"])?;
+                let baseline = snapshot(&root)?;
+                Ok(Self {
+                    backend: IsolationBackend::Copy,
+                    path: root.clone(),
+                    root,
+                    source_root: source,
+                    baseline,
+                    scratch: Some(scratch),
+                })
+            }
+        }
+    }
+
+    pub fn backend(&self) -> IsolationBackend {
+        self.backend
+    }
+
+    /// Directory the agent should work in, matching the directory the workspace was created from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Directory the patch from [`IsolatedWorkspace::diff`] should be applied in
+    pub fn source_root(&self) -> &Path {
+        &self.source_root
+    }
+
+    /// Patch of every change made in the workspace, relative to the repository root for
+    /// worktrees and to the copied directory otherwise
+    pub fn diff(&self) -> Result<String> {
+        snapshot(&self.root)?;
+        git(
+            &self.root,
+            &["diff", "--cached", "--binary", &self.baseline],
+        )
+    }
+
+    /// Leave the scratch directory and worktree in place, for a paused run to be picked up
+    /// from later. Returns the directory the agent was working in.
+    pub fn keep(mut self) -> PathBuf {
+        // Without its drop the scratch directory stays on disk
+        std::mem::forget(self.scratch.take());
+        std::mem::take(&mut self.path)
+    }
+
+    /// Remove the scratch directory and unregister the worktree, which dropping the workspace
+    /// also does but without reporting failures
+    pub fn cleanup(mut self) -> Result<()> {
+        self.remove()
+    }
+
+    fn remove(&mut self) -> Result<()> {
+        let Some(scratch) = self.scratch.take() else {
+            return Ok(());
+        };
+        let result = match self.backend {
+            IsolationBackend::GitWorktree => git(
+                &self.source_root,
+                &[
+                    "worktree",
+                    "remove",
+                    "--force",
+                    &self.root.to_string_lossy(),
+                ],
+            )
+            .map(|_| ()),
+            IsolationBackend::Copy => Ok(()),
+        };
+        drop(scratch);
+        result
+    }
+}
+
+impl Drop for IsolatedWorkspace {
+    fn drop(&mut self) {
+        if let Err(e) = self.remove() {
+            tracing::warn!("Failed to remove isolated workspace: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_workspace_produces_patch_without_touching_source() {
+        let source = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("hello.txt"), "hello\n").unwrap();
+
+        let workspace = IsolatedWorkspace::create(source.path()).unwrap();
+        assert_eq!(workspace.backend(), IsolationBackend::Copy);
+        assert_eq!(
+            fs::read_to_string(workspace.path().join("hello.txt")).unwrap(),
+            "hello\n"
+        );
+        assert!(workspace.diff().unwrap().is_empty());
+
+        fs::write(workspace.path().join("hello.txt"), "goodbye\n").unwrap();
+        fs::write(workspace.path().join("new.txt"), "new\n").unwrap();
+
+        let patch = workspace.diff().unwrap();
+        assert!(patch.contains("-hello"));
+        assert!(patch.contains("+goodbye"));
+        assert!(patch.contains("new.txt"));
+        assert_eq!(
+            fs::read_to_string(source.path().join("hello.txt")).unwrap(),
+            "hello\n"
+        );
+        assert!(!source.path().join("new.txt").exists());
+
+        workspace.cleanup().unwrap();
+    }
+
+    #[test]
+    fn test_dropped_worktree_is_removed() {
+        let repo = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| git(repo.path(), args).unwrap();
+        git(&["init", "--quiet"]);
+        fs::write(repo.path().join("hello.txt"), "hello\n").unwrap();
+        git(&["add", "-A"]);
+        git(&[
+            "-c",
+            "user.name=goose",
+            "-c",
+            "user.email=goose@example.com",
+            "commit",
+            "--quiet",
+            "-m",
+            "init",
+        ]);
+        fs::write(repo.path().join("hello.txt"), "uncommitted\n").unwrap();
+
+        let workspace = IsolatedWorkspace::create(repo.path()).unwrap();
+        assert_eq!(workspace.backend(), IsolationBackend::GitWorktree);
+        assert_eq!(
+            fs::read_to_string(workspace.path().join("hello.txt")).unwrap(),
+            "uncommitted\n"
+        );
+        let scratch = workspace.root.parent().unwrap().to_path_buf();
+        assert_eq!(git(&["worktree", "list"]).lines().count(), 2);
+
+        drop(workspace);
+        assert!(!scratch.exists());
+        assert_eq!(git(&["worktree", "list"]).lines().count(), 1);
+    }
+
+    #[test]
+    fn test_kept_workspace_outlives_the_handle() {
+        let source = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("hello.txt"), "hello\n").unwrap();
+
+        let workspace = IsolatedWorkspace::create(source.path()).unwrap();
+        let scratch = workspace.root.clone();
+        let path = workspace.keep();
+        assert!(path.join("hello.txt").exists());
+        fs::remove_dir_all(scratch.parent().unwrap()).unwrap();
+    }
+}
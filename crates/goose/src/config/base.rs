@@ -17,6 +17,9 @@ pub static APP_STRATEGY: Lazy<AppStrategyArgs> = Lazy::new(|| AppStrategyArgs {
     app_name: "goose".to_string(),
 });
 
+/// Directory holding project-level goose files, relative to the working directory
+pub const PROJECT_CONFIG_DIR: &str = ".goose";
+
 const KEYRING_SERVICE: &str = "goose";
 const KEYRING_USERNAME: &str = "secrets";
 
@@ -69,7 +72,8 @@ impl From<keyring::Error> for ConfigError {
 ///
 /// Configuration values are loaded with the following precedence:
 /// 1. Environment variables (exact key match)
-/// 2. Configuration file (~/.config/goose/config.yaml by default)
+/// 2. Project configuration file (.goose/config.yaml in the working directory)
+/// 3. Configuration file (~/.config/goose/config.yaml by default)
 ///
 /// Secrets are loaded with the following precedence:
 /// 1. Environment variables (exact key match)
@@ -105,6 +109,7 @@ impl From<keyring::Error> for ConfigError {
 /// For Goose-specific configuration, consider prefixing with "goose_" to avoid conflicts.
 pub struct Config {
     config_path: PathBuf,
+    project_config_path: Option<PathBuf>,
     secrets: SecretStorage,
 }
 
@@ -137,8 +142,13 @@ impl Default for Config {
                 service: KEYRING_SERVICE.to_string(),
             },
         };
+        let project_config_path = env::current_dir()
+            .ok()
+            .map(|dir| dir.join(PROJECT_CONFIG_DIR).join("config.yaml"));
+
         Config {
             config_path,
+            project_config_path,
             secrets,
         }
     }
@@ -160,6 +170,7 @@ impl Config {
     pub fn new<P: AsRef<Path>>(config_path: P, service: &str) -> Result<Self, ConfigError> {
         Ok(Config {
             config_path: config_path.as_ref().to_path_buf(),
+            project_config_path: None,
             secrets: SecretStorage::Keyring {
                 service: service.to_string(),
             },
@@ -176,12 +187,19 @@ impl Config {
     ) -> Result<Self, ConfigError> {
         Ok(Config {
             config_path: config_path.as_ref().to_path_buf(),
+            project_config_path: None,
             secrets: SecretStorage::File {
                 path: secrets_path.as_ref().to_path_buf(),
             },
         })
     }
 
+    /// Read values from a project config file before the main one, values are never written to it
+    pub fn with_project_config<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.project_config_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
     /// Check if this config already exists
     pub fn exists(&self) -> bool {
         self.config_path.exists()
@@ -199,8 +217,12 @@ impl Config {
 
     // Load current values from the config file
     pub fn load_values(&self) -> Result<HashMap<String, Value>, ConfigError> {
-        if self.config_path.exists() {
-            let file_content = std::fs::read_to_string(&self.config_path)?;
+        Self::load_file(&self.config_path)
+    }
+
+    fn load_file(path: &Path) -> Result<HashMap<String, Value>, ConfigError> {
+        if path.exists() {
+            let file_content = std::fs::read_to_string(path)?;
             // Parse YAML into JSON Value for consistent internal representation
             let yaml_value: serde_yaml::Value = serde_yaml::from_str(&file_content)?;
             let json_value: Value = serde_json::to_value(yaml_value)?;
@@ -297,7 +319,8 @@ impl Config {
     ///
     /// This will attempt to get the value from:
     /// 1. Environment variable with the exact key name
-    /// 2. Configuration file
+    /// 2. Project configuration file
+    /// 3. Configuration file
     ///
     /// The value will be deserialized into the requested type. This works with
     /// both simple types (String, i32, etc.) and complex types that implement
@@ -318,6 +341,13 @@ impl Config {
             return Ok(serde_json::from_value(value)?);
         }
 
+        // Project values override the user's own
+        if let Some(path) = &self.project_config_path {
+            if let Some(value) = Self::load_file(path)?.remove(key) {
+                return Ok(serde_json::from_value(value)?);
+            }
+        }
+
         // Load current values from file
         let values = self.load_values()?;
 
@@ -529,6 +559,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_project_config_overrides_user_config() -> Result<(), ConfigError> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let project_file = NamedTempFile::new().unwrap();
+        std::fs::write(project_file.path(), "project_key: from_project\n")?;
+        let config = Config::new(temp_file.path(), TEST_KEYRING_SERVICE)?
+            .with_project_config(project_file.path());
+
+        config.set_param("project_key", Value::String("from_user".to_string()))?;
+        config.set_param("user_key", Value::String("from_user".to_string()))?;
+
+        let value: String = config.get_param("project_key")?;
+        assert_eq!(value, "from_project");
+        let value: String = config.get_param("user_key")?;
+        assert_eq!(value, "from_user");
+
+        // Writes only ever go to the user's config
+        assert!(!std::fs::read_to_string(project_file.path())?.contains("user_key"));
+        Ok(())
+    }
+
     #[test]
     fn test_complex_type() -> Result<(), ConfigError> {
         #[derive(Deserialize, Debug, PartialEq)]
@@ -0,0 +1,186 @@
+use super::base::{Config, ConfigError};
+use super::extensions::name_to_key;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Config key holding the health of every extension that has failed
+pub const EXTENSION_HEALTH_KEY: &str = "extension_health";
+/// Config key for how many failures in a row disable an extension, 0 never disables one
+pub const EXTENSION_FAILURE_LIMIT_KEY: &str = "GOOSE_EXTENSION_FAILURE_LIMIT";
+
+const DEFAULT_FAILURE_LIMIT: u32 = 3;
+
+/// Failures of an extension since it last worked, counting both launches and tool calls
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExtensionHealth {
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub last_failure_at: Option<DateTime<Utc>>,
+    /// When the extension was disabled for failing too often, cleared by
+    /// `goose extensions enable`
+    #[serde(default)]
+    pub disabled_at: Option<DateTime<Utc>>,
+}
+
+impl ExtensionHealth {
+    /// Count a failure, returning true when it is the one that disables the extension
+    pub fn fail(&mut self, error: &str, now: DateTime<Utc>, limit: u32) -> bool {
+        self.consecutive_failures += 1;
+        self.last_error = Some(error.to_string());
+        self.last_failure_at = Some(now);
+        if limit > 0 && self.consecutive_failures >= limit && self.disabled_at.is_none() {
+            self.disabled_at = Some(now);
+            return true;
+        }
+        false
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
+    }
+}
+
+/// Sent to the user when an extension is disabled for failing too often
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ExtensionDisabledEvent {
+    pub extension: String,
+    pub failures: u32,
+    pub last_error: Option<String>,
+}
+
+impl ExtensionDisabledEvent {
+    /// What happened and how to turn the extension back on
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Extension {} was disabled after failing {} times in a row",
+            self.extension, self.failures
+        );
+        if let Some(error) = &self.last_error {
+            summary.push_str(&format!(", last error: {}", error));
+        }
+        summary.push_str(&format!(
+            ". Run `goose extensions enable {}` once it is fixed.",
+            self.extension
+        ));
+        summary
+    }
+}
+
+/// Extension failure tracking, so that a broken server is turned off instead of stalling
+/// every reply
+pub struct ExtensionHealthManager;
+
+impl ExtensionHealthManager {
+    /// Health of every extension that has any recorded
+    pub fn get_all() -> HashMap<String, ExtensionHealth> {
+        Config::global()
+            .get_param(EXTENSION_HEALTH_KEY)
+            .unwrap_or_default()
+    }
+
+    pub fn get(name: &str) -> Option<ExtensionHealth> {
+        Self::get_all().remove(&name_to_key(name))
+    }
+
+    fn update<F: FnOnce(&mut HashMap<String, ExtensionHealth>)>(f: F) -> Result<(), ConfigError> {
+        let mut all = Self::get_all();
+        f(&mut all);
+        Config::global().set_param(EXTENSION_HEALTH_KEY, serde_json::to_value(all)?)
+    }
+
+    /// How many failures in a row disable an extension
+    pub fn failure_limit() -> u32 {
+        Config::global()
+            .get_param(EXTENSION_FAILURE_LIMIT_KEY)
+            .unwrap_or(DEFAULT_FAILURE_LIMIT)
+    }
+
+    /// Record a failed launch or tool call, returning the event to show the user when this
+    /// failure disabled the extension
+    pub fn record_failure(
+        name: &str,
+        error: &str,
+    ) -> Result<Option<ExtensionDisabledEvent>, ConfigError> {
+        let limit = Self::failure_limit();
+        let mut event = None;
+        Self::update(|all| {
+            let health = all.entry(name_to_key(name)).or_default();
+            if health.fail(error, Utc::now(), limit) {
+                event = Some(ExtensionDisabledEvent {
+                    extension: name.to_string(),
+                    failures: health.consecutive_failures,
+                    last_error: health.last_error.clone(),
+                });
+            }
+        })?;
+        Ok(event)
+    }
+
+    /// Record that an extension worked, resetting its failure count. Only writes the config
+    /// when there is a count to reset.
+    pub fn record_success(name: &str) -> Result<(), ConfigError> {
+        match Self::get(name) {
+            Some(health) if health.consecutive_failures > 0 && !health.is_disabled() => {
+                Self::update(|all| {
+                    all.remove(&name_to_key(name));
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_disabled(name: &str) -> bool {
+        Self::get(name).is_some_and(|health| health.is_disabled())
+    }
+
+    /// Turn a disabled extension back on, forgetting its failures
+    pub fn enable(name: &str) -> Result<(), ConfigError> {
+        Self::update(|all| {
+            all.remove(&name_to_key(name));
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fail_disables_at_limit() {
+        let now = Utc::now();
+        let mut health = ExtensionHealth::default();
+        assert!(!health.fail("connection closed", now, 3));
+        assert!(!health.fail("connection closed", now, 3));
+        assert!(health.fail("timed out", now, 3));
+        assert!(health.is_disabled());
+        assert_eq!(health.last_error.as_deref(), Some("timed out"));
+
+        // Only the failure that crosses the limit reports it
+        assert!(!health.fail("timed out", now, 3));
+        assert_eq!(health.consecutive_failures, 4);
+
+        let mut unlimited = ExtensionHealth::default();
+        for _ in 0..10 {
+            assert!(!unlimited.fail("boom", now, 0));
+        }
+        assert!(!unlimited.is_disabled());
+    }
+
+    #[test]
+    fn test_disabled_summary() {
+        let event = ExtensionDisabledEvent {
+            extension: "github".to_string(),
+            failures: 3,
+            last_error: Some("process exited".to_string()),
+        };
+        let summary = event.summary();
+        assert!(summary.contains("3 times"));
+        assert!(summary.contains("process exited"));
+        assert!(summary.contains("goose extensions enable github"));
+    }
+}
@@ -0,0 +1,295 @@
+use super::base::{Config, ConfigError};
+use crate::providers::providers;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Config key holding the metadata of every stored provider key
+pub const KEY_METADATA_KEY: &str = "key_metadata";
+/// Config key for how many days before expiry to start warning, defaults to 7
+pub const KEY_EXPIRY_WARNING_DAYS_KEY: &str = "GOOSE_KEY_EXPIRY_WARNING_DAYS";
+
+const DEFAULT_EXPIRY_WARNING_DAYS: i64 = 7;
+
+/// Bookkeeping for a stored provider key. The key itself stays in the keyring.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyMetadata {
+    /// When the key was stored, unknown for keys stored before metadata was tracked
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When a session last started with this key
+    #[serde(default)]
+    pub last_used: Option<DateTime<Utc>>,
+    /// When the provider last rejected the key, cleared when the key is rotated
+    #[serde(default)]
+    pub auth_failed_at: Option<DateTime<Utc>>,
+}
+
+/// Something the user should know about a stored key
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyWarning {
+    Expired {
+        name: String,
+        expires_at: DateTime<Utc>,
+    },
+    ExpiringSoon {
+        name: String,
+        expires_at: DateTime<Utc>,
+    },
+    Rejected {
+        name: String,
+        failed_at: DateTime<Utc>,
+    },
+}
+
+impl KeyWarning {
+    pub fn key_name(&self) -> &str {
+        match self {
+            KeyWarning::Expired { name, .. }
+            | KeyWarning::ExpiringSoon { name, .. }
+            | KeyWarning::Rejected { name, .. } => name,
+        }
+    }
+}
+
+impl std::fmt::Display for KeyWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyWarning::Expired { name, expires_at } => {
+                write!(f, "{} expired on {}", name, expires_at.format("%Y-%m-%d"))
+            }
+            KeyWarning::ExpiringSoon { name, expires_at } => {
+                write!(f, "{} expires on {}", name, expires_at.format("%Y-%m-%d"))
+            }
+            KeyWarning::Rejected { name, failed_at } => write!(
+                f,
+                "{} was rejected by the provider on {}",
+                name,
+                failed_at.format("%Y-%m-%d %H:%M")
+            ),
+        }
+    }
+}
+
+impl KeyMetadata {
+    /// The most pressing warning for this key at `now`, if any
+    pub fn warning(
+        &self,
+        name: &str,
+        now: DateTime<Utc>,
+        warn_before: Duration,
+    ) -> Option<KeyWarning> {
+        if let Some(failed_at) = self.auth_failed_at {
+            return Some(KeyWarning::Rejected {
+                name: name.to_string(),
+                failed_at,
+            });
+        }
+        let expires_at = self.expires_at?;
+        if expires_at <= now {
+            Some(KeyWarning::Expired {
+                name: name.to_string(),
+                expires_at,
+            })
+        } else if expires_at - now <= warn_before {
+            Some(KeyWarning::ExpiringSoon {
+                name: name.to_string(),
+                expires_at,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Provider key management: metadata, rotation and expiry warnings
+pub struct KeyManager;
+
+impl KeyManager {
+    /// Metadata for every key that has any recorded
+    pub fn get_all() -> HashMap<String, KeyMetadata> {
+        Config::global()
+            .get_param(KEY_METADATA_KEY)
+            .unwrap_or_default()
+    }
+
+    pub fn get(name: &str) -> Option<KeyMetadata> {
+        Self::get_all().remove(name)
+    }
+
+    /// Whether a provider declares `name` as one of its secret keys
+    pub fn is_provider_key(name: &str) -> bool {
+        providers()
+            .into_iter()
+            .flat_map(|p| p.config_keys)
+            .any(|key| key.secret && key.name == name)
+    }
+
+    /// Apply `f` to the metadata, writing the config only when `f` reports a change
+    fn update<F: FnOnce(&mut HashMap<String, KeyMetadata>) -> bool>(
+        f: F,
+    ) -> Result<(), ConfigError> {
+        let mut all = Self::get_all();
+        if !f(&mut all) {
+            return Ok(());
+        }
+        Config::global().set_param(KEY_METADATA_KEY, serde_json::to_value(all)?)
+    }
+
+    /// Store a new value for a key, starting its metadata afresh
+    pub fn set_key(
+        name: &str,
+        value: String,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), ConfigError> {
+        Config::global().set_secret(name, Value::String(value))?;
+        Self::update(|all| {
+            let metadata = all.entry(name.to_string()).or_default();
+            metadata.created_at = Some(Utc::now());
+            metadata.expires_at = expires_at;
+            metadata.auth_failed_at = None;
+            true
+        })
+    }
+
+    /// Remove a provider key and its metadata
+    pub fn remove_key(name: &str) -> Result<(), ConfigError> {
+        if !Self::is_provider_key(name) {
+            return Err(ConfigError::NotFound(format!(
+                "'{}' is not a provider key",
+                name
+            )));
+        }
+        Config::global().delete_secret(name)?;
+        Self::update(|all| all.remove(name).is_some())
+    }
+
+    /// Record that a session started with these keys. Use is tracked by the day, so the
+    /// config is rewritten at most once a day rather than on every session.
+    pub fn mark_used(names: &[String]) -> Result<(), ConfigError> {
+        let now = Utc::now();
+        Self::update(|all| {
+            let mut changed = false;
+            for name in names {
+                let metadata = all.entry(name.clone()).or_default();
+                if metadata.last_used.map(|used| used.date_naive()) != Some(now.date_naive()) {
+                    metadata.last_used = Some(now);
+                    changed = true;
+                }
+            }
+            changed
+        })
+    }
+
+    /// Record that the provider rejected these keys, keeping the time of the first rejection
+    pub fn mark_auth_failed(names: &[String]) -> Result<(), ConfigError> {
+        let now = Utc::now();
+        Self::update(|all| {
+            let mut changed = false;
+            for name in names {
+                let metadata = all.entry(name.clone()).or_default();
+                if metadata.auth_failed_at.is_none() {
+                    metadata.auth_failed_at = Some(now);
+                    changed = true;
+                }
+            }
+            changed
+        })
+    }
+
+    /// How long before a key expires to start warning about it
+    pub fn expiry_warning_window() -> Duration {
+        Duration::days(
+            Config::global()
+                .get_param(KEY_EXPIRY_WARNING_DAYS_KEY)
+                .unwrap_or(DEFAULT_EXPIRY_WARNING_DAYS),
+        )
+    }
+
+    /// Warnings for the given keys, such as expired keys or keys the provider rejected
+    pub fn warnings(names: &[String]) -> Vec<KeyWarning> {
+        let warn_before = Self::expiry_warning_window();
+        let all = Self::get_all();
+        let now = Utc::now();
+        names
+            .iter()
+            .filter_map(|name| all.get(name)?.warning(name, now, warn_before))
+            .collect()
+    }
+
+    /// Names of the secret keys a provider is configured with, skipping keys that are not set
+    /// or are supplied through the environment
+    pub fn provider_keys(provider_name: &str) -> Vec<String> {
+        let config = Config::global();
+        providers()
+            .into_iter()
+            .filter(|p| p.name == provider_name)
+            .flat_map(|p| p.config_keys)
+            .filter(|key| key.secret && std::env::var(&key.name).is_err())
+            .filter(|key| config.get_secret::<Value>(&key.name).is_ok())
+            .map(|key| key.name)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_metadata_warnings() {
+        let now = Utc::now();
+        let week = Duration::days(7);
+
+        let fresh = KeyMetadata {
+            created_at: Some(now),
+            expires_at: Some(now + Duration::days(30)),
+            ..Default::default()
+        };
+        assert_eq!(fresh.warning("KEY", now, week), None);
+        assert_eq!(KeyMetadata::default().warning("KEY", now, week), None);
+
+        let expiring = KeyMetadata {
+            expires_at: Some(now + Duration::days(3)),
+            ..Default::default()
+        };
+        assert!(matches!(
+            expiring.warning("KEY", now, week),
+            Some(KeyWarning::ExpiringSoon { .. })
+        ));
+
+        let expired = KeyMetadata {
+            expires_at: Some(now - Duration::days(1)),
+            ..Default::default()
+        };
+        assert!(matches!(
+            expired.warning("KEY", now, week),
+            Some(KeyWarning::Expired { .. })
+        ));
+
+        // A rejected key matters more than its expiry date
+        let rejected = KeyMetadata {
+            expires_at: Some(now - Duration::days(1)),
+            auth_failed_at: Some(now),
+            ..Default::default()
+        };
+        let warning = rejected.warning("KEY", now, week).unwrap();
+        assert_eq!(warning.key_name(), "KEY");
+        assert!(warning.to_string().contains("rejected"));
+    }
+
+    #[test]
+    fn test_is_provider_key() {
+        assert!(KeyManager::is_provider_key("OPENAI_API_KEY"));
+        // Declared by the provider, but not a secret
+        assert!(!KeyManager::is_provider_key("OPENAI_HOST"));
+        assert!(!KeyManager::is_provider_key("GITHUB_TOKEN"));
+        assert!(matches!(
+            KeyManager::remove_key("GITHUB_TOKEN"),
+            Err(ConfigError::NotFound(_))
+        ));
+    }
+}
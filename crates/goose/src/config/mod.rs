@@ -1,12 +1,16 @@
 pub mod base;
 mod experiments;
+pub mod extension_health;
 pub mod extensions;
+pub mod key_manager;
 pub mod permission;
 
 pub use crate::agents::ExtensionConfig;
-pub use base::{Config, ConfigError, APP_STRATEGY};
+pub use base::{Config, ConfigError, APP_STRATEGY, PROJECT_CONFIG_DIR};
 pub use experiments::ExperimentManager;
+pub use extension_health::{ExtensionDisabledEvent, ExtensionHealthManager};
 pub use extensions::{ExtensionConfigManager, ExtensionEntry};
+pub use key_manager::KeyManager;
 pub use permission::PermissionManager;
 
 pub use extensions::DEFAULT_DISPLAY_NAME;
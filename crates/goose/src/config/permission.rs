@@ -1,4 +1,4 @@
-use super::APP_STRATEGY;
+use super::{Config, APP_STRATEGY};
 use etcetera::{choose_app_strategy, AppStrategy};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -15,6 +15,38 @@ pub enum PermissionLevel {
     NeverAllow,  // Tool is never allowed to be used
 }
 
+/// How the annotation hints declared by an extension's tools are used for approvals.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationPolicy {
+    /// Ignore the hints, e.g. for extensions that are not trusted to describe their tools
+    Ignore,
+    /// Destructive tools always ask before running and read-only tools are
+    /// auto-approved in smart_approve mode
+    #[default]
+    Enforce,
+    /// Like `Enforce`, but read-only tools are also auto-approved in approve mode
+    Trusted,
+}
+
+/// Config key for the annotation policy applied to all extensions
+pub const ANNOTATION_POLICY_KEY: &str = "GOOSE_TOOL_ANNOTATION_POLICY";
+/// Config key for a map of extension name to annotation policy overriding the default
+pub const ANNOTATION_POLICY_OVERRIDES_KEY: &str = "GOOSE_TOOL_ANNOTATION_OVERRIDES";
+
+impl AnnotationPolicy {
+    /// Resolve the policy for an extension, preferring a per-extension override
+    pub fn for_extension(extension_name: &str) -> Self {
+        let config = Config::global();
+        config
+            .get_param::<HashMap<String, AnnotationPolicy>>(ANNOTATION_POLICY_OVERRIDES_KEY)
+            .ok()
+            .and_then(|overrides| overrides.get(extension_name).copied())
+            .or_else(|| config.get_param(ANNOTATION_POLICY_KEY).ok())
+            .unwrap_or_default()
+    }
+}
+
 /// Struct representing the configuration of permissions, categorized by level.
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
 pub struct PermissionConfig {
@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+
+use mcp_core::role::Role;
+use mcp_core::Tool;
+use serde::Serialize;
+
+use crate::agents::extension::ExtensionInfo;
+use crate::message::{Message, MessageContent};
+use crate::token_counter::TokenCounter;
+
+use super::get_messages_token_counts;
+
+/// What a part of the context window holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentKind {
+    /// The base instructions, hints and other additions to the system prompt
+    SystemPrompt,
+    /// The instructions of one extension, part of the system prompt
+    ExtensionInstructions,
+    /// The schemas of the tools of one extension
+    Tools,
+    /// The conversation before the latest user message
+    History,
+    /// The latest user message and everything after it
+    LatestTurn,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextSegment {
+    pub kind: SegmentKind,
+    /// The extension the segment belongs to, or a description of it
+    pub label: String,
+    pub tokens: usize,
+    /// Whether the segment is in the prefix that providers with prompt caching, such as
+    /// Anthropic, reuse from the previous turn instead of processing again
+    pub cached: bool,
+}
+
+/// Where the tokens of the context window go, see `Agent::context_breakdown`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextBreakdown {
+    pub segments: Vec<ContextSegment>,
+    pub context_limit: usize,
+}
+
+impl ContextBreakdown {
+    pub fn total_tokens(&self) -> usize {
+        self.segments.iter().map(|segment| segment.tokens).sum()
+    }
+
+    pub fn cached_tokens(&self) -> usize {
+        self.segments
+            .iter()
+            .filter(|segment| segment.cached)
+            .map(|segment| segment.tokens)
+            .sum()
+    }
+
+    /// `tokens` as a percentage of the context limit
+    pub fn percent(&self, tokens: usize) -> f64 {
+        if self.context_limit == 0 {
+            return 0.0;
+        }
+        tokens as f64 / self.context_limit as f64 * 100.0
+    }
+}
+
+/// Break the context of the next request down into the system prompt, each extension's
+/// instructions and tools, and the conversation
+pub fn context_breakdown(
+    token_counter: &TokenCounter,
+    system_prompt: &str,
+    extensions: &[ExtensionInfo],
+    tools: &[Tool],
+    messages: &[Message],
+    context_limit: usize,
+) -> ContextBreakdown {
+    let mut segments = Vec::new();
+
+    // Extension instructions are rendered into the system prompt, so they are taken out of it
+    let mut instructions = Vec::new();
+    for extension in extensions {
+        if extension.instructions.trim().is_empty() {
+            continue;
+        }
+        instructions.push(ContextSegment {
+            kind: SegmentKind::ExtensionInstructions,
+            label: extension.name.clone(),
+            tokens: token_counter.count_tokens(&extension.instructions),
+            cached: true,
+        });
+    }
+    let instruction_tokens: usize = instructions.iter().map(|segment| segment.tokens).sum();
+    segments.push(ContextSegment {
+        kind: SegmentKind::SystemPrompt,
+        label: "system prompt".to_string(),
+        tokens: token_counter
+            .count_tokens(system_prompt)
+            .saturating_sub(instruction_tokens),
+        cached: true,
+    });
+    segments.extend(instructions);
+
+    let mut tools_by_extension: BTreeMap<&str, Vec<Tool>> = BTreeMap::new();
+    for tool in tools {
+        let extension = tool
+            .name
+            .split_once("__")
+            .map_or("frontend", |(name, _)| name);
+        tools_by_extension
+            .entry(extension)
+            .or_default()
+            .push(tool.clone());
+    }
+    for (extension, tools) in tools_by_extension {
+        segments.push(ContextSegment {
+            kind: SegmentKind::Tools,
+            label: extension.to_string(),
+            tokens: token_counter.count_tokens_for_tools(&tools),
+            cached: true,
+        });
+    }
+
+    // Tool responses are sent as user messages, the turn starts at the last one the user typed
+    let latest_turn = messages
+        .iter()
+        .rposition(|message| {
+            message.role == Role::User
+                && message
+                    .content
+                    .iter()
+                    .any(|content| matches!(content, MessageContent::Text(_)))
+        })
+        .unwrap_or(0);
+    let counts = get_messages_token_counts(token_counter, messages);
+    for (kind, label, range, cached) in [
+        (
+            SegmentKind::History,
+            "conversation history",
+            0..latest_turn,
+            true,
+        ),
+        (
+            SegmentKind::LatestTurn,
+            "latest turn",
+            latest_turn..messages.len(),
+            false,
+        ),
+    ] {
+        if range.is_empty() {
+            continue;
+        }
+        segments.push(ContextSegment {
+            kind,
+            label: label.to_string(),
+            tokens: counts[range].iter().sum(),
+            cached,
+        });
+    }
+
+    ContextBreakdown {
+        segments,
+        context_limit,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::GPT_4O_TOKENIZER;
+    use mcp_core::tool::ToolCall;
+    use mcp_core::Content;
+    use serde_json::json;
+
+    #[test]
+    fn test_context_breakdown() {
+        let counter = TokenCounter::new(GPT_4O_TOKENIZER);
+        let instructions = "Use the shell to run commands.";
+        let system_prompt = format!("You are a helpful agent.\n\n## developer\n{}", instructions);
+        let extensions = vec![
+            ExtensionInfo::new("developer", instructions, false),
+            ExtensionInfo::new("memory", "", false),
+        ];
+        let tool = |name: &str| {
+            Tool::new(
+                name,
+                "Run a command",
+                json!({"type": "object", "properties": {"command": {"type": "string"}}}),
+                None,
+            )
+        };
+        let tools = vec![
+            tool("developer__shell"),
+            tool("developer__text_editor"),
+            tool("memory__remember"),
+        ];
+        let messages = vec![
+            Message::user().with_text("List the files"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(ToolCall::new("developer__shell", json!({"command": "ls"}))),
+            ),
+            Message::user().with_tool_response("1", Ok(vec![Content::text("a.txt\nb.txt")])),
+            Message::assistant().with_text("There are two files."),
+            Message::user().with_text("Open the first one"),
+        ];
+
+        let breakdown = context_breakdown(
+            &counter,
+            &system_prompt,
+            &extensions,
+            &tools,
+            &messages,
+            1000,
+        );
+        let labels: Vec<(SegmentKind, &str)> = breakdown
+            .segments
+            .iter()
+            .map(|segment| (segment.kind, segment.label.as_str()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                (SegmentKind::SystemPrompt, "system prompt"),
+                (SegmentKind::ExtensionInstructions, "developer"),
+                (SegmentKind::Tools, "developer"),
+                (SegmentKind::Tools, "memory"),
+                (SegmentKind::History, "conversation history"),
+                (SegmentKind::LatestTurn, "latest turn"),
+            ]
+        );
+        assert_eq!(
+            breakdown.segments[0].tokens + breakdown.segments[1].tokens,
+            counter.count_tokens(&system_prompt)
+        );
+        assert_eq!(
+            breakdown.cached_tokens(),
+            breakdown.total_tokens() - breakdown.segments[5].tokens
+        );
+        assert!(breakdown.percent(breakdown.total_tokens()) > 0.0);
+    }
+}
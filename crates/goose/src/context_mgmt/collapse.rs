@@ -0,0 +1,141 @@
+use crate::message::{Message, MessageContent};
+use crate::providers::base::Provider;
+use anyhow::Result;
+use mcp_core::Role;
+use std::sync::Arc;
+
+/// Messages kept verbatim at the end of the history when the caller doesn't say how many
+pub const DEFAULT_KEEP_RECENT: usize = 6;
+
+/// Tool output longer than this is cut when building the transcript to summarize
+const TOOL_OUTPUT_MAX_CHARS: usize = 2_000;
+
+const COLLAPSE_PROMPT: &str = "You summarize the earlier part of a conversation between a user \
+    and an AI coding assistant so the assistant can keep working without it. Keep the user's \
+    goals and instructions, decisions that were made and why, files and commands that were \
+    touched, and any open problems. Leave out pleasantries and tool output that no longer \
+    matters. Reply with the summary only.";
+
+/// Where to split `messages` so everything before the index can be replaced by one summary
+///
+/// The summary is a user message, so the kept messages have to start with an assistant
+/// message, which also keeps every tool response next to the request it answers. Returns
+/// `None` when fewer than two messages could be collapsed.
+pub fn collapse_split(messages: &[Message], keep_recent: usize) -> Option<usize> {
+    let latest = messages.len().saturating_sub(keep_recent);
+    (2..=latest)
+        .rev()
+        .find(|&i| i == messages.len() || messages[i].role == Role::Assistant)
+}
+
+/// The message that stands in for `count` collapsed messages in the history
+pub fn summary_message(count: usize, archive_id: Option<&str>, summary: &str) -> Message {
+    let header = match archive_id {
+        Some(id) => format!(
+            "[Summary of {} earlier messages, the originals are archived as {}]",
+            count, id
+        ),
+        None => format!("[Summary of {} earlier messages]", count),
+    };
+    Message::user().with_text(format!("{}\n\n{}", header, summary.trim()))
+}
+
+/// Ask the provider for a summary of `messages`, paying extra attention to `focus`
+pub async fn summarize_range(
+    provider: &Arc<dyn Provider>,
+    messages: &[Message],
+    focus: Option<&str>,
+) -> Result<String> {
+    let mut request = format!(
+        "Summarize this conversation:\n\n{}",
+        format_transcript(messages)
+    );
+    if let Some(focus) = focus.filter(|focus| !focus.trim().is_empty()) {
+        request.push_str(&format!("\n\nPay particular attention to: {}", focus));
+    }
+
+    let (response, _) = provider
+        .complete(COLLAPSE_PROMPT, &[Message::user().with_text(request)], &[])
+        .await?;
+    let summary = response.as_concat_text();
+    if summary.trim().is_empty() {
+        anyhow::bail!("The provider returned an empty summary");
+    }
+    Ok(summary)
+}
+
+fn format_transcript(messages: &[Message]) -> String {
+    let mut transcript = String::new();
+    for message in messages {
+        let role = match message.role {
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+        };
+        for content in &message.content {
+            let line = match content {
+                MessageContent::Text(text) => text.text.clone(),
+                MessageContent::ToolRequest(request) => match &request.tool_call {
+                    Ok(call) => format!("[called {} with {}]", call.name, call.arguments),
+                    Err(e) => format!("[invalid tool call: {}]", e),
+                },
+                MessageContent::ToolResponse(response) => match &response.tool_result {
+                    Ok(contents) => {
+                        let output = contents
+                            .iter()
+                            .filter_map(|content| content.as_text())
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        format!("[tool output]\n{}", truncate(&output))
+                    }
+                    Err(e) => format!("[tool error: {}]", e),
+                },
+                _ => continue,
+            };
+            transcript.push_str(&format!("{}: {}\n\n", role, line));
+        }
+    }
+    transcript
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= TOOL_OUTPUT_MAX_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(TOOL_OUTPUT_MAX_CHARS).collect();
+    format!("{}\n[output truncated]", cut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::{Content, ToolCall};
+    use serde_json::json;
+
+    #[test]
+    fn test_collapse_split() {
+        let messages = vec![
+            Message::user().with_text("Fix the build"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "cargo build"}),
+                )),
+            ),
+            Message::user().with_tool_response("1", Ok(vec![Content::text("error[E0308]")])),
+            Message::assistant().with_text("Fixed the type mismatch"),
+            Message::user().with_text("Now run the tests"),
+        ];
+
+        // The kept messages can't start with a user message, so the split moves back
+        // to the assistant message before it
+        assert_eq!(collapse_split(&messages, 1), Some(3));
+        assert_eq!(collapse_split(&messages, 2), Some(3));
+        assert_eq!(collapse_split(&messages, 0), Some(5));
+        assert_eq!(collapse_split(&messages, 4), None);
+
+        let transcript = format_transcript(&messages[..3]);
+        assert!(transcript.contains("called developer__shell"));
+        assert!(transcript.contains("error[E0308]"));
+    }
+}
@@ -1,4 +1,7 @@
+pub mod breakdown;
+pub mod collapse;
 mod common;
+pub mod pinned;
 pub mod summarize;
 pub mod truncate;
 
@@ -0,0 +1,225 @@
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Files larger than this are cut off
+pub const MAX_PINNED_FILE_BYTES: usize = 64 * 1024;
+/// Most files taken from one pinned directory
+pub const MAX_PINNED_DIR_FILES: usize = 50;
+
+struct CachedFile {
+    modified: Option<SystemTime>,
+    content: Option<String>,
+}
+
+/// Files and directories kept in the context of every request.
+///
+/// Their content goes in the system prompt rather than the conversation, so truncating or
+/// summarizing the conversation never drops it. Files are only read again when their
+/// modification time changes.
+#[derive(Default)]
+pub struct PinnedContext {
+    paths: Vec<PathBuf>,
+    cache: HashMap<PathBuf, CachedFile>,
+}
+
+impl PinnedContext {
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Pin an existing file or directory, returning false if it already was
+    pub fn pin(&mut self, path: PathBuf) -> Result<bool> {
+        if !path.exists() {
+            bail!("{} does not exist", path.display());
+        }
+        if self.paths.contains(&path) {
+            return Ok(false);
+        }
+        self.paths.push(path);
+        Ok(true)
+    }
+
+    /// Unpin a path, returning false if it wasn't pinned
+    pub fn unpin(&mut self, path: &Path) -> bool {
+        let before = self.paths.len();
+        self.paths.retain(|pinned| pinned != path);
+        self.cache.retain(|file, _| !file.starts_with(path));
+        self.paths.len() != before
+    }
+
+    /// Replace the pinned paths, such as with those saved in a resumed session
+    pub fn set_paths(&mut self, paths: Vec<PathBuf>) {
+        self.paths = paths;
+        self.cache.clear();
+    }
+
+    /// The pinned files, with directories expanded to the files in them
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for path in &self.paths {
+            if path.is_dir() {
+                let mut found = Vec::new();
+                collect_files(path, &mut found);
+                found.sort();
+                found.truncate(MAX_PINNED_DIR_FILES);
+                files.extend(found);
+            } else {
+                files.push(path.clone());
+            }
+        }
+        files.dedup();
+        files
+    }
+
+    /// The current content of a file, None when it is missing or not text
+    fn content(&mut self, file: &Path) -> Option<String> {
+        let modified = fs::metadata(file).and_then(|m| m.modified()).ok();
+        let stale = self
+            .cache
+            .get(file)
+            .is_none_or(|cached| modified.is_none() || cached.modified != modified);
+        if stale {
+            let content = fs::read(file)
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .map(|mut text| {
+                    if text.len() > MAX_PINNED_FILE_BYTES {
+                        let mut end = MAX_PINNED_FILE_BYTES;
+                        while !text.is_char_boundary(end) {
+                            end -= 1;
+                        }
+                        text.truncate(end);
+                        text.push_str("\n[truncated]");
+                    }
+                    text
+                });
+            self.cache
+                .insert(file.to_path_buf(), CachedFile { modified, content });
+        }
+        self.cache
+            .get(file)
+            .and_then(|cached| cached.content.clone())
+    }
+
+    /// The pinned files as a section of the system prompt, None when nothing is pinned
+    pub fn render(&mut self) -> Option<String> {
+        if self.paths.is_empty() {
+            return None;
+        }
+        let mut prompt = String::from(
+            "# Pinned files\n\nThe user pinned these files to keep them in context. This is their \
+             current content, refreshed whenever they change, so there is no need to read them again.\n",
+        );
+        for file in self.files() {
+            let language = file
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or_default();
+            match self.content(&file) {
+                Some(content) => prompt.push_str(&format!(
+                    "\n## {}\n\n```{}\n{}\n```\n",
+                    file.display(),
+                    language,
+                    content.trim_end()
+                )),
+                None => prompt.push_str(&format!(
+                    "\n## {}\n\n(missing or not a text file)\n",
+                    file.display()
+                )),
+            }
+        }
+        Some(prompt)
+    }
+}
+
+/// Files under `dir`, skipping hidden files and directories
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+/// Whether `path` is inside `working_dir`, with `..` and symlinks resolved in both. Only
+/// paths in a session's working directory may be pinned to it.
+pub fn is_within(path: &Path, working_dir: &Path) -> bool {
+    match (path.canonicalize(), working_dir.canonicalize()) {
+        (Ok(path), Ok(working_dir)) => path.starts_with(working_dir),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_and_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = dir.path().join("schema.sql");
+        fs::write(&schema, "create table users (id int);").unwrap();
+
+        let mut pinned = PinnedContext::default();
+        assert!(pinned.render().is_none());
+        assert!(pinned.pin(dir.path().join("missing.md")).is_err());
+        assert!(pinned.pin(schema.clone()).unwrap());
+        assert!(!pinned.pin(schema.clone()).unwrap());
+
+        let prompt = pinned.render().unwrap();
+        assert!(prompt.contains("```sql\ncreate table users (id int);\n```"));
+
+        fs::write(&schema, "create table accounts (id int);").unwrap();
+        // Make sure the change is seen on filesystems with coarse timestamps
+        let file = fs::File::options().write(true).open(&schema).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(5))
+            .unwrap();
+        assert!(pinned.render().unwrap().contains("accounts"));
+
+        assert!(pinned.unpin(&schema));
+        assert!(pinned.render().is_none());
+    }
+
+    #[test]
+    fn test_pin_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("docs")).unwrap();
+        fs::write(dir.path().join("docs/api.md"), "# API").unwrap();
+        fs::write(dir.path().join("docs/.draft.md"), "secret").unwrap();
+        fs::write(dir.path().join("docs/logo.png"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let mut pinned = PinnedContext::default();
+        pinned.pin(dir.path().join("docs")).unwrap();
+        let prompt = pinned.render().unwrap();
+        assert!(prompt.contains("# API"));
+        assert!(!prompt.contains("secret"));
+        assert!(prompt.contains("logo.png\n\n(missing or not a text file)"));
+    }
+
+    #[test]
+    fn test_is_within() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        fs::write(project.join("notes.md"), "notes").unwrap();
+        fs::write(dir.path().join("secrets.env"), "KEY=1").unwrap();
+
+        assert!(is_within(&project.join("notes.md"), &project));
+        assert!(is_within(&project, &project));
+        assert!(!is_within(&project.join("../secrets.env"), &project));
+        assert!(!is_within(&dir.path().join("secrets.env"), &project));
+        assert!(!is_within(&project.join("missing.md"), &project));
+    }
+}
@@ -64,6 +64,12 @@ pub struct ToolConfirmationRequest {
     pub tool_name: String,
     pub arguments: Value,
     pub prompt: Option<String>,
+    /// A unified diff of the change the tool call would make to a file, when it edits one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    /// Whether the tool is marked as destructive, for clients to warn about it
+    #[serde(default)]
+    pub destructive: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
@@ -153,6 +159,8 @@ impl MessageContent {
             tool_name,
             arguments,
             prompt,
+            diff: None,
+            destructive: false,
         })
     }
 
@@ -599,6 +607,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tool_confirmation_request_destructive() {
+        let request = ToolConfirmationRequest {
+            id: "1".to_string(),
+            tool_name: "shell".to_string(),
+            arguments: json!({"command": "rm -rf build"}),
+            prompt: None,
+            diff: None,
+            destructive: true,
+        };
+        let message =
+            Message::user().with_content(MessageContent::ToolConfirmationRequest(request));
+        let mut value = serde_json::to_value(&message).unwrap();
+        assert_eq!(value["content"][0]["destructive"], true);
+        assert_eq!(
+            serde_json::from_value::<Message>(value.clone()).unwrap(),
+            message
+        );
+
+        // Requests saved before the flag existed are not destructive
+        value["content"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("destructive");
+        let message: Message = serde_json::from_value(value).unwrap();
+        let MessageContent::ToolConfirmationRequest(request) = &message.content[0] else {
+            panic!("Expected ToolConfirmationRequest content");
+        };
+        assert!(!request.destructive);
+    }
+
     #[test]
     fn test_from_prompt_message_text() {
         let prompt_content = PromptMessageContent::Text {
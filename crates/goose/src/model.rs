@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 const DEFAULT_CONTEXT_LIMIT: usize = 128_000;
+const DEFAULT_THINKING_BUDGET: i32 = 16_000;
 
 // Tokenizer names, used to infer from model name
 pub const GPT_4O_TOKENIZER: &str = "Xenova--gpt-4o";
@@ -53,6 +54,12 @@ pub struct ModelConfig {
     pub toolshim: bool,
     /// Model to use for toolshim (optional as a default exists)
     pub toolshim_model: Option<String>,
+    /// Optional token budget for extended thinking, enabled on models that support it when set
+    #[serde(default)]
+    pub thinking_budget: Option<i32>,
+    /// Optional host to send requests for this model to instead of the provider's configured one
+    #[serde(default)]
+    pub base_url: Option<String>,
 }
 
 /// Struct to represent model pattern matches and their limits
@@ -83,6 +90,8 @@ impl ModelConfig {
             .ok()
             .and_then(|val| val.parse::<f32>().ok());
 
+        let thinking_budget = Self::thinking_budget_from_env();
+
         Self {
             model_name,
             tokenizer_name: tokenizer_name.to_string(),
@@ -91,7 +100,23 @@ impl ModelConfig {
             max_tokens: None,
             toolshim,
             toolshim_model,
+            thinking_budget,
+            base_url: None,
+        }
+    }
+
+    /// GOOSE_THINKING_BUDGET, or the older CLAUDE_THINKING_ENABLED and CLAUDE_THINKING_BUDGET
+    fn thinking_budget_from_env() -> Option<i32> {
+        if let Ok(val) = std::env::var("GOOSE_THINKING_BUDGET") {
+            return val.parse::<i32>().ok().filter(|budget| *budget > 0);
         }
+        std::env::var("CLAUDE_THINKING_ENABLED").ok()?;
+        Some(
+            std::env::var("CLAUDE_THINKING_BUDGET")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or(DEFAULT_THINKING_BUDGET),
+        )
     }
 
     fn infer_tokenizer_name(model_name: &str) -> &'static str {
@@ -159,6 +184,18 @@ impl ModelConfig {
         self
     }
 
+    /// Set the token budget for extended thinking, disabling it when None
+    pub fn with_thinking_budget(mut self, budget: Option<i32>) -> Self {
+        self.thinking_budget = budget;
+        self
+    }
+
+    /// Set the host requests for this model go to, the provider's configured host when None
+    pub fn with_base_url(mut self, base_url: Option<String>) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
     /// Get the tokenizer name
     pub fn tokenizer_name(&self) -> &str {
         &self.tokenizer_name
@@ -245,6 +282,47 @@ mod tests {
         assert_eq!(config.temperature, None);
     }
 
+    #[test]
+    fn test_model_config_thinking_budget_env_var() {
+        use temp_env::with_vars;
+
+        with_vars(
+            [
+                ("GOOSE_THINKING_BUDGET", Some("4096")),
+                ("CLAUDE_THINKING_ENABLED", None),
+            ],
+            || {
+                let config = ModelConfig::new("claude-sonnet-4".to_string());
+                assert_eq!(config.thinking_budget, Some(4096));
+            },
+        );
+
+        with_vars(
+            [
+                ("GOOSE_THINKING_BUDGET", None),
+                ("CLAUDE_THINKING_ENABLED", Some("true")),
+                ("CLAUDE_THINKING_BUDGET", None),
+            ],
+            || {
+                let config = ModelConfig::new("claude-sonnet-4".to_string());
+                assert_eq!(config.thinking_budget, Some(DEFAULT_THINKING_BUDGET));
+            },
+        );
+
+        with_vars(
+            [
+                ("GOOSE_THINKING_BUDGET", None::<&str>),
+                ("CLAUDE_THINKING_ENABLED", None),
+            ],
+            || {
+                let config = ModelConfig::new("claude-sonnet-4".to_string());
+                assert_eq!(config.thinking_budget, None);
+                let config = config.with_thinking_budget(Some(2048));
+                assert_eq!(config.thinking_budget, Some(2048));
+            },
+        );
+    }
+
     #[test]
     fn test_get_all_model_limits() {
         let limits = ModelConfig::get_all_model_limits();
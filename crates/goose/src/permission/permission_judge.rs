@@ -1,5 +1,5 @@
 use crate::agents::platform_tools::PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME;
-use crate::config::permission::PermissionLevel;
+use crate::config::permission::{AnnotationPolicy, PermissionLevel};
 use crate::config::PermissionManager;
 use crate::message::{Message, MessageContent, ToolRequest};
 use crate::providers::base::Provider;
@@ -12,6 +12,10 @@ use serde_json::{json, Value};
 use std::collections::HashSet;
 use std::sync::Arc;
 
+/// Tools that run code the user must see first, so they ask for confirmation in every mode
+/// and can't be set to always allow
+pub const ALWAYS_CONFIRM_TOOLS: &[&str] = &["computercontroller__app_automation"];
+
 /// Creates the tool definition for checking read-only permissions.
 fn create_read_only_tool() -> Tool {
     Tool::new(
@@ -152,6 +156,57 @@ pub async fn detect_read_only_tools(
     }
 }
 
+/// Tool names grouped by the annotation hints that apply to them once each
+/// extension's [`AnnotationPolicy`] has been taken into account
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolAnnotationCategories {
+    /// Tools declared read-only
+    pub read_only: HashSet<String>,
+    /// Read-only tools from trusted extensions, auto-approved even in approve mode
+    pub trusted_read_only: HashSet<String>,
+    /// Tools declared destructive, which always ask before running
+    pub destructive: HashSet<String>,
+    /// Tools without usable hints, classified by the LLM in smart_approve mode
+    pub unclassified: HashSet<String>,
+}
+
+impl ToolAnnotationCategories {
+    /// Categorize prefixed tools, resolving the policy of the extension owning each tool
+    pub fn from_tools<F>(tools: &[Tool], policy_for_extension: F) -> Self
+    where
+        F: Fn(&str) -> AnnotationPolicy,
+    {
+        let mut categories = Self::default();
+        for tool in tools {
+            let extension_name = tool
+                .name
+                .split_once("__")
+                .map(|(extension, _)| extension)
+                .unwrap_or_default();
+            let policy = policy_for_extension(extension_name);
+
+            match &tool.annotations {
+                Some(annotations) if policy != AnnotationPolicy::Ignore => {
+                    if annotations.read_only_hint {
+                        categories.read_only.insert(tool.name.clone());
+                        if policy == AnnotationPolicy::Trusted {
+                            categories.trusted_read_only.insert(tool.name.clone());
+                        }
+                    } else if annotations.destructive_hint {
+                        categories.destructive.insert(tool.name.clone());
+                    } else {
+                        categories.unclassified.insert(tool.name.clone());
+                    }
+                }
+                _ => {
+                    categories.unclassified.insert(tool.name.clone());
+                }
+            }
+        }
+        categories
+    }
+}
+
 // Define return structure
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PermissionCheckResult {
@@ -163,8 +218,7 @@ pub struct PermissionCheckResult {
 pub async fn check_tool_permissions(
     candidate_requests: &[ToolRequest],
     mode: &str,
-    tools_with_readonly_annotation: HashSet<String>,
-    tools_without_annotation: HashSet<String>,
+    annotations: &ToolAnnotationCategories,
     permission_manager: &mut PermissionManager,
     provider: Arc<dyn Provider>,
 ) -> (PermissionCheckResult, Vec<String>) {
@@ -178,6 +232,11 @@ pub async fn check_tool_permissions(
         if let Ok(tool_call) = request.tool_call.clone() {
             if mode == "chat" {
                 continue;
+            } else if ALWAYS_CONFIRM_TOOLS.contains(&tool_call.name.as_str()) {
+                match permission_manager.get_user_permission(&tool_call.name) {
+                    Some(PermissionLevel::NeverAllow) => denied.push(request.clone()),
+                    _ => needs_approval.push(request.clone()),
+                }
             } else if mode == "auto" {
                 approved.push(request.clone());
             } else {
@@ -198,9 +257,19 @@ pub async fn check_tool_permissions(
                 // 2. Fallback based on mode
                 match mode {
                     "approve" => {
-                        needs_approval.push(request.clone());
+                        if annotations.trusted_read_only.contains(&tool_call.name) {
+                            approved.push(request.clone());
+                        } else {
+                            needs_approval.push(request.clone());
+                        }
                     }
                     "smart_approve" => {
+                        // Destructive tools always ask, regardless of earlier smart approvals
+                        if annotations.destructive.contains(&tool_call.name) {
+                            needs_approval.push(request.clone());
+                            continue;
+                        }
+
                         if let Some(level) =
                             permission_manager.get_smart_approve_permission(&tool_call.name)
                         {
@@ -212,9 +281,9 @@ pub async fn check_tool_permissions(
                             continue;
                         }
 
-                        if tools_with_readonly_annotation.contains(&tool_call.name) {
+                        if annotations.read_only.contains(&tool_call.name) {
                             approved.push(request.clone());
-                        } else if tools_without_annotation.contains(&tool_call.name) {
+                        } else if annotations.unclassified.contains(&tool_call.name) {
                             llm_detect_candidates.push(request.clone());
                         } else {
                             needs_approval.push(request.clone());
@@ -404,10 +473,11 @@ mod tests {
         let mut permission_manager = PermissionManager::new(temp_path);
         let provider = create_mock_provider();
 
-        let tools_with_readonly_annotation: HashSet<String> =
-            vec!["file_reader".to_string()].into_iter().collect();
-        let tools_without_annotation: HashSet<String> =
-            vec!["data_fetcher".to_string()].into_iter().collect();
+        let annotations = ToolAnnotationCategories {
+            read_only: vec!["file_reader".to_string()].into_iter().collect(),
+            unclassified: vec!["data_fetcher".to_string()].into_iter().collect(),
+            ..Default::default()
+        };
 
         permission_manager.update_user_permission("file_reader", PermissionLevel::AlwaysAllow);
         permission_manager
@@ -444,8 +514,7 @@ mod tests {
         let (result, enable_extension_request_ids) = check_tool_permissions(
             &candidate_requests,
             "smart_approve",
-            tools_with_readonly_annotation,
-            tools_without_annotation,
+            &annotations,
             &mut permission_manager,
             provider,
         )
@@ -472,10 +541,11 @@ mod tests {
         let mut permission_manager = PermissionManager::new(temp_path);
         let provider = create_mock_provider();
 
-        let tools_with_readonly_annotation: HashSet<String> =
-            vec!["file_reader".to_string()].into_iter().collect();
-        let tools_without_annotation: HashSet<String> =
-            vec!["data_fetcher".to_string()].into_iter().collect();
+        let annotations = ToolAnnotationCategories {
+            read_only: vec!["file_reader".to_string()].into_iter().collect(),
+            unclassified: vec!["data_fetcher".to_string()].into_iter().collect(),
+            ..Default::default()
+        };
 
         permission_manager.update_user_permission("file_reader", PermissionLevel::AlwaysAllow);
         permission_manager
@@ -503,8 +573,7 @@ mod tests {
         let (result, _) = check_tool_permissions(
             &candidate_requests,
             "auto",
-            tools_with_readonly_annotation,
-            tools_without_annotation,
+            &annotations,
             &mut permission_manager,
             provider,
         )
@@ -515,4 +584,111 @@ mod tests {
         assert_eq!(result.needs_approval.len(), 0); // data_fetcher should need approval
         assert_eq!(result.denied.len(), 0); // No tool should be denied in this test
     }
+
+    #[tokio::test]
+    async fn test_always_confirm_tools_ask_in_auto_mode() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut permission_manager = PermissionManager::new(temp_file.path());
+        let provider = create_mock_provider();
+        let tool_name = ALWAYS_CONFIRM_TOOLS[0];
+        permission_manager.update_user_permission(tool_name, PermissionLevel::AlwaysAllow);
+
+        let request = ToolRequest {
+            id: "tool_1".to_string(),
+            tool_call: ToolResult::Ok(ToolCall {
+                name: tool_name.to_string(),
+                arguments: serde_json::json!({"app": "Spotify", "script": "play"}),
+            }),
+        };
+
+        for mode in ["auto", "approve", "smart_approve"] {
+            let (result, _) = check_tool_permissions(
+                &[request.clone()],
+                mode,
+                &ToolAnnotationCategories::default(),
+                &mut permission_manager,
+                provider.clone(),
+            )
+            .await;
+            assert!(result.approved.is_empty());
+            assert_eq!(result.needs_approval.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_tool_annotation_categories() {
+        let read_only = ToolAnnotations::new().with_read_only(true);
+        let destructive = ToolAnnotations::new();
+        let additive = ToolAnnotations::new().with_destructive(false);
+        let tools = vec![
+            Tool::new("ext__read", "", json!({}), Some(read_only.clone())),
+            Tool::new("ext__delete", "", json!({}), Some(destructive.clone())),
+            Tool::new("ext__append", "", json!({}), Some(additive)),
+            Tool::new("ext__plain", "", json!({}), None),
+            Tool::new("trusted__read", "", json!({}), Some(read_only)),
+            Tool::new("untrusted__delete", "", json!({}), Some(destructive)),
+        ];
+
+        let categories =
+            ToolAnnotationCategories::from_tools(&tools, |extension| match extension {
+                "trusted" => AnnotationPolicy::Trusted,
+                "untrusted" => AnnotationPolicy::Ignore,
+                _ => AnnotationPolicy::Enforce,
+            });
+
+        assert!(categories.read_only.contains("ext__read"));
+        assert!(categories.read_only.contains("trusted__read"));
+        assert_eq!(categories.trusted_read_only.len(), 1);
+        assert!(categories.trusted_read_only.contains("trusted__read"));
+        assert_eq!(categories.destructive.len(), 1);
+        assert!(categories.destructive.contains("ext__delete"));
+        assert!(categories.unclassified.contains("ext__append"));
+        assert!(categories.unclassified.contains("ext__plain"));
+        assert!(categories.unclassified.contains("untrusted__delete"));
+    }
+
+    #[tokio::test]
+    async fn test_check_tool_permissions_annotations() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut permission_manager = PermissionManager::new(temp_file.path());
+        let provider = create_mock_provider();
+
+        // A cached smart approval must not bypass the destructive hint
+        permission_manager
+            .update_smart_approve_permission("ext__delete", PermissionLevel::AlwaysAllow);
+
+        let annotations = ToolAnnotationCategories {
+            read_only: vec!["ext__read".to_string()].into_iter().collect(),
+            trusted_read_only: vec!["ext__read".to_string()].into_iter().collect(),
+            destructive: vec!["ext__delete".to_string()].into_iter().collect(),
+            ..Default::default()
+        };
+
+        let candidate_requests: Vec<ToolRequest> = ["ext__read", "ext__delete"]
+            .iter()
+            .map(|name| ToolRequest {
+                id: name.to_string(),
+                tool_call: ToolResult::Ok(ToolCall {
+                    name: name.to_string(),
+                    arguments: json!({}),
+                }),
+            })
+            .collect();
+
+        for mode in ["approve", "smart_approve"] {
+            let (result, _) = check_tool_permissions(
+                &candidate_requests,
+                mode,
+                &annotations,
+                &mut permission_manager,
+                provider.clone(),
+            )
+            .await;
+
+            assert_eq!(result.approved.len(), 1);
+            assert_eq!(result.approved[0].id, "ext__read");
+            assert_eq!(result.needs_approval.len(), 1);
+            assert_eq!(result.needs_approval[0].id, "ext__delete");
+        }
+    }
 }
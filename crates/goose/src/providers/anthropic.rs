@@ -7,7 +7,9 @@ use std::time::Duration;
 
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
 use super::errors::ProviderError;
-use super::formats::anthropic::{create_request, get_usage, response_to_message};
+use super::formats::anthropic::{
+    create_request, get_usage, response_to_message, thinking_budget, StreamAccumulator, StreamDelta,
+};
 use super::utils::{emit_debug_trace, get_model};
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -25,12 +27,29 @@ pub const ANTHROPIC_KNOWN_MODELS: &[&str] = &[
 pub const ANTHROPIC_DOC_URL: &str = "https://docs.anthropic.com/en/docs/about-claude/models";
 pub const ANTHROPIC_API_VERSION: &str = "2023-06-01";
 
+/// The `anthropic-version` header to send, pinning the API version
+fn api_version_from_config() -> Result<String> {
+    let Ok(version) = crate::config::Config::global().get_param::<String>("ANTHROPIC_VERSION")
+    else {
+        return Ok(ANTHROPIC_API_VERSION.to_string());
+    };
+    if chrono::NaiveDate::parse_from_str(&version, "%Y-%m-%d").is_err() {
+        anyhow::bail!(
+            "ANTHROPIC_VERSION should be a date like {}, got '{}'",
+            ANTHROPIC_API_VERSION,
+            version
+        );
+    }
+    Ok(version)
+}
+
 #[derive(serde::Serialize)]
 pub struct AnthropicProvider {
     #[serde(skip)]
     client: Client,
     host: String,
     api_key: String,
+    api_version: String,
     model: ModelConfig,
 }
 
@@ -45,11 +64,14 @@ impl AnthropicProvider {
     pub fn from_env(model: ModelConfig) -> Result<Self> {
         let config = crate::config::Config::global();
         let api_key: String = config.get_secret("ANTHROPIC_API_KEY")?;
-        let host: String = config
-            .get_param("ANTHROPIC_HOST")
-            .unwrap_or_else(|_| "https://api.anthropic.com".to_string());
+        let host: String = model.base_url.clone().unwrap_or_else(|| {
+            config
+                .get_param("ANTHROPIC_HOST")
+                .unwrap_or_else(|_| "https://api.anthropic.com".to_string())
+        });
+        let api_version = api_version_from_config()?;
 
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
@@ -57,6 +79,7 @@ impl AnthropicProvider {
             client,
             host,
             api_key,
+            api_version,
             model,
         })
     }
@@ -77,6 +100,9 @@ impl AnthropicProvider {
             .await?;
 
         let status = response.status();
+        if status == StatusCode::OK && payload.get("stream") == Some(&Value::Bool(true)) {
+            return collect_stream(response).await;
+        }
         let payload: Option<Value> = response.json().await.ok();
 
         // https://docs.anthropic.com/en/api/errors
@@ -117,6 +143,45 @@ impl AnthropicProvider {
     }
 }
 
+/// Read a streamed response into the response the API returns without streaming, journaling
+/// the response text as it arrives. Thinking is not journaled as it is hidden by default.
+async fn collect_stream(response: reqwest::Response) -> Result<Value, ProviderError> {
+    use futures_util::StreamExt;
+
+    let mut accumulator = StreamAccumulator::new();
+    let mut journal = crate::session::partial::StreamJournal::for_current_session();
+    let mut stream = response.bytes_stream();
+    // Events can be split across chunks, so only complete lines are parsed
+    let mut buffer = String::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(end) = buffer.find('\n') {
+            let line: String = buffer.drain(..=end).collect();
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let event: Value = match serde_json::from_str(data.trim()) {
+                Ok(event) => event,
+                Err(_) => continue,
+            };
+            let delta = accumulator
+                .add_event(&event)
+                .map_err(|e| ProviderError::ServerError(e.to_string()))?;
+            if let (Some(StreamDelta::Text(text)), Some(journal)) = (delta, journal.as_mut()) {
+                journal.push(text);
+            }
+        }
+    }
+    if let Some(journal) = journal {
+        journal.finish();
+    }
+
+    accumulator
+        .finish()
+        .map_err(|e| ProviderError::RequestFailed(e.to_string()))
+}
+
 #[async_trait]
 impl Provider for AnthropicProvider {
     fn metadata() -> ProviderMetadata {
@@ -135,6 +200,12 @@ impl Provider for AnthropicProvider {
                     false,
                     Some("https://api.anthropic.com"),
                 ),
+                ConfigKey::new(
+                    "ANTHROPIC_VERSION",
+                    false,
+                    false,
+                    Some(ANTHROPIC_API_VERSION),
+                ),
             ],
         )
     }
@@ -153,13 +224,20 @@ impl Provider for AnthropicProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(&self.model, system, messages, tools)?;
+        let mut payload = create_request(&self.model, system, messages, tools)?;
 
         let mut headers = reqwest::header::HeaderMap::new();
         headers.insert("x-api-key", self.api_key.parse().unwrap());
-        headers.insert("anthropic-version", ANTHROPIC_API_VERSION.parse().unwrap());
+        headers.insert("anthropic-version", self.api_version.parse().unwrap());
 
-        let is_thinking_enabled = std::env::var("CLAUDE_THINKING_ENABLED").is_ok();
+        let is_thinking_enabled = thinking_budget(&self.model).is_some();
+        if is_thinking_enabled {
+            // Thinking makes for long responses, which the API requires to be streamed
+            payload
+                .as_object_mut()
+                .unwrap()
+                .insert("stream".to_string(), Value::Bool(true));
+        }
         if self.model.model_name.starts_with("claude-3-7-sonnet-") && is_thinking_enabled {
             // https://docs.anthropic.com/en/docs/build-with-claude/extended-thinking#extended-output-capabilities-beta
             headers.insert("anthropic-beta", "output-128k-2025-02-19".parse().unwrap());
@@ -191,7 +269,7 @@ impl Provider for AnthropicProvider {
         let response = self
             .client
             .get(&url)
-            .header("anthropic-version", ANTHROPIC_API_VERSION)
+            .header("anthropic-version", &self.api_version)
             .header("x-api-key", self.api_key.clone())
             .send()
             .await?;
@@ -10,6 +10,7 @@ use super::azureauth::AzureAuth;
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
 use super::errors::ProviderError;
 use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::schema::{mark_strict, SchemaCapabilities};
 use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -71,7 +72,7 @@ impl AzureProvider {
         let api_key = config.get_secret("AZURE_OPENAI_API_KEY").ok();
         let auth = AzureAuth::new(api_key)?;
 
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
@@ -235,6 +236,10 @@ impl Provider for AzureProvider {
         self.model.clone()
     }
 
+    fn schema_capabilities(&self) -> SchemaCapabilities {
+        SchemaCapabilities::openai()
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -245,7 +250,11 @@ impl Provider for AzureProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        let mut payload =
+            create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        if self.schema_capabilities().strict {
+            mark_strict(&mut payload);
+        }
         let response = self.post(payload.clone()).await?;
 
         let message = response_to_message(response.clone())?;
@@ -2,6 +2,8 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use super::errors::ProviderError;
+use super::schema::SchemaCapabilities;
+use super::tiered::TieredProvider;
 use crate::message::Message;
 use crate::model::ModelConfig;
 use mcp_core::tool::Tool;
@@ -9,6 +11,7 @@ use utoipa::ToSchema;
 
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
+use std::time::Duration;
 
 /// A global store for the current model being used, we use this as when a provider returns, it tells us the real model, not an alias
 pub static CURRENT_MODEL: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
@@ -117,11 +120,69 @@ impl ConfigKey {
 pub struct ProviderUsage {
     pub model: String,
     pub usage: Usage,
+    /// How long the response took, filled in by the agent when the provider does not report it
+    #[serde(default)]
+    pub timing: Option<ResponseTiming>,
+    /// Estimated cost in USD, when the provider knows the model's price
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
+    /// Estimated USD saved by routing this request to a cheaper model
+    #[serde(default)]
+    pub saved_usd: Option<f64>,
 }
 
 impl ProviderUsage {
     pub fn new(model: String, usage: Usage) -> Self {
-        Self { model, usage }
+        Self {
+            model,
+            usage,
+            timing: None,
+            cost_usd: None,
+            saved_usd: None,
+        }
+    }
+
+    pub fn with_timing(mut self, timing: ResponseTiming) -> Self {
+        self.timing = Some(timing);
+        self
+    }
+
+    pub fn with_cost(mut self, cost_usd: Option<f64>, saved_usd: Option<f64>) -> Self {
+        self.cost_usd = cost_usd;
+        self.saved_usd = saved_usd;
+        self
+    }
+}
+
+/// Latency of a single provider response
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct ResponseTiming {
+    /// Milliseconds until the first token arrived, only known for streamed responses
+    pub time_to_first_token_ms: Option<u64>,
+    /// Milliseconds until the full response was received
+    pub total_ms: u64,
+    /// Output tokens per second, measured from the first token when it is known
+    pub tokens_per_second: Option<f64>,
+}
+
+impl ResponseTiming {
+    pub fn new(
+        time_to_first_token: Option<Duration>,
+        total: Duration,
+        output_tokens: Option<i32>,
+    ) -> Self {
+        // For streamed responses only count the time spent generating, so the rate is not
+        // skewed by queueing and prompt processing
+        let generation = total.saturating_sub(time_to_first_token.unwrap_or_default());
+        let tokens_per_second = output_tokens
+            .filter(|tokens| *tokens > 0 && !generation.is_zero())
+            .map(|tokens| tokens as f64 / generation.as_secs_f64());
+
+        Self {
+            time_to_first_token_ms: time_to_first_token.map(|d| d.as_millis() as u64),
+            total_ms: total.as_millis() as u64,
+            tokens_per_second,
+        }
     }
 }
 
@@ -130,6 +191,9 @@ pub struct Usage {
     pub input_tokens: Option<i32>,
     pub output_tokens: Option<i32>,
     pub total_tokens: Option<i32>,
+    /// Tokens a reasoning model spent thinking, already counted in output_tokens
+    #[serde(default)]
+    pub reasoning_tokens: Option<i32>,
 }
 
 impl Usage {
@@ -142,8 +206,14 @@ impl Usage {
             input_tokens,
             output_tokens,
             total_tokens,
+            reasoning_tokens: None,
         }
     }
+
+    pub fn with_reasoning_tokens(mut self, reasoning_tokens: Option<i32>) -> Self {
+        self.reasoning_tokens = reasoning_tokens;
+        self
+    }
 }
 
 use async_trait::async_trait;
@@ -190,6 +260,12 @@ pub trait Provider: Send + Sync {
         Ok(None)
     }
 
+    /// The JSON Schema constructs this provider accepts in tool parameters, tools are
+    /// rewritten to fit before they are sent
+    fn schema_capabilities(&self) -> SchemaCapabilities {
+        SchemaCapabilities::FULL
+    }
+
     /// Check if this provider supports embeddings
     fn supports_embeddings(&self) -> bool {
         false
@@ -207,6 +283,11 @@ pub trait Provider: Send + Sync {
     fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
         None
     }
+
+    /// Check if this provider is a TieredProvider, whose budget is kept per session
+    fn as_tiered(&self) -> Option<&TieredProvider> {
+        None
+    }
 }
 
 #[cfg(test)]
@@ -243,6 +324,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_response_timing() {
+        // Streamed: the rate only counts time after the first token
+        let timing = ResponseTiming::new(
+            Some(Duration::from_millis(500)),
+            Duration::from_millis(2500),
+            Some(100),
+        );
+        assert_eq!(timing.time_to_first_token_ms, Some(500));
+        assert_eq!(timing.total_ms, 2500);
+        assert_eq!(timing.tokens_per_second, Some(50.0));
+
+        // Not streamed: the rate covers the whole response
+        let timing = ResponseTiming::new(None, Duration::from_secs(4), Some(100));
+        assert_eq!(timing.time_to_first_token_ms, None);
+        assert_eq!(timing.tokens_per_second, Some(25.0));
+
+        let timing = ResponseTiming::new(None, Duration::from_secs(1), None);
+        assert_eq!(timing.tokens_per_second, None);
+    }
+
     #[test]
     fn test_set_and_get_current_model() {
         // Set the model
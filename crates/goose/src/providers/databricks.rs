@@ -1,22 +1,27 @@
-use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, ResponseTiming, Usage};
 use super::embedding::EmbeddingCapable;
 use super::errors::ProviderError;
 use super::formats::databricks::{create_request, get_usage, response_to_message};
 use super::oauth;
+use super::schema::SchemaCapabilities;
 use super::utils::{get_model, ImageFormat};
+use super::utils_universal_openai_stream::{OAIStreamChunk, OAIStreamCollector};
 use crate::config::ConfigError;
 use crate::message::Message;
 use crate::model::ModelConfig;
+use crate::session::partial::StreamJournal;
 use mcp_core::tool::Tool;
 use serde_json::json;
 use url::Url;
 
 use anyhow::Result;
 use async_trait::async_trait;
-use reqwest::{Client, StatusCode};
+use futures::StreamExt;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Client, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 const DEFAULT_CLIENT_ID: &str = "databricks-cli";
@@ -48,6 +53,10 @@ pub const DATABRICKS_KNOWN_MODELS: &[&str] = &[
 pub const DATABRICKS_DOC_URL: &str =
     "https://docs.databricks.com/en/generative-ai/external-models/index.html";
 
+/// Config key to stream responses from the serving endpoint, which also measures the time to
+/// the first token and keeps the partial response if the session is interrupted
+pub const DATABRICKS_STREAMING_KEY: &str = "DATABRICKS_STREAMING";
+
 /// Retry configuration for handling rate limit errors
 #[derive(Debug, Clone)]
 struct RetryConfig {
@@ -131,6 +140,8 @@ pub struct DatabricksProvider {
     image_format: ImageFormat,
     #[serde(skip)]
     retry_config: RetryConfig,
+    #[serde(skip)]
+    streaming: bool,
 }
 
 impl Default for DatabricksProvider {
@@ -160,12 +171,13 @@ impl DatabricksProvider {
 
         let host = host?;
 
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
             .build()?;
 
         // Load optional retry configuration from environment
         let retry_config = Self::load_retry_config(config);
+        let streaming = config.get_param(DATABRICKS_STREAMING_KEY).unwrap_or(false);
 
         // If we find a databricks token we prefer that
         if let Ok(api_key) = config.get_secret("DATABRICKS_TOKEN") {
@@ -176,6 +188,7 @@ impl DatabricksProvider {
                 model,
                 image_format: ImageFormat::OpenAi,
                 retry_config,
+                streaming,
             });
         }
 
@@ -187,6 +200,7 @@ impl DatabricksProvider {
             model,
             image_format: ImageFormat::OpenAi,
             retry_config,
+            streaming,
         })
     }
 
@@ -235,7 +249,7 @@ impl DatabricksProvider {
     ///
     /// Returns a Result containing the new DatabricksProvider instance
     pub fn from_params(host: String, api_key: String, model: ModelConfig) -> Result<Self> {
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
@@ -246,6 +260,7 @@ impl DatabricksProvider {
             model,
             image_format: ImageFormat::OpenAi,
             retry_config: RetryConfig::default(),
+            streaming: false,
         })
     }
 
@@ -266,6 +281,15 @@ impl DatabricksProvider {
     }
 
     async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
+        let response = self.send(&payload).await?;
+        response.json().await.map_err(|_| {
+            ProviderError::RequestFailed("Response body is not valid JSON".to_string())
+        })
+    }
+
+    /// Send a request to the serving endpoint, retrying rate limits and server errors, and
+    /// return the successful response before its body is read
+    async fn send(&self, payload: &Value) -> Result<Response, ProviderError> {
         let base_url = Url::parse(&self.host)
             .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
 
@@ -303,19 +327,21 @@ impl DatabricksProvider {
                 .client
                 .post(url.clone())
                 .header("Authorization", auth_header)
-                .json(&payload)
+                .json(payload)
                 .send()
                 .await?;
 
             let status = response.status();
+            if status == StatusCode::OK {
+                return Ok(response);
+            }
+            // Serving endpoints say how long to back off when they are rate limited
+            let delay = retry_after(response.headers())
+                .map(|delay| delay.min(Duration::from_millis(self.retry_config.max_interval_ms)))
+                .unwrap_or_else(|| self.retry_config.delay_for_attempt(attempts + 1));
             let payload: Option<Value> = response.json().await.ok();
 
             match status {
-                StatusCode::OK => {
-                    return payload.ok_or_else(|| {
-                        ProviderError::RequestFailed("Response body is not valid JSON".to_string())
-                    });
-                }
                 StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
                     return Err(ProviderError::Authentication(format!(
                         "Authentication failed. Please ensure your API keys are valid and have the required permissions. \
@@ -385,8 +411,7 @@ impl DatabricksProvider {
                     // Store the error in case we need to return it after max retries
                     last_error = Some(ProviderError::RateLimitExceeded(error_msg));
 
-                    // Calculate and apply the backoff delay
-                    let delay = self.retry_config.delay_for_attempt(attempts);
+                    // Apply the backoff delay
                     tracing::info!("Backing off for {:?} before retry", delay);
                     sleep(delay).await;
 
@@ -404,8 +429,7 @@ impl DatabricksProvider {
                     // Store the error in case we need to return it after max retries
                     last_error = Some(ProviderError::ServerError(error_msg));
 
-                    // Calculate and apply the backoff delay
-                    let delay = self.retry_config.delay_for_attempt(attempts);
+                    // Apply the backoff delay
                     tracing::info!("Backing off for {:?} before retry", delay);
                     sleep(delay).await;
 
@@ -430,6 +454,125 @@ impl DatabricksProvider {
     }
 }
 
+/// The delay a rate limited response asks for in its Retry-After header, in seconds
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+/// Rewrite a streamed chunk into the OpenAI shape. Endpoints serving other model families
+/// send content as a list of blocks and tool call arguments as objects, and leave out indexes.
+fn normalize_chunk(mut chunk: Value) -> Value {
+    let Some(choices) = chunk.get_mut("choices").and_then(Value::as_array_mut) else {
+        return chunk;
+    };
+    for (index, choice) in choices.iter_mut().enumerate() {
+        if choice.get("index").is_none_or(Value::is_null) {
+            choice["index"] = json!(index);
+        }
+        if choice.get("delta").is_none_or(Value::is_null) {
+            choice["delta"] = json!({});
+        }
+        let delta = &mut choice["delta"];
+        if let Some(blocks) = delta.get("content").and_then(Value::as_array) {
+            let text: String = blocks
+                .iter()
+                .filter(|block| block.get("type").and_then(Value::as_str) == Some("text"))
+                .filter_map(|block| block.get("text").and_then(Value::as_str))
+                .collect();
+            delta["content"] = json!(text);
+        }
+        if delta.get("tool_calls").is_some_and(Value::is_null) {
+            delta["tool_calls"] = json!([]);
+        }
+        if let Some(tool_calls) = delta.get_mut("tool_calls").and_then(Value::as_array_mut) {
+            for (index, tool_call) in tool_calls.iter_mut().enumerate() {
+                if tool_call.get("index").is_none_or(Value::is_null) {
+                    tool_call["index"] = json!(index);
+                }
+                if let Some(arguments) = tool_call
+                    .pointer_mut("/function/arguments")
+                    .filter(|arguments| arguments.is_object())
+                {
+                    *arguments = json!(arguments.to_string());
+                }
+            }
+        }
+    }
+    chunk
+}
+
+/// Collect a streamed response into the shape of a complete one, with when the first chunk
+/// arrived
+async fn collect_stream(response: Response) -> Result<(Value, Option<Instant>), ProviderError> {
+    let mut collector = OAIStreamCollector::new();
+    let mut journal = StreamJournal::for_current_session();
+    let mut first_token = None;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut stream = response.bytes_stream();
+
+    'read: while let Some(bytes) = stream.next().await {
+        let bytes = bytes.map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
+        buffer.extend_from_slice(&bytes);
+        // Only whole lines, a chunk can end in the middle of a character
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                break 'read;
+            }
+            let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+                continue;
+            };
+            if let Some(error) = chunk.get("error") {
+                return Err(ProviderError::RequestFailed(format!(
+                    "Streamed response failed: {}",
+                    error
+                )));
+            }
+            match serde_json::from_value::<OAIStreamChunk>(normalize_chunk(chunk)) {
+                Ok(chunk) => {
+                    first_token.get_or_insert_with(Instant::now);
+                    if let Some(journal) = journal.as_mut() {
+                        chunk
+                            .choices
+                            .iter()
+                            .filter_map(|choice| choice.delta.content.as_deref())
+                            .for_each(|text| journal.push(text));
+                    }
+                    // The collector only merges choices, usage comes in the last chunk
+                    if chunk.usage.is_some() {
+                        collector.usage = chunk.usage.clone();
+                    }
+                    if chunk.model.is_some() {
+                        collector.model = chunk.model.clone();
+                    }
+                    collector.add_chunk(&chunk);
+                }
+                Err(e) => tracing::debug!("Skipping unrecognized stream chunk: {}", e),
+            }
+        }
+    }
+
+    if let Some(journal) = journal {
+        journal.finish();
+    }
+    let response = serde_json::to_value(collector.build_response())
+        .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
+    Ok((response, first_token))
+}
+
 #[async_trait]
 impl Provider for DatabricksProvider {
     fn metadata() -> ProviderMetadata {
@@ -451,6 +594,10 @@ impl Provider for DatabricksProvider {
         self.model.clone()
     }
 
+    fn schema_capabilities(&self) -> SchemaCapabilities {
+        SchemaCapabilities::for_model(&self.model.model_name)
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -468,7 +615,21 @@ impl Provider for DatabricksProvider {
             .expect("payload should have model key")
             .remove("model");
 
-        let response = self.post(payload.clone()).await?;
+        if self.streaming {
+            let payload = payload
+                .as_object_mut()
+                .expect("payload should be an object");
+            payload.insert("stream".to_string(), json!(true));
+            payload.insert("stream_options".to_string(), json!({"include_usage": true}));
+        }
+
+        let start = Instant::now();
+        let (response, first_token) = if self.streaming {
+            collect_stream(self.send(&payload).await?).await?
+        } else {
+            (self.post(payload.clone()).await?, None)
+        };
+        let elapsed = start.elapsed();
 
         // Parse response
         let message = response_to_message(response.clone())?;
@@ -482,8 +643,16 @@ impl Provider for DatabricksProvider {
         };
         let model = get_model(&response);
         super::utils::emit_debug_trace(&self.model, &payload, &response, &usage);
-
-        Ok((message, ProviderUsage::new(model, usage)))
+        let timing = ResponseTiming::new(
+            first_token.map(|t| t.duration_since(start)),
+            elapsed,
+            usage.output_tokens,
+        );
+
+        Ok((
+            message,
+            ProviderUsage::new(model, usage).with_timing(timing),
+        ))
     }
 
     fn supports_embeddings(&self) -> bool {
@@ -529,3 +698,51 @@ impl EmbeddingCapable for DatabricksProvider {
         Ok(embeddings)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, "2".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(2)));
+        headers.insert(RETRY_AFTER, "0.5".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(500)));
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_normalize_chunk() {
+        let chunk = normalize_chunk(json!({
+            "model": "databricks-claude-3-7-sonnet",
+            "choices": [{
+                "delta": {
+                    "content": [{"type": "text", "text": "Hello"}, {"type": "text", "text": " there"}],
+                    "tool_calls": [{
+                        "id": "toolu_1",
+                        "function": {"name": "developer__shell", "arguments": {"command": "ls"}}
+                    }]
+                }
+            }]
+        }));
+        let chunk: OAIStreamChunk = serde_json::from_value(chunk).unwrap();
+        let choice = &chunk.choices[0];
+        assert_eq!(choice.index, 0);
+        assert_eq!(choice.delta.content.as_deref(), Some("Hello there"));
+        let tool_call = &choice.delta.tool_calls[0];
+        assert_eq!(tool_call.index, 0);
+        assert_eq!(tool_call.function.arguments, r#"{"command":"ls"}"#);
+
+        // The final usage chunk has no choices
+        let usage = normalize_chunk(json!({
+            "choices": [],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+        }));
+        let usage: OAIStreamChunk = serde_json::from_value(usage).unwrap();
+        assert!(usage.usage.is_some());
+    }
+}
@@ -18,6 +18,10 @@ pub enum ProviderError {
     #[error("Request failed: {0}")]
     RequestFailed(String),
 
+    /// The provider couldn't be reached or didn't answer in time
+    #[error("Network error: {0}")]
+    NetworkError(String),
+
     #[error("Execution error: {0}")]
     ExecutionError(String),
 
@@ -25,6 +29,19 @@ pub enum ProviderError {
     UsageError(String),
 }
 
+impl ProviderError {
+    /// Failures that may succeed when the same request is sent again: rate limits, server
+    /// errors and connection failures. Other failed requests would fail the same way again.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::RateLimitExceeded(_)
+                | ProviderError::ServerError(_)
+                | ProviderError::NetworkError(_)
+        )
+    }
+}
+
 impl From<anyhow::Error> for ProviderError {
     fn from(error: anyhow::Error) -> Self {
         ProviderError::ExecutionError(error.to_string())
@@ -33,7 +50,11 @@ impl From<anyhow::Error> for ProviderError {
 
 impl From<reqwest::Error> for ProviderError {
     fn from(error: reqwest::Error) -> Self {
-        ProviderError::ExecutionError(error.to_string())
+        if error.is_connect() || error.is_timeout() {
+            ProviderError::NetworkError(error.to_string())
+        } else {
+            ProviderError::ExecutionError(error.to_string())
+        }
     }
 }
 
@@ -177,3 +198,19 @@ impl std::fmt::Display for OpenAIError {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_retry_rate_limits_server_and_network_errors() {
+        assert!(ProviderError::RateLimitExceeded("slow down".into()).is_transient());
+        assert!(ProviderError::ServerError("bad gateway".into()).is_transient());
+        assert!(ProviderError::NetworkError("connection refused".into()).is_transient());
+        // A bad request fails the same way every time
+        assert!(!ProviderError::RequestFailed("status 400".into()).is_transient());
+        assert!(!ProviderError::Authentication("invalid key".into()).is_transient());
+        assert!(!ProviderError::ContextLengthExceeded("too long".into()).is_transient());
+    }
+}
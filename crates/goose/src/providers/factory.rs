@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use super::{
@@ -14,11 +15,33 @@ use super::{
     ollama::OllamaProvider,
     openai::OpenAiProvider,
     openrouter::OpenRouterProvider,
+    pricing::{get_model_pricing, lookup_pricing},
     snowflake::SnowflakeProvider,
+    tiered::{ModelTier, TieredModelsConfig, TieredProvider, TIERED_MODELS_KEY},
     venice::VeniceProvider,
 };
 use crate::model::ModelConfig;
-use anyhow::Result;
+use anyhow::{bail, Result};
+
+/// Config key mapping model names to the host their requests go to, replacing the provider's
+/// configured host for those models only, e.g.
+///
+/// ```yaml
+/// model_base_urls:
+///   gpt-4o: https://llm-gateway.example.com
+/// ```
+pub const MODEL_BASE_URLS_KEY: &str = "model_base_urls";
+
+/// Providers that send every request to a single configurable host
+const BASE_URL_PROVIDERS: &[&str] = &[
+    "openai",
+    "anthropic",
+    "groq",
+    "ollama",
+    "openrouter",
+    "google",
+    "venice",
+];
 
 #[cfg(test)]
 use super::errors::ProviderError;
@@ -63,10 +86,36 @@ pub fn create(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
         return create_lead_worker_from_env(name, &model, &lead_model_name);
     }
 
+    // Tiers stand in for the configured model, not for one asked for explicitly
+    if let Ok(tiers) = config.get_param::<TieredModelsConfig>(TIERED_MODELS_KEY) {
+        if is_configured_model(name, &model) {
+            tracing::info!("Creating tiered provider from {}", TIERED_MODELS_KEY);
+
+            return create_tiered(name, tiers);
+        }
+        tracing::info!(
+            "Not using {} for {}/{}, which was asked for explicitly",
+            TIERED_MODELS_KEY,
+            name,
+            model.model_name
+        );
+    }
+
     // Default: create regular provider
     create_provider(name, model)
 }
 
+/// Whether `name` and `model` are the configured GOOSE_PROVIDER and GOOSE_MODEL
+fn is_configured_model(name: &str, model: &ModelConfig) -> bool {
+    let config = crate::config::Config::global();
+    config
+        .get_param::<String>("GOOSE_PROVIDER")
+        .is_ok_and(|provider| provider == name)
+        && config
+            .get_param::<String>("GOOSE_MODEL")
+            .map_or(true, |configured| configured == model.model_name)
+}
+
 /// Create a lead/worker provider from environment variables
 fn create_lead_worker_from_env(
     default_provider_name: &str,
@@ -109,7 +158,75 @@ fn create_lead_worker_from_env(
     )))
 }
 
+/// Create a tiered provider, each tier defaulting to the main provider and priced from
+/// GOOSE_MODEL_PRICING
+fn create_tiered(
+    default_provider_name: &str,
+    tiers: TieredModelsConfig,
+) -> Result<Arc<dyn Provider>> {
+    let pricing = get_model_pricing();
+    let create_tier = |tier: &ModelTier| {
+        let provider_name = tier.provider.as_deref().unwrap_or(default_provider_name);
+        let price = lookup_pricing(&pricing, provider_name, &tier.model).copied();
+        create_provider(provider_name, ModelConfig::new(tier.model.clone()))
+            .map(|provider| (provider, price))
+    };
+    let (cheap_provider, cheap_pricing) = create_tier(&tiers.cheap)?;
+    let (expensive_provider, expensive_pricing) = create_tier(&tiers.expensive)?;
+    Ok(Arc::new(
+        TieredProvider::new(cheap_provider, expensive_provider, tiers)
+            .with_pricing(cheap_pricing, expensive_pricing),
+    ))
+}
+
+/// The base URL configured for `model` in `overrides`, checked to be an http(s) URL that
+/// `provider` can use
+fn base_url_override(
+    overrides: &HashMap<String, String>,
+    provider: &str,
+    model: &str,
+) -> Result<Option<String>> {
+    let Some(base_url) = overrides.get(model) else {
+        return Ok(None);
+    };
+    if !BASE_URL_PROVIDERS.contains(&provider) {
+        bail!(
+            "{} sets a base URL for {}, but the {} provider doesn't support one",
+            MODEL_BASE_URLS_KEY,
+            model,
+            provider
+        );
+    }
+    let url = url::Url::parse(base_url).map_err(|e| {
+        anyhow::anyhow!(
+            "Invalid base URL '{}' for {} in {}: {}",
+            base_url,
+            model,
+            MODEL_BASE_URLS_KEY,
+            e
+        )
+    })?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        bail!(
+            "Invalid base URL '{}' for {} in {}: expected an http or https URL",
+            base_url,
+            model,
+            MODEL_BASE_URLS_KEY
+        );
+    }
+    Ok(Some(base_url.clone()))
+}
+
 fn create_provider(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
+    let overrides = crate::config::Config::global()
+        .get_param::<HashMap<String, String>>(MODEL_BASE_URLS_KEY)
+        .unwrap_or_default();
+    let base_url = base_url_override(&overrides, name, &model.model_name)?;
+    if let Some(base_url) = &base_url {
+        tracing::info!("Sending requests for {} to {}", model.model_name, base_url);
+    }
+    let model = model.with_base_url(base_url);
+
     // We use Arc instead of Box to be able to clone for multiple async tasks
     match name {
         "openai" => Ok(Arc::new(OpenAiProvider::from_env(model)?)),
@@ -339,4 +456,56 @@ mod tests {
             env::set_var("GOOSE_LEAD_FALLBACK_TURNS", val);
         }
     }
+
+    #[test]
+    fn test_is_configured_model() {
+        let saved_provider = env::var("GOOSE_PROVIDER").ok();
+        let saved_model = env::var("GOOSE_MODEL").ok();
+        env::set_var("GOOSE_PROVIDER", "openai");
+        env::set_var("GOOSE_MODEL", "gpt-4o");
+
+        let gpt_4o = ModelConfig::new("gpt-4o".to_string());
+        assert!(is_configured_model("openai", &gpt_4o));
+        // A model or provider asked for explicitly keeps the tiers out of the way
+        assert!(!is_configured_model(
+            "openai",
+            &ModelConfig::new("o3".to_string())
+        ));
+        assert!(!is_configured_model("anthropic", &gpt_4o));
+
+        for (key, value) in [
+            ("GOOSE_PROVIDER", saved_provider),
+            ("GOOSE_MODEL", saved_model),
+        ] {
+            match value {
+                Some(val) => env::set_var(key, val),
+                None => env::remove_var(key),
+            }
+        }
+    }
+
+    #[test]
+    fn test_base_url_override() {
+        let overrides: HashMap<String, String> = [
+            ("gpt-4o", "https://llm-gateway.example.com"),
+            ("gpt-4.1", "ftp://llm-gateway.example.com"),
+            ("o3", "not a url"),
+        ]
+        .into_iter()
+        .map(|(model, url)| (model.to_string(), url.to_string()))
+        .collect();
+
+        assert_eq!(
+            base_url_override(&overrides, "openai", "gpt-4o").unwrap(),
+            Some("https://llm-gateway.example.com".to_string())
+        );
+        // Models without an entry keep the provider's host
+        assert_eq!(
+            base_url_override(&overrides, "openai", "gpt-4o-mini").unwrap(),
+            None
+        );
+        assert!(base_url_override(&overrides, "openai", "gpt-4.1").is_err());
+        assert!(base_url_override(&overrides, "openai", "o3").is_err());
+        assert!(base_url_override(&overrides, "databricks", "gpt-4o").is_err());
+    }
 }
@@ -9,6 +9,9 @@ use mcp_core::tool::{Tool, ToolCall};
 use serde_json::{json, Value};
 use std::collections::HashSet;
 
+/// Anthropic rejects thinking budgets below this
+pub const MIN_THINKING_BUDGET: i32 = 1024;
+
 /// Convert internal Message format to Anthropic's API message specification
 pub fn format_messages(messages: &[Message]) -> Vec<Value> {
     let mut anthropic_messages = Vec::new();
@@ -233,6 +236,139 @@ pub fn response_to_message(response: Value) -> Result<Message> {
     Ok(message)
 }
 
+/// Text streamed for a content block, reported as the stream arrives
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamDelta<'a> {
+    Text(&'a str),
+    Thinking(&'a str),
+}
+
+/// Builds a Messages API response from its server-sent events, so a streamed response is
+/// handled the same as a complete one,
+/// https://docs.anthropic.com/en/docs/build-with-claude/streaming
+#[derive(Debug, Default)]
+pub struct StreamAccumulator {
+    message: Option<Value>,
+    blocks: Vec<Value>,
+    // Tool inputs arrive as fragments of JSON
+    tool_inputs: Vec<String>,
+}
+
+impl StreamAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the data of one event, returning any text it added to the response
+    pub fn add_event<'a>(&mut self, event: &'a Value) -> Result<Option<StreamDelta<'a>>> {
+        let index = event.get("index").and_then(|i| i.as_u64()).unwrap_or(0) as usize;
+        match event.get("type").and_then(|t| t.as_str()) {
+            Some("message_start") => {
+                self.message = event.get("message").cloned();
+            }
+            Some("content_block_start") => {
+                let block = event
+                    .get("content_block")
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Missing content_block in content_block_start"))?;
+                if self.blocks.len() <= index {
+                    self.blocks.resize(index + 1, Value::Null);
+                    self.tool_inputs.resize(index + 1, String::new());
+                }
+                self.blocks[index] = block;
+            }
+            Some("content_block_delta") => {
+                let delta = event
+                    .get("delta")
+                    .ok_or_else(|| anyhow!("Missing delta in content_block_delta"))?;
+                let block = self.blocks.get_mut(index).ok_or_else(|| {
+                    anyhow!("Delta for content block {} before it started", index)
+                })?;
+                let field = match delta.get("type").and_then(|t| t.as_str()) {
+                    Some("text_delta") => "text",
+                    Some("thinking_delta") => "thinking",
+                    Some("signature_delta") => "signature",
+                    Some("input_json_delta") => {
+                        if let Some(json) = delta.get("partial_json").and_then(|j| j.as_str()) {
+                            self.tool_inputs[index].push_str(json);
+                        }
+                        return Ok(None);
+                    }
+                    _ => return Ok(None),
+                };
+                let text = delta
+                    .get(field)
+                    .and_then(|t| t.as_str())
+                    .unwrap_or_default();
+                let mut value = block
+                    .get(field)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                value.push_str(text);
+                block[field] = json!(value);
+                return Ok(match field {
+                    "text" => Some(StreamDelta::Text(text)),
+                    "thinking" => Some(StreamDelta::Thinking(text)),
+                    _ => None,
+                });
+            }
+            Some("content_block_stop") => {
+                let input = self
+                    .tool_inputs
+                    .get(index)
+                    .filter(|input| !input.is_empty());
+                if let (Some(block), Some(input)) = (self.blocks.get_mut(index), input) {
+                    block["input"] = serde_json::from_str(input)
+                        .map_err(|e| anyhow!("Invalid tool input for block {}: {}", index, e))?;
+                }
+            }
+            Some("message_delta") => {
+                let message = self
+                    .message
+                    .as_mut()
+                    .ok_or_else(|| anyhow!("message_delta before message_start"))?;
+                if let Some(delta) = event.get("delta").and_then(|d| d.as_object()) {
+                    for (key, value) in delta {
+                        message[key] = value.clone();
+                    }
+                }
+                // Usage in message_delta is cumulative
+                if let Some(usage) = event.get("usage").and_then(|u| u.as_object()) {
+                    for (key, value) in usage {
+                        message["usage"][key] = value.clone();
+                    }
+                }
+            }
+            Some("error") => {
+                let message = event
+                    .get("error")
+                    .and_then(|e| e.get("message"))
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("Unknown error");
+                return Err(anyhow!("Stream error: {}", message));
+            }
+            // ping and message_stop
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// The response as the API would have returned it without streaming
+    pub fn finish(self) -> Result<Value> {
+        let mut message = self
+            .message
+            .ok_or_else(|| anyhow!("Stream ended before message_start"))?;
+        message["content"] = Value::Array(
+            self.blocks
+                .into_iter()
+                .filter(|block| !block.is_null())
+                .collect(),
+        );
+        Ok(message)
+    }
+}
+
 /// Extract usage information from Anthropic's API response
 pub fn get_usage(data: &Value) -> Result<Usage> {
     // Extract usage data if available
@@ -274,6 +410,28 @@ pub fn get_usage(data: &Value) -> Result<Usage> {
     }
 }
 
+/// Models that can think before responding,
+/// https://docs.anthropic.com/en/docs/build-with-claude/extended-thinking#supported-models
+pub fn supports_extended_thinking(model_name: &str) -> bool {
+    const THINKING_MODELS: &[&str] = &[
+        "claude-3-7-sonnet",
+        "claude-sonnet-4",
+        "claude-opus-4",
+        "claude-4",
+    ];
+    THINKING_MODELS
+        .iter()
+        .any(|prefix| model_name.starts_with(prefix))
+}
+
+/// The thinking budget to request, if extended thinking is enabled and the model supports it
+pub fn thinking_budget(model_config: &ModelConfig) -> Option<i32> {
+    model_config
+        .thinking_budget
+        .filter(|_| supports_extended_thinking(&model_config.model_name))
+        .map(|budget| budget.max(MIN_THINKING_BUDGET))
+}
+
 /// Create a complete request payload for Anthropic's API
 pub fn create_request(
     model_config: &ModelConfig,
@@ -315,10 +473,11 @@ pub fn create_request(
             .insert("tools".to_string(), json!(tool_specs));
     }
 
-    // Add temperature if specified and not using extended thinking model
+    let thinking_budget = thinking_budget(model_config);
+
+    // Extended thinking doesn't support setting the temperature
     if let Some(temp) = model_config.temperature {
-        // Claude 3.7 models with thinking enabled don't support temperature
-        if !model_config.model_name.starts_with("claude-3-7-sonnet-") {
+        if thinking_budget.is_none() {
             payload
                 .as_object_mut()
                 .unwrap()
@@ -326,15 +485,10 @@ pub fn create_request(
         }
     }
 
-    // Add thinking parameters for claude-3-7-sonnet model
-    let is_thinking_enabled = std::env::var("CLAUDE_THINKING_ENABLED").is_ok();
-    if model_config.model_name.starts_with("claude-3-7-sonnet-") && is_thinking_enabled {
-        // Minimum budget_tokens is 1024
-        let budget_tokens = std::env::var("CLAUDE_THINKING_BUDGET")
-            .unwrap_or_else(|_| "16000".to_string())
-            .parse()
-            .unwrap_or(16000);
-
+    // https://docs.anthropic.com/en/docs/build-with-claude/extended-thinking
+    if let Some(budget_tokens) = thinking_budget {
+        // The thinking budget counts towards max_tokens, so extend it to leave the same room
+        // for the response itself
         payload
             .as_object_mut()
             .unwrap()
@@ -591,44 +745,91 @@ mod tests {
 
     #[test]
     fn test_create_request_with_thinking() -> Result<()> {
-        // Save the original env var value if it exists
-        let original_value = std::env::var("CLAUDE_THINKING_ENABLED").ok();
+        let model_config = ModelConfig::new("claude-3-7-sonnet-20250219".to_string())
+            .with_temperature(Some(0.5))
+            .with_max_tokens(Some(4000))
+            .with_thinking_budget(Some(500));
+        let system = "You are a helpful assistant.";
+        let messages = vec![Message::user().with_text("Hello")];
 
-        // Set the env var for this test
-        std::env::set_var("CLAUDE_THINKING_ENABLED", "true");
+        let payload = create_request(&model_config, system, &messages, &[])?;
 
-        // Execute the test
-        let result = (|| {
-            let model_config = ModelConfig::new("claude-3-7-sonnet-20250219".to_string());
-            let system = "You are a helpful assistant.";
-            let messages = vec![Message::user().with_text("Hello")];
-            let tools = vec![];
+        // Verify basic structure
+        assert_eq!(payload["model"], "claude-3-7-sonnet-20250219");
+        assert_eq!(payload["messages"][0]["role"], "user");
+        assert_eq!(payload["messages"][0]["content"][0]["text"], "Hello");
 
-            let payload = create_request(&model_config, system, &messages, &tools)?;
+        // Verify thinking parameters, the budget is raised to the minimum
+        assert_eq!(payload["thinking"]["type"], "enabled");
+        assert_eq!(payload["thinking"]["budget_tokens"], MIN_THINKING_BUDGET);
+        assert_eq!(payload["max_tokens"], 4000 + MIN_THINKING_BUDGET);
 
-            // Verify basic structure
-            assert_eq!(payload["model"], "claude-3-7-sonnet-20250219");
-            assert_eq!(payload["messages"][0]["role"], "user");
-            assert_eq!(payload["messages"][0]["content"][0]["text"], "Hello");
+        // Temperature should not be present with thinking
+        assert!(payload.get("temperature").is_none());
 
-            // Verify thinking parameters
-            assert!(payload.get("thinking").is_some());
-            assert_eq!(payload["thinking"]["type"], "enabled");
-            assert!(payload["thinking"]["budget_tokens"].as_i64().unwrap() >= 1024);
+        // Models without extended thinking ignore the budget
+        let model_config = ModelConfig::new("claude-3-5-sonnet-latest".to_string())
+            .with_temperature(Some(0.5))
+            .with_thinking_budget(Some(2048));
+        let payload = create_request(&model_config, system, &messages, &[])?;
+        assert!(payload.get("thinking").is_none());
+        assert_eq!(payload["temperature"], 0.5);
 
-            // Temperature should not be present for 3.7 models with thinking
-            assert!(payload.get("temperature").is_none());
+        Ok(())
+    }
 
-            Ok(())
-        })();
+    #[test]
+    fn test_stream_accumulator() -> Result<()> {
+        let events = vec![
+            json!({"type": "message_start", "message": {"id": "msg_1", "model": "claude-sonnet-4-20250514", "content": [], "usage": {"input_tokens": 12, "output_tokens": 1}}}),
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "thinking", "thinking": ""}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "Let me "}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "check."}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "signature_delta", "signature": "sig"}}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "ping"}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "text_delta", "text": "Checking"}}),
+            json!({"type": "content_block_stop", "index": 1}),
+            json!({"type": "content_block_start", "index": 2, "content_block": {"type": "tool_use", "id": "tool_1", "name": "shell", "input": {}}}),
+            json!({"type": "content_block_delta", "index": 2, "delta": {"type": "input_json_delta", "partial_json": "{\"command\": "}}),
+            json!({"type": "content_block_delta", "index": 2, "delta": {"type": "input_json_delta", "partial_json": "\"ls\"}"}}),
+            json!({"type": "content_block_stop", "index": 2}),
+            json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}, "usage": {"output_tokens": 42}}),
+            json!({"type": "message_stop"}),
+        ];
 
-        // Restore the original env var state
-        match original_value {
-            Some(val) => std::env::set_var("CLAUDE_THINKING_ENABLED", val),
-            None => std::env::remove_var("CLAUDE_THINKING_ENABLED"),
+        let mut accumulator = StreamAccumulator::new();
+        let mut deltas = Vec::new();
+        for event in &events {
+            if let Some(delta) = accumulator.add_event(event)? {
+                deltas.push(delta);
+            }
         }
+        assert_eq!(
+            deltas,
+            vec![
+                StreamDelta::Thinking("Let me "),
+                StreamDelta::Thinking("check."),
+                StreamDelta::Text("Checking"),
+            ]
+        );
+
+        let response = accumulator.finish()?;
+        assert_eq!(response["stop_reason"], "tool_use");
+        let usage = get_usage(&response)?;
+        assert_eq!(usage.input_tokens, Some(12));
+        assert_eq!(usage.output_tokens, Some(42));
 
-        // Return the test result
-        result
+        let message = response_to_message(response)?;
+        assert_eq!(message.content.len(), 3);
+        let thinking = message.content[0].as_thinking().unwrap();
+        assert_eq!(thinking.thinking, "Let me check.");
+        assert_eq!(thinking.signature, "sig");
+        assert_eq!(message.content[1].as_text(), Some("Checking"));
+        let request = message.content[2].as_tool_request().unwrap();
+        let call = request.tool_call.as_ref().unwrap();
+        assert_eq!(call.arguments, json!({"command": "ls"}));
+        Ok(())
     }
 }
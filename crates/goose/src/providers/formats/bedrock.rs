@@ -290,6 +290,7 @@ pub fn from_bedrock_usage(usage: &bedrock::TokenUsage) -> Usage {
         input_tokens: Some(usage.input_tokens),
         output_tokens: Some(usage.output_tokens),
         total_tokens: Some(usage.total_tokens),
+        reasoning_tokens: None,
     }
 }
 
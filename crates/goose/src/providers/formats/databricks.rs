@@ -317,15 +317,21 @@ pub fn response_to_message(response: Value) -> anyhow::Result<Message> {
     if let Some(tool_calls) = original.get("tool_calls") {
         if let Some(tool_calls_array) = tool_calls.as_array() {
             for tool_call in tool_calls_array {
-                let id = tool_call["id"].as_str().unwrap_or_default().to_string();
-                let function_name = tool_call["function"]["name"]
+                // Some serving endpoints leave out the id of a call
+                let id = tool_call["id"]
                     .as_str()
-                    .unwrap_or_default()
-                    .to_string();
-                let mut arguments = tool_call["function"]["arguments"]
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple()));
+                let function_name = tool_call["function"]["name"]
                     .as_str()
                     .unwrap_or_default()
                     .to_string();
+                // and send the arguments as an object rather than a JSON string
+                let mut arguments = match &tool_call["function"]["arguments"] {
+                    Value::Object(_) => tool_call["function"]["arguments"].to_string(),
+                    value => value.as_str().unwrap_or_default().to_string(),
+                };
                 // If arguments is empty, we will have invalid json parsing error later.
                 if arguments.is_empty() {
                     arguments = "{}".to_string();
@@ -969,6 +975,26 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_response_to_message_object_arguments_without_id() -> anyhow::Result<()> {
+        let mut response: Value = serde_json::from_str(OPENAI_TOOL_USE_RESPONSE)?;
+        let tool_call = &mut response["choices"][0]["message"]["tool_calls"][0];
+        tool_call["function"]["arguments"] = json!({"param": "value"});
+        tool_call.as_object_mut().unwrap().remove("id");
+
+        let message = response_to_message(response)?;
+
+        if let MessageContent::ToolRequest(request) = &message.content[0] {
+            assert!(request.id.starts_with("call_"));
+            let tool_call = request.tool_call.as_ref().unwrap();
+            assert_eq!(tool_call.arguments, json!({"param": "value"}));
+        } else {
+            panic!("Expected ToolRequest content");
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_create_request_gpt_4o() -> anyhow::Result<()> {
         // Test default medium reasoning effort for O3 model
@@ -980,6 +1006,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            base_url: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
@@ -1012,6 +1040,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            base_url: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
@@ -1045,6 +1075,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            base_url: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
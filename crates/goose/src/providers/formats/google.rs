@@ -371,6 +371,7 @@ mod tests {
                 "properties": params
             }),
             annotations: None,
+            output_schema: None,
         }
     }
 
@@ -515,6 +516,7 @@ mod tests {
                 "properties": {}
             }),
             annotations: None,
+            output_schema: None,
         }];
         let result = format_tools(&tools);
         assert_eq!(result.len(), 1);
@@ -305,7 +305,14 @@ pub fn get_usage(data: &Value) -> Result<Usage, ProviderError> {
             _ => None,
         });
 
-    Ok(Usage::new(input_tokens, output_tokens, total_tokens))
+    let reasoning_tokens = usage
+        .get("completion_tokens_details")
+        .and_then(|details| details.get("reasoning_tokens"))
+        .and_then(|v| v.as_i64())
+        .map(|v| v as i32);
+
+    Ok(Usage::new(input_tokens, output_tokens, total_tokens)
+        .with_reasoning_tokens(reasoning_tokens))
 }
 
 /// Validates and fixes tool schemas to ensure they have proper parameter structure.
@@ -354,6 +361,31 @@ fn ensure_valid_json_schema(schema: &mut Value) {
     }
 }
 
+const REASONING_EFFORTS: &[&str] = &["low", "medium", "high"];
+
+/// The parameter set a model accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFamily {
+    /// gpt-4o and other chat models
+    Chat,
+    /// o-series reasoning models, which take a reasoning effort and max_completion_tokens but
+    /// no sampling parameters, and a developer rather than a system message
+    Reasoning,
+}
+
+impl ModelFamily {
+    /// Detect the family from a model name, which may be prefixed with its vendor as in
+    /// `openai/o3`
+    pub fn detect(model_name: &str) -> Self {
+        let name = model_name.rsplit('/').next().unwrap_or(model_name);
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some('o'), Some(c)) if c.is_ascii_digit() => ModelFamily::Reasoning,
+            _ => ModelFamily::Chat,
+        }
+    }
+}
+
 pub fn create_request(
     model_config: &ModelConfig,
     system: &str,
@@ -367,28 +399,36 @@ pub fn create_request(
         ));
     }
 
-    let is_ox_model = model_config.model_name.starts_with("o");
+    let family = ModelFamily::detect(&model_config.model_name);
+    let is_ox_model = family == ModelFamily::Reasoning;
 
     // Only extract reasoning effort for O1/O3 models
     let (model_name, reasoning_effort) = if is_ox_model {
         let parts: Vec<&str> = model_config.model_name.split('-').collect();
         let last_part = parts.last().unwrap();
 
-        match *last_part {
-            "low" | "medium" | "high" => {
-                let base_name = parts[..parts.len() - 1].join("-");
-                (base_name, Some(last_part.to_string()))
-            }
-            _ => (
+        if REASONING_EFFORTS.contains(last_part) {
+            let base_name = parts[..parts.len() - 1].join("-");
+            (base_name, Some(last_part.to_string()))
+        } else {
+            (
                 model_config.model_name.to_string(),
                 Some("medium".to_string()),
-            ),
+            )
         }
     } else {
         // For non-O family models, use the model name as is and no reasoning effort
         (model_config.model_name.to_string(), None)
     };
 
+    // Reasoning models reject sampling parameters, say so rather than silently dropping them
+    if is_ox_model && model_config.temperature.is_some() {
+        return Err(anyhow!(
+            "{} is a reasoning model and does not support setting the temperature, unset GOOSE_TEMPERATURE to use it",
+            model_config.model_name
+        ));
+    }
+
     let system_message = json!({
         "role": if is_ox_model { "developer" } else { "system" },
         "content": system
@@ -425,17 +465,15 @@ pub fn create_request(
             .unwrap()
             .insert("tools".to_string(), json!(tools_spec));
     }
-    // o1, o3 models currently don't support temperature
-    if !is_ox_model {
-        if let Some(temp) = model_config.temperature {
-            payload
-                .as_object_mut()
-                .unwrap()
-                .insert("temperature".to_string(), json!(temp));
-        }
+    if let Some(temp) = model_config.temperature {
+        payload
+            .as_object_mut()
+            .unwrap()
+            .insert("temperature".to_string(), json!(temp));
     }
 
-    // o1 models use max_completion_tokens instead of max_tokens
+    // Reasoning models use max_completion_tokens instead of max_tokens, as the limit also
+    // covers reasoning tokens
     if let Some(tokens) = model_config.max_tokens {
         let key = if is_ox_model {
             "max_completion_tokens"
@@ -859,6 +897,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            base_url: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
@@ -891,6 +931,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            base_url: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
@@ -924,6 +966,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            base_url: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
@@ -945,4 +989,48 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_model_family_detection() {
+        assert_eq!(ModelFamily::detect("o1"), ModelFamily::Reasoning);
+        assert_eq!(ModelFamily::detect("o3-mini-high"), ModelFamily::Reasoning);
+        assert_eq!(ModelFamily::detect("o4-mini"), ModelFamily::Reasoning);
+        assert_eq!(ModelFamily::detect("openai/o3"), ModelFamily::Reasoning);
+        assert_eq!(ModelFamily::detect("gpt-4o"), ModelFamily::Chat);
+        assert_eq!(ModelFamily::detect("openai/gpt-4.1"), ModelFamily::Chat);
+        assert_eq!(ModelFamily::detect("olmo-2"), ModelFamily::Chat);
+    }
+
+    #[test]
+    fn test_create_request_reasoning_model_rejects_temperature() {
+        let model_config = ModelConfig::new("o3".to_string()).with_temperature(Some(0.2));
+        let err = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("does not support setting the temperature"));
+
+        let model_config = ModelConfig::new("gpt-4o".to_string()).with_temperature(Some(0.2));
+        let request =
+            create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi).unwrap();
+        assert_eq!(request["temperature"], json!(0.2_f32));
+    }
+
+    #[test]
+    fn test_get_usage_reasoning_tokens() -> anyhow::Result<()> {
+        let response = json!({
+            "usage": {
+                "prompt_tokens": 100,
+                "completion_tokens": 350,
+                "total_tokens": 450,
+                "completion_tokens_details": {"reasoning_tokens": 300}
+            }
+        });
+        let usage = get_usage(&response)?;
+        assert_eq!(usage.output_tokens, Some(350));
+        assert_eq!(usage.reasoning_tokens, Some(300));
+
+        let response = json!({"usage": {"prompt_tokens": 10, "completion_tokens": 5}});
+        assert_eq!(get_usage(&response)?.reasoning_tokens, None);
+        Ok(())
+    }
 }
@@ -356,7 +356,9 @@ impl GcpAuth {
     pub async fn new() -> Result<Self, AuthError> {
         Ok(Self {
             credentials: AdcCredentials::load().await?,
-            client: reqwest::Client::new(),
+            client: super::http::client().map_err(|e| {
+                AuthError::TokenExchange(format!("Failed to create HTTP client: {}", e))
+            })?,
             cached_token: Arc::new(RwLock::new(None)),
         })
     }
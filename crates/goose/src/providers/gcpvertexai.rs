@@ -10,6 +10,7 @@ use url::Url;
 use crate::message::Message;
 use crate::model::ModelConfig;
 use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
+use crate::providers::schema::SchemaCapabilities;
 
 use crate::providers::errors::ProviderError;
 use crate::providers::formats::gcpvertexai::{
@@ -150,7 +151,7 @@ impl GcpVertexAIProvider {
         let location = Self::determine_location(config)?;
         let host = format!("https://{}-aiplatform.googleapis.com", location);
 
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
             .build()?;
 
@@ -520,6 +521,11 @@ impl Provider for GcpVertexAIProvider {
     fn get_model_config(&self) -> ModelConfig {
         self.model.clone()
     }
+
+    /// Gemini models take Google's schema subset, Claude models take full JSON Schema
+    fn schema_capabilities(&self) -> SchemaCapabilities {
+        SchemaCapabilities::for_model(&self.model.model_name)
+    }
 }
 
 #[cfg(test)]
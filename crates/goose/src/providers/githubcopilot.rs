@@ -9,11 +9,12 @@ use serde_json::Value;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use super::base::{Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{Provider, ProviderMetadata, ProviderUsage, ResponseTiming, Usage};
 use super::errors::ProviderError;
 use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::schema::SchemaCapabilities;
 use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
 
 use crate::config::{Config, ConfigError};
@@ -123,7 +124,7 @@ impl Default for GithubCopilotProvider {
 
 impl GithubCopilotProvider {
     pub fn from_env(model: ModelConfig) -> Result<Self> {
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
         let cache = DiskCache::new();
@@ -136,7 +137,9 @@ impl GithubCopilotProvider {
         })
     }
 
-    async fn post(&self, mut payload: Value) -> Result<Value, ProviderError> {
+    /// Send the request, returning the response and, for streamed responses, when the first
+    /// content arrived
+    async fn post(&self, mut payload: Value) -> Result<(Value, Option<Instant>), ProviderError> {
         use crate::providers::utils_universal_openai_stream::{OAIStreamChunk, OAIStreamCollector};
         use futures_util::StreamExt;
         // Detect gpt-4.1 and stream
@@ -163,7 +166,9 @@ impl GithubCopilotProvider {
             .await?;
         if stream_only_model {
             let mut collector = OAIStreamCollector::new();
+            let mut journal = crate::session::partial::StreamJournal::for_current_session();
             let mut stream = response.bytes_stream();
+            let mut first_token = None;
             while let Some(chunk) = stream.next().await {
                 let chunk = chunk.map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
                 let text = String::from_utf8_lossy(&chunk);
@@ -177,17 +182,29 @@ impl GithubCopilotProvider {
                         break;
                     }
                     match serde_json::from_str::<OAIStreamChunk>(payload) {
-                        Ok(ch) => collector.add_chunk(&ch),
+                        Ok(ch) => {
+                            first_token.get_or_insert_with(Instant::now);
+                            if let Some(journal) = journal.as_mut() {
+                                ch.choices
+                                    .iter()
+                                    .filter_map(|choice| choice.delta.content.as_deref())
+                                    .for_each(|text| journal.push(text));
+                            }
+                            collector.add_chunk(&ch)
+                        }
                         Err(_) => continue,
                     }
                 }
             }
+            if let Some(journal) = journal {
+                journal.finish();
+            }
             let final_response = collector.build_response();
             let value = serde_json::to_value(final_response)
                 .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
-            Ok(value)
+            Ok((value, first_token))
         } else {
-            handle_response_openai_compat(response).await
+            Ok((handle_response_openai_compat(response).await?, None))
         }
     }
 
@@ -397,6 +414,10 @@ impl Provider for GithubCopilotProvider {
         self.model.clone()
     }
 
+    fn schema_capabilities(&self) -> SchemaCapabilities {
+        SchemaCapabilities::for_model(&self.model.model_name)
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -410,7 +431,9 @@ impl Provider for GithubCopilotProvider {
         let payload = create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
 
         // Make request
-        let response = self.post(payload.clone()).await?;
+        let start = Instant::now();
+        let (response, first_token) = self.post(payload.clone()).await?;
+        let elapsed = start.elapsed();
 
         // Parse response
         let message = response_to_message(response.clone())?;
@@ -424,6 +447,14 @@ impl Provider for GithubCopilotProvider {
         };
         let model = get_model(&response);
         emit_debug_trace(&self.model, &payload, &response, &usage);
-        Ok((message, ProviderUsage::new(model, usage)))
+        let timing = ResponseTiming::new(
+            first_token.map(|t| t.duration_since(start)),
+            elapsed,
+            usage.output_tokens,
+        );
+        Ok((
+            message,
+            ProviderUsage::new(model, usage).with_timing(timing),
+        ))
     }
 }
@@ -3,6 +3,7 @@ use crate::message::Message;
 use crate::model::ModelConfig;
 use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
 use crate::providers::formats::google::{create_request, get_usage, response_to_message};
+use crate::providers::schema::SchemaCapabilities;
 use crate::providers::utils::{
     emit_debug_trace, handle_response_google_compat, unescape_json_values,
 };
@@ -47,11 +48,13 @@ impl GoogleProvider {
     pub fn from_env(model: ModelConfig) -> Result<Self> {
         let config = crate::config::Config::global();
         let api_key: String = config.get_secret("GOOGLE_API_KEY")?;
-        let host: String = config
-            .get_param("GOOGLE_HOST")
-            .unwrap_or_else(|_| GOOGLE_API_HOST.to_string());
+        let host: String = model.base_url.clone().unwrap_or_else(|| {
+            config
+                .get_param("GOOGLE_HOST")
+                .unwrap_or_else(|_| GOOGLE_API_HOST.to_string())
+        });
 
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
@@ -143,6 +146,10 @@ impl Provider for GoogleProvider {
         self.model.clone()
     }
 
+    fn schema_capabilities(&self) -> SchemaCapabilities {
+        SchemaCapabilities::GOOGLE
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -38,11 +38,13 @@ impl GroqProvider {
     pub fn from_env(model: ModelConfig) -> Result<Self> {
         let config = crate::config::Config::global();
         let api_key: String = config.get_secret("GROQ_API_KEY")?;
-        let host: String = config
-            .get_param("GROQ_HOST")
-            .unwrap_or_else(|_| GROQ_API_HOST.to_string());
+        let host: String = model.base_url.clone().unwrap_or_else(|| {
+            config
+                .get_param("GROQ_HOST")
+                .unwrap_or_else(|_| GROQ_API_HOST.to_string())
+        });
 
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
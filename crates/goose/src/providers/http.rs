@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::tls::Version;
+use reqwest::{Certificate, Client, ClientBuilder, NoProxy, Proxy, StatusCode};
+
+use crate::config::Config;
+
+/// Proxy for every provider request, e.g. http://proxy.corp:8080. Without it the
+/// standard HTTP_PROXY, HTTPS_PROXY and NO_PROXY environment variables apply.
+pub const HTTP_PROXY_KEY: &str = "GOOSE_HTTP_PROXY";
+/// Comma separated hosts that bypass GOOSE_HTTP_PROXY, in the NO_PROXY format
+pub const NO_PROXY_KEY: &str = "GOOSE_NO_PROXY";
+/// PEM file with CA certificates to trust in addition to the system's, for networks that
+/// inspect TLS traffic
+pub const CA_BUNDLE_KEY: &str = "GOOSE_CA_BUNDLE";
+/// Lowest TLS version to accept, "1.2" or "1.3"
+pub const TLS_MIN_VERSION_KEY: &str = "GOOSE_TLS_MIN_VERSION";
+/// Skip certificate verification entirely, only as a last resort on trusted networks
+pub const TLS_INSECURE_KEY: &str = "GOOSE_TLS_INSECURE";
+
+/// How provider clients reach the network, read from the config so that every provider
+/// goes through the same proxy and trusts the same certificates
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpSettings {
+    pub proxy: Option<String>,
+    pub no_proxy: Option<String>,
+    pub ca_bundle: Option<PathBuf>,
+    pub min_tls_version: Option<String>,
+    pub accept_invalid_certs: bool,
+}
+
+impl HttpSettings {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let non_empty = |key: &str| {
+            config
+                .get_param::<String>(key)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        Self {
+            proxy: non_empty(HTTP_PROXY_KEY),
+            no_proxy: non_empty(NO_PROXY_KEY),
+            ca_bundle: non_empty(CA_BUNDLE_KEY).map(PathBuf::from),
+            min_tls_version: non_empty(TLS_MIN_VERSION_KEY),
+            accept_invalid_certs: config.get_param(TLS_INSECURE_KEY).unwrap_or(false),
+        }
+    }
+
+    /// Configure a client builder with the proxy and TLS settings
+    pub fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder> {
+        if let Some(proxy) = &self.proxy {
+            let proxy = Proxy::all(proxy)
+                .with_context(|| format!("Invalid {} '{}'", HTTP_PROXY_KEY, proxy))?
+                .no_proxy(self.no_proxy.as_deref().and_then(NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &self.ca_bundle {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Failed to read {} {}", CA_BUNDLE_KEY, path.display()))?;
+            let certificates = Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("Invalid certificates in {}", path.display()))?;
+            if certificates.is_empty() {
+                anyhow::bail!("No certificates found in {}", path.display());
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(version) = &self.min_tls_version {
+            let version = match version.trim() {
+                "1.2" => Version::TLS_1_2,
+                "1.3" => Version::TLS_1_3,
+                other => anyhow::bail!(
+                    "Unsupported {} '{}', use 1.2 or 1.3",
+                    TLS_MIN_VERSION_KEY,
+                    other
+                ),
+            };
+            builder = builder.min_tls_version(version);
+        }
+
+        if self.accept_invalid_certs {
+            tracing::warn!(
+                "{} is set, provider certificates are not verified",
+                TLS_INSECURE_KEY
+            );
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(builder)
+    }
+}
+
+/// A client builder for provider requests, with the configured proxy and TLS settings
+pub fn client_builder() -> Result<ClientBuilder> {
+    HttpSettings::from_config().apply(Client::builder())
+}
+
+/// A client for provider requests, with the configured proxy and TLS settings
+pub fn client() -> Result<Client> {
+    Ok(client_builder()?.build()?)
+}
+
+/// Whether `url` can be reached with the configured settings. Any HTTP response counts,
+/// an unauthenticated request is expected to be refused.
+pub async fn check_connectivity(url: &str, timeout: Duration) -> Result<StatusCode> {
+    let client = client_builder()?.timeout(timeout).build()?;
+    let response = client.get(url).send().await.map_err(|e| {
+        // reqwest's own message hides the cause, such as an untrusted certificate
+        let mut message = e.to_string();
+        let mut source = std::error::Error::source(&e);
+        while let Some(cause) = source {
+            message.push_str(&format!(": {}", cause));
+            source = cause.source();
+        }
+        anyhow::anyhow!(message)
+    })?;
+    Ok(response.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_settings() {
+        let settings = HttpSettings {
+            proxy: Some("http://proxy.corp:8080".to_string()),
+            no_proxy: Some("localhost,.internal".to_string()),
+            min_tls_version: Some("1.2".to_string()),
+            ..Default::default()
+        };
+        assert!(settings.apply(Client::builder()).unwrap().build().is_ok());
+
+        let bad_version = HttpSettings {
+            min_tls_version: Some("1.0".to_string()),
+            ..Default::default()
+        };
+        assert!(bad_version.apply(Client::builder()).is_err());
+
+        let missing_bundle = HttpSettings {
+            ca_bundle: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..Default::default()
+        };
+        assert!(missing_bundle.apply(Client::builder()).is_err());
+    }
+
+    #[test]
+    fn test_empty_ca_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        std::fs::write(&path, "not a certificate").unwrap();
+        let settings = HttpSettings {
+            ca_bundle: Some(path),
+            ..Default::default()
+        };
+        assert!(settings.apply(Client::builder()).is_err());
+    }
+}
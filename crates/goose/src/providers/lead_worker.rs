@@ -5,6 +5,7 @@ use tokio::sync::Mutex;
 
 use super::base::{LeadWorkerProviderTrait, Provider, ProviderMetadata, ProviderUsage};
 use super::errors::ProviderError;
+use super::schema::SchemaCapabilities;
 use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use mcp_core::{tool::Tool, Content};
@@ -314,6 +315,13 @@ impl Provider for LeadWorkerProvider {
         self.lead_provider.get_model_config()
     }
 
+    /// Tools are rewritten once for both models, so only what both accept is used
+    fn schema_capabilities(&self) -> SchemaCapabilities {
+        self.lead_provider
+            .schema_capabilities()
+            .intersect(&self.worker_provider.schema_capabilities())
+    }
+
     async fn complete(
         &self,
         system: &str,
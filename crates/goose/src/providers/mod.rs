@@ -13,12 +13,16 @@ pub mod gcpvertexai;
 pub mod githubcopilot;
 pub mod google;
 pub mod groq;
+pub mod http;
 pub mod lead_worker;
 pub mod oauth;
 pub mod ollama;
 pub mod openai;
 pub mod openrouter;
+pub mod pricing;
+pub mod schema;
 pub mod snowflake;
+pub mod tiered;
 pub mod toolshim;
 pub mod utils;
 pub mod utils_universal_openai_stream;
@@ -8,7 +8,8 @@ use anyhow::Result;
 use async_trait::async_trait;
 use mcp_core::tool::Tool;
 use reqwest::Client;
-use serde_json::Value;
+use serde::Deserialize;
+use serde_json::{json, Value};
 use std::time::Duration;
 use url::Url;
 
@@ -18,6 +19,27 @@ pub const OLLAMA_DEFAULT_MODEL: &str = "qwen2.5";
 // Ollama can run many models, we only provide the default
 pub const OLLAMA_KNOWN_MODELS: &[&str] = &[OLLAMA_DEFAULT_MODEL];
 pub const OLLAMA_DOC_URL: &str = "https://ollama.com/library";
+/// When true, models missing from the local Ollama install are pulled automatically
+pub const OLLAMA_AUTO_PULL_KEY: &str = "OLLAMA_AUTO_PULL";
+
+/// A progress update streamed by Ollama while pulling a model
+#[derive(Debug, Clone, Deserialize)]
+pub struct PullProgress {
+    pub status: String,
+    pub digest: Option<String>,
+    pub total: Option<u64>,
+    pub completed: Option<u64>,
+}
+
+/// Whether `model` is in the list of local models, treating a missing tag as `latest`
+fn is_model_available(models: &[String], model: &str) -> bool {
+    let wanted = if model.contains(':') {
+        model.to_string()
+    } else {
+        format!("{}:latest", model)
+    };
+    models.iter().any(|m| m == model || *m == wanted)
+}
 
 #[derive(serde::Serialize)]
 pub struct OllamaProvider {
@@ -37,11 +59,13 @@ impl Default for OllamaProvider {
 impl OllamaProvider {
     pub fn from_env(model: ModelConfig) -> Result<Self> {
         let config = crate::config::Config::global();
-        let host: String = config
-            .get_param("OLLAMA_HOST")
-            .unwrap_or_else(|_| OLLAMA_HOST.to_string());
+        let host: String = model.base_url.clone().unwrap_or_else(|| {
+            config
+                .get_param("OLLAMA_HOST")
+                .unwrap_or_else(|_| OLLAMA_HOST.to_string())
+        });
 
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
@@ -82,15 +106,135 @@ impl OllamaProvider {
 
     async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
         // TODO: remove this later when the UI handles provider config refresh
-        let base_url = self.get_base_url()?;
+        let url = self.endpoint("v1/chat/completions")?;
 
-        let url = base_url.join("v1/chat/completions").map_err(|e| {
+        let response = self
+            .client
+            .post(url)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| self.map_request_error(e))?;
+
+        handle_response_openai_compat(response)
+            .await
+            .map_err(|e| match e {
+                ProviderError::RequestFailed(msg) if msg.contains("not found") => {
+                    ProviderError::RequestFailed(format!(
+                        "{msg}. Pull the model with `ollama pull {}`",
+                        self.model.model_name
+                    ))
+                }
+                e => e,
+            })
+    }
+
+    fn endpoint(&self, path: &str) -> Result<Url, ProviderError> {
+        self.get_base_url()?.join(path).map_err(|e| {
             ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
-        })?;
+        })
+    }
+
+    /// Point users at `ollama serve` when nothing is listening on the configured host
+    fn map_request_error(&self, error: reqwest::Error) -> ProviderError {
+        if error.is_connect() {
+            ProviderError::RequestFailed(format!(
+                "Could not connect to Ollama at {}. Make sure Ollama is running, for example with `ollama serve`",
+                self.host
+            ))
+        } else {
+            error.into()
+        }
+    }
+
+    /// List the models available in the local Ollama install
+    pub async fn list_local_models(&self) -> Result<Vec<String>, ProviderError> {
+        let response = self
+            .client
+            .get(self.endpoint("api/tags")?)
+            .send()
+            .await
+            .map_err(|e| self.map_request_error(e))?;
+        let json: Value = response.json().await?;
+        Ok(json
+            .get("models")
+            .and_then(Value::as_array)
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m.get("name").and_then(Value::as_str))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
 
-        let response = self.client.post(url).json(&payload).send().await?;
+    /// Pull the configured model, reporting each progress update to `on_progress`
+    pub async fn pull_model<F>(&self, mut on_progress: F) -> Result<(), ProviderError>
+    where
+        F: FnMut(&PullProgress),
+    {
+        let mut response = self
+            .client
+            .post(self.endpoint("api/pull")?)
+            .json(&json!({ "model": self.model.model_name, "stream": true }))
+            .send()
+            .await
+            .map_err(|e| self.map_request_error(e))?;
+        if !response.status().is_success() {
+            return Err(ProviderError::RequestFailed(format!(
+                "Failed to pull model {}: {}",
+                self.model.model_name,
+                response.text().await.unwrap_or_default()
+            )));
+        }
 
-        handle_response_openai_compat(response).await
+        // Progress arrives as newline delimited JSON
+        let mut buffer = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let Ok(value) = serde_json::from_slice::<Value>(&line) else {
+                    continue;
+                };
+                if let Some(error) = value.get("error").and_then(Value::as_str) {
+                    return Err(ProviderError::RequestFailed(format!(
+                        "Failed to pull model {}: {}",
+                        self.model.model_name, error
+                    )));
+                }
+                if let Ok(progress) = serde_json::from_value::<PullProgress>(value) {
+                    on_progress(&progress);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the configured model is available locally, pulling it when
+    /// `OLLAMA_AUTO_PULL` is enabled
+    pub async fn ensure_model_available<F>(&self, on_progress: F) -> Result<(), ProviderError>
+    where
+        F: FnMut(&PullProgress),
+    {
+        let models = self.list_local_models().await?;
+        if is_model_available(&models, &self.model.model_name) {
+            return Ok(());
+        }
+
+        let auto_pull = crate::config::Config::global()
+            .get_param::<bool>(OLLAMA_AUTO_PULL_KEY)
+            .unwrap_or(false);
+        if !auto_pull {
+            return Err(ProviderError::RequestFailed(format!(
+                "Model '{}' is not available in Ollama. Pull it with `ollama pull {}` or set {}=true to pull it automatically",
+                self.model.model_name, self.model.model_name, OLLAMA_AUTO_PULL_KEY
+            )));
+        }
+
+        tracing::info!("Pulling Ollama model {}", self.model.model_name);
+        self.pull_model(on_progress).await
     }
 }
 
@@ -117,6 +261,10 @@ impl Provider for OllamaProvider {
         self.model.clone()
     }
 
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        Ok(Some(self.list_local_models().await?))
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -151,3 +299,18 @@ impl Provider for OllamaProvider {
         Ok((message, ProviderUsage::new(model, usage)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_model_available_defaults_to_latest_tag() {
+        let models = vec!["qwen2.5:latest".to_string(), "llama3.2:3b".to_string()];
+        assert!(is_model_available(&models, "qwen2.5"));
+        assert!(is_model_available(&models, "qwen2.5:latest"));
+        assert!(is_model_available(&models, "llama3.2:3b"));
+        assert!(!is_model_available(&models, "llama3.2"));
+        assert!(!is_model_available(&models, "mistral"));
+    }
+}
@@ -9,6 +9,7 @@ use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
 use super::embedding::{EmbeddingCapable, EmbeddingRequest, EmbeddingResponse};
 use super::errors::ProviderError;
 use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::schema::{mark_strict, SchemaCapabilities};
 use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -27,6 +28,25 @@ pub const OPEN_AI_KNOWN_MODELS: &[&str] = &[
 
 pub const OPEN_AI_DOC_URL: &str = "https://platform.openai.com/docs/models";
 
+/// A config value sent as a header, checked up front so a stray newline or quote fails with
+/// the key's name rather than on the first request
+fn header_param(key: &str) -> Result<Option<String>> {
+    let Ok(value) = crate::config::Config::global().get_param::<String>(key) else {
+        return Ok(None);
+    };
+    let value = value.trim().to_string();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    if reqwest::header::HeaderValue::from_str(&value).is_err() {
+        anyhow::bail!(
+            "{} contains characters that aren't allowed in a header",
+            key
+        );
+    }
+    Ok(Some(value))
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct OpenAiProvider {
     #[serde(skip)]
@@ -51,21 +71,23 @@ impl OpenAiProvider {
     pub fn from_env(model: ModelConfig) -> Result<Self> {
         let config = crate::config::Config::global();
         let api_key: String = config.get_secret("OPENAI_API_KEY")?;
-        let host: String = config
-            .get_param("OPENAI_HOST")
-            .unwrap_or_else(|_| "https://api.openai.com".to_string());
+        let host: String = model.base_url.clone().unwrap_or_else(|| {
+            config
+                .get_param("OPENAI_HOST")
+                .unwrap_or_else(|_| "https://api.openai.com".to_string())
+        });
         let base_path: String = config
             .get_param("OPENAI_BASE_PATH")
             .unwrap_or_else(|_| "v1/chat/completions".to_string());
-        let organization: Option<String> = config.get_param("OPENAI_ORGANIZATION").ok();
-        let project: Option<String> = config.get_param("OPENAI_PROJECT").ok();
+        let organization = header_param("OPENAI_ORGANIZATION")?;
+        let project = header_param("OPENAI_PROJECT")?;
         let custom_headers: Option<HashMap<String, String>> = config
             .get_secret("OPENAI_CUSTOM_HEADERS")
             .or_else(|_| config.get_param("OPENAI_CUSTOM_HEADERS"))
             .ok()
             .map(parse_custom_headers);
         let timeout_secs: u64 = config.get_param("OPENAI_TIMEOUT").unwrap_or(600);
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(timeout_secs))
             .build()?;
 
@@ -149,6 +171,10 @@ impl Provider for OpenAiProvider {
         self.model.clone()
     }
 
+    fn schema_capabilities(&self) -> SchemaCapabilities {
+        SchemaCapabilities::openai()
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -159,7 +185,11 @@ impl Provider for OpenAiProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        let mut payload =
+            create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        if self.schema_capabilities().strict {
+            mark_strict(&mut payload);
+        }
 
         // Make request
         let response = self.post(payload.clone()).await?;
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::base::Usage;
+use crate::config::Config;
+
+/// Config key for per-model prices in USD per million tokens, keyed by `provider/model` or by
+/// model name, e.g. `{"gpt-4o": {"input": 2.5, "output": 10}}`
+pub const MODEL_PRICING_KEY: &str = "GOOSE_MODEL_PRICING";
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl ModelPricing {
+    /// Estimated cost in USD of a request with `usage`, None when the token counts are unknown
+    pub fn estimate_cost(&self, usage: &Usage) -> Option<f64> {
+        let input = usage.input_tokens? as f64 * self.input;
+        let output = usage.output_tokens? as f64 * self.output;
+        Some((input + output) / 1_000_000.0)
+    }
+}
+
+/// Configured model prices, empty when none are set
+pub fn get_model_pricing() -> HashMap<String, ModelPricing> {
+    Config::global()
+        .get_param(MODEL_PRICING_KEY)
+        .unwrap_or_default()
+}
+
+/// Find the price for a model, preferring a `provider/model` entry over a bare model name
+pub fn lookup_pricing<'a>(
+    pricing: &'a HashMap<String, ModelPricing>,
+    provider: &str,
+    model: &str,
+) -> Option<&'a ModelPricing> {
+    pricing
+        .get(&format!("{}/{}", provider, model))
+        .or_else(|| pricing.get(model))
+}
@@ -0,0 +1,746 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use mcp_core::tool::Tool;
+use once_cell::sync::Lazy;
+use serde_json::{json, Map, Value};
+
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+
+/// Config key turning on strict tool schemas for providers that support them, where the
+/// provider guarantees tool call arguments match the schema
+pub const STRICT_TOOL_SCHEMAS_KEY: &str = "GOOSE_STRICT_TOOL_SCHEMAS";
+
+/// How deep `$ref`s are inlined before giving up on a recursive schema
+const MAX_REF_DEPTH: usize = 8;
+
+/// Keywords strict mode rejects rather than ignores
+const STRICT_UNSUPPORTED_KEYWORDS: &[&str] = &[
+    "default",
+    "patternProperties",
+    "minProperties",
+    "maxProperties",
+    "not",
+    "if",
+    "then",
+    "else",
+    "dependentRequired",
+    "dependentSchemas",
+];
+
+const COMBINATORS: &[&str] = &["oneOf", "anyOf", "allOf"];
+
+/// Lossy rewrites already logged, so each is only warned about once
+static WARNED: Lazy<Mutex<HashSet<String>>> = Lazy::new(Default::default);
+
+/// Whether strict tool schemas are turned on
+pub fn strict_tool_schemas() -> bool {
+    Config::global()
+        .get_param::<bool>(STRICT_TOOL_SCHEMAS_KEY)
+        .unwrap_or(false)
+}
+
+/// The JSON Schema constructs a provider accepts in tool parameters. Anything else is
+/// rewritten into a supported equivalent before the tools are sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaCapabilities {
+    /// Which of oneOf, anyOf and allOf are understood
+    pub combinators: Cow<'static, [&'static str]>,
+    /// `$ref`s to `$defs` or `definitions`
+    pub refs: bool,
+    /// A list of types, such as `["string", "null"]`
+    pub type_arrays: bool,
+    /// The string formats understood, `None` for any
+    pub formats: Option<Cow<'static, [&'static str]>>,
+    /// Rewrite every object for strict mode, where all properties are required and no others
+    /// are allowed
+    pub strict: bool,
+}
+
+impl SchemaCapabilities {
+    /// Full JSON Schema, sent as is
+    pub const FULL: Self = Self {
+        combinators: Cow::Borrowed(COMBINATORS),
+        refs: true,
+        type_arrays: true,
+        formats: None,
+        strict: false,
+    };
+
+    /// The OpenAPI subset Gemini accepts
+    pub const GOOGLE: Self = Self {
+        combinators: Cow::Borrowed(&[]),
+        refs: false,
+        type_arrays: false,
+        formats: Some(Cow::Borrowed(&["enum", "date-time"])),
+        strict: false,
+    };
+
+    /// OpenAI's structured outputs subset
+    pub const OPENAI_STRICT: Self = Self {
+        combinators: Cow::Borrowed(&["anyOf"]),
+        refs: true,
+        type_arrays: true,
+        formats: Some(Cow::Borrowed(&[
+            "date-time",
+            "time",
+            "date",
+            "duration",
+            "email",
+            "hostname",
+            "ipv4",
+            "ipv6",
+            "uuid",
+        ])),
+        strict: true,
+    };
+
+    /// OpenAI accepts any schema, and the structured outputs subset in strict mode
+    pub fn openai() -> Self {
+        if strict_tool_schemas() {
+            Self::OPENAI_STRICT
+        } else {
+            Self::FULL
+        }
+    }
+
+    /// For providers serving models from several vendors, going by the model name
+    pub fn for_model(model_name: &str) -> Self {
+        if model_name.to_lowercase().contains("gemini") {
+            Self::GOOGLE
+        } else {
+            Self::FULL
+        }
+    }
+
+    /// What both accept, for providers that switch between two others
+    pub fn intersect(&self, other: &Self) -> Self {
+        let formats = match (&self.formats, &other.formats) {
+            (None, formats) | (formats, None) => formats.clone(),
+            (Some(a), Some(b)) => Some(common(a, b)),
+        };
+        Self {
+            combinators: common(&self.combinators, &other.combinators),
+            refs: self.refs && other.refs,
+            type_arrays: self.type_arrays && other.type_arrays,
+            formats,
+            strict: self.strict && other.strict,
+        }
+    }
+
+    fn supports(&self, combinator: &str) -> bool {
+        self.combinators.contains(&combinator)
+    }
+}
+
+/// The entries found in both lists
+fn common(a: &[&'static str], b: &[&'static str]) -> Cow<'static, [&'static str]> {
+    Cow::Owned(
+        a.iter()
+            .filter(|entry| b.contains(*entry))
+            .copied()
+            .collect(),
+    )
+}
+
+/// Rewrite the tools' input schemas into what `caps` accepts, warning once about each rewrite
+/// that loses information
+pub fn lower_tools(tools: &[Tool], caps: &SchemaCapabilities) -> Vec<Tool> {
+    if *caps == SchemaCapabilities::FULL {
+        return tools.to_vec();
+    }
+    tools
+        .iter()
+        .map(|tool| {
+            let (input_schema, warnings) = lower_schema(&tool.input_schema, caps);
+            for warning in warnings {
+                warn_once(format!("{}: {}", tool.name, warning));
+            }
+            Tool {
+                input_schema,
+                ..tool.clone()
+            }
+        })
+        .collect()
+}
+
+fn warn_once(warning: String) {
+    let mut warned = WARNED.lock().unwrap_or_else(|e| e.into_inner());
+    if warned.insert(warning.clone()) {
+        tracing::warn!("Tool schema rewritten for the provider, {}", warning);
+    }
+}
+
+/// Rewrite `schema` into what `caps` accepts, returning the lossy rewrites made
+pub fn lower_schema(schema: &Value, caps: &SchemaCapabilities) -> (Value, Vec<String>) {
+    let mut lowering = Lowering {
+        caps,
+        root: schema,
+        warnings: Vec::new(),
+    };
+    let lowered = lowering.lower(schema, "", 0);
+    (lowered, lowering.warnings)
+}
+
+struct Lowering<'a> {
+    caps: &'a SchemaCapabilities,
+    root: &'a Value,
+    warnings: Vec<String>,
+}
+
+impl Lowering<'_> {
+    fn warn(&mut self, path: &str, message: String) {
+        let at = if path.is_empty() { "the root" } else { path };
+        self.warnings.push(format!("{} at {}", message, at));
+    }
+
+    fn lower(&mut self, schema: &Value, path: &str, depth: usize) -> Value {
+        let Value::Object(schema) = schema else {
+            return schema.clone();
+        };
+        let mut map = schema.clone();
+
+        if !self.caps.refs {
+            if let Some(Value::String(reference)) = map.remove("$ref") {
+                let resolved = resolve_ref(self.root, &reference).and_then(Value::as_object);
+                match resolved {
+                    Some(resolved) if depth < MAX_REF_DEPTH => {
+                        for (key, value) in resolved {
+                            map.entry(key.clone()).or_insert_with(|| value.clone());
+                        }
+                        return self.lower(&Value::Object(map), path, depth + 1);
+                    }
+                    _ => {
+                        self.warn(path, format!("{} could not be inlined", reference));
+                        map.entry("type").or_insert_with(|| json!("object"));
+                    }
+                }
+            }
+            map.remove("$defs");
+            map.remove("definitions");
+        }
+
+        if map.contains_key("allOf") && !self.caps.supports("allOf") {
+            if let Some(Value::Array(variants)) = map.remove("allOf") {
+                for (i, variant) in variants.iter().enumerate() {
+                    let variant = self.lower(variant, &format!("{}/allOf/{}", path, i), depth);
+                    merge_all_of(&mut map, &variant);
+                }
+            }
+        }
+
+        for combinator in ["oneOf", "anyOf"] {
+            if !map.contains_key(combinator) || self.caps.supports(combinator) {
+                continue;
+            }
+            let Some(Value::Array(variants)) = map.remove(combinator) else {
+                continue;
+            };
+            if self.caps.supports("anyOf") && !map.contains_key("anyOf") {
+                // Every value valid for exactly one option is valid for at least one
+                map.insert("anyOf".to_string(), Value::Array(variants));
+                continue;
+            }
+            let variants: Vec<Value> = variants
+                .iter()
+                .enumerate()
+                .map(|(i, v)| self.lower(v, &format!("{}/{}/{}", path, combinator, i), depth))
+                .collect();
+            self.flatten_options(&mut map, combinator, variants, path);
+        }
+
+        if !self.caps.type_arrays {
+            if let Some(Value::Array(types)) = map.get("type").cloned() {
+                let nullable = types.iter().any(|t| t == "null");
+                let types: Vec<&Value> = types.iter().filter(|t| *t != "null").collect();
+                if types.len() > 1 {
+                    let names: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                    self.warn(
+                        path,
+                        format!("types {} narrowed to the first", names.join(", ")),
+                    );
+                }
+                match types.first() {
+                    Some(first) => map.insert("type".to_string(), (*first).clone()),
+                    None => map.remove("type"),
+                };
+                if nullable {
+                    map.insert("nullable".to_string(), Value::Bool(true));
+                }
+            }
+        }
+
+        if let (Some(formats), Some(Value::String(format))) =
+            (self.caps.formats.as_deref(), map.get("format").cloned())
+        {
+            if !formats.contains(&format.as_str()) {
+                map.remove("format");
+                append_description(&mut map, &format!("(format: {})", format));
+                self.warn(path, format!("unsupported format '{}' removed", format));
+            }
+        }
+
+        if self.caps.strict {
+            for keyword in STRICT_UNSUPPORTED_KEYWORDS {
+                if map.remove(*keyword).is_some() {
+                    self.warn(path, format!("'{}' removed for strict mode", keyword));
+                }
+            }
+        }
+
+        if let Some(Value::Object(properties)) = map.get("properties").cloned() {
+            let properties: Map<String, Value> = properties
+                .iter()
+                .map(|(key, property)| {
+                    let lowered = self.lower(property, &format!("{}/{}", path, key), depth);
+                    (key.clone(), lowered)
+                })
+                .collect();
+            map.insert("properties".to_string(), Value::Object(properties));
+        }
+        match map.get("items").cloned() {
+            Some(items @ Value::Object(_)) => {
+                let items = self.lower(&items, &format!("{}/items", path), depth);
+                map.insert("items".to_string(), items);
+            }
+            Some(Value::Array(items)) => {
+                let items = items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| self.lower(item, &format!("{}/items/{}", path, i), depth))
+                    .collect();
+                map.insert("items".to_string(), Value::Array(items));
+            }
+            _ => {}
+        }
+        if let Some(additional @ Value::Object(_)) = map.get("additionalProperties").cloned() {
+            let additional = self.lower(
+                &additional,
+                &format!("{}/additionalProperties", path),
+                depth,
+            );
+            map.insert("additionalProperties".to_string(), additional);
+        }
+        for combinator in COMBINATORS {
+            if let Some(Value::Array(variants)) = map.get(*combinator).cloned() {
+                let variants = variants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| self.lower(v, &format!("{}/{}/{}", path, combinator, i), depth))
+                    .collect();
+                map.insert(combinator.to_string(), Value::Array(variants));
+            }
+        }
+        for defs in ["$defs", "definitions"] {
+            if let Some(Value::Object(definitions)) = map.get(defs).cloned() {
+                let definitions: Map<String, Value> = definitions
+                    .iter()
+                    .map(|(key, definition)| {
+                        let path = format!("{}/{}/{}", path, defs, key);
+                        (key.clone(), self.lower(definition, &path, depth))
+                    })
+                    .collect();
+                map.insert(defs.to_string(), Value::Object(definitions));
+            }
+        }
+
+        if self.caps.strict && is_object_schema(&map) {
+            make_strict(&mut map);
+        }
+        Value::Object(map)
+    }
+
+    /// Replace a oneOf or anyOf the provider doesn't understand with a single schema
+    fn flatten_options(
+        &mut self,
+        map: &mut Map<String, Value>,
+        combinator: &str,
+        variants: Vec<Value>,
+        path: &str,
+    ) {
+        let nullable = variants
+            .iter()
+            .any(|v| v.get("type") == Some(&json!("null")));
+        let options: Vec<&Map<String, Value>> = variants
+            .iter()
+            .filter(|v| v.get("type") != Some(&json!("null")))
+            .filter_map(Value::as_object)
+            .collect();
+
+        let enum_values = |option: &Map<String, Value>| match option.get("enum") {
+            Some(Value::Array(values)) => Some(values.clone()),
+            _ => option.get("const").map(|value| vec![value.clone()]),
+        };
+        let first_type = options
+            .first()
+            .and_then(|option| option.get("type"))
+            .cloned();
+        let same_type = options
+            .iter()
+            .all(|option| option.get("type") == first_type.as_ref());
+
+        match options.as_slice() {
+            [] => {}
+            [option] => merge_missing(map, option),
+            _ if same_type && options.iter().all(|option| enum_values(option).is_some()) => {
+                let values: Vec<Value> = options
+                    .iter()
+                    .flat_map(|option| enum_values(option).unwrap_or_default())
+                    .collect();
+                if let Some(t) = first_type {
+                    map.entry("type").or_insert(t);
+                }
+                map.insert("enum".to_string(), Value::Array(values));
+            }
+            _ if options.iter().all(|option| is_object_schema(option)) => {
+                let mut properties = Map::new();
+                let mut required: Option<HashSet<String>> = None;
+                for option in &options {
+                    if let Some(Value::Object(props)) = option.get("properties") {
+                        for (key, property) in props {
+                            properties
+                                .entry(key.clone())
+                                .or_insert_with(|| property.clone());
+                        }
+                    }
+                    let option_required: HashSet<String> = required_names(option);
+                    required = Some(match required {
+                        Some(required) => &required & &option_required,
+                        None => option_required,
+                    });
+                }
+                map.insert("type".to_string(), json!("object"));
+                map.insert("properties".to_string(), Value::Object(properties));
+                let mut required: Vec<String> = required.unwrap_or_default().into_iter().collect();
+                required.sort();
+                map.insert("required".to_string(), json!(required));
+                self.warn(
+                    path,
+                    format!("{} options merged into one object", combinator),
+                );
+            }
+            [first, rest @ ..] => {
+                merge_missing(map, first);
+                let others =
+                    Value::Array(rest.iter().map(|o| Value::Object((*o).clone())).collect());
+                append_description(map, &format!("Also accepts: {}", others));
+                self.warn(path, format!("{} narrowed to its first option", combinator));
+            }
+        }
+
+        if nullable {
+            if self.caps.type_arrays {
+                if let Some(Value::String(t)) = map.get("type").cloned() {
+                    map.insert("type".to_string(), json!([t, "null"]));
+                }
+            } else {
+                map.insert("nullable".to_string(), Value::Bool(true));
+            }
+        }
+    }
+}
+
+fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    root.pointer(reference.strip_prefix('#')?)
+}
+
+fn is_object_schema(map: &Map<String, Value>) -> bool {
+    map.get("type") == Some(&json!("object")) || map.contains_key("properties")
+}
+
+fn required_names(map: &Map<String, Value>) -> HashSet<String> {
+    map.get("required")
+        .and_then(Value::as_array)
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn append_description(map: &mut Map<String, Value>, note: &str) {
+    let description = match map.get("description").and_then(Value::as_str) {
+        Some(description) if !description.is_empty() => format!("{} {}", description, note),
+        _ => note.to_string(),
+    };
+    map.insert("description".to_string(), Value::String(description));
+}
+
+fn merge_missing(map: &mut Map<String, Value>, other: &Map<String, Value>) {
+    for (key, value) in other {
+        map.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+}
+
+/// Fold one allOf member into the schema: properties and required names are combined, the
+/// schema's own keywords win otherwise
+fn merge_all_of(map: &mut Map<String, Value>, variant: &Value) {
+    let Value::Object(variant) = variant else {
+        return;
+    };
+    for (key, value) in variant {
+        match (key.as_str(), map.get_mut(key), value) {
+            ("properties", Some(Value::Object(properties)), Value::Object(more)) => {
+                for (name, property) in more {
+                    properties
+                        .entry(name.clone())
+                        .or_insert_with(|| property.clone());
+                }
+            }
+            ("required", Some(Value::Array(required)), Value::Array(more)) => {
+                for name in more {
+                    if !required.contains(name) {
+                        required.push(name.clone());
+                    }
+                }
+            }
+            (_, None, _) => {
+                map.insert(key.clone(), value.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Require every property and allow no others. Properties that were optional become nullable
+/// instead, and `strip_optional_nulls` drops the nulls again from the model's arguments.
+fn make_strict(map: &mut Map<String, Value>) {
+    map.insert("type".to_string(), json!("object"));
+    map.insert("additionalProperties".to_string(), Value::Bool(false));
+    let required = required_names(map);
+    if !matches!(map.get("properties"), Some(Value::Object(_))) {
+        map.insert("properties".to_string(), json!({}));
+    }
+    let Some(Value::Object(properties)) = map.get_mut("properties") else {
+        return;
+    };
+    for (name, property) in properties.iter_mut() {
+        if !required.contains(name) {
+            make_nullable(property);
+        }
+    }
+    let names: Vec<Value> = properties.keys().cloned().map(Value::String).collect();
+    map.insert("required".to_string(), Value::Array(names));
+}
+
+fn make_nullable(property: &mut Value) {
+    let Value::Object(map) = property else {
+        return;
+    };
+    match map.get("type").cloned() {
+        Some(Value::String(t)) if t != "null" => {
+            map.insert("type".to_string(), json!([t, "null"]));
+        }
+        Some(Value::Array(mut types)) => {
+            if !types.contains(&json!("null")) {
+                types.push(json!("null"));
+                map.insert("type".to_string(), Value::Array(types));
+            }
+        }
+        Some(_) => return,
+        None => {
+            *property = json!({"anyOf": [property.clone(), {"type": "null"}]});
+            return;
+        }
+    }
+    if let Some(Value::Array(values)) = map.get_mut("enum") {
+        if !values.contains(&Value::Null) {
+            values.push(Value::Null);
+        }
+    }
+}
+
+/// Whether an OpenAI function's parameters meet strict mode's rules, so it can be sent with
+/// `"strict": true`
+pub fn is_strict_compatible(schema: &Value) -> bool {
+    let Value::Object(map) = schema else {
+        return true;
+    };
+    if is_object_schema(map) {
+        let properties = map.get("properties").and_then(Value::as_object);
+        let required = required_names(map);
+        if map.get("additionalProperties") != Some(&Value::Bool(false))
+            || properties.is_some_and(|props| props.keys().any(|key| !required.contains(key)))
+        {
+            return false;
+        }
+    }
+    if STRICT_UNSUPPORTED_KEYWORDS
+        .iter()
+        .any(|keyword| map.contains_key(*keyword))
+    {
+        return false;
+    }
+    map.iter().all(|(key, value)| match (key.as_str(), value) {
+        ("properties" | "$defs" | "definitions", Value::Object(children)) => {
+            children.values().all(is_strict_compatible)
+        }
+        ("items" | "additionalProperties", child) => is_strict_compatible(child),
+        ("anyOf", Value::Array(variants)) => variants.iter().all(is_strict_compatible),
+        ("oneOf" | "allOf", _) => false,
+        _ => true,
+    })
+}
+
+/// Set `"strict": true` on the OpenAI function specs in a request whose parameters allow it
+pub fn mark_strict(payload: &mut Value) {
+    let Some(Value::Array(tools)) = payload.get_mut("tools") else {
+        return;
+    };
+    for tool in tools {
+        if let Some(Value::Object(function)) = tool.get_mut("function") {
+            if function.get("parameters").is_some_and(is_strict_compatible) {
+                function.insert("strict".to_string(), Value::Bool(true));
+            }
+        }
+    }
+}
+
+/// Remove the nulls strict mode makes the model send for properties the tool's own schema
+/// leaves optional
+pub fn strip_optional_nulls(message: &mut Message, tools: &[Tool]) {
+    for content in message.content.iter_mut() {
+        if let MessageContent::ToolRequest(request) = content {
+            if let Ok(call) = &mut request.tool_call {
+                if let Some(tool) = tools.iter().find(|tool| tool.name == call.name) {
+                    strip_nulls(&mut call.arguments, &tool.input_schema);
+                }
+            }
+        }
+    }
+}
+
+fn strip_nulls(value: &mut Value, schema: &Value) {
+    let (Value::Object(arguments), Some(schema)) = (value, schema.as_object()) else {
+        return;
+    };
+    let required = required_names(schema);
+    arguments.retain(|key, value| !value.is_null() || required.contains(key));
+    if let Some(Value::Object(properties)) = schema.get("properties") {
+        for (key, argument) in arguments.iter_mut() {
+            if let Some(property) = properties.get(key) {
+                strip_nulls(argument, property);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lower_schema_for_google() {
+        let schema = json!({
+            "type": "object",
+            "$defs": {"mode": {"type": "string", "enum": ["fast", "slow"]}},
+            "properties": {
+                "mode": {"$ref": "#/$defs/mode"},
+                "id": {"type": "string", "format": "uuid", "description": "Run id"},
+                "limit": {"type": ["integer", "null"]},
+                "level": {"oneOf": [{"const": "low"}, {"const": "high"}], "type": "string"},
+                "target": {"anyOf": [{"type": "string"}, {"type": "integer"}]},
+                "retry": {"allOf": [
+                    {"type": "object", "properties": {"count": {"type": "integer"}}, "required": ["count"]},
+                    {"properties": {"delay": {"type": "number"}}}
+                ]}
+            }
+        });
+        let (lowered, warnings) = lower_schema(&schema, &SchemaCapabilities::GOOGLE);
+        let properties = &lowered["properties"];
+
+        assert!(lowered.get("$defs").is_none());
+        assert_eq!(
+            properties["mode"],
+            json!({"type": "string", "enum": ["fast", "slow"]})
+        );
+        assert_eq!(
+            properties["id"],
+            json!({"type": "string", "description": "Run id (format: uuid)"})
+        );
+        assert_eq!(
+            properties["limit"],
+            json!({"type": "integer", "nullable": true})
+        );
+        assert_eq!(
+            properties["level"],
+            json!({"type": "string", "enum": ["low", "high"]})
+        );
+        assert_eq!(properties["target"]["type"], json!("string"));
+        assert_eq!(
+            properties["retry"]["properties"],
+            json!({"count": {"type": "integer"}, "delay": {"type": "number"}})
+        );
+        assert_eq!(properties["retry"]["required"], json!(["count"]));
+
+        // Only the format and the narrowed anyOf lost information
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings.iter().any(|w| w.contains("/target")));
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "command": {"type": "string"},
+                "timeout": {"type": "integer", "default": 30},
+                "choice": {"oneOf": [{"type": "string"}, {"type": "number"}]}
+            },
+            "required": ["command"]
+        });
+        assert!(!is_strict_compatible(&schema));
+
+        let (lowered, warnings) = lower_schema(&schema, &SchemaCapabilities::OPENAI_STRICT);
+        assert!(is_strict_compatible(&lowered));
+        assert_eq!(lowered["additionalProperties"], json!(false));
+        assert_eq!(lowered["required"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            lowered["properties"]["timeout"],
+            json!({"type": ["integer", "null"]})
+        );
+        assert!(lowered["properties"]["choice"]["anyOf"][0]["anyOf"].is_array());
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+
+        let mut payload =
+            json!({"tools": [{"type": "function", "function": {"parameters": lowered}}]});
+        mark_strict(&mut payload);
+        assert_eq!(payload["tools"][0]["function"]["strict"], json!(true));
+
+        let mut arguments = json!({"command": "ls", "timeout": null, "choice": null});
+        strip_nulls(&mut arguments, &schema);
+        assert_eq!(arguments, json!({"command": "ls"}));
+    }
+
+    #[test]
+    fn test_intersect() {
+        let both = SchemaCapabilities::OPENAI_STRICT.intersect(&SchemaCapabilities::GOOGLE);
+        assert!(both.combinators.is_empty());
+        assert_eq!(both.formats.as_deref(), Some(&["date-time"][..]));
+        assert!(!both.refs && !both.type_arrays && !both.strict);
+
+        // Lists of the same length can still differ
+        let one_of = SchemaCapabilities {
+            combinators: Cow::Borrowed(&["anyOf", "oneOf"]),
+            ..SchemaCapabilities::FULL
+        };
+        let all_of = SchemaCapabilities {
+            combinators: Cow::Borrowed(&["allOf", "anyOf"]),
+            ..SchemaCapabilities::FULL
+        };
+        assert_eq!(&*one_of.intersect(&all_of).combinators, &["anyOf"]);
+
+        // Any format combined with a list is the list
+        assert_eq!(
+            SchemaCapabilities::FULL
+                .intersect(&SchemaCapabilities::GOOGLE)
+                .formats,
+            SchemaCapabilities::GOOGLE.formats
+        );
+    }
+}
@@ -86,7 +86,7 @@ impl SnowflakeProvider {
             .into());
         }
 
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
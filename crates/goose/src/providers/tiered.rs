@@ -0,0 +1,516 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::base::{Provider, ProviderMetadata, ProviderUsage};
+use super::errors::ProviderError;
+use super::pricing::ModelPricing;
+use super::schema::SchemaCapabilities;
+use crate::message::{Message, MessageContent};
+use crate::model::ModelConfig;
+use crate::session::MessageUsage;
+use mcp_core::{role::Role, tool::Tool};
+
+/// Config key for routing turns between a cheap and an expensive model, e.g.
+///
+/// ```yaml
+/// tiered_models:
+///   cheap: { model: gpt-4o-mini }
+///   expensive: { provider: anthropic, model: claude-sonnet-4 }
+///   session_budget: 2.0
+/// ```
+///
+/// Costs and savings are estimated with the prices in GOOSE_MODEL_PRICING.
+pub const TIERED_MODELS_KEY: &str = "tiered_models";
+
+const DEFAULT_SHORT_MESSAGE_CHARS: usize = 200;
+
+/// Words that make a user message a task to plan rather than a clarification
+const PLANNING_WORDS: &[&str] = &[
+    "implement",
+    "refactor",
+    "debug",
+    "design",
+    "plan",
+    "migrate",
+    "fix",
+    "investigate",
+    "write",
+    "build",
+    "create",
+];
+
+/// One of the models in `tiered_models`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelTier {
+    /// Defaults to the configured GOOSE_PROVIDER
+    pub provider: Option<String>,
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TieredModelsConfig {
+    pub cheap: ModelTier,
+    pub expensive: ModelTier,
+    /// Estimated USD a session may spend, after which every turn goes to the cheap model
+    pub session_budget: Option<f64>,
+    /// User messages up to this many characters count as clarifications
+    pub short_message_chars: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    Cheap,
+    Expensive,
+}
+
+/// Pick the model for the next turn of `messages` and the reason, for logging. `previous` is
+/// the tier of the last turn, which a chain of tool calls stays with.
+pub fn route(
+    messages: &[Message],
+    previous: Option<Tier>,
+    short_message_chars: usize,
+) -> (Tier, &'static str) {
+    let Some(last) = messages.last().filter(|m| m.role == Role::User) else {
+        return (Tier::Expensive, "no user message to judge");
+    };
+
+    if last.is_tool_response() {
+        let failed = last.content.iter().any(|content| {
+            matches!(content, MessageContent::ToolResponse(response) if response.tool_result.is_err())
+        });
+        if failed {
+            return (Tier::Expensive, "a tool call failed");
+        }
+        return (
+            previous.unwrap_or(Tier::Expensive),
+            "continuing a chain of tool calls",
+        );
+    }
+
+    let user_turns = messages
+        .iter()
+        .filter(|m| m.role == Role::User && !m.is_tool_response())
+        .count();
+    if user_turns <= 1 {
+        return (Tier::Expensive, "the first request of the session");
+    }
+    let text = last.as_concat_text();
+    if text.chars().count() > short_message_chars || text.contains("```") {
+        return (Tier::Expensive, "a long request");
+    }
+    let lowercase = text.to_lowercase();
+    let plans = lowercase.split(|c: char| !c.is_alphanumeric()).any(|word| {
+        PLANNING_WORDS
+            .iter()
+            .any(|planning| word.starts_with(planning))
+    });
+    if plans {
+        return (Tier::Expensive, "a task to plan");
+    }
+    (Tier::Cheap, "a short clarification")
+}
+
+/// A provider that sends each turn to a cheap or an expensive model depending on how complex
+/// the turn looks, within a per-session budget, and reports the cost and savings of each
+/// response in its usage
+pub struct TieredProvider {
+    cheap_provider: Arc<dyn Provider>,
+    expensive_provider: Arc<dyn Provider>,
+    config: TieredModelsConfig,
+    cheap_pricing: Option<ModelPricing>,
+    expensive_pricing: Option<ModelPricing>,
+    /// The session the spend and savings belong to
+    session: Arc<Mutex<Option<String>>>,
+    previous_tier: Arc<Mutex<Option<Tier>>>,
+    spent: Arc<Mutex<f64>>,
+    saved: Arc<Mutex<f64>>,
+}
+
+impl TieredProvider {
+    pub fn new(
+        cheap_provider: Arc<dyn Provider>,
+        expensive_provider: Arc<dyn Provider>,
+        config: TieredModelsConfig,
+    ) -> Self {
+        Self {
+            cheap_provider,
+            expensive_provider,
+            config,
+            cheap_pricing: None,
+            expensive_pricing: None,
+            session: Arc::new(Mutex::new(None)),
+            previous_tier: Arc::new(Mutex::new(None)),
+            spent: Arc::new(Mutex::new(0.0)),
+            saved: Arc::new(Mutex::new(0.0)),
+        }
+    }
+
+    /// Prices for estimating costs and savings, unpriced models count as free
+    pub fn with_pricing(
+        mut self,
+        cheap: Option<ModelPricing>,
+        expensive: Option<ModelPricing>,
+    ) -> Self {
+        self.cheap_pricing = cheap;
+        self.expensive_pricing = expensive;
+        self
+    }
+
+    /// Reset the spend and savings (useful for new conversations)
+    pub async fn reset_session(&self) {
+        *self.session.lock().await = None;
+        *self.previous_tier.lock().await = None;
+        *self.spent.lock().await = 0.0;
+        *self.saved.lock().await = 0.0;
+    }
+
+    /// Follow the session about to be replied to. When it isn't the session of the last reply,
+    /// the spend and savings start over from what its recorded responses cost.
+    pub async fn start_session(&self, session_id: &str, usage: &[MessageUsage]) {
+        let mut session = self.session.lock().await;
+        if session.as_deref() == Some(session_id) {
+            return;
+        }
+        *session = Some(session_id.to_string());
+
+        let cheap_model = self.cheap_provider.get_model_config().model_name;
+        *self.previous_tier.lock().await = usage.last().map(|last| {
+            if last.model == cheap_model {
+                Tier::Cheap
+            } else {
+                Tier::Expensive
+            }
+        });
+        *self.spent.lock().await = usage.iter().filter_map(|u| u.cost_usd).sum();
+        *self.saved.lock().await = usage.iter().filter_map(|u| u.saved_usd).sum();
+    }
+
+    /// Estimated USD spent in this session, counting only priced models
+    pub async fn session_spend(&self) -> f64 {
+        *self.spent.lock().await
+    }
+
+    /// Estimated USD saved by turns the cheap model handled
+    pub async fn session_savings(&self) -> f64 {
+        *self.saved.lock().await
+    }
+
+    async fn choose_tier(&self, messages: &[Message]) -> Tier {
+        let previous = *self.previous_tier.lock().await;
+        let short_message_chars = self
+            .config
+            .short_message_chars
+            .unwrap_or(DEFAULT_SHORT_MESSAGE_CHARS);
+        let (tier, reason) = route(messages, previous, short_message_chars);
+
+        let spent = *self.spent.lock().await;
+        if tier == Tier::Expensive
+            && self
+                .config
+                .session_budget
+                .is_some_and(|budget| spent >= budget)
+        {
+            tracing::info!(
+                "Using the cheap model for {}: the session budget is spent (${:.2})",
+                reason,
+                spent
+            );
+            return Tier::Cheap;
+        }
+        tracing::info!("Using the {:?} model for {}", tier, reason);
+        tier
+    }
+}
+
+#[async_trait]
+impl Provider for TieredProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "tiered",
+            "Tiered Provider",
+            "A provider that routes each turn to a cheap or an expensive model",
+            "",     // No default model as this is determined by the wrapped providers
+            vec![], // No known models as this depends on wrapped providers
+            "",     // No doc link
+            vec![], // Configured through tiered_models
+        )
+    }
+
+    /// The expensive model's config, limited to the context both models accept
+    fn get_model_config(&self) -> ModelConfig {
+        let cheap = self.cheap_provider.get_model_config();
+        let expensive = self.expensive_provider.get_model_config();
+        let context_limit = cheap.context_limit().min(expensive.context_limit());
+        expensive.with_context_limit(Some(context_limit))
+    }
+
+    /// Tools are rewritten once for both models, so only what both accept is used
+    fn schema_capabilities(&self) -> SchemaCapabilities {
+        self.cheap_provider
+            .schema_capabilities()
+            .intersect(&self.expensive_provider.schema_capabilities())
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let tier = self.choose_tier(messages).await;
+        let provider = match tier {
+            Tier::Cheap => &self.cheap_provider,
+            Tier::Expensive => &self.expensive_provider,
+        };
+        let (message, usage) = provider.complete(system, messages, tools).await?;
+
+        let estimate = |pricing: &Option<ModelPricing>| {
+            pricing
+                .as_ref()
+                .and_then(|pricing| pricing.estimate_cost(&usage.usage))
+        };
+        let (cost, saved) = match tier {
+            Tier::Cheap => {
+                let cost = estimate(&self.cheap_pricing);
+                let saved = estimate(&self.expensive_pricing)
+                    .zip(cost)
+                    .map(|(expensive, cheap)| (expensive - cheap).max(0.0));
+                (cost, saved)
+            }
+            Tier::Expensive => (estimate(&self.expensive_pricing), None),
+        };
+        *self.previous_tier.lock().await = Some(tier);
+        *self.spent.lock().await += cost.unwrap_or(0.0);
+        *self.saved.lock().await += saved.unwrap_or(0.0);
+
+        Ok((message, usage.with_cost(cost, saved)))
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        let cheap_models = self.cheap_provider.fetch_supported_models_async().await?;
+        let expensive_models = self
+            .expensive_provider
+            .fetch_supported_models_async()
+            .await?;
+
+        match (cheap_models, expensive_models) {
+            (Some(mut models), Some(expensive)) => {
+                models.extend(expensive);
+                models.sort();
+                models.dedup();
+                Ok(Some(models))
+            }
+            (Some(models), None) | (None, Some(models)) => Ok(Some(models)),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.cheap_provider.supports_embeddings() || self.expensive_provider.supports_embeddings()
+    }
+
+    fn as_tiered(&self) -> Option<&TieredProvider> {
+        Some(self)
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        // Embeddings are cheap either way, prefer the cheap model
+        if self.cheap_provider.supports_embeddings() {
+            self.cheap_provider.create_embeddings(texts).await
+        } else if self.expensive_provider.supports_embeddings() {
+            self.expensive_provider.create_embeddings(texts).await
+        } else {
+            Err(ProviderError::ExecutionError(
+                "Neither the cheap nor the expensive provider supports embeddings".to_string(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use mcp_core::{Content, ToolError};
+
+    struct MockProvider {
+        name: String,
+    }
+
+    #[async_trait]
+    impl Provider for MockProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new(self.name.clone())
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            Ok((
+                Message::assistant().with_text("done"),
+                ProviderUsage::new(
+                    self.name.clone(),
+                    Usage::new(Some(100_000), Some(10_000), Some(110_000)),
+                ),
+            ))
+        }
+    }
+
+    fn tier(model: &str) -> ModelTier {
+        ModelTier {
+            provider: None,
+            model: model.to_string(),
+        }
+    }
+
+    fn pricing(input: f64, output: f64) -> Option<ModelPricing> {
+        Some(ModelPricing { input, output })
+    }
+
+    fn provider(session_budget: Option<f64>) -> TieredProvider {
+        TieredProvider::new(
+            Arc::new(MockProvider {
+                name: "cheap".to_string(),
+            }),
+            Arc::new(MockProvider {
+                name: "expensive".to_string(),
+            }),
+            TieredModelsConfig {
+                cheap: tier("cheap"),
+                expensive: tier("expensive"),
+                session_budget,
+                short_message_chars: None,
+            },
+        )
+        .with_pricing(pricing(1.0, 2.0), pricing(10.0, 20.0))
+    }
+
+    #[test]
+    fn test_route() {
+        let task = Message::user().with_text("Add a --verbose flag to the CLI");
+        let call = Message::assistant().with_tool_request(
+            "1",
+            Ok(mcp_core::tool::ToolCall::new(
+                "shell",
+                serde_json::json!({}),
+            )),
+        );
+        let ok = Message::user().with_tool_response("1", Ok(vec![Content::text("ok")]));
+        let failed = Message::user().with_tool_response(
+            "1",
+            Err(ToolError::ExecutionError("exit status 1".to_string())),
+        );
+        let reply = Message::assistant().with_text("Which file?");
+
+        assert_eq!(
+            route(std::slice::from_ref(&task), None, 200).0,
+            Tier::Expensive
+        );
+        let mut messages = vec![task, reply, Message::user().with_text("the main one")];
+        assert_eq!(route(&messages, None, 200).0, Tier::Cheap);
+        assert_eq!(route(&messages, None, 5).0, Tier::Expensive);
+
+        messages.push(call);
+        messages.push(ok);
+        assert_eq!(route(&messages, Some(Tier::Cheap), 200).0, Tier::Cheap);
+        messages.pop();
+        messages.push(failed);
+        assert_eq!(route(&messages, Some(Tier::Cheap), 200).0, Tier::Expensive);
+
+        let planning = Message::user().with_text("now refactor it");
+        assert_eq!(
+            route(
+                &[messages[0].clone(), messages[1].clone(), planning],
+                None,
+                200
+            )
+            .0,
+            Tier::Expensive
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tiered_costs_and_budget() {
+        let provider = provider(Some(1.0));
+        let task = Message::user().with_text("Add a --verbose flag to the CLI");
+        let question = vec![
+            task.clone(),
+            Message::assistant().with_text("Which file?"),
+            Message::user().with_text("the main one"),
+        ];
+
+        // 0.1M input and 0.01M output tokens, $1.2 on the expensive model
+        let (_, usage) = provider
+            .complete("system", std::slice::from_ref(&task), &[])
+            .await
+            .unwrap();
+        assert_eq!(usage.model, "expensive");
+        assert!((usage.cost_usd.unwrap() - 1.2).abs() < 1e-9);
+        assert_eq!(usage.saved_usd, None);
+
+        let (_, usage) = provider.complete("system", &question, &[]).await.unwrap();
+        assert_eq!(usage.model, "cheap");
+        assert!((usage.cost_usd.unwrap() - 0.12).abs() < 1e-9);
+        assert!((usage.saved_usd.unwrap() - 1.08).abs() < 1e-9);
+        assert!((provider.session_savings().await - 1.08).abs() < 1e-9);
+
+        // Over the budget, even a new task goes to the cheap model
+        let (_, usage) = provider
+            .complete("system", std::slice::from_ref(&task), &[])
+            .await
+            .unwrap();
+        assert_eq!(usage.model, "cheap");
+        assert!((provider.session_spend().await - 1.44).abs() < 1e-9);
+
+        provider.reset_session().await;
+        let (_, usage) = provider.complete("system", &[task], &[]).await.unwrap();
+        assert_eq!(usage.model, "expensive");
+    }
+
+    #[tokio::test]
+    async fn test_budget_follows_the_session() {
+        let provider = provider(Some(1.0));
+        let task = Message::user().with_text("Add a --verbose flag to the CLI");
+        let usage = |model: &str, cost_usd: f64| MessageUsage {
+            message_index: 1,
+            model: model.to_string(),
+            input_tokens: None,
+            output_tokens: None,
+            cost_usd: Some(cost_usd),
+            saved_usd: Some(0.5),
+        };
+
+        // A session that already spent its budget stays on the cheap model
+        provider
+            .start_session("spent", &[usage("expensive", 0.7), usage("cheap", 0.4)])
+            .await;
+        assert!((provider.session_spend().await - 1.1).abs() < 1e-9);
+        assert!((provider.session_savings().await - 1.0).abs() < 1e-9);
+        let (_, response) = provider
+            .complete("system", std::slice::from_ref(&task), &[])
+            .await
+            .unwrap();
+        assert_eq!(response.model, "cheap");
+
+        // Replying to the same session again keeps counting
+        provider.start_session("spent", &[]).await;
+        assert!((provider.session_spend().await - 1.22).abs() < 1e-9);
+
+        // Another session starts from its own spend
+        provider.start_session("fresh", &[]).await;
+        assert_eq!(provider.session_spend().await, 0.0);
+        let (_, response) = provider.complete("system", &[task], &[]).await.unwrap();
+        assert_eq!(response.model, "expensive");
+    }
+}
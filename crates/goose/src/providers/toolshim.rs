@@ -69,10 +69,11 @@ pub struct OllamaInterpreter {
 
 impl OllamaInterpreter {
     pub fn new() -> Result<Self, ProviderError> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(600))
-            .build()
-            .expect("Failed to create HTTP client");
+        let client = super::http::client_builder()
+            .and_then(|builder| Ok(builder.timeout(Duration::from_secs(600)).build()?))
+            .map_err(|e| {
+                ProviderError::RequestFailed(format!("Failed to create HTTP client: {}", e))
+            })?;
 
         let base_url = Self::get_ollama_base_url()?;
 
@@ -77,7 +77,7 @@ pub async fn handle_response_openai_compat(response: Response) -> Result<Value,
         StatusCode::TOO_MANY_REQUESTS => {
             Err(ProviderError::RateLimitExceeded(format!("{:?}", payload)))
         }
-        StatusCode::INTERNAL_SERVER_ERROR | StatusCode::SERVICE_UNAVAILABLE => {
+        status if status.is_server_error() => {
             Err(ProviderError::ServerError(format!("{:?}", payload)))
         }
         _ => {
@@ -168,7 +168,7 @@ pub async fn handle_response_google_compat(response: Response) -> Result<Value,
         StatusCode::TOO_MANY_REQUESTS => {
             Err(ProviderError::RateLimitExceeded(format!("{:?}", payload)))
         }
-        StatusCode::INTERNAL_SERVER_ERROR | StatusCode::SERVICE_UNAVAILABLE => {
+        status if status.is_server_error() => {
             Err(ProviderError::ServerError(format!("{:?}", payload)))
         }
         _ => {
@@ -332,6 +332,7 @@ pub fn emit_debug_trace(
         input_tokens = ?usage.input_tokens.unwrap_or_default(),
         output_tokens = ?usage.output_tokens.unwrap_or_default(),
         total_tokens = ?usage.total_tokens.unwrap_or_default(),
+        reasoning_tokens = ?usage.reasoning_tokens,
     );
 }
 
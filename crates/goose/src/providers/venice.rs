@@ -90,9 +90,11 @@ impl VeniceProvider {
     pub fn from_env(mut model: ModelConfig) -> Result<Self> {
         let config = crate::config::Config::global();
         let api_key: String = config.get_secret("VENICE_API_KEY")?;
-        let host: String = config
-            .get_param("VENICE_HOST")
-            .unwrap_or_else(|_| VENICE_DEFAULT_HOST.to_string());
+        let host: String = model.base_url.clone().unwrap_or_else(|| {
+            config
+                .get_param("VENICE_HOST")
+                .unwrap_or_else(|_| VENICE_DEFAULT_HOST.to_string())
+        });
         let base_path: String = config
             .get_param("VENICE_BASE_PATH")
             .unwrap_or_else(|_| VENICE_DEFAULT_BASE_PATH.to_string());
@@ -103,7 +105,7 @@ impl VeniceProvider {
         // Ensure we only keep the bare model id internally
         model.model_name = strip_flags(&model.model_name).to_string();
 
-        let client = Client::builder()
+        let client = super::http::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
@@ -554,6 +556,7 @@ impl Provider for VeniceProvider {
             input_tokens: usage_data["prompt_tokens"].as_i64().map(|v| v as i32),
             output_tokens: usage_data["completion_tokens"].as_i64().map(|v| v as i32),
             total_tokens: usage_data["total_tokens"].as_i64().map(|v| v as i32),
+            reasoning_tokens: None,
         };
 
         Ok((
@@ -7,14 +7,16 @@ use std::sync::Arc;
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use etcetera::{choose_app_strategy, AppStrategy};
+use mcp_core::Content;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use tokio_cron_scheduler::{job::JobId, Job, JobScheduler as TokioJobScheduler};
 
+use crate::agents::ask_user::UserQuestion;
 use crate::agents::AgentEvent;
 use crate::agents::{Agent, SessionConfig};
 use crate::config::{self, Config};
-use crate::message::Message;
+use crate::message::{Message, MessageContent};
 use crate::providers::base::Provider as GooseProvider; // Alias to avoid conflict in test section
 use crate::providers::create;
 use crate::recipe::Recipe;
@@ -1104,6 +1106,25 @@ async fn run_scheduled_job_internal(
 
                     match message_result {
                         Ok(AgentEvent::Message(msg)) => {
+                            // Nobody is around to answer questions in a scheduled job
+                            if let Some(MessageContent::FrontendToolRequest(request)) =
+                                msg.content.first()
+                            {
+                                if let Some(question) = request
+                                    .tool_call
+                                    .as_ref()
+                                    .ok()
+                                    .and_then(UserQuestion::from_call)
+                                {
+                                    agent
+                                        .handle_tool_result(
+                                            request.id.clone(),
+                                            Ok(vec![Content::text(question.default_answer())]),
+                                        )
+                                        .await;
+                                    continue;
+                                }
+                            }
                             if msg.role == mcp_core::role::Role::Assistant {
                                 tracing::info!("[Job {}] Assistant: {:?}", job.id, msg.content);
                             }
@@ -1112,6 +1133,25 @@ async fn run_scheduled_job_internal(
                         Ok(AgentEvent::McpNotification(_)) => {
                             // Handle notifications if needed
                         }
+                        Ok(AgentEvent::HistoryReplaced(messages)) => {
+                            all_session_messages = messages;
+                        }
+                        Ok(AgentEvent::Guardrail(event)) => {
+                            tracing::warn!("[Job {}] {}", job.id, event.summary());
+                        }
+                        Ok(AgentEvent::ExtensionDisabled(event)) => {
+                            tracing::warn!("[Job {}] {}", job.id, event.summary());
+                        }
+                        Ok(AgentEvent::ContextRecovered(event)) => {
+                            tracing::info!("[Job {}] {}", job.id, event.summary());
+                        }
+                        Ok(AgentEvent::Timeout(timeout)) => {
+                            tracing::warn!(
+                                "[Job {}] Stopped waiting for the user: {:?}",
+                                job.id,
+                                timeout
+                            );
+                        }
                         Err(e) => {
                             tracing::error!(
                                 "[Job {}] Error receiving message from agent: {}",
@@ -1155,6 +1195,11 @@ async fn run_scheduled_job_internal(
                             accumulated_total_tokens: None,
                             accumulated_input_tokens: None,
                             accumulated_output_tokens: None,
+                            last_response_timing: None,
+                            tags: Vec::new(),
+                            notes: Vec::new(),
+                            message_usage: Vec::new(),
+                            pinned: Vec::new(),
                         };
                         if let Err(e_fb) = crate::session::storage::save_messages_with_metadata(
                             &session_file_path,
@@ -0,0 +1,93 @@
+use crate::message::Message;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Where messages collapsed into a summary are kept for a session, `<session>.archive`
+pub fn archive_path(session_file: &Path) -> PathBuf {
+    session_file.with_extension("archive")
+}
+
+/// A run of messages that was replaced by a summary in the session history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedRange {
+    /// Referenced from the summary message that replaced these messages
+    pub id: String,
+    pub archived_at: i64,
+    /// How many messages were summarized
+    pub count: usize,
+    pub messages: Vec<Message>,
+}
+
+/// Append `messages` to the session's archive, one JSON line per range, returning its id
+pub fn archive_messages(session_file: &Path, messages: &[Message]) -> Result<String> {
+    let existing = read_archive(session_file)?.len();
+    let range = ArchivedRange {
+        id: format!("archive-{}", existing + 1),
+        archived_at: Utc::now().timestamp(),
+        count: messages.len(),
+        messages: messages.to_vec(),
+    };
+
+    let path = archive_path(session_file);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(&range)?)?;
+    file.sync_all()?;
+    Ok(range.id)
+}
+
+/// Every range archived for the session, oldest first
+pub fn read_archive(session_file: &Path) -> Result<Vec<ArchivedRange>> {
+    let path = archive_path(session_file);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    fs::read_to_string(&path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(Into::into))
+        .collect()
+}
+
+/// The original messages behind a summary
+pub fn read_archived_range(session_file: &Path, id: &str) -> Result<Option<ArchivedRange>> {
+    Ok(read_archive(session_file)?
+        .into_iter()
+        .find(|range| range.id == id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_archive_messages() -> Result<()> {
+        let dir = tempdir()?;
+        let session_file = dir.path().join("session.jsonl");
+        assert!(read_archive(&session_file)?.is_empty());
+
+        let first = vec![
+            Message::user().with_text("Add a parser"),
+            Message::assistant().with_text("Done"),
+        ];
+        let second = vec![Message::user().with_text("Now the tests")];
+        assert_eq!(archive_messages(&session_file, &first)?, "archive-1");
+        assert_eq!(archive_messages(&session_file, &second)?, "archive-2");
+
+        let archive = read_archive(&session_file)?;
+        assert_eq!(archive.len(), 2);
+        assert_eq!(archive[0].count, 2);
+        let range = read_archived_range(&session_file, "archive-2")?.unwrap();
+        assert_eq!(range.messages[0].as_concat_text(), "Now the tests");
+        assert!(read_archived_range(&session_file, "archive-3")?.is_none());
+        Ok(())
+    }
+}
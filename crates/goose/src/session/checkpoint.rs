@@ -0,0 +1,146 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::agents::extension::ExtensionConfig;
+
+/// How often a headless run checks whether it was asked to pause
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The kind of run that was paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HandoffMode {
+    Headless,
+    Interactive,
+}
+
+/// Written next to a session when a run stops part way so it can be picked up in the other
+/// mode, from a headless run to an interactive session or back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunCheckpoint {
+    pub paused_from: HandoffMode,
+    pub paused_at: DateTime<Utc>,
+    pub working_dir: PathBuf,
+    /// Why the run stopped, shown when it is picked up
+    pub reason: String,
+    /// The extensions the run had, so the one picking it up has the same tools
+    #[serde(default)]
+    pub extensions: Vec<ExtensionConfig>,
+}
+
+impl RunCheckpoint {
+    pub fn new(
+        paused_from: HandoffMode,
+        reason: impl Into<String>,
+        extensions: Vec<ExtensionConfig>,
+    ) -> Self {
+        Self {
+            paused_from,
+            paused_at: Utc::now(),
+            working_dir: std::env::current_dir().unwrap_or_default(),
+            reason: reason.into(),
+            extensions,
+        }
+    }
+}
+
+/// Where the checkpoint of a paused run is kept, `<session>.checkpoint`
+pub fn checkpoint_path(session_file: &Path) -> PathBuf {
+    session_file.with_extension("checkpoint")
+}
+
+/// Where a request to pause a running session is left for it to find, `<session>.pause`
+pub fn pause_request_path(session_file: &Path) -> PathBuf {
+    session_file.with_extension("pause")
+}
+
+pub fn write_checkpoint(session_file: &Path, checkpoint: &RunCheckpoint) -> Result<()> {
+    let path = checkpoint_path(session_file);
+    let tmp = path.with_extension("checkpoint.tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(checkpoint)?)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+pub fn read_checkpoint(session_file: &Path) -> Result<Option<RunCheckpoint>> {
+    match fs::read(checkpoint_path(session_file)) {
+        Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Read the checkpoint of a paused run and remove it, so it is only picked up once
+pub fn take_checkpoint(session_file: &Path) -> Result<Option<RunCheckpoint>> {
+    let checkpoint = read_checkpoint(session_file)?;
+    if checkpoint.is_some() {
+        fs::remove_file(checkpoint_path(session_file))?;
+    }
+    Ok(checkpoint)
+}
+
+/// Ask the run working on `session_file` to checkpoint and stop after its current step
+pub fn request_pause(session_file: &Path) -> Result<()> {
+    if !session_file.exists() {
+        anyhow::bail!("No session at {}", session_file.display());
+    }
+    fs::write(pause_request_path(session_file), Utc::now().to_rfc3339())?;
+    Ok(())
+}
+
+/// Consume a pending pause request, true if there was one
+pub fn take_pause_request(session_file: &Path) -> bool {
+    fs::remove_file(pause_request_path(session_file)).is_ok()
+}
+
+/// Resolves once a pause is requested for `session_file`
+pub async fn pause_requested(session_file: &Path) {
+    let mut interval = tokio::time::interval(PAUSE_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        if take_pause_request(session_file) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_checkpoint_round_trip() -> Result<()> {
+        let dir = tempdir()?;
+        let session_file = dir.path().join("20250101_120000.jsonl");
+        assert!(read_checkpoint(&session_file)?.is_none());
+
+        let checkpoint = RunCheckpoint::new(HandoffMode::Headless, "interrupted", vec![]);
+        write_checkpoint(&session_file, &checkpoint)?;
+        let read = read_checkpoint(&session_file)?.unwrap();
+        assert_eq!(read.paused_from, HandoffMode::Headless);
+        assert_eq!(read.reason, "interrupted");
+
+        assert!(take_checkpoint(&session_file)?.is_some());
+        assert!(take_checkpoint(&session_file)?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pause_request() -> Result<()> {
+        let dir = tempdir()?;
+        let session_file = dir.path().join("run.jsonl");
+        assert!(request_pause(&session_file).is_err());
+
+        fs::write(&session_file, "")?;
+        assert!(!take_pause_request(&session_file));
+        request_pause(&session_file)?;
+        tokio::time::timeout(Duration::from_secs(5), pause_requested(&session_file)).await?;
+        assert!(!pause_request_path(&session_file).exists());
+        Ok(())
+    }
+}
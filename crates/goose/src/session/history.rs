@@ -0,0 +1,327 @@
+use super::storage::{list_sessions, read_messages, read_metadata, SessionMetadata};
+use crate::config::Config;
+use crate::message::Message;
+use chrono::{DateTime, Local};
+use mcp_core::role::Role;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+/// Config key to stop new sessions from starting with a digest of earlier sessions in the
+/// same project, on by default
+pub const PROJECT_HISTORY_KEY: &str = "GOOSE_PROJECT_HISTORY";
+
+/// How many earlier sessions are summarized
+pub const DEFAULT_HISTORY_SESSIONS: usize = 5;
+/// Upper bound on the rendered digest, older sessions are dropped to stay under it
+const MAX_DIGEST_CHARS: usize = 6000;
+const MAX_TEXT_CHARS: usize = 300;
+const MAX_FILES: usize = 12;
+const MAX_OPEN_ITEMS: usize = 4;
+/// Open items are looked for in this many of the session's last replies
+const OPEN_ITEM_MESSAGES: usize = 3;
+
+static OPEN_ITEM: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\b(todo|fixme|follow[- ]ups?|next steps?|remaining|still needs?|left to do|not (yet )?(done|implemented|handled|covered)|out of scope)\b",
+    )
+    .unwrap()
+});
+
+pub fn is_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>(PROJECT_HISTORY_KEY)
+        .unwrap_or(true)
+}
+
+/// The git repository containing `dir`, or `dir` itself outside of one
+pub fn project_root(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        text
+    } else {
+        format!("{}...", text.chars().take(max_chars).collect::<String>())
+    }
+}
+
+/// What an earlier session was asked, what it changed and what it left open
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionDigest {
+    pub id: String,
+    pub modified: DateTime<Local>,
+    pub description: String,
+    pub request: Option<String>,
+    pub outcome: Option<String>,
+    /// Files edited through the text editor, relative to the project root when inside it
+    pub files_changed: Vec<String>,
+    pub open_items: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+impl SessionDigest {
+    pub fn from_messages(
+        id: String,
+        modified: DateTime<Local>,
+        metadata: &SessionMetadata,
+        messages: &[Message],
+        root: &Path,
+    ) -> Self {
+        let texts = |role: Role| {
+            messages
+                .iter()
+                .filter(move |message| message.role == role)
+                .map(Message::as_concat_text)
+                .filter(|text| !text.trim().is_empty())
+        };
+        let request = texts(Role::User)
+            .next()
+            .map(|text| truncate(&text, MAX_TEXT_CHARS));
+        let replies: Vec<String> = texts(Role::Assistant).collect();
+        let outcome = replies.last().map(|text| truncate(text, MAX_TEXT_CHARS));
+
+        let mut files_changed: Vec<String> = Vec::new();
+        for request in messages
+            .iter()
+            .flat_map(|message| &message.content)
+            .filter_map(|content| content.as_tool_request())
+        {
+            let Ok(call) = &request.tool_call else {
+                continue;
+            };
+            let edits = call.name.ends_with("text_editor")
+                && call
+                    .arguments
+                    .get("command")
+                    .and_then(|command| command.as_str())
+                    .is_some_and(|command| matches!(command, "write" | "str_replace" | "insert"));
+            let Some(path) = call
+                .arguments
+                .get("path")
+                .and_then(|path| path.as_str())
+                .filter(|_| edits)
+            else {
+                continue;
+            };
+            let path = Path::new(path);
+            let path = path
+                .strip_prefix(root)
+                .unwrap_or(path)
+                .display()
+                .to_string();
+            if !files_changed.contains(&path) {
+                files_changed.push(path);
+            }
+        }
+
+        let mut open_items: Vec<String> = Vec::new();
+        for reply in replies.iter().rev().take(OPEN_ITEM_MESSAGES).rev() {
+            for line in reply.lines().filter(|line| OPEN_ITEM.is_match(line)) {
+                let item = truncate(
+                    line.trim()
+                        .trim_start_matches(['-', '*', '#', ' '])
+                        .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.'),
+                    MAX_TEXT_CHARS / 2,
+                );
+                // Skip headings such as "Next steps:", the items follow them
+                if !item.is_empty() && !item.ends_with(':') && !open_items.contains(&item) {
+                    open_items.push(item);
+                }
+            }
+        }
+        // The most recent mentions are the most likely to still be open
+        if open_items.len() > MAX_OPEN_ITEMS {
+            open_items.drain(..open_items.len() - MAX_OPEN_ITEMS);
+        }
+
+        Self {
+            id,
+            modified,
+            description: metadata.description.clone(),
+            request,
+            outcome,
+            files_changed,
+            open_items,
+            notes: metadata.notes.clone(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let title = if self.description.is_empty() {
+            self.id.as_str()
+        } else {
+            self.description.as_str()
+        };
+        let mut text = format!(
+            "## {} - {}\n",
+            self.modified.format("%Y-%m-%d %H:%M"),
+            title
+        );
+        if let Some(request) = &self.request {
+            text.push_str(&format!("Asked: {}\n", request));
+        }
+        if let Some(outcome) = &self.outcome {
+            text.push_str(&format!("Last reply: {}\n", outcome));
+        }
+        if !self.files_changed.is_empty() {
+            let mut files = self
+                .files_changed
+                .iter()
+                .take(MAX_FILES)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ");
+            if self.files_changed.len() > MAX_FILES {
+                files.push_str(&format!(
+                    " and {} more",
+                    self.files_changed.len() - MAX_FILES
+                ));
+            }
+            text.push_str(&format!("Changed: {}\n", files));
+        }
+        for item in &self.open_items {
+            text.push_str(&format!("Open: {}\n", item));
+        }
+        for note in &self.notes {
+            text.push_str(&format!("Note: {}\n", note));
+        }
+        text
+    }
+}
+
+/// Digests of the most recent sessions in the same project as `working_dir`, newest first.
+/// `exclude` is the current session's file.
+pub fn project_history(
+    working_dir: &Path,
+    exclude: Option<&Path>,
+    limit: usize,
+) -> Vec<SessionDigest> {
+    let root = project_root(working_dir);
+    let sessions = match list_sessions() {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            tracing::warn!("Failed to list sessions for the project history: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut candidates: Vec<(String, PathBuf, DateTime<Local>, SessionMetadata)> = sessions
+        .into_iter()
+        .filter(|(_, path)| Some(path.as_path()) != exclude)
+        .filter_map(|(id, path)| {
+            let metadata = read_metadata(&path).ok()?;
+            if metadata.message_count == 0 || project_root(&metadata.working_dir) != root {
+                return None;
+            }
+            let modified = path.metadata().and_then(|m| m.modified()).ok()?;
+            Some((id, path, DateTime::<Local>::from(modified), metadata))
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.2.cmp(&a.2));
+
+    candidates
+        .into_iter()
+        .take(limit)
+        .filter_map(|(id, path, modified, metadata)| {
+            let messages = read_messages(&path).ok()?;
+            Some(SessionDigest::from_messages(
+                id, modified, &metadata, &messages, &root,
+            ))
+        })
+        .filter(|digest| digest.request.is_some())
+        .collect()
+}
+
+/// The digests as a system prompt section, or None when there are none
+pub fn render_project_history(digests: &[SessionDigest], root: &Path) -> Option<String> {
+    let mut text = format!(
+        "# Earlier sessions in this project\n\n\
+        These digests of recent sessions in {} are listed newest first. Use them to pick up \
+        where earlier work left off instead of rediscovering the project. Files may have changed \
+        since, so check the code before relying on a digest.\n",
+        root.display()
+    );
+    let mut included = 0;
+    for digest in digests {
+        let section = digest.to_markdown();
+        if included > 0 && text.len() + section.len() > MAX_DIGEST_CHARS {
+            break;
+        }
+        text.push('\n');
+        text.push_str(&section);
+        included += 1;
+    }
+    (included > 0).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::tool::ToolCall;
+    use serde_json::json;
+
+    #[test]
+    fn test_session_digest() {
+        let root = Path::new("/work/app");
+        let mut metadata = SessionMetadata::new(PathBuf::from("/"));
+        metadata.description = "Fix login redirect".to_string();
+        metadata.notes = vec!["Waiting on design review".to_string()];
+
+        let edit = |command: &str, path: &str| {
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(ToolCall::new(
+                    "developer__text_editor",
+                    json!({"command": command, "path": path}),
+                )),
+            )
+        };
+        let messages = vec![
+            Message::user().with_text("The login page   redirects\nto a 404"),
+            edit("view", "/work/app/src/routes.rs"),
+            edit("str_replace", "/work/app/src/login.rs"),
+            edit("write", "/work/app/src/login.rs"),
+            edit("write", "/tmp/scratch.txt"),
+            Message::assistant().with_text(
+                "Fixed the redirect.\n\nNext steps:\n- TODO: add a test for expired sessions\n1. Logout is not handled yet",
+            ),
+        ];
+
+        let digest = SessionDigest::from_messages(
+            "20250602_101500".to_string(),
+            Local::now(),
+            &metadata,
+            &messages,
+            root,
+        );
+        assert_eq!(
+            digest.request.as_deref(),
+            Some("The login page redirects to a 404")
+        );
+        assert_eq!(
+            digest.files_changed,
+            vec!["src/login.rs", "/tmp/scratch.txt"]
+        );
+        assert_eq!(
+            digest.open_items,
+            vec![
+                "TODO: add a test for expired sessions",
+                "Logout is not handled yet"
+            ]
+        );
+
+        let rendered = render_project_history(&[digest], root).unwrap();
+        assert!(
+            rendered.contains("- Fix login redirect\nAsked: The login page redirects to a 404\n")
+        );
+        assert!(rendered.contains("Changed: src/login.rs, /tmp/scratch.txt\n"));
+        assert!(rendered.contains("Note: Waiting on design review\n"));
+        assert!(render_project_history(&[], root).is_none());
+    }
+}
@@ -2,6 +2,7 @@ use crate::session::{self, SessionMetadata};
 use anyhow::Result;
 use serde::Serialize;
 use std::cmp::Ordering;
+use std::path::Path;
 use utoipa::ToSchema;
 
 #[derive(Clone, Serialize, ToSchema)]
@@ -19,7 +20,12 @@ pub enum SortOrder {
 }
 
 pub fn get_session_info(sort_order: SortOrder) -> Result<Vec<SessionInfo>> {
-    let sessions = match session::list_sessions() {
+    get_session_info_in(&session::ensure_session_dir()?, sort_order)
+}
+
+/// Like `get_session_info`, for the sessions kept in `session_dir`
+pub fn get_session_info_in(session_dir: &Path, sort_order: SortOrder) -> Result<Vec<SessionInfo>> {
+    let sessions = match session::list_sessions_in(session_dir) {
         Ok(sessions) => sessions,
         Err(e) => {
             tracing::error!("Failed to list sessions: {:?}", e);
@@ -0,0 +1,309 @@
+use crate::message::Message;
+use anyhow::{anyhow, bail, Result};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::storage::{read_messages, read_metadata, save_messages_with_metadata};
+
+/// Version of the message format written to session files
+///
+/// Bump this whenever `Message` or `MessageContent` change in a way older files can no longer
+/// be deserialized, and add a step to `MIGRATIONS` that upgrades the previous version.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Key on the metadata line of a session file that records the message format version.
+/// Files written before versioning was introduced have no such key and are version 0.
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+type Migration = fn(Value) -> Result<Value>;
+
+/// `MIGRATIONS[n]` upgrades a serialized message from version `n` to version `n + 1`
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1];
+
+/// The message format version recorded on a session file's metadata line
+pub fn schema_version(metadata: &Value) -> u32 {
+    metadata
+        .get(SCHEMA_VERSION_KEY)
+        .and_then(Value::as_u64)
+        .map(|version| version as u32)
+        .unwrap_or(0)
+}
+
+/// Upgrade a serialized message written with format `version` to the current format
+pub fn migrate_message(mut message: Value, version: u32) -> Result<Value> {
+    if version > CURRENT_SCHEMA_VERSION {
+        bail!(
+            "Session was written by a newer version of goose (message format {}, this version supports up to {})",
+            version,
+            CURRENT_SCHEMA_VERSION
+        );
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        message = migration(message)?;
+    }
+    Ok(message)
+}
+
+/// Parse a line of a session file written with format `version`
+pub fn parse_message(line: &str, version: u32) -> Result<Message> {
+    if version == CURRENT_SCHEMA_VERSION {
+        return Ok(serde_json::from_str(line)?);
+    }
+    let value = migrate_message(serde_json::from_str(line)?, version)?;
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Version 0 covers every file written before the format was versioned, so this step
+/// accepts the shapes older releases produced and leaves already current messages untouched:
+/// - `content` as a plain string or a single item instead of a list
+/// - a missing `created` timestamp
+/// - snake_case content types and field names
+/// - tool calls and results stored without the `status` wrapper
+fn migrate_v0_to_v1(mut message: Value) -> Result<Value> {
+    let object = message
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Expected a message object"))?;
+
+    object.entry("created").or_insert(Value::from(0));
+
+    let content = match object.remove("content") {
+        Some(Value::Array(items)) => items,
+        Some(Value::String(text)) => vec![serde_json::json!({"type": "text", "text": text})],
+        Some(Value::Null) | None => Vec::new(),
+        Some(item) => vec![item],
+    };
+    let content = content
+        .into_iter()
+        .map(|item| match item {
+            Value::String(text) => serde_json::json!({"type": "text", "text": text}),
+            Value::Object(fields) => Value::Object(migrate_content_v0(fields)),
+            other => other,
+        })
+        .collect();
+    object.insert("content".to_string(), Value::Array(content));
+
+    Ok(message)
+}
+
+fn migrate_content_v0(fields: Map<String, Value>) -> Map<String, Value> {
+    let mut migrated: Map<String, Value> = fields
+        .into_iter()
+        .map(|(key, value)| {
+            let value = if key == "type" {
+                value
+                    .as_str()
+                    .map(|t| Value::String(snake_to_camel(t)))
+                    .unwrap_or(value)
+            } else {
+                value
+            };
+            (snake_to_camel(&key), value)
+        })
+        .collect();
+
+    for key in ["toolCall", "toolResult"] {
+        if let Some(value) = migrated.get_mut(key) {
+            let wrapped = value.as_object().is_some_and(|v| v.contains_key("status"));
+            if !wrapped {
+                *value = serde_json::json!({"status": "success", "value": value.take()});
+            }
+        }
+    }
+    migrated
+}
+
+fn snake_to_camel(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut upper = false;
+    for c in s.chars() {
+        if c == '_' && !out.is_empty() {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// The message format version of a session file, without reading its messages
+pub fn file_schema_version(session_file: &Path) -> Result<u32> {
+    let content = fs::read_to_string(session_file)?;
+    let Some(first_line) = content.lines().next() else {
+        return Ok(CURRENT_SCHEMA_VERSION);
+    };
+    let value: Value = serde_json::from_str(first_line)?;
+    // A file whose first line is a message has no metadata and predates versioning
+    Ok(if value.get("role").is_some() {
+        0
+    } else {
+        schema_version(&value)
+    })
+}
+
+/// Result of migrating a single session file
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationOutcome {
+    UpToDate,
+    Migrated {
+        from: u32,
+        backup: PathBuf,
+    },
+    /// Would be migrated, only returned for dry runs
+    Pending {
+        from: u32,
+    },
+}
+
+/// Rewrite a session file in the current message format, keeping a copy of the original
+/// next to it as `<name>.v<version>.bak`
+pub fn migrate_session_file(session_file: &Path, dry_run: bool) -> Result<MigrationOutcome> {
+    let from = file_schema_version(session_file)?;
+    if from == CURRENT_SCHEMA_VERSION {
+        return Ok(MigrationOutcome::UpToDate);
+    }
+    // Parse everything before touching the file so a failure leaves it as it was
+    let metadata = read_metadata(session_file)?;
+    let messages = read_messages(session_file)?;
+    if dry_run {
+        return Ok(MigrationOutcome::Pending { from });
+    }
+
+    let mut backup = session_file.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", from));
+    let backup = PathBuf::from(backup);
+    fs::copy(session_file, &backup)?;
+    save_messages_with_metadata(session_file, &metadata, &messages)?;
+    Ok(MigrationOutcome::Migrated { from, backup })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::MessageContent;
+    use crate::session::storage::SessionMetadata;
+    use mcp_core::tool::ToolCall;
+    use mcp_core::Content;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn sample_messages() -> Vec<Message> {
+        vec![
+            Message::user().with_text("Hello"),
+            Message::user().with_image("aGVsbG8=", "image/png"),
+            Message::assistant()
+                .with_thinking("hmm", "sig")
+                .with_redacted_thinking("redacted")
+                .with_tool_request("1", Ok(ToolCall::new("view", json!({"path": "/a"})))),
+            Message::user().with_tool_response("1", Ok(vec![Content::text("file contents")])),
+            Message::assistant().with_tool_confirmation_request(
+                "3",
+                "shell".to_string(),
+                json!({"command": "ls"}),
+                Some("Allow?".to_string()),
+            ),
+            Message::assistant().with_frontend_tool_request(
+                "4",
+                Ok(ToolCall::new("frontend", json!({"a": [1, 2, {"b": null}]}))),
+            ),
+            Message::assistant().with_context_length_exceeded("too long"),
+            Message::assistant().with_summarization_requested("summarize"),
+            Message::user().with_text("unicode ✓ \"quotes\" \n newlines \u{0}"),
+        ]
+    }
+
+    #[test]
+    fn test_round_trip_every_content_type() -> Result<()> {
+        let messages = sample_messages();
+        let dir = tempdir()?;
+        let file = dir.path().join("session.jsonl");
+        save_messages_with_metadata(&file, &SessionMetadata::default(), &messages)?;
+
+        assert_eq!(file_schema_version(&file)?, CURRENT_SCHEMA_VERSION);
+        assert_eq!(read_messages(&file)?, messages);
+
+        // Current messages pass through every migration unchanged
+        for message in &messages {
+            let value = serde_json::to_value(message)?;
+            for version in 0..=CURRENT_SCHEMA_VERSION {
+                assert_eq!(migrate_message(value.clone(), version)?, value);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_legacy_messages() -> Result<()> {
+        let legacy = json!({
+            "role": "assistant",
+            "content": [
+                "plain text",
+                {"type": "tool_request", "id": "1", "tool_call": {"name": "view", "arguments": {}}},
+                {"type": "image", "data": "aGVsbG8=", "mime_type": "image/png"}
+            ]
+        });
+        let message: Message = serde_json::from_value(migrate_message(legacy, 0)?)?;
+        assert_eq!(message.created, 0);
+        assert_eq!(message.as_concat_text(), "plain text");
+        assert!(matches!(
+            &message.content[1],
+            MessageContent::ToolRequest(request) if request.tool_call.as_ref().unwrap().name == "view"
+        ));
+        assert!(matches!(&message.content[2], MessageContent::Image(_)));
+
+        let string_content = json!({"role": "user", "created": 5, "content": "hi"});
+        let message = parse_message(&string_content.to_string(), 0)?;
+        assert_eq!(message.as_concat_text(), "hi");
+
+        // Tool errors keep their wrapper, only the message is stored
+        let error = json!({"role": "user", "created": 5, "content": [{
+            "type": "toolResponse", "id": "1", "toolResult": {"status": "error", "error": "boom"}
+        }]});
+        let message = parse_message(&error.to_string(), 0)?;
+        assert!(matches!(
+            &message.content[0],
+            MessageContent::ToolResponse(response) if response.tool_result.is_err()
+        ));
+
+        assert!(migrate_message(json!({}), CURRENT_SCHEMA_VERSION + 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_session_file() -> Result<()> {
+        let dir = tempdir()?;
+        let file = dir.path().join("old.jsonl");
+        let lines = [
+            json!({"description": "old session", "working_dir": "/tmp", "message_count": 2}),
+            json!({"role": "user", "created": 1, "content": "hello"}),
+            json!({"role": "assistant", "content": [{"type": "text", "text": "hi"}]}),
+        ];
+        let content: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        fs::write(&file, &content)?;
+
+        assert_eq!(
+            migrate_session_file(&file, true)?,
+            MigrationOutcome::Pending { from: 0 }
+        );
+        assert_eq!(fs::read_to_string(&file)?, content);
+
+        let MigrationOutcome::Migrated { from, backup } = migrate_session_file(&file, false)?
+        else {
+            panic!("expected the session to be migrated");
+        };
+        assert_eq!(from, 0);
+        assert_eq!(fs::read_to_string(&backup)?, content);
+        assert_eq!(file_schema_version(&file)?, CURRENT_SCHEMA_VERSION);
+        assert_eq!(read_metadata(&file)?.description, "old session");
+        assert_eq!(read_messages(&file)?.len(), 2);
+
+        assert_eq!(
+            migrate_session_file(&file, false)?,
+            MigrationOutcome::UpToDate
+        );
+        Ok(())
+    }
+}
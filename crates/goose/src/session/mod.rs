@@ -1,11 +1,20 @@
+pub mod archive;
+pub mod checkpoint;
+pub mod history;
 pub mod info;
+pub mod migration;
+pub mod partial;
 pub mod storage;
 
 // Re-export common session types and functions
 pub use storage::{
     ensure_session_dir, generate_description, generate_session_id, get_most_recent_session,
-    get_path, list_sessions, persist_messages, read_messages, read_metadata, update_metadata,
-    Identifier, SessionMetadata,
+    get_path, list_sessions, list_sessions_in, persist_messages, read_messages, read_metadata,
+    update_metadata, Identifier, MessageUsage, SessionMetadata,
 };
 
-pub use info::{get_session_info, SessionInfo};
+pub use archive::{archive_messages, read_archive, ArchivedRange};
+pub use checkpoint::{HandoffMode, RunCheckpoint};
+pub use info::{get_session_info, get_session_info_in, SessionInfo};
+pub use migration::{migrate_session_file, MigrationOutcome, CURRENT_SCHEMA_VERSION};
+pub use partial::recover_partial_message;
@@ -0,0 +1,264 @@
+use crate::message::Message;
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::future::Future;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::storage::{read_messages, read_metadata, save_messages_with_metadata};
+
+/// Appended to a recovered message so neither the user nor the model mistakes it for a
+/// complete response
+pub const INTERRUPTED_MARKER: &str =
+    "[This response was interrupted before it finished and is incomplete]";
+
+/// Deltas are written once this much text is buffered, or `FLUSH_INTERVAL` after the last
+/// write, so fast streams are not slowed down by a disk write per token
+const FLUSH_BYTES: usize = 512;
+const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+tokio::task_local! {
+    static SESSION_FILE: PathBuf;
+}
+
+/// Run `f` with streamed responses journaled next to `session_file`
+pub async fn with_stream_journal<F: Future>(session_file: PathBuf, f: F) -> F::Output {
+    SESSION_FILE.scope(session_file, f).await
+}
+
+/// Where the response being streamed for a session is journaled, `<session>.partial`
+pub fn partial_path(session_file: &Path) -> PathBuf {
+    session_file.with_extension("partial")
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Delta {
+    text: String,
+    /// Set on the last delta once the whole response arrived
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    complete: bool,
+}
+
+/// Append-only journal of a response as it streams in, so a crash or dropped connection
+/// does not lose all of a long generation
+pub struct StreamJournal {
+    path: PathBuf,
+    writer: io::BufWriter<File>,
+    pending: String,
+    last_flush: Instant,
+}
+
+impl StreamJournal {
+    pub fn create(session_file: &Path) -> io::Result<Self> {
+        let path = partial_path(session_file);
+        let file = File::create(&path)?;
+        Ok(Self {
+            path,
+            writer: io::BufWriter::new(file),
+            pending: String::new(),
+            last_flush: Instant::now(),
+        })
+    }
+
+    /// A journal for the session of the current reply, if it has one
+    pub fn for_current_session() -> Option<Self> {
+        SESSION_FILE
+            .try_with(|session_file| Self::create(session_file))
+            .ok()?
+            .map_err(|e| tracing::warn!("Failed to create stream journal: {}", e))
+            .ok()
+    }
+
+    /// Record more of the response. Journaling is best effort and never fails the stream.
+    pub fn push(&mut self, text: &str) {
+        self.pending.push_str(text);
+        if self.pending.len() >= FLUSH_BYTES || self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            if let Err(e) = self.flush() {
+                tracing::warn!("Failed to write stream journal: {}", e);
+            }
+        }
+    }
+
+    fn write_delta(&mut self, complete: bool) -> io::Result<()> {
+        if !self.pending.is_empty() || complete {
+            let delta = Delta {
+                text: std::mem::take(&mut self.pending),
+                complete,
+            };
+            serde_json::to_writer(&mut self.writer, &delta)?;
+            writeln!(self.writer)?;
+        }
+        self.writer.flush()?;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_delta(false)
+    }
+
+    /// The response arrived in full. The journal is kept, marked complete, until the response
+    /// is persisted with the session, see [`clear_finished`].
+    pub fn finish(mut self) {
+        if let Err(e) = self.write_delta(true) {
+            tracing::warn!("Failed to write stream journal: {}", e);
+        }
+    }
+}
+
+impl Drop for StreamJournal {
+    // Keep what was buffered when the stream is dropped, e.g. when the user interrupts it
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Text of a journaled response and whether all of it arrived. A line cut short by a crash
+/// is skipped.
+fn read_partial(path: &Path) -> io::Result<(String, bool)> {
+    let file = File::open(path)?;
+    let mut text = String::new();
+    let mut complete = false;
+    for line in io::BufReader::new(file).lines() {
+        if let Ok(delta) = serde_json::from_str::<Delta>(&line?) {
+            text.push_str(&delta.text);
+            complete = delta.complete;
+        }
+    }
+    Ok((text, complete))
+}
+
+/// Drop the journal of a response that arrived in full, called once the session's messages
+/// are persisted. A journal still being streamed into is left alone.
+pub fn clear_finished(session_file: &Path) -> io::Result<()> {
+    let path = partial_path(session_file);
+    if !path.exists() {
+        return Ok(());
+    }
+    let (_, complete) = read_partial(&path)?;
+    if complete {
+        fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+/// Turn a journaled response that never made it into the session into a message, marked as
+/// incomplete when it was interrupted part way through, removing the journal
+pub fn take_partial_message(session_file: &Path) -> Result<Option<Message>> {
+    let path = partial_path(session_file);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let (text, complete) = read_partial(&path)?;
+    let modified = fs::metadata(&path).and_then(|m| m.modified());
+    fs::remove_file(&path)?;
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let text = if complete {
+        text
+    } else {
+        format!("{}\n\n{}", text.trim_end(), INTERRUPTED_MARKER)
+    };
+    let mut message = Message::assistant().with_text(text);
+    if let Ok(modified) = modified {
+        message.created = chrono::DateTime::<Utc>::from(modified).timestamp();
+    }
+    Ok(Some(message))
+}
+
+/// Append a response that was still streaming when goose stopped to the end of its session,
+/// marked as incomplete
+pub fn recover_partial_message(session_file: &Path) -> Result<Option<Message>> {
+    let Some(message) = take_partial_message(session_file)? else {
+        return Ok(None);
+    };
+    let metadata = read_metadata(session_file)?;
+    let mut messages = read_messages(session_file)?;
+    messages.push(message.clone());
+    save_messages_with_metadata(session_file, &metadata, &messages)?;
+    Ok(Some(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::storage::SessionMetadata;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_recover_interrupted_response() -> Result<()> {
+        let dir = tempdir()?;
+        let session_file = dir.path().join("session.jsonl");
+        let history = vec![Message::user().with_text("Write a long story")];
+        save_messages_with_metadata(&session_file, &SessionMetadata::default(), &history)?;
+
+        let mut journal = StreamJournal::create(&session_file)?;
+        for word in ["Once ", "upon ", "a ", "time"] {
+            journal.push(word);
+        }
+        // Simulate the process dying part way through writing a delta
+        drop(journal);
+        fs::OpenOptions::new()
+            .append(true)
+            .open(partial_path(&session_file))?
+            .write_all(b"{\"text\": \"the e")?;
+
+        let recovered = recover_partial_message(&session_file)?.unwrap();
+        assert!(recovered
+            .as_concat_text()
+            .starts_with("Once upon a time\n\n"));
+        assert!(recovered.as_concat_text().ends_with(INTERRUPTED_MARKER));
+        assert!(!partial_path(&session_file).exists());
+
+        let messages = read_messages(&session_file)?;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1], recovered);
+
+        // Nothing left to recover
+        assert!(recover_partial_message(&session_file)?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_finished_journal_is_removed_once_persisted() -> Result<()> {
+        let dir = tempdir()?;
+        let session_file = dir.path().join("session.jsonl");
+        let mut messages = vec![Message::user().with_text("Hello")];
+        save_messages_with_metadata(&session_file, &SessionMetadata::default(), &messages)?;
+
+        let mut journal = StreamJournal::create(&session_file)?;
+        journal.push("complete response");
+        // A journal still streaming survives the session being saved
+        crate::session::persist_messages(&session_file, &messages, None).await?;
+        assert!(partial_path(&session_file).exists());
+        journal.finish();
+        assert!(partial_path(&session_file).exists());
+
+        messages.push(Message::assistant().with_text("complete response"));
+        crate::session::persist_messages(&session_file, &messages, None).await?;
+        assert!(!partial_path(&session_file).exists());
+        assert!(recover_partial_message(&session_file)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_finished_but_unsaved_response() -> Result<()> {
+        let dir = tempdir()?;
+        let session_file = dir.path().join("session.jsonl");
+        save_messages_with_metadata(&session_file, &SessionMetadata::default(), &[])?;
+
+        let mut journal = StreamJournal::create(&session_file)?;
+        journal.push("complete response");
+        journal.finish();
+
+        let recovered = recover_partial_message(&session_file)?.unwrap();
+        assert_eq!(recovered.as_concat_text(), "complete response");
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
+use super::migration;
 use crate::message::Message;
-use crate::providers::base::Provider;
+use crate::providers::base::{Provider, ResponseTiming};
 use anyhow::Result;
 use chrono::Local;
 use etcetera::{choose_app_strategy, AppStrategy, AppStrategyArgs};
@@ -41,6 +42,31 @@ pub struct SessionMetadata {
     pub accumulated_input_tokens: Option<i32>,
     /// The number of output tokens used in the session. Accumulated across all messages.
     pub accumulated_output_tokens: Option<i32>,
+    /// Latency of the provider's last response
+    pub last_response_timing: Option<ResponseTiming>,
+    /// Labels for finding the session later, lowercase without spaces
+    pub tags: Vec<String>,
+    /// Free form notes the user added to the session
+    pub notes: Vec<String>,
+    /// The model that produced each assistant response and what it cost
+    pub message_usage: Vec<MessageUsage>,
+    /// Files and directories kept in the context of every request
+    #[schema(value_type = Vec<String>)]
+    pub pinned: Vec<PathBuf>,
+}
+
+/// Tokens and estimated cost of one provider response, attributed to the model that handled it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct MessageUsage {
+    /// Position of the response in the conversation when it was produced
+    pub message_index: usize,
+    pub model: String,
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
+    /// Estimated cost in USD, when the model's price is known
+    pub cost_usd: Option<f64>,
+    /// Estimated USD saved by routing the response to a cheaper model
+    pub saved_usd: Option<f64>,
 }
 
 // Custom deserializer to handle old sessions without working_dir
@@ -61,6 +87,16 @@ impl<'de> Deserialize<'de> for SessionMetadata {
             accumulated_input_tokens: Option<i32>,
             accumulated_output_tokens: Option<i32>,
             working_dir: Option<PathBuf>,
+            #[serde(default)]
+            last_response_timing: Option<ResponseTiming>,
+            #[serde(default)]
+            tags: Vec<String>,
+            #[serde(default)]
+            notes: Vec<String>,
+            #[serde(default)]
+            message_usage: Vec<MessageUsage>,
+            #[serde(default)]
+            pinned: Vec<PathBuf>,
         }
 
         let helper = Helper::deserialize(deserializer)?;
@@ -82,6 +118,11 @@ impl<'de> Deserialize<'de> for SessionMetadata {
             accumulated_input_tokens: helper.accumulated_input_tokens,
             accumulated_output_tokens: helper.accumulated_output_tokens,
             working_dir,
+            last_response_timing: helper.last_response_timing,
+            tags: helper.tags,
+            notes: helper.notes,
+            message_usage: helper.message_usage,
+            pinned: helper.pinned,
         })
     }
 }
@@ -106,8 +147,44 @@ impl SessionMetadata {
             accumulated_total_tokens: None,
             accumulated_input_tokens: None,
             accumulated_output_tokens: None,
+            last_response_timing: None,
+            tags: Vec::new(),
+            notes: Vec::new(),
+            message_usage: Vec::new(),
+            pinned: Vec::new(),
+        }
+    }
+
+    /// Tags are compared case insensitively and may be written with a leading `#`
+    pub fn normalize_tag(tag: &str) -> Option<String> {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        (!tag.is_empty() && !tag.contains(char::is_whitespace)).then_some(tag)
+    }
+
+    /// Add `tag`, returning false if it was invalid or already present
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        match Self::normalize_tag(tag) {
+            Some(tag) if !self.tags.contains(&tag) => {
+                self.tags.push(tag);
+                true
+            }
+            _ => false,
         }
     }
+
+    /// Remove `tag`, returning false if the session didn't have it
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let Some(tag) = Self::normalize_tag(tag) else {
+            return false;
+        };
+        let before = self.tags.len();
+        self.tags.retain(|existing| existing != &tag);
+        self.tags.len() != before
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        Self::normalize_tag(tag).is_some_and(|tag| self.tags.contains(&tag))
+    }
 }
 
 impl Default for SessionMetadata {
@@ -184,8 +261,15 @@ pub fn get_most_recent_session() -> Result<PathBuf> {
 
 /// List all available session files
 pub fn list_sessions() -> Result<Vec<(String, PathBuf)>> {
-    let session_dir = ensure_session_dir()?;
-    let entries = fs::read_dir(&session_dir)?
+    list_sessions_in(&ensure_session_dir()?)
+}
+
+/// List the session files in a directory other than the default one
+pub fn list_sessions_in(session_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    if !session_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(session_dir)?
         .filter_map(|entry| {
             let entry = entry.ok()?;
             let path = entry.path();
@@ -211,6 +295,7 @@ pub fn generate_session_id() -> String {
 ///
 /// Creates the file if it doesn't exist, reads and deserializes all messages if it does.
 /// The first line of the file is expected to be metadata, and the rest are messages.
+/// Messages written in an older format are migrated to the current one as they are read.
 pub fn read_messages(session_file: &Path) -> Result<Vec<Message>> {
     let file = fs::OpenOptions::new()
         .read(true)
@@ -223,21 +308,25 @@ pub fn read_messages(session_file: &Path) -> Result<Vec<Message>> {
     let mut lines = reader.lines();
     let mut messages = Vec::new();
 
+    // Files without a metadata line predate versioning
+    let mut version = 0;
+
     // Read the first line as metadata or create default if empty/missing
     if let Some(line) = lines.next() {
         let line = line?;
         // Try to parse as metadata, but if it fails, treat it as a message
-        if let Ok(_metadata) = serde_json::from_str::<SessionMetadata>(&line) {
+        if serde_json::from_str::<SessionMetadata>(&line).is_ok() {
             // Metadata successfully parsed, continue with the rest of the lines as messages
+            version = migration::schema_version(&serde_json::from_str(&line)?);
         } else {
             // This is not metadata, it's a message
-            messages.push(serde_json::from_str::<Message>(&line)?);
+            messages.push(migration::parse_message(&line, version)?);
         }
     }
 
-    // Read the rest of the lines as messages
+    // Read the rest of the lines as messages, upgrading them from older formats
     for line in lines {
-        messages.push(serde_json::from_str::<Message>(&line?)?);
+        messages.push(migration::parse_message(&line?, version)?);
     }
 
     Ok(messages)
@@ -290,15 +379,21 @@ pub async fn persist_messages(
     match provider {
         Some(provider) if user_message_count < 4 => {
             //generate_description is responsible for writing the messages
-            generate_description(session_file, messages, provider).await
+            generate_description(session_file, messages, provider).await?;
         }
         _ => {
             // Read existing metadata
             let metadata = read_metadata(session_file)?;
             // Write the file with metadata and messages
-            save_messages_with_metadata(session_file, &metadata, messages)
+            save_messages_with_metadata(session_file, &metadata, messages)?;
         }
     }
+
+    // The streamed response is safely in the session now
+    if let Err(e) = super::partial::clear_finished(session_file) {
+        tracing::warn!("Failed to remove stream journal: {}", e);
+    }
+    Ok(())
 }
 
 /// Write messages to a session file with the provided metadata
@@ -312,7 +407,9 @@ pub fn save_messages_with_metadata(
     let file = File::create(session_file).expect("The path specified does not exist");
     let mut writer = io::BufWriter::new(file);
 
-    // Write metadata as the first line
+    // Write metadata as the first line, recording the format the messages are written in
+    let mut metadata = serde_json::to_value(metadata)?;
+    metadata[migration::SCHEMA_VERSION_KEY] = migration::CURRENT_SCHEMA_VERSION.into();
     serde_json::to_writer(&mut writer, &metadata)?;
     writeln!(writer)?;
 
@@ -615,4 +712,32 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_session_tags() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("tags.jsonl");
+
+        let mut metadata = SessionMetadata::default();
+        assert!(metadata.add_tag("#Refactor"));
+        assert!(!metadata.add_tag("refactor"));
+        assert!(!metadata.add_tag("two words"));
+        assert!(metadata.add_tag("migration"));
+        metadata.notes.push("Has the migration plan".to_string());
+        assert!(metadata.remove_tag("MIGRATION"));
+        assert!(!metadata.remove_tag("missing"));
+
+        save_messages_with_metadata(&file_path, &metadata, &[])?;
+        let read_metadata = read_metadata(&file_path)?;
+        assert_eq!(read_metadata.tags, vec!["refactor"]);
+        assert!(read_metadata.has_tag("#refactor"));
+        assert_eq!(read_metadata.notes, vec!["Has the migration plan"]);
+
+        // Sessions written before tags existed still load
+        fs::write(&file_path, r#"{"description":"old","message_count":0}"#)?;
+        let old = read_metadata(&file_path)?;
+        assert_eq!(old.description, "old");
+        assert!(old.tags.is_empty());
+        Ok(())
+    }
 }
@@ -311,6 +311,7 @@ mod tests {
                 "required": ["location"]
             }),
             annotations: None,
+            output_schema: None,
         }];
 
         let token_count_without_tools = counter.count_chat_tokens(system_prompt, &messages, &[]);
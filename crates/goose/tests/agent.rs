@@ -136,6 +136,21 @@ async fn run_truncate_test(
             Ok(AgentEvent::McpNotification(n)) => {
                 println!("MCP Notification: {n:?}");
             }
+            Ok(AgentEvent::Timeout(timeout)) => {
+                println!("Timeout: {timeout:?}");
+            }
+            Ok(AgentEvent::HistoryReplaced(history)) => {
+                println!("History replaced with {} messages", history.len());
+            }
+            Ok(AgentEvent::Guardrail(event)) => {
+                println!("Guardrail: {event:?}");
+            }
+            Ok(AgentEvent::ExtensionDisabled(event)) => {
+                println!("Extension disabled: {event:?}");
+            }
+            Ok(AgentEvent::ContextRecovered(event)) => {
+                println!("Context recovered: {event:?}");
+            }
             Err(e) => {
                 println!("Error: {:?}", e);
                 return Err(e);
@@ -1,10 +1,12 @@
 use mcp_core::protocol::{
-    CallToolResult, GetPromptResult, Implementation, InitializeResult, JsonRpcError,
-    JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, ListPromptsResult,
-    ListResourcesResult, ListToolsResult, ReadResourceResult, ServerCapabilities, METHOD_NOT_FOUND,
+    CallToolResult, GetInstructionsResult, GetPromptResult, Implementation, InitializeResult,
+    JsonRpcError, JsonRpcMessage, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse,
+    ListPromptsResult, ListResourcesResult, ListToolsResult, ReadResourceResult,
+    ServerCapabilities, METHOD_NOT_FOUND,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::sync::{
     atomic::{AtomicU64, Ordering},
     Arc,
@@ -13,6 +15,7 @@ use thiserror::Error;
 use tokio::sync::{mpsc, Mutex};
 use tower::{timeout::TimeoutLayer, Layer, Service, ServiceExt};
 
+use crate::validation::{self, OutputValidation};
 use crate::{McpService, TransportHandle};
 
 pub type BoxError = Box<dyn std::error::Error + Sync + Send>;
@@ -51,6 +54,9 @@ pub enum Error {
         #[source]
         source: BoxError,
     },
+
+    #[error("Tool '{tool}' returned output that doesn't match its output schema: {}", .errors.join("; "))]
+    InvalidToolOutput { tool: String, errors: Vec<String> },
 }
 
 // BoxError from mcp-server gets converted to our Error type
@@ -103,6 +109,8 @@ pub trait McpClientTrait: Send + Sync {
 
     async fn get_prompt(&self, name: &str, arguments: Value) -> Result<GetPromptResult, Error>;
 
+    async fn get_instructions(&self) -> Result<GetInstructionsResult, Error>;
+
     async fn subscribe(&self) -> mpsc::Receiver<JsonRpcMessage>;
 }
 
@@ -112,10 +120,43 @@ where
     T: TransportHandle + Send + Sync + 'static,
 {
     service: Mutex<tower::timeout::Timeout<McpService<T>>>,
+    /// Unlocked handle to the service, for cancelling requests while another one holds the lock
+    canceller: McpService<T>,
     next_id: AtomicU64,
     server_capabilities: Option<ServerCapabilities>,
     server_info: Option<Implementation>,
     notification_subscribers: Arc<Mutex<Vec<mpsc::Sender<JsonRpcMessage>>>>,
+    /// Output schemas of the tools seen in `list_tools`, by tool name
+    output_schemas: Mutex<HashMap<String, Value>>,
+    output_validation: OutputValidation,
+}
+
+/// Sends `notifications/cancelled` for a request unless disarmed before it is dropped
+struct CancelOnDrop<T: TransportHandle> {
+    service: McpService<T>,
+    id: u64,
+    armed: bool,
+}
+
+impl<T: TransportHandle> Drop for CancelOnDrop<T> {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let service = self.service.clone();
+        let id = self.id;
+        runtime.spawn(async move {
+            if let Err(e) = service
+                .cancel(id, "The client stopped waiting for the request")
+                .await
+            {
+                tracing::debug!("failed to cancel request {}: {:?}", id, e);
+            }
+        });
+    }
 }
 
 impl<T> McpClient<T>
@@ -157,14 +198,23 @@ where
         let middleware = TimeoutLayer::new(timeout);
 
         Ok(Self {
+            canceller: service.clone(),
             service: Mutex::new(middleware.layer(service)),
             next_id: AtomicU64::new(1),
             server_capabilities: None,
             server_info: None,
             notification_subscribers,
+            output_schemas: Mutex::new(HashMap::new()),
+            output_validation: OutputValidation::default(),
         })
     }
 
+    /// Set how results of tools that declare an output schema are checked against it
+    pub fn with_output_validation(mut self, output_validation: OutputValidation) -> Self {
+        self.output_validation = output_validation;
+        self
+    }
+
     /// Send a JSON-RPC request and check we don't get an error response.
     async fn send_request<R>(&self, method: &str, params: Value) -> Result<R, Error>
     where
@@ -186,19 +236,29 @@ where
             params: Some(params),
         });
 
-        let response_msg = service
-            .call(request)
-            .await
-            .map_err(|e| Error::McpServerError {
-                server: self
-                    .server_info
-                    .as_ref()
-                    .map(|s| s.name.clone())
-                    .unwrap_or("".to_string()),
-                method: method.to_string(),
-                // we don't need include params because it can be really large
-                source: Box::<Error>::new(e.into()),
-            })?;
+        // If the caller stops waiting, whether it timed out or dropped this future, let the
+        // server know so it can stop the work
+        let mut cancel = CancelOnDrop {
+            service: self.canceller.clone(),
+            id,
+            armed: true,
+        };
+        let response = service.call(request).await;
+        if !matches!(response, Err(ref e) if e.is::<tower::timeout::error::Elapsed>()) {
+            cancel.armed = false;
+        }
+        drop(cancel);
+
+        let response_msg = response.map_err(|e| Error::McpServerError {
+            server: self
+                .server_info
+                .as_ref()
+                .map(|s| s.name.clone())
+                .unwrap_or("".to_string()),
+            method: method.to_string(),
+            // we don't need include params because it can be really large
+            source: Box::<Error>::new(e.into()),
+        })?;
 
         match response_msg {
             JsonRpcMessage::Response(JsonRpcResponse {
@@ -370,7 +430,16 @@ where
             .map(|cursor| serde_json::json!({"cursor": cursor}))
             .unwrap_or_else(|| serde_json::json!({}));
 
-        self.send_request("tools/list", payload).await
+        let result: ListToolsResult = self.send_request("tools/list", payload).await?;
+
+        let mut output_schemas = self.output_schemas.lock().await;
+        for tool in &result.tools {
+            match &tool.output_schema {
+                Some(schema) => output_schemas.insert(tool.name.clone(), schema.clone()),
+                None => output_schemas.remove(&tool.name),
+            };
+        }
+        Ok(result)
     }
 
     async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallToolResult, Error> {
@@ -389,7 +458,35 @@ where
 
         // TODO ERROR: check that if there is an error, we send back is_error: true with msg
         // https://modelcontextprotocol.io/docs/concepts/tools#error-handling-2
-        self.send_request("tools/call", params).await
+        let mut result: CallToolResult = self.send_request("tools/call", params).await?;
+
+        // Check results against the declared schema, so a buggy server is caught here rather
+        // than feeding malformed data to the model
+        if self.output_validation == OutputValidation::Off || result.is_error == Some(true) {
+            return Ok(result);
+        }
+        let Some(schema) = self.output_schemas.lock().await.get(name).cloned() else {
+            return Ok(result);
+        };
+        let errors = validation::check_result(&schema, &result);
+        if errors.is_empty() {
+            return Ok(result);
+        }
+        tracing::warn!(
+            "Output of tool '{}' doesn't match its schema: {}",
+            name,
+            errors.join("; ")
+        );
+        if self.output_validation == OutputValidation::Strict {
+            return Err(Error::InvalidToolOutput {
+                tool: name.to_string(),
+                errors,
+            });
+        }
+        result
+            .content
+            .push(validation::warning_content(name, &errors));
+        Ok(result)
     }
 
     async fn list_prompts(&self, next_cursor: Option<String>) -> Result<ListPromptsResult, Error> {
@@ -430,9 +527,268 @@ where
         self.send_request("prompts/get", params).await
     }
 
+    async fn get_instructions(&self) -> Result<GetInstructionsResult, Error> {
+        if !self.completed_initialization() {
+            return Err(Error::NotInitialized);
+        }
+
+        // Only servers that advertise dynamic instructions can be asked to re-send them
+        if self
+            .server_capabilities
+            .as_ref()
+            .unwrap()
+            .instructions
+            .is_none()
+        {
+            return Err(Error::RpcError {
+                code: METHOD_NOT_FOUND,
+                message: "Server does not support 'instructions' capability".to_string(),
+            });
+        }
+
+        self.send_request("instructions/get", serde_json::json!({}))
+            .await
+    }
+
     async fn subscribe(&self) -> mpsc::Receiver<JsonRpcMessage> {
         let (tx, rx) = mpsc::channel(16);
         self.notification_subscribers.lock().await.push(tx);
         rx
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    type Handler = Arc<dyn Fn(&str, &Value) -> Option<Value> + Send + Sync>;
+
+    /// Answers requests through `handler`, leaving them unanswered when it returns None, and
+    /// records everything the client sends
+    #[derive(Clone)]
+    struct MockTransport {
+        handler: Handler,
+        sent: Arc<std::sync::Mutex<Vec<JsonRpcMessage>>>,
+        tx: mpsc::Sender<JsonRpcMessage>,
+        rx: Arc<Mutex<mpsc::Receiver<JsonRpcMessage>>>,
+    }
+
+    impl MockTransport {
+        fn new(handler: impl Fn(&str, &Value) -> Option<Value> + Send + Sync + 'static) -> Self {
+            let (tx, rx) = mpsc::channel(16);
+            Self {
+                handler: Arc::new(handler),
+                sent: Arc::new(std::sync::Mutex::new(Vec::new())),
+                tx,
+                rx: Arc::new(Mutex::new(rx)),
+            }
+        }
+
+        fn notifications(&self, method: &str) -> Vec<Value> {
+            self.sent
+                .lock()
+                .unwrap()
+                .iter()
+                .filter_map(|message| match message {
+                    JsonRpcMessage::Notification(n) if n.method == method => n.params.clone(),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TransportHandle for MockTransport {
+        async fn send(
+            &self,
+            message: JsonRpcMessage,
+        ) -> Result<(), super::super::transport::Error> {
+            self.sent.lock().unwrap().push(message.clone());
+            if let JsonRpcMessage::Request(JsonRpcRequest {
+                id: Some(id),
+                method,
+                params,
+                ..
+            }) = message
+            {
+                let params = params.unwrap_or_default();
+                if let Some(result) = (self.handler)(&method, &params) {
+                    let _ = self
+                        .tx
+                        .send(JsonRpcMessage::Response(JsonRpcResponse {
+                            jsonrpc: "2.0".to_string(),
+                            id: Some(id),
+                            result: Some(result),
+                            error: None,
+                        }))
+                        .await;
+                }
+            }
+            Ok(())
+        }
+
+        async fn receive(&self) -> Result<JsonRpcMessage, super::super::transport::Error> {
+            self.rx
+                .lock()
+                .await
+                .recv()
+                .await
+                .ok_or(super::super::transport::Error::ChannelClosed)
+        }
+    }
+
+    fn initialize_result() -> Value {
+        json!({
+            "protocolVersion": "2025-03-26",
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "mock", "version": "1.0.0" },
+        })
+    }
+
+    async fn connect(transport: MockTransport, timeout: Duration) -> McpClient<MockTransport> {
+        let mut client = McpClient::connect(transport, timeout).await.unwrap();
+        client
+            .initialize(
+                ClientInfo {
+                    name: "test".to_string(),
+                    version: "1.0.0".to_string(),
+                },
+                ClientCapabilities::default(),
+            )
+            .await
+            .unwrap();
+        client
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_requests_are_cancelled() {
+        let unanswered = || {
+            MockTransport::new(|method, _| match method {
+                "initialize" => Some(initialize_result()),
+                _ => None,
+            })
+        };
+
+        // The caller gives up on the call and drops it
+        let transport = unanswered();
+        let client = connect(transport.clone(), Duration::from_secs(30)).await;
+        let dropped = tokio::time::timeout(
+            Duration::from_millis(50),
+            client.call_tool("slow", json!({})),
+        );
+        assert!(dropped.await.is_err());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            transport.notifications("notifications/cancelled"),
+            vec![json!({
+                "requestId": 2,
+                "reason": "The client stopped waiting for the request",
+            })]
+        );
+
+        // The client's own timeout runs out
+        let transport = unanswered();
+        let client = connect(transport.clone(), Duration::from_millis(50)).await;
+        assert!(client.call_tool("slow", json!({})).await.is_err());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(transport.notifications("notifications/cancelled").len(), 1);
+
+        // Answered requests are left alone
+        let transport = MockTransport::new(|method, _| match method {
+            "initialize" => Some(initialize_result()),
+            _ => Some(json!({ "content": [] })),
+        });
+        let client = connect(transport.clone(), Duration::from_secs(30)).await;
+        client.call_tool("fast", json!({})).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(transport
+            .notifications("notifications/cancelled")
+            .is_empty());
+    }
+
+    /// A server whose `weather` tool declares an output schema, and returns a result matching
+    /// it when called for the city "valid"
+    fn weather_server() -> MockTransport {
+        MockTransport::new(|method, params| match method {
+            "initialize" => Some(initialize_result()),
+            "tools/list" => Some(json!({
+                "tools": [{
+                    "name": "weather",
+                    "description": "Current weather",
+                    "inputSchema": { "type": "object" },
+                    "outputSchema": {
+                        "type": "object",
+                        "properties": { "temperature": { "type": "number" } },
+                        "required": ["temperature"],
+                    },
+                }],
+            })),
+            "tools/call" => {
+                let output = match params["arguments"]["city"].as_str() {
+                    Some("valid") => json!({ "temperature": 21.5 }),
+                    _ => json!({ "temperature": "warm" }),
+                };
+                Some(json!({
+                    "content": [{ "type": "text", "text": output.to_string() }],
+                    "structuredContent": output,
+                }))
+            }
+            _ => None,
+        })
+    }
+
+    async fn connect_with(validation: OutputValidation) -> McpClient<MockTransport> {
+        let client = connect(weather_server(), Duration::from_secs(30))
+            .await
+            .with_output_validation(validation);
+        client.list_tools(None).await.unwrap();
+        client
+    }
+
+    #[tokio::test]
+    async fn test_tool_output_is_checked_against_its_schema() {
+        // A result matching the schema is passed on as it is
+        let client = connect_with(OutputValidation::Warn).await;
+        let result = client
+            .call_tool("weather", json!({ "city": "valid" }))
+            .await
+            .unwrap();
+        assert_eq!(result.content.len(), 1);
+
+        // One that doesn't gets a warning for the model
+        let result = client
+            .call_tool("weather", json!({ "city": "invalid" }))
+            .await
+            .unwrap();
+        assert_eq!(result.content.len(), 2);
+        let warning = result.content[1].as_text().unwrap();
+        assert!(warning.starts_with("Warning: the output of 'weather'"));
+        assert!(warning.contains("temperature"));
+
+        // Strict validation fails the call instead
+        let client = connect_with(OutputValidation::Strict).await;
+        assert!(client
+            .call_tool("weather", json!({ "city": "valid" }))
+            .await
+            .is_ok());
+        match client
+            .call_tool("weather", json!({ "city": "invalid" }))
+            .await
+        {
+            Err(Error::InvalidToolOutput { tool, errors }) => {
+                assert_eq!(tool, "weather");
+                assert!(!errors.is_empty());
+            }
+            other => panic!("expected invalid tool output, got {:?}", other),
+        }
+
+        // And without validation nothing is checked
+        let client = connect_with(OutputValidation::Off).await;
+        let result = client
+            .call_tool("weather", json!({ "city": "invalid" }))
+            .await
+            .unwrap();
+        assert_eq!(result.content.len(), 1);
+    }
+}
@@ -1,7 +1,9 @@
 pub mod client;
 pub mod service;
 pub mod transport;
+pub mod validation;
 
 pub use client::{ClientCapabilities, ClientInfo, Error, McpClient, McpClientTrait};
 pub use service::McpService;
 pub use transport::{SseTransport, StdioTransport, Transport, TransportHandle};
+pub use validation::OutputValidation;
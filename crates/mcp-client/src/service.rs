@@ -1,5 +1,6 @@
 use futures::future::BoxFuture;
-use mcp_core::protocol::{JsonRpcMessage, JsonRpcRequest};
+use mcp_core::protocol::{JsonRpcMessage, JsonRpcNotification, JsonRpcRequest};
+use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::task::{Context, Poll};
@@ -30,6 +31,22 @@ impl<T: TransportHandle> McpService<T> {
     pub async fn hangup(&self) {
         self.pending_requests.broadcast_close().await
     }
+
+    /// Tell the server to stop working on a request the client no longer waits for, and
+    /// forget its pending response
+    pub async fn cancel(&self, id: u64, reason: &str) -> Result<(), Error> {
+        self.pending_requests.remove(&id.to_string()).await;
+        self.inner
+            .send(JsonRpcMessage::Notification(JsonRpcNotification {
+                jsonrpc: "2.0".to_string(),
+                method: "notifications/cancelled".to_string(),
+                params: Some(json!({
+                    "requestId": id,
+                    "reason": reason,
+                })),
+            }))
+            .await
+    }
 }
 
 impl<T> Service<JsonRpcMessage> for McpService<T>
@@ -115,6 +132,10 @@ impl PendingRequests {
         }
     }
 
+    pub async fn remove(&self, id: &str) {
+        self.requests.write().await.remove(id);
+    }
+
     pub async fn broadcast_close(&self) {
         for (_, tx) in self.requests.write().await.drain() {
             let _ = tx.send(Err(Error::ChannelClosed));
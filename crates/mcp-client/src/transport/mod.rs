@@ -74,7 +74,7 @@ pub async fn serialize_and_send(
 }
 
 pub mod stdio;
-pub use stdio::StdioTransport;
+pub use stdio::{StdioTransport, BASE_INHERITED_ENV};
 
 pub mod sse;
 pub use sse::SseTransport;
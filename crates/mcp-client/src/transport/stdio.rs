@@ -1,11 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::OsString;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Arc;
 use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
 
 use async_trait::async_trait;
 use mcp_core::protocol::JsonRpcMessage;
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::sync::{mpsc, Mutex};
 
 // Import nix crate components instead of libc
@@ -19,6 +21,48 @@ use super::{serialize_and_send, Error, Transport, TransportHandle};
 // Global to track process groups we've created
 static PROCESS_GROUP: AtomicI32 = AtomicI32::new(-1);
 
+/// Variables a server inherits even with an allow-list, most programs misbehave without them
+pub const BASE_INHERITED_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "LANG",
+    "LC_*",
+    "TERM",
+    "TMPDIR",
+    "TMP",
+    "TEMP",
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+    // Tags the logs of goose's own extensions with the session that started them
+    "GOOSE_LOG_SESSION",
+];
+
+// How many trailing lines of stderr are kept for the error reported when the process exits
+const STDERR_TAIL_LINES: usize = 50;
+
+/// Whether `key` matches an allow-list entry, entries ending in `*` match a prefix
+fn env_allowed(key: &str, allow_list: &[String]) -> bool {
+    let key = key.to_uppercase();
+    BASE_INHERITED_ENV
+        .iter()
+        .copied()
+        .chain(allow_list.iter().map(String::as_str))
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => key.starts_with(&prefix.to_uppercase()),
+            None => key == pattern.to_uppercase(),
+        })
+}
+
 /// A `StdioTransport` uses a child process's stdin/stdout as a communication channel.
 ///
 /// It uses channels for message passing and handles responses asynchronously through a background task.
@@ -30,6 +74,7 @@ pub struct StdioActor {
     stdin: Option<ChildStdin>,
     stdout: Option<ChildStdout>,
     stderr: Option<ChildStderr>,
+    name: String,
 }
 
 impl Drop for StdioActor {
@@ -57,6 +102,10 @@ impl StdioActor {
         let stdin = self.stdin.take().expect("stdin should be available");
         let msg_inbox = self.receiver.take().expect("receiver should be available");
         let msg_outbox = self.sender.take().expect("sender should be available");
+        let stderr = self.stderr.take().expect("stderr should be available");
+
+        // Log stderr as it arrives rather than only when the process dies
+        let stderr_tail = tokio::spawn(Self::handle_proc_stderr(stderr, self.name.clone()));
 
         let incoming = Self::handle_proc_output(stdout, msg_outbox);
         let outgoing = Self::handle_proc_input(stdin, msg_inbox);
@@ -79,23 +128,47 @@ impl StdioActor {
             }
         }
 
-        // Then always try to read stderr before cleaning up
-        let mut stderr_buffer = Vec::new();
-        if let Some(mut stderr) = self.stderr.take() {
-            if let Ok(bytes) = stderr.read_to_end(&mut stderr_buffer).await {
-                let err_msg = if bytes > 0 {
-                    String::from_utf8_lossy(&stderr_buffer).to_string()
-                } else {
-                    "Process ended unexpectedly".to_string()
-                };
-
-                tracing::info!("Process stderr: {}", err_msg);
-                let _ = self
-                    .error_sender
-                    .send(Error::StdioProcessError(err_msg))
-                    .await;
+        // Then always wait for the rest of stderr before cleaning up
+        if let Ok(tail) = stderr_tail.await {
+            let err_msg = if tail.is_empty() {
+                "Process ended unexpectedly".to_string()
+            } else {
+                tail.into_iter().collect::<Vec<_>>().join("\n")
+            };
+            let _ = self
+                .error_sender
+                .send(Error::StdioProcessError(err_msg))
+                .await;
+        }
+    }
+
+    /// Log each line of stderr prefixed with the server name, returns the last lines
+    async fn handle_proc_stderr(stderr: ChildStderr, name: String) -> VecDeque<String> {
+        let mut reader = BufReader::new(stderr);
+        let mut tail = VecDeque::with_capacity(STDERR_TAIL_LINES);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line).await {
+                Ok(0) => break,
+                Ok(_) => {
+                    let text = String::from_utf8_lossy(&line).trim_end().to_string();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    tracing::info!("[{}] {}", name, text);
+                    if tail.len() == STDERR_TAIL_LINES {
+                        tail.pop_front();
+                    }
+                    tail.push_back(text);
+                }
+                Err(e) => {
+                    tracing::error!(error = ?e, "Error reading stderr of {}", name);
+                    break;
+                }
             }
         }
+        tail
     }
 
     async fn handle_proc_output(stdout: ChildStdout, sender: mpsc::Sender<JsonRpcMessage>) {
@@ -189,6 +262,10 @@ pub struct StdioTransport {
     command: String,
     args: Vec<String>,
     env: HashMap<String, String>,
+    name: Option<String>,
+    cwd: Option<PathBuf>,
+    inherit_env: Option<Vec<String>>,
+    path_prepend: Vec<PathBuf>,
 }
 
 impl StdioTransport {
@@ -201,13 +278,80 @@ impl StdioTransport {
             command: command.into(),
             args,
             env,
+            name: None,
+            cwd: None,
+            inherit_env: None,
+            path_prepend: Vec::new(),
         }
     }
 
+    /// Name used to prefix the server's stderr in the logs, defaults to the command
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Run the server in `cwd` instead of the current directory
+    pub fn with_cwd<P: Into<PathBuf>>(mut self, cwd: P) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    /// Only inherit the variables named in `allow_list` and `BASE_INHERITED_ENV` from this
+    /// process, so servers don't see secrets they weren't given. Entries ending in `*` match
+    /// a prefix. Variables passed to `new` are always set.
+    pub fn with_inherited_env(mut self, allow_list: Vec<String>) -> Self {
+        self.inherit_env = Some(allow_list);
+        self
+    }
+
+    /// Put `dirs` in front of the server's PATH
+    pub fn with_path_prepend(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.path_prepend = dirs;
+        self
+    }
+
+    fn path_env(&self) -> Result<Option<OsString>, Error> {
+        if self.path_prepend.is_empty() {
+            return Ok(None);
+        }
+        let current = match self.env.get("PATH") {
+            Some(path) => Some(OsString::from(path)),
+            None => std::env::var_os("PATH"),
+        };
+        let dirs = self.path_prepend.iter().cloned().chain(
+            current
+                .iter()
+                .flat_map(|path| std::env::split_paths(path).collect::<Vec<_>>()),
+        );
+        std::env::join_paths(dirs)
+            .map(Some)
+            .map_err(|e| Error::StdioProcessError(format!("Invalid PATH entry: {}", e)))
+    }
+
     async fn spawn_process(&self) -> Result<(Child, ChildStdin, ChildStdout, ChildStderr), Error> {
         let mut command = Command::new(&self.command);
+        if let Some(allow_list) = &self.inherit_env {
+            command.env_clear().envs(
+                std::env::vars_os().filter(|(key, _)| {
+                    key.to_str().is_some_and(|key| env_allowed(key, allow_list))
+                }),
+            );
+        }
+        command.envs(&self.env);
+        if let Some(path) = self.path_env()? {
+            command.env("PATH", path);
+        }
+        if let Some(cwd) = &self.cwd {
+            if !cwd.is_dir() {
+                return Err(Error::StdioProcessError(format!(
+                    "Working directory {} does not exist",
+                    cwd.display()
+                )));
+            }
+            command.current_dir(cwd);
+        }
         command
-            .envs(&self.env)
             .args(&self.args)
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
@@ -272,6 +416,7 @@ impl Transport for StdioTransport {
             stdin: Some(stdin),
             stdout: Some(stdout),
             stderr: Some(stderr),
+            name: self.name.clone().unwrap_or_else(|| self.command.clone()),
         };
 
         tokio::spawn(actor.run());
@@ -299,3 +444,74 @@ impl Transport for StdioTransport {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[test]
+    fn test_env_allowed() {
+        let allow_list = vec!["GITHUB_TOKEN".to_string(), "aws_*".to_string()];
+        assert!(env_allowed("PATH", &[]));
+        assert!(env_allowed("LC_ALL", &[]));
+        assert!(env_allowed("github_token", &allow_list));
+        assert!(env_allowed("AWS_REGION", &allow_list));
+        assert!(!env_allowed("GITHUB_TOKEN", &[]));
+        assert!(!env_allowed("OPENAI_API_KEY", &allow_list));
+    }
+
+    #[test]
+    fn test_path_prepend() {
+        let base = std::env::join_paths([PathBuf::from("/usr/bin")]).unwrap();
+        let env = HashMap::from([("PATH".to_string(), base.into_string().unwrap())]);
+        let transport = StdioTransport::new("server", vec![], env)
+            .with_path_prepend(vec![PathBuf::from("/opt/tools/bin")]);
+        let path = transport.path_env().unwrap().unwrap();
+        assert_eq!(
+            std::env::split_paths(&path).collect::<Vec<_>>(),
+            vec![PathBuf::from("/opt/tools/bin"), PathBuf::from("/usr/bin")]
+        );
+
+        let transport = StdioTransport::new("server", vec![], HashMap::new());
+        assert!(transport.path_env().unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_spawn_with_cwd_and_inherited_env() {
+        std::env::set_var("MCP_STDIO_TEST_ALLOWED", "allowed");
+        std::env::set_var("MCP_STDIO_TEST_SECRET", "secret");
+        let cwd = std::env::temp_dir().canonicalize().unwrap();
+        let script = "pwd; echo $MCP_STDIO_TEST_ALLOWED; echo \"[$MCP_STDIO_TEST_SECRET]\"; echo $GIVEN; echo failed >&2";
+        let transport = StdioTransport::new(
+            "sh",
+            vec!["-c".to_string(), script.to_string()],
+            HashMap::from([("GIVEN".to_string(), "given".to_string())]),
+        )
+        .with_cwd(&cwd)
+        .with_inherited_env(vec!["MCP_STDIO_TEST_ALLOWED".to_string()]);
+
+        let (mut child, _stdin, mut stdout, stderr) = transport.spawn_process().await.unwrap();
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).await.unwrap();
+        child.wait().await.unwrap();
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(PathBuf::from(lines[0]).canonicalize().unwrap(), cwd);
+        assert_eq!(lines[1..], ["allowed", "[]", "given"]);
+
+        let tail = StdioActor::handle_proc_stderr(stderr, "test".to_string()).await;
+        assert_eq!(tail, ["failed"]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_cwd_fails_to_spawn() {
+        let transport = StdioTransport::new("sh", vec![], HashMap::new())
+            .with_cwd(std::env::temp_dir().join("mcp-stdio-test-missing-dir"));
+        assert!(matches!(
+            transport.spawn_process().await,
+            Err(Error::StdioProcessError(_))
+        ));
+    }
+}
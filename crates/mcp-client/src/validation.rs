@@ -0,0 +1,165 @@
+use mcp_core::protocol::CallToolResult;
+use mcp_core::Content;
+use serde_json::Value;
+
+/// What the client does with tool results that don't match the tool's declared output schema
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputValidation {
+    /// Pass results through unchecked
+    Off,
+    /// Append the mismatches to the result, so the agent knows not to trust it
+    #[default]
+    Warn,
+    /// Fail the call with `Error::InvalidToolOutput`
+    Strict,
+}
+
+impl std::str::FromStr for OutputValidation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" | "false" => Ok(OutputValidation::Off),
+            "warn" | "" => Ok(OutputValidation::Warn),
+            "strict" | "true" => Ok(OutputValidation::Strict),
+            other => Err(format!(
+                "Unknown output validation '{}', expected off, warn or strict",
+                other
+            )),
+        }
+    }
+}
+
+/// The JSON a result carries, its structured content or else a text block holding JSON, for
+/// servers that put their structured output in the text
+fn structured_output(result: &CallToolResult) -> Option<Value> {
+    if let Some(structured) = &result.structured_content {
+        return Some(structured.clone());
+    }
+    match result.content.as_slice() {
+        [content] => content
+            .as_text()
+            .and_then(|text| serde_json::from_str(text).ok()),
+        _ => None,
+    }
+}
+
+/// Check a tool result against the tool's output schema, returning what doesn't match
+pub fn check_result(schema: &Value, result: &CallToolResult) -> Vec<String> {
+    match structured_output(result) {
+        Some(output) => validate(schema, &output),
+        None => vec!["the result has no structured content".to_string()],
+    }
+}
+
+/// The note appended to a result that doesn't match its schema
+pub fn warning_content(tool: &str, errors: &[String]) -> Content {
+    Content::text(format!(
+        "Warning: the output of '{}' doesn't match the schema the server declared for it, \
+         treat it with care: {}",
+        tool,
+        errors.join("; ")
+    ))
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        // Types we don't know about are not held against the server
+        _ => true,
+    }
+}
+
+/// Validate `value` against the parts of JSON Schema tool schemas use: type, enum, const,
+/// properties, required, additionalProperties, items, anyOf and oneOf. Anything else in the
+/// schema is ignored rather than guessed at.
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(schema, value, "$", &mut errors);
+    errors
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        if schema == &Value::Bool(false) {
+            errors.push(format!("{} is not allowed", path));
+        }
+        return;
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|t| type_matches(t, value)) {
+            errors.push(format!("{} should be {}", path, types.join(" or ")));
+            // The remaining keywords only make sense for the right type
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(format!(
+                "{} should be one of {}",
+                path,
+                Value::from(allowed.clone())
+            ));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(format!("{} should be {}", path, expected));
+        }
+    }
+
+    for (keyword, exactly_one) in [("anyOf", false), ("oneOf", true)] {
+        if let Some(options) = schema.get(keyword).and_then(Value::as_array) {
+            let matching = options
+                .iter()
+                .filter(|option| validate(option, value).is_empty())
+                .count();
+            if matching == 0 || (exactly_one && matching > 1) {
+                errors.push(format!("{} doesn't match the {} options", path, keyword));
+            }
+        }
+    }
+
+    if let Value::Object(object) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(format!("{}.{} is missing", path, name));
+                }
+            }
+        }
+        for (name, field) in object {
+            let field_path = format!("{}.{}", path, name);
+            match properties.and_then(|properties| properties.get(name)) {
+                Some(field_schema) => validate_at(field_schema, field, &field_path, errors),
+                None => {
+                    if let Some(additional) = schema.get("additionalProperties") {
+                        validate_at(additional, field, &field_path, errors);
+                    }
+                }
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            validate_at(item_schema, item, &format!("{}[{}]", path, index), errors);
+        }
+    }
+}
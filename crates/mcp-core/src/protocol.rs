@@ -171,6 +171,10 @@ pub struct ServerCapabilities {
     pub resources: Option<ResourcesCapability>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<ToolsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<InstructionsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_errors: Option<ToolErrorsCapability>,
     // Add other capabilities as needed
 }
 
@@ -193,6 +197,28 @@ pub struct ToolsCapability {
     pub list_changed: Option<bool>,
 }
 
+/// Advertises that the server's instructions are dynamic and should be re-fetched
+/// by the client via `instructions/get`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InstructionsCapability {
+    /// Re-fetch the instructions once this many seconds have passed since the last fetch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_interval_secs: Option<u64>,
+    /// Re-fetch the instructions after any tool on this server has been called
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_on_tool_call: Option<bool>,
+}
+
+/// Rules the client can use to classify errors from the server's tools and suggest what to
+/// try next. Each rule is an object with a `pattern` regex, a `hint` and optionally a `kind`
+/// and `match_output`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolErrorsCapability {
+    pub rules: Vec<Value>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ListResourcesResult {
@@ -218,6 +244,9 @@ pub struct ListToolsResult {
 #[serde(rename_all = "camelCase")]
 pub struct CallToolResult {
     pub content: Vec<Content>,
+    /// The result as JSON, conforming to the tool's output schema when it declares one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
 }
@@ -234,6 +263,11 @@ pub struct GetPromptResult {
     pub messages: Vec<PromptMessage>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GetInstructionsResult {
+    pub instructions: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EmptyResult {}
 
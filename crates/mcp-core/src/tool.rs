@@ -112,6 +112,9 @@ pub struct Tool {
     pub input_schema: Value,
     /// Optional additional tool information.
     pub annotations: Option<ToolAnnotations>,
+    /// An optional JSON Schema object the structured results of the tool conform to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
 }
 
 impl Tool {
@@ -131,8 +134,15 @@ impl Tool {
             description: description.into(),
             input_schema,
             annotations,
+            output_schema: None,
         }
     }
+
+    /// Declare the schema of the tool's structured results, so clients can check them
+    pub fn with_output_schema(mut self, output_schema: Value) -> Self {
+        self.output_schema = Some(output_schema);
+        self
+    }
 }
 
 /// A tool call request that an extension can execute
@@ -18,6 +18,9 @@ pub enum TransportError {
 
     #[error("Invalid message format: {0}")]
     InvalidMessage(String),
+
+    #[error("Message of {size} bytes exceeds the maximum message size of {limit} bytes")]
+    MessageTooLarge { size: usize, limit: usize },
 }
 
 #[derive(Error, Debug)]
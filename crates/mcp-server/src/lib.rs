@@ -8,7 +8,7 @@ use mcp_core::protocol::{JsonRpcError, JsonRpcMessage, JsonRpcResponse};
 use pin_project::pin_project;
 use router::McpRequest;
 use tokio::{
-    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    io::{AsyncBufRead, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
     sync::mpsc,
 };
 use tower_service::Service;
@@ -19,16 +19,28 @@ pub use errors::{BoxError, RouterError, ServerError, TransportError};
 pub mod router;
 pub use router::Router;
 
+pub mod trace;
+pub use trace::{TraceConfig, TraceLayer, TraceService};
+
+/// Environment variable holding the largest JSON-RPC message in bytes the transport reads or writes
+pub const MAX_MESSAGE_SIZE_ENV: &str = "MCP_MAX_MESSAGE_SIZE";
+
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
 /// A transport layer that handles JSON-RPC messages over byte
 #[pin_project]
 pub struct ByteTransport<R, W> {
     // Reader is a BufReader on the underlying stream (stdin or similar) buffering
-    // the underlying data across poll calls, we clear one line (\n) during each
-    // iteration of poll_next from this buffer
+    // the underlying data across poll calls, each message ends with a newline (\n)
     #[pin]
     reader: BufReader<R>,
     #[pin]
     writer: W,
+    // The message read so far, kept across polls until its newline arrives
+    line: Vec<u8>,
+    // The size of a message over the limit that is being skipped up to its newline
+    oversized: Option<usize>,
+    max_message_size: usize,
 }
 
 impl<R, W> ByteTransport<R, W>
@@ -37,13 +49,58 @@ where
     W: AsyncWrite,
 {
     pub fn new(reader: R, writer: W) -> Self {
+        let max_message_size = match std::env::var(MAX_MESSAGE_SIZE_ENV) {
+            Ok(value) => value.parse().unwrap_or_else(|_| {
+                tracing::warn!(value = %value, "Invalid {}, using default", MAX_MESSAGE_SIZE_ENV);
+                DEFAULT_MAX_MESSAGE_SIZE
+            }),
+            Err(_) => DEFAULT_MAX_MESSAGE_SIZE,
+        };
         Self {
             // Default BufReader capacity is 8 * 1024, increase this to 2MB to the file size limit
             // allows the buffer to have the capacity to read very large calls
             reader: BufReader::with_capacity(2 * 1024 * 1024, reader),
             writer,
+            line: Vec::new(),
+            oversized: None,
+            max_message_size,
         }
     }
+
+    /// Messages over `max_message_size` bytes are answered with an error instead of being read,
+    /// and responses over it are replaced with an error
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+}
+
+/// Parse one line read from the transport into a JSON-RPC message
+fn parse_message(line: Vec<u8>) -> Result<JsonRpcMessage, TransportError> {
+    // Convert to UTF-8 string
+    let line = String::from_utf8(line)?;
+    // Log incoming message here before serde conversion to
+    // track incomplete chunks which are not valid JSON
+    tracing::info!(json = %line, "incoming message");
+
+    // Parse JSON and validate message format
+    let value = serde_json::from_str::<serde_json::Value>(&line)?;
+    // Validate basic JSON-RPC structure
+    let Some(obj) = value.as_object() else {
+        return Err(TransportError::InvalidMessage(
+            "Message must be a JSON object".into(),
+        ));
+    };
+
+    // Check jsonrpc version field
+    if !obj.contains_key("jsonrpc") || obj["jsonrpc"] != "2.0" {
+        return Err(TransportError::InvalidMessage(
+            "Missing or invalid jsonrpc version".into(),
+        ));
+    }
+
+    // Now try to parse as proper message
+    Ok(serde_json::from_value::<JsonRpcMessage>(value)?)
 }
 
 impl<R, W> Stream for ByteTransport<R, W>
@@ -55,51 +112,54 @@ where
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let mut this = self.project();
-        let mut buf = Vec::new();
-
-        let mut reader = this.reader.as_mut();
-        let mut read_future = Box::pin(reader.read_until(b'\n', &mut buf));
-        match read_future.as_mut().poll(cx) {
-            Poll::Ready(Ok(0)) => Poll::Ready(None), // EOF
-            Poll::Ready(Ok(_)) => {
-                // Convert to UTF-8 string
-                let line = match String::from_utf8(buf) {
-                    Ok(s) => s,
-                    Err(e) => return Poll::Ready(Some(Err(TransportError::Utf8(e)))),
-                };
-                // Log incoming message here before serde conversion to
-                // track incomplete chunks which are not valid JSON
-                tracing::info!(json = %line, "incoming message");
-
-                // Parse JSON and validate message format
-                match serde_json::from_str::<serde_json::Value>(&line) {
-                    Ok(value) => {
-                        // Validate basic JSON-RPC structure
-                        if !value.is_object() {
-                            return Poll::Ready(Some(Err(TransportError::InvalidMessage(
-                                "Message must be a JSON object".into(),
-                            ))));
-                        }
-                        let obj = value.as_object().unwrap(); // Safe due to check above
+        let limit = *this.max_message_size;
 
-                        // Check jsonrpc version field
-                        if !obj.contains_key("jsonrpc") || obj["jsonrpc"] != "2.0" {
-                            return Poll::Ready(Some(Err(TransportError::InvalidMessage(
-                                "Missing or invalid jsonrpc version".into(),
-                            ))));
-                        }
+        loop {
+            let available = match this.reader.as_mut().poll_fill_buf(cx) {
+                Poll::Ready(Ok(available)) => available,
+                Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(TransportError::Io(e)))),
+                Poll::Pending => return Poll::Pending,
+            };
 
-                        // Now try to parse as proper message
-                        match serde_json::from_value::<JsonRpcMessage>(value) {
-                            Ok(msg) => Poll::Ready(Some(Ok(msg))),
-                            Err(e) => Poll::Ready(Some(Err(TransportError::Json(e)))),
-                        }
-                    }
-                    Err(e) => Poll::Ready(Some(Err(TransportError::Json(e)))),
+            // EOF, a last message without a newline is still handled
+            if available.is_empty() {
+                if let Some(size) = this.oversized.take() {
+                    return Poll::Ready(Some(Err(TransportError::MessageTooLarge { size, limit })));
+                }
+                let line = std::mem::take(this.line);
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    return Poll::Ready(None);
+                }
+                return Poll::Ready(Some(parse_message(line)));
+            }
+
+            let (len, complete) = match available.iter().position(|b| *b == b'\n') {
+                Some(newline) => (newline + 1, true),
+                None => (available.len(), false),
+            };
+            // Partial reads accumulate in `line`, a message over the limit is skipped without
+            // buffering it so the next message is read in sync
+            if let Some(size) = this.oversized.as_mut() {
+                *size += len;
+            } else if this.line.len() + len > limit {
+                *this.oversized = Some(this.line.len() + len);
+                this.line.clear();
+            } else {
+                this.line.extend_from_slice(&available[..len]);
+            }
+            this.reader.as_mut().consume(len);
+
+            if complete {
+                if let Some(size) = this.oversized.take() {
+                    tracing::warn!(size, limit, "Skipped a message over the maximum size");
+                    return Poll::Ready(Some(Err(TransportError::MessageTooLarge { size, limit })));
+                }
+                let line = std::mem::take(this.line);
+                // Blank lines between messages are not an error
+                if !line.iter().all(u8::is_ascii_whitespace) {
+                    return Poll::Ready(Some(parse_message(line)));
                 }
             }
-            Poll::Ready(Err(e)) => Poll::Ready(Some(Err(TransportError::Io(e)))),
-            Poll::Pending => Poll::Pending,
         }
     }
 }
@@ -110,7 +170,32 @@ where
     W: AsyncWrite + Unpin,
 {
     pub async fn write_message(&mut self, msg: JsonRpcMessage) -> Result<(), std::io::Error> {
-        let json = serde_json::to_string(&msg)?;
+        let mut json = serde_json::to_string(&msg)?;
+        if json.len() > self.max_message_size {
+            let message = format!(
+                "Response of {} bytes exceeds the maximum message size of {} bytes",
+                json.len(),
+                self.max_message_size
+            );
+            tracing::warn!("{}", message);
+            match msg {
+                // The client is still waiting for an answer to the request
+                JsonRpcMessage::Response(response) => {
+                    json = serde_json::to_string(&JsonRpcMessage::Response(JsonRpcResponse {
+                        jsonrpc: "2.0".to_string(),
+                        id: response.id,
+                        result: None,
+                        error: Some(mcp_core::protocol::ErrorData {
+                            code: mcp_core::protocol::INTERNAL_ERROR,
+                            message,
+                            data: None,
+                        }),
+                    }))?;
+                }
+                // Notifications such as progress updates can be dropped
+                _ => return Ok(()),
+            }
+        }
         Pin::new(&mut self.writer)
             .write_all(json.as_bytes())
             .await?;
@@ -243,11 +328,13 @@ where
                                 data: None,
                             }
                         }
-                        TransportError::Protocol(_) => mcp_core::protocol::ErrorData {
-                            code: mcp_core::protocol::INVALID_REQUEST,
-                            message: e.to_string(),
-                            data: None,
-                        },
+                        TransportError::Protocol(_) | TransportError::MessageTooLarge { .. } => {
+                            mcp_core::protocol::ErrorData {
+                                code: mcp_core::protocol::INVALID_REQUEST,
+                                message: e.to_string(),
+                                data: None,
+                            }
+                        }
                         _ => mcp_core::protocol::ErrorData {
                             code: mcp_core::protocol::INTERNAL_ERROR,
                             message: e.to_string(),
@@ -296,3 +383,171 @@ impl<T> BoundedService for T where
         + 'static
 {
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use tokio::io::ReadBuf;
+
+    fn xorshift(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    /// Yields its data a few bytes at a time, returning Pending between reads
+    struct ChunkedReader {
+        data: Vec<u8>,
+        pos: usize,
+        rng: u64,
+        pending: bool,
+    }
+
+    impl ChunkedReader {
+        fn new(data: Vec<u8>, seed: u64) -> Self {
+            Self {
+                data,
+                pos: 0,
+                rng: seed,
+                pending: false,
+            }
+        }
+    }
+
+    impl AsyncRead for ChunkedReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            let this = &mut *self;
+            this.pending = !this.pending;
+            if this.pending {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let len = (xorshift(&mut this.rng) % 7 + 1) as usize;
+            let end = (this.pos + len)
+                .min(this.data.len())
+                .min(this.pos + buf.remaining());
+            buf.put_slice(&this.data[this.pos..end]);
+            this.pos = end;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn request(id: u64) -> String {
+        format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"tools/call","params":{{"name":"read"}}}}"#,
+            id
+        )
+    }
+
+    async fn read_all(
+        data: Vec<u8>,
+        seed: u64,
+        limit: usize,
+    ) -> Vec<Result<JsonRpcMessage, TransportError>> {
+        ByteTransport::new(ChunkedReader::new(data, seed), tokio::io::sink())
+            .with_max_message_size(limit)
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_partial_reads_and_oversized_messages() {
+        let oversized = format!(
+            r#"{{"jsonrpc":"2.0","id":2,"result":"{}"}}"#,
+            "x".repeat(500)
+        );
+        let input = format!(
+            "{}\n\n{}\n{}\n{}",
+            request(1),
+            oversized,
+            request(3),
+            request(4)
+        );
+        let messages = read_all(input.into_bytes(), 7, 200).await;
+
+        assert_eq!(messages.len(), 4);
+        assert!(matches!(&messages[0], Ok(JsonRpcMessage::Request(r)) if r.id == Some(1)));
+        // The size counts the newline that ends the message
+        assert!(matches!(
+            &messages[1],
+            Err(TransportError::MessageTooLarge { size, limit: 200 }) if *size == oversized.len() + 1
+        ));
+        assert!(matches!(&messages[2], Ok(JsonRpcMessage::Request(r)) if r.id == Some(3)));
+        // The last message has no newline before EOF
+        assert!(matches!(&messages[3], Ok(JsonRpcMessage::Request(r)) if r.id == Some(4)));
+    }
+
+    #[tokio::test]
+    async fn test_framing_fuzz() {
+        for seed in 1..50u64 {
+            let mut rng = seed * 0x9E37_79B9;
+            let mut input = Vec::new();
+            let mut expected = Vec::new();
+            for id in 0..40 {
+                match xorshift(&mut rng) % 5 {
+                    0 | 1 => {
+                        input.extend_from_slice(request(id).as_bytes());
+                        expected.push(Some(id));
+                    }
+                    2 => {
+                        // Garbage without newlines, sometimes invalid UTF-8
+                        input.push(b'#');
+                        for _ in 0..xorshift(&mut rng) % 100 {
+                            let byte = (xorshift(&mut rng) % 256) as u8;
+                            input.push(if byte == b'\n' { b' ' } else { byte });
+                        }
+                        expected.push(None);
+                    }
+                    3 => {
+                        input.extend_from_slice(b"{\"jsonrpc\":\"2.0\",\"params\":\"");
+                        input.extend_from_slice(&[b'a'; 300]);
+                        input.extend_from_slice(b"\"}");
+                        expected.push(None);
+                    }
+                    _ => input.extend_from_slice(b"  "),
+                }
+                input.push(b'\n');
+            }
+
+            let messages = read_all(input, seed, 256).await;
+            let ids: Vec<Option<u64>> = messages
+                .iter()
+                .map(|message| match message {
+                    Ok(JsonRpcMessage::Request(request)) => request.id,
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(ids, expected, "seed {}", seed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_response_is_replaced_with_error() {
+        let mut transport =
+            ByteTransport::new(tokio::io::empty(), Vec::new()).with_max_message_size(100);
+        let response = JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: Some(9),
+            result: Some(serde_json::json!({"text": "x".repeat(200)})),
+            error: None,
+        };
+        transport
+            .write_message(JsonRpcMessage::Response(response))
+            .await
+            .unwrap();
+
+        let written: serde_json::Value = serde_json::from_slice(&transport.writer).unwrap();
+        assert_eq!(written["id"], 9);
+        assert_eq!(written["error"]["code"], mcp_core::protocol::INTERNAL_ERROR);
+        assert!(written["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("exceeds the maximum message size of 100 bytes"));
+    }
+}
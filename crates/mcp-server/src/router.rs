@@ -2,6 +2,7 @@ use std::{
     future::Future,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 type PromptFuture = Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>>;
@@ -11,10 +12,10 @@ use mcp_core::{
     handler::{PromptError, ResourceError, ToolError},
     prompt::{Prompt, PromptMessage, PromptMessageRole},
     protocol::{
-        CallToolResult, GetPromptResult, Implementation, InitializeResult, JsonRpcMessage,
-        JsonRpcRequest, JsonRpcResponse, ListPromptsResult, ListResourcesResult, ListToolsResult,
-        PromptsCapability, ReadResourceResult, ResourcesCapability, ServerCapabilities,
-        ToolsCapability,
+        CallToolResult, GetInstructionsResult, GetPromptResult, Implementation, InitializeResult,
+        InstructionsCapability, JsonRpcMessage, JsonRpcRequest, JsonRpcResponse, ListPromptsResult,
+        ListResourcesResult, ListToolsResult, PromptsCapability, ReadResourceResult,
+        ResourcesCapability, ServerCapabilities, ToolErrorsCapability, ToolsCapability,
     },
     ResourceContents,
 };
@@ -29,6 +30,8 @@ pub struct CapabilitiesBuilder {
     tools: Option<ToolsCapability>,
     prompts: Option<PromptsCapability>,
     resources: Option<ResourcesCapability>,
+    instructions: Option<InstructionsCapability>,
+    tool_errors: Option<ToolErrorsCapability>,
 }
 
 impl Default for CapabilitiesBuilder {
@@ -43,6 +46,8 @@ impl CapabilitiesBuilder {
             tools: None,
             prompts: None,
             resources: None,
+            instructions: None,
+            tool_errors: None,
         }
     }
 
@@ -71,6 +76,26 @@ impl CapabilitiesBuilder {
         self
     }
 
+    /// Mark the instructions as dynamic so clients re-fetch them on an interval
+    /// and/or after each tool call
+    pub fn with_dynamic_instructions(
+        mut self,
+        refresh_interval: Option<Duration>,
+        refresh_on_tool_call: bool,
+    ) -> Self {
+        self.instructions = Some(InstructionsCapability {
+            refresh_interval_secs: refresh_interval.map(|interval| interval.as_secs()),
+            refresh_on_tool_call: Some(refresh_on_tool_call),
+        });
+        self
+    }
+
+    /// Advertise rules for classifying errors from the tools, see [`ToolErrorsCapability`]
+    pub fn with_tool_error_rules(mut self, rules: Vec<Value>) -> Self {
+        self.tool_errors = Some(ToolErrorsCapability { rules });
+        self
+    }
+
     /// Build the router with automatic capability inference
     pub fn build(self) -> ServerCapabilities {
         // Create capabilities based on what's configured
@@ -78,6 +103,8 @@ impl CapabilitiesBuilder {
             tools: self.tools,
             prompts: self.prompts,
             resources: self.resources,
+            instructions: self.instructions,
+            tool_errors: self.tool_errors,
         }
     }
 }
@@ -137,6 +164,25 @@ pub trait Router: Send + Sync + 'static {
         }
     }
 
+    fn handle_instructions_get(
+        &self,
+        req: JsonRpcRequest,
+    ) -> impl Future<Output = Result<JsonRpcResponse, RouterError>> + Send {
+        async move {
+            let result = GetInstructionsResult {
+                instructions: self.instructions(),
+            };
+
+            let mut response = self.create_response(req.id);
+            response.result =
+                Some(serde_json::to_value(result).map_err(|e| {
+                    RouterError::Internal(format!("JSON serialization error: {}", e))
+                })?);
+
+            Ok(response)
+        }
+    }
+
     fn handle_tools_list(
         &self,
         req: JsonRpcRequest,
@@ -178,10 +224,12 @@ pub trait Router: Send + Sync + 'static {
             let result = match self.call_tool(name, arguments, notifier).await {
                 Ok(result) => CallToolResult {
                     content: result,
+                    structured_content: None,
                     is_error: None,
                 },
                 Err(err) => CallToolResult {
                     content: vec![Content::text(err.to_string())],
+                    structured_content: None,
                     is_error: Some(true),
                 },
             };
@@ -420,6 +468,7 @@ where
         Box::pin(async move {
             let result = match req.request.method.as_str() {
                 "initialize" => this.handle_initialize(req.request).await,
+                "instructions/get" => this.handle_instructions_get(req.request).await,
                 "tools/list" => this.handle_tools_list(req.request).await,
                 "tools/call" => this.handle_tools_call(req.request, req.notifier).await,
                 "resources/list" => this.handle_resources_list(req.request).await,
@@ -0,0 +1,368 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use futures::Future;
+use mcp_core::protocol::JsonRpcResponse;
+use serde_json::{json, Value};
+use tower::Layer;
+use tower_service::Service;
+
+use crate::{router::McpRequest, BoxError};
+
+/// Environment variable holding the slow tool call threshold in milliseconds
+pub const SLOW_TOOL_THRESHOLD_ENV: &str = "MCP_SLOW_TOOL_THRESHOLD_MS";
+/// Environment variable holding the directory where per-server trace files are written
+pub const TRACE_DIR_ENV: &str = "MCP_TRACE_DIR";
+
+const DEFAULT_SLOW_TOOL_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Configuration for request tracing on a server instance
+#[derive(Debug, Clone)]
+pub struct TraceConfig {
+    /// Tool calls running longer than this are reported with a warning
+    pub slow_tool_threshold: Option<Duration>,
+    /// File that receives one JSON line per request and per response
+    pub trace_file: Option<PathBuf>,
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        Self {
+            slow_tool_threshold: Some(DEFAULT_SLOW_TOOL_THRESHOLD),
+            trace_file: None,
+        }
+    }
+}
+
+impl TraceConfig {
+    /// Build a config from `MCP_SLOW_TOOL_THRESHOLD_MS` and `MCP_TRACE_DIR`.
+    ///
+    /// When a trace directory is set, the trace file is named after the server and
+    /// process id so that concurrently running servers never share a file.
+    pub fn from_env(server_name: &str) -> Self {
+        let slow_tool_threshold = match std::env::var(SLOW_TOOL_THRESHOLD_ENV) {
+            Ok(value) => match value.parse::<u64>() {
+                Ok(0) => None,
+                Ok(ms) => Some(Duration::from_millis(ms)),
+                Err(_) => {
+                    tracing::warn!(value = %value, "Invalid {}, using default", SLOW_TOOL_THRESHOLD_ENV);
+                    Some(DEFAULT_SLOW_TOOL_THRESHOLD)
+                }
+            },
+            Err(_) => Some(DEFAULT_SLOW_TOOL_THRESHOLD),
+        };
+
+        let trace_file = std::env::var(TRACE_DIR_ENV).ok().map(|dir| {
+            Path::new(&dir).join(format!("{}-{}.jsonl", server_name, std::process::id()))
+        });
+
+        Self {
+            slow_tool_threshold,
+            trace_file,
+        }
+    }
+}
+
+/// Appends JSON lines to a trace file shared by all in-flight requests
+#[derive(Clone)]
+struct TraceWriter(Arc<Mutex<File>>);
+
+impl TraceWriter {
+    fn open(path: &Path) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self(Arc::new(Mutex::new(file))))
+    }
+
+    fn write(&self, record: Value) {
+        let Ok(mut file) = self.0.lock() else {
+            return;
+        };
+        if let Err(e) = writeln!(file, "{}", record) {
+            tracing::error!(error = %e, "Failed to write trace record");
+        }
+    }
+}
+
+fn timestamp_ms() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}
+
+/// Layer that wraps a service with [`TraceService`]
+#[derive(Clone)]
+pub struct TraceLayer {
+    slow_tool_threshold: Option<Duration>,
+    writer: Option<TraceWriter>,
+}
+
+impl TraceLayer {
+    pub fn new(config: TraceConfig) -> Self {
+        let writer = config
+            .trace_file
+            .as_deref()
+            .and_then(|path| match TraceWriter::open(path) {
+                Ok(writer) => {
+                    tracing::info!(path = %path.display(), "Writing request trace");
+                    Some(writer)
+                }
+                Err(e) => {
+                    tracing::error!(path = %path.display(), error = %e, "Failed to open trace file");
+                    None
+                }
+            });
+
+        Self {
+            slow_tool_threshold: config.slow_tool_threshold,
+            writer,
+        }
+    }
+}
+
+impl<S> Layer<S> for TraceLayer {
+    type Service = TraceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TraceService {
+            inner,
+            slow_tool_threshold: self.slow_tool_threshold,
+            writer: self.writer.clone(),
+        }
+    }
+}
+
+/// Middleware that records every request with its duration, warns about slow tool
+/// calls and optionally mirrors the traffic to a trace file
+pub struct TraceService<S> {
+    inner: S,
+    slow_tool_threshold: Option<Duration>,
+    writer: Option<TraceWriter>,
+}
+
+impl<S> TraceService<S> {
+    pub fn new(inner: S, config: TraceConfig) -> Self {
+        TraceLayer::new(config).layer(inner)
+    }
+}
+
+impl<S> Service<McpRequest> for TraceService<S>
+where
+    S: Service<McpRequest, Response = JsonRpcResponse> + Send,
+    S::Error: Into<BoxError> + Send,
+    S::Future: Send + 'static,
+{
+    type Response = JsonRpcResponse;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: McpRequest) -> Self::Future {
+        let id = req.request.id;
+        let method = req.request.method.clone();
+        let tool_name = if method == "tools/call" {
+            req.request
+                .params
+                .as_ref()
+                .and_then(|params| params.get("name"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        } else {
+            None
+        };
+
+        let writer = self.writer.clone();
+        if let Some(writer) = &writer {
+            writer.write(json!({
+                "timestamp_ms": timestamp_ms(),
+                "direction": "request",
+                "id": id,
+                "method": method,
+                "tool": tool_name,
+                "request": req.request,
+            }));
+        }
+
+        let slow_tool_threshold = tool_name.as_ref().and(self.slow_tool_threshold);
+        let fut = self.inner.call(req);
+
+        Box::pin(async move {
+            let start = Instant::now();
+            tokio::pin!(fut);
+
+            // Warn while a slow tool is still running so hangs show up in the logs
+            // before (or instead of) the response
+            let result = match slow_tool_threshold {
+                Some(threshold) => {
+                    tokio::select! {
+                        result = &mut fut => result,
+                        _ = tokio::time::sleep(threshold) => {
+                            tracing::warn!(
+                                request_id = ?id,
+                                tool = ?tool_name,
+                                threshold_ms = threshold.as_millis() as u64,
+                                "Tool call still running after threshold"
+                            );
+                            fut.await
+                        }
+                    }
+                }
+                None => fut.await,
+            };
+
+            let duration = start.elapsed();
+            let duration_ms = duration.as_millis() as u64;
+            let result = result.map_err(Into::into);
+
+            match &result {
+                Ok(_) => tracing::info!(
+                    request_id = ?id,
+                    method = %method,
+                    tool = ?tool_name,
+                    duration_ms,
+                    "Request completed"
+                ),
+                Err(e) => tracing::error!(
+                    request_id = ?id,
+                    method = %method,
+                    tool = ?tool_name,
+                    duration_ms,
+                    error = %e,
+                    "Request failed"
+                ),
+            }
+
+            if let Some(threshold) = slow_tool_threshold {
+                if duration >= threshold {
+                    tracing::warn!(
+                        request_id = ?id,
+                        tool = ?tool_name,
+                        duration_ms,
+                        threshold_ms = threshold.as_millis() as u64,
+                        "Slow tool call"
+                    );
+                }
+            }
+
+            if let Some(writer) = &writer {
+                let (response, error) = match &result {
+                    Ok(response) => (Some(response), None),
+                    Err(e) => (None, Some(e.to_string())),
+                };
+                writer.write(json!({
+                    "timestamp_ms": timestamp_ms(),
+                    "direction": "response",
+                    "id": id,
+                    "method": method,
+                    "tool": tool_name,
+                    "duration_ms": duration_ms,
+                    "response": response,
+                    "error": error,
+                }));
+            }
+
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::protocol::JsonRpcRequest;
+    use tokio::sync::mpsc;
+
+    #[derive(Clone)]
+    struct EchoService;
+
+    impl Service<McpRequest> for EchoService {
+        type Response = JsonRpcResponse;
+        type Error = BoxError;
+        type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: McpRequest) -> Self::Future {
+            Box::pin(async move {
+                Ok(JsonRpcResponse {
+                    jsonrpc: "2.0".to_string(),
+                    id: req.request.id,
+                    result: Some(json!({})),
+                    error: None,
+                })
+            })
+        }
+    }
+
+    fn tool_request(id: u64) -> McpRequest {
+        let (notifier, _) = mpsc::channel(1);
+        McpRequest {
+            request: JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(id),
+                method: "tools/call".to_string(),
+                params: Some(json!({"name": "echo", "arguments": {}})),
+            },
+            notifier,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trace_file_records_request_and_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trace.jsonl");
+        let layer = TraceLayer::new(TraceConfig {
+            slow_tool_threshold: Some(Duration::from_secs(1)),
+            trace_file: Some(path.clone()),
+        });
+        let mut service = layer.layer(EchoService);
+
+        let response = service.call(tool_request(7)).await.unwrap();
+        assert_eq!(response.id, Some(7));
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let records: Vec<Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["direction"], "request");
+        assert_eq!(records[0]["tool"], "echo");
+        assert_eq!(records[1]["direction"], "response");
+        assert_eq!(records[1]["id"], 7);
+        assert!(records[1]["duration_ms"].is_u64());
+    }
+
+    #[test]
+    fn test_config_from_env_names_trace_file_per_server() {
+        std::env::set_var(TRACE_DIR_ENV, "/tmp/mcp-traces");
+        std::env::set_var(SLOW_TOOL_THRESHOLD_ENV, "0");
+        let config = TraceConfig::from_env("developer");
+        std::env::remove_var(TRACE_DIR_ENV);
+        std::env::remove_var(SLOW_TOOL_THRESHOLD_ENV);
+
+        assert!(config.slow_tool_threshold.is_none());
+        let trace_file = config.trace_file.unwrap();
+        assert!(trace_file.starts_with("/tmp/mcp-traces"));
+        assert!(trace_file
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("developer-"));
+    }
+}